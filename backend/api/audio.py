@@ -1,4 +1,5 @@
 """Audio API endpoints"""
+import base64
 import logging
 import traceback
 from fastapi import APIRouter, HTTPException
@@ -7,6 +8,7 @@ from pydantic import BaseModel
 from typing import Optional
 from pathlib import Path
 from services.audio_generator import audio_service
+from storage.audio_store import audio_store
 from services.event_logger import log_content_generated
 from config import settings
 
@@ -69,6 +71,21 @@ async def generate_audio(request: AudioGenerateRequest):
         raise HTTPException(status_code=500, detail=f"Podcast generation failed: {str(e)}")
 
 
+class AudioRestore(BaseModel):
+    """A finished audio generation put back with its file, as the desktop app
+    does when it unlocks an encrypted notebook."""
+    script: str = ""
+    topic: str = ""
+    duration_minutes: int = 10
+    host1_gender: str = "male"
+    host2_gender: str = "female"
+    accent: str = "us"
+    skill_id: Optional[str] = None
+    duration_seconds: Optional[int] = None
+    extension: str = ".mp3"
+    data: str  # the audio file, base64-encoded
+
+
 # NOTE: Specific routes must come BEFORE /{notebook_id} to avoid route conflicts
 @router.delete("/remove/{audio_id}")
 async def delete_audio(audio_id: str):
@@ -142,6 +159,38 @@ async def download_audio(audio_id: str):
     )
 
 
+@router.post("/{notebook_id}/restore", response_model=AudioGeneration)
+async def restore_audio(notebook_id: str, request: AudioRestore):
+    """Recreate a completed audio generation from its file"""
+    ext = request.extension.lower()
+    if ext not in (".m4a", ".mp3", ".aiff", ".wav"):
+        raise HTTPException(status_code=400, detail=f"Unsupported audio type: {ext}")
+    try:
+        data = base64.b64decode(request.data, validate=True)
+    except ValueError:
+        raise HTTPException(status_code=400, detail="Audio data is not valid base64")
+
+    record = await audio_store.create(
+        notebook_id=notebook_id,
+        script=request.script,
+        topic=request.topic,
+        duration_minutes=request.duration_minutes,
+        host1_gender=request.host1_gender,
+        host2_gender=request.host2_gender,
+        accent=request.accent,
+        skill_id=request.skill_id,
+    )
+    audio_dir = settings.data_dir / "audio"
+    audio_dir.mkdir(parents=True, exist_ok=True)
+    file_path = audio_dir / f"{record['audio_id']}{ext}"
+    file_path.write_bytes(data)
+    return await audio_store.update(record["audio_id"], {
+        "audio_file_path": str(file_path),
+        "duration_seconds": request.duration_seconds,
+        "status": "completed",
+    })
+
+
 @router.get("/{notebook_id}")
 async def list_audio(notebook_id: str):
     """List all audio files for a notebook"""
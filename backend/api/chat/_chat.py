@@ -66,6 +66,8 @@ async def query(chat_query: ChatQuery):
             enable_web_search=chat_query.enable_web_search,
             llm_provider=chat_query.llm_provider,
             extra_system_context=extra_ctx,
+            model=chat_query.model,
+            temperature=chat_query.temperature,
         )
         
         # Log Q&A for memory consolidation (fire-and-forget)
@@ -226,6 +228,8 @@ async def query_stream(chat_query: ChatQuery):
                     llm_provider=chat_query.llm_provider,
                     deep_think=chat_query.deep_think or False,
                     extra_system_context=extra_ctx,
+                    model=chat_query.model,
+                    temperature=chat_query.temperature,
                 ):
                     if chunk.get("type") == "answer_chunk":
                         answer_parts.append(chunk.get("content", ""))
@@ -92,6 +92,8 @@ class ChatQuery(BaseModel):
     top_k: Optional[int] = 4  # Reduced from 5 for faster LLM response
     enable_web_search: Optional[bool] = False
     llm_provider: Optional[str] = None
+    model: Optional[str] = None  # Per-notebook model override, resolved by the desktop app; None = settings.ollama_model
+    temperature: Optional[float] = None  # Per-notebook temperature override; None = the model's defaults
     deep_think: Optional[bool] = False  # Enable Deep Think mode with chain-of-thought reasoning
     use_orchestrator: Optional[bool] = True  # v0.60: Auto-detect complex queries and decompose
     target: Optional[str] = None  # v1.4: @mention routing — 'curator', 'collector', 'studio', or None for default RAG
@@ -542,7 +542,7 @@ async def full_health_check():
     try:
         from flashrank import Ranker
         # Use persistent cache dir (not /tmp which gets cleared on reboot)
-        cache_dir = settings.data_dir / "models" / "flashrank"
+        cache_dir = settings.models_dir / "flashrank"
         cache_dir.mkdir(parents=True, exist_ok=True)
         _ranker = Ranker(model_name=settings.reranker_model, cache_dir=str(cache_dir))
         add_check("ai_models", {
@@ -1962,7 +1962,7 @@ async def execute_repair(request: RepairRequest, background_tasks: BackgroundTas
             from flashrank import Ranker, RerankRequest
             
             # Use persistent cache dir (not /tmp which gets cleared on reboot)
-            cache_dir = settings.data_dir / "models" / "flashrank"
+            cache_dir = settings.models_dir / "flashrank"
             cache_dir.mkdir(parents=True, exist_ok=True)
             add_log("INFO", f"Using cache dir: {cache_dir}", "health_portal")
             
@@ -3,7 +3,7 @@ import shutil
 from pathlib import Path
 from fastapi import APIRouter, HTTPException
 from pydantic import BaseModel
-from typing import Optional
+from typing import List, Optional
 from storage.notebook_store import notebook_store
 from api.settings import _load_app_preferences
 from config import settings
@@ -45,6 +45,11 @@ class NotebookMove(BaseModel):
     section_id: Optional[str] = None
     sort_order: Optional[int] = None
 
+class NotebookSearch(BaseModel):
+    query: str
+    notebook_ids: Optional[List[str]] = None
+    top_k: int = 10
+
 @router.get("/")
 async def list_notebooks():
     """List all notebooks, with primary notebook first"""
@@ -188,6 +193,26 @@ async def move_notebook(notebook_id: str, body: NotebookMove):
         raise HTTPException(status_code=404, detail="Notebook not found")
     return result
 
+@router.post("/search")
+async def search_notebooks(body: NotebookSearch):
+    """Passages matching a query across notebooks (all, or those given), without
+    generating an answer. Used by the shell's automation interfaces."""
+    from services.cross_notebook_search import cross_notebook_search
+
+    query = body.query.strip()
+    if not query:
+        raise HTTPException(status_code=400, detail="Query cannot be empty")
+    top_k = max(1, min(body.top_k, 50))
+    result = await cross_notebook_search.search(
+        query,
+        notebook_ids=body.notebook_ids,
+        top_k=top_k,
+        top_k_per_notebook=top_k,
+    )
+    for hit in result["results"]:
+        hit["distance"] = hit.pop("_distance", None)
+    return result
+
 @router.get("/colors/palette")
 async def get_color_palette():
     """Get available color palette"""
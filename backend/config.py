@@ -1,4 +1,5 @@
 """Application configuration"""
+import os
 import sys
 from pathlib import Path
 from pydantic_settings import BaseSettings
@@ -8,7 +9,13 @@ def get_data_directory() -> Path:
     
     All environments (dev, bundled) use: ~/Library/Application Support/LocalBook/
     This ensures consistent data across development and production.
+    LOCALBOOK_DATA_DIR (set by the app when the library lives in a
+    cloud-synced folder) overrides it.
     """
+    override = os.environ.get("LOCALBOOK_DATA_DIR")
+    if override:
+        return Path(override)
+
     app_support = Path.home() / "Library" / "Application Support" / "LocalBook"
     
     # Auto-migrate from old bundle location if needed (for bundled apps)
@@ -60,6 +67,13 @@ class Settings(BaseSettings):
     # Data paths - computed based on environment
     data_dir: Path = get_data_directory()
     db_path: Path = get_data_directory() / "lancedb"
+    # Downloaded model files. The desktop app passes the folder the user
+    # picked for models (LOCALBOOK_MODELS_DIR); otherwise they live with the data.
+    models_dir: Path = Path(os.environ.get("LOCALBOOK_MODELS_DIR") or get_data_directory() / "models")
+    # WAL keeps commits in a side file that a cloud sync client can upload
+    # apart from the database, so a library in a synced folder uses the
+    # rollback journal instead.
+    sqlite_journal_mode: str = "DELETE" if os.environ.get("LOCALBOOK_CLOUD_FOLDER") else "WAL"
 
     # LLM settings
     llm_provider: str = "ollama"  # ollama, openai, or anthropic
@@ -20,8 +20,28 @@ except ImportError:
     if os.path.exists("/etc/ssl/cert.pem"):
         os.environ.setdefault("SSL_CERT_FILE", "/etc/ssl/cert.pem")
 
+# ── Extra root CAs trusted in the desktop app ──
+# TLS-intercepting corporate proxies re-sign traffic with their own root;
+# append those to a copy of the CA bundle picked above.
+_extra_ca = os.environ.get("LOCALBOOK_EXTRA_CA_CERTS")
+if _extra_ca and os.path.exists(_extra_ca):
+    try:
+        import tempfile
+        _base = os.environ.get("SSL_CERT_FILE")
+        _merged = os.path.join(tempfile.gettempdir(), "localbook-ca-bundle.pem")
+        with open(_merged, "w") as out:
+            if _base and os.path.exists(_base):
+                with open(_base) as f:
+                    out.write(f.read().rstrip("\n") + "\n")
+            with open(_extra_ca) as f:
+                out.write(f.read())
+        for _var in ("SSL_CERT_FILE", "REQUESTS_CA_BUNDLE", "CURL_CA_BUNDLE"):
+            os.environ[_var] = _merged
+    except OSError as e:
+        print(f"Could not add extra CA certificates: {e}")
+
 # ── Rich logging: colored output + better tracebacks ──
-from utils.logging_config import setup_logging
+from utils.logging_config import debug_mode, setup_logging
 setup_logging()
 
 # ── Quick-exit CLI flags (must run before any heavy imports) ──
@@ -129,7 +149,7 @@ from api import notebooks, sources, chat, skills, audio, source_viewer, web, set
 from api.capture import capture_router
 from api.updates import check_if_upgrade, set_startup_status, mark_startup_complete, CURRENT_VERSION
 from services.model_warmup import initial_warmup, start_warmup_task, stop_warmup_task
-from services.startup_checks import run_all_startup_checks
+from services.startup_checks import run_all_startup_checks, skip_index_checks
 from services.migration_manager import check_and_migrate_on_startup
 
 async def _run_startup_tasks():
@@ -249,8 +269,11 @@ async def _run_startup_tasks():
     await _step("checking", "Verifying data directory...", 15)
 
     # ── Step 4: Check AI models ───────────────────────────────────────────
+    # The app can ask us to skip the full index scans; never after an upgrade,
+    # when a dimension migration may be due.
+    skip_index = skip_index_checks() and not is_upgrade
     await _step("checking", "Checking AI models...", 30,
-                run_all_startup_checks(status_callback=set_startup_status))
+                run_all_startup_checks(status_callback=set_startup_status, skip_index=skip_index))
 
     # ── Step 5: Checking embeddings ───────────────────────────────────────
     await _step("checking", "Checking embedding compatibility...", 55)
@@ -377,7 +400,8 @@ async def _run_startup_tasks():
             except Exception as e:
                 print(f"⚠️ Derived-store reconcile failed (non-fatal): {e}")
 
-        safe_create_task(_reconcile_derived_stores(), name="reconcile-derived-stores")
+        if not skip_index:
+            safe_create_task(_reconcile_derived_stores(), name="reconcile-derived-stores")
 
     await _step("starting", "Starting background services...", 75, _start_services())
 
@@ -434,6 +458,10 @@ async def lifespan(app: FastAPI):
 
     # Start startup tasks in background - HTTP server will be ready immediately
     _startup_task = safe_create_task(_run_startup_tasks(), name="startup-tasks")
+
+    # READY line on stdout for the Tauri shell once the socket is bound.
+    from utils.ready_signal import announce_ready
+    safe_create_task(announce_ready(settings.api_host, settings.api_port), name="ready-signal")
     
     # Layer 2: Start heartbeat logger (30s interval)
     start_heartbeat()
@@ -580,6 +608,11 @@ from utils.auth_middleware import AppTokenAuthMiddleware
 app.add_middleware(AppTokenAuthMiddleware, enforce=settings.auth_enforce)
 logger.info(f"[main] auth middleware enforce={settings.auth_enforce}")
 
+# Request ids from the Tauri shell (utils/request_id.py). Outside auth, so a
+# rejected request is still logged under its id.
+from utils.request_id import RequestIdMiddleware
+app.add_middleware(RequestIdMiddleware)
+
 # CORS middleware — added LAST so it's the OUTERMOST wrapper. This is
 # critical: it ensures error responses (401 from auth, etc.) include
 # CORS headers, so browsers don't block the response and our retry logic
@@ -591,7 +624,9 @@ logger.info(f"[main] auth middleware enforce={settings.auth_enforce}")
 # can't make credentialed requests. Defense in depth.
 #
 # Allowed origins:
-#   - tauri://localhost              → main app webview
+#   - tauri://localhost              → main app webview (macOS, iOS)
+#   - http(s)://tauri.localhost      → app webview on Windows and Android,
+#     which reach a desktop's backend lent to them (backend_share.rs)
 #   - http://localhost:1420          → Vite dev server (if used)
 #   - http://localhost:8000          → loopback (Tauri Rust → backend)
 #   - chrome-extension://<id>        → pinned LocalBook Companion extension
@@ -602,6 +637,8 @@ app.add_middleware(
     CORSMiddleware,
     allow_origins=[
         "tauri://localhost",
+        "http://tauri.localhost",
+        "https://tauri.localhost",
         "http://localhost:1420",
         "http://localhost:8000",
         f"chrome-extension://{_cfg_for_cors.extension_id}",
@@ -690,6 +727,6 @@ if __name__ == "__main__":
         app,
         host=settings.api_host,
         port=settings.api_port,
-        log_level="warning",
+        log_level="debug" if debug_mode() else "warning",
         loop="asyncio"  # Explicitly use asyncio loop for PyInstaller compatibility
     )
@@ -41,6 +41,15 @@ LEGACY_KEYS = [                  # Per-key items written by old approach
     "custom_llm",
 ]
 
+# Cloud provider keys the desktop app keeps in its own keychain entries and
+# passes to the backend it launches as environment variables. When set, they
+# take precedence over the bundle.
+ENV_KEYS = {
+    "openai_api_key": "OPENAI_API_KEY",
+    "anthropic_api_key": "ANTHROPIC_API_KEY",
+    "gemini_api_key": "GEMINI_API_KEY",
+}
+
 # ── Touch ID / LocalAuthentication ──────────────────────────────────────────
 
 GATED_KEYS = frozenset({"brave_api_key", "youtube_api_key"})  # search + YouTube only
@@ -210,11 +219,20 @@ def _migrate_legacy_keys() -> None:
 
 # ── Public API ───────────────────────────────────────────────────────────────
 
+def _env_key(key_name: str) -> Optional[str]:
+    """The key as passed by the desktop app, if it was."""
+    var = ENV_KEYS.get(key_name)
+    return (os.environ.get(var) or None) if var else None
+
+
 def get_api_key(key_name: str) -> Optional[str]:
     """Get a single API key (sync). Search/YouTube keys are Touch ID-gated."""
     if key_name in GATED_KEYS and not _request_biometric_auth_sync():
         logger.info(f"[Keychain] {key_name} withheld — Touch ID not satisfied.")
         return None
+    env = _env_key(key_name)
+    if env:
+        return env
     _migrate_legacy_keys()
     bundle = _load_bundle()
     return bundle.get(key_name) or None
@@ -225,6 +243,9 @@ async def get_api_key_async(key_name: str) -> Optional[str]:
     if key_name in GATED_KEYS and not await _request_biometric_auth_async():
         logger.info(f"[Keychain] {key_name} withheld — Touch ID not satisfied.")
         return None
+    env = _env_key(key_name)
+    if env:
+        return env
     _migrate_legacy_keys()
     bundle = _load_bundle()
     return bundle.get(key_name) or None
@@ -252,4 +273,4 @@ async def get_all_keys_status(key_names: list) -> dict:
     """Return {key_name: bool} presence map — no Touch ID (no secret values returned)."""
     _migrate_legacy_keys()
     bundle = _load_bundle()
-    return {k: bool(bundle.get(k)) for k in key_names}
+    return {k: bool(_env_key(k) or bundle.get(k)) for k in key_names}
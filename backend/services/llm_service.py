@@ -313,6 +313,7 @@ async def stream_text(
     temperature_override: Optional[float] = None,
     extra_options: dict = None,
     voice_modifier: bool = True,
+    model_override: Optional[str] = None,
 ) -> AsyncGenerator[str, None]:
     """Stream response from Ollama API with stop sequences to prevent citation lists.
 
@@ -325,13 +326,16 @@ async def stream_text(
         extra_options: Additional Ollama options merged last (e.g., Mirostat overrides).
         voice_modifier: Prepend the active model's voice instruction to the system prompt.
                         Defaults True. Set False for structured/format-sensitive outputs.
+        model_override: Stream from this model instead (a notebook's own model choice).
     """
     timeout = httpx.Timeout(10.0, read=600.0)
 
     # Two-tier model selection:
     # - System 1 (phi4-mini): Factual queries, fast responses
     # - System 2 (olmo-3:7b-instruct): Synthesis, complex queries, Deep Think
-    if use_fast_model and not deep_think:
+    if model_override:
+        model = model_override
+    elif use_fast_model and not deep_think:
         model = settings.ollama_fast_model
     else:
         model = settings.ollama_model
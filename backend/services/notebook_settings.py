@@ -0,0 +1,95 @@
+"""
+Notebook Settings — per-notebook overrides resolved by the desktop app.
+
+The app keeps global defaults plus per-notebook overrides (model and
+temperature, chunk size, citation style) and writes the resolved result for each notebook to
+notebook_settings.json in the data directory whenever they change, so the
+backend and the UI agree on what applies. This module reads that file,
+re-reading it when it changes. Without the app (or the file), nothing is
+overridden and the backend's own configuration applies.
+"""
+import json
+from pathlib import Path
+from typing import Any, Dict, Optional
+
+from config import settings
+
+_FILE = "notebook_settings.json"
+
+_cache: Dict[str, Any] = {}
+_cache_mtime: Optional[float] = None
+
+# System-prompt lines for the citation styles other than the default
+# inline [N] after each fact; they override the output rules' citation rule.
+_CITATION_INSTRUCTIONS = {
+    "paragraph": "CITATION STYLE (overrides the rule above): cite sources once at the end of each paragraph as [1], [2], not after every fact.",
+    "off": "CITATION STYLE (overrides the rule above): do not put citation markers like [1] in the answer.",
+}
+
+# The closing line of the user prompt, per citation style.
+_ANSWER_LINES = {
+    "inline": "Answer the question, citing sources inline as [N]. Do not list references at the end.",
+    "paragraph": "Answer the question, citing sources as [N] at the end of each paragraph. Do not list references at the end.",
+    "off": "Answer the question without citation markers. Do not list references at the end.",
+}
+
+
+def _path() -> Path:
+    return Path(settings.data_dir) / _FILE
+
+
+def _load() -> Dict[str, Any]:
+    global _cache, _cache_mtime
+    try:
+        mtime = _path().stat().st_mtime
+    except OSError:
+        _cache, _cache_mtime = {}, None
+        return _cache
+    if mtime != _cache_mtime:
+        try:
+            _cache = json.loads(_path().read_text(encoding="utf-8"))
+        except (OSError, ValueError) as e:
+            print(f"[NotebookSettings] Could not read {_FILE}: {e}")
+            _cache = {}
+        _cache_mtime = mtime
+    return _cache
+
+
+def effective(notebook_id: Optional[str]) -> Dict[str, Any]:
+    """The settings that apply in a notebook (the defaults for None or a
+    notebook without overrides)."""
+    data = _load()
+    notebooks = data.get("notebooks") or {}
+    if notebook_id and notebook_id in notebooks:
+        return notebooks[notebook_id]
+    return data.get("defaults") or {}
+
+
+def chunk_size(notebook_id: Optional[str]) -> Optional[int]:
+    """Characters per chunk for the notebook; None uses settings.chunk_size."""
+    size = effective(notebook_id).get("chunk_size")
+    return size if isinstance(size, int) and size > 0 else None
+
+
+def model(notebook_id: Optional[str]) -> Optional[str]:
+    """The answer model for the notebook; None uses settings.ollama_model."""
+    name = effective(notebook_id).get("model")
+    return name if isinstance(name, str) and name else None
+
+
+def temperature(notebook_id: Optional[str]) -> Optional[float]:
+    """The answer temperature for the notebook; None uses the model's defaults."""
+    value = effective(notebook_id).get("temperature")
+    return float(value) if isinstance(value, (int, float)) and not isinstance(value, bool) else None
+
+
+def citation_instruction(notebook_id: Optional[str]) -> str:
+    """A system-prompt line for the notebook's citation style; empty for the
+    default inline [N] citations."""
+    return _CITATION_INSTRUCTIONS.get(effective(notebook_id).get("citation_style"), "")
+
+
+def answer_instruction(notebook_id: Optional[str]) -> str:
+    """The closing line of the answer prompt for the notebook's citation style."""
+    style = effective(notebook_id).get("citation_style")
+    return _ANSWER_LINES.get(style, _ANSWER_LINES["inline"])
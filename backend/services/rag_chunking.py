@@ -5,14 +5,14 @@ Extracted from rag_engine.py Phase 4a. Functions take explicit parameters
 instead of relying on instance state. RAGEngine delegates to these.
 """
 import re
-from typing import List
+from typing import List, Optional
 
 from config import settings
 
 
 # ─── Smart Chunking Router ──────────────────────────────────────────────────────
 
-def chunk_text_smart(text: str, source_type: str, filename: str) -> List[str]:
+def chunk_text_smart(text: str, source_type: str, filename: str, chunk_size: Optional[int] = None) -> List[str]:
     """Smart chunking that adapts strategy based on source type.
     
     Different file types need different chunking strategies:
@@ -20,6 +20,9 @@ def chunk_text_smart(text: str, source_type: str, filename: str) -> List[str]:
     - Documents (pdf, docx): Hierarchical chunking by sections/paragraphs
     - Code: Split by functions/classes
     - Transcripts: Split by speaker turns or time segments
+
+    chunk_size: characters per chunk; None uses settings.chunk_size. A
+    notebook's own size comes from services.notebook_settings.
     """
     filename_lower = filename.lower()
     
@@ -32,22 +35,22 @@ def chunk_text_smart(text: str, source_type: str, filename: str) -> List[str]:
         is_tabular = True
     
     if is_tabular:
-        return chunk_tabular_data(text)
+        return chunk_tabular_data(text, chunk_size)
     
     # Use hierarchical chunking for structured documents (PDFs, docx)
     is_structured_doc = source_type in ['pdf', 'docx', 'doc', 'pptx'] or \
                        filename_lower.endswith(('.pdf', '.docx', '.doc', '.pptx'))
     
     if is_structured_doc and len(text) > 2000:
-        return chunk_hierarchical(text, filename)
+        return chunk_hierarchical(text, filename, chunk_size)
     
     # Default: use standard semantic chunking
-    return chunk_text(text)
+    return chunk_text(text, chunk_size)
 
 
 # ─── Hierarchical Chunking ──────────────────────────────────────────────────────
 
-def chunk_hierarchical(text: str, filename: str) -> List[str]:
+def chunk_hierarchical(text: str, filename: str, chunk_size: Optional[int] = None) -> List[str]:
     """Hierarchical chunking for structured documents.
     
     Creates chunks at section and paragraph levels while preserving
@@ -83,12 +86,12 @@ def chunk_hierarchical(text: str, filename: str) -> List[str]:
         print(f"[RAG] Hierarchical chunking failed, falling back to standard: {e}")
     
     # Fallback to standard chunking
-    return chunk_text(text)
+    return chunk_text(text, chunk_size)
 
 
 # ─── Tabular Chunking ───────────────────────────────────────────────────────────
 
-def chunk_tabular_data(text: str) -> List[str]:
+def chunk_tabular_data(text: str, chunk_size: Optional[int] = None) -> List[str]:
     """Chunk tabular data keeping related rows together with context.
     
     Strategy:
@@ -96,7 +99,7 @@ def chunk_tabular_data(text: str) -> List[str]:
     2. Group rows into chunks respecting both row count AND character limits
     3. Prepend header context to each chunk for self-contained retrieval
     """
-    max_chunk_chars = settings.chunk_size
+    max_chunk_chars = chunk_size or settings.chunk_size
     
     lines = text.split('\n')
     
@@ -149,7 +152,7 @@ def chunk_tabular_data(text: str) -> List[str]:
             chunks.append(chunk_text)
     
     if not chunks:
-        return chunk_text_fallback(text)
+        return chunk_text_fallback(text, chunk_size)
     
     print(f"[RAG] Tabular chunking: {len(data_lines)} rows -> {len(chunks)} chunks (max {max_chunk_chars} chars/chunk)")
     return chunks
@@ -157,14 +160,14 @@ def chunk_tabular_data(text: str) -> List[str]:
 
 # ─── Standard Semantic Chunking ──────────────────────────────────────────────────
 
-def chunk_text(text: str) -> List[str]:
+def chunk_text(text: str, chunk_size: Optional[int] = None) -> List[str]:
     """Chunk text into smaller pieces with semantic boundary awareness.
     
     Tries to split at paragraph/sentence boundaries rather than mid-sentence
     for better embedding quality. Falls back to character-based splitting.
     """
-    chunk_size = settings.chunk_size
-    chunk_overlap = settings.chunk_overlap
+    chunk_size = chunk_size or settings.chunk_size
+    chunk_overlap = min(settings.chunk_overlap, chunk_size // 4)
 
     paragraphs = [p.strip() for p in text.split('\n\n') if p.strip()]
     
@@ -214,9 +217,9 @@ def chunk_text(text: str) -> List[str]:
 
 
 # Alias for tabular fallback (avoids circular call)
-def chunk_text_fallback(text: str) -> List[str]:
+def chunk_text_fallback(text: str, chunk_size: Optional[int] = None) -> List[str]:
     """Fallback chunking used when tabular chunking produces no results."""
-    return chunk_text(text)
+    return chunk_text(text, chunk_size)
 
 
 # ─── Helpers ─────────────────────────────────────────────────────────────────────
@@ -35,6 +35,7 @@ from services.entity_graph import entity_graph
 from services.community_detection import community_detector
 from services import rag_query_analyzer
 from services import rag_chunking
+from services import notebook_settings
 from services import rag_generation
 from services import rag_embeddings
 from services import llm_service
@@ -221,6 +222,8 @@ class RAGEngine:
         enable_web_search: bool = False,
         llm_provider: Optional[str] = None,
         extra_system_context: Optional[str] = None,
+        model: Optional[str] = None,
+        temperature: Optional[float] = None,
     ) -> Dict:
         """Query the RAG system (non-streaming).
 
@@ -228,8 +231,15 @@ class RAGEngine:
         prompt. Used by api/chat.py to inject the curator's mental model
         for this notebook (Curator Phase 3.5, 2026-05-13). Default None
         preserves existing behaviour for all other callers.
+
+        model / temperature: the answer model and its temperature. None
+        uses the notebook's settings (notebook_settings), else the defaults.
         """
         total_start = time.time()
+        # The notebook's model settings when the caller didn't pass them.
+        model = model or notebook_settings.model(notebook_id)
+        if temperature is None:
+            temperature = notebook_settings.temperature(notebook_id)
         query_id = str(uuid.uuid4())
         query_type = self._classify_query(question)
         
@@ -472,7 +482,7 @@ class RAGEngine:
         conversation_id = str(uuid.uuid4())
         answer_result = await self._generate_answer(
             question, context, num_citations, llm_provider, notebook_id, conversation_id,
-            extra_system_context=extra_system_context,
+            extra_system_context=extra_system_context, model=model, temperature=temperature,
         )
         answer = answer_result["answer"]
         memory_used = answer_result.get("memory_used", [])
@@ -510,7 +520,8 @@ class RAGEngine:
                 
                 # Regenerate answer with better context
                 answer_result = await self._generate_answer(
-                    question, context, num_citations, llm_provider, notebook_id, conversation_id
+                    question, context, num_citations, llm_provider, notebook_id, conversation_id,
+                    model=model, temperature=temperature,
                 )
                 answer = answer_result["answer"]
                 print(f"[RAG] Step 5b - Corrective retrieval + re-answer: {time.time() - step_start:.2f}s")
@@ -546,7 +557,8 @@ class RAGEngine:
                         # Regenerate answer with web context
                         answer_result = await self._generate_answer(
                             question, combined_context, num_citations + len(web_sources),
-                            llm_provider, notebook_id, conversation_id
+                            llm_provider, notebook_id, conversation_id,
+                            model=model, temperature=temperature,
                         )
                         answer = answer_result["answer"]
                         low_confidence = False  # Web augmented, no longer low confidence
@@ -772,6 +784,8 @@ JSON:"""
         deep_think: bool = False,
         extra_system_context: Optional[str] = None,
         use_cache: bool = True,
+        model: Optional[str] = None,
+        temperature: Optional[float] = None,
     ) -> AsyncGenerator[Dict, None]:
         """Query the RAG system with streaming response.
 
@@ -779,8 +793,15 @@ JSON:"""
         prompt. Used by api/chat.py to inject the curator's mental model
         for this notebook (Curator Phase 3.5, 2026-05-13). Default None
         preserves existing behaviour for all other callers.
+
+        model / temperature: the answer model and its temperature. None
+        uses the notebook's settings (notebook_settings), else the defaults.
         """
         total_start = time.time()
+        # The notebook's model settings when the caller didn't pass them.
+        model = model or notebook_settings.model(notebook_id)
+        if temperature is None:
+            temperature = notebook_settings.temperature(notebook_id)
         query_id = str(uuid.uuid4())
         query_type = self._classify_query(question)
         
@@ -1127,6 +1148,9 @@ JSON:"""
         # FORMAT REQUIREMENTS in their prompts, and dual FORMAT instructions confuse the LLM
         format_hint = self._detect_response_format(question) if query_type == 'factual' else ""
         system_prompt = f"User context: {user_context}\n\n{base_prompt}{format_hint}" if user_context else f"{base_prompt}{format_hint}"
+        citation_instruction = notebook_settings.citation_instruction(notebook_id)
+        if citation_instruction:
+            system_prompt = f"{system_prompt}\n\n{citation_instruction}"
 
         # Curator Phase 3.5 (2026-05-13): if api/chat.py passed mental-model
         # context, prepend it. Terse — just thesis + stage. Skipped when
@@ -1168,7 +1192,7 @@ JSON:"""
 
 Question: {question}{temporal_note}
 
-Answer the question, citing sources inline as [N]. Do not list references at the end."""
+{notebook_settings.answer_instruction(notebook_id)}"""
 
         # Two-tier model routing:
         # - System 1 (phi4-mini): Factual queries - fast, reliable
@@ -1196,7 +1220,7 @@ Answer the question, citing sources inline as [N]. Do not list references at the
         references_started = False
         
         # Phase 1: STREAM — emit tokens in real-time as they arrive
-        async for token in self._stream_ollama(system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model):
+        async for token in self._stream_ollama(system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model, temperature_override=temperature, model_override=model):
             full_answer += token
             
             # Detect references/bibliography section — stop streaming there
@@ -1253,7 +1277,8 @@ Answer the question, citing sources inline as [N]. Do not list references at the
             retry_answer = ""
             try:
                 async for _tok in self._stream_ollama(
-                    system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model
+                    system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model,
+                    temperature_override=temperature, model_override=model,
                 ):
                     retry_answer += _tok
             except Exception as _empty_e:
@@ -1302,7 +1327,7 @@ Answer the question, citing sources inline as [N]. Do not list references at the
 
 Question: {question}{temporal_note}
 
-Answer the question, citing sources inline as [N]. Do not list references at the end."""
+{notebook_settings.answer_instruction(notebook_id)}"""
                         retry_citations = c_citations
                         print(f"[RAG STREAM] CaRR: corrective retrieval added "
                               f"{len(corrected_results) - len(results)} new chunk(s) → retrying on fresh evidence")
@@ -1434,13 +1459,14 @@ Answer the question, citing sources inline as [N]. Do not list references at the
         """Fallback suggested questions"""
         return rag_generation.default_suggested_questions()
 
-    async def _generate_answer(self, question: str, context: str, num_citations: int = 5, llm_provider: Optional[str] = None, notebook_id: Optional[str] = None, conversation_id: Optional[str] = None, deep_think: bool = False, extra_system_context: Optional[str] = None) -> Dict:
+    async def _generate_answer(self, question: str, context: str, num_citations: int = 5, llm_provider: Optional[str] = None, notebook_id: Optional[str] = None, conversation_id: Optional[str] = None, deep_think: bool = False, extra_system_context: Optional[str] = None, model: Optional[str] = None, temperature: Optional[float] = None) -> Dict:
         """Generate answer using LLM with memory augmentation and user personalization."""
         return await rag_generation.generate_answer(
             question, context, num_citations=num_citations, llm_provider=llm_provider,
             notebook_id=notebook_id, conversation_id=conversation_id, deep_think=deep_think,
             detect_response_format_fn=self._detect_response_format,
             extra_system_context=extra_system_context,
+            model=model, temperature=temperature,
         )
     
     async def _call_ollama(self, system_prompt: str, prompt: str, model: str = None, num_predict: int = 500, num_ctx: int = None, temperature: float = None, repeat_penalty: float = None, extra_options: dict = None, voice_modifier: bool = True) -> str:
@@ -1458,7 +1484,7 @@ Answer the question, citing sources inline as [N]. Do not list references at the
         """
         return await llm_service.generate_text(system_prompt, prompt, model=model, num_predict=num_predict, num_ctx=num_ctx, temperature=temperature, repeat_penalty=repeat_penalty, extra_options=extra_options, voice_modifier=voice_modifier)
 
-    async def _stream_ollama(self, system_prompt: str, prompt: str, deep_think: bool = False, use_fast_model: bool = False, num_predict: Optional[int] = None, temperature_override: Optional[float] = None, extra_options: dict = None) -> AsyncGenerator[str, None]:
+    async def _stream_ollama(self, system_prompt: str, prompt: str, deep_think: bool = False, use_fast_model: bool = False, num_predict: Optional[int] = None, temperature_override: Optional[float] = None, extra_options: dict = None, model_override: Optional[str] = None) -> AsyncGenerator[str, None]:
         """Stream response from Ollama API with stop sequences to prevent citation lists
         
         Args:
@@ -1468,13 +1494,14 @@ Answer the question, citing sources inline as [N]. Do not list references at the
                          Set higher (2000-4000) for document generation.
             temperature_override: Per-skill adaptive temperature. None = use model defaults.
             extra_options: Additional Ollama options merged last (e.g., Mirostat overrides).
+            model_override: Model to stream from instead of the System 1/2 choice.
         """
-        async for token in llm_service.stream_text(system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model, num_predict=num_predict, temperature_override=temperature_override, extra_options=extra_options):
+        async for token in llm_service.stream_text(system_prompt, prompt, deep_think=deep_think, use_fast_model=use_fast_model, num_predict=num_predict, temperature_override=temperature_override, extra_options=extra_options, model_override=model_override):
             yield token
 
-    def _chunk_text_smart(self, text: str, source_type: str, filename: str) -> List[str]:
+    def _chunk_text_smart(self, text: str, source_type: str, filename: str, chunk_size: Optional[int] = None) -> List[str]:
         """Smart chunking that adapts strategy based on source type."""
-        return rag_chunking.chunk_text_smart(text, source_type, filename)
+        return rag_chunking.chunk_text_smart(text, source_type, filename, chunk_size)
     
     def _chunk_hierarchical(self, text: str, filename: str) -> List[str]:
         """Hierarchical chunking for structured documents."""
@@ -10,6 +10,7 @@ from typing import Dict, List, Optional
 
 from config import settings
 from services import llm_service
+from services import notebook_settings
 
 
 # ─── Prompt Templates ────────────────────────────────────────────────────────────
@@ -165,8 +166,13 @@ async def generate_answer(
     deep_think: bool = False,
     detect_response_format_fn=None,
     extra_system_context: Optional[str] = None,
+    model: Optional[str] = None,
+    temperature: Optional[float] = None,
 ) -> Dict:
     """Generate answer using LLM with memory augmentation and user personalization.
+
+    model / temperature override the answer model and its temperature (the
+    notebook's settings); None keeps the defaults.
     
     Returns dict with answer, memory_used, and memory_context_summary.
     """
@@ -221,6 +227,9 @@ async def generate_answer(
         if memory_context.core_memory_block.strip():
             memory_used.append("core_context")
     system_parts.append(base_prompt)
+    citation_instruction = notebook_settings.citation_instruction(notebook_id)
+    if citation_instruction:
+        system_parts.append(citation_instruction)
 
     # Only add format hint for factual queries (synthesis/complex already have FORMAT REQUIREMENTS)
     if detect_response_format_fn and query_type == 'factual':
@@ -241,12 +250,12 @@ async def generate_answer(
 {memory_section}
 Q: {question}
 
-Answer the question, citing sources inline as [N]. Do not list references at the end."""
+{notebook_settings.answer_instruction(notebook_id)}"""
 
     # Simplification S1/B2 (2026-07-03): cloud providers removed — LocalBook is
     # 100% local by design; no UI ever surfaced openai/anthropic. llm_provider is
     # kept in signatures only to avoid a wide call-chain refactor.
-    answer = await llm_service.generate_text(system_prompt, prompt)
+    answer = await llm_service.generate_text(system_prompt, prompt, model=model, temperature=temperature)
 
     return {
         "answer": answer,
@@ -48,7 +48,7 @@ def _get_reranker():
     if HAS_FLASHRANK and settings.reranker_type == "flashrank":
         if _flashrank_reranker is None:
             # Use persistent cache dir (not /tmp which gets cleared on reboot)
-            cache_dir = settings.data_dir / "models" / "flashrank"
+            cache_dir = settings.models_dir / "flashrank"
             cache_dir.mkdir(parents=True, exist_ok=True)
             _flashrank_reranker = FlashRanker(
                 model_name=settings.reranker_model,
@@ -25,6 +25,7 @@ import numpy as np
 from config import settings
 from services import rag_embeddings
 from services import rag_chunking
+from services import notebook_settings
 from services.entity_extractor import entity_extractor
 from services.entity_graph import entity_graph
 from services.progress_reporter import ProgressReporter, get_noop_reporter
@@ -151,7 +152,9 @@ async def ingest_document(
 
     # Use source-type-aware chunking for better retrieval
     await reporter.emit("chunking", 50, f"Splitting text into semantic chunks ({source_type})...")
-    chunks = rag_chunking.chunk_text_smart(text, source_type, filename)
+    chunks = rag_chunking.chunk_text_smart(
+        text, source_type, filename, chunk_size=notebook_settings.chunk_size(notebook_id)
+    )
     await reporter.emit(
         "chunking", 55,
         f"Split into {len(chunks)} chunks for semantic search",
@@ -387,7 +390,9 @@ async def append_to_document(
         return {"chunks_added": 0}
 
     # Chunk the new text
-    chunks = rag_chunking.chunk_text_smart(text, "supplementary", "background")
+    chunks = rag_chunking.chunk_text_smart(
+        text, "supplementary", "background", chunk_size=notebook_settings.chunk_size(notebook_id)
+    )
 
     if not chunks:
         return {"chunks_added": 0}
@@ -7,6 +7,8 @@ Handles all first-launch and upgrade checks:
 3. Embedding dimension migration (768 -> 1024 for all tables)
 4. Knowledge graph table schema validation
 """
+import os
+
 import httpx
 import lancedb
 import pyarrow as pa
@@ -46,12 +48,18 @@ MIN_OLLAMA_VERSION = "0.5.0"
 EXPECTED_EMBEDDING_DIM = 1024
 
 
-async def run_all_startup_checks(status_callback=None) -> Dict[str, Any]:
+def skip_index_checks() -> bool:
+    """Whether the app asked us to leave out the checks that read every index."""
+    return os.environ.get("LOCALBOOK_SKIP_INDEX_CHECKS") == "1"
+
+
+async def run_all_startup_checks(status_callback=None, skip_index: bool = False) -> Dict[str, Any]:
     """
     Run all startup checks and migrations.
     
     Args:
         status_callback: Optional function(status, message, progress) to report progress
+        skip_index: Leave out the embedding dimension checks (steps 3-5)
     
     Returns:
         Dict with results of all checks
@@ -102,6 +110,13 @@ async def run_all_startup_checks(status_callback=None) -> Dict[str, Any]:
                 f"Models not installed: {_missing_names}. Install them in LLM Labs "
                 f"or run `ollama pull <model>`. (Not auto-downloaded — Wave 9 decision.)")
         
+        if skip_index:
+            print("[Startup] Skipping index checks (startup setting)")
+            results["embedding_migration"] = "skipped"
+            results["kg_migration"] = "skipped"
+            update_status("ready", "All checks complete!", 100)
+            return results
+
         # Step 3: Check RAG embedding dimensions
         update_status("checking", "Checking embedding compatibility...", 50)
         rag_needs_migration = check_rag_embedding_dimensions()
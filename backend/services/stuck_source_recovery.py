@@ -205,7 +205,10 @@ class StuckSourceRecovery:
                 source_type = "document"
             
             # Chunk the content
-            chunks = rag_engine._chunk_text_smart(content, source_type, title)
+            from services import notebook_settings
+            chunks = rag_engine._chunk_text_smart(
+                content, source_type, title, notebook_settings.chunk_size(notebook_id)
+            )
             
             if not chunks:
                 return 0
@@ -8,6 +8,9 @@ This installs:
     bundled-binary process (whose stdout the Tauri app swallows) leaves
     a tailable trail for support / live debugging.
   - Suppresses noisy third-party loggers
+
+With LOCALBOOK_DEBUG=1 (the app's debug mode) everything logs at DEBUG and
+the third-party loggers aren't quieted.
 """
 import logging
 import os
@@ -15,6 +18,8 @@ import sys
 from logging.handlers import RotatingFileHandler
 from pathlib import Path
 
+from utils.request_id import RequestIdFilter
+
 
 def _resolve_log_path() -> Path:
     """Where should backend.log live?
@@ -42,7 +47,12 @@ def _resolve_log_path() -> Path:
     return base / "backend.log"
 
 
-def setup_logging(level: int = logging.INFO) -> None:
+def debug_mode() -> bool:
+    """Whether the app started us in debug mode."""
+    return os.environ.get("LOCALBOOK_DEBUG") == "1"
+
+
+def setup_logging(level: int | None = None) -> None:
     """Configure the root logger with Rich output AND a rotating log file.
 
     Safe to call in PyInstaller bundles — falls back to standard
@@ -51,6 +61,8 @@ def setup_logging(level: int = logging.INFO) -> None:
     too: if the log directory can't be created (read-only home, weird
     sandboxing), we skip it without breaking startup.
     """
+    if level is None:
+        level = logging.DEBUG if debug_mode() else logging.INFO
     handlers: list[logging.Handler] = []
 
     # ── Console: Rich if available, else stdlib basicConfig ──
@@ -100,12 +112,15 @@ def setup_logging(level: int = logging.INFO) -> None:
             encoding="utf-8",
         )
         file_handler.setLevel(level)
+        # request_id: the shell's X-Request-ID while handling one of its
+        # requests, "-" otherwise (utils/request_id.py).
         file_handler.setFormatter(
             logging.Formatter(
-                fmt="[%(asctime)s] %(levelname)-7s %(name)s — %(message)s",
+                fmt="[%(asctime)s] %(levelname)-7s %(name)s [%(request_id)s] — %(message)s",
                 datefmt="%Y-%m-%d %H:%M:%S",
             )
         )
+        file_handler.addFilter(RequestIdFilter())
         handlers.append(file_handler)
     except Exception as e:  # pragma: no cover — never break startup over logging
         # Use stderr directly since logging isn't configured yet.
@@ -129,7 +144,8 @@ def setup_logging(level: int = logging.INFO) -> None:
     except Exception:
         pass
 
-    _quiet_noisy_loggers()
+    if not debug_mode():
+        _quiet_noisy_loggers()
 
 
 def _quiet_noisy_loggers() -> None:
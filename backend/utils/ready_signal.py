@@ -0,0 +1,38 @@
+"""Tell the Tauri shell the moment the API is accepting connections.
+
+The shell reads our stdout (src-tauri/src/backend_events.rs) and, on a line
+``READY {"port": 8000}``, marks the backend ready without waiting for its
+next /health poll. The lifespan starts before uvicorn binds its socket, so
+``announce_ready`` waits until a connection to our own port succeeds. If it
+never does the shell's polling still applies; nothing depends on the line.
+"""
+import asyncio
+import json
+import logging
+
+logger = logging.getLogger(__name__)
+
+_POLL_SECONDS = 0.05
+_GIVE_UP_SECONDS = 60
+
+
+async def announce_ready(host: str, port: int) -> None:
+    """Print the READY line once ``host:port`` accepts a connection."""
+    probe_host = "127.0.0.1" if host in ("", "0.0.0.0", "localhost") else host
+    loop = asyncio.get_running_loop()
+    deadline = loop.time() + _GIVE_UP_SECONDS
+    while loop.time() < deadline:
+        try:
+            _, writer = await asyncio.open_connection(probe_host, port)
+        except OSError:
+            await asyncio.sleep(_POLL_SECONDS)
+            continue
+        writer.close()
+        try:
+            await writer.wait_closed()
+        except OSError:
+            pass
+        # stdout is a pipe, so block-buffered: flush or the shell waits.
+        print(f"READY {json.dumps({'port': port})}", flush=True)
+        return
+    logger.warning(f"[ready] Port {port} never accepted a connection; not announcing")
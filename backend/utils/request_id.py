@@ -0,0 +1,68 @@
+"""Request ids shared with the Tauri shell, for following one request
+through both logs.
+
+The shell sends a fresh ``X-Request-ID`` with every request it makes (see
+src-tauri/src/traces.rs). ``RequestIdMiddleware`` keeps it in a context
+variable for the length of the request, echoes it on the response, and logs
+the request's status and duration under it. ``RequestIdFilter`` gives every
+log record a ``request_id`` attribute ("-" outside a traced request) so the
+file formatter can print it. Requests without the header (the webview's)
+aren't logged here.
+"""
+import logging
+import re
+import time
+from contextvars import ContextVar
+
+HEADER = b"x-request-id"
+_VALID = re.compile(r"^[A-Za-z0-9-]{1,64}$")
+
+request_id: ContextVar[str] = ContextVar("request_id", default="-")
+
+logger = logging.getLogger(__name__)
+
+
+class RequestIdFilter(logging.Filter):
+    """Adds ``record.request_id`` for formatters."""
+
+    def filter(self, record: logging.LogRecord) -> bool:
+        record.request_id = request_id.get()
+        return True
+
+
+class RequestIdMiddleware:
+    """Plain ASGI middleware, so the id is set in the same context the
+    endpoint runs in (streaming responses included)."""
+
+    def __init__(self, app):
+        self.app = app
+
+    async def __call__(self, scope, receive, send):
+        if scope["type"] != "http":
+            return await self.app(scope, receive, send)
+        given = dict(scope.get("headers") or []).get(HEADER, b"").decode("latin-1")
+        if not _VALID.match(given):
+            return await self.app(scope, receive, send)
+
+        token = request_id.set(given)
+        started = time.monotonic()
+        status = 0
+
+        async def send_with_id(message):
+            nonlocal status
+            if message["type"] == "http.response.start":
+                status = message["status"]
+                message["headers"] = list(message.get("headers", [])) + [(HEADER, given.encode())]
+            await send(message)
+
+        try:
+            await self.app(scope, receive, send_with_id)
+        finally:
+            if scope.get("path") != "/health":
+                # The id is in the message too: console output (and the
+                # shell's copy of it) has no request_id column.
+                logger.info(
+                    f"[request {given}] {scope.get('method')} {scope.get('path')} "
+                    f"{status or 'failed'} in {(time.monotonic() - started) * 1000:.0f}ms"
+                )
+            request_id.reset(token)
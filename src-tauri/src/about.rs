@@ -0,0 +1,54 @@
+//! Version and install details in one place.
+//!
+//! `get_app_info` is what the About screen shows and what goes into a
+//! diagnostics bundle as `info.json`, so a bug report and a screenshot of
+//! About never disagree. The commit and build date are stamped in by
+//! `build.rs`.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Serialize)]
+pub(crate) struct AppInfo {
+    app_version: String,
+    tauri_version: &'static str,
+    /// The backend in use: an installed update, or the bundled one.
+    backend_version: String,
+    git_commit: &'static str,
+    /// UTC, YYYY-MM-DD.
+    build_date: &'static str,
+    debug_build: bool,
+    os: &'static str,
+    arch: &'static str,
+    /// The shell's data folder (settings, logs, crash reports).
+    data_dir: Option<PathBuf>,
+    /// The backend's data folder (notebooks, sources, indexes).
+    backend_data_dir: PathBuf,
+    models_dir: Option<PathBuf>,
+    /// None = the default profile.
+    profile: Option<String>,
+}
+
+pub(crate) fn info(app: &AppHandle) -> AppInfo {
+    AppInfo {
+        app_version: app.package_info().version.to_string(),
+        tauri_version: tauri::VERSION,
+        backend_version: crate::sidecar::backend_version(app),
+        git_commit: env!("LOCALBOOK_GIT_COMMIT"),
+        build_date: env!("LOCALBOOK_BUILD_DATE"),
+        debug_build: cfg!(debug_assertions),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        data_dir: crate::data_dir(app).ok(),
+        backend_data_dir: crate::backend_data_dir(),
+        models_dir: crate::models::models_dir(app).ok(),
+        profile: None,
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_app_info(app: AppHandle) -> Result<AppInfo, String> {
+    Ok(info(&app))
+}
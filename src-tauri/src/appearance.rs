@@ -0,0 +1,278 @@
+//! Appearance preferences that aren't the theme itself: text size, spacing,
+//! accent colour, and a native translucent background per window.
+//!
+//! Font scale, density and accent colour are only stored here; the webview
+//! applies them and hears about changes on `appearance://changed`. Window
+//! effects are applied from Rust: vibrancy on macOS, Mica or Acrylic on
+//! Windows (Mica needs Windows 11; on 10 the window just stays opaque). They
+//! are chosen per kind of window — main, mini, notebook — so every notebook
+//! window gets the same, and follow the effective light or dark theme, so
+//! they're applied again when it changes. While one is on the webview's
+//! background is cleared; the frontend should then leave its own background
+//! translucent for the effect to show.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+const MIN_FONT_SCALE: f32 = 0.75;
+const MAX_FONT_SCALE: f32 = 1.5;
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Density {
+    Compact,
+    #[default]
+    Comfortable,
+    Spacious,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WindowEffect {
+    #[default]
+    None,
+    /// macOS.
+    Vibrancy,
+    /// Windows 11.
+    Mica,
+    /// Windows.
+    Acrylic,
+}
+
+impl WindowEffect {
+    fn name(self) -> &'static str {
+        match self {
+            WindowEffect::None => "none",
+            WindowEffect::Vibrancy => "vibrancy",
+            WindowEffect::Mica => "mica",
+            WindowEffect::Acrylic => "acrylic",
+        }
+    }
+
+    fn supported(self) -> bool {
+        match self {
+            WindowEffect::None => true,
+            WindowEffect::Vibrancy => cfg!(target_os = "macos"),
+            WindowEffect::Mica | WindowEffect::Acrylic => cfg!(target_os = "windows"),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AppearanceSettings {
+    /// Multiplies the base font size, 0.75–1.5.
+    pub font_scale: f32,
+    pub density: Density,
+    /// "#rrggbb"; None follows the OS accent (see `theme`).
+    pub accent_color: Option<String>,
+    /// By window kind: "main", "mini", "notebook".
+    pub window_effects: HashMap<String, WindowEffect>,
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            font_scale: 1.0,
+            density: Density::default(),
+            accent_color: None,
+            window_effects: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Appearance {
+    #[serde(flatten)]
+    settings: AppearanceSettings,
+    /// The effects this platform has.
+    supported_effects: Vec<WindowEffect>,
+}
+
+fn appearance(settings: AppearanceSettings) -> Appearance {
+    let supported_effects = [
+        WindowEffect::None,
+        WindowEffect::Vibrancy,
+        WindowEffect::Mica,
+        WindowEffect::Acrylic,
+    ]
+    .into_iter()
+    .filter(|e| e.supported())
+    .collect();
+    Appearance {
+        settings,
+        supported_effects,
+    }
+}
+
+/// What kind of window `label` is, for `window_effects`.
+fn window_kind(label: &str) -> &str {
+    if label.starts_with(crate::windows::NOTEBOOK_WINDOW_PREFIX) {
+        "notebook"
+    } else {
+        label
+    }
+}
+
+fn check_accent(color: &str) -> Result<(), String> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid accent colour {:?}; use #rrggbb", color))
+    }
+}
+
+fn apply_effect(window: &WebviewWindow, effect: WindowEffect, dark: bool) {
+    #[cfg(desktop)]
+    {
+        use tauri::window::{Color, Effect, EffectState, EffectsBuilder};
+        let native = match effect {
+            WindowEffect::None => None,
+            WindowEffect::Vibrancy => {
+                Some(EffectsBuilder::new().effect(Effect::UnderWindowBackground))
+            }
+            WindowEffect::Mica if dark => Some(EffectsBuilder::new().effect(Effect::MicaDark)),
+            WindowEffect::Mica => Some(EffectsBuilder::new().effect(Effect::MicaLight)),
+            WindowEffect::Acrylic => {
+                let tint = if dark {
+                    Color(32, 32, 32, 128)
+                } else {
+                    Color(243, 243, 243, 128)
+                };
+                Some(EffectsBuilder::new().effect(Effect::Acrylic).color(tint))
+            }
+        };
+        let on = native.is_some();
+        let effects = native.map(|b| b.state(EffectState::FollowsWindowActiveState).build());
+        if let Err(e) = window.set_effects(effects) {
+            tracing::warn!("[Appearance] Effect on {} failed: {}", window.label(), e);
+            return;
+        }
+        let background = on.then_some(Color(0, 0, 0, 0));
+        if let Err(e) = window.set_background_color(background) {
+            tracing::warn!(
+                "[Appearance] Background on {} failed: {}",
+                window.label(),
+                e
+            );
+        }
+    }
+    #[cfg(not(desktop))]
+    let _ = (window, effect, dark);
+}
+
+fn dark(app: &AppHandle) -> bool {
+    crate::theme::effective(app) == "dark"
+}
+
+/// Give a new window its kind's effect. Called once it's built.
+pub(crate) fn apply_window(window: &WebviewWindow) {
+    let app = window.app_handle();
+    let effect = crate::settings::get(app)
+        .appearance
+        .window_effects
+        .get(window_kind(window.label()))
+        .copied()
+        .unwrap_or_default();
+    if effect != WindowEffect::None && effect.supported() {
+        apply_effect(window, effect, dark(app));
+    }
+}
+
+/// Apply every window's effect again, after a settings or theme change.
+pub(crate) fn reapply(app: &AppHandle) {
+    let effects = crate::settings::get(app).appearance.window_effects;
+    let dark = dark(app);
+    for (label, window) in app.webview_windows() {
+        let effect = effects
+            .get(window_kind(&label))
+            .copied()
+            .unwrap_or_default();
+        if effect.supported() {
+            apply_effect(&window, effect, dark);
+        }
+    }
+}
+
+/// Apply the main window's effect. Called in setup.
+pub(crate) fn start(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        apply_window(&window);
+    }
+}
+
+fn changed(app: &AppHandle, settings: AppearanceSettings) -> Appearance {
+    let appearance = appearance(settings);
+    let _ = app.emit("appearance://changed", &appearance);
+    appearance
+}
+
+#[tauri::command]
+pub(crate) async fn get_appearance(app: AppHandle) -> Result<Appearance, String> {
+    Ok(appearance(crate::settings::get(&app).appearance))
+}
+
+/// Save font scale, density, accent colour and window effects together.
+#[tauri::command]
+pub(crate) async fn set_appearance(
+    app: AppHandle,
+    appearance: AppearanceSettings,
+) -> Result<Appearance, String> {
+    if !(MIN_FONT_SCALE..=MAX_FONT_SCALE).contains(&appearance.font_scale) {
+        return Err(format!(
+            "Font scale must be between {} and {}",
+            MIN_FONT_SCALE, MAX_FONT_SCALE
+        ));
+    }
+    if let Some(color) = &appearance.accent_color {
+        check_accent(color)?;
+    }
+    let mut appearance = appearance;
+    appearance.accent_color = appearance.accent_color.map(|c| c.to_lowercase());
+    appearance
+        .window_effects
+        .retain(|_, e| *e != WindowEffect::None);
+    let settings = crate::settings::update(&app, |s| s.appearance = appearance)?;
+    reapply(&app);
+    Ok(changed(&app, settings.appearance))
+}
+
+/// Set the effect for `label`'s kind of window, or the calling window's.
+/// Refused when this platform doesn't have it.
+#[tauri::command]
+pub(crate) async fn set_window_effect(
+    app: AppHandle,
+    window: WebviewWindow,
+    label: Option<String>,
+    effect: WindowEffect,
+) -> Result<Appearance, String> {
+    if !effect.supported() {
+        return Err(format!(
+            "{} isn't available on {}",
+            effect.name(),
+            std::env::consts::OS
+        ));
+    }
+    let label = label.unwrap_or_else(|| window.label().to_string());
+    let kind = window_kind(&label).to_string();
+    let settings = crate::settings::update(&app, |s| {
+        if effect == WindowEffect::None {
+            s.appearance.window_effects.remove(&kind);
+        } else {
+            s.appearance.window_effects.insert(kind.clone(), effect);
+        }
+    })?;
+    let dark = dark(&app);
+    for (label, window) in app.webview_windows() {
+        if window_kind(&label) == kind {
+            apply_effect(&window, effect, dark);
+        }
+    }
+    tracing::info!("[Appearance] {} windows: {}", kind, effect.name());
+    Ok(changed(&app, settings.appearance))
+}
@@ -0,0 +1,159 @@
+//! Local audit log of sensitive operations, for users who have to show who
+//! touched what: notebooks opened, documents imported, exports and backups,
+//! keychain reads and writes, backend restarts, app-lock attempts and
+//! settings changes.
+//!
+//! One JSON object per line in `<app data>/audit.log`, only ever appended to.
+//! Each entry carries the SHA-256 of the line before it, so an edited or
+//! deleted entry breaks the chain and `get_audit_log` reports the log as not
+//! intact. Details name things (a notebook id, a key name, which settings
+//! changed) but never contain their values.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+const LOG_FILE: &str = "audit.log";
+const DEFAULT_LIMIT: usize = 500;
+
+struct Log {
+    path: PathBuf,
+    /// Hash of the last line written, chained into the next entry.
+    last_hash: String,
+    seq: u64,
+}
+
+static LOG: Mutex<Option<Log>> = Mutex::new(None);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct AuditEntry {
+    seq: u64,
+    /// RFC 3339, local time.
+    at: String,
+    action: String,
+    detail: String,
+    /// SHA-256 of the previous line ("" for the first entry).
+    prev: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct AuditLog {
+    /// Newest first.
+    entries: Vec<AuditEntry>,
+    /// Every entry's `prev` matched the line before it.
+    intact: bool,
+}
+
+fn hash(line: &str) -> String {
+    format!("{:x}", Sha256::digest(line.as_bytes()))
+}
+
+/// Open the log and pick up the chain where it left off. Called in setup
+/// right after settings load, so later startup steps are recorded.
+pub(crate) fn start(app: &AppHandle) {
+    let path = match crate::data_dir(app) {
+        Ok(dir) => dir.join(LOG_FILE),
+        Err(e) => {
+            tracing::warn!("[Audit] No app data dir: {}", e);
+            return;
+        }
+    };
+    let (mut last_hash, mut seq) = (String::new(), 0);
+    if let Ok(file) = std::fs::File::open(&path) {
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) {
+                seq = entry.seq;
+            }
+            last_hash = hash(&line);
+        }
+    }
+    *LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(Log {
+        path,
+        last_hash,
+        seq,
+    });
+}
+
+/// Append an entry. Failures are logged, never returned — auditing must not
+/// break the operation being audited.
+pub(crate) fn record(action: &str, detail: impl Into<String>) {
+    let mut guard = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(log) = guard.as_mut() else {
+        return;
+    };
+    let entry = AuditEntry {
+        seq: log.seq + 1,
+        at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        action: action.to_string(),
+        detail: detail.into(),
+        prev: log.last_hash.clone(),
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options
+        .open(&log.path)
+        .and_then(|mut f| writeln!(f, "{}", line));
+    match written {
+        Ok(()) => {
+            log.seq = entry.seq;
+            log.last_hash = hash(&line);
+        }
+        Err(e) => tracing::warn!("[Audit] Failed to write {}: {}", log.path.display(), e),
+    }
+}
+
+/// The most recent entries (default 500), optionally only one action.
+#[tauri::command]
+pub(crate) async fn get_audit_log(
+    limit: Option<usize>,
+    action: Option<String>,
+) -> Result<AuditLog, String> {
+    let path = LOG
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|l| l.path.clone())
+        .ok_or("The audit log isn't available")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = match std::fs::File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(AuditLog {
+                    entries: Vec::new(),
+                    intact: true,
+                })
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let mut entries = Vec::new();
+        let (mut prev, mut intact) = (String::new(), true);
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) => {
+                    intact &= entry.prev == prev;
+                    if action.as_ref().is_none_or(|a| *a == entry.action) {
+                        entries.push(entry);
+                    }
+                }
+                Err(_) => intact = false,
+            }
+            prev = hash(&line);
+        }
+        entries.reverse();
+        entries.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+        Ok(AuditLog { entries, intact })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
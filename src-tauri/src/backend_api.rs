@@ -0,0 +1,305 @@
+//! Calls from the shell to the Python backend: the sidecar on :8000 (or the
+//! `--port` given), or a remote one (see `remote_backend`).
+//!
+//! Long-running backend operations (uploads, OCR) answer with a stream of
+//! server-sent progress frames, `data: {"stage": …, "percent": …}`, ending in
+//! a `complete` frame whose `details` is the result or an `error` frame.
+//! `read_progress_stream` turns that into a callback per frame plus the final
+//! result. Errors keep the distinction between an unreachable backend, an
+//! HTTP error and a failure the backend reported, so background jobs can tell
+//! transient failures from permanent ones.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::multipart;
+use tokio_util::io::ReaderStream;
+
+const SIDECAR_PORT: u16 = 8000;
+
+/// The port the sidecar listens on, on loopback: the one it announced (see
+/// `backend_events`), else the one it's started with.
+pub(crate) fn sidecar_port() -> u16 {
+    crate::backend_events::announced_port().unwrap_or_else(configured_port)
+}
+
+pub(crate) fn configured_port() -> u16 {
+    crate::cli::options().port.unwrap_or(SIDECAR_PORT)
+}
+
+/// Where backend calls go: the remote backend when one is set.
+pub(crate) fn base_url() -> String {
+    crate::remote_backend::url().unwrap_or_else(|| format!("http://localhost:{}", sidecar_port()))
+}
+
+#[derive(Debug)]
+pub(crate) enum ApiError {
+    /// Connection refused, reset or timed out — the backend is down or busy.
+    Unreachable(String),
+    /// Non-success status with (the start of) the response body.
+    Status(u16, String),
+    /// The backend reported a failure in the stream, or sent garbage.
+    Failed(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unreachable(e) => write!(f, "Backend not reachable: {}", e),
+            ApiError::Status(code, body) => write!(f, "HTTP {} - {}", code, body),
+            ApiError::Failed(e) => f.write_str(e),
+        }
+    }
+}
+
+fn client(timeout: Duration) -> Result<reqwest::Client, ApiError> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .no_proxy()
+        .build()
+        .map_err(|e| ApiError::Failed(format!("HTTP client build failed: {}", e)))
+}
+
+/// Attach the app token and a request id (see `traces`), send, and turn
+/// non-success statuses into errors.
+async fn send(req: reqwest::RequestBuilder) -> Result<reqwest::Response, ApiError> {
+    crate::startup::backend_wanted();
+    let token = crate::read_app_token().await.unwrap_or_default();
+    let (client, request) = req.header("X-LocalBook-Token", &token).build_split();
+    let mut request = request.map_err(|e| ApiError::Failed(e.to_string()))?;
+    let trace = crate::traces::start("shell", request.method().as_str(), request.url().path());
+    if let Ok(id) = reqwest::header::HeaderValue::from_str(trace.id()) {
+        request.headers_mut().insert(crate::traces::HEADER, id);
+    }
+    let resp = match client.execute(request).await {
+        Ok(resp) => {
+            trace.finish(Some(resp.status().as_u16()), None);
+            resp
+        }
+        Err(e) => {
+            trace.finish(None, Some(e.to_string()));
+            return Err(ApiError::Unreachable(e.to_string()));
+        }
+    };
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiError::Status(status, body.chars().take(300).collect()));
+    }
+    Ok(resp)
+}
+
+/// GET and parse the JSON response.
+pub(crate) async fn get_json(path: &str, timeout: Duration) -> Result<serde_json::Value, ApiError> {
+    let resp = send(client(timeout)?.get(format!("{}{}", base_url(), path))).await?;
+    resp.json()
+        .await
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// GET a plain-text response.
+pub(crate) async fn get_text(path: &str, timeout: Duration) -> Result<String, ApiError> {
+    let resp = send(client(timeout)?.get(format!("{}{}", base_url(), path))).await?;
+    resp.text()
+        .await
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// GET a binary response, such as a file download.
+pub(crate) async fn get_bytes(path: &str, timeout: Duration) -> Result<Vec<u8>, ApiError> {
+    let resp = send(client(timeout)?.get(format!("{}{}", base_url(), path))).await?;
+    resp.bytes()
+        .await
+        .map(|b| b.to_vec())
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// DELETE, ignoring the response body.
+pub(crate) async fn delete(path: &str, timeout: Duration) -> Result<(), ApiError> {
+    send(client(timeout)?.delete(format!("{}{}", base_url(), path))).await?;
+    Ok(())
+}
+
+/// POST a JSON body and parse the JSON response.
+pub(crate) async fn post_json(
+    path: &str,
+    body: &serde_json::Value,
+    timeout: Duration,
+) -> Result<serde_json::Value, ApiError> {
+    let resp = send(
+        client(timeout)?
+            .post(format!("{}{}", base_url(), path))
+            .json(body),
+    )
+    .await?;
+    resp.json()
+        .await
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// POST a JSON body and read a plain-text response.
+pub(crate) async fn post_text(
+    path: &str,
+    body: &serde_json::Value,
+    timeout: Duration,
+) -> Result<String, ApiError> {
+    let resp = send(
+        client(timeout)?
+            .post(format!("{}{}", base_url(), path))
+            .json(body),
+    )
+    .await?;
+    resp.text()
+        .await
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// PUT a JSON body and parse the JSON response.
+pub(crate) async fn put_json(
+    path: &str,
+    body: &serde_json::Value,
+    timeout: Duration,
+) -> Result<serde_json::Value, ApiError> {
+    let resp = send(
+        client(timeout)?
+            .put(format!("{}{}", base_url(), path))
+            .json(body),
+    )
+    .await?;
+    resp.json()
+        .await
+        .map_err(|e| ApiError::Failed(format!("Unexpected {} response: {}", path, e)))
+}
+
+/// POST a JSON body to an endpoint that answers with a progress stream.
+pub(crate) async fn post_json_stream(
+    path: &str,
+    body: &serde_json::Value,
+    timeout: Duration,
+    on_event: impl FnMut(&serde_json::Value),
+) -> Result<serde_json::Value, ApiError> {
+    let resp = send(
+        client(timeout)?
+            .post(format!("{}{}", base_url(), path))
+            .json(body),
+    )
+    .await?;
+    read_progress_stream(resp, on_event).await
+}
+
+/// Read progress frames until the backend closes the stream. Every frame but
+/// the terminal ones goes to `on_event`; returns the `complete` frame's
+/// `details` (or `{}` if the stream ended without one).
+pub(crate) async fn read_progress_stream(
+    mut resp: reqwest::Response,
+    mut on_event: impl FnMut(&serde_json::Value),
+) -> Result<serde_json::Value, ApiError> {
+    // The backend emits frames separated by "\n\n", each with a "data: {json}" line.
+    let mut buffer = String::new();
+    let mut final_result: Option<serde_json::Value> = None;
+
+    loop {
+        match resp.chunk().await {
+            Ok(Some(bytes)) => {
+                if let Ok(s) = std::str::from_utf8(&bytes) {
+                    buffer.push_str(s);
+                } else {
+                    // Non-UTF8 bytes shouldn't appear in SSE, skip safely
+                    continue;
+                }
+
+                // Drain any complete SSE frames in the buffer
+                while let Some(idx) = buffer.find("\n\n") {
+                    let raw_event = buffer[..idx].to_string();
+                    buffer.replace_range(..idx + 2, "");
+
+                    for line in raw_event.lines() {
+                        // Skip comments (": ping") and non-data lines
+                        if line.starts_with(':') {
+                            continue;
+                        }
+                        let payload = match line.strip_prefix("data:") {
+                            Some(p) => p.trim(),
+                            None => continue,
+                        };
+                        if payload.is_empty() || payload == "{}" {
+                            continue;
+                        }
+                        let evt: serde_json::Value = match serde_json::from_str(payload) {
+                            Ok(v) => v,
+                            Err(_) => continue, // skip malformed frames
+                        };
+
+                        match evt.get("stage").and_then(|s| s.as_str()).unwrap_or("") {
+                            // Don't return yet — continue draining stream until backend closes
+                            "complete" => final_result = evt.get("details").cloned(),
+                            "error" => {
+                                let msg = evt
+                                    .get("message")
+                                    .and_then(|s| s.as_str())
+                                    .unwrap_or("Backend operation failed");
+                                return Err(ApiError::Failed(msg.to_string()));
+                            }
+                            _ => on_event(&evt),
+                        }
+                    }
+                }
+            }
+            Ok(None) => break, // End of stream
+            Err(e) => {
+                // If we already received the terminal 'complete' event,
+                // a subsequent stream-read error is just the connection
+                // closing — the operation succeeded. Returning Err here
+                // would surface as a red error toast on a successful
+                // upload (the symptom the heavy user hit 2026-05-27).
+                if final_result.is_some() {
+                    tracing::info!(
+                        "[Backend] stream-close hiccup after complete event (ignoring): {}",
+                        e
+                    );
+                    break;
+                }
+                return Err(ApiError::Unreachable(format!("Stream read error: {}", e)));
+            }
+        }
+    }
+
+    Ok(final_result.unwrap_or(serde_json::json!({})))
+}
+
+/// Stream a file from disk into a notebook (`/sources/upload/stream`). The
+/// file is multipart-encoded without buffering, so large files never sit in
+/// memory.
+pub(crate) async fn upload_file(
+    path: &Path,
+    notebook_id: &str,
+    on_event: impl FnMut(&serde_json::Value),
+) -> Result<serde_json::Value, ApiError> {
+    let filename = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| ApiError::Failed("Invalid file path (no filename)".to_string()))?;
+
+    // The backend keeps a copy and may re-open it with helper tools later;
+    // don't let a browser-download quarantine flag travel with it.
+    crate::quarantine::strip(path);
+
+    // Open the file as a streaming body — bytes never sit in memory at once
+    let file = tokio::fs::File::open(path).await.map_err(|e| {
+        ApiError::Failed(format!("Failed to open file '{}': {}", path.display(), e))
+    })?;
+    let body = reqwest::Body::wrap_stream(ReaderStream::new(file));
+
+    let part = multipart::Part::stream(body).file_name(filename);
+    let form = multipart::Form::new()
+        .text("notebook_id", notebook_id.to_string())
+        .part("file", part);
+
+    // 1 hour for very large files
+    let req = client(Duration::from_secs(3600))?
+        .post(format!("{}/sources/upload/stream", base_url()))
+        .multipart(form);
+    read_progress_stream(send(req).await?, on_event).await
+}
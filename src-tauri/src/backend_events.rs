@@ -0,0 +1,211 @@
+//! What the sidecar's own output says about it.
+//!
+//! Once its socket accepts connections the backend prints
+//! `READY {"port": 8000}` on stdout (`utils/ready_signal.py`). `logging`
+//! hands each line of output here, and `wait_for_backend_ready` returns as
+//! soon as the line comes instead of on its next once-a-second `/health`
+//! poll. The port it names is the one backend calls go to from then on (see
+//! `backend_api::sidecar_port`). A backend this app didn't start never sends
+//! the line and is polled as before.
+//!
+//! Both output streams closing means the sidecar has exited. Unless it was
+//! being stopped (`stopping`, from `kill_existing_backend`), that's a crash:
+//! the backend stops counting as ready at once, `backend://crashed` goes out
+//! with the exit code and the last `KEEP_STDERR` lines of stderr, and the
+//! watchdog is woken to restart it rather than noticing on its next check.
+//! Every spawn starts over, so a late line or close from the previous
+//! process doesn't count.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+const READY_PREFIX: &str = "READY ";
+const KEEP_STDERR: usize = 20;
+/// stdout and stderr.
+const STREAMS: u8 = 2;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static STATE: Mutex<State> = Mutex::new(State {
+    spawn: 0,
+    pid: 0,
+    port: None,
+    stopping: false,
+    closed: 0,
+    crashed: false,
+    stderr: VecDeque::new(),
+});
+static ANNOUNCED: Notify = Notify::const_new();
+static CRASHED: Notify = Notify::const_new();
+
+/// The current sidecar's.
+struct State {
+    /// Counts spawns; output is tagged with the one it came from.
+    spawn: u64,
+    pid: u32,
+    port: Option<u16>,
+    stopping: bool,
+    /// Output streams that have ended.
+    closed: u8,
+    crashed: bool,
+    stderr: VecDeque<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct Crash {
+    pid: u32,
+    /// None when it was killed by a signal, or its status couldn't be read.
+    exit_code: Option<i32>,
+    /// The signal that ended it, on Unix.
+    signal: Option<i32>,
+    /// Redacted, oldest first.
+    stderr: Vec<String>,
+}
+
+fn state() -> std::sync::MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+/// A sidecar was just spawned as `pid`. Returns the tag for its output.
+pub(crate) fn spawned(pid: u32) -> u64 {
+    let mut state = state();
+    state.spawn += 1;
+    state.pid = pid;
+    state.port = None;
+    state.stopping = false;
+    state.closed = 0;
+    state.crashed = false;
+    state.stderr.clear();
+    state.spawn
+}
+
+/// The sidecar is being stopped on purpose; its exit isn't a crash.
+pub(crate) fn stopping() {
+    state().stopping = true;
+}
+
+/// It's gone; what it announced no longer holds.
+pub(crate) fn stopped() {
+    state().port = None;
+}
+
+/// The running sidecar's process id.
+pub(crate) fn sidecar_pid() -> Option<u32> {
+    let state = state();
+    (state.spawn > 0 && state.closed < STREAMS).then_some(state.pid)
+}
+
+/// The port the running sidecar said it's listening on.
+pub(crate) fn announced_port() -> Option<u16> {
+    state().port
+}
+
+/// One line of output from spawn `spawn`.
+pub(crate) fn line(spawn: u64, line: &str, stderr: bool) {
+    let mut state = state();
+    if state.spawn != spawn {
+        return;
+    }
+    if stderr {
+        if state.stderr.len() == KEEP_STDERR {
+            state.stderr.pop_front();
+        }
+        state.stderr.push_back(crate::logging::redact::line(line));
+        return;
+    }
+    let Some(payload) = line.strip_prefix(READY_PREFIX) else {
+        return;
+    };
+    let port = serde_json::from_str::<serde_json::Value>(payload)
+        .ok()
+        .and_then(|v| v["port"].as_u64())
+        .and_then(|p| u16::try_from(p).ok());
+    let Some(port) = port else {
+        tracing::warn!("[Backend] Unreadable READY line: {}", payload);
+        return;
+    };
+    let expected = crate::backend_api::configured_port();
+    if port != expected {
+        tracing::warn!(
+            "[Backend] Listening on port {} instead of {}; using it",
+            port,
+            expected
+        );
+    }
+    tracing::info!("[Backend] Ready on port {}", port);
+    state.port = Some(port);
+    ANNOUNCED.notify_one();
+}
+
+/// One of spawn `spawn`'s output streams ended. Called on the thread that
+/// was reading it, which may wait here a moment for the exit status.
+pub(crate) fn stream_closed(spawn: u64) {
+    let (pid, stderr) = {
+        let mut state = state();
+        if state.spawn != spawn {
+            return;
+        }
+        state.closed += 1;
+        if state.closed < STREAMS || state.stopping {
+            return;
+        }
+        state.crashed = true;
+        state.port = None;
+        (state.pid, Vec::from(state.stderr.clone()))
+    };
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let status = crate::sidecar_exit_status(app, pid);
+    #[cfg(unix)]
+    let signal = status.and_then(|s| std::os::unix::process::ExitStatusExt::signal(&s));
+    #[cfg(not(unix))]
+    let signal = None;
+    let crash = Crash {
+        pid,
+        exit_code: status.and_then(|s| s.code()),
+        signal,
+        stderr,
+    };
+    let exit = status.map_or_else(|| "unknown status".to_string(), |s| s.to_string());
+    tracing::error!("[Backend] Process {} exited ({})", pid, exit);
+    crate::mark_backend_crashed(app, &exit);
+    let _ = app.emit("backend://crashed", crash);
+    CRASHED.notify_one();
+}
+
+/// Wait until the sidecar announces itself, or `timeout` passes. True if it
+/// has.
+pub(crate) async fn wait_ready(timeout: Duration) -> bool {
+    if announced_port().is_some() {
+        return true;
+    }
+    let _ = tokio::time::timeout(timeout, ANNOUNCED.notified()).await;
+    announced_port().is_some()
+}
+
+/// Wait `timeout`, or less if the current sidecar crashes. True if it has;
+/// each crash is only reported once. For the watchdog.
+pub(crate) async fn wait_crashed(timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::from_std(Instant::now() + timeout);
+    loop {
+        if std::mem::take(&mut state().crashed) {
+            return true;
+        }
+        // A wakeup left from an earlier sidecar just goes round again.
+        if tokio::time::timeout_at(deadline, CRASHED.notified())
+            .await
+            .is_err()
+        {
+            return std::mem::take(&mut state().crashed);
+        }
+    }
+}
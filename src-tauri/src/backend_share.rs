@@ -0,0 +1,121 @@
+//! Lending this machine's backend to paired devices that can't run one — an
+//! iPad or phone (see `remote_backend`).
+//!
+//! The backend only listens on loopback. When a paired device asks over the
+//! LAN sync channel, a forwarder starts on a free port and passes connections
+//! through to the backend, accepting them only from the addresses of devices
+//! that asked and only while they stay paired. The device gets the port and
+//! the app token in the encrypted reply, and the backend's token check still
+//! applies to every request. The forwarded traffic is plain HTTP, so like
+//! `share` this is meant for a trusted network.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+
+struct Forwarder {
+    port: u16,
+    /// Addresses let through, with the device each belongs to.
+    allowed: HashMap<IpAddr, String>,
+    server: tauri::async_runtime::JoinHandle<()>,
+}
+
+static FORWARDER: Mutex<Option<Forwarder>> = Mutex::new(None);
+
+fn forwarder() -> std::sync::MutexGuard<'static, Option<Forwarder>> {
+    FORWARDER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn allowed(ip: IpAddr) -> bool {
+    forwarder()
+        .as_ref()
+        .is_some_and(|f| f.allowed.contains_key(&ip.to_canonical()))
+}
+
+async fn pass_through(mut inbound: TcpStream) {
+    let backend_addr = (Ipv4Addr::LOCALHOST, crate::backend_api::sidecar_port());
+    let Ok(mut backend) = TcpStream::connect(backend_addr).await else {
+        return;
+    };
+    let _ = tokio::io::copy_bidirectional(&mut inbound, &mut backend).await;
+}
+
+async fn serve(listener: TcpListener) {
+    loop {
+        let Ok((inbound, addr)) = listener.accept().await else {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        };
+        if !allowed(addr.ip()) {
+            tracing::warn!("[BackendShare] Refused a connection from {}", addr.ip());
+            continue;
+        }
+        tauri::async_runtime::spawn(pass_through(inbound));
+    }
+}
+
+/// Let `device` at `ip` reach the backend. Returns the forwarder's port.
+pub(crate) async fn grant(device: &str, ip: IpAddr) -> Result<u16, String> {
+    if crate::privacy::local_only() {
+        return Err("Local-only mode is on".to_string());
+    }
+    if crate::remote_backend::url().is_some() || crate::remote_backend::required() {
+        return Err("This device doesn't run its own backend".to_string());
+    }
+    if forwarder().is_none() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .await
+            .map_err(|e| format!("Failed to open the backend to the network: {}", e))?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        let mut current = forwarder();
+        // Another request may have started one meanwhile; keep that.
+        if current.is_none() {
+            *current = Some(Forwarder {
+                port,
+                allowed: HashMap::new(),
+                server: tauri::async_runtime::spawn(serve(listener)),
+            });
+            tracing::info!("[BackendShare] Forwarding the backend on port {}", port);
+        }
+    }
+    let port = {
+        let mut current = forwarder();
+        let f = current
+            .as_mut()
+            .ok_or("The backend forwarder stopped; try again")?;
+        f.allowed.insert(ip.to_canonical(), device.to_string());
+        f.port
+    };
+    crate::audit::record("backend_shared", format!("{} at {}", device, ip));
+    Ok(port)
+}
+
+/// Stop letting `device` through; the forwarder stops with the last one.
+pub(crate) fn revoke(device: &str) {
+    let mut current = forwarder();
+    let Some(f) = current.as_mut() else {
+        return;
+    };
+    f.allowed.retain(|_, d| d != device);
+    if f.allowed.is_empty() {
+        stop(&mut current);
+    }
+}
+
+fn stop(current: &mut Option<Forwarder>) {
+    if let Some(f) = current.take() {
+        f.server.abort();
+        tracing::info!("[BackendShare] Stopped forwarding the backend");
+        crate::audit::record("backend_share_stopped", f.port.to_string());
+    }
+}
+
+/// Close the backend to every device that was let through.
+#[tauri::command]
+pub(crate) async fn stop_backend_sharing() -> Result<(), String> {
+    stop(&mut forwarder());
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Backups of the backend's data — notebooks database, sources, vector store,
+//! generated audio — into a timestamped folder.
+//!
+//! SQLite databases are snapshotted with `VACUUM INTO`, which is consistent
+//! while the backend keeps writing; everything else is copied file by file.
+//! Downloaded models are left out (they can be fetched again and dwarf the
+//! rest), as are the app token, the library lock and earlier backups. Runs as a `backup` job;
+//! scheduled backups into the default folder keep the newest `KEEP_SCHEDULED`.
+
+use std::path::{Path, PathBuf};
+
+pub(crate) const KEEP_SCHEDULED: usize = 10;
+const SKIP: &[&str] = &[
+    ".app_token",
+    "models",
+    "backups",
+    "localbook.db.backup",
+    crate::folder_sync::LOCK_FILE,
+];
+
+fn skipped(name: &str) -> bool {
+    SKIP.contains(&name) || name.ends_with("-wal") || name.ends_with("-shm")
+}
+
+/// Default destination: `<app data>/backups/`.
+pub(crate) fn default_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::data_dir(app)?.join("backups"))
+}
+
+fn collect(root: &Path, rel: &Path, out: &mut Vec<(PathBuf, u64)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(root.join(rel))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if rel.as_os_str().is_empty() && skipped(&name.to_string_lossy()) {
+            continue;
+        }
+        let path = rel.join(&name);
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            collect(root, &path, out)?;
+        } else if meta.is_file() {
+            out.push((path, meta.len()));
+        }
+    }
+    Ok(())
+}
+
+fn snapshot_db(from: &Path, to: &Path) -> Result<(), String> {
+    let conn =
+        rusqlite::Connection::open_with_flags(from, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open {}: {}", from.display(), e))?;
+    conn.execute("VACUUM INTO ?1", [to.to_string_lossy()])
+        .map_err(|e| format!("Failed to snapshot {}: {}", from.display(), e))?;
+    Ok(())
+}
+
+/// Blocking: back up the backend data dir into a new folder under `dest_dir`.
+/// `on_progress` gets (bytes done, bytes total); an error from it stops the
+/// backup. Returns the backup folder.
+pub(crate) fn create(
+    dest_dir: &Path,
+    on_progress: impl FnMut(u64, u64) -> Result<(), String>,
+) -> Result<PathBuf, String> {
+    let source = crate::backend_data_dir();
+    if !source.is_dir() {
+        return Err(format!("No backend data at {}", source.display()));
+    }
+    let dest = dest_dir.join(format!("localbook-{}", crate::models::now_secs()));
+    if dest.starts_with(&source) && !dest.starts_with(source.join("backups")) {
+        return Err("Backups can't be stored inside the data they back up".to_string());
+    }
+    copy(&source, &dest, on_progress)?;
+    crate::audit::record("backup_created", dest.display().to_string());
+    Ok(dest)
+}
+
+/// Blocking: copy the backend data in `source` into `dest`, with the same
+/// snapshotting and exclusions as a backup. A failed copy is removed.
+pub(crate) fn copy(
+    source: &Path,
+    dest: &Path,
+    mut on_progress: impl FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    std::fs::create_dir_all(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+
+    let mut files = Vec::new();
+    collect(source, Path::new(""), &mut files)
+        .map_err(|e| format!("Failed to list {}: {}", source.display(), e))?;
+    let total: u64 = files.iter().map(|(_, len)| len).sum();
+    let mut done = 0;
+    tracing::info!(
+        "[Backup] {} files ({} MB) -> {:?}",
+        files.len(),
+        total / 1024 / 1024,
+        dest
+    );
+
+    let result = files.iter().try_for_each(|(rel, len)| {
+        let (from, to) = (source.join(rel), dest.join(rel));
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        if rel.extension().is_some_and(|e| e == "db") {
+            snapshot_db(&from, &to)?;
+        } else {
+            std::fs::copy(&from, &to)
+                .map_err(|e| format!("Failed to copy {}: {}", from.display(), e))?;
+        }
+        done += len;
+        on_progress(done, total)
+    });
+    if let Err(e) = result {
+        // Don't leave a half backup that looks complete.
+        let _ = std::fs::remove_dir_all(dest);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Delete all but the newest `keep` backups in `dir`.
+pub(crate) fn prune(dir: &Path, keep: usize) -> Result<(), String> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_dir()
+                && p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("localbook-"))
+        })
+        .collect();
+    // Names end in the creation time; newest first.
+    backups.sort_by_key(|p| std::cmp::Reverse(p.file_name().map(|n| n.to_os_string())));
+    for old in backups.iter().skip(keep) {
+        tracing::info!("[Backup] Removing old backup {:?}", old);
+        std::fs::remove_dir_all(old)
+            .map_err(|e| format!("Failed to remove {}: {}", old.display(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,220 @@
+//! Extra trusted root certificates, for networks that intercept TLS.
+//!
+//! Corporate proxies that re-sign HTTPS traffic with their own root CA make
+//! every download fail certificate checks unless that root is trusted.
+//! `add_trusted_cert` takes a PEM or DER file (a PEM file may hold several
+//! certificates), copies each certificate into `<app data>/certs/` by its
+//! SHA-256 fingerprint, and from then on every internet client built through
+//! `proxy::client()` trusts them alongside the system roots.
+//!
+//! The backend gets the same certificates as a PEM bundle
+//! (`LOCALBOOK_EXTRA_CA_CERTS`), which it appends to its own CA bundle at
+//! startup — so this applies to URL import there after the next restart.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+const CERTS_DIR: &str = "certs";
+/// All trusted certificates as PEM, for the backend.
+const BUNDLE_FILE: &str = "bundle.pem";
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// Settings entry for a trusted certificate.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TrustedCert {
+    /// SHA-256 of the DER encoding, hex.
+    pub fingerprint: String,
+    /// Name of the file it was added from.
+    pub source: String,
+    /// Unix seconds.
+    pub added_at: u64,
+}
+
+static CERTS: RwLock<Vec<reqwest::Certificate>> = RwLock::new(Vec::new());
+static BUNDLE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+fn certs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::data_dir(app)?.join(CERTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn der_path(dir: &Path, fingerprint: &str) -> PathBuf {
+    dir.join(format!("{}.der", fingerprint))
+}
+
+/// Every certificate in a PEM or DER file, as DER.
+fn parse(bytes: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let Some(text) = std::str::from_utf8(bytes)
+        .ok()
+        .filter(|t| t.contains(PEM_BEGIN))
+    else {
+        return Ok(vec![bytes.to_vec()]);
+    };
+    text.split(PEM_BEGIN)
+        .skip(1)
+        .map(|block| {
+            let body: String = block
+                .split(PEM_END)
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(body)
+                .map_err(|e| format!("Malformed PEM certificate: {}", e))
+        })
+        .collect()
+}
+
+fn to_pem(der: &[u8]) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD.encode(der);
+    let lines: Vec<&str> = b64
+        .as_bytes()
+        .chunks(64)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect();
+    format!("{}\n{}\n{}\n", PEM_BEGIN, lines.join("\n"), PEM_END)
+}
+
+/// Reload the certificates from disk and rewrite the backend's bundle.
+fn reload(app: &AppHandle) -> Result<(), String> {
+    let dir = certs_dir(app)?;
+    let (mut certs, mut pem) = (Vec::new(), String::new());
+    for entry in crate::settings::get(app).trusted_certs {
+        let der = match std::fs::read(der_path(&dir, &entry.fingerprint)) {
+            Ok(der) => der,
+            Err(e) => {
+                tracing::warn!("[Certs] Missing {}: {}", entry.fingerprint, e);
+                continue;
+            }
+        };
+        match reqwest::Certificate::from_der(&der) {
+            Ok(cert) => {
+                certs.push(cert);
+                pem.push_str(&to_pem(&der));
+            }
+            Err(e) => tracing::warn!("[Certs] Unusable {}: {}", entry.fingerprint, e),
+        }
+    }
+    let bundle = dir.join(BUNDLE_FILE);
+    if pem.is_empty() {
+        let _ = std::fs::remove_file(&bundle);
+        *BUNDLE.write().unwrap_or_else(|e| e.into_inner()) = None;
+    } else {
+        std::fs::write(&bundle, pem)
+            .map_err(|e| format!("Failed to write {}: {}", bundle.display(), e))?;
+        *BUNDLE.write().unwrap_or_else(|e| e.into_inner()) = Some(bundle);
+    }
+    let count = certs.len();
+    *CERTS.write().unwrap_or_else(|e| e.into_inner()) = certs;
+    if count > 0 {
+        tracing::info!("[Certs] Trusting {} extra root certificate(s)", count);
+    }
+    Ok(())
+}
+
+/// Load the trusted certificates. Called in setup after settings load.
+pub(crate) fn start(app: &AppHandle) {
+    if let Err(e) = reload(app) {
+        tracing::warn!("[Certs] {}", e);
+    }
+}
+
+/// Add the trusted certificates to a client.
+pub(crate) fn apply(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    CERTS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .fold(builder, |b, cert| b.add_root_certificate(cert))
+}
+
+/// The PEM bundle for the backend, if any certificates are trusted.
+pub(crate) fn backend_env() -> Option<(&'static str, String)> {
+    BUNDLE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|p| ("LOCALBOOK_EXTRA_CA_CERTS", p.to_string_lossy().into_owned()))
+}
+
+#[tauri::command]
+pub(crate) async fn list_trusted_certs(app: AppHandle) -> Result<Vec<TrustedCert>, String> {
+    Ok(crate::settings::get(&app).trusted_certs)
+}
+
+/// Trust every certificate in a PEM or DER file. Returns the ones added.
+#[tauri::command]
+pub(crate) async fn add_trusted_cert(
+    app: AppHandle,
+    path: String,
+) -> Result<Vec<TrustedCert>, String> {
+    let path = crate::scope::check(&app, Path::new(&path))?;
+    let bytes =
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let ders = parse(&bytes)?;
+    let dir = certs_dir(&app)?;
+    let source = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let known = crate::settings::get(&app).trusted_certs;
+    let mut added = Vec::new();
+    for der in ders {
+        reqwest::Certificate::from_der(&der)
+            .map_err(|e| format!("{} is not a valid certificate: {}", source, e))?;
+        let fingerprint = format!("{:x}", Sha256::digest(&der));
+        if known
+            .iter()
+            .chain(&added)
+            .any(|c| c.fingerprint == fingerprint)
+        {
+            continue;
+        }
+        let dest = der_path(&dir, &fingerprint);
+        std::fs::write(&dest, &der)
+            .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        added.push(TrustedCert {
+            fingerprint,
+            source: source.clone(),
+            added_at: crate::models::now_secs(),
+        });
+    }
+    if added.is_empty() {
+        return Ok(added);
+    }
+    crate::settings::update(&app, |s| s.trusted_certs.extend(added.iter().cloned()))?;
+    for cert in &added {
+        crate::audit::record(
+            "cert_trusted",
+            format!("{} from {}", cert.fingerprint, cert.source),
+        );
+    }
+    reload(&app)?;
+    Ok(added)
+}
+
+#[tauri::command]
+pub(crate) async fn remove_trusted_cert(app: AppHandle, fingerprint: String) -> Result<(), String> {
+    let mut found = false;
+    crate::settings::update(&app, |s| {
+        let before = s.trusted_certs.len();
+        s.trusted_certs.retain(|c| c.fingerprint != fingerprint);
+        found = s.trusted_certs.len() != before;
+    })?;
+    if !found {
+        return Err(format!("No trusted certificate {}", fingerprint));
+    }
+    let _ = std::fs::remove_file(der_path(&certs_dir(&app)?, &fingerprint));
+    crate::audit::record("cert_removed", &fingerprint);
+    reload(&app)
+}
@@ -0,0 +1,805 @@
+//! The command line: startup options for any run, and the headless commands
+//! for scripts and cron jobs on a server.
+//!
+//! Startup options:
+//!
+//! - `--data-dir <dir>`: the library (the backend's data) for this run,
+//!   instead of the configured one.
+//! - `--profile <name>`: separate settings, shell data and default library,
+//!   side by side with the usual ones. Downloaded models, the Python
+//!   environment and backend updates are still shared.
+//! - `--port <port>`: the sidecar's port instead of 8000.
+//! - `--no-sidecar`: don't start (or stop) a backend; one is run separately,
+//!   as in development.
+//! - `--minimized`: start in the tray with the window hidden.
+//! - files: offered to the webview to import once it's up
+//!   (`take_launch_files`).
+//! - `localbook://notebook/<id>` links: open that notebook
+//!   (`take_launch_notebook`).
+//!
+//! Opening files or a link while LocalBook is running — a double-click, or
+//! the command again — starts a second process that hands its arguments to
+//! the running one and exits (`forward`). The running one brings the right
+//! window forward and imports or navigates; startup options given then are
+//! ignored.
+//!
+//! Commands:
+//!
+//! - `localbook import <paths...> --notebook <name>` imports files into the
+//!   notebook with that title (created when there's none). Folders are
+//!   imported recursively, skipping hidden files, and the paths given are
+//!   granted the way a file dialog would (see `scope`).
+//! - `localbook ask --notebook <name> [--json] "<question>"` asks the
+//!   notebook and prints the answer with its citations, or the backend's
+//!   whole response as JSON.
+//! - `localbook --serve[=<port>]` serves the app to browsers on the network
+//!   until stopped (see `serve`).
+//! - `localbook rpc` answers automation requests on stdin and stdout until
+//!   stdin closes (see `rpc`).
+//! - `localbook mcp` is a Model Context Protocol server on stdin and stdout,
+//!   for AI clients to search the library (see `mcp`).
+//!
+//! A command runs the normal app minus windows, tray and background
+//! services. When a backend already answers — LocalBook is open, or a remote
+//! one is set — the command uses it; otherwise setup boots one as usual, and
+//! it stops on exit. Imports run as jobs and report progress on stdout; the
+//! shell's own log lines go to stderr in this mode. The exit status is 1
+//! when anything failed, 130 when interrupted. On Linux the webview toolkit
+//! still needs a display (`xvfb-run`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tauri::plugin::TauriPlugin;
+use tauri::utils::config::{Csp, CspDirectiveSources};
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::mpsc;
+
+use crate::backend_api;
+use crate::jobs::{self, JobSpec};
+
+const USAGE: &str = "Usage:
+  localbook [options] [files...]
+  localbook [options] import <paths...> --notebook <name>
+  localbook [options] ask --notebook <name> [--json] <question>
+  localbook [options] --serve[=<port>]
+  localbook [options] rpc
+  localbook [options] mcp
+
+Options:
+  --data-dir <dir>   Use this library folder
+  --profile <name>   Use a separate profile
+  --port <port>      Run the backend on this port (default 8000)
+  --no-sidecar       Use a backend started separately
+  --minimized        Start hidden in the tray";
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+/// Answers from a large local model can take minutes.
+const ASK_TIMEOUT: Duration = Duration::from_secs(600);
+const BACKEND_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const LINK_SCHEME: &str = "localbook://";
+
+/// Startup options, for any run.
+#[derive(Default)]
+pub(crate) struct Options {
+    /// Absolute.
+    pub data_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub port: Option<u16>,
+    pub no_sidecar: bool,
+    pub minimized: bool,
+}
+
+enum Command {
+    Import {
+        /// Absolute.
+        paths: Vec<PathBuf>,
+        notebook: String,
+    },
+    Ask {
+        question: String,
+        notebook: String,
+        json: bool,
+    },
+    Serve {
+        port: u16,
+    },
+    Rpc,
+    Mcp,
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+static COMMAND: OnceLock<Command> = OnceLock::new();
+/// Files named at launch, until the webview takes them.
+static LAUNCH_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+/// The notebook a launch link named, until the main window takes it.
+static LAUNCH_NOTEBOOK: Mutex<Option<String>> = Mutex::new(None);
+/// Using a backend that was already running, which is left running.
+static ATTACHED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn options() -> &'static Options {
+    OPTIONS.get_or_init(Options::default)
+}
+
+/// Whether this run is a command-line one.
+pub(crate) fn active() -> bool {
+    COMMAND.get().is_some()
+}
+
+pub(crate) fn attached() -> bool {
+    ATTACHED.load(Ordering::Relaxed)
+}
+
+/// The value of `--name value` or `--name=value` when `arg` is that option.
+fn option_value<'a>(
+    arg: &'a str,
+    name: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<Option<&'a str>, String> {
+    if arg == name {
+        return args
+            .next()
+            .map(|v| Some(v.as_str()))
+            .ok_or_else(|| format!("{} needs a value", name));
+    }
+    Ok(arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')))
+}
+
+fn absolute(path: &str) -> Result<PathBuf, String> {
+    std::path::absolute(path).map_err(|e| format!("Invalid path {}: {}", path, e))
+}
+
+/// Take the startup options out of `args`, wherever they are, leaving the
+/// rest. Also returns the `--serve` port.
+fn parse_options(args: &[String]) -> Result<(Options, Option<u16>, Vec<String>), String> {
+    let mut options = Options::default();
+    let mut serve = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_str();
+        if arg == "--" {
+            rest.push(arg.to_string());
+            rest.extend(args.by_ref().cloned());
+        } else if let Some(dir) = option_value(arg, "--data-dir", &mut args)? {
+            options.data_dir = Some(absolute(dir)?);
+        } else if let Some(name) = option_value(arg, "--profile", &mut args)? {
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err("A profile name is letters, digits, '-' and '_'".to_string());
+            }
+            options.profile = Some(name.to_string());
+        } else if let Some(port) = option_value(arg, "--port", &mut args)? {
+            let port = port.parse().map_err(|_| format!("Invalid port {}", port))?;
+            options.port = Some(port);
+        } else if arg == "--no-sidecar" {
+            options.no_sidecar = true;
+        } else if arg == "--minimized" {
+            options.minimized = true;
+        } else if arg == "--serve" {
+            serve = Some(crate::serve::DEFAULT_PORT);
+        } else if let Some(port) = arg.strip_prefix("--serve=") {
+            serve = Some(port.parse().map_err(|_| format!("Invalid port {}", port))?);
+        } else {
+            rest.push(arg.to_string());
+        }
+    }
+    Ok((options, serve, rest))
+}
+
+/// The arguments after `import` or `ask`; None for `--help`.
+fn parse(command: &str, args: &[String]) -> Result<Option<Command>, String> {
+    let mut words = Vec::new();
+    let mut notebook = None;
+    let mut json = false;
+    let mut args = args.iter();
+    let mut options = true;
+    while let Some(arg) = args.next() {
+        if !options {
+            words.push(arg.clone());
+            continue;
+        }
+        if let Some(name) =
+            option_value(arg, "--notebook", &mut args)?.or(option_value(arg, "-n", &mut args)?)
+        {
+            notebook = Some(name.to_string());
+            continue;
+        }
+        match arg.as_str() {
+            "--" => options = false,
+            "-h" | "--help" => return Ok(None),
+            "--json" if command == "ask" => json = true,
+            a if a.starts_with('-') => return Err(format!("Unknown option {}", a)),
+            a => words.push(a.to_string()),
+        }
+    }
+    let notebook = notebook
+        .filter(|n| !n.trim().is_empty())
+        .ok_or("Name the notebook with --notebook")?;
+    if command == "ask" {
+        let question = words.join(" ");
+        if question.trim().is_empty() {
+            return Err("Give the question to ask".to_string());
+        }
+        return Ok(Some(Command::Ask {
+            question,
+            notebook,
+            json,
+        }));
+    }
+    if words.is_empty() {
+        return Err("Give at least one file or folder to import".to_string());
+    }
+    let paths = words
+        .iter()
+        .map(|w| absolute(w))
+        .collect::<Result<_, _>>()?;
+    Ok(Some(Command::Import { paths, notebook }))
+}
+
+/// What a normal run was asked to open: files, relative to `cwd`, and
+/// `localbook://` links.
+#[derive(Default)]
+struct Launch {
+    files: Vec<PathBuf>,
+    links: Vec<String>,
+}
+
+/// The files and links to open in a normal run; None for `--help`.
+fn parse_launch(args: &[String], cwd: &Path) -> Result<Option<Launch>, String> {
+    let mut launch = Launch::default();
+    let mut options = true;
+    for arg in args {
+        match arg.as_str() {
+            "--" if options => options = false,
+            "-h" | "--help" if options => return Ok(None),
+            // Added by macOS to apps started from the Finder on old versions.
+            a if options && a.starts_with("-psn_") => {}
+            a if options && a.starts_with('-') => return Err(format!("Unknown option {}", a)),
+            a if a.starts_with(LINK_SCHEME) => launch.links.push(a.to_string()),
+            a => launch.files.push(absolute(&cwd.join(a).to_string_lossy())?),
+        }
+    }
+    Ok(Some(launch))
+}
+
+/// The notebook id in a `localbook://notebook/<id>` link.
+fn link_notebook(link: &str) -> Option<String> {
+    let id = link
+        .strip_prefix(LINK_SCHEME)?
+        .strip_prefix("notebook/")?
+        .split(['/', '?', '#'])
+        .next()?;
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| id.to_string())
+}
+
+/// Queue a launch's files and notebook for the webview. Returns whether
+/// there were files, and the notebook.
+fn queue(launch: Launch) -> (bool, Option<String>) {
+    let notebook = launch.links.iter().fold(None, |found, link| {
+        let id = link_notebook(link);
+        if id.is_none() {
+            tracing::warn!("[Launch] Unknown link {}", link);
+        }
+        id.or(found)
+    });
+    if let (Some(id), Ok(mut launched)) = (&notebook, LAUNCH_NOTEBOOK.lock()) {
+        *launched = Some(id.clone());
+    }
+    let has_files = !launch.files.is_empty();
+    if let Ok(mut files) = LAUNCH_FILES.lock() {
+        files.extend(launch.files);
+    }
+    (has_files, notebook)
+}
+
+fn usage_error(e: &str) -> ! {
+    attach_console();
+    std::eprintln!("{}\n\n{}", e, USAGE);
+    std::process::exit(2)
+}
+
+fn help() -> ! {
+    attach_console();
+    std::println!("{}", USAGE);
+    std::process::exit(0)
+}
+
+/// Read the command line, before the app is built. True for a headless
+/// command; exits after `--help` or a malformed command line.
+pub(crate) fn init() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (options, serve, rest) = parse_options(&args).unwrap_or_else(|e| usage_error(&e));
+    let _ = OPTIONS.set(options);
+    let command = match rest.first().map(String::as_str) {
+        _ if serve.is_some() && !rest.is_empty() => usage_error("--serve takes no arguments"),
+        _ if serve.is_some() => serve.map(|port| Command::Serve { port }),
+        Some("rpc") if rest.len() == 1 => Some(Command::Rpc),
+        Some("mcp") if rest.len() == 1 => Some(Command::Mcp),
+        Some(c @ ("rpc" | "mcp")) => usage_error(&format!("{} takes no arguments", c)),
+        Some(c @ ("import" | "ask")) => {
+            match parse(c, &rest[1..]).unwrap_or_else(|e| usage_error(&e)) {
+                Some(command) => Some(command),
+                None => help(),
+            }
+        }
+        _ => {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let launch = parse_launch(&rest, &cwd)
+                .unwrap_or_else(|e| usage_error(&e))
+                .unwrap_or_else(|| help());
+            queue(launch);
+            None
+        }
+    };
+    let Some(command) = command else {
+        return false;
+    };
+    attach_console();
+    COMMAND.set(command).is_ok()
+}
+
+/// Whether the launch named files or a notebook the webview hasn't taken.
+pub(crate) fn has_launch_work() -> bool {
+    LAUNCH_FILES.lock().is_ok_and(|f| !f.is_empty())
+        || LAUNCH_NOTEBOOK.lock().is_ok_and(|n| n.is_some())
+}
+
+/// What the backend needs to know about the startup options.
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    let options = options();
+    let mut env = Vec::new();
+    if options.data_dir.is_some() || options.profile.is_some() {
+        env.push((
+            "LOCALBOOK_DATA_DIR",
+            crate::backend_data_dir().display().to_string(),
+        ));
+    }
+    if let Some(port) = options.port {
+        env.push(("API_PORT", port.to_string()));
+    }
+    env
+}
+
+/// Apply the startup options to the app config, before the app is built:
+/// the backend port in the CSP.
+pub(crate) fn configure<R: tauri::Runtime>(context: &mut tauri::Context<R>) {
+    let config = context.config_mut();
+    if let Some(port) = options().port {
+        let security = &mut config.app.security;
+        if let Some(csp) = security.csp.take() {
+            let mut directives: HashMap<String, CspDirectiveSources> = csp.into();
+            for sources in directives.values_mut() {
+                let list: Vec<String> = std::mem::take(sources).into();
+                *sources = CspDirectiveSources::List(
+                    list.into_iter()
+                        .map(|s| s.replace("localhost:8000", &format!("localhost:{}", port)))
+                        .collect(),
+                );
+            }
+            security.csp = Some(Csp::DirectiveMap(directives));
+        }
+    }
+}
+
+/// Tells the webview the backend port given with `--port` (see `api.ts`).
+pub(crate) fn plugin<R: tauri::Runtime>() -> TauriPlugin<R> {
+    let builder = tauri::plugin::Builder::new("cli");
+    match options().port {
+        Some(port) => builder
+            .js_init_script(format!("window.__LOCALBOOK_BACKEND_PORT__ = {};", port))
+            .build(),
+        None => builder.build(),
+    }
+}
+
+/// Files named on the command line, for the webview to import once it's up.
+/// Cleared once taken; the paths are granted the way a file dialog would.
+#[tauri::command]
+pub(crate) async fn take_launch_files(app: AppHandle) -> Result<Vec<String>, String> {
+    let files = LAUNCH_FILES
+        .lock()
+        .map(|mut f| std::mem::take(&mut *f))
+        .unwrap_or_default();
+    for path in &files {
+        crate::scope::grant(&app, path, "cli");
+    }
+    Ok(files.iter().map(|p| p.display().to_string()).collect())
+}
+
+/// The notebook a launch link named, for the main window to open. Cleared
+/// once taken.
+#[tauri::command]
+pub(crate) async fn take_launch_notebook() -> Result<Option<String>, String> {
+    Ok(LAUNCH_NOTEBOOK.lock().ok().and_then(|mut n| n.take()))
+}
+
+/// A second launch's arguments, handed over by the single-instance plugin.
+#[cfg(desktop)]
+pub(crate) fn forward(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    // argv[0] is the executable path — only the user-supplied args matter.
+    let args: Vec<String> = argv.into_iter().skip(1).collect();
+    let launch = parse_options(&args)
+        .and_then(|(_, _, rest)| parse_launch(&rest, Path::new(&cwd)))
+        .unwrap_or_else(|e| {
+            tracing::warn!("[Launch] Ignored a second launch: {}", e);
+            None
+        });
+    open(app, launch.unwrap_or_default());
+}
+
+/// Files and links opened from the Finder or another app: macOS sends
+/// these to the running app rather than as arguments.
+#[cfg(target_os = "macos")]
+pub(crate) fn open_urls(app: &AppHandle, urls: Vec<tauri::Url>) {
+    let mut launch = Launch::default();
+    for url in urls {
+        match url.to_file_path() {
+            Ok(path) if url.scheme() == "file" => launch.files.push(path),
+            _ => launch.links.push(url.to_string()),
+        }
+    }
+    open(app, launch);
+}
+
+/// Bring the right window forward for what was opened — the notebook's own
+/// window when it has one, otherwise the main one — and tell it there's
+/// something to take.
+#[cfg(desktop)]
+fn open(app: &AppHandle, launch: Launch) {
+    use tauri::Emitter;
+    let (has_files, notebook) = queue(launch);
+
+    let window = notebook.as_ref().and_then(|id| {
+        app.get_webview_window(&format!("{}{}", crate::windows::NOTEBOOK_WINDOW_PREFIX, id))
+    });
+    let label = match window {
+        Some(window) if !crate::lock::is_locked() => {
+            // Already showing that notebook; the link has nothing left to do.
+            if let Ok(mut launched) = LAUNCH_NOTEBOOK.lock() {
+                *launched = None;
+            }
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+            window.label().to_string()
+        }
+        _ => {
+            crate::tray::show_main(app);
+            if notebook.is_some() {
+                let _ = app.emit_to("main", "launch://notebook", ());
+            }
+            "main".to_string()
+        }
+    };
+    if has_files {
+        let _ = app.emit_to(label.as_str(), "launch://files", ());
+    }
+}
+
+/// Release builds on Windows are GUI apps with no console of their own;
+/// write to the one the command was run from.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Called from setup once settings are loaded. True when a backend already
+/// answers, so setup mustn't start one — that would kill LocalBook's.
+pub(crate) fn attach() -> bool {
+    let running =
+        tauri::async_runtime::block_on(async { crate::check_health().await.unwrap_or(false) });
+    if running {
+        tracing::info!("[Cli] Using the backend at {}", backend_api::base_url());
+        ATTACHED.store(true, Ordering::Relaxed);
+    }
+    running
+}
+
+/// Ctrl+C, or (on Unix) a service manager stopping us.
+async fn interrupted() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Serve until interrupted.
+async fn serve(app: &AppHandle, port: u16) -> Result<bool, String> {
+    crate::serve::start(app, port).await?;
+    wait_for_backend(app).await?;
+    std::println!("Ready");
+    std::future::pending().await
+}
+
+/// Run the command and exit with its status — going through the app's exit
+/// even when interrupted, so the backend stops too. Called at the end of
+/// setup.
+pub(crate) fn start(app: &AppHandle) {
+    let Some(command) = COMMAND.get() else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let run = async {
+            match command {
+                Command::Import { paths, notebook } => import(&app, paths, notebook).await,
+                Command::Ask {
+                    question,
+                    notebook,
+                    json,
+                } => ask(&app, question, notebook, *json).await,
+                Command::Serve { port } => serve(&app, *port).await,
+                Command::Rpc => {
+                    wait_for_backend(&app).await?;
+                    crate::rpc::serve_stdio(&app).await
+                }
+                // Answers the handshake while the backend starts; tool
+                // calls wait for it.
+                Command::Mcp => crate::mcp::serve_stdio(&app).await,
+            }
+        };
+        let code = tokio::select! {
+            outcome = run => match outcome {
+                Ok(true) => 0,
+                Ok(false) => 1,
+                Err(e) => {
+                    std::eprintln!("{}", e);
+                    1
+                }
+            },
+            _ = interrupted() => match command {
+                Command::Serve { .. } => 0,
+                _ => 130,
+            },
+        };
+        app.exit(code);
+    });
+}
+
+/// `path`, or the files under it.
+pub(crate) fn collect(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            !p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    entries.sort();
+    for entry in entries {
+        collect(&entry, files)?;
+    }
+    Ok(())
+}
+
+/// Wait for setup to bring the backend up, echoing its startup stages.
+pub(crate) async fn wait_for_backend(app: &AppHandle) -> Result<(), String> {
+    let mut shown = String::new();
+    while !attached() && !crate::backend_ready(app) {
+        if let Some(state) = app.try_state::<crate::BackendState>() {
+            let status = state.status.lock().map(|s| s.clone());
+            if let Ok(status) = status {
+                if status.stage == "error" {
+                    return Err(status.last_error.unwrap_or(status.message));
+                }
+                if status.message != shown {
+                    // stdout carries the protocol for `rpc` and `mcp`.
+                    if matches!(COMMAND.get(), Some(Command::Rpc | Command::Mcp)) {
+                        std::eprintln!("{}", status.message);
+                    } else {
+                        std::println!("{}", status.message);
+                    }
+                    shown = status.message;
+                }
+            }
+        }
+        tokio::time::sleep(BACKEND_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// The id of the notebook titled `name`.
+async fn find_notebook(name: &str) -> Result<Option<String>, String> {
+    let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(notebooks["notebooks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|n| n["title"].as_str() == Some(name))
+        .and_then(|n| n["id"].as_str())
+        .map(String::from))
+}
+
+/// The id of the notebook titled `name`, creating it if there's none.
+async fn notebook_id(name: &str) -> Result<String, String> {
+    if let Some(id) = find_notebook(name).await? {
+        return Ok(id);
+    }
+    let created = backend_api::post_json("/notebooks/", &json!({ "title": name }), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    std::println!("Created notebook \"{}\"", name);
+    created["id"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| "Backend didn't return the new notebook's id".to_string())
+}
+
+/// A queued file, for its progress lines.
+struct Pending {
+    label: String,
+    /// The last percentage (in tens) and message shown.
+    shown: (Option<u32>, Option<String>),
+}
+
+/// A job's outcome line; true if it completed.
+fn report(label: &str, job: &Value) -> bool {
+    if job["state"].as_str() == Some("completed") {
+        std::println!("{} done", label);
+        return true;
+    }
+    let error = job["error"]["message"].as_str().unwrap_or("cancelled");
+    std::println!("{} failed: {}", label, error);
+    false
+}
+
+/// Import everything; Ok(false) if some files failed.
+async fn import(app: &AppHandle, paths: &[PathBuf], notebook: &str) -> Result<bool, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        crate::scope::grant(app, path, "cli");
+        collect(path, &mut files)?;
+    }
+    if files.is_empty() {
+        return Err("No files to import".to_string());
+    }
+    wait_for_backend(app).await?;
+    let notebook_id = notebook_id(notebook).await?;
+
+    // Listen before queueing so no update is missed.
+    let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+    for event in ["jobs://progress", "jobs://finished"] {
+        let tx = tx.clone();
+        app.listen(event, move |e| {
+            if let Ok(payload) = serde_json::from_str(e.payload()) {
+                let _ = tx.send(payload);
+            }
+        });
+    }
+
+    let total = files.len();
+    let mut pending = HashMap::new();
+    let mut imported = 0;
+    for (i, file) in files.iter().enumerate() {
+        let label = format!("[{}/{}] {}", i + 1, total, file.display());
+        let spec = JobSpec::Import {
+            notebook_id: notebook_id.clone(),
+            path: file.to_string_lossy().to_string(),
+        };
+        let queued = jobs::enqueue(app, spec, None)
+            .and_then(|job| serde_json::to_value(job).map_err(|e| e.to_string()));
+        match queued {
+            Ok(job) => match job["id"].as_str() {
+                Some(id) => {
+                    pending.insert(
+                        id.to_string(),
+                        Pending {
+                            label,
+                            shown: (None, None),
+                        },
+                    );
+                }
+                None => std::println!("{} failed: no job id", label),
+            },
+            Err(e) => std::println!("{} failed: {}", label, e),
+        }
+    }
+
+    while !pending.is_empty() {
+        let Some(update) = rx.recv().await else {
+            break;
+        };
+        let Some(id) = update["id"].as_str() else {
+            continue;
+        };
+        if update["state"].is_string() {
+            if let Some(p) = pending.remove(id) {
+                imported += usize::from(report(&p.label, &update));
+            }
+            continue;
+        }
+        let Some(p) = pending.get_mut(id) else {
+            continue;
+        };
+        let percent = update["progress"].as_f64().map(|f| (f * 100.0) as u32);
+        let message = update["message"].as_str().map(String::from);
+        let shown = (percent.map(|p| p / 10), message.clone());
+        if shown != p.shown {
+            p.shown = shown;
+            let percent = percent.map_or_else(String::new, |p| format!(" {}%", p));
+            let message = message.map_or_else(String::new, |m| format!(" {}", m));
+            std::println!("{}{}{}", p.label, percent, message);
+        }
+    }
+
+    std::println!(
+        "Imported {} of {} files into \"{}\"",
+        imported,
+        total,
+        notebook
+    );
+    Ok(imported == total)
+}
+
+/// Ask the notebook and print the answer.
+async fn ask(app: &AppHandle, question: &str, notebook: &str, json: bool) -> Result<bool, String> {
+    wait_for_backend(app).await?;
+    let notebook_id = find_notebook(notebook)
+        .await?
+        .ok_or_else(|| format!("No notebook titled \"{}\"", notebook))?;
+    let response = backend_api::post_json(
+        "/chat/query",
+        &json!({ "notebook_id": notebook_id, "question": question }),
+        ASK_TIMEOUT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if json {
+        let pretty = serde_json::to_string_pretty(&response).map_err(|e| e.to_string())?;
+        std::println!("{}", pretty);
+        return Ok(true);
+    }
+    std::println!("{}", response["answer"].as_str().unwrap_or_default().trim());
+    let citations = response["citations"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    if !citations.is_empty() {
+        std::println!("\nSources:");
+    }
+    for c in &citations {
+        let page = c["page"]
+            .as_u64()
+            .map_or_else(String::new, |p| format!(", p. {}", p));
+        std::println!(
+            "[{}] {}{}",
+            c["number"].as_u64().unwrap_or_default(),
+            c["filename"].as_str().unwrap_or("?"),
+            page
+        );
+    }
+    Ok(true)
+}
@@ -0,0 +1,463 @@
+//! Merging synced notebooks, and the conflicts left when that can't be done.
+//!
+//! `sync` merges a peer's version of a notebook with the local one against
+//! the version both last had: a source changed on one side takes that side's
+//! change, and a note changed on both is merged line by line when the edits
+//! touch different lines. What's left (the same lines edited differently, or
+//! a note edited on one side and deleted on the other) is a conflict: the
+//! local notebook stays as it is, and the peer's version and the merge base
+//! are kept under `<app data>/sync/conflicts/`.
+//!
+//! `list_conflicts` shows each conflict with the notes in question on all
+//! three sides; `resolve_conflict` settles it by keeping both versions as
+//! separate notebooks, taking whichever side changed last, or with a choice
+//! per note.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::sync::{self, Conflict, Offer};
+use crate::vault::{SealedNotebook, SealedSource};
+
+/// Largest line-by-line comparison tried, in lines × lines; bigger edits to
+/// the same note count as a conflict.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A source changed on both sides in ways that don't merge. Contents are
+/// None where the source doesn't exist (deleted, or never there).
+#[derive(Clone, Serialize)]
+pub(crate) struct NoteConflict {
+    /// Identifies the source in `resolve_conflict`'s choices.
+    pub key: String,
+    pub title: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+pub(crate) enum Merge {
+    Clean(SealedNotebook),
+    Conflicted(Vec<NoteConflict>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Resolution {
+    /// Keep the local notebook and add the peer's version as another one.
+    KeepBoth,
+    /// Merge, taking the side changed most recently for conflicting notes.
+    PreferLatest,
+    /// Merge, with the content for each conflicting note given in `choices`.
+    Manual,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConflictInfo {
+    /// `<peer>/<notebook>`, for `resolve_conflict`.
+    id: String,
+    peer: String,
+    peer_name: Option<String>,
+    local_id: String,
+    title: String,
+    /// Unix seconds.
+    detected_at: u64,
+    remote_updated_at: Option<String>,
+    notes: Vec<NoteConflict>,
+}
+
+/// Index pairs of a longest common subsequence of lines, or None when the
+/// texts are too different to compare cheaply.
+fn common_lines(a: &[&str], b: &[&str]) -> Option<Vec<(usize, usize)>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (ma, mb) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if ma.len().saturating_mul(mb.len()) > MAX_DIFF_CELLS {
+        return None;
+    }
+    let width = mb.len() + 1;
+    let mut lengths = vec![0u32; (ma.len() + 1) * width];
+    for i in (0..ma.len()).rev() {
+        for j in (0..mb.len()).rev() {
+            lengths[i * width + j] = if ma[i] == mb[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < ma.len() && j < mb.len() {
+        if ma[i] == mb[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    Some(pairs)
+}
+
+/// Line-based three-way merge; None if both sides changed the same stretch
+/// differently.
+fn merge_text(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    let b: Vec<&str> = base.lines().collect();
+    let o: Vec<&str> = ours.lines().collect();
+    let t: Vec<&str> = theirs.lines().collect();
+    let mut in_ours = vec![None; b.len()];
+    for (bi, oi) in common_lines(&b, &o)? {
+        in_ours[bi] = Some(oi);
+    }
+    let mut in_theirs = vec![None; b.len()];
+    for (bi, ti) in common_lines(&b, &t)? {
+        in_theirs[bi] = Some(ti);
+    }
+    // Base lines both sides kept split the texts into stretches that are
+    // merged on their own.
+    let anchors = (0..b.len())
+        .filter_map(|bi| Some((bi, in_ours[bi]?, in_theirs[bi]?)))
+        .chain([(b.len(), o.len(), t.len())]);
+    let mut merged = Vec::new();
+    let (mut bp, mut op, mut tp) = (0, 0, 0);
+    for (bi, oi, ti) in anchors {
+        let (bs, os, ts) = (&b[bp..bi], &o[op..oi], &t[tp..ti]);
+        if os == ts || ts == bs {
+            merged.extend_from_slice(os);
+        } else if os == bs {
+            merged.extend_from_slice(ts);
+        } else {
+            return None;
+        }
+        if bi < b.len() {
+            merged.push(b[bi]);
+        }
+        (bp, op, tp) = (bi + 1, oi + 1, ti + 1);
+    }
+    Some(merged.join("\n"))
+}
+
+/// A notebook's sources by `sync::source_key`, numbering repeats.
+fn keyed(notebook: &SealedNotebook) -> Vec<(String, &SealedSource)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    notebook
+        .sources
+        .iter()
+        .filter(|s| !s.content.trim().is_empty())
+        .map(|s| {
+            let key = sync::source_key(s);
+            let n = seen.entry(key.clone()).or_default();
+            *n += 1;
+            let key = if *n > 1 {
+                format!("{}#{}", key, n)
+            } else {
+                key
+            };
+            (key, s)
+        })
+        .collect()
+}
+
+/// The three-way rule for a single value: whichever side changed it, and
+/// ours when both did.
+fn pick<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> T {
+    if ours == base {
+        theirs.clone()
+    } else {
+        ours.clone()
+    }
+}
+
+/// Merge `ours` and `theirs` against `base` (an empty notebook when there's
+/// none). Conflicting sources are settled by `choose` where it returns
+/// Some (the content to keep, None to drop the source) and listed otherwise.
+fn merge_with(
+    base: Option<&SealedNotebook>,
+    ours: &SealedNotebook,
+    theirs: &SealedNotebook,
+    choose: impl Fn(&NoteConflict) -> Option<Option<String>>,
+) -> (SealedNotebook, Vec<NoteConflict>) {
+    let empty = SealedNotebook {
+        title: ours.title.clone(),
+        description: None,
+        color: None,
+        sources: Vec::new(),
+        audio: Vec::new(),
+    };
+    let base = base.unwrap_or(&empty);
+    let (b, o, t) = (keyed(base), keyed(ours), keyed(theirs));
+    let find = |list: &[(String, &SealedSource)], key: &str| {
+        list.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, s)| (*s).clone())
+    };
+    let mut keys: Vec<&String> = o.iter().map(|(k, _)| k).collect();
+    keys.extend(
+        t.iter()
+            .map(|(k, _)| k)
+            .filter(|k| !o.iter().any(|(ok, _)| ok == *k)),
+    );
+    keys.extend(
+        b.iter()
+            .map(|(k, _)| k)
+            .filter(|k| !o.iter().any(|(ok, _)| ok == *k) && !t.iter().any(|(tk, _)| tk == *k)),
+    );
+
+    let mut sources = Vec::new();
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let (bs, os, ts) = (find(&b, key), find(&o, key), find(&t, key));
+        let text = |s: &Option<SealedSource>| s.as_ref().map(|s| s.content.trim().to_string());
+        let (bc, oc, tc) = (text(&bs), text(&os), text(&ts));
+        let source = if oc == tc || tc == bc {
+            os
+        } else if oc == bc {
+            ts
+        } else {
+            let merged = match (&oc, &tc) {
+                (Some(oc), Some(tc)) => merge_text(bc.as_deref().unwrap_or_default(), oc, tc),
+                _ => None,
+            };
+            let like = os.clone().or(ts.clone());
+            match merged {
+                Some(content) => like.map(|s| SealedSource { content, ..s }),
+                None => {
+                    let Some(like) = like else {
+                        continue;
+                    };
+                    let conflict = NoteConflict {
+                        key: key.clone(),
+                        title: like.title.clone(),
+                        base: bc,
+                        ours: oc,
+                        theirs: tc,
+                    };
+                    match choose(&conflict) {
+                        Some(content) => content.map(|content| SealedSource {
+                            id: None,
+                            content,
+                            ..like
+                        }),
+                        None => {
+                            conflicts.push(conflict);
+                            None
+                        }
+                    }
+                }
+            }
+        };
+        sources.extend(source);
+    }
+    let merged = SealedNotebook {
+        title: pick(&base.title, &ours.title, &theirs.title),
+        description: pick(&base.description, &ours.description, &theirs.description),
+        color: pick(&base.color, &ours.color, &theirs.color),
+        sources,
+        audio: Vec::new(),
+    };
+    (merged, conflicts)
+}
+
+pub(crate) fn merge(
+    base: Option<&SealedNotebook>,
+    ours: &SealedNotebook,
+    theirs: &SealedNotebook,
+) -> Merge {
+    match merge_with(base, ours, theirs, |_| None) {
+        (merged, conflicts) if conflicts.is_empty() => Merge::Clean(merged),
+        (_, conflicts) => Merge::Conflicted(conflicts),
+    }
+}
+
+/// Where a conflict's copies of the peer's version and the merge base live.
+fn files(app: &AppHandle, key: &str) -> Result<(std::path::PathBuf, std::path::PathBuf), String> {
+    Ok((
+        sync::state_path(app, "conflicts", key)?,
+        sync::state_path(app, "conflicts/base", key)?,
+    ))
+}
+
+/// Keep `theirs` aside and add the conflict to settings, replacing an older
+/// one for the same notebook.
+pub(crate) fn record(
+    app: &AppHandle,
+    conflict: Conflict,
+    base: Option<&SealedNotebook>,
+    theirs: &SealedNotebook,
+    notes: usize,
+) -> Result<(), String> {
+    let key = sync::origin_key(&conflict.peer, &conflict.remote_id);
+    let (theirs_path, base_path) = files(app, &key)?;
+    sync::save_snapshot(&theirs_path, theirs)?;
+    match base {
+        Some(base) => sync::save_snapshot(&base_path, base)?,
+        None => {
+            let _ = std::fs::remove_file(&base_path);
+        }
+    }
+    tracing::warn!(
+        "[Sync] Conflict in {} from {}: {} notes changed on both devices",
+        conflict.local_id,
+        conflict.peer,
+        notes
+    );
+    crate::audit::record(
+        "sync_conflict",
+        format!("{} from {}", conflict.local_id, conflict.peer),
+    );
+    crate::settings::update(app, |s| {
+        s.sync
+            .conflicts
+            .retain(|c| !(c.peer == conflict.peer && c.remote_id == conflict.remote_id));
+        s.sync.conflicts.push(conflict.clone());
+    })?;
+    Ok(())
+}
+
+/// Remove a conflict's kept files, if any.
+pub(crate) fn discard(app: &AppHandle, key: &str) {
+    if let Ok((theirs_path, base_path)) = files(app, key) {
+        let _ = std::fs::remove_file(theirs_path);
+        let _ = std::fs::remove_file(base_path);
+    }
+}
+
+/// The peer's version and the merge base kept for a conflict.
+fn load(app: &AppHandle, key: &str) -> Result<(SealedNotebook, Option<SealedNotebook>), String> {
+    let (theirs_path, base_path) = files(app, key)?;
+    let theirs = sync::load_snapshot(&theirs_path)
+        .ok_or("The other device's version is missing; sync again to fetch it")?;
+    Ok((theirs, sync::load_snapshot(&base_path)))
+}
+
+#[tauri::command]
+pub(crate) async fn list_conflicts(app: AppHandle) -> Result<Vec<ConflictInfo>, String> {
+    let settings = crate::settings::get(&app).sync;
+    let mut infos = Vec::new();
+    for conflict in settings.conflicts {
+        let id = sync::origin_key(&conflict.peer, &conflict.remote_id);
+        // Worked out afresh, as the local notebook may have changed since.
+        let notes = match (
+            load(&app, &id),
+            crate::vault::export(&conflict.local_id).await,
+        ) {
+            (Ok((theirs, base)), Ok(ours)) => match merge(base.as_ref(), &ours, &theirs) {
+                Merge::Clean(_) => Vec::new(),
+                Merge::Conflicted(notes) => notes,
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("[Sync] Conflict {}: {}", id, e);
+                Vec::new()
+            }
+        };
+        infos.push(ConflictInfo {
+            id,
+            peer_name: sync::peer(&app, &conflict.peer).map(|p| p.name),
+            peer: conflict.peer,
+            local_id: conflict.local_id,
+            title: conflict.title,
+            detected_at: conflict.detected_at,
+            remote_updated_at: conflict.remote_updated_at,
+            notes,
+        });
+    }
+    Ok(infos)
+}
+
+/// Settle conflict `id` (as listed). `choices` is for `manual`: the content
+/// to keep for each conflicting note's key, or "" to delete the note.
+#[tauri::command]
+pub(crate) async fn resolve_conflict(
+    app: AppHandle,
+    id: String,
+    strategy: Resolution,
+    choices: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    let conflict = crate::settings::get(&app)
+        .sync
+        .conflicts
+        .into_iter()
+        .find(|c| sync::origin_key(&c.peer, &c.remote_id) == id)
+        .ok_or_else(|| format!("No conflict {}", id))?;
+    let (theirs, base) = load(&app, &id)?;
+    let offer = Offer {
+        id: conflict.remote_id.clone(),
+        title: theirs.title.clone(),
+        fingerprint: conflict.fingerprint.clone(),
+        origin: None,
+        base: None,
+        updated_at: conflict.remote_updated_at.clone(),
+        metadata_only: false,
+    };
+    match strategy {
+        Resolution::KeepBoth => {
+            let peer_name = sync::peer(&app, &conflict.peer)
+                .map(|p| p.name)
+                .unwrap_or_else(|| "other device".to_string());
+            let copy = SealedNotebook {
+                title: format!("{} ({})", theirs.title, peer_name),
+                ..theirs.clone()
+            };
+            let copy_id = crate::vault::import(&app, &copy).await?;
+            // Later changes from the peer go to the new notebook.
+            sync::record_taken(&app, &conflict.peer, &offer, &copy_id, false, &theirs)?;
+        }
+        Resolution::PreferLatest | Resolution::Manual => {
+            let ours = crate::vault::export(&conflict.local_id).await?;
+            let (merged, left) = if let Resolution::PreferLatest = strategy {
+                let notebook = crate::backend_api::get_json(
+                    &format!("/notebooks/{}", conflict.local_id),
+                    std::time::Duration::from_secs(30),
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+                let ours_at = notebook["updated_at"].as_str().unwrap_or_default();
+                let theirs_newer = conflict
+                    .remote_updated_at
+                    .as_deref()
+                    .is_some_and(|at| at > ours_at);
+                merge_with(base.as_ref(), &ours, &theirs, |n| {
+                    Some(if theirs_newer {
+                        n.theirs.clone()
+                    } else {
+                        n.ours.clone()
+                    })
+                })
+            } else {
+                let choices = choices.unwrap_or_default();
+                merge_with(base.as_ref(), &ours, &theirs, |n| {
+                    choices
+                        .get(&n.key)
+                        .map(|c| Some(c.clone()).filter(|c| !c.trim().is_empty()))
+                })
+            };
+            if !left.is_empty() {
+                let keys: Vec<&str> = left.iter().map(|n| n.key.as_str()).collect();
+                return Err(format!("Choose a version for: {}", keys.join(", ")));
+            }
+            sync::update_in_place(&app, &conflict.local_id, &ours, &merged).await?;
+            sync::record_taken(
+                &app,
+                &conflict.peer,
+                &offer,
+                &conflict.local_id,
+                conflict.mirror,
+                &theirs,
+            )?;
+        }
+    }
+    tracing::info!("[Sync] Resolved conflict {} ({:?})", id, strategy);
+    crate::audit::record("sync_conflict_resolved", format!("{} {:?}", id, strategy));
+    sync::refresh_status(&app);
+    Ok(())
+}
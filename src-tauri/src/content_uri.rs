@@ -0,0 +1,81 @@
+//! Files picked on a phone or tablet aren't plain paths. On Android they
+//! arrive as `content://` URIs from the storage access framework, which only
+//! the content resolver can open and which don't carry the file name. On iOS
+//! the document picker and share sheet hand over `file://` URLs to
+//! security-scoped resources, readable only between starting and stopping
+//! access. Either way the fs plugin opens them: for an import the file is
+//! copied into the app's cache under the name the picker reported, uploaded
+//! from there, and removed.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_fs::{FilePath, FsExt, OpenOptions};
+
+pub(crate) fn is_content_uri(path: &str) -> bool {
+    path.starts_with("content://") || (cfg!(target_os = "ios") && path.starts_with("file://"))
+}
+
+/// A file name safe to create, from what the picker reported.
+fn safe_name(name: Option<&str>) -> String {
+    let name: String = name
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | '\0'))
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "import".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Copy a picked file into `<app cache>/imports/<id>/<file name>`. The caller
+/// removes it with `discard` once it's been uploaded.
+pub(crate) async fn stage(
+    app: &AppHandle,
+    uri: &str,
+    file_name: Option<&str>,
+) -> Result<PathBuf, String> {
+    let source = FilePath::from_str(uri).map_err(|e| format!("Invalid file URI: {}", e))?;
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get app cache dir: {}", e))?
+        .join("imports")
+        .join(uuid::Uuid::new_v4().simple().to_string());
+    let dest = dir.join(safe_name(file_name));
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut options = OpenOptions::new();
+        options.read(true);
+        let copied = (|| {
+            let mut from = app
+                .fs()
+                .open(source.clone(), options)
+                .map_err(|e| format!("Failed to open the picked file: {}", e))?;
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let mut to = std::fs::File::create(&dest)
+                .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+            std::io::copy(&mut from, &mut to)
+                .map_err(|e| format!("Failed to copy the picked file: {}", e))?;
+            Ok(dest)
+        })();
+        // Opening started access to an iOS security-scoped file; end it.
+        #[cfg(target_os = "ios")]
+        let _ = app.fs().stop_accessing_security_scoped_resource(source);
+        copied
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Remove a staged file and its folder.
+pub(crate) fn discard(path: &std::path::Path) {
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,129 @@
+//! Context-window budgeting — how much of a notebook fits the model before a
+//! question is sent, so the UI can warn that sources will be truncated.
+//!
+//! Source sizes come from the backend's source list (`characters` / `chunks`
+//! per source). There is no tokenizer on the Rust side, so tokens are estimated
+//! the way the backend sizes `num_ctx`: one token per three characters, which
+//! errs towards warning early. The window mirrors the backend's cap for Ollama
+//! models — 8192 tokens scaled by RAM (×1 at 16 GB, up to ×8), never beyond
+//! the model's native length.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+const CHARS_PER_TOKEN: u64 = 3;
+const BASE_CTX_CAP: u64 = 8192;
+/// Assumed window for cloud providers, which don't report one.
+const CLOUD_CTX: u64 = 128_000;
+/// Room kept for the system prompt and instructions.
+const PROMPT_OVERHEAD_TOKENS: u64 = 512;
+/// Room kept for the answer.
+const ANSWER_RESERVE_TOKENS: u64 = 1024;
+
+#[derive(Serialize)]
+pub(crate) struct ContextEstimate {
+    model: Option<String>,
+    context_window: u64,
+    question_tokens: u64,
+    /// Tokens left for source text after the prompt and answer reserve.
+    available_tokens: u64,
+    source_tokens: u64,
+    sources_total: usize,
+    /// Sources that fit whole, in the backend's listing order.
+    sources_fit: usize,
+    /// Retrieved chunks that fit, for regular (top-k) questions.
+    chunks_fit: u64,
+    truncated: bool,
+}
+
+fn estimate_tokens(chars: u64) -> u64 {
+    chars.div_ceil(CHARS_PER_TOKEN)
+}
+
+async fn backend_json(path: &str) -> Result<serde_json::Value, String> {
+    let token = crate::read_app_token().await.unwrap_or_default();
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?
+        .get(format!("{}{}", crate::backend_api::base_url(), path))
+        .header("X-LocalBook-Token", &token)
+        .send()
+        .await
+        .map_err(|e| format!("Backend not reachable: {}", e))?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected {} response: {}", path, e))
+}
+
+/// The backend's cap for an Ollama model on this machine.
+async fn ollama_window(model: &str) -> u64 {
+    let ram_gb = crate::hardware::info(false).total_ram_bytes as f64 / 1e9;
+    let cap = (BASE_CTX_CAP as f64 * (ram_gb / 16.0).clamp(1.0, 8.0)) as u64;
+    match crate::ollama::context_length(model).await {
+        Ok(Some(native)) => cap.min(native),
+        Ok(None) => cap,
+        Err(e) => {
+            tracing::warn!("[Context] {}", e);
+            cap
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn estimate_context(
+    app: AppHandle,
+    notebook_id: String,
+    question: String,
+) -> Result<ContextEstimate, String> {
+    let prefs = crate::model_prefs::effective(&app, Some(&notebook_id));
+    let info = backend_json("/settings/llm-info").await?;
+    let provider = prefs
+        .llm_provider
+        .or_else(|| info["provider"].as_str().map(String::from));
+    let model = prefs
+        .model
+        .or_else(|| info["model_name"].as_str().map(String::from));
+
+    let context_window = match (provider.as_deref(), model.as_deref()) {
+        (Some("ollama") | None, Some(m)) => ollama_window(m).await,
+        (Some("ollama") | None, None) => BASE_CTX_CAP,
+        _ => CLOUD_CTX,
+    };
+
+    let sources = backend_json(&format!("/sources/{}", notebook_id)).await?;
+    let sources = sources.as_array().cloned().unwrap_or_default();
+    let question_tokens = estimate_tokens(question.len() as u64);
+    let available_tokens = context_window
+        .saturating_sub(question_tokens + PROMPT_OVERHEAD_TOKENS + ANSWER_RESERVE_TOKENS);
+
+    let mut source_tokens = 0;
+    let mut sources_fit = 0;
+    let (mut total_chunks, mut total_chars) = (0, 0);
+    for s in &sources {
+        let chars = s["characters"].as_u64().unwrap_or(0);
+        total_chars += chars;
+        total_chunks += s["chunks"].as_u64().unwrap_or(0);
+        source_tokens += estimate_tokens(chars);
+        if source_tokens <= available_tokens {
+            sources_fit += 1;
+        }
+    }
+    let chunk_tokens = estimate_tokens(total_chars / total_chunks.max(1)).max(1);
+
+    Ok(ContextEstimate {
+        model,
+        context_window,
+        question_tokens,
+        available_tokens,
+        source_tokens,
+        sources_total: sources.len(),
+        sources_fit,
+        chunks_fit: available_tokens / chunk_tokens,
+        truncated: source_tokens > available_tokens,
+    })
+}
@@ -0,0 +1,393 @@
+//! Crash reports for Rust panics and backend crashes.
+//!
+//! A panic hook, installed first thing in `run()`, logs each panic and
+//! writes it — message, location, thread and backtrace — to
+//! `<app data>/logs/crashes/` as JSON before the default hook runs. A panic
+//! on the main thread takes the app down, so that one also gets a native
+//! dialog offering to copy the details or export a diagnostics bundle, and
+//! the backend is shut down properly rather than left behind. The
+//! watchdog does the same when the backend exits abnormally or stops
+//! answering, with its exit status and, on macOS, the key lines of the
+//! system's crash report. Everything is passed through `logging::redact`.
+//!
+//! On the next launch, reports nobody has seen yet bring up a dialog that
+//! offers to save a diagnostics bundle with them included; crash reports only
+//! go into a bundle when asked for (`include_crash_reports`).
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::logging::redact;
+
+const CRASH_DIR: &str = "crashes";
+const KEEP_REPORTS: usize = 20;
+#[cfg(desktop)]
+const EXPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+static APP_VERSION: OnceLock<String> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CrashReport {
+    id: String,
+    /// "panic", "backend_exit" or "backend_unresponsive".
+    kind: String,
+    message: String,
+    /// Backtrace, or the backend's crash log entry.
+    details: String,
+    app_version: String,
+    /// Unix seconds.
+    created_at: u64,
+    /// Already offered in the recovery dialog.
+    #[serde(default)]
+    reviewed: bool,
+}
+
+/// `<app data>/logs/crashes`.
+pub(crate) fn dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::logging::dir(app)?.join(CRASH_DIR))
+}
+
+fn save(report: &CrashReport) {
+    let Some(dir) = DIR.get() else {
+        return;
+    };
+    let path = dir.join(format!("{}.json", report.id));
+    let written = std::fs::create_dir_all(dir).and_then(|_| {
+        std::fs::write(
+            &path,
+            serde_json::to_string_pretty(report).unwrap_or_default(),
+        )
+    });
+    if let Err(e) = written {
+        // Not through the logging sink: a panic there may hold its lock.
+        std::eprintln!("[Crash] Failed to write {}: {}", path.display(), e);
+    }
+}
+
+fn new_report(kind: &str, message: String, details: String) -> CrashReport {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    CrashReport {
+        id: format!("{}-{}", kind, now.as_millis()),
+        kind: kind.to_string(),
+        message: redact::line(&message),
+        details: details
+            .lines()
+            .map(redact::line)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        app_version: APP_VERSION.get().cloned().unwrap_or_default(),
+        created_at: now.as_secs(),
+        reviewed: false,
+    }
+}
+
+/// All reports, newest first.
+fn load(app: &AppHandle) -> Vec<CrashReport> {
+    let Ok(entries) = dir(app).and_then(|d| std::fs::read_dir(&d).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .flatten()
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect();
+    reports.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    reports
+}
+
+/// Report files, for the diagnostics bundle.
+pub(crate) fn files(app: &AppHandle) -> Vec<PathBuf> {
+    let Ok(dir) = dir(app) else {
+        return Vec::new();
+    };
+    load(app)
+        .into_iter()
+        .map(|r| dir.join(format!("{}.json", r.id)))
+        .collect()
+}
+
+fn prune(app: &AppHandle, reports: &[CrashReport]) {
+    let Ok(dir) = dir(app) else {
+        return;
+    };
+    for old in reports.iter().skip(KEEP_REPORTS) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", old.id)));
+    }
+}
+
+/// Record a backend that exited (`exit` is its status) or had to be killed
+/// for not answering (`exit` None). `log_entry` is what the watchdog wrote
+/// to the crash log.
+pub(crate) fn record_backend_crash(exit: Option<&str>, log_entry: &str) {
+    let report = match exit {
+        Some(status) => new_report(
+            "backend_exit",
+            format!("The backend exited ({})", status),
+            log_entry.to_string(),
+        ),
+        None => new_report(
+            "backend_unresponsive",
+            "The backend stopped responding".to_string(),
+            log_entry.to_string(),
+        ),
+    };
+    save(&report);
+}
+
+/// Install the panic hook. First thing in `run()`, after `logging::init`,
+/// so a panic while the app is being built is caught too.
+pub(crate) fn init() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info
+            .location()
+            .map(|l| format!(" at {}:{}", l.file(), l.line()))
+            .unwrap_or_default();
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        let report = new_report(
+            "panic",
+            format!("{}{} (thread {})", payload, location, thread),
+            std::backtrace::Backtrace::force_capture().to_string(),
+        );
+        // A panic inside logging may hold the sink's lock.
+        if info
+            .location()
+            .is_none_or(|l| !l.file().ends_with("logging.rs"))
+        {
+            tracing::error!("[Crash] Panic: {}\n{}", report.message, report.details);
+        }
+        save(&report);
+        previous(info);
+        if thread == "main" || cfg!(panic = "abort") {
+            on_fatal_panic(&report);
+        }
+    }));
+}
+
+/// The app is going down: tell the user, then stop the backend.
+fn on_fatal_panic(report: &CrashReport) {
+    #[cfg(desktop)]
+    if !crate::cli::active() {
+        fatal_dialog(report);
+    }
+    #[cfg(not(desktop))]
+    let _ = report;
+    if !crate::cli::attached() {
+        crate::stop_backend();
+        if let Some(app) = APP.get() {
+            crate::folder_sync::release(app);
+        }
+    }
+    crate::ollama::stop_managed();
+}
+
+/// Blocking, and straight through rfd: the dialog plugin goes by way of the
+/// event loop, which is what just panicked.
+#[cfg(desktop)]
+fn fatal_dialog(report: &CrashReport) {
+    use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+    const EXPORT: &str = "Export Diagnostics…";
+    const COPY: &str = "Copy Details";
+    const QUIT: &str = "Quit";
+    let details = format!("{}\n\n{}", report.message, report.details);
+    loop {
+        let chosen = MessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("LocalBook")
+            .set_description(format!(
+                "LocalBook ran into a problem and has to close.\n\n{}",
+                report.message
+            ))
+            .set_buttons(MessageButtons::YesNoCancelCustom(
+                EXPORT.to_string(),
+                COPY.to_string(),
+                QUIT.to_string(),
+            ))
+            .show();
+        match chosen {
+            MessageDialogResult::Custom(c) if c == COPY => {
+                if let Err(e) = copy_to_clipboard(&details) {
+                    std::eprintln!("[Crash] {}", e);
+                }
+            }
+            MessageDialogResult::Custom(c) if c == EXPORT => {
+                export_after_panic();
+                return;
+            }
+            _ => return,
+        }
+    }
+}
+
+/// Save a diagnostics bundle (crash reports included) where the user picks.
+#[cfg(desktop)]
+fn export_after_panic() {
+    let Some(app) = APP.get().cloned() else {
+        return;
+    };
+    let mut dialog = rfd::FileDialog::new();
+    if let Some(dir) = crate::default_dirs::path(&app, crate::default_dirs::DirKind::Exports) {
+        dialog = dialog.set_directory(dir);
+    }
+    let Some(path) = dialog
+        .set_file_name("localbook-diagnostics.zip")
+        .add_filter("Zip", &["zip"])
+        .save_file()
+    else {
+        return;
+    };
+    // Its own thread: this one may be inside the async runtime.
+    let exported = std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            tokio::time::timeout(EXPORT_TIMEOUT, crate::diagnostics::export(&app, path, true))
+                .await
+                .map_err(|_| "Timed out writing the diagnostics bundle".to_string())?
+        })
+    })
+    .join()
+    .unwrap_or_else(|_| Err("Failed to write the diagnostics bundle".to_string()));
+    let (level, message) = match exported {
+        Ok(path) => (
+            rfd::MessageLevel::Info,
+            format!("Diagnostics saved to {}", path.display()),
+        ),
+        Err(e) => (rfd::MessageLevel::Error, e),
+    };
+    rfd::MessageDialog::new()
+        .set_level(level)
+        .set_title("LocalBook")
+        .set_description(message)
+        .show();
+}
+
+/// Through the platform's own tool; nothing else here touches the clipboard.
+#[cfg(desktop)]
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    #[cfg(target_os = "macos")]
+    let (program, args): (&str, &[&str]) = ("pbcopy", &[]);
+    #[cfg(windows)]
+    let (program, args): (&str, &[&str]) = ("clip", &[]);
+    #[cfg(all(not(target_os = "macos"), not(windows)))]
+    let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-copy", &[])
+    } else {
+        ("xclip", &["-selection", "clipboard"])
+    };
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to copy: {}", e))?;
+    }
+    child.wait().map_err(|e| format!("Failed to copy: {}", e))?;
+    Ok(())
+}
+
+/// Offer the reports nobody has seen yet, then mark them seen.
+fn offer_recovery(app: &AppHandle, reports: &mut [CrashReport]) {
+    let unseen = reports.iter().filter(|r| !r.reviewed).count();
+    if unseen == 0 {
+        return;
+    }
+    let app_crashed = reports.iter().any(|r| !r.reviewed && r.kind == "panic");
+    let message = format!(
+        "{} Saving a diagnostics bundle with the crash report helps get it fixed.",
+        if app_crashed {
+            "LocalBook closed unexpectedly last time."
+        } else {
+            "LocalBook's backend stopped unexpectedly last time and was restarted."
+        }
+    );
+    tracing::info!("[Crash] {} unreviewed crash report(s)", unseen);
+    for report in reports.iter_mut().filter(|r| !r.reviewed) {
+        report.reviewed = true;
+        save(report);
+    }
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("Crash Report")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Save Report…".to_string(),
+            "Not Now".to_string(),
+        ))
+        .show(move |chosen| {
+            if !chosen {
+                return;
+            }
+            let app = handle.clone();
+            handle
+                .dialog()
+                .file()
+                .set_file_name("localbook-diagnostics.zip")
+                .add_filter("Zip", &["zip"])
+                .save_file(move |path| {
+                    let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+                        return;
+                    };
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = crate::diagnostics::export(&app, path, true).await {
+                            tracing::warn!("[Crash] {}", e);
+                        }
+                    });
+                });
+        });
+}
+
+/// Offer any reports from the last run. Called in setup after logging
+/// starts; panics are only saved from here on.
+pub(crate) fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let _ = APP_VERSION.set(app.package_info().version.to_string());
+    match dir(app) {
+        Ok(dir) => {
+            let _ = DIR.set(dir);
+        }
+        Err(e) => tracing::warn!("[Crash] {}", e),
+    }
+    let mut reports = load(app);
+    prune(app, &reports);
+    reports.truncate(KEEP_REPORTS);
+    offer_recovery(app, &mut reports);
+}
+
+#[tauri::command]
+pub(crate) async fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, String> {
+    Ok(load(&app))
+}
+
+#[tauri::command]
+pub(crate) async fn clear_crash_reports(app: AppHandle) -> Result<(), String> {
+    let dir = dir(&app)?;
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", dir.display(), e)),
+    }
+}
@@ -0,0 +1,117 @@
+//! Debug mode: verbose logs from both processes until it's turned off, so a
+//! user can reproduce a problem for a bug report without editing launch
+//! scripts.
+//!
+//! While it's on, the backend is started with `LOCALBOOK_DEBUG=1`, which
+//! puts its logging at debug level and lets the usually quieted libraries
+//! (uvicorn's request log among them) through, and the shell logs at debug
+//! unless a log level was chosen (see `logging`). Turning it on or off
+//! restarts a sidecar this app started; a remote backend or one started
+//! separately is left alone. Builds that include devtools (debug builds, or
+//! the `devtools` feature) also open them on the main window; release builds
+//! leave them out on purpose (see `hardening`), and the mode says so.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize)]
+pub(crate) struct DebugMode {
+    enabled: bool,
+    /// This build can open devtools.
+    devtools: bool,
+    /// The backend was restarted to pick the change up.
+    backend_restarted: bool,
+}
+
+pub(crate) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What the backend needs to know about the mode.
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    if enabled() {
+        vec![("LOCALBOOK_DEBUG", "1".to_string())]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Load the mode from settings. Called in setup before the backend starts.
+pub(crate) fn start(app: &AppHandle) {
+    let on = crate::settings::get(app).debug_mode;
+    ENABLED.store(on, Ordering::Relaxed);
+    if on {
+        tracing::warn!("[Debug] Debug mode is on");
+    }
+}
+
+fn devtools(app: &AppHandle, open: bool) -> bool {
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    {
+        use tauri::Manager;
+        if let Some(window) = app.get_webview_window("main") {
+            if open {
+                window.open_devtools();
+            } else {
+                window.close_devtools();
+            }
+        }
+        true
+    }
+    #[cfg(not(any(debug_assertions, feature = "devtools")))]
+    {
+        let _ = (app, open);
+        false
+    }
+}
+
+/// A sidecar this app started and can start again.
+fn owns_backend() -> bool {
+    crate::remote_backend::url().is_none()
+        && !crate::cli::options().no_sidecar
+        && !crate::cli::attached()
+}
+
+#[tauri::command]
+pub(crate) async fn get_debug_mode() -> Result<DebugMode, String> {
+    Ok(DebugMode {
+        enabled: enabled(),
+        devtools: cfg!(any(debug_assertions, feature = "devtools")),
+        backend_restarted: false,
+    })
+}
+
+/// Turn debug mode on or off, now and for later runs.
+#[tauri::command]
+pub(crate) async fn set_debug_mode(app: AppHandle, enabled: bool) -> Result<DebugMode, String> {
+    let was = ENABLED.swap(enabled, Ordering::Relaxed);
+    crate::settings::update(&app, |s| s.debug_mode = enabled)?;
+    crate::logging::load_filter(&app);
+    let devtools = devtools(&app, enabled);
+    if was == enabled {
+        return Ok(DebugMode {
+            enabled,
+            devtools,
+            backend_restarted: false,
+        });
+    }
+    tracing::warn!("[Debug] Debug mode {}", if enabled { "on" } else { "off" });
+    let backend_restarted = owns_backend();
+    if backend_restarted {
+        let reason = if enabled {
+            "debug mode on"
+        } else {
+            "debug mode off"
+        };
+        crate::restart_backend_and_wait(&app, reason).await?;
+    }
+    Ok(DebugMode {
+        enabled,
+        devtools,
+        backend_restarted,
+    })
+}
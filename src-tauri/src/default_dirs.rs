@@ -0,0 +1,146 @@
+//! The folders file dialogs open in: one each for imports, exports, backups
+//! and downloads.
+//!
+//! Unset, they're the OS documents or downloads folder, or for backups the
+//! one in app data (`backup::default_dir`). A folder the user sets must have
+//! been picked in a dialog first (so `scope` allows it), and must exist and
+//! take a test file. The webview passes them to its dialogs as the default
+//! path, the crash dialog opens in the exports folder, and backups with no
+//! destination of their own go to the backups folder. They're cached here
+//! so the crash dialog, shown mid-panic, never waits on the settings lock.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+static CACHE: RwLock<Option<DefaultDirs>> = RwLock::new(None);
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DirKind {
+    Imports,
+    Exports,
+    Backups,
+    Downloads,
+}
+
+/// What the user chose; None is the default.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct DefaultDirs {
+    pub imports: Option<String>,
+    pub exports: Option<String>,
+    pub backups: Option<String>,
+    pub downloads: Option<String>,
+}
+
+impl DefaultDirs {
+    fn get(&self, kind: DirKind) -> Option<&String> {
+        match kind {
+            DirKind::Imports => self.imports.as_ref(),
+            DirKind::Exports => self.exports.as_ref(),
+            DirKind::Backups => self.backups.as_ref(),
+            DirKind::Downloads => self.downloads.as_ref(),
+        }
+    }
+
+    fn set(&mut self, kind: DirKind, path: Option<String>) {
+        match kind {
+            DirKind::Imports => self.imports = path,
+            DirKind::Exports => self.exports = path,
+            DirKind::Backups => self.backups = path,
+            DirKind::Downloads => self.downloads = path,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct DefaultDir {
+    kind: DirKind,
+    path: Option<String>,
+    /// Chosen by the user rather than the default.
+    custom: bool,
+}
+
+fn fallback(app: &AppHandle, kind: DirKind) -> Option<PathBuf> {
+    let paths = app.path();
+    match kind {
+        DirKind::Imports | DirKind::Exports => paths.document_dir().ok(),
+        DirKind::Downloads => paths.download_dir().ok(),
+        DirKind::Backups => crate::backup::default_dir(app).ok(),
+    }
+}
+
+/// The user's folder for `kind`, if they chose one.
+pub(crate) fn custom(kind: DirKind) -> Option<PathBuf> {
+    let cache = CACHE.try_read().ok()?;
+    cache.as_ref()?.get(kind).map(PathBuf::from)
+}
+
+/// Where dialogs for `kind` should open.
+pub(crate) fn path(app: &AppHandle, kind: DirKind) -> Option<PathBuf> {
+    custom(kind).or_else(|| fallback(app, kind))
+}
+
+/// A folder that exists and can be written to.
+fn check_writable(dir: &Path) -> Result<(), String> {
+    if !dir.is_absolute() {
+        return Err(format!("{} isn't a full path", dir.display()));
+    }
+    if !dir.is_dir() {
+        return Err(format!("{} isn't a folder", dir.display()));
+    }
+    let probe = dir.join(format!(".localbook-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"").map_err(|e| format!("Can't write to {}: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Load the folders from settings. Called in setup.
+pub(crate) fn start(app: &AppHandle) {
+    let dirs = crate::settings::get(app).default_dirs;
+    *CACHE.write().unwrap_or_else(|e| e.into_inner()) = Some(dirs);
+}
+
+fn list(app: &AppHandle) -> Vec<DefaultDir> {
+    [
+        DirKind::Imports,
+        DirKind::Exports,
+        DirKind::Backups,
+        DirKind::Downloads,
+    ]
+    .into_iter()
+    .map(|kind| DefaultDir {
+        kind,
+        path: path(app, kind).map(|p| p.to_string_lossy().into_owned()),
+        custom: custom(kind).is_some(),
+    })
+    .collect()
+}
+
+#[tauri::command]
+pub(crate) async fn get_default_dirs(app: AppHandle) -> Result<Vec<DefaultDir>, String> {
+    Ok(list(&app))
+}
+
+/// Set the folder for `kind`; None goes back to the default.
+#[tauri::command]
+pub(crate) async fn set_default_dir(
+    app: AppHandle,
+    kind: DirKind,
+    path: Option<String>,
+) -> Result<Vec<DefaultDir>, String> {
+    let path = match path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => {
+            let dir = crate::scope::check(&app, Path::new(path.trim()))?;
+            check_writable(&dir)?;
+            Some(dir.to_string_lossy().into_owned())
+        }
+        None => None,
+    };
+    let settings = crate::settings::update(&app, |s| s.default_dirs.set(kind, path))?;
+    *CACHE.write().unwrap_or_else(|e| e.into_inner()) = Some(settings.default_dirs);
+    Ok(list(&app))
+}
@@ -0,0 +1,226 @@
+//! Diagnostics bundle for bug reports.
+//!
+//! `export_diagnostics` writes one zip with everything a bug report needs:
+//! the shell logs and the backend's output (already sanitized as they were
+//! written), the backend crash log, a fresh `run_doctor` report, versions, the settings, this
+//! run's backend health history and the last week's failed jobs, plus the crash reports (see `crash`) when asked
+//! for. Nothing in it holds a secret — API keys and the
+//! proxy password live in the keychain — and everything not already
+//! sanitized goes through `logging::redact` on the way in, so home-folder
+//! paths, the user name and notebook titles don't leave the machine.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+
+use crate::logging::redact;
+
+/// Failed jobs from this far back go in the bundle.
+const JOB_FAILURES_SECS: u64 = 7 * 24 * 60 * 60;
+const CRASH_LOG: &str = "backend_crashes.log";
+
+/// Settings as JSON with what identifies the user or their network taken
+/// out: sealed notebook titles, titles of notebooks in sync conflicts, the
+/// proxy, sync storage and note history remote users and URLs, the remote
+/// backend's URL, and paths.
+fn sanitized_settings(app: &AppHandle) -> serde_json::Value {
+    let mut settings = serde_json::to_value(crate::settings::get(app)).unwrap_or_default();
+    if let Some(sealed) = settings.get_mut("encrypted_notebooks") {
+        let count = sealed.as_object().map_or(0, |o| o.len());
+        *sealed = json!(format!("[{} sealed notebooks]", count));
+    }
+    for pointer in [
+        "/proxy/username",
+        "/lock/recovery_hash",
+        "/sync/remote/username",
+        "/sync/remote/url",
+        "/versioning/remote_url",
+        "/versioning/remote_user",
+        "/remote_backend_url",
+    ] {
+        if let Some(value) = settings.pointer_mut(pointer) {
+            if !value.is_null() {
+                *value = json!("[set]");
+            }
+        }
+    }
+    if let Some(conflicts) = settings
+        .pointer_mut("/sync/conflicts")
+        .and_then(|c| c.as_array_mut())
+    {
+        for conflict in conflicts {
+            if let Some(title) = conflict.get_mut("title") {
+                *title = json!("[notebook]");
+            }
+        }
+    }
+    if let Some(url) = settings.get_mut("external_llm_url") {
+        if let Some(raw) = url.as_str() {
+            // Keep the host for context, drop any credentials in it.
+            let cleaned = reqwest::Url::parse(raw)
+                .map(|mut u| {
+                    let _ = u.set_username("");
+                    let _ = u.set_password(None);
+                    u.to_string()
+                })
+                .unwrap_or_else(|_| "[invalid]".to_string());
+            *url = json!(cleaned);
+        }
+    }
+    redacted(settings)
+}
+
+/// `value` with paths and the user name redacted as in the logs.
+fn redacted(value: serde_json::Value) -> serde_json::Value {
+    serde_json::from_str(&redact::line(&value.to_string())).unwrap_or(value)
+}
+
+/// `get_app_info`, plus when the bundle was made.
+fn info(app: &AppHandle) -> serde_json::Value {
+    let mut info = serde_json::to_value(crate::about::info(app)).unwrap_or_default();
+    if let Some(fields) = info.as_object_mut() {
+        fields.insert("local_only".into(), json!(crate::privacy::local_only()));
+        fields.insert(
+            "created_at".into(),
+            json!(chrono::Local::now().to_rfc3339()),
+        );
+    }
+    redacted(info)
+}
+
+/// Log files to include: the shell logs and crash reports as they are, the
+/// backend crash log redacted.
+fn log_files(app: &AppHandle, include_crashes: bool) -> Vec<(String, PathBuf, bool)> {
+    let mut files = Vec::new();
+    if let Ok(entries) = crate::logging::dir(app).and_then(|d| {
+        std::fs::read_dir(&d).map_err(|e| format!("Failed to read {}: {}", d.display(), e))
+    }) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.extension().is_some_and(|e| e == "log") {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                files.push((format!("logs/{}", name), path, false));
+            }
+        }
+    }
+    if let Ok(dir) = crate::data_dir(app) {
+        let crash_log = dir.join(CRASH_LOG);
+        if crash_log.is_file() {
+            files.push((format!("logs/{}", CRASH_LOG), crash_log, true));
+        }
+    }
+    if include_crashes {
+        for path in crate::crash::files(app) {
+            if let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) {
+                files.push((format!("crashes/{}", name), path, false));
+            }
+        }
+    }
+    files
+}
+
+/// Blocking: write the zip.
+fn write_zip(
+    dest: &Path,
+    documents: Vec<(&'static str, serde_json::Value)>,
+    logs: Vec<(String, PathBuf, bool)>,
+) -> Result<(), String> {
+    let tmp = dest.with_extension("zip.part");
+    let file = std::fs::File::create(&tmp)
+        .map_err(|e| format!("Failed to create {}: {}", tmp.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(bytes).map_err(|e| e.to_string())
+    };
+    let result = (|| {
+        for (name, value) in documents {
+            let text = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+            add(name, text.as_bytes())?;
+        }
+        for (name, path, needs_redacting) in logs {
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            if needs_redacting {
+                let text = String::from_utf8_lossy(&bytes)
+                    .lines()
+                    .map(redact::line)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                add(&name, text.as_bytes())?;
+            } else {
+                add(&name, &bytes)?;
+            }
+        }
+        Ok(())
+    })();
+    let finished = result.and_then(|_| zip.finish().map(|_| ()).map_err(|e| e.to_string()));
+    if let Err(e) = finished {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to write the diagnostics bundle: {}", e));
+    }
+    std::fs::rename(&tmp, dest).map_err(|e| format!("Failed to save {}: {}", dest.display(), e))
+}
+
+/// Write a diagnostics zip to `dest` — a file path, or a folder to create
+/// `localbook-diagnostics-<time>.zip` in. Returns the file written.
+#[tauri::command]
+pub(crate) async fn export_diagnostics(
+    app: AppHandle,
+    dest: String,
+    include_crash_reports: Option<bool>,
+) -> Result<String, String> {
+    let dest = crate::scope::check(&app, Path::new(&dest))?;
+    let written = export(&app, dest, include_crash_reports.unwrap_or(false)).await?;
+    Ok(written.to_string_lossy().into_owned())
+}
+
+/// Write the bundle to a path the user chose.
+pub(crate) async fn export(
+    app: &AppHandle,
+    dest: PathBuf,
+    include_crashes: bool,
+) -> Result<PathBuf, String> {
+    let dest = if dest.is_dir() {
+        dest.join(format!(
+            "localbook-diagnostics-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    } else {
+        dest
+    };
+
+    let doctor = match crate::doctor::run_doctor(app.clone()).await {
+        Ok(report) => serde_json::to_value(report).unwrap_or_default(),
+        Err(e) => json!({ "error": e }),
+    };
+    let since = crate::models::now_secs().saturating_sub(JOB_FAILURES_SECS);
+    let failures = match crate::jobs::recent_failures(since).await {
+        Ok(entries) => serde_json::to_value(entries).unwrap_or_default(),
+        Err(e) => json!({ "error": e }),
+    };
+    let documents = vec![
+        ("info.json", info(app)),
+        ("doctor.json", redacted(doctor)),
+        ("settings.json", sanitized_settings(app)),
+        ("job_failures.json", redacted(failures)),
+        (
+            "health_history.json",
+            serde_json::to_value(crate::health_history::snapshot()).unwrap_or_default(),
+        ),
+    ];
+    let logs = log_files(app, include_crashes);
+
+    let target = dest.clone();
+    tauri::async_runtime::spawn_blocking(move || write_zip(&target, documents, logs))
+        .await
+        .map_err(|e| e.to_string())??;
+    tracing::info!("[Diagnostics] Bundle written");
+    crate::audit::record("diagnostics_exported", dest.display().to_string());
+    Ok(dest)
+}
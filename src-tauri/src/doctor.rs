@@ -0,0 +1,376 @@
+//! Self-diagnostics for "it doesn't start" reports.
+//!
+//! `run_doctor` checks the things a broken install usually comes down to —
+//! the backend binary, port 8000, the data folder, free disk, the health
+//! endpoint, model files, GPU drivers and the keychain — and returns one
+//! pass/warn/fail entry per check with a suggested fix, in a fixed order the
+//! UI can render as a checklist. Nothing is repaired here.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// Below this much free space, downloads and indexing will fail.
+const DISK_FAIL_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const DISK_WARN_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Check {
+    id: &'static str,
+    label: &'static str,
+    status: CheckStatus,
+    message: String,
+    /// What to do about a warning or failure.
+    fix: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DoctorReport {
+    /// The worst status of any check.
+    status: CheckStatus,
+    checks: Vec<Check>,
+    /// Unix seconds.
+    ran_at: u64,
+}
+
+impl Check {
+    fn new(id: &'static str, label: &'static str, status: CheckStatus, message: String) -> Self {
+        Self {
+            id,
+            label,
+            status,
+            message,
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+fn check_sidecar(app: &AppHandle) -> Check {
+    const ID: &str = "sidecar";
+    const LABEL: &str = "Backend binary";
+    let found = crate::backend_candidates(app)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|c| c.exists());
+    match found {
+        Some(exe) if is_executable(&exe) => {
+            Check::new(ID, LABEL, CheckStatus::Pass, format!("{}", exe.display()))
+        }
+        Some(exe) => Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Fail,
+            format!("{} is not executable", exe.display()),
+        )
+        .fix("Reinstall LocalBook"),
+        None => match crate::pyenv::source_dir(app) {
+            Some(source) => Check::new(
+                ID,
+                LABEL,
+                CheckStatus::Pass,
+                format!("Runs from source in {}", source.display()),
+            ),
+            None if cfg!(debug_assertions) => Check::new(
+                ID,
+                LABEL,
+                CheckStatus::Warn,
+                "No bundled backend (development build)".to_string(),
+            )
+            .fix("Start the backend with ./start.sh"),
+            None => Check::new(
+                ID,
+                LABEL,
+                CheckStatus::Fail,
+                "The bundled backend is missing".to_string(),
+            )
+            .fix("Reinstall LocalBook"),
+        },
+    }
+}
+
+/// The port is fine if it's free or our backend answers on it.
+fn check_port(healthy: bool) -> Check {
+    const ID: &str = "port";
+    const LABEL: &str = "Backend port";
+    let port = crate::backend_api::sidecar_port();
+    if healthy {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Pass,
+            format!("Port {} is used by the backend", port),
+        );
+    }
+    match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Pass,
+            format!("Port {} is free", port),
+        ),
+        Err(e) => Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Fail,
+            format!("Port {} is taken by another program: {}", port, e),
+        )
+        .fix(format!(
+            "Quit whatever is listening on port {} and restart LocalBook",
+            port
+        )),
+    }
+}
+
+fn check_data_dir(app: &AppHandle) -> Check {
+    const ID: &str = "data_dir";
+    const LABEL: &str = "Data folder";
+    let result = crate::data_dir(app).and_then(|dir| {
+        let probe = dir.join(".doctor-write-test");
+        std::fs::write(&probe, b"ok")
+            .and_then(|_| std::fs::remove_file(&probe))
+            .map_err(|e| format!("{} is not writable: {}", dir.display(), e))?;
+        Ok(dir)
+    });
+    match result {
+        Ok(dir) => Check::new(ID, LABEL, CheckStatus::Pass, dir.display().to_string()),
+        Err(e) => Check::new(ID, LABEL, CheckStatus::Fail, e)
+            .fix("Check the folder's permissions and that the disk isn't read-only"),
+    }
+}
+
+fn check_disk(app: &AppHandle) -> Check {
+    const ID: &str = "disk_space";
+    const LABEL: &str = "Disk space";
+    let mut dirs = Vec::new();
+    if let Ok(dir) = crate::data_dir(app) {
+        dirs.push(dir);
+    }
+    if let Ok(dir) = crate::models::models_dir(app) {
+        dirs.push(dir);
+    }
+    let lowest = dirs
+        .iter()
+        .filter_map(|d| fs4::available_space(d).ok().map(|free| (free, d)))
+        .min_by_key(|(free, _)| *free);
+    let Some((free, dir)) = lowest else {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            "Couldn't read free space".to_string(),
+        );
+    };
+    let message = format!(
+        "{:.1} GB free on {}",
+        free as f64 / (1024.0 * 1024.0 * 1024.0),
+        dir.display()
+    );
+    let status = if free < DISK_FAIL_BYTES {
+        CheckStatus::Fail
+    } else if free < DISK_WARN_BYTES {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    let check = Check::new(ID, LABEL, status, message);
+    if status == CheckStatus::Pass {
+        check
+    } else {
+        check.fix("Free up space; models and indexes need several GB")
+    }
+}
+
+fn check_health(healthy: bool) -> Check {
+    if healthy {
+        Check::new(
+            "health",
+            "Backend health",
+            CheckStatus::Pass,
+            "The backend answers /health".to_string(),
+        )
+    } else {
+        Check::new(
+            "health",
+            "Backend health",
+            CheckStatus::Fail,
+            "The backend doesn't answer /health".to_string(),
+        )
+        .fix("Restart the backend from the tray menu; if it keeps failing, export diagnostics")
+    }
+}
+
+async fn check_models(app: &AppHandle) -> Check {
+    const ID: &str = "models";
+    const LABEL: &str = "Model files";
+    let mut broken = Vec::new();
+    if let Ok(dir) = crate::models::models_dir(app) {
+        for entry in crate::models::load_registry(app) {
+            match std::fs::metadata(dir.join(&entry.file_name)) {
+                Ok(m) if m.len() == entry.size_bytes => {}
+                Ok(_) => broken.push(format!("{} (incomplete)", entry.id)),
+                Err(_) => broken.push(format!("{} (missing)", entry.id)),
+            }
+        }
+    }
+    if !broken.is_empty() {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Fail,
+            format!("Damaged: {}", broken.join(", ")),
+        )
+        .fix("Remove and download these models again");
+    }
+    if !crate::ollama::is_running().await {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            "Ollama isn't running, so its models couldn't be checked".to_string(),
+        )
+        .fix("Start Ollama");
+    }
+    let mut missing = Vec::new();
+    for (name, _) in crate::REQUIRED_MODELS {
+        if !crate::ollama::model_available(name).await {
+            missing.push(*name);
+        }
+    }
+    if missing.is_empty() {
+        Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Pass,
+            "All required models are installed".to_string(),
+        )
+    } else {
+        Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            format!("Not downloaded yet: {}", missing.join(", ")),
+        )
+        .fix("They download on the next start, or pull them from the model manager")
+    }
+}
+
+async fn check_gpu() -> Check {
+    const ID: &str = "gpu";
+    const LABEL: &str = "GPU drivers";
+    let Ok(hw) = tauri::async_runtime::spawn_blocking(|| crate::hardware::info(false)).await else {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            "Hardware detection failed".to_string(),
+        );
+    };
+    if hw.gpus.is_empty() {
+        return Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            "No GPU found; models run on the CPU".to_string(),
+        );
+    }
+    let names = hw
+        .gpus
+        .iter()
+        .map(|g| match &g.driver_version {
+            Some(v) => format!("{} (driver {})", g.name, v),
+            None => g.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let vendor = |v: &str| hw.gpus.iter().any(|g| g.vendor == v);
+    if vendor("nvidia") && !hw.cuda {
+        Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            format!("{}: no working NVIDIA driver", names),
+        )
+        .fix("Install the NVIDIA driver (nvidia-smi should run)")
+    } else if (vendor("amd") || vendor("intel")) && !hw.metal && !hw.cuda && !hw.vulkan {
+        Check::new(
+            ID,
+            LABEL,
+            CheckStatus::Warn,
+            format!("{}: no Vulkan driver, so it can't be used", names),
+        )
+        .fix("Install the GPU vendor's Vulkan driver")
+    } else {
+        Check::new(ID, LABEL, CheckStatus::Pass, names)
+    }
+}
+
+async fn check_keychain() -> Check {
+    const ID: &str = "keychain";
+    const LABEL: &str = "Keychain";
+    let result = tauri::async_runtime::spawn_blocking(crate::secrets::probe)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    match result {
+        Ok(()) => Check::new(ID, LABEL, CheckStatus::Pass, "Available".to_string()),
+        Err(e) => Check::new(ID, LABEL, CheckStatus::Fail, e).fix(if cfg!(target_os = "linux") {
+            "Install and unlock a Secret Service keyring (GNOME Keyring or KWallet)"
+        } else {
+            "Unlock the system keychain and allow LocalBook to use it"
+        }),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn run_doctor(app: AppHandle) -> Result<DoctorReport, String> {
+    let healthy = crate::check_health().await.unwrap_or(false);
+    let checks = vec![
+        check_sidecar(&app),
+        check_port(healthy),
+        check_data_dir(&app),
+        check_disk(&app),
+        check_health(healthy),
+        check_models(&app).await,
+        check_gpu().await,
+        check_keychain().await,
+    ];
+    let status =
+        checks.iter().map(|c| c.status).fold(
+            CheckStatus::Pass,
+            |worst, s| if s > worst { s } else { worst },
+        );
+    for check in checks.iter().filter(|c| c.status != CheckStatus::Pass) {
+        tracing::info!("[Doctor] {}: {}", check.label, check.message);
+    }
+    Ok(DoctorReport {
+        status,
+        checks,
+        ran_at: crate::models::now_secs(),
+    })
+}
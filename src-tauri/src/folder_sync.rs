@@ -0,0 +1,502 @@
+//! Keeping the library (the backend's data dir) in a cloud-synced folder —
+//! iCloud Drive, Dropbox, OneDrive, Google Drive — so several machines can
+//! open it in turn.
+//!
+//! The sync client copies files, not databases, so two rules keep the data
+//! whole:
+//!
+//! - One machine at a time. Whoever runs the backend on the library keeps a
+//!   lock file in it with its device id and a heartbeat. A machine that finds
+//!   another's fresh heartbeat won't start its backend there; one that finds
+//!   another machine took over meanwhile (both opened it offline) stops its
+//!   backend and says so on `folder-sync://lost`. Quitting marks the lock
+//!   released rather than deleting it, so the next machine knows who wrote last.
+//! - Only start on a settled copy. Before the backend starts, files the sync
+//!   client hasn't downloaded yet (iCloud `.icloud` stubs, dataless or
+//!   recall-on-access placeholders) are fetched, and when another machine
+//!   wrote last, nothing may have changed for `SETTLE` — its writes can still
+//!   be arriving.
+//!
+//! The backend is told to keep SQLite in rollback-journal mode here, since a
+//! WAL file synced apart from its database loses commits. Conflict copies the
+//! sync client made are reported, never merged or deleted.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+pub(crate) const LOCK_FILE: &str = ".localbook-lock.json";
+const HEARTBEAT: Duration = Duration::from_secs(60);
+/// A heartbeat older than this is from a machine that crashed or went offline.
+const STALE_AFTER: u64 = 5 * 60;
+/// How long the library must go unchanged after another machine wrote it.
+const SETTLE: Duration = Duration::from_secs(15);
+const PREPARE_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+const POLL: Duration = Duration::from_secs(3);
+/// Files that mark a folder as a LocalBook library.
+const LIBRARY_MARKERS: &[&str] = &["localbook.db", LOCK_FILE];
+
+static LIBRARY: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// This machine holds the lock on `LIBRARY`.
+static HELD: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Holder {
+    device_id: String,
+    device_name: String,
+    /// Unix seconds.
+    heartbeat_at: u64,
+    released: bool,
+}
+
+impl Holder {
+    fn active(&self) -> bool {
+        !self.released && crate::models::now_secs().saturating_sub(self.heartbeat_at) < STALE_AFTER
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct LibraryStatus {
+    /// None when the library is in this machine's data dir.
+    folder: Option<String>,
+    /// "iCloud Drive", "Dropbox"… from the folder's location.
+    provider: Option<&'static str>,
+    /// This machine's backend is running on the library.
+    open_here: bool,
+    holder: Option<Holder>,
+    /// Files the sync client hasn't downloaded yet.
+    placeholders: usize,
+    /// Conflicting copies the sync client made, relative to the library.
+    conflict_copies: Vec<String>,
+}
+
+#[derive(Default)]
+struct Scan {
+    placeholders: Vec<PathBuf>,
+    conflict_copies: Vec<PathBuf>,
+    newest: Option<SystemTime>,
+}
+
+/// The cloud-synced library folder, when one is set.
+pub(crate) fn library_dir() -> Option<PathBuf> {
+    LIBRARY.lock().ok().and_then(|l| l.clone())
+}
+
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    library_dir()
+        .map(|dir| {
+            vec![
+                ("LOCALBOOK_DATA_DIR", dir.display().to_string()),
+                ("LOCALBOOK_CLOUD_FOLDER", "1".to_string()),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+fn provider(path: &Path) -> Option<&'static str> {
+    let path = path.to_string_lossy();
+    [
+        ("Mobile Documents", "iCloud Drive"),
+        ("iCloud Drive", "iCloud Drive"),
+        ("Dropbox", "Dropbox"),
+        ("OneDrive", "OneDrive"),
+        ("Google Drive", "Google Drive"),
+        ("GoogleDrive", "Google Drive"),
+        ("CloudStorage", "cloud storage"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| path.contains(marker))
+    .map(|(_, name)| name)
+}
+
+/// The real file an iCloud stub (`.name.ext.icloud`) stands for.
+fn icloud_target(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let real = name.strip_prefix('.')?.strip_suffix(".icloud")?;
+    Some(path.with_file_name(real))
+}
+
+/// A file whose content is still in the cloud.
+fn is_placeholder(path: &Path, meta: &std::fs::Metadata) -> bool {
+    if icloud_target(path).is_some() {
+        return true;
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt;
+        const SF_DATALESS: u32 = 0x4000_0000;
+        meta.st_flags() & SF_DATALESS != 0
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const OFFLINE: u32 = 0x1000;
+        const RECALL_ON_OPEN: u32 = 0x4_0000;
+        const RECALL_ON_DATA_ACCESS: u32 = 0x40_0000;
+        meta.file_attributes() & (OFFLINE | RECALL_ON_OPEN | RECALL_ON_DATA_ACCESS) != 0
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let _ = meta;
+        false
+    }
+}
+
+/// Names sync clients give the losing side of a conflict.
+fn is_conflict_copy(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["conflicted copy", "(conflict", "[conflict]", "-conflict-"]
+        .iter()
+        .any(|m| name.contains(m))
+}
+
+fn scan_into(dir: &Path, scan: &mut Scan) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            scan_into(&path, scan);
+            continue;
+        }
+        if path.file_name().is_some_and(|n| n == LOCK_FILE) {
+            continue;
+        }
+        if is_placeholder(&path, &meta) {
+            scan.placeholders.push(path.clone());
+        }
+        if path
+            .file_name()
+            .is_some_and(|n| is_conflict_copy(&n.to_string_lossy()))
+        {
+            scan.conflict_copies.push(path.clone());
+        }
+        if let Ok(modified) = meta.modified() {
+            scan.newest = scan.newest.max(Some(modified));
+        }
+    }
+}
+
+fn scan(dir: &Path) -> Scan {
+    let mut scan = Scan::default();
+    scan_into(dir, &mut scan);
+    scan
+}
+
+/// Ask the sync client for the placeholders' content. Reading a byte is what
+/// makes a File Provider or cloud-files placeholder download; iCloud stubs
+/// need `brctl`. Runs detached since a read can wait on the network.
+fn request_download(placeholders: Vec<PathBuf>) {
+    std::thread::spawn(move || {
+        for path in placeholders {
+            if let Some(real) = icloud_target(&path) {
+                #[cfg(target_os = "macos")]
+                {
+                    let _ = std::process::Command::new("brctl")
+                        .arg("download")
+                        .arg(&real)
+                        .status();
+                }
+                #[cfg(not(target_os = "macos"))]
+                let _ = real;
+                continue;
+            }
+            if let Ok(mut file) = std::fs::File::open(&path) {
+                use std::io::Read;
+                let _ = file.read(&mut [0u8; 1]);
+            }
+        }
+    });
+}
+
+fn read_holder(dir: &Path) -> Option<Holder> {
+    let raw = std::fs::read(dir.join(LOCK_FILE)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+/// Replaces the lock in one rename, so the sync client never uploads half.
+fn write_holder(dir: &Path, holder: &Holder) -> Result<(), String> {
+    let path = dir.join(LOCK_FILE);
+    let tmp = dir.join(format!("{}.tmp", LOCK_FILE));
+    let bytes = serde_json::to_vec_pretty(holder).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, bytes)
+        .and_then(|_| std::fs::rename(&tmp, &path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn own_holder(app: &AppHandle, released: bool) -> Holder {
+    Holder {
+        device_id: crate::sync::device_id(app),
+        device_name: crate::sync::device_name(app),
+        heartbeat_at: crate::models::now_secs(),
+        released,
+    }
+}
+
+fn blocked_by(holder: &Holder) -> String {
+    format!(
+        "The library is open on {}; quit LocalBook there first",
+        holder.device_name
+    )
+}
+
+/// Load the library folder from settings and keep the heartbeat going while
+/// this machine holds the library.
+pub(crate) fn start(app: &AppHandle) {
+    // A library given with --data-dir takes its place for this run.
+    let folder = crate::settings::get(app)
+        .sync
+        .library_folder
+        .filter(|_| crate::cli::options().data_dir.is_none());
+    if let Ok(mut library) = LIBRARY.lock() {
+        *library = folder.map(PathBuf::from);
+    }
+    if let Some(dir) = library_dir() {
+        tracing::info!(
+            "[FolderSync] Library in {} ({})",
+            dir.display(),
+            provider(&dir).unwrap_or("folder")
+        );
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(HEARTBEAT).await;
+            if HELD.load(Ordering::SeqCst) {
+                heartbeat(&app).await;
+            }
+        }
+    });
+}
+
+async fn heartbeat(app: &AppHandle) {
+    let Some(dir) = library_dir() else {
+        return;
+    };
+    let own = own_holder(app, false);
+    let result = tauri::async_runtime::spawn_blocking(move || match read_holder(&dir) {
+        Some(other) if other.device_id != own.device_id && other.active() => Err(other),
+        _ => Ok(write_holder(&dir, &own)),
+    })
+    .await;
+    match result {
+        Ok(Ok(Ok(()))) => {}
+        Ok(Ok(Err(e))) => tracing::warn!("[FolderSync] Heartbeat failed: {}", e),
+        Ok(Err(other)) => lost(app, &other),
+        Err(e) => tracing::warn!("[FolderSync] Heartbeat failed: {}", e),
+    }
+}
+
+/// Another machine holds the library now: stop writing to it.
+fn lost(app: &AppHandle, other: &Holder) {
+    HELD.store(false, Ordering::SeqCst);
+    tracing::warn!(
+        "[FolderSync] {} took over the library; stopping the backend",
+        other.device_name
+    );
+    crate::audit::record("library_taken_over", &other.device_name);
+    crate::stop_backend();
+    let _ = app.emit("folder-sync://lost", other.clone());
+}
+
+/// Called before each backend start: wait for the library to be fully
+/// downloaded and settled, then take the lock. Errors when another machine
+/// has it open or it doesn't settle in time.
+pub(crate) async fn prepare(app: &AppHandle) -> Result<(), String> {
+    let Some(dir) = library_dir() else {
+        return Ok(());
+    };
+    if !dir.is_dir() {
+        return Err(format!(
+            "Library folder {} is not available — is it still synced to this computer?",
+            dir.display()
+        ));
+    }
+    let own = own_holder(app, false);
+    let deadline = Instant::now() + PREPARE_TIMEOUT;
+    let mut requested = false;
+    let conflict_copies = loop {
+        let at = dir.clone();
+        let (holder, found) =
+            tauri::async_runtime::spawn_blocking(move || (read_holder(&at), scan(&at)))
+                .await
+                .map_err(|e| e.to_string())?;
+        let last_writer = holder.filter(|h| h.device_id != own.device_id);
+        if let Some(other) = last_writer.as_ref().filter(|h| h.active()) {
+            return Err(blocked_by(other));
+        }
+        let settled = last_writer.is_none()
+            || found
+                .newest
+                .is_none_or(|t| t.elapsed().unwrap_or_default() >= SETTLE);
+        if found.placeholders.is_empty() && settled {
+            break found.conflict_copies;
+        }
+        if Instant::now() >= deadline {
+            return Err(if found.placeholders.is_empty() {
+                "The library is still being updated from another machine; try again once it has synced".to_string()
+            } else {
+                format!(
+                    "{} library files are still downloading; try again once they have synced",
+                    found.placeholders.len()
+                )
+            });
+        }
+        if !found.placeholders.is_empty() && !requested {
+            tracing::info!(
+                "[FolderSync] Waiting for {} files to download",
+                found.placeholders.len()
+            );
+            request_download(found.placeholders);
+            requested = true;
+        }
+        tokio::time::sleep(POLL).await;
+    };
+    for copy in &conflict_copies {
+        tracing::warn!(
+            "[FolderSync] Conflict copy left by the sync client: {}",
+            copy.display()
+        );
+    }
+    let at = dir.clone();
+    tauri::async_runtime::spawn_blocking(move || write_holder(&at, &own))
+        .await
+        .map_err(|e| e.to_string())??;
+    HELD.store(true, Ordering::SeqCst);
+    tracing::info!("[FolderSync] Holding the library in {}", dir.display());
+    Ok(())
+}
+
+/// Mark the lock released, once the backend has stopped.
+pub(crate) fn release(app: &AppHandle) {
+    if !HELD.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let Some(dir) = library_dir() else {
+        return;
+    };
+    if let Err(e) = write_holder(&dir, &own_holder(app, true)) {
+        tracing::warn!("[FolderSync] Failed to release the library: {}", e);
+    }
+}
+
+fn is_library(dir: &Path) -> bool {
+    LIBRARY_MARKERS.iter().any(|m| dir.join(m).exists())
+}
+
+fn is_empty(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .all(|e| e.file_name() == ".DS_Store" || e.file_name() == "desktop.ini")
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn get_library_folder() -> Result<LibraryStatus, String> {
+    let folder = library_dir();
+    let dir = crate::backend_data_dir();
+    let at = dir.clone();
+    let in_folder = folder.is_some();
+    let (holder, found) = tauri::async_runtime::spawn_blocking(move || {
+        (read_holder(&at), in_folder.then(|| scan(&at)))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let found = found.unwrap_or_default();
+    Ok(LibraryStatus {
+        provider: folder.as_deref().and_then(provider),
+        folder: folder.map(|d| d.display().to_string()),
+        open_here: HELD.load(Ordering::SeqCst),
+        holder,
+        placeholders: found.placeholders.len(),
+        conflict_copies: found
+            .conflict_copies
+            .iter()
+            .map(|p| p.strip_prefix(&dir).unwrap_or(p).display().to_string())
+            .collect(),
+    })
+}
+
+/// Move the library into a cloud-synced folder, or back to this machine
+/// with `None`. An empty folder gets a copy of the current library; a folder
+/// that already holds one (synced from another machine) is used as it is.
+/// Going back uses this machine's earlier library, or a copy of the folder's
+/// when there's none. The backend restarts on the new location.
+#[tauri::command]
+pub(crate) async fn set_library_folder(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<LibraryStatus, String> {
+    if crate::cli::options().data_dir.is_some() {
+        return Err("LocalBook was started with --data-dir; restart without it first".to_string());
+    }
+    let target = match &path {
+        Some(p) => {
+            let dir = crate::scope::check(&app, Path::new(p))?;
+            if !dir.is_dir() {
+                return Err(format!("{} is not a folder", dir.display()));
+            }
+            dir
+        }
+        None => crate::default_backend_data_dir(),
+    };
+    let current = crate::backend_data_dir();
+    if target == current {
+        return get_library_folder().await;
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err("The library can't be moved into itself".to_string());
+    }
+    let own_id = crate::sync::device_id(&app);
+    if let Some(other) = read_holder(&target).filter(|h| h.device_id != own_id && h.active()) {
+        return Err(blocked_by(&other));
+    }
+    let adopt = !target.exists() || is_empty(&target);
+    if !adopt && path.is_some() && !is_library(&target) {
+        return Err(
+            "Choose an empty folder or one that already holds a LocalBook library".to_string(),
+        );
+    }
+
+    crate::stop_backend();
+    release(&app);
+    if adopt && current.is_dir() {
+        tracing::info!(
+            "[FolderSync] Copying the library from {} to {}",
+            current.display(),
+            target.display()
+        );
+        let (from, to) = (current.clone(), target.clone());
+        let copied = tauri::async_runtime::spawn_blocking(move || {
+            crate::backup::copy(&from, &to, |_, _| Ok(()))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        if let Err(e) = copied {
+            crate::restart_backend_and_wait(&app, "library move failed").await?;
+            return Err(e);
+        }
+    }
+    crate::settings::update(&app, |s| {
+        s.sync.library_folder = path.as_ref().map(|_| target.display().to_string());
+    })?;
+    if let Ok(mut library) = LIBRARY.lock() {
+        *library = path.as_ref().map(|_| target.clone());
+    }
+    crate::audit::record(
+        "library_folder_changed",
+        format!("{} -> {}", current.display(), target.display()),
+    );
+    crate::restart_backend_and_wait(&app, "library folder changed").await?;
+    get_library_folder().await
+}
@@ -0,0 +1,130 @@
+//! Handing the open chat to another device: `handoff_session` sends the
+//! current notebook and chat state to a paired device over the LAN sync
+//! channel, and that device opens the same notebook with the chat as it was.
+//!
+//! The session is whatever the chat view needs to pick up again (messages,
+//! the unsent draft, its own extra state); the shell only carries it. The
+//! notebook has to be synced between the two devices already — each side
+//! knows the other's copy by the ids recorded when it was taken (see `sync`).
+//! A handoff that arrives is kept until the webview takes it, and announced
+//! as `handoff://received`.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::sync::{self, SyncMode};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct HandoffSession {
+    /// Backend notebook id on the sending device.
+    pub notebook_id: String,
+    /// The receiver's own id for the notebook, when the sender's is a copy
+    /// taken from it. Filled in by the shell.
+    #[serde(default)]
+    pub origin_id: Option<String>,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    /// The chat so far, as the webview keeps it.
+    #[serde(default)]
+    pub messages: Vec<serde_json::Value>,
+    /// Text typed but not sent yet.
+    #[serde(default)]
+    pub draft: Option<String>,
+    /// Anything else the chat view wants back (selected sources, scroll…).
+    #[serde(default)]
+    pub context: serde_json::Value,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Handoff {
+    from_device: String,
+    from_name: String,
+    /// This device's copy of the notebook.
+    notebook_id: String,
+    session: HandoffSession,
+    /// Unix seconds.
+    received_at: u64,
+}
+
+static PENDING: Mutex<Option<Handoff>> = Mutex::new(None);
+
+/// This device's id for a notebook `peer` knows as `remote_id`: the copy
+/// taken from it, or the original it took a copy of.
+fn local_notebook(app: &AppHandle, peer: &str, remote_id: &str) -> Option<String> {
+    crate::settings::get(app)
+        .sync
+        .received
+        .into_values()
+        .find(|r| r.peer == peer && r.remote_id == remote_id)
+        .map(|r| r.local_id)
+}
+
+/// Called by `lan_sync` with a session a paired device sent. Errors go back
+/// to the sender.
+pub(crate) fn receive(app: &AppHandle, peer: &str, session: HandoffSession) -> Result<(), String> {
+    let notebook_id = session
+        .origin_id
+        .clone()
+        .filter(|id| sync::is_shared(app, id))
+        .or_else(|| local_notebook(app, peer, &session.notebook_id))
+        .ok_or("That notebook hasn't synced to the other device yet")?;
+    let from_name = sync::peer(app, peer).map_or_else(|| peer.to_string(), |p| p.name);
+    let handoff = Handoff {
+        from_device: peer.to_string(),
+        from_name,
+        notebook_id,
+        session,
+        received_at: crate::models::now_secs(),
+    };
+    tracing::info!(
+        "[Handoff] Chat in {} from {}",
+        handoff.notebook_id,
+        handoff.from_name
+    );
+    crate::audit::record(
+        "handoff_received",
+        format!("{} from {}", handoff.notebook_id, handoff.from_name),
+    );
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = Some(handoff.clone());
+    }
+    crate::tray::show_main(app);
+    let _ = app.emit("handoff://received", handoff);
+    Ok(())
+}
+
+/// Continue the current chat on a paired device on the network.
+#[tauri::command]
+pub(crate) async fn handoff_session(
+    app: AppHandle,
+    device_id: String,
+    mut session: HandoffSession,
+) -> Result<(), String> {
+    if sync::peer(&app, &device_id).is_none() {
+        return Err("Pair with this device first".to_string());
+    }
+    let settings = crate::settings::get(&app).sync;
+    session.origin_id = settings
+        .received
+        .values()
+        .find(|r| r.peer == device_id && r.local_id == session.notebook_id && !r.mirror)
+        .map(|r| r.remote_id.clone());
+    if session.origin_id.is_none()
+        && sync::mode(&settings, &session.notebook_id) == SyncMode::LocalOnly
+    {
+        return Err("Sync this notebook with that device before handing off".to_string());
+    }
+    let notebook_id = session.notebook_id.clone();
+    crate::lan_sync::send_handoff(&app, &device_id, session).await?;
+    tracing::info!("[Handoff] Sent chat in {} to {}", notebook_id, device_id);
+    crate::audit::record("handoff_sent", format!("{} to {}", notebook_id, device_id));
+    Ok(())
+}
+
+/// The handoff waiting to be opened, if any; it's cleared once taken.
+#[tauri::command]
+pub(crate) async fn take_handoff() -> Result<Option<Handoff>, String> {
+    Ok(PENDING.lock().ok().and_then(|mut p| p.take()))
+}
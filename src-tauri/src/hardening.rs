@@ -0,0 +1,188 @@
+//! Production hardening for the webviews.
+//!
+//! Release builds run hardened unless they were built with the `devtools`
+//! cargo feature (for debugging a packaged app); `tauri dev` and other debug
+//! builds never are. Hardened means:
+//!
+//! - no devtools — the tauri `devtools` feature is only enabled by our own
+//!   `devtools` feature, so release binaries don't contain them at all;
+//! - no right-click menu outside text fields, so there's no "Inspect Element"
+//!   or "Reload" to reach;
+//! - webviews can't navigate away from the bundled app: other http(s) links
+//!   open in the default browser instead, anything else is refused;
+//! - the configured CSP is tightened (`'unsafe-eval'` dropped, no framing,
+//!   no `<base>`, plugins or form posts);
+//! - IPC is only accepted from our own windows showing the bundled app.
+//!
+//! In every build, hardened or not, the lock screen and the splash window
+//! may only call the handful of commands they need, and while the app is
+//! locked no other window may call anything — the app lock relies on that.
+//! (These checks cover the app's commands; plugin commands are limited per
+//! window by the capability files.)
+
+use std::collections::HashMap;
+
+use tauri::ipc::Invoke;
+use tauri::plugin::TauriPlugin;
+use tauri::utils::config::{Csp, CspDirectiveSources};
+use tauri::{Context, Runtime, Url, Webview};
+
+/// Windows the app creates (mirrors the capability file).
+const WINDOW_LABELS: &[&str] = &["main", "mini", "lock", "splash"];
+const WINDOW_LABEL_PREFIXES: &[&str] = &["notebook-"];
+
+/// All the lock screen needs; everything else waits until it's unlocked.
+const LOCK_COMMANDS: &[&str] = &[
+    "get_lock_state",
+    "unlock_app",
+    "unlock_with_biometrics",
+    "recover_app_lock",
+    "get_system_theme",
+    "get_appearance",
+    "get_system_locale",
+    "get_translations",
+];
+
+/// The splash window only reads the startup status.
+const SPLASH_COMMANDS: &[&str] = &["get_backend_status"];
+
+/// Keep the context menu for editing text, nothing else.
+const NO_CONTEXT_MENU_JS: &str = r#"
+document.addEventListener('contextmenu', (e) => {
+  const t = e.target;
+  if (t instanceof Element && t.closest('input, textarea, [contenteditable="true"]')) return;
+  e.preventDefault();
+}, { capture: true });
+"#;
+
+pub(crate) fn enabled() -> bool {
+    !cfg!(debug_assertions) && !cfg!(feature = "devtools")
+}
+
+/// The bundled frontend: `tauri://localhost` on macOS/Linux,
+/// `http(s)://tauri.localhost` on Windows, plus the asset and IPC protocols.
+fn is_app_url(url: &Url) -> bool {
+    match url.scheme() {
+        "tauri" | "asset" | "ipc" | "about" | "blob" => true,
+        "http" | "https" => matches!(
+            url.host_str(),
+            Some("tauri.localhost" | "asset.localhost" | "ipc.localhost")
+        ),
+        _ => false,
+    }
+}
+
+fn on_navigation<R: Runtime>(webview: &Webview<R>, url: &Url) -> bool {
+    if !enabled() || is_app_url(url) {
+        return true;
+    }
+    if matches!(url.scheme(), "http" | "https") {
+        tracing::info!("[Hardening] Opening {} in the browser", url);
+        if let Err(e) = tauri_plugin_opener::open_url(url.as_str(), None::<&str>) {
+            tracing::warn!("[Hardening] Failed to open {}: {}", url, e);
+        }
+    } else {
+        tracing::warn!(
+            "[Hardening] Blocked navigation of {} to {}",
+            webview.label(),
+            url
+        );
+    }
+    false
+}
+
+/// Context-menu blocking and the navigation guard, for every webview
+/// including the main window from the config.
+pub(crate) fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    let builder = tauri::plugin::Builder::new("hardening").on_navigation(on_navigation);
+    if enabled() {
+        builder.js_init_script(NO_CONTEXT_MENU_JS).build()
+    } else {
+        builder.build()
+    }
+}
+
+/// Tighten the configured CSP before the app is built.
+pub(crate) fn apply_csp<R: Runtime>(context: &mut Context<R>) {
+    if !enabled() {
+        return;
+    }
+    let security = &mut context.config_mut().app.security;
+    let mut directives: HashMap<String, CspDirectiveSources> =
+        security.csp.take().map(Into::into).unwrap_or_default();
+    directives.retain(|name, _| !name.is_empty());
+    for sources in directives.values_mut() {
+        let list: Vec<String> = std::mem::take(sources).into();
+        *sources = CspDirectiveSources::List(
+            list.into_iter()
+                .filter(|s| !s.is_empty() && s != "'unsafe-eval'")
+                .collect(),
+        );
+    }
+    directives
+        .entry("default-src".to_string())
+        .or_insert_with(|| CspDirectiveSources::List(vec!["'self'".to_string()]));
+    for name in ["object-src", "base-uri", "form-action", "frame-ancestors"] {
+        directives.insert(
+            name.to_string(),
+            CspDirectiveSources::List(vec!["'none'".to_string()]),
+        );
+    }
+    security.csp = Some(Csp::DirectiveMap(directives));
+    security.dangerous_disable_asset_csp_modification = Default::default();
+}
+
+fn check_invoke<R: Runtime>(invoke: &Invoke<R>) -> Result<(), String> {
+    let webview = invoke.message.webview();
+    let label = webview.label();
+    let command = invoke.message.command();
+    let known = WINDOW_LABELS.contains(&label)
+        || WINDOW_LABEL_PREFIXES.iter().any(|p| label.starts_with(p));
+    if !known {
+        return Err(format!("{} is not allowed from window {}", command, label));
+    }
+    if !webview.url().is_ok_and(|u| is_app_url(&u)) {
+        return Err(format!("{} is only allowed from the app itself", command));
+    }
+    Ok(())
+}
+
+/// The commands the lock screen and the splash window are limited to; while
+/// locked, other windows get none.
+fn check_window_commands(label: &str, command: &str) -> Result<(), String> {
+    if crate::lock::is_locked() && label != crate::lock::LOCK_WINDOW_LABEL {
+        return Err(format!(
+            "{} is not allowed while the app is locked",
+            command
+        ));
+    }
+    if label == crate::lock::LOCK_WINDOW_LABEL && !LOCK_COMMANDS.contains(&command) {
+        return Err(format!("{} is not allowed from the lock screen", command));
+    }
+    if label == crate::splash::SPLASH_WINDOW_LABEL && !SPLASH_COMMANDS.contains(&command) {
+        return Err(format!("{} is not allowed from the splash window", command));
+    }
+    Ok(())
+}
+
+/// Wrap the command handler so every call is checked first: the lock and
+/// splash allowlists always, the rest in hardened builds.
+pub(crate) fn guard_ipc<R: Runtime>(
+    commands: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let webview = invoke.message.webview();
+        let mut checked = check_window_commands(webview.label(), invoke.message.command());
+        if checked.is_ok() && enabled() {
+            checked = check_invoke(&invoke);
+        }
+        match checked {
+            Ok(()) => commands(invoke),
+            Err(e) => {
+                tracing::warn!("[Hardening] {}", e);
+                invoke.resolver.reject(e);
+                true
+            }
+        }
+    }
+}
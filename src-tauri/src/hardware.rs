@@ -0,0 +1,523 @@
+//! Hardware report — GPUs, VRAM, compute backends, CPU features and RAM.
+//!
+//! Used to recommend model sizes in the picker and to pick launch flags for the
+//! inference engines. Detection shells out to the vendor tools that are already
+//! on the machine (system_profiler, nvidia-smi, CIM, lspci/sysfs) and takes a
+//! second or two, so the result is cached for the life of the process; pass
+//! `refresh` to re-probe after a driver install or an eGPU is plugged in.
+//!
+//! On first run the report is turned into a `BackendTuning` (GPU offload,
+//! threads, quantization, Ollama memory knobs) that is saved in settings and
+//! applied to the Ollama and backend launch environment from then on, instead
+//! of one conservative default for every machine. `rerun_hardware_tuning`
+//! recomputes it.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+static CACHED: Mutex<Option<HardwareInfo>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct GpuInfo {
+    pub(crate) name: String,
+    /// "nvidia", "amd", "intel", "apple" or "unknown".
+    pub(crate) vendor: String,
+    /// Dedicated VRAM; None when the platform doesn't report it.
+    vram_bytes: Option<u64>,
+    pub(crate) driver_version: Option<String>,
+    /// Shares system RAM (Apple Silicon, most integrated GPUs).
+    unified_memory: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct CpuInfo {
+    brand: String,
+    arch: String,
+    physical_cores: Option<usize>,
+    logical_cores: usize,
+    /// SIMD extensions relevant to CPU inference, e.g. "avx2", "avx512f", "neon".
+    features: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct HardwareInfo {
+    cpu: CpuInfo,
+    pub(crate) total_ram_bytes: u64,
+    pub(crate) gpus: Vec<GpuInfo>,
+    pub(crate) cuda: bool,
+    pub(crate) metal: bool,
+    pub(crate) vulkan: bool,
+    /// Largest model file that should comfortably fit in memory the selected
+    /// backend can use, leaving room for the KV cache and the rest of the app.
+    pub(crate) recommended_max_model_bytes: u64,
+}
+
+fn cpu_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = Vec::new();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx") {
+            features.push("avx");
+        }
+        if is_x86_feature_detected!("avx2") {
+            features.push("avx2");
+        }
+        if is_x86_feature_detected!("avx512f") {
+            features.push("avx512f");
+        }
+        if is_x86_feature_detected!("fma") {
+            features.push("fma");
+        }
+        if is_x86_feature_detected!("f16c") {
+            features.push("f16c");
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            features.push("neon");
+        }
+        if std::arch::is_aarch64_feature_detected!("dotprod") {
+            features.push("dotprod");
+        }
+    }
+    features.into_iter().map(String::from).collect()
+}
+
+fn vendor_of(name: &str) -> &'static str {
+    let n = name.to_ascii_lowercase();
+    if n.contains("nvidia") || n.contains("geforce") || n.contains("quadro") || n.contains("rtx") {
+        "nvidia"
+    } else if n.contains("amd") || n.contains("radeon") || n.contains("ati ") {
+        "amd"
+    } else if n.contains("intel") {
+        "intel"
+    } else if n.contains("apple") {
+        "apple"
+    } else {
+        "unknown"
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+/// NVIDIA GPUs via nvidia-smi; its presence with a working driver is also our
+/// CUDA signal.
+fn nvidia_gpus() -> Vec<GpuInfo> {
+    // "NVIDIA GeForce RTX 4090, 24564, 550.54.14"
+    run(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,memory.total,driver_version",
+            "--format=csv,noheader,nounits",
+        ],
+    )
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|l| {
+        let mut cols = l.split(',').map(str::trim);
+        let name = cols.next().filter(|n| !n.is_empty())?.to_string();
+        let vram_mib: Option<u64> = cols.next().and_then(|m| m.parse().ok());
+        Some(GpuInfo {
+            name,
+            vendor: "nvidia".to_string(),
+            vram_bytes: vram_mib.map(|m| m * 1024 * 1024),
+            driver_version: cols.next().map(String::from),
+            unified_memory: false,
+        })
+    })
+    .collect()
+}
+
+/// "8 GB" / "1536 MB" → bytes.
+#[cfg(target_os = "macos")]
+fn parse_size(s: &str) -> Option<u64> {
+    let mut parts = s.split_whitespace();
+    let n: u64 = parts.next()?.parse().ok()?;
+    match parts.next()? {
+        "GB" => Some(n * 1024 * 1024 * 1024),
+        "MB" => Some(n * 1024 * 1024),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let Some(out) = run("system_profiler", &["SPDisplaysDataType", "-json"]) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&out) else {
+        return Vec::new();
+    };
+    json["SPDisplaysDataType"]
+        .as_array()
+        .map(|gpus| {
+            gpus.iter()
+                .filter_map(|g| {
+                    let name = g["sppci_model"].as_str()?.to_string();
+                    // Discrete cards report "spdisplays_vram"; Intel integrated
+                    // reports "spdisplays_vram_shared"; Apple Silicon reports neither.
+                    let dedicated = g["spdisplays_vram"].as_str().and_then(parse_size);
+                    let vendor = vendor_of(&name);
+                    Some(GpuInfo {
+                        unified_memory: dedicated.is_none(),
+                        vram_bytes: dedicated,
+                        vendor: vendor.to_string(),
+                        driver_version: None,
+                        name,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let Some(out) = run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | Select-Object Name,AdapterRAM,DriverVersion | ConvertTo-Json",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    // A single adapter serialises as an object, several as an array.
+    let items = match serde_json::from_str::<serde_json::Value>(&out) {
+        Ok(serde_json::Value::Array(a)) => a,
+        Ok(v @ serde_json::Value::Object(_)) => vec![v],
+        _ => return Vec::new(),
+    };
+    items
+        .iter()
+        .filter_map(|g| {
+            let name = g["Name"].as_str()?.to_string();
+            let vendor = vendor_of(&name);
+            // AdapterRAM is a uint32 and saturates at 4 GiB, so it's only a
+            // lower bound for modern cards (nvidia-smi gives the real figure).
+            let vram = g["AdapterRAM"].as_u64().filter(|&b| b > 0);
+            Some(GpuInfo {
+                unified_memory: vendor == "intel",
+                vram_bytes: vram,
+                vendor: vendor.to_string(),
+                driver_version: g["DriverVersion"].as_str().map(String::from),
+                name,
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    // "01:00.0 VGA compatible controller: Advanced Micro Devices, Inc. [AMD/ATI] Navi 21 …"
+    let mut gpus: Vec<GpuInfo> = run("lspci", &[])
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| {
+            l.contains("VGA compatible controller")
+                || l.contains("3D controller")
+                || l.contains("Display controller")
+        })
+        .filter_map(|l| {
+            let name = l.split_once(": ")?.1.trim().to_string();
+            let vendor = vendor_of(&name);
+            Some(GpuInfo {
+                unified_memory: vendor == "intel",
+                vram_bytes: None,
+                vendor: vendor.to_string(),
+                driver_version: None,
+                name,
+            })
+        })
+        .collect();
+
+    // amdgpu exposes VRAM size in sysfs; attach it to AMD entries in order.
+    let mut amd_vram: Vec<u64> = std::fs::read_dir("/sys/class/drm")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    std::fs::read_to_string(e.path().join("device/mem_info_vram_total"))
+                        .ok()?
+                        .trim()
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    amd_vram.reverse();
+    for gpu in gpus.iter_mut().filter(|g| g.vendor == "amd") {
+        gpu.vram_bytes = amd_vram.pop();
+    }
+    gpus
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn platform_gpus() -> Vec<GpuInfo> {
+    Vec::new()
+}
+
+/// Vulkan loader present — enough for llama.cpp's Vulkan backend to try.
+fn vulkan_available() -> bool {
+    #[cfg(target_os = "windows")]
+    let candidates: &[&str] = &["C:\\Windows\\System32\\vulkan-1.dll"];
+    #[cfg(target_os = "macos")]
+    let candidates: &[&str] = &[
+        "/opt/homebrew/lib/libvulkan.1.dylib",
+        "/usr/local/lib/libvulkan.1.dylib",
+    ];
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let candidates: &[&str] = &[
+        "/usr/lib/x86_64-linux-gnu/libvulkan.so.1",
+        "/usr/lib/aarch64-linux-gnu/libvulkan.so.1",
+        "/usr/lib64/libvulkan.so.1",
+        "/usr/lib/libvulkan.so.1",
+    ];
+    candidates.iter().any(|p| std::path::Path::new(p).exists())
+}
+
+fn detect() -> HardwareInfo {
+    use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+    let sys = System::new_with_specifics(
+        RefreshKind::nothing()
+            .with_memory(MemoryRefreshKind::nothing().with_ram())
+            .with_cpu(CpuRefreshKind::nothing()),
+    );
+    let total_ram_bytes = sys.total_memory();
+    let cpu = CpuInfo {
+        brand: sys
+            .cpus()
+            .first()
+            .map(|c| c.brand().trim().to_string())
+            .unwrap_or_default(),
+        arch: std::env::consts::ARCH.to_string(),
+        physical_cores: System::physical_core_count(),
+        logical_cores: sys.cpus().len(),
+        features: cpu_features(),
+    };
+
+    // nvidia-smi has the accurate VRAM figure; drop the platform duplicates.
+    let nvidia = nvidia_gpus();
+    let cuda = !nvidia.is_empty();
+    let mut gpus = nvidia;
+    gpus.extend(
+        platform_gpus()
+            .into_iter()
+            .filter(|g| !(cuda && g.vendor == "nvidia")),
+    );
+
+    let metal = cfg!(target_os = "macos");
+    let vulkan = vulkan_available();
+
+    // Memory the inference backend can actually use: the biggest dedicated
+    // card when there's a usable GPU backend, otherwise (or with unified
+    // memory) a share of system RAM.
+    let best_vram = gpus
+        .iter()
+        .filter(|g| !g.unified_memory && (cuda || vulkan || metal))
+        .filter_map(|g| g.vram_bytes)
+        .max();
+    let recommended_max_model_bytes = match best_vram {
+        Some(vram) => vram * 80 / 100,
+        None if metal => total_ram_bytes * 65 / 100,
+        None => total_ram_bytes / 2,
+    };
+
+    HardwareInfo {
+        cpu,
+        total_ram_bytes,
+        gpus,
+        cuda,
+        metal,
+        vulkan,
+        recommended_max_model_bytes,
+    }
+}
+
+/// Cached hardware report; `refresh` re-probes. Blocking.
+pub(crate) fn info(refresh: bool) -> HardwareInfo {
+    let mut cached = CACHED.lock().unwrap_or_else(|e| e.into_inner());
+    if refresh || cached.is_none() {
+        let hw = detect();
+        tracing::info!(
+            "[Hardware] {} ({} cores), {} MB RAM, {} GPU(s), cuda={} metal={} vulkan={}",
+            hw.cpu.brand,
+            hw.cpu.logical_cores,
+            hw.total_ram_bytes / (1024 * 1024),
+            hw.gpus.len(),
+            hw.cuda,
+            hw.metal,
+            hw.vulkan
+        );
+        *cached = Some(hw);
+    }
+    cached.clone().unwrap()
+}
+
+/// Launch parameters derived from the hardware report. Stored in settings so
+/// they're stable across runs (and hand-editable) until re-tuned.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct BackendTuning {
+    /// Layers to offload to the GPU: -1 = all, 0 = CPU only.
+    pub gpu_layers: i32,
+    /// No GPU was found at all, so Ollama is told not to look for one.
+    /// A GPU that's too small to offload to is left to Ollama, which may
+    /// still use it (e.g. ROCm, which isn't detected here).
+    #[serde(default)]
+    pub cpu_only: bool,
+    /// CPU threads for inference.
+    pub threads: usize,
+    /// Preferred GGUF quantization when choosing what to download.
+    pub quantization: String,
+    /// Ollama KV cache type ("q8_0" halves context memory vs "f16").
+    pub kv_cache_type: String,
+    pub flash_attention: bool,
+    /// OLLAMA_MAX_LOADED_MODELS.
+    pub max_loaded_models: u32,
+    /// Concurrent main-model requests the backend allows.
+    pub lane_cap: u32,
+    /// Unix seconds.
+    pub tuned_at: u64,
+}
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn tune(hw: &HardwareInfo) -> BackendTuning {
+    let ram_gib = hw.total_ram_bytes / GIB;
+    let usable_gib = hw.recommended_max_model_bytes / GIB;
+
+    // Offload everything when there's a GPU backend with room for at least a
+    // small model; a 2 GB card is slower than the CPU once layers spill.
+    let best_vram = hw
+        .gpus
+        .iter()
+        .filter(|g| !g.unified_memory)
+        .filter_map(|g| g.vram_bytes)
+        .max();
+    let gpu_backend = hw.metal || hw.cuda || (hw.vulkan && best_vram.is_some());
+    let gpu_layers = if gpu_backend && (hw.metal || best_vram.unwrap_or(0) >= 3 * GIB) {
+        -1
+    } else {
+        0
+    };
+
+    // Physical cores, keeping one free for the UI on bigger machines.
+    let cores = hw
+        .cpu
+        .physical_cores
+        .unwrap_or((hw.cpu.logical_cores / 2).max(1));
+    let threads = if cores > 4 { cores - 1 } else { cores };
+
+    let quantization = match usable_gib {
+        0..=9 => "q4_k_m",
+        10..=19 => "q5_k_m",
+        20..=39 => "q6_k",
+        _ => "q8_0",
+    };
+
+    BackendTuning {
+        gpu_layers,
+        cpu_only: hw.gpus.is_empty(),
+        threads,
+        quantization: quantization.to_string(),
+        kv_cache_type: if ram_gib < 32 { "q8_0" } else { "f16" }.to_string(),
+        flash_attention: gpu_layers != 0,
+        max_loaded_models: match ram_gib {
+            0..=15 => 1,
+            16..=47 => 2,
+            _ => 3,
+        },
+        lane_cap: match ram_gib {
+            0..=23 => 1,
+            24..=63 => 2,
+            _ => 3,
+        },
+        tuned_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    }
+}
+
+fn retune(app: &AppHandle, refresh: bool) -> Result<BackendTuning, String> {
+    let tuning = tune(&info(refresh));
+    tracing::info!(
+        "[Hardware] Tuned: gpu_layers={} threads={} quant={} kv={} max_loaded={} lanes={}",
+        tuning.gpu_layers,
+        tuning.threads,
+        tuning.quantization,
+        tuning.kv_cache_type,
+        tuning.max_loaded_models,
+        tuning.lane_cap
+    );
+    let saved = tuning.clone();
+    crate::settings::update(app, move |s| s.backend_tuning = Some(saved))?;
+    Ok(tuning)
+}
+
+/// The saved tuning, computing it on first run. Blocking the first time
+/// (hardware detection), cheap afterwards.
+pub(crate) fn tuning(app: &AppHandle) -> BackendTuning {
+    if let Some(t) = crate::settings::get(app).backend_tuning {
+        return t;
+    }
+    retune(app, false).unwrap_or_else(|e| {
+        tracing::warn!("[Hardware] Could not save tuning: {}", e);
+        tune(&info(false))
+    })
+}
+
+/// Environment for `ollama serve`.
+pub(crate) fn ollama_env(t: &BackendTuning) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("OLLAMA_MAX_LOADED_MODELS", t.max_loaded_models.to_string()),
+        (
+            "OLLAMA_FLASH_ATTENTION",
+            if t.flash_attention { "1" } else { "0" }.to_string(),
+        ),
+        ("OLLAMA_KV_CACHE_TYPE", t.kv_cache_type.clone()),
+    ];
+    if t.cpu_only {
+        env.push(("OLLAMA_LLM_LIBRARY", "cpu".to_string()));
+    }
+    env
+}
+
+/// Environment for the Python backend.
+pub(crate) fn backend_env(t: &BackendTuning) -> Vec<(&'static str, String)> {
+    vec![
+        ("LOCALBOOK_GEMMA_LANE_CAP", t.lane_cap.to_string()),
+        // numpy / onnxruntime (embeddings, reranker) thread pools
+        ("OMP_NUM_THREADS", t.threads.to_string()),
+    ]
+}
+
+/// Re-probe the hardware and recompute launch parameters. They apply the next
+/// time Ollama and the backend start.
+#[tauri::command]
+pub(crate) async fn rerun_hardware_tuning(app: AppHandle) -> Result<BackendTuning, String> {
+    tauri::async_runtime::spawn_blocking(move || retune(&app, true))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub(crate) async fn get_hardware_info(refresh: Option<bool>) -> Result<HardwareInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || info(refresh.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())
+}
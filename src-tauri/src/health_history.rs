@@ -0,0 +1,119 @@
+//! What the backend's health has been doing this run, for "it keeps
+//! disconnecting" reports.
+//!
+//! The watchdog's liveness checks are kept (the last `KEEP_CHECKS`, about two
+//! hours at one every 15s) with how long each took and why it failed, and so
+//! are changes of state — ready, degraded, crashed, restarting, recovered —
+//! with the reason (the last `KEEP_TRANSITIONS`). `get_health_history`
+//! returns both, newest first, and the diagnostics bundle includes them.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+const KEEP_CHECKS: usize = 480;
+const KEEP_TRANSITIONS: usize = 200;
+
+static HISTORY: Mutex<History> = Mutex::new(History {
+    checks: VecDeque::new(),
+    transitions: VecDeque::new(),
+});
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Check {
+    /// Unix seconds.
+    at: u64,
+    healthy: bool,
+    duration_ms: u64,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Transition {
+    /// Unix seconds.
+    at: u64,
+    from: Option<String>,
+    to: String,
+    reason: String,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct History {
+    checks: VecDeque<Check>,
+    transitions: VecDeque<Transition>,
+}
+
+fn history() -> std::sync::MutexGuard<'static, History> {
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn push<T>(list: &mut VecDeque<T>, keep: usize, item: T) {
+    if list.len() == keep {
+        list.pop_front();
+    }
+    list.push_back(item);
+}
+
+/// One liveness check.
+pub(crate) fn check(healthy: bool, took: Duration, error: Option<String>) {
+    let check = Check {
+        at: crate::models::now_secs(),
+        healthy,
+        duration_ms: took.as_millis() as u64,
+        error: error.map(|e| crate::logging::redact::line(&e)),
+    };
+    push(&mut history().checks, KEEP_CHECKS, check);
+}
+
+/// The backend is now in `state`; ignored if it already was.
+pub(crate) fn transition(state: &str, reason: impl Into<String>) {
+    let mut history = history();
+    let from = history.transitions.back().map(|t| t.to.clone());
+    if from.as_deref() == Some(state) {
+        return;
+    }
+    let transition = Transition {
+        at: crate::models::now_secs(),
+        from,
+        to: state.to_string(),
+        reason: crate::logging::redact::line(&reason.into()),
+    };
+    push(&mut history.transitions, KEEP_TRANSITIONS, transition);
+}
+
+/// Everything, oldest first, for the diagnostics bundle.
+pub(crate) fn snapshot() -> History {
+    history().clone()
+}
+
+/// The latest checks and transitions, newest first; with `since` (Unix
+/// seconds), only those after it.
+#[tauri::command]
+pub(crate) async fn get_health_history(
+    limit: Option<usize>,
+    since: Option<u64>,
+) -> Result<History, String> {
+    let history = history();
+    let since = since.unwrap_or(0);
+    let limit = limit.unwrap_or(usize::MAX);
+    Ok(History {
+        checks: history
+            .checks
+            .iter()
+            .rev()
+            .filter(|c| c.at >= since)
+            .take(limit)
+            .cloned()
+            .collect(),
+        transitions: history
+            .transitions
+            .iter()
+            .rev()
+            .filter(|t| t.at >= since)
+            .take(limit)
+            .cloned()
+            .collect(),
+    })
+}
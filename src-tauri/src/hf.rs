@@ -0,0 +1,254 @@
+//! Hugging Face Hub — model search for the picker and authenticated downloads.
+//!
+//! The access token lives in the OS keychain (never in settings, never sent back
+//! to the webview) and is attached only to requests to huggingface.co, which is
+//! what unlocks gated and private repos. Downloads themselves go through the
+//! model manager; this module supplies the URL resolution (`hf://` shorthand
+//! with optional `@revision` pinning) and the auth header.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+const HF_BASE: &str = "https://huggingface.co";
+const TOKEN_SECRET: &str = "huggingface_token";
+
+#[derive(Deserialize)]
+struct ApiModel {
+    id: String,
+    #[serde(default)]
+    downloads: u64,
+    #[serde(default)]
+    likes: u64,
+    #[serde(default)]
+    pipeline_tag: Option<String>,
+    /// false, "auto" or "manual" in the API.
+    #[serde(default)]
+    gated: serde_json::Value,
+    #[serde(default, rename = "lastModified")]
+    last_modified: Option<String>,
+    #[serde(default)]
+    siblings: Vec<ApiSibling>,
+}
+
+#[derive(Deserialize)]
+struct ApiSibling {
+    rfilename: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct HfModel {
+    id: String,
+    downloads: u64,
+    likes: u64,
+    pipeline_tag: Option<String>,
+    gated: bool,
+    last_modified: Option<String>,
+    /// Downloadable .gguf / .onnx files in the repo.
+    files: Vec<String>,
+}
+
+/// `hf://owner/repo[@revision]/path/in/repo` → resolve URL. The revision (branch,
+/// tag or commit sha) defaults to `main`; pinning a commit sha makes the
+/// download reproducible even if the repo is later updated.
+pub(crate) fn resolve(source: &str) -> Option<Result<String, String>> {
+    let rest = source.strip_prefix("hf://")?;
+    let mut parts = rest.splitn(3, '/');
+    Some(match (parts.next(), parts.next(), parts.next()) {
+        (Some(owner), Some(repo_rev), Some(path)) if !path.is_empty() => {
+            let (repo, revision) = repo_rev.split_once('@').unwrap_or((repo_rev, "main"));
+            Ok(format!(
+                "{}/{}/{}/resolve/{}/{}",
+                HF_BASE, owner, repo, revision, path
+            ))
+        }
+        _ => Err(format!(
+            "Expected hf://<owner>/<repo>[@revision]/<file>, got {}",
+            source
+        )),
+    })
+}
+
+/// "owner/repo" for a resolved Hub file URL.
+pub(crate) fn repo_id(url: &str) -> Option<String> {
+    let rest = url.strip_prefix(HF_BASE)?.strip_prefix('/')?;
+    let mut parts = rest.split('/');
+    let (owner, repo) = (parts.next()?, parts.next()?);
+    (parts.next() == Some("resolve")).then(|| format!("{}/{}", owner, repo))
+}
+
+/// License declared by a Hub repo — the model card's `license` field, falling
+/// back to the `license:` tag. None if the repo doesn't declare one.
+pub(crate) async fn fetch_license(
+    client: &reqwest::Client,
+    repo: &str,
+    token: Option<&str>,
+) -> Option<String> {
+    crate::privacy::guard(HF_BASE, "Model license lookup").ok()?;
+    let mut req = client.get(format!("{}/api/models/{}", HF_BASE, repo));
+    if let Some(t) = token {
+        req = req.bearer_auth(t);
+    }
+    let info: serde_json::Value = req
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    if let Some(license) = info["cardData"]["license"].as_str() {
+        return Some(license.to_string());
+    }
+    info["tags"]
+        .as_array()?
+        .iter()
+        .filter_map(|t| t.as_str())
+        .find_map(|t| t.strip_prefix("license:"))
+        .map(String::from)
+}
+
+/// Bearer token for `url` if it points at the Hub and a token is stored.
+/// Blocking (keychain).
+pub(crate) fn auth_token_for(url: &str) -> Option<String> {
+    if !is_hub_url(url) {
+        return None;
+    }
+    crate::secrets::get(TOKEN_SECRET).ok().flatten()
+}
+
+/// An https URL on the Hub itself — not just one whose text starts with it,
+/// like `https://huggingface.co.example.com`.
+fn is_hub_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    url.scheme() == "https" && matches!(url.host_str(), Some("huggingface.co" | "hf.co"))
+}
+
+#[tauri::command]
+pub(crate) async fn set_hf_token(token: String) -> Result<(), String> {
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err("Token is empty".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::set(TOKEN_SECRET, &token))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub(crate) async fn delete_hf_token() -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(|| crate::secrets::delete(TOKEN_SECRET))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Whether a token is stored — the token itself is never returned.
+#[tauri::command]
+pub(crate) async fn has_hf_token() -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(|| crate::secrets::get(TOKEN_SECRET))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|t| t.is_some())
+}
+
+/// Search the Hub for models that ship GGUF/ONNX weights, most downloaded first.
+/// `task` is a pipeline tag such as "text-generation" or "sentence-similarity".
+#[tauri::command]
+pub(crate) async fn search_hf_models(
+    query: String,
+    task: Option<String>,
+) -> Result<Vec<HfModel>, String> {
+    crate::privacy::guard(HF_BASE, "Hugging Face search")?;
+    let token = tauri::async_runtime::spawn_blocking(|| auth_token_for(HF_BASE))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let client = crate::proxy::client()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    let mut params = vec![
+        ("search", query),
+        ("sort", "downloads".to_string()),
+        ("direction", "-1".to_string()),
+        ("limit", "30".to_string()),
+        ("full", "true".to_string()),
+    ];
+    if let Some(task) = task.filter(|t| !t.is_empty()) {
+        params.push(("pipeline_tag", task));
+    }
+    let mut req = client.get(format!("{}/api/models", HF_BASE)).query(&params);
+    if let Some(t) = token {
+        req = req.bearer_auth(t);
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| format!("Hugging Face search failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!(
+            "Hugging Face search failed: HTTP {}",
+            resp.status()
+        ));
+    }
+    let models: Vec<ApiModel> = resp
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected response from Hugging Face: {}", e))?;
+
+    Ok(models
+        .into_iter()
+        .filter_map(|m| {
+            let files: Vec<String> = m
+                .siblings
+                .into_iter()
+                .map(|s| s.rfilename)
+                .filter(|f| {
+                    let f = f.to_ascii_lowercase();
+                    f.ends_with(".gguf") || f.ends_with(".onnx")
+                })
+                .collect();
+            if files.is_empty() {
+                return None; // nothing we can run
+            }
+            Some(HfModel {
+                id: m.id,
+                downloads: m.downloads,
+                likes: m.likes,
+                pipeline_tag: m.pipeline_tag,
+                gated: !matches!(
+                    m.gated,
+                    serde_json::Value::Bool(false) | serde_json::Value::Null
+                ),
+                last_modified: m.last_modified,
+                files,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hub_urls() {
+        assert!(is_hub_url(
+            "https://huggingface.co/org/model/resolve/main/x.gguf"
+        ));
+        assert!(is_hub_url("https://hf.co/org/model"));
+    }
+
+    #[test]
+    fn not_hub_urls() {
+        assert!(!is_hub_url("http://huggingface.co/org/model"));
+        assert!(!is_hub_url("https://huggingface.co.example.com/x"));
+        assert!(!is_hub_url("https://example.com/huggingface.co"));
+        assert!(!is_hub_url("https://cdn-lfs.huggingface.co/x"));
+        assert!(!is_hub_url("not a url"));
+        assert_eq!(auth_token_for("https://example.com/model.gguf"), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! System locale + bundled translations.
+//!
+//! Translation files ship as resources (`locales/<tag>.json`) instead of being
+//! compiled into the JS bundle, so the webview only ever loads the language it
+//! needs. Lookups walk a fallback chain (`pt-BR` → `pt` → `en`) and deep-merge
+//! from least to most specific, so a partial translation still renders every
+//! string.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Serialize)]
+pub(crate) struct Translations {
+    /// Requested locale, normalised (e.g. "pt-BR").
+    locale: String,
+    /// Files that were found and merged, most specific first.
+    resolved: Vec<String>,
+    messages: Value,
+}
+
+/// "pt_BR.UTF-8" / "pt-br" → "pt-BR"; "zh-Hant-TW" keeps its script subtag.
+/// Anything but ASCII letters, digits and separators gives `DEFAULT_LOCALE`,
+/// since the result names a file in the locales folder.
+fn normalize(tag: &str) -> String {
+    let base = tag.split(['.', '@']).next().unwrap_or(tag);
+    if !base
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return DEFAULT_LOCALE.to_string();
+    }
+    let mut parts = base.split(['-', '_']).filter(|p| !p.is_empty());
+    let mut out = match parts.next() {
+        Some(lang) => lang.to_ascii_lowercase(),
+        None => return DEFAULT_LOCALE.to_string(),
+    };
+    for p in parts {
+        out.push('-');
+        match p.len() {
+            2 => out.push_str(&p.to_ascii_uppercase()),
+            4 => {
+                out.push_str(&p[..1].to_ascii_uppercase());
+                out.push_str(&p[1..].to_ascii_lowercase());
+            }
+            _ => out.push_str(p),
+        }
+    }
+    out
+}
+
+/// "zh-Hant-TW" → ["zh-Hant-TW", "zh-Hant", "zh", "en"].
+fn fallback_chain(locale: &str) -> Vec<String> {
+    let parts: Vec<&str> = locale.split('-').collect();
+    let mut chain: Vec<String> = (1..=parts.len())
+        .rev()
+        .map(|n| parts[..n].join("-"))
+        .collect();
+    if !chain.iter().any(|l| l == DEFAULT_LOCALE) {
+        chain.push(DEFAULT_LOCALE.to_string());
+    }
+    chain
+}
+
+fn locales_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resource_dir()
+        .map(|d| d.join("locales"))
+        .map_err(|e| format!("Failed to get resource dir: {}", e))
+}
+
+/// Overlay `top` onto `base`, recursing into objects so nested sections merge.
+fn merge(base: &mut Value, top: Value) {
+    match (base, top) {
+        (Value::Object(b), Value::Object(t)) => {
+            for (k, v) in t {
+                merge(b.entry(k).or_insert(Value::Null), v);
+            }
+        }
+        (b, t) => *b = t,
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_system_locale() -> Result<String, String> {
+    Ok(sys_locale::get_locale()
+        .map(|l| normalize(&l))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string()))
+}
+
+#[tauri::command]
+pub(crate) async fn get_translations(
+    app: AppHandle,
+    locale: String,
+) -> Result<Translations, String> {
+    let locale = normalize(&locale);
+    let dir = locales_dir(&app)?;
+
+    let mut messages = Value::Object(Default::default());
+    let mut resolved = Vec::new();
+    // Least specific first so more specific files win.
+    for tag in fallback_chain(&locale).into_iter().rev() {
+        let path = dir.join(format!("{}.json", tag));
+        let Ok(raw) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(v) => {
+                merge(&mut messages, v);
+                resolved.insert(0, tag);
+            }
+            Err(e) => tracing::warn!("[i18n] Skipping malformed {}: {}", path.display(), e),
+        }
+    }
+
+    if resolved.is_empty() {
+        return Err(format!(
+            "No translations found for {} in {}",
+            locale,
+            dir.display()
+        ));
+    }
+    Ok(Translations {
+        locale,
+        resolved,
+        messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_cases_subtags() {
+        assert_eq!(normalize("pt_BR.UTF-8"), "pt-BR");
+        assert_eq!(normalize("pt-br"), "pt-BR");
+        assert_eq!(normalize("EN"), "en");
+        assert_eq!(normalize("zh-hant-tw"), "zh-Hant-TW");
+        assert_eq!(normalize("de_DE@euro"), "de-DE");
+    }
+
+    #[test]
+    fn normalize_rejects_what_isnt_a_tag() {
+        assert_eq!(normalize(""), DEFAULT_LOCALE);
+        assert_eq!(normalize("-_"), DEFAULT_LOCALE);
+        assert_eq!(normalize("../../etc/passwd"), DEFAULT_LOCALE);
+        assert_eq!(normalize("en/US"), DEFAULT_LOCALE);
+    }
+}
@@ -0,0 +1,149 @@
+//! System-wide user idle detection (time since last keyboard/mouse input).
+//!
+//! When "defer heavy work" is on, CPU-heavy background work (re-indexing, OCR,
+//! embedding backfill) only runs once the user has been idle past the threshold
+//! and yields again as soon as they return. The monitor samples every 15s and
+//! emits `idle://changed` on each transition.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+static USER_IDLE: AtomicBool = AtomicBool::new(false);
+static HEAVY_WORK_ALLOWED: AtomicBool = AtomicBool::new(true);
+/// Idle seconds at the last sample; `u64::MAX` when unknown.
+static LAST_IDLE_SECS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct IdleState {
+    /// None when the platform can't report idle time (e.g. Linux without xprintidle).
+    idle_secs: Option<u64>,
+    idle: bool,
+    defer_heavy_work: bool,
+    idle_threshold_secs: u64,
+    /// Whether CPU-heavy work may run right now.
+    heavy_work_allowed: bool,
+}
+
+#[cfg(target_os = "macos")]
+fn idle_secs() -> Option<u64> {
+    // IOHIDSystem's HIDIdleTime is in nanoseconds.
+    let out = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let line = text.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let ns: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(ns / 1_000_000_000)
+}
+
+#[cfg(target_os = "linux")]
+fn idle_secs() -> Option<u64> {
+    // X11 only, and only if xprintidle is installed; reports milliseconds.
+    let out = std::process::Command::new("xprintidle").output().ok()?;
+    let ms: u64 = String::from_utf8_lossy(&out.stdout).trim().parse().ok()?;
+    Some(ms / 1000)
+}
+
+#[cfg(target_os = "windows")]
+fn idle_secs() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // SAFETY: info is a properly sized, initialised LASTINPUTINFO.
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(u64::from(now.wrapping_sub(info.dwTime)) / 1000)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn idle_secs() -> Option<u64> {
+    None
+}
+
+fn sample(app: &AppHandle) -> IdleState {
+    let cfg = crate::settings::get(app).idle;
+    let idle_secs = idle_secs();
+    let idle = idle_secs.is_some_and(|s| s >= cfg.idle_threshold_secs);
+    IdleState {
+        idle_secs,
+        idle,
+        defer_heavy_work: cfg.defer_heavy_work,
+        idle_threshold_secs: cfg.idle_threshold_secs,
+        // If idle time is unknown, deferring would block heavy work forever.
+        heavy_work_allowed: !cfg.defer_heavy_work || idle || idle_secs.is_none(),
+    }
+}
+
+fn refresh(app: &AppHandle) -> IdleState {
+    let state = sample(app);
+    HEAVY_WORK_ALLOWED.store(state.heavy_work_allowed, Ordering::Relaxed);
+    LAST_IDLE_SECS.store(state.idle_secs.unwrap_or(u64::MAX), Ordering::Relaxed);
+    if USER_IDLE.swap(state.idle, Ordering::Relaxed) != state.idle {
+        tracing::info!(
+            "[Idle] User {} (idle {:?}s)",
+            if state.idle { "went idle" } else { "returned" },
+            state.idle_secs
+        );
+        let _ = app.emit("idle://changed", &state);
+    }
+    state
+}
+
+/// Whether CPU-heavy work may start, as of the last sample.
+pub(crate) fn heavy_work_allowed() -> bool {
+    HEAVY_WORK_ALLOWED.load(Ordering::Relaxed)
+}
+
+/// Seconds since the last keyboard/mouse input, as of the last sample. None
+/// when the platform can't tell.
+pub(crate) fn user_idle_secs() -> Option<u64> {
+    Some(LAST_IDLE_SECS.load(Ordering::Relaxed)).filter(|s| *s != u64::MAX)
+}
+
+/// Start the sampling loop. Called once from setup.
+pub(crate) fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || refresh(&handle)).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_idle_state(app: AppHandle) -> Result<IdleState, String> {
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub(crate) async fn set_idle_settings(
+    app: AppHandle,
+    defer_heavy_work: bool,
+    idle_threshold_secs: u64,
+) -> Result<IdleState, String> {
+    if idle_threshold_secs < 30 {
+        return Err("Idle threshold must be at least 30 seconds".to_string());
+    }
+    crate::settings::update(&app, |s| {
+        s.idle.defer_heavy_work = defer_heavy_work;
+        s.idle.idle_threshold_secs = idle_threshold_secs;
+    })?;
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
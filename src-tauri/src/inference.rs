@@ -0,0 +1,229 @@
+//! Inference queue — one generation at a time, interactive work first.
+//!
+//! The backend serves every window from one loaded model; concurrent requests
+//! from several notebooks make it swap contexts (or models) and all of them
+//! slow down. Callers take a slot before generating and give it back after:
+//! the webview through `acquire_inference_slot` / `release_inference_slot`
+//! around its backend request, the built-in engine through `acquire()`.
+//! Waiters are ordered by priority (interactive before background), then by
+//! arrival, and every change is emitted as `inference://queue` so the UI can
+//! show "2nd in line".
+//!
+//! A slot held longer than `MAX_HOLD` (a window that crashed or forgot to
+//! release) is reclaimed by the next waiter.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+const MAX_HOLD: Duration = Duration::from_secs(10 * 60);
+const STALE_CHECK: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Priority {
+    /// A user waiting on an answer.
+    Interactive,
+    /// Summaries, indexing and other work nobody is watching.
+    Background,
+}
+
+struct Running {
+    id: u64,
+    priority: Priority,
+    label: Option<String>,
+    since: Instant,
+}
+
+struct Waiter {
+    id: u64,
+    priority: Priority,
+    label: Option<String>,
+    granted: oneshot::Sender<()>,
+}
+
+struct Queue {
+    next_id: u64,
+    running: Option<Running>,
+    waiting: Vec<Waiter>,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    next_id: 1,
+    running: None,
+    waiting: Vec::new(),
+});
+
+#[derive(Clone, Serialize)]
+pub(crate) struct QueueEntry {
+    id: u64,
+    priority: Priority,
+    label: Option<String>,
+    /// 0 = running, 1 = next…
+    position: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct QueueSnapshot {
+    running: Option<QueueEntry>,
+    waiting: Vec<QueueEntry>,
+}
+
+fn snapshot(q: &Queue) -> QueueSnapshot {
+    QueueSnapshot {
+        running: q.running.as_ref().map(|r| QueueEntry {
+            id: r.id,
+            priority: r.priority,
+            label: r.label.clone(),
+            position: 0,
+        }),
+        waiting: q
+            .waiting
+            .iter()
+            .enumerate()
+            .map(|(i, w)| QueueEntry {
+                id: w.id,
+                priority: w.priority,
+                label: w.label.clone(),
+                position: i + 1,
+            })
+            .collect(),
+    }
+}
+
+fn emit(app: &AppHandle, q: &Queue) {
+    let _ = app.emit("inference://queue", snapshot(q));
+}
+
+/// Hand the slot to the first waiter still listening.
+fn promote(q: &mut Queue) {
+    q.running = None;
+    while !q.waiting.is_empty() {
+        let w = q.waiting.remove(0);
+        if w.granted.send(()).is_ok() {
+            q.running = Some(Running {
+                id: w.id,
+                priority: w.priority,
+                label: w.label,
+                since: Instant::now(),
+            });
+            return;
+        }
+    }
+}
+
+/// Give back a running slot, or withdraw a waiting one. Unknown ids are
+/// ignored (already released or reclaimed).
+pub(crate) fn release(app: &AppHandle, id: u64) {
+    let mut q = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    if q.running.as_ref().is_some_and(|r| r.id == id) {
+        promote(&mut q);
+    } else {
+        q.waiting.retain(|w| w.id != id);
+    }
+    emit(app, &q);
+}
+
+fn reclaim_stale(app: &AppHandle) {
+    let mut q = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(r) = q.running.as_ref().filter(|r| r.since.elapsed() > MAX_HOLD) {
+        tracing::warn!(
+            "[Inference] Slot {} ({}) held for over {} min — reclaiming",
+            r.id,
+            r.label.as_deref().unwrap_or("unlabelled"),
+            MAX_HOLD.as_secs() / 60
+        );
+        promote(&mut q);
+        emit(app, &q);
+    }
+}
+
+/// Released when dropped.
+pub(crate) struct Slot {
+    app: AppHandle,
+    id: u64,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        release(&self.app, self.id);
+    }
+}
+
+async fn wait_for_slot(app: &AppHandle, priority: Priority, label: Option<String>) -> u64 {
+    let (id, mut granted) = {
+        let mut q = QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+        let id = q.next_id;
+        q.next_id += 1;
+        if q.running.is_none() && q.waiting.is_empty() {
+            q.running = Some(Running {
+                id,
+                priority,
+                label,
+                since: Instant::now(),
+            });
+            emit(app, &q);
+            return id;
+        }
+        let (tx, rx) = oneshot::channel();
+        // Behind everyone of the same or higher priority.
+        let at = q
+            .waiting
+            .iter()
+            .position(|w| w.priority > priority)
+            .unwrap_or(q.waiting.len());
+        q.waiting.insert(
+            at,
+            Waiter {
+                id,
+                priority,
+                label,
+                granted: tx,
+            },
+        );
+        emit(app, &q);
+        (id, rx)
+    };
+    loop {
+        tokio::select! {
+            _ = &mut granted => return id,
+            _ = tokio::time::sleep(STALE_CHECK) => reclaim_stale(app),
+        }
+    }
+}
+
+/// Wait for the inference slot; it's held until the returned guard drops.
+pub(crate) async fn acquire(app: &AppHandle, priority: Priority, label: &str) -> Slot {
+    let id = wait_for_slot(app, priority, Some(label.to_string())).await;
+    Slot {
+        app: app.clone(),
+        id,
+    }
+}
+
+/// Resolves with a ticket id once it's this caller's turn to generate. Pass
+/// the id to `release_inference_slot` when the response has finished (or to
+/// give up waiting).
+#[tauri::command]
+pub(crate) async fn acquire_inference_slot(
+    app: AppHandle,
+    priority: Option<Priority>,
+    label: Option<String>,
+) -> Result<u64, String> {
+    Ok(wait_for_slot(&app, priority.unwrap_or(Priority::Interactive), label).await)
+}
+
+#[tauri::command]
+pub(crate) async fn release_inference_slot(app: AppHandle, id: u64) -> Result<(), String> {
+    release(&app, id);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_inference_queue() -> Result<QueueSnapshot, String> {
+    let q = QUEUE.lock().map_err(|e| e.to_string())?;
+    Ok(snapshot(&q))
+}
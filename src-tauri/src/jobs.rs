@@ -0,0 +1,1399 @@
+//! Background jobs — imports, model downloads, OCR, transcription,
+//! re-indexing, backups, collection runs and cache pruning.
+//!
+//! Work that outlives a click goes through one queue instead of each feature
+//! spawning its own task: `enqueue_job` takes a typed `JobSpec`, a small
+//! worker pool runs queued jobs in order, and the UI follows along through
+//! events rather than holding a promise open:
+//!
+//! - `jobs://updated` — the whole job, on every state change
+//! - `jobs://progress` — `{ id, progress, message }` while it runs
+//! - `jobs://finished` — the job once it reaches a terminal state
+//!
+//! Every job carries a cancellation token. `cancel_job` drops a queued job
+//! straight away; for a running one it fires the token, which abandons the
+//! in-flight backend request or download (the backend sees the connection
+//! close) and stops Whisper and backups at their next check, and the job ends
+//! as `cancelled`.
+//!
+//! Failures are captured as a `JobError` with a class (backend down, bad
+//! input, I/O…) alongside the message, so callers can tell a transient
+//! failure from one that will fail again.
+//!
+//! The queue is kept in `jobs.db` in the app data folder, so work queued or
+//! interrupted when the app quits picks up again on the next launch once the
+//! backend is ready (a job that was running starts over). Jobs carry an
+//! idempotency key — given by the caller, or derived from the file and its
+//! modification time for imports and from the URL for downloads — and
+//! enqueueing a key that is already queued or running returns that job
+//! instead of adding a duplicate. Once it has finished, the same key queues
+//! new work (e.g. importing a file again after its document was deleted).
+//!
+//! A failure whose class is in the job kind's retry policy (by default,
+//! backend-down and network-type errors for imports, downloads, indexing and
+//! collection) sends the job back to the queue after an exponential backoff,
+//! until its attempts run out, so a backend restart or a network blip heals
+//! itself.
+//!
+//! Finished jobs are also written to a history table (kept for 90 days) with
+//! their timing, a one-line summary of the input and any error, which
+//! `get_job_history` filters — e.g. to find which of last night's imports
+//! failed and why.
+//!
+//! Nothing new starts while background work is paused (see `pause`).
+//! Workers respect per-category concurrency limits from settings (so a batch
+//! of 500 imports doesn't hold up a transcription or saturate every core), the
+//! power policy (nothing starts while background work is paused, one job at a
+//! time while throttled) and, for CPU-heavy kinds, the idle-deferral setting.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::backend_api::{self, ApiError};
+use crate::power::BackgroundPolicy;
+use crate::settings::{JobConcurrency, RetryPolicy};
+
+/// Upper bound on jobs running at once, across all categories.
+const WORKERS: usize = 8;
+/// Finished jobs kept for `list_jobs`; older ones are dropped.
+const KEEP_FINISHED: usize = 200;
+/// Workers re-check the queue this often even without a wake-up, so jobs held
+/// back by the power or idle state start once it changes.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+const BACKEND_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const HISTORY_DAYS: u64 = 90;
+/// Most rows one `get_job_history` call returns.
+const HISTORY_PAGE_MAX: u32 = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum JobSpec {
+    /// Add a file to a notebook.
+    Import { notebook_id: String, path: String },
+    /// Fetch a model file into the models dir (see `models::download_model`).
+    Download {
+        url: String,
+        #[serde(default)]
+        file_name: Option<String>,
+        #[serde(default)]
+        sha256: Option<String>,
+    },
+    /// OCR scanned pages; the result carries the merged text.
+    Ocr {
+        paths: Vec<String>,
+        #[serde(default)]
+        mode: Option<String>,
+        #[serde(default)]
+        target_language: Option<String>,
+    },
+    /// Local Whisper transcription of an audio or video file.
+    Transcribe {
+        path: String,
+        #[serde(default)]
+        size: Option<String>,
+        #[serde(default)]
+        language: Option<String>,
+    },
+    /// Re-index one notebook, or all of them.
+    Index {
+        #[serde(default)]
+        notebook_id: Option<String>,
+        #[serde(default)]
+        force: bool,
+    },
+    /// Back up the backend's data; defaults to `<app data>/backups`.
+    Backup {
+        #[serde(default)]
+        dest_dir: Option<String>,
+    },
+    /// Run the Collector for one notebook, or every notebook that isn't set
+    /// to manual collection.
+    Collect {
+        #[serde(default)]
+        notebook_id: Option<String>,
+    },
+    /// Delete cache files (voice previews, temp renders) older than
+    /// `max_age_days`.
+    PruneCache { max_age_days: u64 },
+}
+
+/// Every job kind, as used for retry policies.
+const KINDS: &[&str] = &[
+    "import",
+    "download",
+    "ocr",
+    "transcribe",
+    "index",
+    "backup",
+    "collect",
+    "prune_cache",
+];
+
+/// The retry policy used for `kind` unless settings override it.
+fn default_retry(kind: &str) -> RetryPolicy {
+    use ErrorClass::*;
+    let (max_attempts, backoff_secs, retry_on) = match kind {
+        "import" => (3, 15, vec![BackendUnavailable, BackendError]),
+        // Downloads already retry dropped connections themselves.
+        "download" => (3, 60, vec![Io]),
+        "ocr" => (2, 15, vec![BackendUnavailable]),
+        "index" | "collect" => (3, 60, vec![BackendUnavailable, BackendError]),
+        "backup" => (2, 300, vec![Io]),
+        _ => (1, 30, Vec::new()),
+    };
+    RetryPolicy {
+        max_attempts,
+        backoff_secs,
+        retry_on,
+        ..RetryPolicy::default()
+    }
+}
+
+fn retry_policy(app: &AppHandle, kind: &str) -> RetryPolicy {
+    crate::settings::get(app)
+        .jobs
+        .retry
+        .remove(kind)
+        .unwrap_or_else(|| default_retry(kind))
+}
+
+/// Groups of job kinds that share a concurrency limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobCategory {
+    Import,
+    Download,
+    Transcription,
+    Ocr,
+    Maintenance,
+}
+
+impl JobCategory {
+    fn limit(self, c: &JobConcurrency) -> usize {
+        match self {
+            JobCategory::Import => c.imports,
+            JobCategory::Download => c.downloads,
+            JobCategory::Transcription => c.transcriptions,
+            JobCategory::Ocr => c.ocr,
+            JobCategory::Maintenance => c.maintenance,
+        }
+    }
+}
+
+impl JobSpec {
+    fn category(&self) -> JobCategory {
+        match self {
+            JobSpec::Import { .. } => JobCategory::Import,
+            JobSpec::Download { .. } => JobCategory::Download,
+            JobSpec::Transcribe { .. } => JobCategory::Transcription,
+            JobSpec::Ocr { .. } => JobCategory::Ocr,
+            JobSpec::Index { .. }
+            | JobSpec::Backup { .. }
+            | JobSpec::Collect { .. }
+            | JobSpec::PruneCache { .. } => JobCategory::Maintenance,
+        }
+    }
+
+    /// OCR, transcription and indexing keep the CPU busy for minutes.
+    fn is_heavy(&self) -> bool {
+        matches!(
+            self,
+            JobSpec::Ocr { .. } | JobSpec::Transcribe { .. } | JobSpec::Index { .. }
+        )
+    }
+
+    /// The `kind` tag, e.g. "import".
+    fn kind(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v["kind"].as_str().map(String::from))
+            .unwrap_or_default()
+    }
+
+    /// One line describing the input, for the history.
+    fn summary(&self) -> String {
+        let file = |p: &str| {
+            Path::new(p)
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_else(|| p.to_string())
+        };
+        let scope = |id: &Option<String>| match id {
+            Some(id) => format!("notebook {}", id),
+            None => "all notebooks".to_string(),
+        };
+        match self {
+            JobSpec::Import { notebook_id, path } => {
+                format!("Import {} into notebook {}", file(path), notebook_id)
+            }
+            JobSpec::Download { url, file_name, .. } => {
+                format!("Download {}", file_name.as_deref().unwrap_or(url))
+            }
+            JobSpec::Ocr { paths, .. } => format!("OCR {} page(s)", paths.len()),
+            JobSpec::Transcribe { path, .. } => format!("Transcribe {}", file(path)),
+            JobSpec::Index { notebook_id, force } => format!(
+                "Re-index {}{}",
+                scope(notebook_id),
+                if *force { " (full)" } else { "" }
+            ),
+            JobSpec::Backup { dest_dir } => match dest_dir {
+                Some(d) => format!("Back up to {}", d),
+                None => "Back up".to_string(),
+            },
+            JobSpec::Collect { notebook_id } => format!("Collect for {}", scope(notebook_id)),
+            JobSpec::PruneCache { max_age_days } => {
+                format!("Prune cache files older than {} days", max_age_days)
+            }
+        }
+    }
+
+    /// The key used when the caller doesn't give one.
+    fn default_key(&self) -> Option<String> {
+        match self {
+            JobSpec::Import { notebook_id, path } => {
+                let meta = std::fs::metadata(path).ok()?;
+                let mtime = meta
+                    .modified()
+                    .ok()?
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .ok()?
+                    .as_secs();
+                Some(format!(
+                    "import:{}:{}:{}:{}",
+                    notebook_id,
+                    path,
+                    meta.len(),
+                    mtime
+                ))
+            }
+            JobSpec::Download { url, file_name, .. } => Some(format!(
+                "download:{}:{}",
+                url,
+                file_name.as_deref().unwrap_or_default()
+            )),
+            _ => None,
+        }
+    }
+
+    /// Local paths the job reads or writes.
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            JobSpec::Import { path, .. } | JobSpec::Transcribe { path, .. } => vec![path],
+            JobSpec::Ocr { paths, .. } => paths.iter().map(String::as_str).collect(),
+            JobSpec::Backup { dest_dir } => dest_dir.iter().map(String::as_str).collect(),
+            JobSpec::Download { .. }
+            | JobSpec::Index { .. }
+            | JobSpec::Collect { .. }
+            | JobSpec::PruneCache { .. } => Vec::new(),
+        }
+    }
+
+    /// Every path must have been granted (see `scope`). Checked at enqueue
+    /// and again before running, since a grant can be revoked in between.
+    fn check_scope(&self, app: &AppHandle) -> Result<(), String> {
+        self.paths()
+            .into_iter()
+            .try_for_each(|p| crate::scope::check(app, Path::new(p)).map(|_| ()))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let require_file = |p: &str| {
+            if Path::new(p).is_file() {
+                Ok(())
+            } else {
+                Err(format!("File not found: {}", p))
+            }
+        };
+        match self {
+            JobSpec::Import { path, .. } | JobSpec::Transcribe { path, .. } => require_file(path),
+            JobSpec::Ocr { paths, .. } if paths.is_empty() => Err("No pages to OCR".to_string()),
+            JobSpec::Ocr { paths, .. } => paths.iter().try_for_each(|p| require_file(p)),
+            JobSpec::Download { .. }
+            | JobSpec::Index { .. }
+            | JobSpec::Backup { .. }
+            | JobSpec::Collect { .. }
+            | JobSpec::PruneCache { .. } => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorClass {
+    /// The backend couldn't be reached (starting, restarting, crashed).
+    BackendUnavailable,
+    /// The backend answered with a server error or reported a failure.
+    BackendError,
+    /// The request itself was rejected — bad path, unknown notebook…
+    InvalidInput,
+    /// Reading or writing local files failed.
+    Io,
+    /// A local engine (Whisper) failed or isn't in this build.
+    Engine,
+    Internal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct JobError {
+    class: ErrorClass,
+    message: String,
+}
+
+impl JobError {
+    fn new(class: ErrorClass, message: impl Into<String>) -> Self {
+        Self {
+            class,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ApiError> for JobError {
+    fn from(e: ApiError) -> Self {
+        let class = match &e {
+            ApiError::Unreachable(_) => ErrorClass::BackendUnavailable,
+            ApiError::Status(code, _) if *code < 500 => ErrorClass::InvalidInput,
+            ApiError::Status(..) | ApiError::Failed(_) => ErrorClass::BackendError,
+        };
+        JobError::new(class, e.to_string())
+    }
+}
+
+/// A finished job as kept in the history.
+#[derive(Serialize)]
+pub(crate) struct HistoryEntry {
+    id: String,
+    kind: String,
+    summary: String,
+    state: JobState,
+    created_at: u64,
+    started_at: Option<u64>,
+    finished_at: u64,
+    /// Seconds from start to finish; None if it never started.
+    duration_secs: Option<u64>,
+    error: Option<JobError>,
+}
+
+/// `get_job_history` filter; every field is optional.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct HistoryFilter {
+    state: Option<JobState>,
+    /// A job kind, e.g. "import".
+    kind: Option<String>,
+    /// Finished at or after (Unix seconds).
+    since: Option<u64>,
+    /// Finished before (Unix seconds).
+    until: Option<u64>,
+    /// Substring of the summary or error message.
+    text: Option<String>,
+    /// Default 100.
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Job {
+    id: String,
+    idempotency_key: Option<String>,
+    spec: JobSpec,
+    state: JobState,
+    /// 0..1, when the work reports it.
+    progress: Option<f32>,
+    message: Option<String>,
+    created_at: u64,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    result: Option<serde_json::Value>,
+    /// The last failure; kept while a retry is pending.
+    error: Option<JobError>,
+    /// Tries so far, counting the one running.
+    #[serde(default)]
+    attempts: u32,
+    /// A retry waits in the queue until then (Unix seconds).
+    #[serde(default)]
+    retry_at: Option<u64>,
+    #[serde(skip)]
+    cancel: CancellationToken,
+}
+
+static JOBS: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+
+fn wake() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<Job>> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Drop finished jobs whose result is the document `source_id` (the import
+/// that created it), from the queue and the history. Returns how many.
+pub(crate) fn forget_source(source_id: &str) -> usize {
+    let ids = {
+        let mut jobs = lock();
+        let ids: Vec<String> = jobs
+            .iter()
+            .filter(|j| {
+                matches!(
+                    j.state,
+                    JobState::Completed | JobState::Failed | JobState::Cancelled
+                ) && j
+                    .result
+                    .as_ref()
+                    .and_then(|r| r.get("source_id"))
+                    .and_then(serde_json::Value::as_str)
+                    == Some(source_id)
+            })
+            .map(|j| j.id.clone())
+            .collect();
+        jobs.retain(|j| !ids.contains(&j.id));
+        ids
+    };
+    store::delete(&ids);
+    store::delete_history(&ids);
+    ids.len()
+}
+
+/// Apply `f` to a job and return the updated copy.
+fn modify(id: &str, f: impl FnOnce(&mut Job)) -> Option<Job> {
+    let mut jobs = lock();
+    let job = jobs.iter_mut().find(|j| j.id == id)?;
+    f(job);
+    Some(job.clone())
+}
+
+/// The job table in `jobs.db`. Writes happen on state changes only, not on
+/// every progress tick; failures are logged and the in-memory queue carries on.
+mod store {
+    use std::path::Path;
+    use std::sync::{Mutex, OnceLock};
+
+    use rusqlite::{params, Connection};
+
+    use super::{ErrorClass, HistoryEntry, HistoryFilter, Job, JobError, JobState};
+
+    static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+    pub(super) fn open(path: &Path) -> Result<(), String> {
+        let conn = Connection::open(path)
+            .and_then(|c| {
+                c.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS jobs (
+                        id TEXT PRIMARY KEY,
+                        idempotency_key TEXT,
+                        state TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        job TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS jobs_key ON jobs (idempotency_key);
+                    CREATE TABLE IF NOT EXISTS history (
+                        id TEXT PRIMARY KEY,
+                        kind TEXT NOT NULL,
+                        summary TEXT NOT NULL,
+                        state TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        started_at INTEGER,
+                        finished_at INTEGER NOT NULL,
+                        error TEXT
+                    );
+                    CREATE INDEX IF NOT EXISTS history_finished ON history (finished_at);",
+                )?;
+                let cutoff = crate::models::now_secs().saturating_sub(super::HISTORY_DAYS * 86400);
+                c.execute(
+                    "DELETE FROM history WHERE finished_at < ?1",
+                    [cutoff as i64],
+                )?;
+                Ok(c)
+            })
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let _ = DB.set(Mutex::new(conn));
+        Ok(())
+    }
+
+    fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Option<T> {
+        let conn = DB.get()?.lock().unwrap_or_else(|e| e.into_inner());
+        f(&conn)
+            .map_err(|e| tracing::warn!("[Jobs] Job store error: {}", e))
+            .ok()
+    }
+
+    pub(super) fn save(job: &Job) {
+        let Ok(json) = serde_json::to_string(job) else {
+            return;
+        };
+        let state = serde_json::to_value(job.state).unwrap_or_default();
+        with_db(|db| {
+            db.execute(
+                "INSERT OR REPLACE INTO jobs (id, idempotency_key, state, created_at, job)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    job.id,
+                    job.idempotency_key,
+                    state.as_str(),
+                    job.created_at as i64,
+                    json
+                ],
+            )
+        });
+    }
+
+    pub(super) fn delete(ids: &[String]) {
+        with_db(|db| {
+            let mut stmt = db.prepare("DELETE FROM jobs WHERE id = ?1")?;
+            for id in ids {
+                stmt.execute([id])?;
+            }
+            Ok(())
+        });
+    }
+
+    pub(super) fn delete_history(ids: &[String]) {
+        with_db(|db| {
+            let mut stmt = db.prepare("DELETE FROM history WHERE id = ?1")?;
+            for id in ids {
+                stmt.execute([id])?;
+            }
+            Ok(())
+        });
+    }
+
+    /// Add a finished job to the history.
+    pub(super) fn record(job: &Job) {
+        let state = serde_json::to_value(job.state).unwrap_or_default();
+        let error = job
+            .error
+            .as_ref()
+            .and_then(|e| serde_json::to_string(e).ok());
+        with_db(|db| {
+            db.execute(
+                "INSERT OR REPLACE INTO history
+                 (id, kind, summary, state, created_at, started_at, finished_at, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    job.id,
+                    job.spec.kind(),
+                    job.spec.summary(),
+                    state.as_str(),
+                    job.created_at as i64,
+                    job.started_at.map(|t| t as i64),
+                    job.finished_at.unwrap_or(job.created_at) as i64,
+                    error
+                ],
+            )
+        });
+    }
+
+    /// History rows matching `filter`, newest first.
+    pub(super) fn history(filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, String> {
+        let state = filter
+            .state
+            .and_then(|s| serde_json::to_value(s).ok())
+            .and_then(|v| v.as_str().map(String::from));
+        let text = filter.text.as_ref().map(|t| format!("%{}%", t));
+        let limit = filter.limit.unwrap_or(100).min(super::HISTORY_PAGE_MAX);
+        let conn = DB
+            .get()
+            .ok_or("Job history is unavailable")?
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, summary, state, created_at, started_at, finished_at, error
+                 FROM history
+                 WHERE (?1 IS NULL OR state = ?1)
+                   AND (?2 IS NULL OR kind = ?2)
+                   AND (?3 IS NULL OR finished_at >= ?3)
+                   AND (?4 IS NULL OR finished_at < ?4)
+                   AND (?5 IS NULL OR summary LIKE ?5 OR error LIKE ?5)
+                 ORDER BY finished_at DESC, rowid DESC
+                 LIMIT ?6 OFFSET ?7",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![
+                    state,
+                    filter.kind,
+                    filter.since.map(|t| t as i64),
+                    filter.until.map(|t| t as i64),
+                    text,
+                    limit,
+                    filter.offset.unwrap_or(0)
+                ],
+                |row| {
+                    let state: String = row.get(3)?;
+                    let started_at: Option<i64> = row.get(5)?;
+                    let finished_at: i64 = row.get(6)?;
+                    let error: Option<String> = row.get(7)?;
+                    Ok(HistoryEntry {
+                        id: row.get(0)?,
+                        kind: row.get(1)?,
+                        summary: row.get(2)?,
+                        state: serde_json::from_value(state.into()).unwrap_or(JobState::Failed),
+                        created_at: row.get::<_, i64>(4)? as u64,
+                        started_at: started_at.map(|t| t as u64),
+                        finished_at: finished_at as u64,
+                        duration_secs: started_at.map(|t| finished_at.saturating_sub(t) as u64),
+                        error: error.map(|e| {
+                            serde_json::from_str(&e)
+                                .unwrap_or_else(|_| JobError::new(ErrorClass::Internal, e))
+                        }),
+                    })
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Every stored job, oldest first. Rows that no longer parse (a job kind
+    /// from a newer version) are skipped.
+    pub(super) fn load() -> Vec<Job> {
+        with_db(|db| {
+            let mut stmt = db.prepare("SELECT job FROM jobs ORDER BY created_at, rowid")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            Ok(rows
+                .flatten()
+                .filter_map(|json| serde_json::from_str(&json).ok())
+                .collect())
+        })
+        .unwrap_or_default()
+    }
+}
+
+fn emit_updated(app: &AppHandle, job: &Job) {
+    let _ = app.emit("jobs://updated", job);
+    if job.state.is_terminal() {
+        let _ = app.emit("jobs://finished", job);
+    }
+}
+
+/// Handed to the code running a job for progress reporting.
+struct JobCtx {
+    app: AppHandle,
+    id: String,
+    cancel: CancellationToken,
+}
+
+impl JobCtx {
+    fn progress(&self, progress: Option<f32>, message: Option<String>) {
+        modify(&self.id, |j| {
+            j.progress = progress.or(j.progress);
+            j.message = message.clone().or(j.message.take());
+        });
+        let _ = self.app.emit(
+            "jobs://progress",
+            json!({ "id": self.id, "progress": progress, "message": message }),
+        );
+    }
+
+    /// A backend progress frame: `{"stage", "percent", "message"}`.
+    fn backend_progress(&self, evt: &serde_json::Value) {
+        let percent = evt["percent"].as_f64().map(|p| (p / 100.0) as f32);
+        self.progress(percent, evt["message"].as_str().map(String::from));
+    }
+}
+
+async fn execute(ctx: &JobCtx, spec: JobSpec) -> Result<serde_json::Value, JobError> {
+    spec.check_scope(&ctx.app)
+        .map_err(|e| JobError::new(ErrorClass::InvalidInput, e))?;
+    match spec {
+        JobSpec::Import { notebook_id, path } => {
+            backend_api::upload_file(Path::new(&path), &notebook_id, |e| ctx.backend_progress(e))
+                .await
+                .map_err(JobError::from)
+        }
+        JobSpec::Download {
+            url,
+            file_name,
+            sha256,
+        } => {
+            let entry = crate::models::download_model(ctx.app.clone(), url, file_name, sha256)
+                .await
+                .map_err(|e| JobError::new(ErrorClass::Io, e))?;
+            serde_json::to_value(entry)
+                .map_err(|e| JobError::new(ErrorClass::Internal, e.to_string()))
+        }
+        JobSpec::Ocr {
+            paths,
+            mode,
+            target_language,
+        } => {
+            let body = json!({
+                "file_paths": paths,
+                "mode": mode.unwrap_or_else(|| "document".to_string()),
+                "target_language": target_language,
+            });
+            backend_api::post_json_stream(
+                "/scan/ocr-batch",
+                &body,
+                Duration::from_secs(3600),
+                |e| ctx.backend_progress(e),
+            )
+            .await
+            .map_err(JobError::from)
+        }
+        JobSpec::Transcribe {
+            path,
+            size,
+            language,
+        } => {
+            let transcript = crate::whisper::transcribe_file(
+                ctx.app.clone(),
+                path,
+                size,
+                language,
+                ctx.cancel.clone(),
+            )
+            .await
+            .map_err(|e| JobError::new(ErrorClass::Engine, e))?;
+            serde_json::to_value(transcript)
+                .map_err(|e| JobError::new(ErrorClass::Internal, e.to_string()))
+        }
+        JobSpec::Index { notebook_id, force } => {
+            let path = match notebook_id {
+                Some(id) => format!("/reindex/notebook/{}?force={}", id, force),
+                None => format!("/reindex/all?force={}", force),
+            };
+            ctx.progress(None, Some("Re-indexing…".to_string()));
+            backend_api::post_json(&path, &json!({}), Duration::from_secs(2 * 3600))
+                .await
+                .map_err(JobError::from)
+        }
+        JobSpec::Backup { dest_dir } => {
+            let scheduled = dest_dir.is_none();
+            let dest_dir = match dest_dir {
+                Some(d) => PathBuf::from(d),
+                None => match crate::default_dirs::custom(crate::default_dirs::DirKind::Backups) {
+                    Some(d) => d,
+                    None => crate::backup::default_dir(&ctx.app)
+                        .map_err(|e| JobError::new(ErrorClass::Io, e))?,
+                },
+            };
+            let prune_dir = dest_dir.clone();
+            let (app, id, cancel) = (ctx.app.clone(), ctx.id.clone(), ctx.cancel.clone());
+            let dest = tauri::async_runtime::spawn_blocking(move || {
+                let ctx = JobCtx { app, id, cancel };
+                crate::backup::create(&dest_dir, |done, total| {
+                    if ctx.cancel.is_cancelled() {
+                        return Err("Backup cancelled".to_string());
+                    }
+                    ctx.progress(Some(done as f32 / total.max(1) as f32), None);
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(|e| JobError::new(ErrorClass::Internal, e.to_string()))?
+            .map_err(|e| JobError::new(ErrorClass::Io, e))?;
+            if scheduled {
+                if let Err(e) = crate::backup::prune(&prune_dir, crate::backup::KEEP_SCHEDULED) {
+                    tracing::warn!("[Jobs] {}", e);
+                }
+            }
+            Ok(json!({ "path": dest }))
+        }
+        JobSpec::Collect { notebook_id } => collect(ctx, notebook_id).await,
+        JobSpec::PruneCache { max_age_days } => {
+            let dir = ctx
+                .app
+                .path()
+                .app_cache_dir()
+                .map_err(|e| JobError::new(ErrorClass::Io, e.to_string()))?;
+            let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+            let (removed, bytes) =
+                tauri::async_runtime::spawn_blocking(move || prune_older_than(&dir, max_age))
+                    .await
+                    .map_err(|e| JobError::new(ErrorClass::Internal, e.to_string()))?;
+            Ok(json!({ "removed": removed, "freed_bytes": bytes }))
+        }
+    }
+}
+
+async fn collect(ctx: &JobCtx, notebook_id: Option<String>) -> Result<serde_json::Value, JobError> {
+    let timeout = Duration::from_secs(10 * 60);
+    let ids = match notebook_id {
+        Some(id) => vec![id],
+        None => {
+            let notebooks = backend_api::get_json("/notebooks/", Duration::from_secs(30)).await?;
+            let mut ids = Vec::new();
+            for nb in notebooks["notebooks"].as_array().into_iter().flatten() {
+                let Some(id) = nb["id"].as_str() else {
+                    continue;
+                };
+                let config =
+                    backend_api::get_json(&format!("/collector/{}/config", id), timeout).await?;
+                if config["collection_mode"].as_str() != Some("manual") {
+                    ids.push(id.to_string());
+                }
+            }
+            ids
+        }
+    };
+    let mut results = serde_json::Map::new();
+    for (i, id) in ids.iter().enumerate() {
+        ctx.progress(
+            Some(i as f32 / ids.len() as f32),
+            Some(format!(
+                "Collecting for notebook {} of {}",
+                i + 1,
+                ids.len()
+            )),
+        );
+        let result = backend_api::post_json(
+            &format!("/collector/{}/collect-now", id),
+            &json!({}),
+            timeout,
+        )
+        .await?;
+        results.insert(id.clone(), result);
+    }
+    Ok(serde_json::Value::Object(results))
+}
+
+/// Blocking: remove files under `dir` not modified within `max_age`. Returns
+/// (files removed, bytes freed).
+fn prune_older_than(dir: &Path, max_age: Duration) -> (usize, u64) {
+    let mut removed = (0, 0);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return removed;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            let (n, bytes) = prune_older_than(&path, max_age);
+            removed = (removed.0 + n, removed.1 + bytes);
+            let _ = std::fs::remove_dir(&path); // only if now empty
+        } else if meta
+            .modified()
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > max_age)
+            && std::fs::remove_file(&path).is_ok()
+        {
+            removed = (removed.0 + 1, removed.1 + meta.len());
+        }
+    }
+    removed
+}
+
+/// Whether `spec` may start now, given what's already running.
+fn may_start(
+    spec: &JobSpec,
+    running: &[JobCategory],
+    limits: &JobConcurrency,
+    policy: Option<BackgroundPolicy>,
+) -> bool {
+    match policy {
+        Some(BackgroundPolicy::Pause) => return false,
+        Some(BackgroundPolicy::Throttle) if !running.is_empty() => return false,
+        _ => {}
+    }
+    let category = spec.category();
+    let same = running.iter().filter(|c| **c == category).count();
+    same < category.limit(limits).max(1) && (!spec.is_heavy() || crate::idle::heavy_work_allowed())
+}
+
+/// Claim the oldest queued job that may start, marking it running.
+fn take_next(app: &AppHandle) -> Option<Job> {
+    if crate::pause::is_paused(app) {
+        return None;
+    }
+    let policy = crate::power::current_policy();
+    let limits = crate::settings::get(app).jobs.concurrency;
+    let now = crate::models::now_secs();
+    let mut jobs = lock();
+    let running: Vec<JobCategory> = jobs
+        .iter()
+        .filter(|j| j.state == JobState::Running)
+        .map(|j| j.spec.category())
+        .collect();
+    let job = jobs.iter_mut().find(|j| {
+        j.state == JobState::Queued
+            && j.retry_at.is_none_or(|t| t <= now)
+            && may_start(&j.spec, &running, &limits, policy)
+    })?;
+    job.state = JobState::Running;
+    job.started_at = Some(now);
+    job.attempts += 1;
+    job.retry_at = None;
+    let job = job.clone();
+    drop(jobs);
+    store::save(&job);
+    Some(job)
+}
+
+/// Drop the oldest finished jobs beyond `KEEP_FINISHED`.
+fn prune(jobs: &mut Vec<Job>) {
+    let finished = jobs.iter().filter(|j| j.state.is_terminal()).count();
+    let mut excess = finished.saturating_sub(KEEP_FINISHED);
+    let mut dropped = Vec::new();
+    jobs.retain(|j| {
+        if excess > 0 && j.state.is_terminal() {
+            excess -= 1;
+            dropped.push(j.id.clone());
+            false
+        } else {
+            true
+        }
+    });
+    store::delete(&dropped);
+}
+
+async fn run(app: &AppHandle, job: Job) {
+    emit_updated(app, &job);
+    tracing::info!("[Jobs] {} started: {:?}", job.id, job.spec);
+    let ctx = JobCtx {
+        app: app.clone(),
+        id: job.id.clone(),
+        cancel: job.cancel.clone(),
+    };
+    let retry = retry_policy(app, &job.spec.kind());
+    let began = std::time::Instant::now();
+    // Blocking work (Whisper, backups) checks the token itself; everything
+    // else stops when its future is dropped here.
+    let outcome = tokio::select! {
+        outcome = execute(&ctx, job.spec) => outcome,
+        _ = job.cancel.cancelled() => Err(JobError::new(ErrorClass::Internal, "Cancelled")),
+    };
+    let mut retry_in = None;
+    let updated = modify(&job.id, |j| {
+        let now = crate::models::now_secs();
+        match outcome {
+            Err(_) if j.cancel.is_cancelled() => j.state = JobState::Cancelled,
+            Ok(result) => {
+                j.state = JobState::Completed;
+                j.progress = Some(1.0);
+                j.result = Some(result);
+                j.error = None;
+            }
+            Err(e) if j.attempts < retry.max_attempts && retry.retry_on.contains(&e.class) => {
+                let delay = retry
+                    .backoff_secs
+                    .saturating_mul(1 << (j.attempts - 1).min(16))
+                    .min(retry.max_backoff_secs);
+                tracing::warn!(
+                    "[Jobs] {} failed ({:?}), retrying in {}s (attempt {} of {}): {}",
+                    j.id,
+                    e.class,
+                    delay,
+                    j.attempts,
+                    retry.max_attempts,
+                    e.message
+                );
+                j.state = JobState::Queued;
+                j.started_at = None;
+                j.progress = None;
+                j.message = Some(format!(
+                    "Retrying in {}s (attempt {} of {})",
+                    delay,
+                    j.attempts + 1,
+                    retry.max_attempts
+                ));
+                j.retry_at = Some(now + delay);
+                j.error = Some(e);
+                retry_in = Some(Duration::from_secs(delay));
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("[Jobs] {} failed ({:?}): {}", j.id, e.class, e.message);
+                j.state = JobState::Failed;
+                j.error = Some(e);
+            }
+        }
+        j.finished_at = Some(now);
+    });
+    if let Some(job) = updated {
+        tracing::info!("[Jobs] {} {:?}", job.id, job.state);
+        store::save(&job);
+        if job.state.is_terminal() {
+            store::record(&job);
+            let outcome = match job.state {
+                JobState::Completed => "completed",
+                JobState::Failed => "failed",
+                _ => "cancelled",
+            };
+            crate::metrics::job(&job.spec.kind(), outcome, began.elapsed());
+            match job.state {
+                JobState::Completed => {
+                    crate::usage::count(&format!("{}_completed", job.spec.kind()))
+                }
+                JobState::Failed => crate::usage::count(&format!("{}_failed", job.spec.kind())),
+                _ => {}
+            }
+        }
+        emit_updated(app, &job);
+    }
+    if let Some(delay) = retry_in {
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+            wake().notify_one();
+        });
+    }
+    prune(&mut lock());
+}
+
+async fn worker(app: AppHandle) {
+    loop {
+        match take_next(&app) {
+            Some(job) => run(&app, job).await,
+            None => {
+                tokio::select! {
+                    _ = wake().notified() => {}
+                    _ = tokio::time::sleep(RECHECK_INTERVAL) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Load jobs left over from the last session. Ones that were running go back
+/// to the queue.
+fn restore(app: &AppHandle) {
+    let mut resumed = Vec::new();
+    {
+        let mut jobs = lock();
+        for mut job in store::load() {
+            if jobs.iter().any(|j| j.id == job.id) {
+                continue;
+            }
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+                job.started_at = None;
+                job.progress = None;
+                job.message = Some("Resumed after restart".to_string());
+            }
+            if !job.state.is_terminal() {
+                resumed.push(job.clone());
+            }
+            jobs.push(job);
+        }
+        jobs.sort_by_key(|j| j.created_at);
+        prune(&mut jobs);
+    }
+    if !resumed.is_empty() {
+        tracing::info!("[Jobs] Resuming {} job(s) from last session", resumed.len());
+    }
+    for job in &resumed {
+        store::save(job);
+        emit_updated(app, job);
+    }
+    wake().notify_waiters();
+}
+
+/// Wake the workers after a pause is lifted.
+pub(crate) fn resume() {
+    wake().notify_waiters();
+}
+
+/// Open the job store and start the worker pool. Called once from setup.
+pub(crate) fn start(app: &AppHandle) {
+    match crate::data_dir(app) {
+        Ok(dir) => {
+            if let Err(e) = store::open(&dir.join("jobs.db")) {
+                tracing::warn!("[Jobs] {} — jobs won't survive a restart", e);
+            }
+        }
+        Err(e) => tracing::warn!("[Jobs] {} — jobs won't survive a restart", e),
+    }
+    // Leftover work mostly talks to the backend, so wait until it's up.
+    let restore_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while !crate::backend_ready(&restore_app) {
+            tokio::time::sleep(BACKEND_POLL_INTERVAL).await;
+        }
+        restore(&restore_app);
+    });
+    for _ in 0..WORKERS {
+        tauri::async_runtime::spawn(worker(app.clone()));
+    }
+}
+
+/// The latest queued or running job with this idempotency key, which a new
+/// request with the key gets instead of a second job.
+fn unfinished_with_key<'a>(jobs: &'a [Job], key: &str) -> Option<&'a Job> {
+    jobs.iter().rev().find(|j| {
+        j.idempotency_key.as_deref() == Some(key)
+            && matches!(j.state, JobState::Queued | JobState::Running)
+    })
+}
+
+/// Queue a job and return it (state "queued"). If `idempotency_key` (or the
+/// spec's default key) matches a job that is queued or running, that job is
+/// returned instead.
+pub(crate) fn enqueue(
+    app: &AppHandle,
+    spec: JobSpec,
+    idempotency_key: Option<String>,
+) -> Result<Job, String> {
+    spec.validate()?;
+    spec.check_scope(app)?;
+    let idempotency_key = idempotency_key.or_else(|| spec.default_key());
+    let mut jobs = lock();
+    if let Some(key) = &idempotency_key {
+        if let Some(existing) = unfinished_with_key(&jobs, key) {
+            tracing::info!("[Jobs] {} already has job {}", key, existing.id);
+            return Ok(existing.clone());
+        }
+    }
+    let job = Job {
+        id: uuid::Uuid::new_v4().to_string(),
+        idempotency_key,
+        spec,
+        state: JobState::Queued,
+        progress: None,
+        message: None,
+        created_at: crate::models::now_secs(),
+        started_at: None,
+        finished_at: None,
+        result: None,
+        error: None,
+        attempts: 0,
+        retry_at: None,
+        cancel: CancellationToken::new(),
+    };
+    jobs.push(job.clone());
+    drop(jobs);
+    store::save(&job);
+    emit_updated(app, &job);
+    wake().notify_one();
+    Ok(job)
+}
+
+#[tauri::command]
+pub(crate) async fn enqueue_job(
+    app: AppHandle,
+    spec: JobSpec,
+    idempotency_key: Option<String>,
+) -> Result<Job, String> {
+    enqueue(&app, spec, idempotency_key)
+}
+
+/// Jobs in creation order, optionally only those in one state.
+#[tauri::command]
+pub(crate) async fn list_jobs(state: Option<JobState>) -> Result<Vec<Job>, String> {
+    Ok(lock()
+        .iter()
+        .filter(|j| state.is_none_or(|s| j.state == s))
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub(crate) async fn get_job(id: String) -> Result<Job, String> {
+    lock()
+        .iter()
+        .find(|j| j.id == id)
+        .cloned()
+        .ok_or_else(|| format!("No job {}", id))
+}
+
+/// Cancel a queued or running job. A running job reports `cancelled` once its
+/// work has stopped.
+#[tauri::command]
+pub(crate) async fn cancel_job(app: AppHandle, id: String) -> Result<Job, String> {
+    let mut jobs = lock();
+    let job = jobs
+        .iter_mut()
+        .find(|j| j.id == id)
+        .ok_or_else(|| format!("No job {}", id))?;
+    match job.state {
+        JobState::Queued => {
+            job.state = JobState::Cancelled;
+            job.finished_at = Some(crate::models::now_secs());
+        }
+        JobState::Running => {}
+        _ => return Err(format!("Job {} has already finished", id)),
+    }
+    job.cancel.cancel();
+    let job = job.clone();
+    drop(jobs);
+    tracing::info!("[Jobs] {} cancel requested", id);
+    if job.state.is_terminal() {
+        store::save(&job);
+        store::record(&job);
+        emit_updated(&app, &job);
+    }
+    Ok(job)
+}
+
+#[tauri::command]
+pub(crate) async fn get_job_concurrency(app: AppHandle) -> Result<JobConcurrency, String> {
+    Ok(crate::settings::get(&app).jobs.concurrency)
+}
+
+/// Set how many jobs of each category may run at once (1 to 8 each).
+#[tauri::command]
+pub(crate) async fn set_job_concurrency(
+    app: AppHandle,
+    concurrency: JobConcurrency,
+) -> Result<JobConcurrency, String> {
+    let limits = [
+        concurrency.imports,
+        concurrency.downloads,
+        concurrency.transcriptions,
+        concurrency.ocr,
+        concurrency.maintenance,
+    ];
+    if limits.iter().any(|n| !(1..=WORKERS).contains(n)) {
+        return Err(format!(
+            "Concurrency limits must be between 1 and {}",
+            WORKERS
+        ));
+    }
+    let settings = crate::settings::update(&app, |s| s.jobs.concurrency = concurrency)?;
+    // Raised limits may let queued jobs start.
+    wake().notify_waiters();
+    Ok(settings.jobs.concurrency)
+}
+
+/// Finished jobs from the last 90 days, newest first.
+#[tauri::command]
+pub(crate) async fn get_job_history(
+    filter: Option<HistoryFilter>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let filter = filter.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || store::history(&filter))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Failed jobs finished since `since` (Unix seconds), newest first.
+pub(crate) async fn recent_failures(since: u64) -> Result<Vec<HistoryEntry>, String> {
+    get_job_history(Some(HistoryFilter {
+        state: Some(JobState::Failed),
+        since: Some(since),
+        limit: Some(50),
+        ..HistoryFilter::default()
+    }))
+    .await
+}
+
+/// The retry policy in effect for every job kind.
+#[tauri::command]
+pub(crate) async fn get_retry_policies(
+    app: AppHandle,
+) -> Result<std::collections::HashMap<String, RetryPolicy>, String> {
+    Ok(KINDS
+        .iter()
+        .map(|kind| (kind.to_string(), retry_policy(&app, kind)))
+        .collect())
+}
+
+/// Override the retry policy for a job kind; None restores the built-in one.
+#[tauri::command]
+pub(crate) async fn set_retry_policy(
+    app: AppHandle,
+    kind: String,
+    policy: Option<RetryPolicy>,
+) -> Result<RetryPolicy, String> {
+    if !KINDS.contains(&kind.as_str()) {
+        return Err(format!("Unknown job kind: {}", kind));
+    }
+    if let Some(p) = &policy {
+        if !(1..=20).contains(&p.max_attempts) {
+            return Err("Max attempts must be between 1 and 20".to_string());
+        }
+        if p.backoff_secs == 0 || p.max_backoff_secs < p.backoff_secs {
+            return Err(
+                "Backoff must be at least 1 second and no more than the maximum".to_string(),
+            );
+        }
+    }
+    crate::settings::update(&app, |s| match policy {
+        Some(p) => {
+            s.jobs.retry.insert(kind.clone(), p);
+        }
+        None => {
+            s.jobs.retry.remove(&kind);
+        }
+    })?;
+    Ok(retry_policy(&app, &kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, key: Option<&str>, state: JobState) -> Job {
+        Job {
+            id: id.to_string(),
+            idempotency_key: key.map(str::to_string),
+            spec: JobSpec::Backup { dest_dir: None },
+            state,
+            progress: None,
+            message: None,
+            created_at: 0,
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+            attempts: 0,
+            retry_at: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    fn found<'a>(jobs: &'a [Job], key: &str) -> Option<&'a str> {
+        unfinished_with_key(jobs, key).map(|j| j.id.as_str())
+    }
+
+    #[test]
+    fn a_queued_or_running_job_is_reused() {
+        let jobs = [
+            job("a", Some("backup"), JobState::Queued),
+            job("b", Some("other"), JobState::Running),
+        ];
+        assert_eq!(found(&jobs, "backup"), Some("a"));
+        assert_eq!(found(&jobs, "other"), Some("b"));
+        assert_eq!(found(&jobs, "missing"), None);
+    }
+
+    #[test]
+    fn finished_jobs_are_not_reused() {
+        let jobs = [
+            job("a", Some("backup"), JobState::Completed),
+            job("b", Some("backup"), JobState::Failed),
+            job("c", Some("backup"), JobState::Cancelled),
+            job("d", None, JobState::Queued),
+        ];
+        assert_eq!(found(&jobs, "backup"), None);
+    }
+
+    #[test]
+    fn the_latest_match_wins() {
+        let jobs = [
+            job("a", Some("backup"), JobState::Running),
+            job("b", Some("backup"), JobState::Queued),
+        ];
+        assert_eq!(found(&jobs, "backup"), Some("b"));
+    }
+}
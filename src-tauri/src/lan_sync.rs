@@ -0,0 +1,996 @@
+//! Sync over the local network: discovery, pairing and the encrypted channel.
+//!
+//! With LAN sync on, the app listens on a random TCP port and advertises it
+//! over mDNS as `_localbook-sync._tcp`, with its device id and name. Pairing
+//! runs SPAKE2 over a six-digit code that one device shows
+//! (`start_sync_pairing`) and the other types in (`pair_sync_device`): the
+//! code is never sent, a wrong guess closes the pairing window, and both
+//! sides end up with a shared secret kept in the keychain. Every later
+//! session derives fresh per-direction keys from that secret and both sides'
+//! nonces and encrypts each frame with ChaCha20-Poly1305, so a device that
+//! isn't paired can neither read nor send anything. Until a frame from the
+//! other side has decrypted (each side's first is a `Ready`), frames are
+//! capped at `MAX_UNAUTHENTICATED_FRAME`, so an unpaired device can't make
+//! this one set aside room for a large one.
+//!
+//! In a session each side in turn lists what the other shares and fetches
+//! what changed (see `sync`), the caller first. A handoff session carries a
+//! chat instead (see `handoff`), and a backend session hands a device
+//! without a backend the way to this one's (see `backend_share`). Local-only
+//! mode keeps all of it off.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use spake2::{Ed25519Group, Identity, Password, Spake2};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::handoff::HandoffSession;
+use crate::sync::{self, Applied, Offer, Peer};
+use crate::vault::SealedNotebook;
+
+const SERVICE_TYPE: &str = "_localbook-sync._tcp.local.";
+const PAIRING_WINDOW: Duration = Duration::from_secs(120);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Per frame; a large notebook can take a while to export on the far side.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_FRAME: u32 = 256 * 1024 * 1024;
+/// Until the other side has proved it holds the session keys.
+const MAX_UNAUTHENTICATED_FRAME: u32 = 64 * 1024;
+const NONCE_LEN: usize = 32;
+
+struct Lan {
+    daemon: ServiceDaemon,
+    listener: tauri::async_runtime::JoinHandle<()>,
+}
+
+#[derive(Clone)]
+struct Discovered {
+    fullname: String,
+    name: String,
+    addrs: Vec<IpAddr>,
+    port: u16,
+}
+
+struct Pairing {
+    code: String,
+    expires: Instant,
+}
+
+static LAN: Mutex<Option<Lan>> = Mutex::new(None);
+/// Devices seen on the network, by device id.
+static DISCOVERED: Mutex<Option<HashMap<String, Discovered>>> = Mutex::new(None);
+static PAIRING: Mutex<Option<Pairing>> = Mutex::new(None);
+/// One session at a time, either direction.
+static SESSION: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Serialize)]
+pub(crate) struct SyncDevice {
+    id: String,
+    name: String,
+    paired: bool,
+    /// Seen on the network right now.
+    online: bool,
+    /// Unix seconds.
+    last_synced_at: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PairingCode {
+    code: String,
+    expires_in_secs: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SyncReport {
+    /// Notebooks taken from the peer.
+    received: usize,
+    /// Notebooks the peer took from this device.
+    sent: usize,
+    /// Notebooks changed on both devices, left as they are here.
+    conflicts: usize,
+}
+
+/// The first, unencrypted message of a connection.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Hello {
+    Pair {
+        device_id: String,
+        name: String,
+        spake: String,
+    },
+    Sync {
+        device_id: String,
+        nonce: String,
+    },
+    /// A session that only carries a chat handed over (see `handoff`).
+    Handoff {
+        device_id: String,
+        nonce: String,
+    },
+    /// A device without a backend asking to use this one (see
+    /// `backend_share`).
+    Backend {
+        device_id: String,
+        nonce: String,
+    },
+}
+
+/// What a session is for, from the caller's side.
+#[derive(Clone, Copy)]
+enum Purpose {
+    Sync,
+    Handoff,
+    Backend,
+}
+
+/// Unencrypted replies during the handshake.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reply {
+    Pair { name: String, spake: String },
+    Confirm { mac: String },
+    Sync { nonce: String },
+    Refused { reason: String },
+}
+
+/// Encrypted session messages.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Msg {
+    List,
+    Offers {
+        offers: Vec<Offer>,
+    },
+    Get {
+        id: String,
+    },
+    Notebook {
+        notebook: SealedNotebook,
+    },
+    Handoff {
+        session: HandoffSession,
+    },
+    /// Where to reach the backend forwarded for the caller, and its token.
+    Backend {
+        port: u16,
+        token: String,
+    },
+    Refused {
+        reason: String,
+    },
+    /// First message each way once a session is encrypted.
+    Ready,
+    /// The caller is done fetching; the other side fetches now.
+    YourTurn,
+    Done,
+}
+
+fn b64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn unb64(text: &str) -> Result<Vec<u8>, String> {
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(|_| "Malformed message from the other device".to_string())
+}
+
+fn derive(secret: &[u8], parts: &[&[u8]], label: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(secret);
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.update(label.as_bytes());
+    hasher.finalize().into()
+}
+
+fn random_bytes() -> [u8; NONCE_LEN] {
+    let mut bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// One direction of an encrypted channel; the nonce is a frame counter.
+struct Cipher {
+    aead: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl Cipher {
+    fn new(key: [u8; 32]) -> Self {
+        Self {
+            aead: ChaCha20Poly1305::new(&key.into()),
+            counter: 0,
+        }
+    }
+
+    fn next_nonce(&mut self) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter += 1;
+        nonce.into()
+    }
+}
+
+struct Channel {
+    stream: TcpStream,
+    send_cipher: Option<Cipher>,
+    recv_cipher: Option<Cipher>,
+    /// A frame from the other side has decrypted.
+    authenticated: bool,
+}
+
+impl Channel {
+    fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            send_cipher: None,
+            recv_cipher: None,
+            authenticated: false,
+        }
+    }
+
+    fn encrypt(&mut self, send: [u8; 32], recv: [u8; 32]) {
+        self.send_cipher = Some(Cipher::new(send));
+        self.recv_cipher = Some(Cipher::new(recv));
+    }
+
+    async fn send<T: Serialize>(&mut self, msg: &T) -> Result<(), String> {
+        let mut bytes = serde_json::to_vec(msg).map_err(|e| e.to_string())?;
+        if let Some(cipher) = &mut self.send_cipher {
+            let nonce = cipher.next_nonce();
+            bytes = cipher
+                .aead
+                .encrypt(&nonce, bytes.as_slice())
+                .map_err(|_| "Encryption failed".to_string())?;
+        }
+        let len = u32::try_from(bytes.len()).map_err(|_| "Message too large".to_string())?;
+        let write = async {
+            self.stream.write_u32(len).await?;
+            self.stream.write_all(&bytes).await?;
+            self.stream.flush().await
+        };
+        tokio::time::timeout(FRAME_TIMEOUT, write)
+            .await
+            .map_err(|_| "The other device stopped responding".to_string())?
+            .map_err(|e| format!("Connection lost: {}", e))
+    }
+
+    async fn recv<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+        let max = if self.authenticated {
+            MAX_FRAME
+        } else {
+            MAX_UNAUTHENTICATED_FRAME
+        };
+        let read = async {
+            let len = self.stream.read_u32().await?;
+            if len > max {
+                return Err(std::io::Error::other("frame too large"));
+            }
+            let mut bytes = vec![0u8; len as usize];
+            self.stream.read_exact(&mut bytes).await?;
+            Ok(bytes)
+        };
+        let mut bytes = tokio::time::timeout(FRAME_TIMEOUT, read)
+            .await
+            .map_err(|_| "The other device stopped responding".to_string())?
+            .map_err(|e| format!("Connection lost: {}", e))?;
+        if let Some(cipher) = &mut self.recv_cipher {
+            let nonce = cipher.next_nonce();
+            bytes = cipher
+                .aead
+                .decrypt(&nonce, bytes.as_slice())
+                .map_err(|_| "The other device isn't paired with this one".to_string())?;
+            self.authenticated = true;
+        }
+        serde_json::from_slice(&bytes)
+            .map_err(|_| "Malformed message from the other device".to_string())
+    }
+}
+
+fn discovered() -> std::sync::MutexGuard<'static, Option<HashMap<String, Discovered>>> {
+    DISCOVERED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+async fn load_secret(peer_id: &str) -> Result<Vec<u8>, String> {
+    let name = sync::peer_secret_name(peer_id);
+    let secret = tauri::async_runtime::spawn_blocking(move || crate::secrets::get(&name))
+        .await
+        .map_err(|e| e.to_string())??
+        .ok_or("This device's pairing is missing; pair it again")?;
+    unb64(&secret)
+}
+
+async fn save_peer(app: &AppHandle, id: &str, name: &str, secret: [u8; 32]) -> Result<(), String> {
+    let key = sync::peer_secret_name(id);
+    let value = b64(&secret);
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::set(&key, &value))
+        .await
+        .map_err(|e| e.to_string())??;
+    crate::settings::update(app, |s| {
+        s.sync.peers.retain(|p| p.id != id);
+        s.sync.peers.push(Peer {
+            id: id.to_string(),
+            name: name.to_string(),
+            paired_at: crate::models::now_secs(),
+            last_synced_at: None,
+        });
+    })?;
+    tracing::info!("[LanSync] Paired with {}", id);
+    crate::audit::record("sync_paired", id);
+    Ok(())
+}
+
+/// Fetch what changed from the other side of `ch`. Returns how many
+/// notebooks were taken, and how many conflicted.
+async fn pull(app: &AppHandle, ch: &mut Channel, peer: &str) -> Result<(usize, usize), String> {
+    ch.send(&Msg::List).await?;
+    let offers = match ch.recv().await? {
+        Msg::Offers { offers } => offers,
+        Msg::Refused { reason } => return Err(reason),
+        _ => return Err("Unexpected reply from the other device".to_string()),
+    };
+    let wanted = sync::wanted(app, peer, offers);
+    let total = wanted.len();
+    let mut taken = 0;
+    let mut conflicts = 0;
+    for (i, offer) in wanted.iter().enumerate() {
+        sync::progress(app, peer, &offer.title, i, total);
+        ch.send(&Msg::Get {
+            id: offer.id.clone(),
+        })
+        .await?;
+        match ch.recv().await? {
+            Msg::Notebook { notebook } => match sync::apply(app, peer, offer, &notebook).await? {
+                Applied::Imported => taken += 1,
+                Applied::Conflict => conflicts += 1,
+            },
+            Msg::Refused { reason } => tracing::warn!("[LanSync] Skipped a notebook: {}", reason),
+            _ => return Err("Unexpected reply from the other device".to_string()),
+        }
+    }
+    sync::progress(app, peer, "", total, total);
+    Ok((taken, conflicts))
+}
+
+/// Answer the other side's requests until it hands over or finishes.
+/// Returns how many notebooks it took.
+async fn serve(app: &AppHandle, ch: &mut Channel) -> Result<usize, String> {
+    let mut sent = 0;
+    // What the last listing said each notebook's version was.
+    let mut listed: Vec<Offer> = Vec::new();
+    loop {
+        match ch.recv().await? {
+            Msg::List => {
+                listed = sync::offers(app).await;
+                ch.send(&Msg::Offers {
+                    offers: listed.clone(),
+                })
+                .await?;
+            }
+            Msg::Get { id } => {
+                let fingerprint = listed
+                    .iter()
+                    .find(|o| o.id == id)
+                    .map(|o| o.fingerprint.clone());
+                let reply = match fingerprint.filter(|_| sync::is_shared(app, &id)) {
+                    None => Msg::Refused {
+                        reason: "Not shared".to_string(),
+                    },
+                    Some(fingerprint) => match sync::snapshot(app, &id, &fingerprint).await {
+                        Ok(notebook) => {
+                            sent += 1;
+                            Msg::Notebook { notebook }
+                        }
+                        Err(e) => Msg::Refused { reason: e },
+                    },
+                };
+                ch.send(&reply).await?;
+            }
+            Msg::YourTurn | Msg::Done => return Ok(sent),
+            Msg::Refused { reason } => return Err(reason),
+            _ => return Err("Unexpected message from the other device".to_string()),
+        }
+    }
+}
+
+async fn accept_pairing(
+    app: &AppHandle,
+    ch: &mut Channel,
+    peer_id: String,
+    peer_name: String,
+    spake: String,
+) -> Result<(), String> {
+    let code = {
+        let mut pairing = PAIRING.lock().unwrap_or_else(|e| e.into_inner());
+        // One attempt per code: a wrong guess closes the window.
+        match pairing.take() {
+            Some(p) if p.expires > Instant::now() => p.code,
+            _ => return Err("Not accepting pairing requests".to_string()),
+        }
+    };
+    let own_id = sync::device_id(app);
+    let (state, outbound) = Spake2::<Ed25519Group>::start_b(
+        &Password::new(code.as_bytes()),
+        &Identity::new(peer_id.as_bytes()),
+        &Identity::new(own_id.as_bytes()),
+    );
+    let key = state
+        .finish(&unb64(&spake)?)
+        .map_err(|_| "Pairing failed".to_string())?;
+    ch.send(&Reply::Pair {
+        name: sync::device_name(app),
+        spake: b64(&outbound),
+    })
+    .await?;
+    let Reply::Confirm { mac } = ch.recv().await? else {
+        return Err("Pairing failed".to_string());
+    };
+    if unb64(&mac)? != derive(&key, &[], "client-confirm") {
+        return Err("Wrong pairing code".to_string());
+    }
+    ch.send(&Reply::Confirm {
+        mac: b64(&derive(&key, &[], "server-confirm")),
+    })
+    .await?;
+    save_peer(app, &peer_id, &peer_name, derive(&key, &[], "pair-secret")).await
+}
+
+/// Server side of a session handshake: answer a paired device's nonce,
+/// switch to the keys derived from both and trade `Ready`s.
+async fn accept_session(
+    app: &AppHandle,
+    ch: &mut Channel,
+    peer_id: &str,
+    client_nonce: &str,
+) -> Result<(), String> {
+    if sync::peer(app, peer_id).is_none() {
+        return Err("Not paired with this device".to_string());
+    }
+    let secret = load_secret(peer_id).await?;
+    let client_nonce = unb64(client_nonce)?;
+    let server_nonce = random_bytes();
+    ch.send(&Reply::Sync {
+        nonce: b64(&server_nonce),
+    })
+    .await?;
+    let parts: [&[u8]; 2] = [&client_nonce, &server_nonce];
+    ch.encrypt(
+        derive(&secret, &parts, "server-to-client"),
+        derive(&secret, &parts, "client-to-server"),
+    );
+    let Msg::Ready = ch.recv().await? else {
+        return Err("Unexpected message from the other device".to_string());
+    };
+    ch.send(&Msg::Ready).await
+}
+
+/// Client side: connect to a paired device and open an encrypted session.
+async fn open_session(
+    app: &AppHandle,
+    device_id: &str,
+    purpose: Purpose,
+) -> Result<Channel, String> {
+    let secret = load_secret(device_id).await?;
+    let mut ch = connect(device_id).await?;
+    let client_nonce = random_bytes();
+    let (device_id, nonce) = (sync::device_id(app), b64(&client_nonce));
+    ch.send(&match purpose {
+        Purpose::Sync => Hello::Sync { device_id, nonce },
+        Purpose::Handoff => Hello::Handoff { device_id, nonce },
+        Purpose::Backend => Hello::Backend { device_id, nonce },
+    })
+    .await?;
+    let server_nonce = match ch.recv().await? {
+        Reply::Sync { nonce } => unb64(&nonce)?,
+        Reply::Refused { reason } => return Err(reason),
+        _ => return Err("Unexpected reply from the other device".to_string()),
+    };
+    let parts: [&[u8]; 2] = [&client_nonce, &server_nonce];
+    ch.encrypt(
+        derive(&secret, &parts, "client-to-server"),
+        derive(&secret, &parts, "server-to-client"),
+    );
+    ch.send(&Msg::Ready).await?;
+    match ch.recv().await? {
+        Msg::Ready => Ok(ch),
+        Msg::Refused { reason } => Err(reason),
+        _ => Err("Unexpected reply from the other device".to_string()),
+    }
+}
+
+async fn accept_sync(
+    app: &AppHandle,
+    ch: &mut Channel,
+    peer_id: String,
+    client_nonce: String,
+) -> Result<(), String> {
+    let Ok(_session) = SESSION.try_lock() else {
+        return Err("Already syncing".to_string());
+    };
+    accept_session(app, ch, &peer_id, &client_nonce).await?;
+    sync::begin(app, &peer_label(app, &peer_id));
+    let result = async {
+        let sent = serve(app, ch).await?;
+        let (received, _) = pull(app, ch, &peer_id).await?;
+        ch.send(&Msg::Done).await?;
+        Ok::<_, String>((received, sent))
+    }
+    .await;
+    sync::finish(app, result.as_ref().err());
+    let (received, sent) = result?;
+    sync::mark_synced(app, &peer_id);
+    tracing::info!(
+        "[LanSync] Session with {}: {} received, {} sent",
+        peer_id,
+        received,
+        sent
+    );
+    Ok(())
+}
+
+async fn accept_handoff(
+    app: &AppHandle,
+    ch: &mut Channel,
+    peer_id: String,
+    client_nonce: String,
+) -> Result<(), String> {
+    accept_session(app, ch, &peer_id, &client_nonce).await?;
+    let Msg::Handoff { session } = ch.recv().await? else {
+        return Err("Unexpected message from the other device".to_string());
+    };
+    crate::handoff::receive(app, &peer_id, session)?;
+    ch.send(&Msg::Done).await
+}
+
+async fn accept_backend(
+    app: &AppHandle,
+    ch: &mut Channel,
+    peer_id: String,
+    client_nonce: String,
+) -> Result<(), String> {
+    accept_session(app, ch, &peer_id, &client_nonce).await?;
+    let peer_ip = ch.stream.peer_addr().map_err(|e| e.to_string())?.ip();
+    let port = crate::backend_share::grant(&peer_id, peer_ip).await?;
+    let token = crate::read_app_token().await?;
+    ch.send(&Msg::Backend { port, token }).await
+}
+
+fn peer_label(app: &AppHandle, peer_id: &str) -> String {
+    sync::peer(app, peer_id).map_or_else(|| peer_id.to_string(), |p| p.name)
+}
+
+async fn handle(app: AppHandle, stream: TcpStream) {
+    let mut ch = Channel::new(stream);
+    let result = async {
+        if crate::privacy::local_only() {
+            return Err("Local-only mode is on".to_string());
+        }
+        match ch.recv::<Hello>().await? {
+            Hello::Pair {
+                device_id,
+                name,
+                spake,
+            } => accept_pairing(&app, &mut ch, device_id, name, spake).await,
+            Hello::Sync { device_id, nonce } => accept_sync(&app, &mut ch, device_id, nonce).await,
+            Hello::Handoff { device_id, nonce } => {
+                accept_handoff(&app, &mut ch, device_id, nonce).await
+            }
+            Hello::Backend { device_id, nonce } => {
+                accept_backend(&app, &mut ch, device_id, nonce).await
+            }
+        }
+    }
+    .await;
+    if let Err(reason) = result {
+        tracing::warn!("[LanSync] Refused a connection: {}", reason);
+        if ch.send_cipher.is_none() {
+            let _ = ch.send(&Reply::Refused { reason }).await;
+        } else {
+            let _ = ch.send(&Msg::Refused { reason }).await;
+        }
+    }
+}
+
+/// Open a connection to a discovered device.
+async fn connect(device_id: &str) -> Result<Channel, String> {
+    let device = discovered()
+        .as_ref()
+        .and_then(|d| d.get(device_id).cloned())
+        .ok_or("That device isn't on the network right now")?;
+    // IPv4 first: it's what a backend forwarded over the same route listens on.
+    let mut addrs = device.addrs.clone();
+    addrs.sort_by_key(|ip| ip.is_ipv6());
+    let mut last_error = String::new();
+    for ip in &addrs {
+        let addr = SocketAddr::new(*ip, device.port);
+        crate::privacy::guard(&format!("http://{}", addr), "LAN sync")?;
+        match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => return Ok(Channel::new(stream)),
+            Ok(Err(e)) => last_error = e.to_string(),
+            Err(_) => last_error = "timed out".to_string(),
+        }
+    }
+    Err(format!("Couldn't reach {}: {}", device.name, last_error))
+}
+
+fn advertise(daemon: &ServiceDaemon, app: &AppHandle, port: u16) -> Result<(), String> {
+    let id = sync::device_id(app);
+    let name = sync::device_name(app);
+    let host = format!("localbook-{}.local.", &id[..id.len().min(12)]);
+    let properties = [("id", id.as_str()), ("name", name.as_str())];
+    let info = ServiceInfo::new(SERVICE_TYPE, &id, &host, (), port, &properties[..])
+        .map_err(|e| format!("Failed to advertise: {}", e))?
+        .enable_addr_auto();
+    daemon
+        .register(info)
+        .map_err(|e| format!("Failed to advertise: {}", e))
+}
+
+fn browse(daemon: &ServiceDaemon, own_id: String) -> Result<(), String> {
+    let events = daemon
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse: {}", e))?;
+    std::thread::spawn(move || {
+        // Ends when the daemon shuts down.
+        while let Ok(event) = events.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some(id) = info.get_property_val_str("id").map(String::from) else {
+                        continue;
+                    };
+                    if id == own_id {
+                        continue;
+                    }
+                    let device = Discovered {
+                        fullname: info.get_fullname().to_string(),
+                        name: info
+                            .get_property_val_str("name")
+                            .unwrap_or("LocalBook")
+                            .to_string(),
+                        addrs: info.get_addresses().iter().copied().collect(),
+                        port: info.get_port(),
+                    };
+                    discovered()
+                        .get_or_insert_with(HashMap::new)
+                        .insert(id, device);
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    if let Some(d) = discovered().as_mut() {
+                        d.retain(|_, dev| dev.fullname != fullname);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn enable(app: &AppHandle) -> Result<(), String> {
+    if LAN.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+        return Ok(());
+    }
+    if crate::privacy::local_only() {
+        return Err("Local-only mode is on".to_string());
+    }
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .await
+        .map_err(|e| format!("Failed to listen for sync: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS unavailable: {}", e))?;
+    advertise(&daemon, app, port)?;
+    browse(&daemon, sync::device_id(app))?;
+    let app = app.clone();
+    let listener = tauri::async_runtime::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(handle(app.clone(), stream));
+                }
+                Err(e) => {
+                    tracing::warn!("[LanSync] Accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    });
+    *LAN.lock().unwrap_or_else(|e| e.into_inner()) = Some(Lan { daemon, listener });
+    tracing::info!("[LanSync] Listening on port {}", port);
+    Ok(())
+}
+
+fn disable() {
+    let Some(lan) = LAN.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    lan.listener.abort();
+    let _ = lan.daemon.shutdown();
+    *discovered() = None;
+    tracing::info!("[LanSync] Stopped");
+}
+
+/// Re-advertise after the device name changed.
+pub(crate) async fn refresh(app: &AppHandle) {
+    if LAN.lock().unwrap_or_else(|e| e.into_inner()).is_none() {
+        return;
+    }
+    disable();
+    if let Err(e) = enable(app).await {
+        tracing::warn!("[LanSync] {}", e);
+    }
+}
+
+/// Start advertising if LAN sync is on. Called in setup.
+pub(crate) fn start(app: &AppHandle) {
+    if !crate::settings::get(app).sync.lan_enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = enable(&app).await {
+            tracing::warn!("[LanSync] {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn set_lan_sync(app: AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled {
+        enable(&app).await?;
+    } else {
+        disable();
+    }
+    crate::settings::update(&app, |s| s.sync.lan_enabled = enabled)?;
+    Ok(())
+}
+
+/// Paired devices and devices seen on the network.
+#[tauri::command]
+pub(crate) async fn list_sync_devices(app: AppHandle) -> Result<Vec<SyncDevice>, String> {
+    let seen = discovered().clone().unwrap_or_default();
+    let mut devices: Vec<SyncDevice> = crate::settings::get(&app)
+        .sync
+        .peers
+        .into_iter()
+        .map(|p| SyncDevice {
+            online: seen.contains_key(&p.id),
+            name: seen.get(&p.id).map_or(p.name, |d| d.name.clone()),
+            paired: true,
+            last_synced_at: p.last_synced_at,
+            id: p.id,
+        })
+        .collect();
+    for (id, device) in seen {
+        if !devices.iter().any(|d| d.id == id) {
+            devices.push(SyncDevice {
+                id,
+                name: device.name,
+                paired: false,
+                online: true,
+                last_synced_at: None,
+            });
+        }
+    }
+    devices.sort_by(|a, b| b.paired.cmp(&a.paired).then(a.name.cmp(&b.name)));
+    Ok(devices)
+}
+
+/// Show a code for another device to pair with this one.
+#[tauri::command]
+pub(crate) async fn start_sync_pairing(app: AppHandle) -> Result<PairingCode, String> {
+    enable(&app).await?;
+    let code = format!("{:06}", OsRng.next_u32() % 1_000_000);
+    *PAIRING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Pairing {
+        code: code.clone(),
+        expires: Instant::now() + PAIRING_WINDOW,
+    });
+    Ok(PairingCode {
+        code,
+        expires_in_secs: PAIRING_WINDOW.as_secs(),
+    })
+}
+
+/// Pair with a discovered device using the code it shows.
+#[tauri::command]
+pub(crate) async fn pair_sync_device(
+    app: AppHandle,
+    device_id: String,
+    code: String,
+) -> Result<(), String> {
+    let code = code.trim();
+    let mut ch = connect(&device_id).await?;
+    let own_id = sync::device_id(&app);
+    let (state, outbound) = Spake2::<Ed25519Group>::start_a(
+        &Password::new(code.as_bytes()),
+        &Identity::new(own_id.as_bytes()),
+        &Identity::new(device_id.as_bytes()),
+    );
+    ch.send(&Hello::Pair {
+        device_id: own_id,
+        name: sync::device_name(&app),
+        spake: b64(&outbound),
+    })
+    .await?;
+    let (name, spake) = match ch.recv().await? {
+        Reply::Pair { name, spake } => (name, spake),
+        Reply::Refused { reason } => return Err(reason),
+        _ => return Err("Pairing failed".to_string()),
+    };
+    let key = state
+        .finish(&unb64(&spake)?)
+        .map_err(|_| "Pairing failed".to_string())?;
+    ch.send(&Reply::Confirm {
+        mac: b64(&derive(&key, &[], "client-confirm")),
+    })
+    .await?;
+    match ch.recv().await {
+        Ok(Reply::Confirm { mac }) if unb64(&mac)? == derive(&key, &[], "server-confirm") => {}
+        Ok(Reply::Refused { reason }) => return Err(reason),
+        _ => return Err("Wrong pairing code".to_string()),
+    }
+    save_peer(&app, &device_id, &name, derive(&key, &[], "pair-secret")).await
+}
+
+#[tauri::command]
+pub(crate) async fn unpair_sync_device(app: AppHandle, device_id: String) -> Result<(), String> {
+    let name = sync::peer_secret_name(&device_id);
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::delete(&name))
+        .await
+        .map_err(|e| e.to_string())??;
+    crate::settings::update(&app, |s| s.sync.peers.retain(|p| p.id != device_id))?;
+    crate::backend_share::revoke(&device_id);
+    crate::audit::record("sync_unpaired", &device_id);
+    Ok(())
+}
+
+/// Sync shared notebooks both ways with a paired device on the network.
+#[tauri::command]
+pub(crate) async fn sync_with_device(
+    app: AppHandle,
+    device_id: String,
+) -> Result<SyncReport, String> {
+    if sync::peer(&app, &device_id).is_none() {
+        return Err("Pair with this device first".to_string());
+    }
+    let _session = SESSION
+        .try_lock()
+        .map_err(|_| "A sync is already running".to_string())?;
+    sync::begin(&app, &peer_label(&app, &device_id));
+    let result = async {
+        let mut ch = open_session(&app, &device_id, Purpose::Sync).await?;
+        let (received, conflicts) = pull(&app, &mut ch, &device_id).await?;
+        ch.send(&Msg::YourTurn).await?;
+        let sent = serve(&app, &mut ch).await?;
+        Ok((received, conflicts, sent))
+    }
+    .await;
+    sync::finish(&app, result.as_ref().err());
+    let (received, conflicts, sent) = result?;
+    sync::mark_synced(&app, &device_id);
+    tracing::info!(
+        "[LanSync] Synced with {}: {} received, {} sent",
+        device_id,
+        received,
+        sent
+    );
+    Ok(SyncReport {
+        received,
+        sent,
+        conflicts,
+    })
+}
+
+/// Send a chat session to a paired device on the network (see `handoff`).
+pub(crate) async fn send_handoff(
+    app: &AppHandle,
+    device_id: &str,
+    session: HandoffSession,
+) -> Result<(), String> {
+    let mut ch = open_session(app, device_id, Purpose::Handoff).await?;
+    ch.send(&Msg::Handoff { session }).await?;
+    match ch.recv().await? {
+        Msg::Done => Ok(()),
+        Msg::Refused { reason } => Err(reason),
+        _ => Err("Unexpected reply from the other device".to_string()),
+    }
+}
+
+/// Ask a paired device for access to its backend. Returns the backend's
+/// URL and app token.
+pub(crate) async fn request_backend(
+    app: &AppHandle,
+    device_id: &str,
+) -> Result<(String, String), String> {
+    let mut ch = open_session(app, device_id, Purpose::Backend).await?;
+    let host = ch.stream.peer_addr().map_err(|e| e.to_string())?.ip();
+    if host.is_ipv6() {
+        return Err("Using another device's backend needs an IPv4 network".to_string());
+    }
+    match ch.recv().await? {
+        Msg::Backend { port, token } => Ok((format!("http://{}:{}", host, port), token)),
+        Msg::Refused { reason } => Err(reason),
+        _ => Err("Unexpected reply from the other device".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn pair() -> (Channel, Channel) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (
+            Channel::new(client.unwrap()),
+            Channel::new(server.unwrap().0),
+        )
+    }
+
+    #[tokio::test]
+    async fn encrypted_frames_round_trip() {
+        let (mut a, mut b) = pair().await;
+        a.encrypt([1; 32], [2; 32]);
+        b.encrypt([2; 32], [1; 32]);
+        for _ in 0..2 {
+            a.send(&Msg::Get {
+                id: "nb".to_string(),
+            })
+            .await
+            .unwrap();
+            assert!(matches!(b.recv().await.unwrap(), Msg::Get { id } if id == "nb"));
+        }
+        assert!(b.authenticated);
+        b.send(&Msg::Done).await.unwrap();
+        assert!(matches!(a.recv().await.unwrap(), Msg::Done));
+    }
+
+    #[tokio::test]
+    async fn a_different_key_is_refused() {
+        let (mut a, mut b) = pair().await;
+        a.encrypt([1; 32], [2; 32]);
+        b.encrypt([2; 32], [3; 32]);
+        a.send(&Msg::Ready).await.unwrap();
+        assert!(b.recv::<Msg>().await.is_err());
+        assert!(!b.authenticated);
+    }
+
+    #[tokio::test]
+    async fn large_frames_wait_for_authentication() {
+        let (mut a, mut b) = pair().await;
+        a.stream
+            .write_u32(MAX_UNAUTHENTICATED_FRAME + 1)
+            .await
+            .unwrap();
+        let Err(err) = b.recv::<Msg>().await else {
+            panic!("an oversized frame was read");
+        };
+        assert!(err.contains("frame too large"), "{}", err);
+
+        let (mut a, mut b) = pair().await;
+        b.authenticated = true;
+        let name = "x".repeat(MAX_UNAUTHENTICATED_FRAME as usize);
+        a.send(&Msg::Refused {
+            reason: name.clone(),
+        })
+        .await
+        .unwrap();
+        assert!(matches!(b.recv().await.unwrap(), Msg::Refused { reason } if reason == name));
+    }
+}
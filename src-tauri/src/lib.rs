@@ -1,26 +1,145 @@
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
 use std::time::Duration;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::Serialize;
-
+use tracing::{debug, error, info, warn, Instrument};
+
+mod about;
+mod appearance;
+mod audit;
+mod backend_api;
+mod backend_events;
+mod backend_share;
+mod backup;
+mod certs;
+mod cli;
+mod conflicts;
+mod content_uri;
+mod context;
+mod crash;
+mod debug_mode;
+mod default_dirs;
+mod diagnostics;
+mod doctor;
+mod folder_sync;
+mod handoff;
+mod hardening;
+mod hardware;
+mod health_history;
+mod hf;
+mod i18n;
+mod idle;
+mod inference;
+mod jobs;
+mod lan_sync;
+mod llama;
+mod lock;
+mod logging;
+mod mcp;
+mod memory;
+mod metrics;
+mod model_prefs;
+mod models;
+mod network;
+mod notebook_settings;
+mod ollama;
+mod onboarding;
+mod pause;
+mod perf_profile;
+mod power;
+mod privacy;
+mod providers;
+mod proxy;
+mod pyenv;
+mod quarantine;
+mod remote_backend;
+mod remote_sync;
+mod rerank;
+mod rpc;
+mod scheduler;
+mod scope;
+mod scripting;
+mod secrets;
+mod serve;
+mod settings;
+mod settings_io;
+mod settings_migrate;
+mod share;
+mod shortcuts;
+mod shred;
+mod sidecar;
+mod startup;
+mod slow_requests;
+mod splash;
+mod sync;
+mod theme;
+mod themes;
+mod titlebar;
+mod traces;
 mod tray;
+mod updater;
+mod usage;
+mod vault;
+mod versioning;
+mod voices;
+mod warmup;
+mod whisper;
+mod windows;
 
 /// Tray "Restart Backend": kill the running backend (by name/port) and re-spawn.
 /// Lives here (same module as the private lifecycle fns) so tray.rs can call it.
 pub(crate) fn restart_backend_from_tray(app: &AppHandle) {
     let app = app.clone();
     tauri::async_runtime::spawn(async move {
-        println!("[Tray] Restarting backend…");
+        info!("[Tray] Restarting backend…");
+        audit::record("backend_restarted", "from the tray");
         kill_existing_backend();
         tokio::time::sleep(Duration::from_millis(600)).await;
         match start_backend(&app).await {
-            Ok(_) => println!("[Tray] Backend restart requested."),
-            Err(e) => eprintln!("[Tray] Backend restart failed: {e}"),
+            Ok(_) => info!("[Tray] Backend restart requested."),
+            Err(e) => error!("[Tray] Backend restart failed: {e}"),
         }
     });
 }
 
+/// Kill the backend and leave it stopped, for when another machine has taken
+/// over the library (folder_sync.rs).
+pub(crate) fn stop_backend() {
+    kill_existing_backend();
+}
+
+/// Switch to a newly configured backend endpoint (remote_backend.rs): restart
+/// onto it and, once it answers, mark the backend ready.
+pub(crate) async fn reconnect_backend(app: &AppHandle) -> Result<(), String> {
+    if let Ok(mut guard) = APP_TOKEN_CACHE.lock() {
+        *guard = None;
+    }
+    restart_backend_and_wait(app, "backend endpoint changed").await?;
+    health_history::transition("ready", "backend endpoint changed");
+    if let Some(state) = app.try_state::<BackendState>() {
+        if let Ok(mut ready) = state.ready.lock() {
+            *ready = true;
+        }
+        if let Ok(mut status) = state.status.lock() {
+            status.stage = "ready".to_string();
+            status.message = "Backend ready".to_string();
+            status.last_error = None;
+        }
+    }
+    Ok(())
+}
+
+/// Kill and re-spawn the backend, then wait for it to pass /health. For
+/// backend updates (sidecar.rs), which need to know the new one came up.
+pub(crate) async fn restart_backend_and_wait(app: &AppHandle, reason: &str) -> Result<(), String> {
+    audit::record("backend_restarted", reason);
+    kill_existing_backend();
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    start_backend(app).await?;
+    wait_for_backend_ready(30).await.map_err(|e| e.to_string())
+}
+
 // P0.1b (2026-05-15) → revised P0.1f (2026-05-21): cache the app token in
 // process memory. CHANGED from OnceLock to Mutex<Option<String>> so we
 // can invalidate the cache when the backend restarts (e.g. after a
@@ -30,13 +149,63 @@ pub(crate) fn restart_backend_from_tray(app: &AppHandle) {
 static APP_TOKEN_CACHE: Mutex<Option<String>> = Mutex::new(None);
 
 fn app_token_file_path() -> PathBuf {
+    backend_data_dir().join(".app_token")
+}
+
+/// The backend's data dir (notebooks database, sources, vector store).
+pub(crate) fn backend_data_dir() -> PathBuf {
+    cli::options()
+        .data_dir
+        .clone()
+        .or_else(folder_sync::library_dir)
+        .unwrap_or_else(default_backend_data_dir)
+}
+
+/// Where the library lives unless it's in a cloud-synced folder (folder_sync.rs)
+/// or given with `--data-dir`; each `--profile` has its own.
+pub(crate) fn default_backend_data_dir() -> PathBuf {
     // Mirrors backend's settings.data_dir = ~/Library/Application Support/LocalBook
     let home = std::env::var("HOME").unwrap_or_default();
-    PathBuf::from(home).join("Library/Application Support/LocalBook/.app_token")
+    let dir = PathBuf::from(home).join("Library/Application Support/LocalBook");
+    match &cli::options().profile {
+        Some(profile) => dir.join("profiles").join(profile),
+        None => dir,
+    }
+}
+
+/// The shell's own data dir (settings, crash log, jobs), created on first
+/// use — under `profiles/<name>` for a `--profile`. Distinct from the
+/// backend's data dir above.
+pub(crate) fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = match &cli::options().profile {
+        Some(profile) => shared_data_dir(app)?.join("profiles").join(profile),
+        None => shared_data_dir(app)?,
+    };
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// The app data dir itself, for what every profile shares: downloaded
+/// models, the Python environment, backend updates.
+pub(crate) fn shared_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
 }
 
 /// Read the app token from the cache, or from disk if cache is empty.
 async fn read_app_token() -> Result<String, String> {
+    // A remote backend's token is in the keychain, not in a file here.
+    if remote_backend::url().is_some() {
+        return remote_backend::token()
+            .await
+            .ok_or_else(|| "No app token set for the remote backend".to_string());
+    }
     // Fast path: cache hit.
     if let Ok(guard) = APP_TOKEN_CACHE.lock() {
         if let Some(t) = guard.as_ref() {
@@ -58,10 +227,13 @@ async fn read_app_token() -> Result<String, String> {
 
 /// Clear the cache and re-read from disk. Called by the webview on 401
 /// so a stale cached token after backend restart can refresh itself.
-async fn refresh_app_token_impl() -> Result<String, String> {
+async fn refresh_app_token_impl(app: &AppHandle) -> Result<String, String> {
     if let Ok(mut guard) = APP_TOKEN_CACHE.lock() {
         *guard = None;
     }
+    if let Some(token) = remote_backend::refresh(app).await {
+        return Ok(token);
+    }
     read_app_token().await
 }
 
@@ -86,12 +258,64 @@ async fn is_backend_ready(state: tauri::State<'_, BackendState>) -> Result<bool,
     Ok(*ready)
 }
 
+/// The sidecar exited on its own (backend_events.rs): it isn't ready
+/// until the watchdog has brought it back.
+pub(crate) fn mark_backend_crashed(app: &AppHandle, exit: &str) {
+    health_history::transition("crashed", format!("process exited ({})", exit));
+    let Some(state) = app.try_state::<BackendState>() else {
+        return;
+    };
+    if let Ok(mut ready) = state.ready.lock() {
+        *ready = false;
+    }
+    if let Ok(mut status) = state.status.lock() {
+        status.stage = "crashed".to_string();
+        status.message = "Backend stopped unexpectedly. Restarting...".to_string();
+        status.last_error = Some(format!("Backend process exited ({})", exit));
+    };
+}
+
+/// How the sidecar `pid` exited, waiting a moment for it to be reapable.
+pub(crate) fn sidecar_exit_status(app: &AppHandle, pid: u32) -> Option<std::process::ExitStatus> {
+    let state = app.try_state::<BackendState>()?;
+    for _ in 0..20 {
+        if let Ok(mut process) = state.process.lock() {
+            match process.as_mut() {
+                Some(child) if child.id() == pid => {
+                    if let Ok(Some(status)) = child.try_wait() {
+                        return Some(status);
+                    }
+                }
+                // Not the tracked process (a tray restart's, say).
+                _ => return None,
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+/// Whether the backend has passed its health check (and hasn't crashed since).
+fn backend_ready(app: &AppHandle) -> bool {
+    app.try_state::<BackendState>()
+        .and_then(|state| state.ready.lock().ok().map(|ready| *ready))
+        .unwrap_or(false)
+}
+
 #[tauri::command]
 async fn get_backend_status(state: tauri::State<'_, BackendState>) -> Result<BackendStatus, String> {
     let status = state.status.lock().map_err(|e| e.to_string())?;
     Ok(status.clone())
 }
 
+/// Tell the splash window (splash.rs) where startup has got to.
+fn report_startup(app: &AppHandle, status_ref: &Arc<Mutex<BackendStatus>>) {
+    let status = status_ref.lock().ok().map(|s| s.clone());
+    if let Some(status) = status {
+        splash::progress(app, &status);
+    }
+}
+
 // P0.1b: expose the app token to the webview so axios can attach it to
 // API requests. Cached after first read; refresh_app_token() invalidates
 // the cache when the backend rotates the token (e.g. after a restart).
@@ -105,8 +329,8 @@ async fn get_app_token() -> Result<String, String> {
 // fresh value. The webview's `localFetch` and axios response-interceptor
 // invoke this once per request on 401, then retry once.
 #[tauri::command]
-async fn refresh_app_token() -> Result<String, String> {
-    refresh_app_token_impl().await
+async fn refresh_app_token(app: AppHandle) -> Result<String, String> {
+    refresh_app_token_impl(&app).await
 }
 
 // Tauri command to check backend health
@@ -115,210 +339,82 @@ async fn check_backend_health() -> Result<bool, String> {
     match check_health().await {
         Ok(healthy) => Ok(healthy),
         Err(e) => {
-            eprintln!("Health check failed: {}", e);
+            warn!("Health check failed: {}", e);
             Ok(false)
         }
     }
 }
 
 // Function to check backend health
-async fn check_health() -> Result<bool, Box<dyn std::error::Error>> {
+pub(crate) async fn check_health() -> Result<bool, Box<dyn std::error::Error>> {
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(5))
+        .no_proxy()
         .build()?;
 
     let response = client
-        .get("http://localhost:8000/health")
+        .get(format!("{}/health", backend_api::base_url()))
         .send()
         .await?;
 
     Ok(response.status().is_success())
 }
 
-// Function to check if Ollama is running
-async fn check_ollama() -> bool {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(2))
-        .build();
-    
-    match client {
-        Ok(c) => c.get("http://localhost:11434/api/tags").send().await.is_ok(),
-        Err(_) => false,
-    }
-}
-
-// Function to check if a model is available in Ollama
-async fn check_model_available(model_name: &str) -> bool {
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build() {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
-    
-    let response = client
-        .get("http://localhost:11434/api/tags")
-        .send()
-        .await;
-    
-    match response {
-        Ok(resp) => {
-            if let Ok(text) = resp.text().await {
-                // Check if model name appears in the response
-                text.contains(model_name)
-            } else {
-                false
-            }
-        }
-        Err(_) => false,
-    }
-}
-
-// Function to pull a model from Ollama
-async fn pull_ollama_model(model_name: &str) -> Result<(), String> {
-    println!("Pulling Ollama model: {}", model_name);
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(600)) // 10 min timeout for large models
-        .build()
-        .map_err(|e| format!("Failed to create client: {}", e))?;
-    
-    let response = client
-        .post("http://localhost:11434/api/pull")
-        .json(&serde_json::json!({
-            "name": model_name,
-            "stream": false
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to pull model: {}", e))?;
-    
-    if response.status().is_success() {
-        println!("Successfully pulled model: {}", model_name);
-        Ok(())
-    } else {
-        Err(format!("Failed to pull model {}: HTTP {}", model_name, response.status()))
-    }
-}
-
 // Required models for LocalBook - must match backend/config.py settings
-const REQUIRED_MODELS: &[(&str, &str)] = &[
+pub(crate) const REQUIRED_MODELS: &[(&str, &str)] = &[
     ("olmo-3:7b-instruct", "Main AI model (~4.5GB)"),
     ("phi4-mini:latest", "Fast AI model (~2.5GB)"),
     ("snowflake-arctic-embed2", "Embedding model (~1.2GB)"),
 ];
 
 // Function to ensure all required models are available
-async fn ensure_required_models(status_ref: &Arc<Mutex<BackendStatus>>) {
-    println!("Checking required AI models...");
+async fn ensure_required_models(app: &AppHandle, status_ref: &Arc<Mutex<BackendStatus>>) {
+    info!("Checking required AI models...");
     
     for (model_name, description) in REQUIRED_MODELS {
         if let Ok(mut status) = status_ref.lock() {
             status.stage = "checking_models".to_string();
             status.message = format!("Checking {}...", description);
         }
+        report_startup(app, status_ref);
         
-        if !check_model_available(model_name).await {
-            println!("Model {} not found, downloading...", model_name);
+        if !ollama::model_available(model_name).await {
+            info!(model = model_name, "Model not found, downloading...");
             
             if let Ok(mut status) = status_ref.lock() {
                 status.stage = "downloading_model".to_string();
                 status.message = format!("Downloading {} (this may take several minutes)...", description);
             }
+            report_startup(app, status_ref);
             
-            match pull_ollama_model(model_name).await {
+            match ollama::pull_model(model_name).await {
                 Ok(_) => {
-                    println!("Model {} downloaded successfully", model_name);
+                    info!(model = model_name, "Model downloaded successfully");
                 }
                 Err(e) => {
-                    eprintln!("Failed to download model {}: {}", model_name, e);
+                    error!(model = model_name, "Failed to download model: {}", e);
                     if let Ok(mut status) = status_ref.lock() {
                         status.last_error = Some(format!("Failed to download {}: {}", model_name, e));
                     }
                 }
             }
         } else {
-            println!("Model {} is available", model_name);
+            debug!(model = model_name, "Model is available");
         }
     }
     
-    println!("Model check complete");
-}
-
-// Function to start Ollama if not running
-async fn ensure_ollama_running() {
-    if check_ollama().await {
-        println!("Ollama is already running");
-        return;
-    }
-
-    println!("Starting Ollama...");
-    
-    // Try common Ollama installation paths
-    let ollama_paths = [
-        "/opt/homebrew/bin/ollama",  // Apple Silicon Homebrew
-        "/usr/local/bin/ollama",      // Intel Homebrew
-        "/Applications/Ollama.app/Contents/Resources/ollama", // Ollama.app
-        "ollama",                      // Fallback to PATH
-    ];
-
-    let mut result = None;
-    for path in &ollama_paths {
-        let attempt = std::process::Command::new(path)
-            .arg("serve")
-            // Memory management: limit concurrent models, enable flash attention,
-            // and use q8_0 KV cache to halve context memory vs f16 default.
-            .env("OLLAMA_MAX_LOADED_MODELS", "2")
-            .env("OLLAMA_FLASH_ATTENTION", "1")
-            .env("OLLAMA_KV_CACHE_TYPE", "q8_0")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn();
-        
-        if attempt.is_ok() {
-            result = Some(attempt);
-            println!("Started Ollama from: {} (MAX_LOADED_MODELS=2, FLASH_ATTN=1, KV=q8_0)", path);
-            break;
-        }
-    }
-
-    let result = result.unwrap_or_else(|| {
-        std::process::Command::new("ollama")
-            .arg("serve")
-            .env("OLLAMA_MAX_LOADED_MODELS", "2")
-            .env("OLLAMA_FLASH_ATTENTION", "1")
-            .env("OLLAMA_KV_CACHE_TYPE", "q8_0")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-    });
-
-    match result {
-        Ok(_) => {
-            // Wait for Ollama to be ready
-            for attempt in 1..=10 {
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                if check_ollama().await {
-                    println!("Ollama started successfully");
-                    return;
-                }
-                println!("Waiting for Ollama... attempt {}/10", attempt);
-            }
-            eprintln!("Warning: Ollama may not have started properly");
-        }
-        Err(e) => {
-            eprintln!("Could not start Ollama: {}", e);
-            eprintln!("Please start Ollama manually: ollama serve");
-        }
-    }
+    info!("Model check complete");
 }
 
 // Function to kill any existing backend process
 fn kill_existing_backend() {
-    // Kill anything on port 8000 AND any localbook-backend processes.
+    backend_events::stopping();
+    // Kill anything on the backend port AND any localbook-backend processes.
     // Port-based kill catches dev-mode (python -m uvicorn) AND bundled processes.
+    // With --no-sidecar the backend isn't ours to stop.
     #[cfg(unix)]
-    {
+    if !cli::options().no_sidecar {
+        let port = format!("-i:{}", backend_api::sidecar_port());
         // 1. Kill by process name (bundled backend)
         let _ = std::process::Command::new("pkill")
             .args(["-f", "localbook-backend"])
@@ -327,7 +423,7 @@ fn kill_existing_backend() {
         // 2. Kill by port (catches dev-mode python, orphaned processes, etc.)
         //    lsof -t -i:8000 returns PIDs; kill sends SIGTERM to each
         if let Ok(output) = std::process::Command::new("lsof")
-            .args(["-t", "-i:8000"])
+            .args(["-t", &port])
             .output()
         {
             let pids = String::from_utf8_lossy(&output.stdout);
@@ -341,9 +437,9 @@ fn kill_existing_backend() {
         // Wait for graceful shutdown (3s is enough for DB flush + model save)
         std::thread::sleep(Duration::from_secs(3));
 
-        // 3. Force-kill stragglers on port 8000
+        // 3. Force-kill stragglers on the port
         if let Ok(output) = std::process::Command::new("lsof")
-            .args(["-t", "-i:8000"])
+            .args(["-t", &port])
             .output()
         {
             let pids = String::from_utf8_lossy(&output.stdout);
@@ -362,15 +458,11 @@ fn kill_existing_backend() {
         // Give it a moment to release the port
         std::thread::sleep(Duration::from_millis(500));
     }
+    backend_events::stopped();
 }
 
-// Function to start the backend from resources
-async fn start_backend(app_handle: &AppHandle) -> Result<Option<std::process::Child>, String> {
-    println!("Attempting to start backend...");
-    
-    // Kill any existing backend first to avoid port conflicts
-    kill_existing_backend();
-
+/// Where the frozen backend may be, in the order `start_backend` tries.
+pub(crate) fn backend_candidates(app_handle: &AppHandle) -> Result<Vec<PathBuf>, String> {
     let resource_dir = app_handle
         .path()
         .resource_dir()
@@ -387,71 +479,142 @@ async fn start_backend(app_handle: &AppHandle) -> Result<Option<std::process::Ch
     //   <resource_dir>/backend/localbook-backend/<exe>
     // or (older/alternative layout):
     //   <resource_dir>/resources/backend/localbook-backend/<exe>
-    let candidate_paths: Vec<PathBuf> = vec![
-        resource_dir
-            .join("backend")
-            .join("localbook-backend")
-            .join(backend_exe_name),
-        resource_dir
-            .join("resources")
-            .join("backend")
-            .join("localbook-backend")
-            .join(backend_exe_name),
-    ];
-
-    for candidate in candidate_paths {
-        println!("Looking for backend at: {:?}", candidate);
+    // An installed backend update (see sidecar.rs) takes precedence.
+    Ok(sidecar::active_exe(app_handle)
+        .into_iter()
+        .chain([
+            resource_dir
+                .join("backend")
+                .join("localbook-backend")
+                .join(backend_exe_name),
+            resource_dir
+                .join("resources")
+                .join("backend")
+                .join("localbook-backend")
+                .join(backend_exe_name),
+        ])
+        .collect())
+}
+
+// Function to start the backend from resources
+#[tracing::instrument(skip_all)]
+async fn start_backend(app_handle: &AppHandle) -> Result<Option<std::process::Child>, String> {
+    info!("Attempting to start backend...");
+    
+    // Kill any existing backend first to avoid port conflicts
+    kill_existing_backend();
+
+    // A backend on another machine (always, on mobile) replaces the sidecar.
+    if let Some(url) = remote_backend::url() {
+        info!("Using remote backend at {}", url);
+        return Ok(None);
+    }
+    if remote_backend::required() {
+        return Err("No backend set — connect to one from settings".to_string());
+    }
+    if cli::options().no_sidecar {
+        info!("Started with --no-sidecar - expecting a backend on port {}", backend_api::sidecar_port());
+        return Ok(None);
+    }
+    folder_sync::prepare(app_handle).await?;
+
+    for candidate in backend_candidates(app_handle)? {
+        debug!("Looking for backend at: {:?}", candidate);
         if !candidate.exists() {
             continue;
         }
 
-        println!("Starting bundled backend...");
+        info!("Starting bundled backend...");
         let backend_dir = candidate
             .parent()
             .ok_or_else(|| "Backend path has no parent directory".to_string())?;
-        println!("Backend working directory: {:?}", backend_dir);
-
-        return match std::process::Command::new(&candidate)
-            .current_dir(backend_dir)
-            .stdin(std::process::Stdio::null())
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .spawn()
-        {
-            Ok(child) => {
-                println!("Backend spawned with PID: {:?}", child.id());
-                Ok(Some(child))
-            }
-            Err(e) => {
-                eprintln!("Failed to start backend: {}", e);
-                Err(format!("Failed to start backend: {}", e))
-            }
-        };
+        debug!("Backend working directory: {:?}", backend_dir);
+
+        // A quarantined bundle (copied out of a downloaded DMG) makes Gatekeeper
+        // block or prompt for the backend and each of its dylibs on spawn.
+        quarantine::strip(backend_dir);
+
+        return spawn_backend(backend_command(app_handle, &candidate, backend_dir));
+    }
+
+    // Source install: run main.py in the app-managed Python environment
+    if let Some(source) = pyenv::source_dir(app_handle) {
+        info!("Starting backend from source: {:?}", source);
+        let python = pyenv::ensure(app_handle, &source).await?;
+        let mut command = backend_command(app_handle, &python, &source);
+        command.arg("main.py");
+        return spawn_backend(command);
     }
 
     // Dev mode: backend should be started externally via start.sh
-    println!("Bundled backend not found in resource dir: {:?}", resource_dir);
-    println!("Running in dev mode - backend should be started externally");
+    info!("Bundled backend not found");
+    info!("Running in dev mode - backend should be started externally");
     Ok(None)
 }
 
+/// The backend process, with everything it's configured through.
+fn backend_command(app_handle: &AppHandle, program: &Path, dir: &Path) -> std::process::Command {
+    let mut command = std::process::Command::new(program);
+    command
+        .current_dir(dir)
+        .env("OLLAMA_BASE_URL", ollama::endpoint())
+        .envs(hardware::backend_env(&hardware::tuning(app_handle)))
+        .envs(models::models_dir(app_handle).map(|d| ("LOCALBOOK_MODELS_DIR", d)))
+        .envs(providers::backend_env(app_handle))
+        .envs(secrets::backend_env())
+        .envs(privacy::backend_env())
+        .envs(proxy::backend_env())
+        .envs(certs::backend_env())
+        .envs(folder_sync::backend_env())
+        .envs(cli::backend_env())
+        .envs(debug_mode::backend_env())
+        .envs(startup::backend_env(app_handle))
+        .stdin(std::process::Stdio::null())
+        // Passed on by `logging`, which keeps a copy for the log viewer.
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    command
+}
+
+fn spawn_backend(mut command: std::process::Command) -> Result<Option<std::process::Child>, String> {
+    match command.spawn() {
+        Ok(mut child) => {
+            info!(pid = child.id(), "Backend spawned");
+            metrics::startup(metrics::Phase::BackendSpawned);
+            logging::capture_sidecar(&mut child);
+            audit::record("backend_started", format!("pid {}", child.id()));
+            Ok(Some(child))
+        }
+        Err(e) => {
+            error!("Failed to start backend: {}", e);
+            Err(format!("Failed to start backend: {}", e))
+        }
+    }
+}
+
 // Function to wait for backend to be ready
+#[tracing::instrument]
 async fn wait_for_backend_ready(max_attempts: u32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    println!("Waiting for backend to be ready...");
+    info!("Waiting for backend to be ready...");
 
     for attempt in 1..=max_attempts {
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        // The sidecar says when it's listening (backend_events.rs); poll
+        // /health as well, for a backend that doesn't.
+        if backend_events::wait_ready(Duration::from_secs(1)).await {
+            info!("Backend is ready (announced)");
+            return Ok(());
+        }
 
         match check_health().await {
             Ok(true) => {
-                println!("Backend is ready!");
+                info!("Backend is ready!");
                 return Ok(());
             }
             Ok(false) => {
-                println!("Attempt {}/{}: Backend not healthy yet", attempt, max_attempts);
+                debug!(attempt, "Backend not healthy yet");
             }
             Err(e) => {
-                println!("Attempt {}/{}: {}", attempt, max_attempts, e);
+                debug!(attempt, "{}", e);
             }
         }
     }
@@ -499,6 +662,7 @@ fn is_process_alive(process_ref: &Arc<Mutex<Option<std::process::Child>>>) -> Op
     }
 }
 
+#[tracing::instrument(name = "watchdog", skip_all)]
 async fn backend_watchdog(
     app_handle: AppHandle,
     process_ref: Arc<Mutex<Option<std::process::Child>>>,
@@ -538,30 +702,44 @@ async fn backend_watchdog(
         }
     }
 
-    println!("[Watchdog] Backend health monitoring active");
+    info!("[Watchdog] Backend health monitoring active");
 
     // Startup grace period — backend startup is resource-intensive
     // (model warmup, KG extraction, memory scheduler, etc.)
-    tokio::time::sleep(Duration::from_secs(STARTUP_GRACE_SECS)).await;
-    println!("[Watchdog] Startup grace period ({}s) complete — monitoring started", STARTUP_GRACE_SECS);
+    let mut crashed =
+        backend_events::wait_crashed(Duration::from_secs(STARTUP_GRACE_SECS)).await;
+    info!("[Watchdog] Startup grace period ({}s) complete — monitoring started", STARTUP_GRACE_SECS);
 
     loop {
-        tokio::time::sleep(LIVENESS_INTERVAL).await;
+        // A sidecar whose output ends has exited (backend_events.rs): no
+        // need to wait for the next check, or to confirm it.
+        if !crashed {
+            crashed = backend_events::wait_crashed(LIVENESS_INTERVAL).await;
+        }
+        let exited = std::mem::take(&mut crashed);
 
         // ── Tier 1: PID check — is the process still alive? ──
-        let pid_status = is_process_alive(&process_ref);
+        let pid_status = if exited {
+            Some(false)
+        } else {
+            is_process_alive(&process_ref)
+        };
 
         if pid_status == Some(false) {
             // Process has exited — this is a real crash
-            pid_dead_count += 1;
-            println!(
+            pid_dead_count = if exited {
+                PROCESS_DEAD_CONFIRMS
+            } else {
+                pid_dead_count + 1
+            };
+            warn!(
                 "[Watchdog] Process exited! (confirm {}/{})",
                 pid_dead_count, PROCESS_DEAD_CONFIRMS
             );
 
             if pid_dead_count >= PROCESS_DEAD_CONFIRMS {
                 // Confirmed dead — skip HTTP checks, go straight to restart
-                println!("[Watchdog] Process confirmed dead — initiating restart");
+                error!("[Watchdog] Process confirmed dead — initiating restart");
                 http_failures = 0;
                 pid_dead_count = 0;
                 // Fall through to restart logic below
@@ -573,14 +751,24 @@ async fn backend_watchdog(
             pid_dead_count = 0;
 
             // ── Tier 2: HTTP liveness — can it respond? ──
-            let healthy = check_health().await.unwrap_or(false);
+            let checked_at = std::time::Instant::now();
+            let (healthy, check_error) = match check_health().await {
+                Ok(true) => (true, None),
+                Ok(false) => (false, Some("unhealthy status".to_string())),
+                Err(e) => (false, Some(e.to_string())),
+            };
+            health_history::check(healthy, checked_at.elapsed(), check_error.clone());
 
             if healthy {
                 if http_failures > 0 {
-                    println!(
+                    info!(
                         "[Watchdog] Backend responsive after {} slow check(s) — healthy",
                         http_failures
                     );
+                    health_history::transition(
+                        "ready",
+                        format!("responsive after {} failed check(s)", http_failures),
+                    );
                 }
                 http_failures = 0;
                 continue; // All good
@@ -588,7 +776,11 @@ async fn backend_watchdog(
 
             // Process alive but HTTP failed — likely slow under memory pressure
             http_failures += 1;
-            println!(
+            health_history::transition(
+                "degraded",
+                check_error.unwrap_or_else(|| "health check failed".to_string()),
+            );
+            warn!(
                 "[Watchdog] HTTP liveness failed ({}/{}) — process alive, likely under pressure",
                 http_failures, HTTP_FAIL_THRESHOLD
             );
@@ -598,7 +790,7 @@ async fn backend_watchdog(
             }
 
             // Exhausted patience — process alive but unresponsive for 2+ minutes
-            println!(
+            error!(
                 "[Watchdog] Backend unresponsive for {}s — initiating restart",
                 http_failures as u64 * LIVENESS_INTERVAL.as_secs()
             );
@@ -606,7 +798,16 @@ async fn backend_watchdog(
         }
 
         // ── Backend needs restart ──
-        log_crash_to_file(&app_handle, restart_count);
+        let exit = exit_status(&process_ref);
+        health_history::transition(
+            "crashed",
+            match &exit {
+                Some(status) => format!("process exited ({})", status),
+                None => "unresponsive".to_string(),
+            },
+        );
+        log_crash_to_file(&app_handle, restart_count, exit);
+        usage::count("backend_crash");
 
         if let Ok(mut ready) = ready_ref.lock() {
             *ready = false;
@@ -629,10 +830,11 @@ async fn backend_watchdog(
         );
 
         if restart_count >= MAX_RESTARTS {
-            println!(
+            error!(
                 "[Watchdog] Max restarts ({}) reached — stopping watchdog",
                 MAX_RESTARTS
             );
+            health_history::transition("failed", format!("{} restarts used up", MAX_RESTARTS));
             if let Ok(mut status) = status_ref.lock() {
                 status.stage = "error".to_string();
                 status.message = format!(
@@ -652,11 +854,15 @@ async fn backend_watchdog(
 
         // ── Attempt restart with exponential backoff ──
         restart_count += 1;
-        println!(
+        warn!(
             "[Watchdog] Restart attempt {}/{}",
             restart_count, MAX_RESTARTS
         );
 
+        health_history::transition(
+            "restarting",
+            format!("attempt {}/{}", restart_count, MAX_RESTARTS),
+        );
         if let Ok(mut status) = status_ref.lock() {
             status.stage = "restarting".to_string();
             status.message = format!(
@@ -677,12 +883,16 @@ async fn backend_watchdog(
         // Exponential backoff: 5s, 10s, 20s, 40s, 80s
         // Gives macOS time to free memory between restart attempts
         let backoff_secs = 5u64 * 2u64.pow(restart_count.saturating_sub(1));
-        println!(
+        info!(
             "[Watchdog] Waiting {}s before restart (backoff)...",
             backoff_secs
         );
         tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
 
+        audit::record(
+            "backend_restarted",
+            format!("watchdog, attempt {}/{}", restart_count, MAX_RESTARTS),
+        );
         match start_backend(&app_handle).await {
             Ok(child_opt) => {
                 if let Some(child) = child_opt {
@@ -693,10 +903,15 @@ async fn backend_watchdog(
 
                 match wait_for_backend_ready(30).await {
                     Ok(_) => {
-                        println!(
+                        info!(
                             "[Watchdog] Backend recovered (restart #{})",
                             restart_count
                         );
+                        sidecar::record_health(&app_handle, true);
+                        health_history::transition(
+                            "ready",
+                            format!("recovered after restart #{}", restart_count),
+                        );
                         if let Ok(mut ready) = ready_ref.lock() {
                             *ready = true;
                         }
@@ -716,27 +931,42 @@ async fn backend_watchdog(
 
                         // Post-restart grace period — startup tasks are
                         // resource-intensive (model warmup, KG extraction)
-                        println!(
+                        info!(
                             "[Watchdog] Post-restart grace period ({}s)...",
                             RESTART_GRACE_SECS
                         );
-                        tokio::time::sleep(Duration::from_secs(RESTART_GRACE_SECS)).await;
+                        crashed = backend_events::wait_crashed(Duration::from_secs(
+                            RESTART_GRACE_SECS,
+                        ))
+                        .await;
                     }
                     Err(e) => {
-                        println!("[Watchdog] Backend failed to recover: {}", e);
-                        // Will loop and try again on next iteration
+                        error!("[Watchdog] Backend failed to recover: {}", e);
+                        health_history::transition("crashed", format!("restart failed: {}", e));
+                        // Will loop and try again on next iteration, on the
+                        // known-good backend if this one keeps failing
+                        sidecar::record_health(&app_handle, false);
                     }
                 }
             }
             Err(e) => {
-                println!("[Watchdog] Failed to restart backend: {}", e);
+                error!("[Watchdog] Failed to restart backend: {}", e);
+                health_history::transition("crashed", format!("restart failed: {}", e));
+                sidecar::record_health(&app_handle, false);
             }
         }
     }
 }
 
-fn log_crash_to_file(app_handle: &AppHandle, restart_count: u32) {
-    if let Ok(data_dir) = app_handle.path().app_data_dir() {
+/// How the tracked backend process exited, if it has.
+fn exit_status(process_ref: &Arc<Mutex<Option<std::process::Child>>>) -> Option<String> {
+    let mut guard = process_ref.lock().ok()?;
+    let status = guard.as_mut()?.try_wait().ok()??;
+    Some(status.to_string())
+}
+
+fn log_crash_to_file(app_handle: &AppHandle, restart_count: u32, exit: Option<String>) {
+    if let Ok(data_dir) = data_dir(app_handle) {
         let log_path = data_dir.join("backend_crashes.log");
         // Use Unix timestamp — keeps it simple without chrono dependency
         let ts = std::time::SystemTime::now()
@@ -749,6 +979,9 @@ fn log_crash_to_file(app_handle: &AppHandle, restart_count: u32) {
             ts,
             restart_count + 1
         );
+        if let Some(exit) = &exit {
+            entry.push_str(&format!("  Exit: {}\n", exit));
+        }
 
         // Layer 3: Check macOS DiagnosticReports for native crash info
         // macOS writes crash reports here for SIGKILL/SIGSEGV/SIGABRT
@@ -787,7 +1020,7 @@ fn log_crash_to_file(app_handle: &AppHandle, restart_count: u32) {
                                             }
                                         }
                                     }
-                                    println!(
+                                    info!(
                                         "[Watchdog] Found macOS crash report: {}", name
                                     );
                                 }
@@ -804,8 +1037,9 @@ fn log_crash_to_file(app_handle: &AppHandle, restart_count: u32) {
             .open(&log_path)
         {
             let _ = std::io::Write::write_all(&mut f, entry.as_bytes());
-            println!("[Watchdog] Crash logged to {:?}", log_path);
+            info!("[Watchdog] Crash logged to {:?}", log_path);
         }
+        crash::record_backend_crash(exit.as_deref(), &entry);
     }
 }
 
@@ -832,39 +1066,62 @@ fn setup_backend(app: &AppHandle) -> Result<BackendState, String> {
     let wd_ready = state.ready.clone();
     let wd_status = state.status.clone();
 
+    let deferred = startup::deferred(app);
+
     // Spawn backend startup in background
     tauri::async_runtime::spawn(async move {
+        // On first use (startup.rs): nothing starts until something needs it.
+        if deferred {
+            if let Ok(mut status) = status_ref.lock() {
+                status.stage = "waiting".to_string();
+                status.message = "Backend starts when LocalBook is first used".to_string();
+            }
+            startup::wait_for_first_use().await;
+            info!("[Startup] Backend needed; starting it");
+        }
         if let Ok(mut status) = status_ref.lock() {
             status.stage = "starting_ollama".to_string();
             status.message = "Starting Ollama...".to_string();
             status.last_error = None;
         }
-        // Ensure Ollama is running first
-        ensure_ollama_running().await;
-
-        // Check and download required models
-        ensure_required_models(&status_ref).await;
+        report_startup(&app_handle, &status_ref);
+        // First run: size launch parameters to this machine (probes hardware)
+        let tune_app = app_handle.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || hardware::tuning(&tune_app)).await;
+
+        // Models belong to the machine running the backend
+        if remote_backend::url().is_none() && !remote_backend::required() {
+            // Ensure Ollama is running first
+            ollama::ensure_running(&app_handle).await;
+            ollama::start_monitor(&app_handle);
+
+            // Check and download required models
+            ensure_required_models(&app_handle, &status_ref).await;
+        }
 
         if let Ok(mut status) = status_ref.lock() {
             status.stage = "starting_backend".to_string();
             status.message = "Starting backend...".to_string();
         }
+        report_startup(&app_handle, &status_ref);
+        health_history::transition("starting", "app launched");
 
         match start_backend(&app_handle).await {
             Ok(child_opt) => {
                 if let Some(child) = child_opt {
-                    println!("Backend process started");
+                    info!("Backend process started");
                     if let Ok(mut process) = process_ref.lock() {
                         *process = Some(child);
                     }
                 } else {
-                    println!("Backend running externally (dev mode)");
+                    info!("Backend running externally (dev mode)");
                 }
 
                 if let Ok(mut status) = status_ref.lock() {
                     status.stage = "waiting_for_backend".to_string();
                     status.message = "Waiting for backend to be ready...".to_string();
                 }
+                report_startup(&app_handle, &status_ref);
 
                 // Wait for backend to be ready
                 match wait_for_backend_ready(30).await {
@@ -877,31 +1134,40 @@ fn setup_backend(app: &AppHandle) -> Result<BackendState, String> {
                             status.message = "Backend ready".to_string();
                             status.last_error = None;
                         }
-                        println!("Backend initialization complete");
+                        report_startup(&app_handle, &status_ref);
+                        info!("Backend initialization complete");
+                        health_history::transition("ready", "first health check passed");
+                        metrics::startup(metrics::Phase::BackendHealthy);
+                        sidecar::record_health(&app_handle, true);
+                        warmup::start(&app_handle);
                     }
                     Err(e) => {
-                        eprintln!("Failed to connect to backend: {}", e);
-                        eprintln!("");
-                        eprintln!("Please ensure the backend is running.");
-                        eprintln!("For dev mode: ./start.sh");
+                        error!("Failed to connect to backend: {}", e);
+                        health_history::transition("error", e.to_string());
+                        sidecar::record_health(&app_handle, false);
+                        pyenv::diagnose(&app_handle);
+                        warn!("Please ensure the backend is running. For dev mode: ./start.sh");
                         if let Ok(mut status) = status_ref.lock() {
                             status.stage = "error".to_string();
                             status.message = "Backend failed to start".to_string();
                             status.last_error = Some(e.to_string());
                         }
+                        report_startup(&app_handle, &status_ref);
                     }
                 }
             }
             Err(e) => {
-                eprintln!("Failed to start backend: {}", e);
+                error!("Failed to start backend: {}", e);
+                health_history::transition("error", e.clone());
                 if let Ok(mut status) = status_ref.lock() {
                     status.stage = "error".to_string();
                     status.message = "Backend failed to start".to_string();
                     status.last_error = Some(e);
                 }
+                report_startup(&app_handle, &status_ref);
             }
         }
-    });
+    }.instrument(tracing::info_span!("backend_startup")));
 
     // Spawn watchdog — waits for ready=true, then monitors continuously
     tauri::async_runtime::spawn(async move {
@@ -926,187 +1192,461 @@ async fn upload_file_streaming(
     path: String,
     notebook_id: String,
     channel_id: String,
+    file_name: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    use std::path::PathBuf;
-    use tokio::fs::File as TokioFile;
-    use tokio_util::io::ReaderStream;
-    use reqwest::multipart;
-
-    let path_buf = PathBuf::from(&path);
-    let filename = path_buf
-        .file_name()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| "Invalid file path (no filename)".to_string())?;
-
-    println!(
+    info!(
         "[upload-stream] Starting upload: {} (channel={})",
-        filename, channel_id
+        path, channel_id
     );
-
-    // Open the file as a streaming body — bytes never sit in memory at once
-    let file = TokioFile::open(&path)
-        .await
-        .map_err(|e| format!("Failed to open file '{}': {}", path, e))?;
-    let stream = ReaderStream::new(file);
-    let body = reqwest::Body::wrap_stream(stream);
-
-    let part = multipart::Part::stream(body).file_name(filename.clone());
-    let form = multipart::Form::new()
-        .text("notebook_id", notebook_id)
-        .part("file", part);
-
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(3600)) // 1 hour for very large files
-        .build()
-        .map_err(|e| format!("HTTP client build failed: {}", e))?;
-
-    // P0.1c (2026-05-15): attach the app token. read_app_token() reads from
-    // the cached OnceLock or falls back to the file; unwrap_or_default()
-    // sends an empty string if neither works — backend isn't enforcing yet
-    // so that's a soft no-op. Once P0.1f Stage 2 enforces, an empty token
-    // returns 401 — that's the right failure mode (caller learns immediately).
-    let token = read_app_token().await.unwrap_or_default();
-
-    let resp = client
-        .post("http://localhost:8000/sources/upload/stream")
-        .header("X-LocalBook-Token", &token)
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| format!("Upload request failed: {}", e))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body_text = resp.text().await.unwrap_or_default();
-        return Err(format!(
-            "Upload failed: HTTP {} - {}",
-            status,
-            body_text.chars().take(300).collect::<String>()
-        ));
+    // Android pickers hand over a content:// URI (with the name alongside);
+    // upload a copy of it instead.
+    if content_uri::is_content_uri(&path) {
+        let staged = content_uri::stage(window.app_handle(), &path, file_name.as_deref()).await?;
+        let result = upload_staged(&window, &staged, &notebook_id, &channel_id).await;
+        content_uri::discard(&staged);
+        return result;
     }
+    let path = scope::check(window.app_handle(), std::path::Path::new(&path))?;
+    upload_staged(&window, &path, &notebook_id, &channel_id).await
+}
 
-    // Read SSE stream chunk by chunk, forward events to the frontend.
-    // The backend emits frames separated by "\n\n", each with a "data: {json}" line.
-    let mut resp = resp;
-    let mut buffer = String::new();
-    let mut final_result: Option<serde_json::Value> = None;
+#[tracing::instrument(skip_all, fields(notebook = notebook_id, channel = channel_id))]
+async fn upload_staged(
+    window: &tauri::Window,
+    path: &Path,
+    notebook_id: &str,
+    channel_id: &str,
+) -> Result<serde_json::Value, String> {
+    audit::record(
+        "document_imported",
+        format!("{} into notebook {}", path.display(), notebook_id),
+    );
     let event_topic = format!("upload-progress-{}", channel_id);
+    let result = backend_api::upload_file(path, notebook_id, |evt| {
+        let _ = window.emit(&event_topic, evt);
+    })
+    .await
+    .map_err(|e| match e {
+        backend_api::ApiError::Status(..) => format!("Upload failed: {}", e),
+        _ => e.to_string(),
+    })?;
+    info!("[upload-stream] Upload complete for {}", path.display());
+    usage::count("document_imported");
+    Ok(result)
+}
 
-    loop {
-        match resp.chunk().await {
-            Ok(Some(bytes)) => {
-                if let Ok(s) = std::str::from_utf8(&bytes) {
-                    buffer.push_str(s);
-                } else {
-                    // Non-UTF8 bytes shouldn't appear in SSE, skip safely
-                    continue;
-                }
-
-                // Drain any complete SSE frames in the buffer
-                while let Some(idx) = buffer.find("\n\n") {
-                    let raw_event = buffer[..idx].to_string();
-                    buffer.replace_range(..idx + 2, "");
 
-                    for line in raw_event.lines() {
-                        // Skip comments (": ping") and non-data lines
-                        if line.starts_with(':') {
-                            continue;
-                        }
-                        let payload = match line.strip_prefix("data:") {
-                            Some(p) => p.trim(),
-                            None => continue,
-                        };
-                        if payload.is_empty() || payload == "{}" {
-                            continue;
-                        }
-                        let evt: serde_json::Value = match serde_json::from_str(payload) {
-                            Ok(v) => v,
-                            Err(_) => continue, // skip malformed frames
-                        };
-
-                        let stage = evt
-                            .get("stage")
-                            .and_then(|s| s.as_str())
-                            .unwrap_or("");
-
-                        if stage == "complete" {
-                            final_result = evt.get("details").cloned();
-                            // Don't return yet — continue draining stream until backend closes
-                        } else if stage == "error" {
-                            let msg = evt
-                                .get("message")
-                                .and_then(|s| s.as_str())
-                                .unwrap_or("Upload failed")
-                                .to_string();
-                            return Err(msg);
-                        } else {
-                            // Forward to frontend
-                            let _ = window.emit(&event_topic, &evt);
-                        }
-                    }
-                }
-            }
-            Ok(None) => break, // End of stream
-            Err(e) => {
-                // If we already received the terminal 'complete' event,
-                // a subsequent stream-read error is just the connection
-                // closing — the upload succeeded. Returning Err here
-                // would surface as a red error toast on a successful
-                // upload (the symptom the heavy user hit 2026-05-27).
-                if final_result.is_some() {
-                    println!(
-                        "[upload-stream] stream-close hiccup after complete event \
-                         for {} (ignoring): {}",
-                        filename, e
-                    );
-                    break;
-                }
-                return Err(format!("Stream read error: {}", e));
-            }
-        }
-    }
 
-    println!("[upload-stream] Upload complete for {}", filename);
-    Ok(final_result.unwrap_or(serde_json::json!({})))
+// Second launch (double-clicked file, deep link, or the dock icon again): the
+// single-instance plugin hands us the new process's argv + cwd instead of letting
+// it boot a second backend on :8000. cli.rs brings the right window forward and
+// queues the files or notebook for it.
+#[cfg(desktop)]
+fn on_second_instance(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    // Only the count: the arguments are file paths and links.
+    info!("[SingleInstance] Second launch forwarded ({} arguments)", argv.len().saturating_sub(1));
+    cli::forward(app, argv, cwd);
 }
 
-
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    // `localbook import|ask …` and `--serve` run headless (cli.rs): no
+    // windows from the config.
+    logging::init();
+    crash::init();
+    metrics::init();
+    let headless = cli::init();
+    let mut context = tauri::generate_context!();
+    cli::configure(&mut context);
+    hardening::apply_csp(&mut context);
+    if headless {
+        context.config_mut().app.windows.clear();
+    }
+    let builder = tauri::Builder::default();
+
+    // Must be the first plugin registered so a second launch exits before any
+    // other plugin (or setup_backend) runs. A command-line run isn't a
+    // second launch; it uses the running app's backend (cli.rs).
+    #[cfg(desktop)]
+    let builder = if headless {
+        builder
+    } else {
+        builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            on_second_instance(app, argv, cwd);
+        }))
+    };
+
+    builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_process::init())
-        .setup(|app| {
-            let backend_state = setup_backend(&app.handle())?;
-            app.manage(backend_state);
+        .plugin(hardening::plugin())
+        .plugin(cli::plugin())
+        .setup(move |app| {
+            logging::start(app.handle());
+            crash::start(app.handle());
+            app.manage(settings::SettingsState::load(app.handle()));
+            logging::load_filter(app.handle());
+            folder_sync::start(app.handle());
+            remote_backend::start(app.handle());
+            if headless {
+                cli::attach();
+                #[cfg(target_os = "macos")]
+                app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+            }
+            audit::start(app.handle());
+            settings_migrate::start();
+            privacy::start(app.handle());
+            debug_mode::start(app.handle());
+            backend_events::start(app.handle());
+            usage::start(app.handle());
+            proxy::start(app.handle());
+            slow_requests::start(app.handle());
+            certs::start(app.handle());
+            lock::start(app.handle());
+            scope::start(app.handle());
+            default_dirs::start(app.handle());
+            notebook_settings::publish(app.handle());
+            theme::apply_override(app.handle());
+            appearance::start(app.handle());
+            power::start_monitor(app.handle());
+            network::start_monitor(app.handle());
+            idle::start_monitor(app.handle());
+            memory::start_monitor(app.handle());
+            jobs::start(app.handle());
+            if !headless {
+                scheduler::start(app.handle());
+                lan_sync::start(app.handle());
+                vault::start(app.handle());
+                rpc::start(app.handle());
+                scripting::start(app.handle());
+                themes::start(app.handle());
+            }
+            versioning::start(app.handle());
+
+            // Window geometry (size, position, maximized, monitor) per window label.
+            // Restored automatically as each window is created; saved on close and
+            // again on exit below. Registered in setup so it's desktop-only.
+            #[cfg(desktop)]
+            if !headless {
+                use tauri_plugin_window_state::StateFlags;
+                // Not visibility: the main window starts hidden and the splash
+                // (or a start without one) decides when it's shown.
+                app.handle().plugin(
+                    tauri_plugin_window_state::Builder::default()
+                        .with_state_flags(StateFlags::all() - StateFlags::VISIBLE)
+                        .build(),
+                )?;
+            }
+
+            // Signed app updates; see updater.rs for channels and install-on-quit.
+            #[cfg(desktop)]
+            if !headless {
+                app.handle()
+                    .plugin(tauri_plugin_updater::Builder::new().build())?;
+                updater::start(app.handle());
+            }
+
+            // Global hotkeys (the mini chat window's); bindings in shortcuts.rs.
+            #[cfg(desktop)]
+            if !headless {
+                use tauri_plugin_global_shortcut::ShortcutState;
+                app.handle().plugin(
+                    tauri_plugin_global_shortcut::Builder::new()
+                        .with_handler(|app, shortcut, event| {
+                            if event.state == ShortcutState::Pressed {
+                                shortcuts::on_global_shortcut(app, shortcut);
+                            }
+                        })
+                        .build(),
+                )?;
+                shortcuts::start(app.handle());
+            }
+
+            if !cli::attached() {
+                let backend_state = setup_backend(app.handle())?;
+                app.manage(backend_state);
+            }
+
+            if headless {
+                cli::start(app.handle());
+                return Ok(());
+            }
 
             // macOS menu-bar tray companion (tray v1) — status + quick-launch.
-            if let Err(e) = tray::init(&app.handle()) {
-                eprintln!("[Tray] init failed (non-fatal): {e}");
+            let minimized = startup::minimized(app.handle());
+            // The main window starts hidden (see tauri.conf.json); starting
+            // minimized, it stays that way until opened from the tray.
+            let tray = tray::init(app.handle());
+            if let Err(e) = &tray {
+                warn!("[Tray] init failed (non-fatal): {e}");
             }
+            // Startup progress until the backend is ready, then the main window.
+            // No way back to a hidden window without the tray.
+            splash::start(app.handle(), minimized && tray.is_ok());
+            startup::restore_session(app.handle(), minimized);
+            // Show conflicts left from earlier runs.
+            sync::refresh_status(app.handle());
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .on_page_load(metrics::on_page_load)
+        .on_window_event(|window, event| {
+            windows::on_window_event(window, event);
+            theme::on_window_event(window, event);
+            lock::on_window_event(window, event);
+            scope::on_window_event(window, event);
+            startup::on_window_event(window, event);
+            splash::on_window_event(window, event);
+        })
+        .invoke_handler(hardening::guard_ipc(tauri::generate_handler![
             is_backend_ready,
             check_backend_health,
             get_backend_status,
             upload_file_streaming,
             get_app_token,
-            refresh_app_token
-        ])
-        .build(tauri::generate_context!())
+            refresh_app_token,
+            windows::open_notebook_window,
+            windows::toggle_mini_mode,
+            windows::set_mini_mode_pinned,
+            titlebar::window_minimize,
+            titlebar::window_maximize,
+            titlebar::window_close,
+            titlebar::window_start_drag,
+            titlebar::window_titlebar_double_click,
+            titlebar::window_set_custom_titlebar,
+            titlebar::get_titlebar_insets,
+            theme::get_system_theme,
+            theme::set_theme_override,
+            appearance::get_appearance,
+            appearance::set_appearance,
+            appearance::set_window_effect,
+            startup::get_startup_settings,
+            default_dirs::get_default_dirs,
+            default_dirs::set_default_dir,
+            startup::set_startup_settings,
+            themes::list_themes,
+            themes::install_theme,
+            themes::get_theme_css,
+            i18n::get_system_locale,
+            i18n::get_translations,
+            power::get_power_state,
+            power::set_battery_policy,
+            network::get_network_status,
+            idle::get_idle_state,
+            idle::set_idle_settings,
+            quarantine::clear_quarantine,
+            ollama::get_ollama_status,
+            ollama::list_ollama_models,
+            ollama::pull_ollama_model,
+            ollama::delete_ollama_model,
+            models::download_model,
+            models::list_local_models,
+            models::remove_model,
+            models::verify_models,
+            models::set_models_dir,
+            warmup::get_warm_state,
+            warmup::set_prewarm_models,
+            providers::list_detected_providers,
+            providers::select_provider,
+            secrets::set_secret,
+            secrets::get_secret,
+            secrets::delete_secret,
+            secrets::validate_api_key,
+            model_prefs::get_notebook_model_prefs,
+            model_prefs::set_notebook_model_prefs,
+            notebook_settings::get_effective_settings,
+            notebook_settings::set_notebook_settings,
+            context::estimate_context,
+            inference::acquire_inference_slot,
+            inference::release_inference_slot,
+            inference::get_inference_queue,
+            memory::get_memory_state,
+            memory::set_memory_settings,
+            rerank::get_reranker_status,
+            rerank::download_reranker,
+            rerank::rerank,
+            jobs::enqueue_job,
+            jobs::list_jobs,
+            jobs::get_job,
+            jobs::cancel_job,
+            jobs::get_job_concurrency,
+            jobs::set_job_concurrency,
+            jobs::get_job_history,
+            jobs::get_retry_policies,
+            jobs::set_retry_policy,
+            lock::get_lock_state,
+            lock::set_lock_passcode,
+            lock::set_lock_options,
+            lock::lock_app,
+            lock::unlock_app,
+            lock::unlock_with_biometrics,
+            lock::recover_app_lock,
+            vault::list_encrypted_notebooks,
+            vault::encrypt_notebook,
+            vault::unlock_notebook,
+            vault::lock_notebook,
+            privacy::get_local_only,
+            privacy::set_local_only,
+            privacy::get_privacy_violations,
+            privacy::clear_privacy_violations,
+            scope::list_granted_paths,
+            scope::revoke_path,
+            audit::get_audit_log,
+            shred::secure_delete,
+            usage::set_usage_analytics,
+            rpc::get_automation_api,
+            rpc::set_automation_api,
+            logging::get_log_level,
+            logging::set_log_level,
+            logging::tail_logs,
+            logging::subscribe_logs,
+            logging::unsubscribe_logs,
+            traces::get_recent_traces,
+            health_history::get_health_history,
+            debug_mode::get_debug_mode,
+            debug_mode::set_debug_mode,
+            perf_profile::capture_performance_profile,
+            slow_requests::get_slow_request_settings,
+            slow_requests::set_slow_request_settings,
+            metrics::get_metrics,
+            metrics::export_metrics,
+            shortcuts::get_shortcuts,
+            shortcuts::set_shortcut,
+            usage::record_usage_event,
+            usage::get_usage_stats,
+            usage::export_usage_summary,
+            proxy::get_proxy_settings,
+            proxy::set_proxy_settings,
+            proxy::test_proxy,
+            certs::list_trusted_certs,
+            certs::add_trusted_cert,
+            certs::remove_trusted_cert,
+            lock::set_auto_lock,
+            sidecar::check_backend_update,
+            sidecar::install_backend_update,
+            sidecar::get_model_manifest,
+            sidecar::rollback_update,
+            pyenv::get_backend_env_status,
+            pyenv::repair_backend_env,
+            doctor::run_doctor,
+            diagnostics::export_diagnostics,
+            crash::list_crash_reports,
+            crash::clear_crash_reports,
+            about::get_app_info,
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            onboarding::reset_onboarding,
+            sync::get_sync_state,
+            sync::get_sync_status,
+            sync::set_notebook_sync,
+            sync::set_notebook_sync_mode,
+            sync::set_sync_device_name,
+            lan_sync::set_lan_sync,
+            lan_sync::list_sync_devices,
+            lan_sync::start_sync_pairing,
+            lan_sync::pair_sync_device,
+            lan_sync::unpair_sync_device,
+            lan_sync::sync_with_device,
+            remote_sync::configure_sync,
+            remote_sync::sync_now,
+            conflicts::list_conflicts,
+            conflicts::resolve_conflict,
+            versioning::get_note_versioning,
+            versioning::set_note_versioning,
+            versioning::commit_note_history,
+            versioning::push_note_history,
+            versioning::get_history,
+            versioning::get_version_diff,
+            share::start_share_server,
+            share::stop_share_server,
+            share::list_share_servers,
+            settings_io::export_settings,
+            settings_io::import_settings,
+            settings_migrate::get_settings_recovery,
+            folder_sync::get_library_folder,
+            folder_sync::set_library_folder,
+            handoff::handoff_session,
+            handoff::take_handoff,
+            remote_backend::get_remote_backend,
+            remote_backend::get_backend_url,
+            remote_backend::set_remote_backend,
+            remote_backend::connect_paired_backend,
+            backend_share::stop_backend_sharing,
+            cli::take_launch_files,
+            cli::take_launch_notebook,
+            #[cfg(desktop)]
+            updater::check_for_updates,
+            #[cfg(desktop)]
+            updater::get_pending_update,
+            #[cfg(desktop)]
+            updater::install_update,
+            #[cfg(desktop)]
+            updater::set_update_settings,
+            #[cfg(desktop)]
+            updater::install_update_from_file,
+            pause::get_background_paused,
+            pause::set_background_paused,
+            scheduler::list_scheduled_tasks,
+            scheduler::run_now,
+            scheduler::set_task_schedule,
+            llama::generate,
+            whisper::list_whisper_models,
+            whisper::transcribe_audio,
+            voices::list_voices,
+            voices::download_voice,
+            voices::remove_voice,
+            voices::preview_voice,
+            voices::set_default_voice,
+            voices::get_default_voice,
+            hf::set_hf_token,
+            hf::delete_hf_token,
+            hf::has_hf_token,
+            hf::search_hf_models,
+            hardware::get_hardware_info,
+            hardware::rerun_hardware_tuning
+        ]))
+        .build(context)
         .expect("error while building tauri application")
-        .run(|_app_handle, event| {
+        .run(move |app_handle, event| {
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = event {
+                cli::open_urls(app_handle, urls);
+                return;
+            }
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                if !headless {
+                    startup::save_session(app_handle);
+                }
+            }
             if let tauri::RunEvent::Exit = event {
-                println!("[Shutdown] Cleaning up backend process...");
-                kill_existing_backend();
-                println!("[Shutdown] Backend cleanup complete");
+                #[cfg(desktop)]
+                if !headless {
+                    use tauri_plugin_window_state::{AppHandleExt, StateFlags};
+                    // The flags the plugin was built with, so a window
+                    // hidden to the tray doesn't come back hidden.
+                    let flags = StateFlags::all() - StateFlags::VISIBLE;
+                    if let Err(e) = app_handle.save_window_state(flags) {
+                        error!("[Shutdown] Failed to save window state: {}", e);
+                    }
+                }
+                info!("[Shutdown] Cleaning up backend process...");
+                // A command-line run leaves a backend it didn't start alone.
+                if !cli::attached() {
+                    kill_existing_backend();
+                    folder_sync::release(app_handle);
+                }
+                ollama::stop_managed();
+                info!("[Shutdown] Backend cleanup complete");
+                if !headless {
+                    rpc::stop(app_handle);
+                }
+                #[cfg(desktop)]
+                if !headless {
+                    updater::install_on_quit();
+                }
             }
         });
 }
@@ -0,0 +1,268 @@
+//! Built-in llama.cpp engine — a fallback path for basic Q&A when the Python
+//! backend (or Ollama behind it) is down.
+//!
+//! Runs GGUF files from the model registry (models.rs) in-process, with GPU
+//! offload and thread count taken from the hardware tuning. Only compiled with
+//! the `llama` cargo feature, since it builds llama.cpp from source (cmake and
+//! a C++ toolchain); without it `generate` reports that the engine isn't
+//! available and the UI keeps to the backend.
+//!
+//! One model stays loaded between calls; asking for a different one swaps it.
+//! Generation takes an interactive slot in the inference queue, so it waits
+//! its turn behind (and doesn't compete with) other generation.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct GenerateParams {
+    /// Registry id of a GGUF model; defaults to the most recently downloaded.
+    pub model_id: Option<String>,
+    pub system: Option<String>,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub context_size: u32,
+    /// Wrap the prompt in the model's chat template (false = raw completion).
+    pub chat: bool,
+}
+
+impl Default for GenerateParams {
+    fn default() -> Self {
+        Self {
+            model_id: None,
+            system: None,
+            max_tokens: 512,
+            temperature: 0.7,
+            top_p: 0.95,
+            context_size: 4096,
+            chat: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Generation {
+    text: String,
+    model_id: String,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+    /// "stop" (end of generation token) or "length" (hit max_tokens).
+    finish_reason: String,
+}
+
+#[cfg(feature = "llama")]
+mod engine {
+    use std::num::NonZeroU32;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, OnceLock};
+
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{LlamaChatMessage, LlamaModel};
+    use llama_cpp_2::sampling::LlamaSampler;
+
+    use super::{GenerateParams, Generation};
+    use crate::hardware::BackendTuning;
+
+    /// llama.cpp's global state; can only be initialised once per process.
+    static BACKEND: OnceLock<LlamaBackend> = OnceLock::new();
+    static LOADED: Mutex<Option<(PathBuf, LlamaModel)>> = Mutex::new(None);
+
+    /// Prompt tokens decoded per batch.
+    const BATCH_SIZE: usize = 512;
+
+    fn backend() -> Result<&'static LlamaBackend, String> {
+        if let Some(b) = BACKEND.get() {
+            return Ok(b);
+        }
+        let b = LlamaBackend::init().map_err(|e| format!("llama.cpp init failed: {}", e))?;
+        Ok(BACKEND.get_or_init(|| b))
+    }
+
+    fn build_prompt(model: &LlamaModel, prompt: &str, params: &GenerateParams) -> String {
+        if !params.chat {
+            return prompt.to_string();
+        }
+        let mut chat = Vec::new();
+        if let Some(system) = params.system.as_deref() {
+            chat.extend(LlamaChatMessage::new("system".into(), system.into()));
+        }
+        chat.extend(LlamaChatMessage::new("user".into(), prompt.into()));
+        // Models without an embedded template get the prompt as-is.
+        model
+            .chat_template(None)
+            .ok()
+            .and_then(|tmpl| model.apply_chat_template(&tmpl, &chat, true).ok())
+            .unwrap_or_else(|| prompt.to_string())
+    }
+
+    /// Blocking: loads the model if needed and runs to completion.
+    pub(super) fn generate(
+        path: &Path,
+        model_id: String,
+        prompt: &str,
+        params: &GenerateParams,
+        tuning: &BackendTuning,
+    ) -> Result<Generation, String> {
+        let backend = backend()?;
+        let mut loaded = LOADED.lock().map_err(|e| e.to_string())?;
+        if loaded.as_ref().map(|(p, _)| p.as_path()) != Some(path) {
+            *loaded = None; // free the old weights before mapping new ones
+            let gpu_layers = u32::try_from(tuning.gpu_layers).unwrap_or(u32::MAX);
+            let model_params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+            tracing::info!(
+                "[Llama] Loading {:?} (gpu_layers={})",
+                path,
+                tuning.gpu_layers
+            );
+            let model = LlamaModel::load_from_file(backend, path, &model_params)
+                .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+            *loaded = Some((path.to_path_buf(), model));
+        }
+        let (_, model) = loaded.as_ref().ok_or("Model not loaded")?;
+
+        let threads = i32::try_from(tuning.threads).unwrap_or(4);
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(params.context_size))
+            .with_n_threads(threads)
+            .with_n_threads_batch(threads);
+        let mut ctx = model
+            .new_context(backend, ctx_params)
+            .map_err(|e| format!("Failed to create context: {}", e))?;
+
+        let vocab = model.vocab();
+        let tokens = vocab.tokenize(build_prompt(model, prompt, params).as_bytes(), true, true);
+        let prompt_tokens = tokens.len();
+        let n_ctx = ctx.n_ctx() as usize;
+        if prompt_tokens + 1 >= n_ctx {
+            return Err(format!(
+                "Prompt is {} tokens; the context window is {}",
+                prompt_tokens, n_ctx
+            ));
+        }
+
+        let mut batch = LlamaBatch::new(BATCH_SIZE, 1);
+        for (chunk_index, chunk) in tokens.chunks(BATCH_SIZE).enumerate() {
+            batch.clear();
+            for (i, token) in chunk.iter().enumerate() {
+                let pos = chunk_index * BATCH_SIZE + i;
+                let last = pos == prompt_tokens - 1;
+                batch
+                    .add(*token, pos as i32, &[0], last)
+                    .map_err(|e| e.to_string())?;
+            }
+            ctx.decode(&mut batch)
+                .map_err(|e| format!("Prompt decode failed: {}", e))?;
+        }
+
+        let mut sampler = LlamaSampler::chain_simple([
+            LlamaSampler::top_p(params.top_p, 1),
+            LlamaSampler::temp(params.temperature),
+            LlamaSampler::dist(rand_seed()),
+        ]);
+        let budget = (params.max_tokens as usize).min(n_ctx - prompt_tokens);
+        let mut bytes = Vec::new();
+        let mut completion_tokens = 0;
+        let mut finish_reason = "length";
+        let mut pos = prompt_tokens;
+        while completion_tokens < budget {
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if vocab.is_eog(token) {
+                finish_reason = "stop";
+                break;
+            }
+            // Pieces can split a UTF-8 sequence; decode once at the end.
+            bytes.extend(vocab.token_to_piece(token, false, None));
+            completion_tokens += 1;
+
+            batch.clear();
+            batch
+                .add(token, pos as i32, &[0], true)
+                .map_err(|e| e.to_string())?;
+            pos += 1;
+            ctx.decode(&mut batch)
+                .map_err(|e| format!("Decode failed: {}", e))?;
+        }
+
+        Ok(Generation {
+            text: String::from_utf8_lossy(&bytes).trim().to_string(),
+            model_id,
+            prompt_tokens,
+            completion_tokens,
+            finish_reason: finish_reason.to_string(),
+        })
+    }
+
+    /// Drop the loaded model; the next `generate` loads it again. Returns its
+    /// path, if one was loaded.
+    pub(super) fn unload() -> Option<PathBuf> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        loaded.take().map(|(path, _)| path)
+    }
+
+    fn rand_seed() -> u32 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0)
+    }
+}
+
+/// Free the in-process model's memory, if one is loaded. Returns its registry
+/// file name.
+pub(crate) fn unload() -> Option<String> {
+    #[cfg(feature = "llama")]
+    {
+        engine::unload().and_then(|p| p.file_name().map(|f| f.to_string_lossy().into_owned()))
+    }
+    #[cfg(not(feature = "llama"))]
+    {
+        None
+    }
+}
+
+/// Generate a completion with the built-in engine. Meant as a fallback when
+/// `get_backend_status` says the backend is unavailable.
+#[tauri::command]
+pub(crate) async fn generate(
+    app: AppHandle,
+    prompt: String,
+    params: Option<GenerateParams>,
+) -> Result<Generation, String> {
+    #[cfg(feature = "llama")]
+    {
+        let params = params.unwrap_or_default();
+        let dir = crate::models::models_dir(&app)?;
+        let entry = crate::models::load_registry(&app)
+            .into_iter()
+            .filter(|e| e.format == "gguf")
+            .filter(|e| params.model_id.as_ref().is_none_or(|id| &e.id == id))
+            .max_by_key(|e| e.downloaded_at)
+            .ok_or_else(|| match &params.model_id {
+                Some(id) => format!("No downloaded GGUF model with id {}", id),
+                None => "No GGUF model downloaded — get one from the model manager".to_string(),
+            })?;
+        let path = dir.join(&entry.file_name);
+        if !path.exists() {
+            return Err(format!("Model file missing: {}", path.display()));
+        }
+        let _slot =
+            crate::inference::acquire(&app, crate::inference::Priority::Interactive, "llama").await;
+        tauri::async_runtime::spawn_blocking(move || {
+            let tuning = crate::hardware::tuning(&app);
+            engine::generate(&path, entry.id, &prompt, &params, &tuning)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+    #[cfg(not(feature = "llama"))]
+    {
+        let _ = (app, prompt, params);
+        Err("This build does not include the built-in llama.cpp engine".to_string())
+    }
+}
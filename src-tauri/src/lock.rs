@@ -0,0 +1,585 @@
+//! App lock — a passcode, or Touch ID / Windows Hello, before any notebook
+//! content is shown.
+//!
+//! The lock lives in Rust rather than in the webview: while locked, every app
+//! window is hidden and blanked (navigated to `about:blank`, and back to its
+//! page on unlock), so no notebook content stays rendered. Only the `lock`
+//! window (the SPA booted with `?view=lock`, which holds no notebook data) is
+//! on screen, `hardening` refuses commands from every other window, and
+//! opening a window from the tray, hotkey or a command brings up the lock
+//! window instead.
+//! The app locks at launch and, with an idle timeout set, once none of its
+//! windows has had focus for that long. With auto-lock set it also locks once
+//! the user has been away from the computer that long (no keyboard or mouse
+//! input, from `idle`) — whichever window is up front, mini window included.
+//! Both timers run here rather than in any one webview. Changes are announced
+//! as `app-lock://changed`.
+//!
+//! The passcode is stored only as an Argon2 hash, in the keychain. Setting
+//! one also gives a recovery key, shown once; its hash is kept in settings.
+//! If the keychain entry has gone (a reset keychain, a profile copied to
+//! another machine) the app stays locked: there's nothing to check a
+//! passcode against, so it only unlocks with biometrics or with the recovery
+//! key and a new passcode (`recover_app_lock`).
+//!
+//! Command-line runs (see `cli`) never lock: they have no window to unlock
+//! from, so every request would be refused.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+pub(crate) const LOCK_WINDOW_LABEL: &str = "lock";
+const PASSCODE_SECRET: &str = "app_lock_passcode";
+const MIN_PASSCODE_LEN: usize = 4;
+/// Recovery keys: 25 characters from an alphabet without look-alikes
+/// (125 bits), shown in groups of five.
+const RECOVERY_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const RECOVERY_KEY_LEN: usize = 25;
+const MISSING_PASSCODE: &str =
+    "The passcode is missing from the keychain; unlock with the recovery key";
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// Slows down guessing; doubled after each further failure, up to a minute.
+const FAILED_UNLOCK_DELAY: Duration = Duration::from_secs(1);
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+/// Windows that were visible when the app locked, to show again on unlock.
+static HIDDEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// The page each blanked window was showing, to go back to on unlock.
+static BLANKED: Mutex<Vec<(String, tauri::Url)>> = Mutex::new(Vec::new());
+/// When the last app window lost focus; None while one has it.
+static BACKGROUND_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+static FAILED_ATTEMPTS: Mutex<u32> = Mutex::new(0);
+/// One passcode check at a time, delay included, so guesses sent in
+/// parallel still wait their turn.
+static UNLOCK_ATTEMPT: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// Idle samples are up to `POLL_INTERVAL` old, so auto-lock also waits this
+/// long after an unlock.
+static UNLOCKED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct LockState {
+    enabled: bool,
+    locked: bool,
+    /// Touch ID / Windows Hello can be used on this machine.
+    biometrics_available: bool,
+    biometrics_enabled: bool,
+    idle_timeout_mins: Option<u64>,
+    auto_lock_mins: Option<u64>,
+    /// System idle time can be read here, so auto-lock works.
+    auto_lock_available: bool,
+    /// A new recovery key, only in the reply that set a passcode; it isn't
+    /// stored anywhere it could be read back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recovery_key: Option<String>,
+}
+
+pub(crate) fn is_locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+fn state(app: &AppHandle) -> LockState {
+    let settings = crate::settings::get(app).lock;
+    LockState {
+        enabled: settings.enabled,
+        locked: is_locked(),
+        biometrics_available: biometrics::available(),
+        biometrics_enabled: settings.biometrics,
+        idle_timeout_mins: settings.idle_timeout_mins,
+        auto_lock_mins: settings.auto_lock_mins,
+        auto_lock_available: crate::idle::user_idle_secs().is_some(),
+        recovery_key: None,
+    }
+}
+
+fn emit_changed(app: &AppHandle) {
+    let _ = app.emit("app-lock://changed", state(app));
+}
+
+/// Show (creating if needed) and focus the lock window.
+pub(crate) fn show_lock_window(app: &AppHandle) {
+    if let Some(w) = app.get_webview_window(LOCK_WINDOW_LABEL) {
+        let _ = w.show();
+        let _ = w.unminimize();
+        let _ = w.set_focus();
+        return;
+    }
+    let built = WebviewWindowBuilder::new(
+        app,
+        LOCK_WINDOW_LABEL,
+        WebviewUrl::App("index.html?view=lock".into()),
+    )
+    .title("LocalBook")
+    .inner_size(420.0, 520.0)
+    .resizable(false)
+    .maximizable(false)
+    .center()
+    .focused(true)
+    .build();
+    if let Err(e) = built {
+        tracing::warn!("[Lock] Failed to open lock window: {}", e);
+    }
+}
+
+/// Hide every window and put up the lock window. No-op unless a passcode is
+/// set.
+pub(crate) fn lock(app: &AppHandle) {
+    if !crate::settings::get(app).lock.enabled || LOCKED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut hidden = HIDDEN.lock().unwrap_or_else(|e| e.into_inner());
+    let mut blanked = BLANKED.lock().unwrap_or_else(|e| e.into_inner());
+    hidden.clear();
+    blanked.clear();
+    for (label, w) in app.webview_windows() {
+        if label == LOCK_WINDOW_LABEL || label == crate::splash::SPLASH_WINDOW_LABEL {
+            continue;
+        }
+        if w.is_visible().unwrap_or(false) {
+            let _ = w.hide();
+            hidden.push(label.clone());
+        }
+        // Hidden windows still hold their page; drop it until unlocked.
+        match w.url() {
+            Ok(url) if url.scheme() != "about" => {
+                if let Err(e) = w.navigate(blank()) {
+                    tracing::warn!("[Lock] Could not blank {}: {}", label, e);
+                }
+                blanked.push((label, url));
+            }
+            _ => {}
+        }
+    }
+    drop(blanked);
+    drop(hidden);
+    show_lock_window(app);
+    tracing::info!("[Lock] Locked");
+    crate::audit::record("app_locked", "");
+    emit_changed(app);
+}
+
+fn unlock(app: &AppHandle) {
+    if !LOCKED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    *FAILED_ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner()) = 0;
+    *BACKGROUND_SINCE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    *UNLOCKED_AT.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    let blanked = std::mem::take(&mut *BLANKED.lock().unwrap_or_else(|e| e.into_inner()));
+    for (label, url) in blanked {
+        if let Some(w) = app.get_webview_window(&label) {
+            if let Err(e) = w.navigate(url) {
+                tracing::warn!("[Lock] Could not restore {}: {}", label, e);
+            }
+        }
+    }
+    let mut hidden = std::mem::take(&mut *HIDDEN.lock().unwrap_or_else(|e| e.into_inner()));
+    if hidden.is_empty() {
+        hidden.push("main".to_string());
+    }
+    for label in hidden {
+        if let Some(w) = app.get_webview_window(&label) {
+            let _ = w.show();
+            let _ = w.set_focus();
+        }
+    }
+    if let Some(w) = app.get_webview_window(LOCK_WINDOW_LABEL) {
+        let _ = w.close();
+    }
+    tracing::info!("[Lock] Unlocked");
+    emit_changed(app);
+}
+
+fn blank() -> tauri::Url {
+    tauri::Url::parse("about:blank").expect("about:blank is a valid URL")
+}
+
+fn hash_passcode(passcode: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passcode.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Failed to hash passcode: {}", e))
+}
+
+/// None when no passcode is stored. Blocking (keychain).
+fn check_passcode(passcode: &str) -> Result<Option<bool>, String> {
+    let Some(stored) = crate::secrets::get(PASSCODE_SECRET)? else {
+        return Ok(None);
+    };
+    let hash =
+        PasswordHash::new(&stored).map_err(|e| format!("Stored passcode is corrupt: {}", e))?;
+    Ok(Some(
+        Argon2::default()
+            .verify_password(passcode.as_bytes(), &hash)
+            .is_ok(),
+    ))
+}
+
+async fn check_passcode_async(passcode: String) -> Result<Option<bool>, String> {
+    tauri::async_runtime::spawn_blocking(move || check_passcode(&passcode))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Upper case, without the group separators people type or paste.
+fn normalize_recovery_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn new_recovery_key() -> String {
+    let mut bytes = [0u8; RECOVERY_KEY_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    let chars: Vec<char> = bytes
+        .iter()
+        .map(|b| RECOVERY_ALPHABET[(*b as usize) % RECOVERY_ALPHABET.len()] as char)
+        .collect();
+    chars
+        .chunks(5)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn check_recovery_key(app: &AppHandle, key: &str) -> bool {
+    let Some(stored) = crate::settings::get(app).lock.recovery_hash else {
+        return false;
+    };
+    PasswordHash::new(&stored).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(normalize_recovery_key(key).as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Store the passcode's hash in the keychain and a new recovery key's hash
+/// in settings, turning the lock on. Returns the recovery key.
+async fn save_passcode(app: &AppHandle, passcode: &str) -> Result<String, String> {
+    if passcode.chars().count() < MIN_PASSCODE_LEN {
+        return Err(format!(
+            "Passcode must be at least {} characters",
+            MIN_PASSCODE_LEN
+        ));
+    }
+    let hash = hash_passcode(passcode)?;
+    let recovery_key = new_recovery_key();
+    let recovery_hash = hash_passcode(&normalize_recovery_key(&recovery_key))?;
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::set(PASSCODE_SECRET, &hash))
+        .await
+        .map_err(|e| e.to_string())??;
+    crate::settings::update(app, |s| {
+        s.lock.enabled = true;
+        s.lock.recovery_hash = Some(recovery_hash);
+    })?;
+    Ok(recovery_key)
+}
+
+/// Count a failed unlock and wait before answering, longer after each one.
+async fn failed_attempt(what: &str) -> String {
+    let failures = {
+        let mut n = FAILED_ATTEMPTS.lock().unwrap_or_else(|e| e.into_inner());
+        *n += 1;
+        *n
+    };
+    tracing::warn!("[Lock] Wrong {} ({} failed attempts)", what, failures);
+    crate::audit::record("unlock_failed", format!("{} failed attempts", failures));
+    let delay = FAILED_UNLOCK_DELAY * 2u32.pow(failures.min(7) - 1);
+    tokio::time::sleep(delay.min(Duration::from_secs(60))).await;
+    format!("Incorrect {}", what)
+}
+
+/// Whether either timer has run out.
+fn timed_out(settings: &crate::settings::LockSettings) -> bool {
+    let background = settings.idle_timeout_mins.is_some_and(|mins| {
+        BACKGROUND_SINCE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|t| t.elapsed() >= Duration::from_secs(mins * 60))
+    });
+    let away = settings.auto_lock_mins.is_some_and(|mins| {
+        let since_unlock = UNLOCKED_AT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .map_or(u64::MAX, |t| t.elapsed().as_secs());
+        crate::idle::user_idle_secs().is_some_and(|secs| secs.min(since_unlock) >= mins * 60)
+    });
+    background || away
+}
+
+/// Lock at launch if enabled, and watch the idle timers. Called once from
+/// setup; does nothing in a command-line run.
+pub(crate) fn start(app: &AppHandle) {
+    if crate::cli::active() {
+        return;
+    }
+    lock(app);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if !is_locked() && timed_out(&crate::settings::get(&app).lock) {
+                lock(&app);
+            }
+        }
+    });
+}
+
+/// Builder-level window event hook: tracks whether any app window has focus.
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if let WindowEvent::Focused(focused) = event {
+        let mut since = BACKGROUND_SINCE.lock().unwrap_or_else(|e| e.into_inner());
+        if *focused {
+            *since = None;
+        } else if window.label() != LOCK_WINDOW_LABEL {
+            // Focus moving between our own windows sends Focused(true) right after.
+            *since = Some(Instant::now());
+        }
+    }
+}
+
+/// Touch ID (macOS) and Windows Hello. Both prompts block until the user
+/// answers, so call them on a blocking thread.
+mod biometrics {
+    #[cfg(target_os = "macos")]
+    pub(super) fn available() -> bool {
+        use objc2_local_authentication::{LAContext, LAPolicy};
+        let ctx = unsafe { LAContext::new() };
+        unsafe { ctx.canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthenticationWithBiometrics) }
+            .is_ok()
+    }
+
+    #[cfg(target_os = "macos")]
+    pub(super) fn verify(_window: &tauri::WebviewWindow, reason: &str) -> Result<bool, String> {
+        use block2::RcBlock;
+        use objc2::runtime::Bool;
+        use objc2_foundation::{NSError, NSString};
+        use objc2_local_authentication::{LAContext, LAPolicy};
+
+        let ctx = unsafe { LAContext::new() };
+        let policy = LAPolicy::DeviceOwnerAuthenticationWithBiometrics;
+        if let Err(e) = unsafe { ctx.canEvaluatePolicy_error(policy) } {
+            return Err(format!(
+                "Touch ID unavailable: {}",
+                e.localizedDescription()
+            ));
+        }
+        let (tx, rx) = std::sync::mpsc::channel();
+        let reply = RcBlock::new(move |ok: Bool, _error: *mut NSError| {
+            let _ = tx.send(ok.as_bool());
+        });
+        unsafe {
+            ctx.evaluatePolicy_localizedReason_reply(policy, &NSString::from_str(reason), &reply)
+        };
+        rx.recv().map_err(|e| format!("Touch ID failed: {}", e))
+    }
+
+    #[cfg(windows)]
+    pub(super) fn available() -> bool {
+        use windows::Security::Credentials::UI::{
+            UserConsentVerifier, UserConsentVerifierAvailability,
+        };
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|op| op.get())
+            .is_ok_and(|a| a == UserConsentVerifierAvailability::Available)
+    }
+
+    #[cfg(windows)]
+    pub(super) fn verify(window: &tauri::WebviewWindow, reason: &str) -> Result<bool, String> {
+        use windows::core::{factory, HSTRING};
+        use windows::Security::Credentials::UI::{
+            UserConsentVerificationResult, UserConsentVerifier,
+        };
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::System::WinRT::IUserConsentVerifierInterop;
+        use windows_future::IAsyncOperation;
+
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+        let interop = factory::<UserConsentVerifier, IUserConsentVerifierInterop>()
+            .map_err(|e| format!("Windows Hello unavailable: {}", e))?;
+        // The interop call parents the prompt to our window; the plain WinRT
+        // call can open it behind the app.
+        let op: IAsyncOperation<UserConsentVerificationResult> = unsafe {
+            interop.RequestVerificationForWindowAsync(HWND(hwnd.0 as _), &HSTRING::from(reason))
+        }
+        .map_err(|e| format!("Windows Hello failed: {}", e))?;
+        let result = op
+            .get()
+            .map_err(|e| format!("Windows Hello failed: {}", e))?;
+        Ok(result == UserConsentVerificationResult::Verified)
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub(super) fn available() -> bool {
+        false
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub(super) fn verify(_window: &tauri::WebviewWindow, _reason: &str) -> Result<bool, String> {
+        Err("Biometric unlock isn't supported on this platform".to_string())
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_lock_state(app: AppHandle) -> Result<LockState, String> {
+    tauri::async_runtime::spawn_blocking(move || state(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Set, change or (with `passcode: None`) remove the passcode. Once one is
+/// set, `current` must match it. Setting one replies with a new recovery
+/// key.
+#[tauri::command]
+pub(crate) async fn set_lock_passcode(
+    app: AppHandle,
+    current: Option<String>,
+    passcode: Option<String>,
+) -> Result<LockState, String> {
+    if crate::settings::get(&app).lock.enabled {
+        match check_passcode_async(current.unwrap_or_default()).await? {
+            Some(true) => {}
+            Some(false) => return Err("Current passcode is incorrect".to_string()),
+            None => return Err(MISSING_PASSCODE.to_string()),
+        }
+    }
+    let recovery_key = match passcode {
+        Some(p) => {
+            let key = save_passcode(&app, &p).await?;
+            tracing::info!("[Lock] Passcode set");
+            Some(key)
+        }
+        None => {
+            crate::settings::update(&app, |s| {
+                s.lock.enabled = false;
+                s.lock.recovery_hash = None;
+            })?;
+            tauri::async_runtime::spawn_blocking(|| crate::secrets::delete(PASSCODE_SECRET))
+                .await
+                .map_err(|e| e.to_string())??;
+            tracing::info!("[Lock] Passcode removed");
+            None
+        }
+    };
+    let mut state = get_lock_state(app).await?;
+    state.recovery_key = recovery_key;
+    Ok(state)
+}
+
+/// `idle_timeout_mins`: lock after the app has been in the background this
+/// long; None never does.
+#[tauri::command]
+pub(crate) async fn set_lock_options(
+    app: AppHandle,
+    biometrics: bool,
+    idle_timeout_mins: Option<u64>,
+) -> Result<LockState, String> {
+    if idle_timeout_mins == Some(0) {
+        return Err("Idle timeout must be at least 1 minute".to_string());
+    }
+    crate::settings::update(&app, |s| {
+        s.lock.biometrics = biometrics;
+        s.lock.idle_timeout_mins = idle_timeout_mins;
+    })?;
+    get_lock_state(app).await
+}
+
+/// `auto_lock_mins`: lock after no keyboard or mouse input for this long;
+/// None turns auto-lock off.
+#[tauri::command]
+pub(crate) async fn set_auto_lock(
+    app: AppHandle,
+    auto_lock_mins: Option<u64>,
+) -> Result<LockState, String> {
+    if auto_lock_mins == Some(0) {
+        return Err("Auto-lock must be at least 1 minute".to_string());
+    }
+    crate::settings::update(&app, |s| s.lock.auto_lock_mins = auto_lock_mins)?;
+    get_lock_state(app).await
+}
+
+#[tauri::command]
+pub(crate) async fn lock_app(app: AppHandle) -> Result<(), String> {
+    if !crate::settings::get(&app).lock.enabled {
+        return Err("Set a passcode first".to_string());
+    }
+    lock(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn unlock_app(app: AppHandle, passcode: String) -> Result<(), String> {
+    let _attempt = UNLOCK_ATTEMPT.lock().await;
+    match check_passcode_async(passcode).await? {
+        Some(true) => {
+            crate::audit::record("app_unlocked", "passcode");
+            unlock(&app);
+            Ok(())
+        }
+        None => {
+            tracing::warn!("[Lock] No passcode in the keychain; staying locked");
+            crate::audit::record("app_lock_passcode_missing", "");
+            Err(MISSING_PASSCODE.to_string())
+        }
+        Some(false) => Err(failed_attempt("passcode").await),
+    }
+}
+
+/// Unlock with the recovery key given when the passcode was set, setting
+/// `passcode` as the new one — for when the passcode is forgotten or its
+/// keychain entry has gone. Replies with a new recovery key.
+#[tauri::command]
+pub(crate) async fn recover_app_lock(
+    app: AppHandle,
+    recovery_key: String,
+    passcode: String,
+) -> Result<LockState, String> {
+    let _attempt = UNLOCK_ATTEMPT.lock().await;
+    if !crate::settings::get(&app).lock.enabled {
+        return Err("The app lock is off".to_string());
+    }
+    let key_app = app.clone();
+    let valid =
+        tauri::async_runtime::spawn_blocking(move || check_recovery_key(&key_app, &recovery_key))
+            .await
+            .map_err(|e| e.to_string())?;
+    if !valid {
+        return Err(failed_attempt("recovery key").await);
+    }
+    let new_key = save_passcode(&app, &passcode).await?;
+    tracing::info!("[Lock] Passcode reset with the recovery key");
+    crate::audit::record("app_unlocked", "recovery key");
+    unlock(&app);
+    let mut state = get_lock_state(app).await?;
+    state.recovery_key = Some(new_key);
+    Ok(state)
+}
+
+#[tauri::command]
+pub(crate) async fn unlock_with_biometrics(app: AppHandle) -> Result<(), String> {
+    if !crate::settings::get(&app).lock.biometrics {
+        return Err("Biometric unlock is turned off".to_string());
+    }
+    let window = app
+        .get_webview_window(LOCK_WINDOW_LABEL)
+        .ok_or("The app isn't locked")?;
+    let verified = tauri::async_runtime::spawn_blocking(move || {
+        biometrics::verify(&window, "unlock LocalBook")
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if !verified {
+        return Err("Not verified".to_string());
+    }
+    crate::audit::record("app_unlocked", "biometrics");
+    unlock(&app);
+    Ok(())
+}
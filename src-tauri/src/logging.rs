@@ -0,0 +1,725 @@
+//! Persistent shell log with PII redaction.
+//!
+//! Logging is `tracing`, with the subscriber here; modules log through its
+//! macros (progress as info, recoverable failures as warn). Each event goes to
+//! stdout/stderr as plain text for `tauri dev` (all of it to stderr in a
+//! command-line run, whose stdout is its output; see `cli`), and a sanitized
+//! JSON copy — time, level, target, message, fields and the spans it
+//! happened in — is appended to `<app data>/logs/shell.log`, the file people
+//! attach to bug reports. The file rotates at `MAX_FILE_BYTES`, keeping
+//! `KEEP_ROTATED` older files.
+//!
+//! Which events are kept is a filter like `debug,jobs=trace,reqwest=info`:
+//! a bare level for this crate, and levels for its modules or for other
+//! crates, the longest match winning. Other crates log warnings only unless
+//! named. The filter is the `LOCALBOOK_LOG` environment variable if set,
+//! else the one saved by `set_log_level`, which also changes it at runtime,
+//! else debug while debug mode is on (see `debug_mode`).
+//!
+//! The backend's stdout and stderr come through here too: passed on as
+//! before, and kept sanitized in `sidecar.log` in the same JSON form. The
+//! log viewer reads both files with `tail_logs` and, while a window has
+//! called `subscribe_logs`, gets each new line as a `logs://line` event.
+//!
+//! Sanitizing replaces every path under the user's home folder with
+//! `~/[hash].ext` (same file, same hash, so a log still shows that two lines
+//! are about the same document) and the user name with `[user]`. Document
+//! titles and prompt text can't be recognised in free text, so code that logs
+//! them wraps them in `redact::text` first.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter, WebviewWindow};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+
+const LOG_DIR: &str = "logs";
+const SHELL: &str = "shell";
+const SIDECAR: &str = "sidecar";
+const LINE_EVENT: &str = "logs://line";
+const DEFAULT_TAIL: usize = 200;
+const MAX_TAIL: usize = 5000;
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEEP_ROTATED: usize = 3;
+/// While debug mode is on and no level was chosen.
+const DEBUG_FILTER: &str = "debug";
+const FILTER_ENV: &str = "LOCALBOOK_LOG";
+const CRATE: &str = env!("CARGO_CRATE_NAME");
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+/// For crates the filter doesn't name.
+const OTHER_CRATES: LevelFilter = LevelFilter::WARN;
+
+/// `<name>.log` in the log folder, rotated at `MAX_FILE_BYTES`.
+struct Sink {
+    dir: PathBuf,
+    name: &'static str,
+    file: File,
+    size: u64,
+}
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+static SIDECAR_SINK: Mutex<Option<Sink>> = Mutex::new(None);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+/// Windows showing the log viewer, by label.
+static WATCHERS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// None until a filter is set: `DEFAULT_LEVEL` for this crate.
+static FILTER: RwLock<Option<Filter>> = RwLock::new(None);
+static SPANS: Mutex<BTreeMap<u64, SpanData>> = Mutex::new(BTreeMap::new());
+static NEXT_SPAN: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Spans entered on this thread, innermost last.
+    static STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Filter {
+    spec: String,
+    level: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter {
+            spec: spec.trim().to_string(),
+            level: DEFAULT_LEVEL,
+            targets: Vec::new(),
+        };
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let level = |l: &str| {
+                l.parse::<LevelFilter>()
+                    .map_err(|_| format!("Unknown log level \"{}\"", l))
+            };
+            match part.split_once('=') {
+                Some((target, l)) if !target.trim().is_empty() => filter
+                    .targets
+                    .push((target.trim().to_string(), level(l.trim())?)),
+                Some(_) => return Err(format!("No module in \"{}\"", part)),
+                None => filter.level = level(part)?,
+            }
+        }
+        Ok(filter)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let target = metadata.target();
+        // This crate's modules can be named without the crate: `jobs`.
+        let module = target
+            .strip_prefix(CRATE)
+            .filter(|rest| rest.is_empty() || rest.starts_with("::"))
+            .map(|rest| rest.trim_start_matches("::"));
+        let under = |path: &str, t: &str| {
+            path == t
+                || path
+                    .strip_prefix(t)
+                    .is_some_and(|rest| rest.starts_with("::"))
+        };
+        let named = self
+            .targets
+            .iter()
+            .filter(|(t, _)| under(target, t) || module.is_some_and(|m| under(m, t)))
+            .max_by_key(|(t, _)| t.len());
+        let level = match (named, module) {
+            (Some((_, level)), _) => *level,
+            (None, Some(_)) => self.level,
+            (None, None) => OTHER_CRATES,
+        };
+        level >= *metadata.level()
+    }
+}
+
+fn with_filter<T>(f: impl FnOnce(&Filter) -> T) -> T {
+    let current = FILTER.read().unwrap_or_else(|e| e.into_inner());
+    match current.as_ref() {
+        Some(filter) => f(filter),
+        None => f(&Filter {
+            spec: String::new(),
+            level: DEFAULT_LEVEL,
+            targets: Vec::new(),
+        }),
+    }
+}
+
+fn set_filter(filter: Filter) {
+    *FILTER.write().unwrap_or_else(|e| e.into_inner()) = Some(filter);
+}
+
+struct SpanData {
+    name: &'static str,
+    fields: Map<String, Value>,
+    refs: usize,
+}
+
+fn spans() -> std::sync::MutexGuard<'static, BTreeMap<u64, SpanData>> {
+    SPANS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Collects an event's or span's fields.
+struct Fields<'a>(&'a mut Map<String, Value>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+}
+
+pub(crate) mod redact {
+    use sha2::{Digest, Sha256};
+
+    fn short_hash(s: &str) -> String {
+        format!("{:x}", Sha256::digest(s.as_bytes()))[..8].to_string()
+    }
+
+    fn home() -> Option<String> {
+        std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .ok()
+            .map(|h| h.trim_end_matches(['/', '\\']).to_string())
+            .filter(|h| h.len() > 1)
+    }
+
+    /// Titles, questions, note text: keep the length, hide the words.
+    pub(crate) fn text(s: &str) -> String {
+        format!("[{} chars #{}]", s.chars().count(), short_hash(s))
+    }
+
+    /// `/Users/ana/Documents/Plan.pdf` → `~/[1a2b3c4d].pdf`.
+    fn path(p: &str, home: &str) -> String {
+        let rest = p[home.len()..].trim_start_matches(['/', '\\']);
+        if rest.is_empty() {
+            return "~".to_string();
+        }
+        let ext = std::path::Path::new(rest)
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        format!("~/[{}]{}", short_hash(rest), ext)
+    }
+
+    /// Scrub one log line.
+    pub(crate) fn line(s: &str) -> String {
+        let Some(home) = home() else {
+            return s.to_string();
+        };
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(start) = rest.find(&home) {
+            out.push_str(&rest[..start]);
+            // A path runs to the next quote, bracket, comma or line end —
+            // file names have spaces, so over-redacting beats leaking.
+            let tail = &rest[start..];
+            let end = tail
+                .find(['"', '\'', '`', ')', ']', '}', ',', '\n'])
+                .unwrap_or(tail.len());
+            out.push_str(&path(&tail[..end], &home));
+            rest = &tail[end..];
+        }
+        out.push_str(rest);
+        match std::path::Path::new(&home).file_name() {
+            Some(user) if user.len() > 2 => user_in_paths(&out, &user.to_string_lossy()),
+            _ => out,
+        }
+    }
+
+    /// The user name where it's a path component (`/Volumes/x/Users/ana/…`,
+    /// `C:\Users\ana`), since paths outside the home folder can carry it
+    /// too. Anywhere else it's left alone — it may well be an ordinary word.
+    fn user_in_paths(s: &str, user: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(i) = rest.find(user) {
+            let after = &rest[i + user.len()..];
+            let component = rest[..i].ends_with(['/', '\\'])
+                && after
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric() && !matches!(c, '-' | '_' | '.'));
+            out.push_str(&rest[..i]);
+            out.push_str(if component { "[user]" } else { user });
+            rest = after;
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// `<name>.log`, or with `rotated` > 0 the older `<name>.<rotated>.log`.
+fn file_path(dir: &Path, name: &str, rotated: usize) -> PathBuf {
+    if rotated == 0 {
+        dir.join(format!("{}.log", name))
+    } else {
+        dir.join(format!("{}.{}.log", name, rotated))
+    }
+}
+
+impl Sink {
+    fn open(dir: &Path, name: &'static str) -> std::io::Result<Sink> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path(dir, name, 0))?;
+        let size = file.metadata()?.len();
+        Ok(Sink {
+            dir: dir.to_path_buf(),
+            name,
+            file,
+            size,
+        })
+    }
+
+    /// shell.log → shell.1.log → … → shell.<KEEP_ROTATED>.log (dropped).
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let name = |i: usize| file_path(&self.dir, self.name, i);
+        let _ = std::fs::remove_file(name(KEEP_ROTATED));
+        for i in (1..KEEP_ROTATED).rev() {
+            let _ = std::fs::rename(name(i), name(i + 1));
+        }
+        std::fs::rename(name(0), name(1))?;
+        *self = Sink::open(&self.dir, self.name)?;
+        Ok(())
+    }
+
+    fn append(&mut self, entry: &Value) {
+        let mut line = entry.to_string();
+        line.push('\n');
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+        if self.size > MAX_FILE_BYTES {
+            if let Err(e) = self.rotate() {
+                std::eprintln!("[Logging] Rotation failed: {}", e);
+            }
+        }
+    }
+}
+
+/// `<app data>/logs`.
+pub(crate) fn dir(app: &AppHandle) -> Result<PathBuf, String> {
+    crate::data_dir(app).map(|d| d.join(LOG_DIR))
+}
+
+/// Open the log files. Lines printed before this (early in `run()`) only go
+/// to stdout.
+pub(crate) fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let Ok(dir) = dir(app) else {
+        return;
+    };
+    for (sink, name) in [(&SINK, SHELL), (&SIDECAR_SINK, SIDECAR)] {
+        match Sink::open(&dir, name) {
+            Ok(opened) => *sink.lock().unwrap_or_else(|e| e.into_inner()) = Some(opened),
+            Err(e) => std::eprintln!("[Logging] Can't open {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// Send a line to the windows showing the log viewer.
+fn broadcast(source: &str, mut entry: Value) {
+    let watchers = WATCHERS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(app) = APP.get().filter(|_| !watchers.is_empty()) else {
+        return;
+    };
+    entry["source"] = json!(source);
+    for label in watchers {
+        let _ = app.emit_to(label.as_str(), LINE_EVENT, &entry);
+    }
+}
+
+/// Scrub the text in a set of fields for the file.
+fn redact_fields(fields: &Map<String, Value>) -> Map<String, Value> {
+    fields
+        .iter()
+        .map(|(k, v)| {
+            let v = match v {
+                Value::String(s) => Value::String(redact::line(s)),
+                other => other.clone(),
+            };
+            (k.clone(), v)
+        })
+        .collect()
+}
+
+fn write(event: &Event<'_>) {
+    let metadata = event.metadata();
+    let mut fields = Map::new();
+    event.record(&mut Fields(&mut fields));
+    let message = match fields.remove("message") {
+        Some(Value::String(s)) => s,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+
+    let mut console = message.clone();
+    for (key, value) in &fields {
+        match value {
+            Value::String(s) => console.push_str(&format!(" {}={}", key, s)),
+            other => console.push_str(&format!(" {}={}", key, other)),
+        }
+    }
+    if *metadata.level() <= Level::WARN || crate::cli::active() {
+        std::eprintln!("{}", console);
+    } else {
+        std::println!("{}", console);
+    }
+
+    let mut entry = Map::new();
+    entry.insert(
+        "time".into(),
+        json!(chrono::Local::now()
+            .format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+            .to_string()),
+    );
+    entry.insert("level".into(), json!(metadata.level().as_str()));
+    entry.insert("target".into(), json!(metadata.target()));
+    entry.insert("message".into(), json!(redact::line(&message)));
+    if !fields.is_empty() {
+        entry.insert("fields".into(), Value::Object(redact_fields(&fields)));
+    }
+    let stack = STACK.with(|s| s.borrow().clone());
+    if !stack.is_empty() {
+        let spans = spans();
+        let entered: Vec<Value> = stack
+            .iter()
+            .filter_map(|id| spans.get(id))
+            .map(|span| {
+                let mut value = redact_fields(&span.fields);
+                value.insert("name".into(), json!(span.name));
+                Value::Object(value)
+            })
+            .collect();
+        entry.insert("spans".into(), Value::Array(entered));
+    }
+    let entry = Value::Object(entry);
+    if let Some(sink) = SINK.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        sink.append(&entry);
+    }
+    broadcast(SHELL, entry);
+}
+
+/// The subscriber behind every log line.
+struct Shell;
+
+impl Subscriber for Shell {
+    // The filter can change at runtime, so ask every time.
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        with_filter(|f| f.enabled(metadata))
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = NEXT_SPAN.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Map::new();
+        span.record(&mut Fields(&mut fields));
+        let name = span.metadata().name();
+        spans().insert(
+            id,
+            SpanData {
+                name,
+                fields,
+                refs: 1,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(span) = spans().get_mut(&span.into_u64()) {
+            values.record(&mut Fields(&mut span.fields));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        write(event);
+    }
+
+    fn enter(&self, span: &Id) {
+        STACK.with(|s| s.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            if let Some(i) = stack.iter().rposition(|id| *id == span.into_u64()) {
+                stack.remove(i);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(data) = spans().get_mut(&span.into_u64()) {
+            data.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = spans();
+        let closed = spans.get_mut(&span.into_u64()).is_some_and(|data| {
+            data.refs -= 1;
+            data.refs == 0
+        });
+        if closed {
+            spans.remove(&span.into_u64());
+        }
+        closed
+    }
+}
+
+/// Install the subscriber, with the filter from `LOCALBOOK_LOG` if set.
+/// First thing in `run()`, so no line is missed.
+pub(crate) fn init() {
+    if let Ok(spec) = std::env::var(FILTER_ENV) {
+        match Filter::parse(&spec) {
+            Ok(filter) => set_filter(filter),
+            Err(e) => std::eprintln!("[Logging] Ignoring {}: {}", FILTER_ENV, e),
+        }
+    }
+    if tracing::subscriber::set_global_default(Shell).is_err() {
+        std::eprintln!("[Logging] A log subscriber was already installed");
+    }
+}
+
+/// Use the filter saved in settings, unless `LOCALBOOK_LOG` gave one.
+/// Called from setup once settings are loaded, and when debug mode changes.
+pub(crate) fn load_filter(app: &AppHandle) {
+    if std::env::var_os(FILTER_ENV).is_some() {
+        return;
+    }
+    let settings = crate::settings::get(app);
+    let spec = match settings.log_level {
+        Some(spec) => spec,
+        None if settings.debug_mode => DEBUG_FILTER.to_string(),
+        None => String::new(),
+    };
+    match Filter::parse(&spec) {
+        Ok(filter) => set_filter(filter),
+        Err(e) => tracing::warn!("[Logging] Ignoring the saved log level: {}", e),
+    }
+}
+
+/// The current filter; empty for the default.
+#[tauri::command]
+pub(crate) async fn get_log_level() -> Result<String, String> {
+    Ok(with_filter(|f| f.spec.clone()))
+}
+
+/// Change the filter now and for later runs; empty goes back to the default.
+#[tauri::command]
+pub(crate) async fn set_log_level(app: AppHandle, level: String) -> Result<String, String> {
+    let filter = Filter::parse(&level)?;
+    let spec = filter.spec.clone();
+    crate::settings::update(&app, |s| {
+        s.log_level = Some(spec.clone()).filter(|s| !s.is_empty())
+    })?;
+    set_filter(filter);
+    tracing::info!("[Logging] Log level set to \"{}\"", spec);
+    Ok(spec)
+}
+
+/// The level a backend line was logged at, from the word for it near the
+/// start (`[12:00:01] WARNING  …`, `ERROR:uvicorn…`); info if there's none.
+fn sidecar_level(line: &str) -> &'static str {
+    let head: String = line.chars().take(48).collect();
+    head.split(|c: char| !c.is_ascii_alphabetic())
+        .find_map(|word| match word {
+            "CRITICAL" | "ERROR" => Some("ERROR"),
+            "WARNING" | "WARN" => Some("WARN"),
+            "INFO" => Some("INFO"),
+            "DEBUG" => Some("DEBUG"),
+            _ => None,
+        })
+        .unwrap_or("INFO")
+}
+
+/// Pass one of the backend's output streams on as before, and keep it.
+fn relay(stream: impl Read, stderr: bool, spawn: u64) {
+    let mut reader = BufReader::new(stream);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => return crate::backend_events::stream_closed(spawn),
+            Ok(_) => {}
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if stderr || crate::cli::active() {
+            std::eprintln!("{}", line);
+        } else {
+            std::println!("{}", line);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        crate::backend_events::line(spawn, line, stderr);
+        let entry = json!({
+            "time": chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+            "level": sidecar_level(line),
+            "stream": if stderr { "stderr" } else { "stdout" },
+            "message": redact::line(line),
+        });
+        if let Some(sink) = SIDECAR_SINK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            sink.append(&entry);
+        }
+        broadcast(SIDECAR, entry);
+    }
+}
+
+/// Read the backend's output, which `backend_command` pipes, for as long as
+/// it runs.
+pub(crate) fn capture_sidecar(child: &mut std::process::Child) {
+    let spawn = crate::backend_events::spawned(child.id());
+    if let Some(stdout) = child.stdout.take() {
+        std::thread::spawn(move || relay(stdout, false, spawn));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        std::thread::spawn(move || relay(stderr, true, spawn));
+    }
+}
+
+/// One line of a log file as the viewer shows it. Lines from before the
+/// log went JSON were `<time> OUT|ERR <message>`.
+fn parse_line(source: &str, line: &str) -> Option<Value> {
+    let mut entry = match serde_json::from_str::<Value>(line) {
+        Ok(entry @ Value::Object(_)) => entry,
+        _ => {
+            let mut parts = line.splitn(3, ' ');
+            let time = parts.next()?;
+            let level = match parts.next()? {
+                "ERR" => "WARN",
+                _ => "INFO",
+            };
+            json!({ "time": time, "level": level, "message": parts.next().unwrap_or_default() })
+        }
+    };
+    entry["source"] = json!(source);
+    Some(entry)
+}
+
+/// The last `count` lines of one log, newest last, at `min` or worse.
+fn tail(dir: &Path, source: &str, count: usize, min: Option<Level>) -> Vec<Value> {
+    let kept = |entry: &Value| {
+        min.is_none_or(|min| {
+            entry["level"]
+                .as_str()
+                .and_then(|l| l.parse::<Level>().ok())
+                .is_some_and(|level| level <= min)
+        })
+    };
+    let mut lines = Vec::new();
+    // Newest file first, until there are enough.
+    for rotated in 0..=KEEP_ROTATED {
+        let Ok(text) = std::fs::read_to_string(file_path(dir, source, rotated)) else {
+            continue;
+        };
+        let older: Vec<Value> = text
+            .lines()
+            .filter_map(|line| parse_line(source, line))
+            .filter(kept)
+            .collect();
+        lines.splice(0..0, older);
+        if lines.len() >= count {
+            break;
+        }
+    }
+    let skip = lines.len().saturating_sub(count);
+    lines.split_off(skip)
+}
+
+/// The newest lines of the shell log, the backend's output or (`all`) both,
+/// oldest first. `level` keeps that level and worse.
+#[tauri::command]
+pub(crate) async fn tail_logs(
+    app: AppHandle,
+    source: String,
+    lines: Option<usize>,
+    level: Option<String>,
+) -> Result<Vec<Value>, String> {
+    let sources: &[&str] = match source.as_str() {
+        SHELL => &[SHELL],
+        SIDECAR => &[SIDECAR],
+        "all" => &[SHELL, SIDECAR],
+        other => return Err(format!("Unknown log \"{}\"", other)),
+    };
+    let min = match level.as_deref().filter(|l| !l.is_empty()) {
+        Some(l) => Some(
+            l.parse::<Level>()
+                .map_err(|_| format!("Unknown log level \"{}\"", l))?,
+        ),
+        None => None,
+    };
+    let count = lines.unwrap_or(DEFAULT_TAIL).min(MAX_TAIL);
+    let dir = dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut found: Vec<Value> = sources
+            .iter()
+            .flat_map(|source| tail(&dir, source, count, min))
+            .collect();
+        // Both logs' times are local RFC 3339 with milliseconds.
+        found.sort_by(|a, b| a["time"].as_str().cmp(&b["time"].as_str()));
+        let skip = found.len().saturating_sub(count);
+        found.split_off(skip)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Send this window each new log line, as `logs://line`.
+#[tauri::command]
+pub(crate) async fn subscribe_logs(window: WebviewWindow) -> Result<(), String> {
+    let mut watchers = WATCHERS.lock().unwrap_or_else(|e| e.into_inner());
+    if !watchers.iter().any(|l| l == window.label()) {
+        watchers.push(window.label().to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn unsubscribe_logs(window: WebviewWindow) -> Result<(), String> {
+    WATCHERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|l| l != window.label());
+    Ok(())
+}
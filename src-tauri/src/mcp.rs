@@ -0,0 +1,332 @@
+//! A Model Context Protocol server, so other AI clients on this machine —
+//! Claude Desktop, IDE agents — can search the LocalBook library and read
+//! its documents.
+//!
+//! The client starts `localbook mcp` (see `cli`) and talks JSON-RPC on its
+//! stdin and stdout, one message per line, as the MCP stdio transport has
+//! it. A client config entry is just the app's executable with `mcp` as its
+//! argument. When LocalBook is already open the server uses its backend;
+//! otherwise it boots one, which can take a while, so the handshake is
+//! answered at once and tool calls wait for the backend.
+//!
+//! Tools: `list_notebooks`, `list_documents`, `search` (passages, with no
+//! answer generated) and `get_document`. Each document is also a resource,
+//! `localbook://notebook/<id>/source/<id>`, read as its extracted text.
+//! Nothing is changed through the server. Being a command-line run, it isn't
+//! covered by the app lock (see `lock`).
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::backend_api;
+
+/// Newest first; the client's version is used when it's one of these.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+const RESOURCE_PREFIX: &str = "localbook://notebook/";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_notebooks",
+            "description": "List the notebooks in the LocalBook library, with their ids and number of documents.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "list_documents",
+            "description": "List the documents (sources) in a notebook.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "notebook": { "type": "string", "description": "Notebook id or exact title" },
+                },
+                "required": ["notebook"],
+            },
+        },
+        {
+            "name": "search",
+            "description": "Find passages in the library relevant to a query, by meaning rather than exact words. Searches every notebook unless one is given.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "notebook": { "type": "string", "description": "Notebook id or exact title" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": 50 },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_document",
+            "description": "Read the full extracted text of a document.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "notebook": { "type": "string", "description": "Notebook id or exact title" },
+                    "source_id": { "type": "string" },
+                },
+                "required": ["notebook", "source_id"],
+            },
+        },
+    ])
+}
+
+fn arg<'a>(args: &'a Value, name: &str) -> Result<&'a str, String> {
+    args[name]
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| format!("\"{}\" is required", name))
+}
+
+/// Backend ids go into URL paths.
+fn check_id(id: &str) -> Result<&str, String> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(id)
+    } else {
+        Err(format!("Invalid id {:?}", id))
+    }
+}
+
+async fn documents(notebook_id: &str) -> Result<Vec<Value>, String> {
+    let sources =
+        backend_api::get_json(&format!("/sources/{}", check_id(notebook_id)?), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(sources
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|s| {
+            json!({
+                "source_id": s["id"],
+                "filename": s["filename"],
+                "format": s["format"],
+                "created_at": s["created_at"],
+            })
+        })
+        .collect())
+}
+
+async fn document_text(notebook_id: &str, source_id: &str) -> Result<String, String> {
+    let path = format!(
+        "/source-viewer/content/{}/{}",
+        check_id(notebook_id)?,
+        check_id(source_id)?
+    );
+    let content = backend_api::get_json(&path, API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(content["content"].as_str().unwrap_or_default().to_string())
+}
+
+async fn call_tool(app: &AppHandle, name: &str, args: &Value) -> Result<String, String> {
+    crate::cli::wait_for_backend(app).await?;
+    let result = match name {
+        "list_notebooks" => {
+            let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT)
+                .await
+                .map_err(|e| e.to_string())?;
+            let list: Vec<Value> = notebooks["notebooks"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|n| {
+                    json!({
+                        "id": n["id"],
+                        "title": n["title"],
+                        "documents": n["source_count"],
+                    })
+                })
+                .collect();
+            json!(list)
+        }
+        "list_documents" => {
+            let notebook_id = crate::rpc::find_notebook(arg(args, "notebook")?).await?;
+            json!(documents(&notebook_id).await?)
+        }
+        "search" => {
+            let limit = args["limit"]
+                .as_u64()
+                .unwrap_or(crate::rpc::DEFAULT_SEARCH_LIMIT);
+            let found =
+                crate::rpc::search_passages(arg(args, "query")?, args["notebook"].as_str(), limit)
+                    .await?;
+            let hits: Vec<Value> = found
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|h| {
+                    json!({
+                        "notebook": h["notebook_title"],
+                        "notebook_id": h["notebook_id"],
+                        "source_id": h["source_id"],
+                        "filename": h["filename"],
+                        "text": h["text"],
+                    })
+                })
+                .collect();
+            json!(hits)
+        }
+        "get_document" => {
+            let notebook_id = crate::rpc::find_notebook(arg(args, "notebook")?).await?;
+            return document_text(&notebook_id, arg(args, "source_id")?).await;
+        }
+        _ => return Err(format!("No tool {}", name)),
+    };
+    serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+}
+
+async fn list_resources(app: &AppHandle) -> Result<Value, String> {
+    crate::cli::wait_for_backend(app).await?;
+    let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut resources = Vec::new();
+    for notebook in notebooks["notebooks"].as_array().into_iter().flatten() {
+        let (Some(id), title) = (notebook["id"].as_str(), notebook["title"].as_str()) else {
+            continue;
+        };
+        for doc in documents(id).await? {
+            let Some(source_id) = doc["source_id"].as_str() else {
+                continue;
+            };
+            let filename = doc["filename"].as_str().unwrap_or(source_id);
+            resources.push(json!({
+                "uri": format!("{}{}/source/{}", RESOURCE_PREFIX, id, source_id),
+                "name": filename,
+                "description": format!("In the notebook \"{}\"", title.unwrap_or_default()),
+                "mimeType": "text/plain",
+            }));
+        }
+    }
+    Ok(json!({ "resources": resources }))
+}
+
+async fn read_resource(app: &AppHandle, uri: &str) -> Result<Value, String> {
+    let (notebook_id, source_id) = uri
+        .strip_prefix(RESOURCE_PREFIX)
+        .and_then(|rest| rest.split_once("/source/"))
+        .ok_or_else(|| format!("Unknown resource {}", uri))?;
+    crate::cli::wait_for_backend(app).await?;
+    let text = document_text(notebook_id, source_id).await?;
+    Ok(json!({ "contents": [{ "uri": uri, "mimeType": "text/plain", "text": text }] }))
+}
+
+/// The result of one request, or a JSON-RPC error code and message.
+async fn respond(app: &AppHandle, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => {
+            let requested = params["protocolVersion"].as_str().unwrap_or_default();
+            let version = PROTOCOL_VERSIONS
+                .iter()
+                .find(|v| **v == requested)
+                .unwrap_or(&PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {}, "resources": {} },
+                "serverInfo": {
+                    "name": "localbook",
+                    "version": app.package_info().version.to_string(),
+                },
+                "instructions": "Search and read the documents in the user's LocalBook notebooks.",
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => {
+            let name = params["name"]
+                .as_str()
+                .ok_or((INVALID_PARAMS, "\"name\" is required".to_string()))?;
+            let args = params
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            // A failed tool is a result the model can read, not a protocol error.
+            Ok(match call_tool(app, name, &args).await {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Err(e) => {
+                    tracing::warn!("[MCP] {} failed: {}", name, e);
+                    json!({ "content": [{ "type": "text", "text": e }], "isError": true })
+                }
+            })
+        }
+        "resources/list" => {
+            list_resources(app).await.map_err(|e| (INVALID_REQUEST, e))
+        }
+        "resources/read" => {
+            let uri = params["uri"]
+                .as_str()
+                .ok_or((INVALID_PARAMS, "\"uri\" is required".to_string()))?;
+            read_resource(app, uri)
+                .await
+                .map_err(|e| (INVALID_PARAMS, e))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("No method {}", method))),
+    }
+}
+
+/// Answer one message; None for notifications and blank lines.
+async fn handle_line(app: &AppHandle, line: &str) -> Option<Value> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": e.to_string() },
+            }))
+        }
+    };
+    // Notifications (initialized, cancelled…) and responses need no answer.
+    let id = message.get("id").cloned()?;
+    let method = message["method"].as_str()?;
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+    Some(match respond(app, method, &params).await {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    })
+}
+
+/// `localbook mcp`: serve stdin and stdout until the client closes stdin.
+pub(crate) async fn serve_stdio(app: &AppHandle) -> Result<bool, String> {
+    tracing::info!("[MCP] Serving on stdio");
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read from the client: {}", e))?
+    {
+        let Some(response) = handle_line(app, &line).await else {
+            continue;
+        };
+        let mut out = response.to_string();
+        out.push('\n');
+        stdout
+            .write_all(out.as_bytes())
+            .await
+            .and(stdout.flush().await)
+            .map_err(|e| format!("Failed to write to the client: {}", e))?;
+    }
+    Ok(true)
+}
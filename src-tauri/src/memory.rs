@@ -0,0 +1,136 @@
+//! Memory-pressure model unloading.
+//!
+//! A loaded 7B model pins several GB. When free RAM drops below the threshold
+//! (another app needs it) the monitor has Ollama evict its models and drops
+//! the built-in engine's model; both reload on the next request, which is
+//! slower — so each unload is announced as `models://unloaded` for the UI to
+//! explain the delay. One unload per low-memory episode: the monitor re-arms
+//! once free memory has recovered to 1.5× the threshold.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const MIN_THRESHOLD_BYTES: u64 = 1536 * 1024 * 1024;
+
+static UNDER_PRESSURE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct MemoryState {
+    total_bytes: u64,
+    available_bytes: u64,
+    threshold_bytes: u64,
+    under_pressure: bool,
+    unload_on_pressure: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct UnloadEvent {
+    models: Vec<String>,
+    available_bytes: u64,
+    threshold_bytes: u64,
+}
+
+fn sample(app: &AppHandle) -> MemoryState {
+    let cfg = crate::settings::get(app).memory;
+    let sys = System::new_with_specifics(
+        RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
+    );
+    let total_bytes = sys.total_memory();
+    let threshold_bytes = cfg
+        .min_available_mb
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or_else(|| (total_bytes / 10).max(MIN_THRESHOLD_BYTES));
+    MemoryState {
+        total_bytes,
+        available_bytes: sys.available_memory(),
+        threshold_bytes,
+        under_pressure: UNDER_PRESSURE.load(Ordering::Relaxed),
+        unload_on_pressure: cfg.unload_on_pressure,
+    }
+}
+
+async fn unload_models(app: &AppHandle, state: &MemoryState) {
+    let mut models = match crate::ollama::unload_all().await {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::warn!("[Memory] {}", e);
+            Vec::new()
+        }
+    };
+    models.extend(crate::llama::unload());
+    tracing::info!(
+        "[Memory] {} MB free (< {} MB) — unloaded {:?}",
+        state.available_bytes / 1024 / 1024,
+        state.threshold_bytes / 1024 / 1024,
+        models
+    );
+    let _ = app.emit(
+        "models://unloaded",
+        UnloadEvent {
+            models,
+            available_bytes: state.available_bytes,
+            threshold_bytes: state.threshold_bytes,
+        },
+    );
+}
+
+async fn check(app: &AppHandle) {
+    let handle = app.clone();
+    let Ok(state) = tauri::async_runtime::spawn_blocking(move || sample(&handle)).await else {
+        return;
+    };
+    if state.available_bytes < state.threshold_bytes {
+        if !UNDER_PRESSURE.swap(true, Ordering::Relaxed) && state.unload_on_pressure {
+            unload_models(app, &state).await;
+        }
+    } else if state.available_bytes > state.threshold_bytes * 3 / 2
+        && UNDER_PRESSURE.swap(false, Ordering::Relaxed)
+    {
+        tracing::info!(
+            "[Memory] Recovered: {} MB free",
+            state.available_bytes / 1024 / 1024
+        );
+    }
+}
+
+/// Start the sampling loop. Called once from setup.
+pub(crate) fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            check(&app).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_memory_state(app: AppHandle) -> Result<MemoryState, String> {
+    tauri::async_runtime::spawn_blocking(move || sample(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// `min_available_mb: None` goes back to the automatic threshold.
+#[tauri::command]
+pub(crate) async fn set_memory_settings(
+    app: AppHandle,
+    unload_on_pressure: bool,
+    min_available_mb: Option<u64>,
+) -> Result<MemoryState, String> {
+    if min_available_mb.is_some_and(|mb| mb < 256) {
+        return Err("Threshold must be at least 256 MB".to_string());
+    }
+    crate::settings::update(&app, |s| {
+        s.memory.unload_on_pressure = unload_on_pressure;
+        s.memory.min_available_mb = min_available_mb;
+    })?;
+    tauri::async_runtime::spawn_blocking(move || sample(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,260 @@
+//! Performance numbers for this run: how long startup took, how long backend
+//! requests and jobs take.
+//!
+//! Startup is timed from launch to the backend being spawned, to its first
+//! passed health check and to the main window's first page load. Requests
+//! are the ones `traces` records, grouped by method and route (ids in the
+//! path become `:id`); jobs by kind and outcome. Nothing is kept between
+//! runs. `get_metrics` has the summary for the UI, and `export_metrics` the
+//! same in the Prometheus text format, which `--serve` also answers at
+//! `/metrics` for self-hosters to scrape.
+
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+/// Upper bounds of the histogram buckets, in seconds.
+const BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 120.0];
+
+static LAUNCHED: OnceLock<Instant> = OnceLock::new();
+static STORE: Mutex<Store> = Mutex::new(Store {
+    startup: BTreeMap::new(),
+    requests: BTreeMap::new(),
+    jobs: BTreeMap::new(),
+});
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Phase {
+    BackendSpawned,
+    BackendHealthy,
+    WebviewReady,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::BackendSpawned => "backend_spawned",
+            Phase::BackendHealthy => "backend_healthy",
+            Phase::WebviewReady => "webview_ready",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    count: u64,
+    sum: Duration,
+    max: Duration,
+    /// Not cumulative; `prometheus` adds them up.
+    buckets: [u64; BUCKETS.len()],
+}
+
+impl Histogram {
+    fn observe(&mut self, took: Duration) {
+        self.count += 1;
+        self.sum += took;
+        self.max = self.max.max(took);
+        if let Some(i) = BUCKETS.iter().position(|b| took.as_secs_f64() <= *b) {
+            self.buckets[i] += 1;
+        }
+    }
+
+    fn summary(&self) -> Value {
+        json!({
+            "count": self.count,
+            "avg_ms": (self.sum.as_millis() as u64).checked_div(self.count).unwrap_or(0),
+            "max_ms": self.max.as_millis() as u64,
+        })
+    }
+}
+
+struct Store {
+    startup: BTreeMap<Phase, Duration>,
+    /// By (origin, method, route).
+    requests: BTreeMap<(&'static str, String, String), Histogram>,
+    /// By (kind, outcome).
+    jobs: BTreeMap<(String, &'static str), Histogram>,
+}
+
+fn store() -> std::sync::MutexGuard<'static, Store> {
+    STORE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start the clock. First thing in `run()`.
+pub(crate) fn init() {
+    LAUNCHED.get_or_init(Instant::now);
+}
+
+/// Note that startup reached `phase`; only the first time counts.
+pub(crate) fn startup(phase: Phase) {
+    let Some(launched) = LAUNCHED.get() else {
+        return;
+    };
+    if let Entry::Vacant(entry) = store().startup.entry(phase) {
+        let took = launched.elapsed();
+        tracing::info!("[Metrics] {} after {}ms", phase.name(), took.as_millis());
+        entry.insert(took);
+    }
+}
+
+/// The main window's first page load.
+pub(crate) fn on_page_load(
+    webview: &tauri::Webview,
+    payload: &tauri::webview::PageLoadPayload<'_>,
+) {
+    if webview.label() == "main" && payload.event() == tauri::webview::PageLoadEvent::Finished {
+        startup(Phase::WebviewReady);
+    }
+}
+
+/// `/notebooks/3f2a…/sources` → `/notebooks/:id/sources`, so routes stay few.
+pub(crate) fn route(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            let digits = segment.chars().filter(char::is_ascii_digit).count();
+            if digits > 0 && (digits == segment.len() || segment.len() >= 8) {
+                ":id"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(crate) fn request(origin: &'static str, method: &str, path: &str, took: Duration) {
+    store()
+        .requests
+        .entry((origin, method.to_string(), route(path)))
+        .or_default()
+        .observe(took);
+}
+
+pub(crate) fn job(kind: &str, outcome: &'static str, took: Duration) {
+    store()
+        .jobs
+        .entry((kind.to_string(), outcome))
+        .or_default()
+        .observe(took);
+}
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn histogram(out: &mut String, name: &str, labels: &str, h: &Histogram) {
+    let mut cumulative = 0;
+    for (bound, count) in BUCKETS.iter().zip(h.buckets) {
+        cumulative += count;
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"{}\"}} {}",
+            name, labels, bound, cumulative
+        );
+    }
+    let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, h.count);
+    let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, h.sum.as_secs_f64());
+    let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, h.count);
+}
+
+/// Everything in the Prometheus text exposition format.
+pub(crate) fn prometheus() -> String {
+    let store = store();
+    let mut out = String::new();
+    out.push_str("# HELP localbook_uptime_seconds Time since the app started.\n");
+    out.push_str("# TYPE localbook_uptime_seconds gauge\n");
+    let uptime = LAUNCHED.get().map_or(0.0, |l| l.elapsed().as_secs_f64());
+    let _ = writeln!(out, "localbook_uptime_seconds {}", uptime);
+
+    out.push_str("# HELP localbook_startup_seconds Time from launch to each startup phase.\n");
+    out.push_str("# TYPE localbook_startup_seconds gauge\n");
+    for (phase, took) in &store.startup {
+        let _ = writeln!(
+            out,
+            "localbook_startup_seconds{{phase=\"{}\"}} {}",
+            phase.name(),
+            took.as_secs_f64()
+        );
+    }
+
+    let name = "localbook_backend_request_duration_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {} Backend requests, until the response starts.",
+        name
+    );
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for ((origin, method, route), h) in &store.requests {
+        let labels = format!(
+            "origin=\"{}\",method=\"{}\",route=\"{}\"",
+            origin,
+            label(method),
+            label(route)
+        );
+        histogram(&mut out, name, &labels, h);
+    }
+
+    let name = "localbook_job_duration_seconds";
+    let _ = writeln!(out, "# HELP {} Background jobs, by kind and outcome.", name);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for ((kind, outcome), h) in &store.jobs {
+        let labels = format!("kind=\"{}\",outcome=\"{}\"", label(kind), outcome);
+        histogram(&mut out, name, &labels, h);
+    }
+    out
+}
+
+#[tauri::command]
+pub(crate) async fn get_metrics() -> Result<Value, String> {
+    let store = store();
+    let startup: serde_json::Map<String, Value> = store
+        .startup
+        .iter()
+        .map(|(phase, took)| {
+            (
+                format!("{}_ms", phase.name()),
+                json!(took.as_millis() as u64),
+            )
+        })
+        .collect();
+    let requests: Vec<Value> = store
+        .requests
+        .iter()
+        .map(|((origin, method, route), h)| {
+            let mut entry = h.summary();
+            entry["origin"] = json!(origin);
+            entry["method"] = json!(method);
+            entry["route"] = json!(route);
+            entry
+        })
+        .collect();
+    let jobs: Vec<Value> = store
+        .jobs
+        .iter()
+        .map(|((kind, outcome), h)| {
+            let mut entry = h.summary();
+            entry["kind"] = json!(kind);
+            entry["outcome"] = json!(outcome);
+            entry
+        })
+        .collect();
+    Ok(json!({
+        "uptime_secs": LAUNCHED.get().map_or(0, |l| l.elapsed().as_secs()),
+        "startup": startup,
+        "requests": requests,
+        "jobs": jobs,
+    }))
+}
+
+/// The metrics as Prometheus text, to save or paste.
+#[tauri::command]
+pub(crate) async fn export_metrics() -> Result<String, String> {
+    Ok(prometheus())
+}
@@ -0,0 +1,78 @@
+//! Per-notebook model, temperature and provider preferences.
+//!
+//! Stored in the shell settings: global defaults plus sparse per-notebook
+//! overrides, merged field by field. Chat requests go from the webview to the
+//! backend directly, so the UI fetches the merged preferences for the active
+//! notebook and sends them along with each question (`llm_provider` is the
+//! backend's existing chat field). `notebook_settings` resolves them with
+//! the rest of a notebook's settings.
+
+use tauri::AppHandle;
+
+use crate::settings::{ModelPrefs, Settings};
+
+fn merge(over: ModelPrefs, base: ModelPrefs) -> ModelPrefs {
+    ModelPrefs {
+        model: over.model.or(base.model),
+        temperature: over.temperature.or(base.temperature),
+        llm_provider: over.llm_provider.or(base.llm_provider),
+    }
+}
+
+fn validate(prefs: &ModelPrefs) -> Result<(), String> {
+    if let Some(t) = prefs.temperature {
+        if !(0.0..=2.0).contains(&t) {
+            return Err(format!("Temperature must be between 0 and 2, got {}", t));
+        }
+    }
+    Ok(())
+}
+
+/// A notebook's overrides merged over the global defaults.
+pub(crate) fn resolve(settings: &Settings, notebook_id: Option<&str>) -> ModelPrefs {
+    let overrides = notebook_id
+        .and_then(|id| settings.notebook_models.get(id))
+        .cloned()
+        .unwrap_or_default();
+    merge(overrides, settings.model_defaults.clone())
+}
+
+pub(crate) fn effective(app: &AppHandle, notebook_id: Option<&str>) -> ModelPrefs {
+    resolve(&crate::settings::get(app), notebook_id)
+}
+
+/// The effective preferences for a notebook, or the global defaults when
+/// `notebook_id` is None.
+#[tauri::command]
+pub(crate) async fn get_notebook_model_prefs(
+    app: AppHandle,
+    notebook_id: Option<String>,
+) -> Result<ModelPrefs, String> {
+    Ok(effective(&app, notebook_id.as_deref()))
+}
+
+/// Replace a notebook's overrides (or the global defaults when `notebook_id`
+/// is None). `prefs: None` clears the notebook's overrides.
+#[tauri::command]
+pub(crate) async fn set_notebook_model_prefs(
+    app: AppHandle,
+    notebook_id: Option<String>,
+    prefs: Option<ModelPrefs>,
+) -> Result<(), String> {
+    if let Some(p) = &prefs {
+        validate(p)?;
+    }
+    crate::settings::update(&app, |s| match notebook_id {
+        Some(id) => match prefs {
+            Some(p) => {
+                s.notebook_models.insert(id, p);
+            }
+            None => {
+                s.notebook_models.remove(&id);
+            }
+        },
+        None => s.model_defaults = prefs.unwrap_or_default(),
+    })?;
+    crate::notebook_settings::publish(&app);
+    Ok(())
+}
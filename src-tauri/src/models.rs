@@ -0,0 +1,780 @@
+//! Model file manager — GGUF/ONNX (and whisper.cpp ggml) weights downloaded and
+//! tracked by the shell.
+//!
+//! Files land in `<app data>/models/` next to a `registry.json` describing each
+//! one (source, size, SHA-256). Downloads stream to `<file>.part` and resume with
+//! an HTTP Range request after an interruption — including waiting out a network
+//! drop — so a 5 GB file doesn't restart from zero. Free space is checked before
+//! writing, and the hash is verified before the `.part` is renamed into place,
+//! so anything in the registry is complete. `verify_models` re-hashes files
+//! against the recorded checksums to catch corruption after disk trouble.
+//!
+//! Each entry also records where the file came from and, for Hub downloads,
+//! the license the repo declares.
+//!
+//! The folder can be moved (e.g. to an external drive) with `set_models_dir`,
+//! which migrates everything in it — registry, weights, voices, reranker — and
+//! is passed to the backend as LOCALBOOK_MODELS_DIR, where it keeps its own
+//! downloads (the FlashRank reranker) from its next start.
+//!
+//! Sources are plain URLs or `hf://<owner>/<repo>[@revision]/<path>` shorthand
+//! for the Hugging Face Hub (authenticated with the stored token, see hf.rs).
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+const REGISTRY_FILE: &str = "registry.json";
+/// Subfolders managed outside the registry (voices.rs, rerank.rs, and the
+/// backend's FlashRank cache).
+const OWNED_DIRS: &[&str] = &["voices", "reranker", "flashrank"];
+/// Refuse a download that would leave less than this free on the volume.
+const DISK_HEADROOM_BYTES: u64 = 1024 * 1024 * 1024;
+const MAX_ATTEMPTS: u32 = 5;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Serialises registry read-modify-write cycles.
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+/// Model ids with a download in flight.
+static ACTIVE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// Set while `set_models_dir` is moving files; new downloads are refused.
+/// Only changed, and checked by new downloads, with ACTIVE locked.
+static MIGRATING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ModelEntry {
+    pub id: String,
+    pub file_name: String,
+    /// "gguf" | "onnx" | "ggml" (whisper.cpp)
+    pub format: String,
+    pub source_url: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    /// Unix seconds.
+    pub downloaded_at: u64,
+    /// SPDX-style id ("apache-2.0", "llama3.1", …) when the source declares one.
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Unix seconds of the last successful `verify_models` pass.
+    #[serde(default)]
+    pub verified_at: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct LocalModel {
+    #[serde(flatten)]
+    entry: ModelEntry,
+    path: String,
+    /// False if the file has gone missing since it was registered.
+    present: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ModelVerification {
+    id: String,
+    /// "ok" | "missing" | "size_mismatch" | "checksum_mismatch" | "downloading" | "error"
+    status: String,
+    expected_sha256: String,
+    actual_sha256: Option<String>,
+    error: Option<String>,
+}
+
+/// Removes the id from ACTIVE however the download ends.
+struct ActiveGuard(String);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.retain(|id| id != &self.0);
+        }
+    }
+}
+
+fn default_models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::shared_data_dir(app)?.join("models"))
+}
+
+/// The configured models folder, or `<app data>/models`. A custom folder that
+/// has disappeared (drive unplugged) is an error rather than being recreated
+/// on the boot volume.
+pub(crate) fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(custom) = crate::settings::get(app).models_dir {
+        let dir = PathBuf::from(custom);
+        if !dir.is_dir() {
+            return Err(format!(
+                "Model folder {} is not available — is the drive connected?",
+                dir.display()
+            ));
+        }
+        return Ok(dir);
+    }
+    let dir = default_models_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+pub(crate) fn load_registry(app: &AppHandle) -> Vec<ModelEntry> {
+    models_dir(app)
+        .ok()
+        .and_then(|d| std::fs::read_to_string(d.join(REGISTRY_FILE)).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_registry(app: &AppHandle, entries: &[ModelEntry]) -> Result<(), String> {
+    let path = models_dir(app)?.join(REGISTRY_FILE);
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+/// Read-modify-write the registry under the lock.
+fn update_registry<F>(app: &AppHandle, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Vec<ModelEntry>),
+{
+    let _lock = REGISTRY_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load_registry(app);
+    f(&mut entries);
+    save_registry(app, &entries)
+}
+
+fn resolve_url(source: &str) -> Result<String, String> {
+    if let Some(hf) = crate::hf::resolve(source) {
+        hf
+    } else if source.starts_with("https://") || source.starts_with("http://") {
+        Ok(source.to_string())
+    } else {
+        Err(format!("Unsupported model source: {}", source))
+    }
+}
+
+fn model_format(file_name: &str) -> Result<&'static str, String> {
+    let lower = file_name.to_ascii_lowercase();
+    if lower.ends_with(".gguf") {
+        Ok("gguf")
+    } else if lower.ends_with(".onnx") {
+        Ok("onnx")
+    } else if lower.starts_with("ggml-") && lower.ends_with(".bin") {
+        // whisper.cpp weights (see whisper.rs)
+        Ok("ggml")
+    } else {
+        Err(format!(
+            "Only .gguf, .onnx and whisper.cpp ggml-*.bin model files are supported ({})",
+            file_name
+        ))
+    }
+}
+
+/// File names become paths — no separators or parent references.
+fn validate_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid model file name: {:?}", name));
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn emit_progress(app: &AppHandle, id: &str, status: &str, downloaded: u64, total: Option<u64>) {
+    let percent = total
+        .filter(|t| *t > 0)
+        .map(|t| (downloaded as f64 / t as f64 * 100.0).round());
+    let _ = app.emit(
+        "models://download-progress",
+        serde_json::json!({
+            "id": id,
+            "status": status,
+            "downloaded": downloaded,
+            "total": total,
+            "percent": percent,
+        }),
+    );
+}
+
+/// Stream `url` into `part`, resuming from whatever is already there.
+/// Returns once the server has sent the whole file.
+pub(crate) async fn fetch_to_part(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    id: &str,
+    url: &str,
+    token: Option<&str>,
+    part: &Path,
+) -> Result<(), String> {
+    // Voice and reranker downloads come straight here; count them as active
+    // too, so the folder isn't moved from under them.
+    let _guard = {
+        let mut active = ACTIVE.lock().map_err(|e| e.to_string())?;
+        if MIGRATING.load(Ordering::SeqCst) {
+            return Err("The models folder is being moved; try again when it finishes".to_string());
+        }
+        (!active.iter().any(|a| a == id)).then(|| {
+            active.push(id.to_string());
+            ActiveGuard(id.to_string())
+        })
+    };
+    crate::privacy::guard(url, "Download")?;
+    let dir = part.parent().ok_or("Model path has no parent directory")?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        if !crate::network::is_online() {
+            emit_progress(app, id, "waiting_for_network", 0, None);
+            tracing::info!("[Models] {} waiting for network…", id);
+            crate::network::wait_until_online().await;
+        }
+
+        let existing = tokio::fs::metadata(part)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let mut req = client.get(url);
+        if let Some(t) = token {
+            req = req.bearer_auth(t);
+        }
+        if existing > 0 {
+            req = req.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+        let resp = match req.send().await {
+            Ok(r) => r,
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                tracing::warn!(
+                    "[Models] {} request failed (attempt {}): {}",
+                    id, attempt, e
+                );
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            Err(e) => return Err(format!("Download failed: {}", e)),
+        };
+
+        let status = resp.status();
+        let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+            // Already have every byte from a previous run.
+            return Ok(());
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(format!(
+                "Download not authorized (HTTP {}). Gated models need a Hugging Face \
+                 token and accepted license terms on the model page.",
+                status.as_u16()
+            ));
+        }
+        if !status.is_success() {
+            return Err(format!("Download failed: HTTP {}", status));
+        }
+
+        let start = if resumed { existing } else { 0 };
+        let total = resp.content_length().map(|len| start + len);
+        if let Some(remaining) = resp.content_length() {
+            let available = fs4::available_space(dir).map_err(|e| e.to_string())?;
+            if available < remaining + DISK_HEADROOM_BYTES {
+                return Err(format!(
+                    "Not enough disk space: need {:.1} GB, {:.1} GB available",
+                    (remaining + DISK_HEADROOM_BYTES) as f64 / 1e9,
+                    available as f64 / 1e9
+                ));
+            }
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(part)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+
+        let mut resp = resp;
+        let mut downloaded = start;
+        let mut last_emit = Instant::now();
+        let outcome = loop {
+            match resp.chunk().await {
+                Ok(Some(bytes)) => {
+                    if let Err(e) = file.write_all(&bytes).await {
+                        break Err(format!("Failed to write model file: {}", e));
+                    }
+                    downloaded += bytes.len() as u64;
+                    if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                        emit_progress(app, id, "downloading", downloaded, total);
+                        last_emit = Instant::now();
+                    }
+                }
+                Ok(None) => break Ok(true),
+                Err(e) => {
+                    tracing::warn!(
+                        "[Models] {} stream interrupted at {} bytes: {}",
+                        id, downloaded, e
+                    );
+                    break Ok(false);
+                }
+            }
+        };
+        let _ = file.flush().await;
+
+        match outcome {
+            Ok(true) => return Ok(()),
+            Ok(false) if attempt < MAX_ATTEMPTS => {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue; // resume from the bytes on disk
+            }
+            Ok(false) => return Err("Download interrupted too many times".to_string()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Download a model file into the models dir and register it. `sha256`, when
+/// given, must match or the file is discarded.
+#[tauri::command]
+pub(crate) async fn download_model(
+    app: AppHandle,
+    url: String,
+    file_name: Option<String>,
+    sha256: Option<String>,
+) -> Result<ModelEntry, String> {
+    let resolved = resolve_url(&url)?;
+    crate::privacy::guard(&resolved, "Model download")?;
+    let file_name = match file_name {
+        Some(f) => f,
+        None => resolved
+            .split(['?', '#'])
+            .next()
+            .and_then(|u| u.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    validate_file_name(&file_name)?;
+    let format = model_format(&file_name)?;
+    let id = file_name[..file_name.rfind('.').unwrap_or(file_name.len())].to_string();
+
+    {
+        let mut active = ACTIVE.lock().map_err(|e| e.to_string())?;
+        if MIGRATING.load(Ordering::SeqCst) {
+            return Err("The models folder is being moved; try again when it finishes".to_string());
+        }
+        if active.contains(&id) {
+            return Err(format!("{} is already downloading", id));
+        }
+        active.push(id.clone());
+    }
+    let _guard = ActiveGuard(id.clone());
+
+    let dir = models_dir(&app)?;
+    let final_path = dir.join(&file_name);
+    let part = dir.join(format!("{}.part", file_name));
+
+    // No overall timeout — multi-GB downloads legitimately take a long time.
+    let client = crate::proxy::client()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+
+    let url_for_token = resolved.clone();
+    let token =
+        tauri::async_runtime::spawn_blocking(move || crate::hf::auth_token_for(&url_for_token))
+            .await
+            .map_err(|e| e.to_string())?;
+
+    tracing::info!("[Models] Downloading {} from {}", id, resolved);
+    fetch_to_part(&app, &client, &id, &resolved, token.as_deref(), &part).await?;
+
+    emit_progress(&app, &id, "verifying", 0, None);
+    let part_for_hash = part.clone();
+    let digest = tauri::async_runtime::spawn_blocking(move || sha256_file(&part_for_hash))
+        .await
+        .map_err(|e| e.to_string())??;
+    if let Some(expected) = sha256.as_deref() {
+        if !digest.eq_ignore_ascii_case(expected.trim()) {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                file_name, expected, digest
+            ));
+        }
+    }
+
+    tokio::fs::rename(&part, &final_path)
+        .await
+        .map_err(|e| format!("Failed to move model into place: {}", e))?;
+    let size_bytes = tokio::fs::metadata(&final_path)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let license = match crate::hf::repo_id(&resolved) {
+        Some(repo) => crate::hf::fetch_license(&client, &repo, token.as_deref()).await,
+        None => None,
+    };
+
+    let entry = ModelEntry {
+        id: id.clone(),
+        file_name,
+        format: format.to_string(),
+        source_url: url,
+        sha256: digest,
+        size_bytes,
+        downloaded_at: now_secs(),
+        license,
+        verified_at: None,
+    };
+    let registered = entry.clone();
+    update_registry(&app, move |entries| {
+        entries.retain(|e| e.id != registered.id);
+        entries.push(registered);
+    })?;
+
+    emit_progress(&app, &id, "complete", size_bytes, Some(size_bytes));
+    tracing::info!("[Models] {} ready ({} bytes)", id, size_bytes);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub(crate) async fn list_local_models(app: AppHandle) -> Result<Vec<LocalModel>, String> {
+    let dir = models_dir(&app)?;
+    Ok(load_registry(&app)
+        .into_iter()
+        .map(|entry| {
+            let path = dir.join(&entry.file_name);
+            LocalModel {
+                present: path.exists(),
+                path: path.display().to_string(),
+                entry,
+            }
+        })
+        .collect())
+}
+
+/// Delete a model's file (and any leftover partial download) and unregister it.
+#[tauri::command]
+pub(crate) async fn remove_model(app: AppHandle, id: String) -> Result<(), String> {
+    let entry = load_registry(&app)
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("Unknown model: {}", id))?;
+    let dir = models_dir(&app)?;
+    for path in [
+        dir.join(&entry.file_name),
+        dir.join(format!("{}.part", entry.file_name)),
+    ] {
+        match tokio::fs::remove_file(&path).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+    update_registry(&app, |entries| entries.retain(|e| e.id != id))?;
+    tracing::info!("[Models] Removed {}", entry.id);
+    Ok(())
+}
+
+/// Re-hash every registered model and compare against the registry. Slow for
+/// large files (it reads every byte); emits `models://verify-progress` per
+/// model. Files that check out get `verified_at` stamped.
+#[tauri::command]
+pub(crate) async fn verify_models(app: AppHandle) -> Result<Vec<ModelVerification>, String> {
+    let dir = models_dir(&app)?;
+    let entries = load_registry(&app);
+    let total = entries.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let _ = app.emit(
+            "models://verify-progress",
+            serde_json::json!({ "id": entry.id, "index": index, "total": total }),
+        );
+        let downloading = ACTIVE
+            .lock()
+            .map(|a| a.contains(&entry.id))
+            .unwrap_or(false);
+        let path = dir.join(&entry.file_name);
+        let (status, actual, error) = if downloading {
+            ("downloading", None, None)
+        } else {
+            match std::fs::metadata(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => ("missing", None, None),
+                Err(e) => ("error", None, Some(e.to_string())),
+                Ok(m) if m.len() != entry.size_bytes => ("size_mismatch", None, None),
+                Ok(_) => {
+                    let p = path.clone();
+                    match tauri::async_runtime::spawn_blocking(move || sha256_file(&p))
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r)
+                    {
+                        Ok(digest) if digest.eq_ignore_ascii_case(&entry.sha256) => {
+                            ("ok", Some(digest), None)
+                        }
+                        Ok(digest) => ("checksum_mismatch", Some(digest), None),
+                        Err(e) => ("error", None, Some(e)),
+                    }
+                }
+            }
+        };
+        if status != "ok" && status != "downloading" {
+            tracing::warn!("[Models] Verify {}: {}", entry.id, status);
+        }
+        results.push(ModelVerification {
+            id: entry.id,
+            status: status.to_string(),
+            expected_sha256: entry.sha256,
+            actual_sha256: actual,
+            error,
+        });
+    }
+
+    let verified: Vec<String> = results
+        .iter()
+        .filter(|r| r.status == "ok")
+        .map(|r| r.id.clone())
+        .collect();
+    if !verified.is_empty() {
+        let now = now_secs();
+        update_registry(&app, move |entries| {
+            for e in entries.iter_mut().filter(|e| verified.contains(&e.id)) {
+                e.verified_at = Some(now);
+            }
+        })?;
+    }
+    Ok(results)
+}
+
+/// Every file under `dir`, relative to `base`.
+fn collect_files(base: &Path, rel: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(base.join(rel))? {
+        let entry = entry?;
+        let rel = rel.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(base, &rel, out)?;
+        } else {
+            out.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// The files in `dir` that belong to us: the registry, registered models (and
+/// their partial downloads) and the voice and reranker folders. A custom
+/// models folder may be shared with other files, which stay where they are.
+fn owned_files(app: &AppHandle, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = vec![PathBuf::from(REGISTRY_FILE)];
+    for entry in load_registry(app) {
+        files.push(PathBuf::from(format!("{}.part", entry.file_name)));
+        files.push(PathBuf::from(entry.file_name));
+    }
+    files.retain(|f| dir.join(f).is_file());
+    for sub in OWNED_DIRS {
+        if dir.join(sub).is_dir() {
+            collect_files(dir, Path::new(sub), &mut files)?;
+        }
+    }
+    Ok(files)
+}
+
+/// Copy with progress callbacks every few MB.
+fn copy_with_progress(
+    from: &Path,
+    to: &Path,
+    mut on_bytes: impl FnMut(u64),
+) -> std::io::Result<()> {
+    use std::io::{Read, Write};
+    let mut src = std::fs::File::open(from)?;
+    let mut dst = std::fs::File::create(to)?;
+    let mut buf = vec![0u8; 4 * 1024 * 1024];
+    loop {
+        let n = src.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        dst.write_all(&buf[..n])?;
+        on_bytes(n as u64);
+    }
+    dst.sync_all()
+}
+
+/// Move everything from `from` to `to`. Same-volume files are renamed; across
+/// volumes they're copied and the originals only deleted once every copy has
+/// succeeded. On failure whatever was moved is put back.
+fn migrate_files(app: &AppHandle, from: &Path, to: &Path) -> Result<(), String> {
+    let files = owned_files(app, from)
+        .map_err(|e| format!("Failed to list {}: {}", from.display(), e))?;
+    let total: u64 = files
+        .iter()
+        .filter_map(|f| std::fs::metadata(from.join(f)).ok())
+        .map(|m| m.len())
+        .sum();
+    let available = fs4::available_space(to).map_err(|e| e.to_string())?;
+    if available < total {
+        return Err(format!(
+            "Not enough space in {}: need {:.1} GB, {:.1} GB available",
+            to.display(),
+            total as f64 / 1e9,
+            available as f64 / 1e9
+        ));
+    }
+
+    let emit = |file: &Path, done: u64| {
+        let _ = app.emit(
+            "models://migrate-progress",
+            serde_json::json!({
+                "file": file.display().to_string(),
+                "done": done,
+                "total": total,
+                "percent": if total > 0 { (done as f64 / total as f64 * 100.0).round() } else { 100.0 },
+            }),
+        );
+    };
+
+    let mut renamed: Vec<&PathBuf> = Vec::new();
+    let mut copied: Vec<&PathBuf> = Vec::new();
+    let mut done = 0u64;
+    let mut last_emit = Instant::now();
+    let result = (|| {
+        for rel in &files {
+            let (src, dst) = (from.join(rel), to.join(rel));
+            if dst.exists() {
+                return Err(format!(
+                    "{} already exists in the new folder",
+                    rel.display()
+                ));
+            }
+            if let Some(parent) = dst.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let size = std::fs::metadata(&src).map(|m| m.len()).unwrap_or(0);
+            if std::fs::rename(&src, &dst).is_ok() {
+                renamed.push(rel);
+                done += size;
+            } else {
+                copy_with_progress(&src, &dst, |n| {
+                    done += n;
+                    if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                        emit(rel, done);
+                        last_emit = Instant::now();
+                    }
+                })
+                .map_err(|e| format!("Failed to copy {}: {}", rel.display(), e))?;
+                copied.push(rel);
+            }
+            emit(rel, done);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for rel in &renamed {
+            let _ = std::fs::rename(to.join(rel), from.join(rel));
+        }
+        for rel in &copied {
+            let _ = std::fs::remove_file(to.join(rel));
+        }
+        return Err(e);
+    }
+    for rel in &copied {
+        let _ = std::fs::remove_file(from.join(rel));
+    }
+    Ok(())
+}
+
+/// Move model storage to `path` (None = back to the default location),
+/// migrating existing files with `models://migrate-progress` events. The
+/// backend picks up the new location on its next start.
+#[tauri::command]
+pub(crate) async fn set_models_dir(app: AppHandle, path: Option<String>) -> Result<String, String> {
+    let from = models_dir(&app)?;
+    let to = match &path {
+        Some(p) => {
+            let p = PathBuf::from(p);
+            if !p.is_absolute() {
+                return Err("Model folder must be an absolute path".to_string());
+            }
+            crate::scope::check(&app, &p)?;
+            p
+        }
+        None => default_models_dir(&app)?,
+    };
+    std::fs::create_dir_all(&to).map_err(|e| format!("Cannot use {}: {}", to.display(), e))?;
+    let (from_c, to_c) = (
+        from.canonicalize().map_err(|e| e.to_string())?,
+        to.canonicalize().map_err(|e| e.to_string())?,
+    );
+    if from_c == to_c {
+        return Ok(to.display().to_string());
+    }
+    if to_c.starts_with(&from_c) || from_c.starts_with(&to_c) {
+        return Err("The new folder can't be inside the current one (or vice versa)".to_string());
+    }
+    // Probe writability before moving gigabytes.
+    let probe = to.join(".localbook-write-test");
+    std::fs::write(&probe, b"ok")
+        .map_err(|e| format!("{} is not writable: {}", to.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+
+    {
+        // Checked and set together so a download can't start in between.
+        let active = ACTIVE.lock().map_err(|e| e.to_string())?;
+        if !active.is_empty() {
+            return Err("Wait for model downloads to finish before moving the folder".to_string());
+        }
+        if MIGRATING.swap(true, Ordering::SeqCst) {
+            return Err("The models folder is already being moved".to_string());
+        }
+    }
+
+    tracing::info!("[Models] Moving model storage {:?} -> {:?}", from, to);
+    let app_for_task = app.clone();
+    let to_for_task = to.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let _lock = REGISTRY_LOCK.lock().map_err(|e| e.to_string())?;
+        migrate_files(&app_for_task, &from, &to_for_task)?;
+        // Only removes folders that are now empty.
+        for sub in OWNED_DIRS {
+            let _ = std::fs::remove_dir(from.join(sub));
+        }
+        let _ = std::fs::remove_dir(&from);
+        crate::settings::update(&app_for_task, |s| {
+            s.models_dir = path.is_some().then(|| to_for_task.display().to_string());
+        })
+        .map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    MIGRATING.store(false, Ordering::SeqCst);
+    result?;
+
+    tracing::info!("[Models] Model storage now at {:?}", to);
+    Ok(to.display().to_string())
+}
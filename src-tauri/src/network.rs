@@ -0,0 +1,110 @@
+//! Internet connectivity awareness.
+//!
+//! There's no portable "network changed" notification, so a monitor loop opens a
+//! TCP connection to a couple of anycast resolvers (no DNS lookup, no HTTP, no
+//! data sent) — every 30s while online, every 10s while offline so recovery is
+//! noticed quickly. Transitions emit `network://changed` so internet-dependent
+//! features (URL import, model downloads, cloud providers) can grey out and
+//! come back without polling. Rust-side downloads park on `wait_until_online()`
+//! and resume by themselves once connectivity returns.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+const ONLINE_INTERVAL: Duration = Duration::from_secs(30);
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(10);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const PROBE_TARGETS: &[&str] = &["1.1.1.1:443", "8.8.8.8:53"];
+
+// Assume online until the first probe says otherwise — a false "offline" at
+// launch would needlessly disable features.
+static ONLINE: AtomicBool = AtomicBool::new(true);
+static BACK_ONLINE: Notify = Notify::const_new();
+
+#[derive(Clone, Serialize)]
+pub(crate) struct NetworkStatus {
+    online: bool,
+    /// Unix seconds of the probe that produced this status.
+    checked_at: u64,
+}
+
+pub(crate) fn is_online() -> bool {
+    ONLINE.load(Ordering::Relaxed)
+}
+
+/// Resolve once the monitor next sees connectivity (immediately if online).
+pub(crate) async fn wait_until_online() {
+    loop {
+        // Created before the check so a wake-up between the two isn't lost.
+        let notified = BACK_ONLINE.notified();
+        if is_online() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+async fn probe() -> bool {
+    // Behind a manual proxy, reaching the proxy is what counts.
+    if let Some(proxy) = crate::proxy::probe_target() {
+        return matches!(
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(proxy)).await,
+            Ok(Ok(_))
+        );
+    }
+    for target in PROBE_TARGETS {
+        let Ok(addr) = target.parse::<SocketAddr>() else {
+            continue;
+        };
+        if let Ok(Ok(_)) =
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(addr)).await
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Probe now, update the shared flag, and emit `network://changed` on a transition.
+async fn refresh(app: &AppHandle) -> NetworkStatus {
+    // Local-only mode: don't even probe, and keep internet features off.
+    let online = !crate::privacy::local_only() && probe().await;
+    let was_online = ONLINE.swap(online, Ordering::Relaxed);
+    let status = NetworkStatus {
+        online,
+        checked_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    if was_online != online {
+        tracing::info!("[Network] Connectivity changed → {}", if online { "online" } else { "offline" });
+        let _ = app.emit("network://changed", &status);
+        if online {
+            BACK_ONLINE.notify_waiters();
+        }
+    }
+    status
+}
+
+/// Start the probe loop. Called once from setup.
+pub(crate) fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let status = refresh(&app).await;
+            let interval = if status.online { ONLINE_INTERVAL } else { OFFLINE_INTERVAL };
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_network_status(app: AppHandle) -> Result<NetworkStatus, String> {
+    Ok(refresh(&app).await)
+}
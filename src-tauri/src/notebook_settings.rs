@@ -0,0 +1,170 @@
+//! Settings a notebook can set for itself: chunk size and citation style,
+//! alongside the model choices in `model_prefs`.
+//!
+//! Like those, they're stored as global defaults plus sparse per-notebook
+//! overrides and resolved here, field by field: notebook → global → the
+//! backend's own configuration. `get_effective_settings` gives the webview
+//! the result. The backend gets the same by reading `SIDECAR_FILE` in its
+//! data folder, which is rewritten whenever any of these change; it holds
+//! the resolved settings for every notebook with overrides and the defaults
+//! for the rest. A new chunk size applies to sources indexed after it's set.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::settings::{ModelPrefs, Settings};
+
+const SIDECAR_FILE: &str = "notebook_settings.json";
+const MIN_CHUNK_SIZE: usize = 200;
+const MAX_CHUNK_SIZE: usize = 8000;
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CitationStyle {
+    /// [N] after each fact.
+    #[default]
+    Inline,
+    /// [N] once at the end of each paragraph.
+    Paragraph,
+    /// No markers in the answer.
+    Off,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct NotebookSettings {
+    /// Characters per chunk when indexing; None is the backend's default.
+    pub chunk_size: Option<usize>,
+    pub citation_style: Option<CitationStyle>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct EffectiveSettings {
+    #[serde(flatten)]
+    models: ModelPrefs,
+    chunk_size: Option<usize>,
+    citation_style: CitationStyle,
+    /// The settings the notebook sets itself rather than inheriting.
+    overridden: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct SidecarFile {
+    defaults: EffectiveSettings,
+    notebooks: HashMap<String, EffectiveSettings>,
+}
+
+fn resolve(settings: &Settings, notebook_id: Option<&str>) -> EffectiveSettings {
+    let models = notebook_id.and_then(|id| settings.notebook_models.get(id));
+    let own = notebook_id.and_then(|id| settings.notebook_overrides.get(id));
+    let mut overridden = Vec::new();
+    if let Some(models) = models {
+        if models.model.is_some() {
+            overridden.push("model");
+        }
+        if models.temperature.is_some() {
+            overridden.push("temperature");
+        }
+        if models.llm_provider.is_some() {
+            overridden.push("llm_provider");
+        }
+    }
+    if let Some(own) = own {
+        if own.chunk_size.is_some() {
+            overridden.push("chunk_size");
+        }
+        if own.citation_style.is_some() {
+            overridden.push("citation_style");
+        }
+    }
+    let defaults = &settings.notebook_defaults;
+    EffectiveSettings {
+        models: crate::model_prefs::resolve(settings, notebook_id),
+        chunk_size: own.and_then(|o| o.chunk_size).or(defaults.chunk_size),
+        citation_style: own
+            .and_then(|o| o.citation_style)
+            .or(defaults.citation_style)
+            .unwrap_or_default(),
+        overridden,
+    }
+}
+
+fn validate(settings: &NotebookSettings) -> Result<(), String> {
+    if let Some(size) = settings.chunk_size {
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&size) {
+            return Err(format!(
+                "Chunk size must be between {} and {} characters, got {}",
+                MIN_CHUNK_SIZE, MAX_CHUNK_SIZE, size
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the backend's copy and tell the webview. Called in setup and
+/// after any change to notebook or model settings.
+pub(crate) fn publish(app: &AppHandle) {
+    let settings = crate::settings::get(app);
+    let ids = settings
+        .notebook_models
+        .keys()
+        .chain(settings.notebook_overrides.keys());
+    let file = SidecarFile {
+        defaults: resolve(&settings, None),
+        notebooks: ids
+            .map(|id| (id.clone(), resolve(&settings, Some(id))))
+            .collect(),
+    };
+    let path = crate::backend_data_dir().join(SIDECAR_FILE);
+    let tmp = path.with_extension("json.tmp");
+    let written = serde_json::to_vec_pretty(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&tmp, json).map_err(|e| e.to_string()))
+        .and_then(|()| std::fs::rename(&tmp, &path).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        tracing::warn!(
+            "[NotebookSettings] Could not write {}: {}",
+            path.display(),
+            e
+        );
+    }
+    let _ = app.emit("notebook-settings://changed", ());
+}
+
+/// What applies in a notebook, or the global defaults when `notebook_id` is
+/// None.
+#[tauri::command]
+pub(crate) async fn get_effective_settings(
+    app: AppHandle,
+    notebook_id: Option<String>,
+) -> Result<EffectiveSettings, String> {
+    Ok(resolve(&crate::settings::get(&app), notebook_id.as_deref()))
+}
+
+/// Replace a notebook's overrides (or the global defaults when `notebook_id`
+/// is None). `settings: None` clears the notebook's overrides.
+#[tauri::command]
+pub(crate) async fn set_notebook_settings(
+    app: AppHandle,
+    notebook_id: Option<String>,
+    settings: Option<NotebookSettings>,
+) -> Result<EffectiveSettings, String> {
+    if let Some(s) = &settings {
+        validate(s)?;
+    }
+    let updated = crate::settings::update(&app, |s| match &notebook_id {
+        Some(id) => match settings {
+            Some(n) => {
+                s.notebook_overrides.insert(id.clone(), n);
+            }
+            None => {
+                s.notebook_overrides.remove(id);
+            }
+        },
+        None => s.notebook_defaults = settings.unwrap_or_default(),
+    })?;
+    publish(&app);
+    Ok(resolve(&updated, notebook_id.as_deref()))
+}
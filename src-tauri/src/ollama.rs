@@ -0,0 +1,541 @@
+//! Ollama detection and lifecycle — the second local server LocalBook needs.
+//!
+//! If Ollama is already serving (Ollama.app, a brew service, a remote
+//! OLLAMA_HOST) we just use it. Otherwise we find the binary, start `ollama
+//! serve` ourselves and keep the Child so we can tell "ours died" from "theirs
+//! is busy". A monitor mirrors the backend watchdog at a smaller scale:
+//! restart a server we own when it stops answering, emit `ollama-health`, and
+//! stop it on exit. The endpoint is handed to the backend as OLLAMA_BASE_URL at
+//! spawn so both sides always agree on where Ollama lives.
+//!
+//! Model management (list / pull with progress / delete) also lives here so the
+//! settings UI never has to talk to Ollama or shell out from the webview.
+
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::hardware::BackendTuning;
+
+const DEFAULT_ENDPOINT: &str = "http://localhost:11434";
+const MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+/// Consecutive failed checks before a server we own is restarted.
+const FAIL_THRESHOLD: u32 = 3;
+
+// Common Ollama installation paths, checked before falling back to PATH.
+const INSTALL_PATHS: &[&str] = &[
+    "/opt/homebrew/bin/ollama",  // Apple Silicon Homebrew
+    "/usr/local/bin/ollama",      // Intel Homebrew
+    "/Applications/Ollama.app/Contents/Resources/ollama", // Ollama.app
+];
+
+/// `ollama serve` we spawned ourselves (None if Ollama was already running).
+static MANAGED: Mutex<Option<Child>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct OllamaStatus {
+    installed: bool,
+    path: Option<String>,
+    version: Option<String>,
+    running: bool,
+    /// True when LocalBook started (and will stop) this server.
+    managed: bool,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagModel>,
+}
+
+#[derive(Deserialize)]
+struct TagModel {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: String,
+    #[serde(default)]
+    details: TagDetails,
+}
+
+#[derive(Deserialize, Default)]
+struct TagDetails {
+    #[serde(default)]
+    family: String,
+    #[serde(default)]
+    parameter_size: String,
+    #[serde(default)]
+    quantization_level: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct OllamaModel {
+    name: String,
+    size_bytes: u64,
+    modified_at: String,
+    family: String,
+    parameter_size: String,
+    quantization_level: String,
+}
+
+/// Base URL for Ollama. Honours OLLAMA_HOST the same way the ollama CLI does
+/// ("host:port", "http://host:port", or just "host").
+/// A remote host is ignored in local-only mode.
+pub(crate) fn endpoint() -> String {
+    let endpoint = match std::env::var("OLLAMA_HOST") {
+        Ok(h) if !h.trim().is_empty() => {
+            let h = h.trim().trim_end_matches('/');
+            let with_scheme = if h.contains("://") {
+                h.to_string()
+            } else {
+                format!("http://{}", h)
+            };
+            let host_part = with_scheme.split("://").nth(1).unwrap_or_default();
+            if host_part.contains(':') {
+                with_scheme
+            } else {
+                format!("{}:11434", with_scheme)
+            }
+        }
+        _ => DEFAULT_ENDPOINT.to_string(),
+    };
+    if crate::privacy::local_only() && !crate::privacy::is_local(&endpoint) {
+        return DEFAULT_ENDPOINT.to_string();
+    }
+    endpoint
+}
+
+/// Locate the ollama binary, if installed.
+pub(crate) fn detect_install() -> Option<PathBuf> {
+    if let Some(p) = INSTALL_PATHS.iter().map(PathBuf::from).find(|p| p.exists()) {
+        return Some(p);
+    }
+    let which = if cfg!(target_os = "windows") { "where" } else { "which" };
+    let out = std::process::Command::new(which).arg("ollama").output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .next()
+        .map(|l| PathBuf::from(l.trim()))
+}
+
+// Function to check if Ollama is running
+pub(crate) async fn is_running() -> bool {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build();
+
+    match client {
+        Ok(c) => c.get(format!("{}/api/tags", endpoint())).send().await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+async fn version() -> Option<String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .ok()?;
+    let v: serde_json::Value = client
+        .get(format!("{}/api/version", endpoint()))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    v.get("version").and_then(|s| s.as_str()).map(String::from)
+}
+
+// Function to check if a model is available in Ollama
+pub(crate) async fn model_available(model_name: &str) -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build() {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+
+    let response = client
+        .get(format!("{}/api/tags", endpoint()))
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if let Ok(text) = resp.text().await {
+                // Check if model name appears in the response
+                text.contains(model_name)
+            } else {
+                false
+            }
+        }
+        Err(_) => false,
+    }
+}
+
+/// Load `model_name` into memory with a one-token prompt and keep it resident.
+pub(crate) async fn warm_model(model_name: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300)) // cold load of a large model from disk
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let resp = client
+        .post(format!("{}/api/generate", endpoint()))
+        .json(&serde_json::json!({
+            "model": model_name,
+            "prompt": "hi",
+            "stream": false,
+            "keep_alive": "30m",
+            "options": { "num_predict": 1 }
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not reachable: {}", e))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "HTTP {}: {}",
+            resp.status(),
+            resp.text().await.unwrap_or_default().trim()
+        ))
+    }
+}
+
+/// A model's native context window from its GGUF metadata
+/// (`<arch>.context_length`), if Ollama reports one.
+pub(crate) async fn context_length(model_name: &str) -> Result<Option<u64>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let info: serde_json::Value = client
+        .post(format!("{}/api/show", endpoint()))
+        .json(&serde_json::json!({ "model": model_name }))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not reachable: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Unknown model {}: {}", model_name, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected /api/show response: {}", e))?;
+    Ok(info["model_info"].as_object().and_then(|m| {
+        m.iter()
+            .find(|(k, _)| k.ends_with(".context_length"))
+            .and_then(|(_, v)| v.as_u64())
+    }))
+}
+
+/// Evict every model Ollama has loaded (`keep_alive: 0`). Returns their names;
+/// Ollama reloads a model on its next request.
+pub(crate) async fn unload_all() -> Result<Vec<String>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let ps: serde_json::Value = client
+        .get(format!("{}/api/ps", endpoint()))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama not reachable: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected /api/ps response: {}", e))?;
+    let loaded: Vec<String> = ps["models"]
+        .as_array()
+        .map(|m| m.iter().filter_map(|m| m["name"].as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    for name in &loaded {
+        client
+            .post(format!("{}/api/generate", endpoint()))
+            .json(&serde_json::json!({ "model": name, "keep_alive": 0 }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to unload {}: {}", name, e))?;
+    }
+    Ok(loaded)
+}
+
+// Function to pull a model from Ollama
+pub(crate) async fn pull_model(model_name: &str) -> Result<(), String> {
+    tracing::info!("Pulling Ollama model: {}", model_name);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(600)) // 10 min timeout for large models
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let response = client
+        .post(format!("{}/api/pull", endpoint()))
+        .json(&serde_json::json!({
+            "name": model_name,
+            "stream": false
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to pull model: {}", e))?;
+
+    if response.status().is_success() {
+        tracing::info!("Successfully pulled model: {}", model_name);
+        Ok(())
+    } else {
+        Err(format!("Failed to pull model {}: HTTP {}", model_name, response.status()))
+    }
+}
+
+fn spawn_serve(path: &std::path::Path, tuning: &BackendTuning) -> std::io::Result<Child> {
+    std::process::Command::new(path)
+        .arg("serve")
+        // Memory management: how many models stay loaded, flash attention and
+        // KV cache precision are sized to this machine (see hardware.rs).
+        .envs(crate::hardware::ollama_env(tuning))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+}
+
+// Function to start Ollama if not running
+pub(crate) async fn ensure_running(app: &AppHandle) {
+    if is_running().await {
+        tracing::info!("Ollama is already running");
+        return;
+    }
+
+    tracing::info!("Starting Ollama...");
+
+    let Some(path) = detect_install() else {
+        tracing::warn!("Could not find an Ollama installation");
+        tracing::warn!("Please install Ollama from https://ollama.com or start it manually: ollama serve");
+        return;
+    };
+
+    let tuning = crate::hardware::tuning(app);
+    match spawn_serve(&path, &tuning) {
+        Ok(child) => {
+            tracing::info!(
+                "Started Ollama from: {:?} (MAX_LOADED_MODELS={}, FLASH_ATTN={}, KV={})",
+                path, tuning.max_loaded_models, tuning.flash_attention, tuning.kv_cache_type
+            );
+            if let Ok(mut managed) = MANAGED.lock() {
+                *managed = Some(child);
+            }
+            // Wait for Ollama to be ready
+            for attempt in 1..=10 {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                if is_running().await {
+                    tracing::info!("Ollama started successfully");
+                    return;
+                }
+                tracing::info!("Waiting for Ollama... attempt {}/10", attempt);
+            }
+            tracing::warn!("Warning: Ollama may not have started properly");
+        }
+        Err(e) => {
+            tracing::warn!("Could not start Ollama: {}", e);
+            tracing::warn!("Please start Ollama manually: ollama serve");
+        }
+    }
+}
+
+/// Watch Ollama after startup. Only a server we spawned is restarted — an
+/// externally managed one that stops answering is reported, not touched.
+pub(crate) fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut failures: u32 = 0;
+        let mut was_running = true;
+        loop {
+            tokio::time::sleep(MONITOR_INTERVAL).await;
+
+            let running = is_running().await;
+            if running {
+                if !was_running {
+                    tracing::info!("[Ollama] Responsive again");
+                    let _ = app.emit("ollama-health", serde_json::json!({ "status": "running" }));
+                }
+                failures = 0;
+                was_running = true;
+                continue;
+            }
+
+            failures += 1;
+            was_running = false;
+            tracing::info!("[Ollama] Health check failed ({}/{})", failures, FAIL_THRESHOLD);
+            if failures < FAIL_THRESHOLD {
+                continue;
+            }
+
+            let owned = MANAGED.lock().map(|m| m.is_some()).unwrap_or(false);
+            if !owned {
+                let _ = app.emit("ollama-health", serde_json::json!({
+                    "status": "unreachable",
+                    "message": "Ollama is not responding. Please check that it is running."
+                }));
+                failures = 0;
+                continue;
+            }
+
+            tracing::info!("[Ollama] Restarting managed server");
+            let _ = app.emit("ollama-health", serde_json::json!({
+                "status": "restarting",
+                "message": "Ollama stopped responding. Restarting..."
+            }));
+            stop_managed();
+            ensure_running(&app).await;
+            failures = 0;
+        }
+    });
+}
+
+/// Stop `ollama serve` if (and only if) we started it.
+pub(crate) fn stop_managed() {
+    if let Ok(mut managed) = MANAGED.lock() {
+        if let Some(mut child) = managed.take() {
+            tracing::info!("[Ollama] Stopping managed server (PID {})", child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_ollama_status() -> Result<OllamaStatus, String> {
+    let path = tauri::async_runtime::spawn_blocking(detect_install)
+        .await
+        .map_err(|e| e.to_string())?;
+    let running = is_running().await;
+    Ok(OllamaStatus {
+        installed: path.is_some(),
+        path: path.map(|p| p.display().to_string()),
+        version: if running { version().await } else { None },
+        running,
+        managed: MANAGED.lock().map(|m| m.is_some()).unwrap_or(false),
+        endpoint: endpoint(),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn list_ollama_models() -> Result<Vec<OllamaModel>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let tags: TagsResponse = client
+        .get(format!("{}/api/tags", endpoint()))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama is not reachable: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected response from Ollama: {}", e))?;
+    Ok(tags
+        .models
+        .into_iter()
+        .map(|m| OllamaModel {
+            name: m.name,
+            size_bytes: m.size,
+            modified_at: m.modified_at,
+            family: m.details.family,
+            parameter_size: m.details.parameter_size,
+            quantization_level: m.details.quantization_level,
+        })
+        .collect())
+}
+
+/// Pull a model, forwarding Ollama's NDJSON progress as `ollama://pull-progress`
+/// events ({ model, status, completed, total, percent }). Resolves when the pull
+/// finishes; a second pull of the same model is cheap (Ollama resumes layers).
+#[tauri::command]
+pub(crate) async fn pull_ollama_model(app: AppHandle, name: String) -> Result<(), String> {
+    tracing::info!("[Ollama] Pulling {}", name);
+    // No overall timeout — multi-GB pulls legitimately take a long time.
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let mut resp = client
+        .post(format!("{}/api/pull", endpoint()))
+        .json(&serde_json::json!({ "model": name, "stream": true }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to pull model: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Failed to pull model {}: HTTP {}", name, resp.status()));
+    }
+
+    // Bytes, not text: a chunk can end partway through a line or a character.
+    let mut buffer: Vec<u8> = Vec::new();
+    loop {
+        let chunk = resp
+            .chunk()
+            .await
+            .map_err(|e| format!("Pull stream interrupted: {}", e))?;
+        let Some(bytes) = chunk else { break };
+        buffer.extend_from_slice(&bytes);
+
+        while let Some(idx) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=idx).collect();
+            let line = line.trim_ascii();
+            if line.is_empty() {
+                continue;
+            }
+            let Ok(evt) = serde_json::from_slice::<serde_json::Value>(line) else {
+                continue;
+            };
+            if let Some(err) = evt.get("error").and_then(|e| e.as_str()) {
+                return Err(format!("Failed to pull model {}: {}", name, err));
+            }
+            let completed = evt.get("completed").and_then(|v| v.as_u64());
+            let total = evt.get("total").and_then(|v| v.as_u64());
+            let percent = match (completed, total) {
+                (Some(c), Some(t)) if t > 0 => Some((c as f64 / t as f64 * 100.0).round()),
+                _ => None,
+            };
+            let _ = app.emit(
+                "ollama://pull-progress",
+                serde_json::json!({
+                    "model": name,
+                    "status": evt.get("status").and_then(|s| s.as_str()).unwrap_or(""),
+                    "completed": completed,
+                    "total": total,
+                    "percent": percent,
+                }),
+            );
+        }
+    }
+
+    tracing::info!("[Ollama] Pulled {}", name);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn delete_ollama_model(name: String) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let resp = client
+        .delete(format!("{}/api/delete", endpoint()))
+        .json(&serde_json::json!({ "model": name }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete model: {}", e))?;
+    if resp.status().is_success() {
+        tracing::info!("[Ollama] Deleted {}", name);
+        Ok(())
+    } else if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        Err(format!("Model {} is not installed", name))
+    } else {
+        Err(format!("Failed to delete model {}: HTTP {}", name, resp.status()))
+    }
+}
@@ -0,0 +1,186 @@
+//! First-run setup progress, kept in shell settings rather than the webview.
+//!
+//! The setup wizard marks each step done with `complete_onboarding_step`;
+//! `get_onboarding_state` says which step is next, or that setup is
+//! finished. Because it lives in the app data folder it survives a
+//! reinstall and clearing webview storage, which used to bring the wizard
+//! back for people who had long finished it.
+//!
+//! The hardware step stores what detection found, and the model step the
+//! model picked, so a later look at setup shows what it was based on.
+//! Installs from before this was tracked are recognised by their existing
+//! backend data and count as set up.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OnboardingStep {
+    Welcome,
+    Hardware,
+    Model,
+    Privacy,
+    FirstNotebook,
+}
+
+/// The wizard's order.
+const STEPS: &[OnboardingStep] = &[
+    OnboardingStep::Welcome,
+    OnboardingStep::Hardware,
+    OnboardingStep::Model,
+    OnboardingStep::Privacy,
+    OnboardingStep::FirstNotebook,
+];
+
+/// What hardware detection found during setup.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct HardwareSummary {
+    pub total_ram_bytes: u64,
+    pub gpus: Vec<String>,
+    /// "cuda", "metal", "vulkan" or "cpu".
+    pub acceleration: String,
+    pub recommended_max_model_bytes: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct OnboardingState {
+    pub completed_steps: Vec<OnboardingStep>,
+    pub hardware: Option<HardwareSummary>,
+    pub chosen_model: Option<String>,
+    /// Unix seconds.
+    pub started_at: u64,
+    /// Unix seconds, once every step is done.
+    pub completed_at: Option<u64>,
+    /// Marked done because the install predates onboarding tracking.
+    pub inferred: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct OnboardingStatus {
+    #[serde(flatten)]
+    state: OnboardingState,
+    steps: &'static [OnboardingStep],
+    /// The first step not yet done; None once setup is finished.
+    next_step: Option<OnboardingStep>,
+    complete: bool,
+}
+
+impl From<OnboardingState> for OnboardingStatus {
+    fn from(state: OnboardingState) -> Self {
+        let next_step = STEPS
+            .iter()
+            .copied()
+            .find(|s| !state.completed_steps.contains(s));
+        Self {
+            complete: next_step.is_none(),
+            next_step,
+            steps: STEPS,
+            state,
+        }
+    }
+}
+
+/// Backend data from before onboarding was tracked.
+fn existing_install() -> bool {
+    std::fs::read_dir(crate::backend_data_dir()).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+fn detect_hardware() -> HardwareSummary {
+    let hw = crate::hardware::info(false);
+    let acceleration = if hw.cuda {
+        "cuda"
+    } else if hw.metal {
+        "metal"
+    } else if hw.vulkan {
+        "vulkan"
+    } else {
+        "cpu"
+    };
+    HardwareSummary {
+        total_ram_bytes: hw.total_ram_bytes,
+        gpus: hw.gpus.iter().map(|g| g.name.clone()).collect(),
+        acceleration: acceleration.to_string(),
+        recommended_max_model_bytes: hw.recommended_max_model_bytes,
+    }
+}
+
+/// The saved state, started (or inferred as done) on first use.
+fn load(app: &AppHandle) -> Result<OnboardingState, String> {
+    if let Some(state) = crate::settings::get(app).onboarding {
+        return Ok(state);
+    }
+    let now = crate::models::now_secs();
+    let state = if existing_install() {
+        tracing::info!("[Onboarding] Existing install, skipping setup");
+        OnboardingState {
+            completed_steps: STEPS.to_vec(),
+            started_at: now,
+            completed_at: Some(now),
+            inferred: true,
+            ..OnboardingState::default()
+        }
+    } else {
+        OnboardingState {
+            started_at: now,
+            ..OnboardingState::default()
+        }
+    };
+    crate::settings::update(app, |s| s.onboarding = Some(state.clone()))?;
+    Ok(state)
+}
+
+#[tauri::command]
+pub(crate) async fn get_onboarding_state(app: AppHandle) -> Result<OnboardingStatus, String> {
+    load(&app).map(OnboardingStatus::from)
+}
+
+/// Mark a step done. The model step takes the model chosen; the hardware
+/// step records what detection finds.
+#[tauri::command]
+pub(crate) async fn complete_onboarding_step(
+    app: AppHandle,
+    step: OnboardingStep,
+    chosen_model: Option<String>,
+) -> Result<OnboardingStatus, String> {
+    let mut state = load(&app)?;
+    match step {
+        OnboardingStep::Model => {
+            let model = chosen_model
+                .filter(|m| !m.trim().is_empty())
+                .ok_or("Choose a model to finish this step")?;
+            state.chosen_model = Some(model);
+        }
+        OnboardingStep::Hardware => {
+            state.hardware = Some(
+                tauri::async_runtime::spawn_blocking(detect_hardware)
+                    .await
+                    .map_err(|e| e.to_string())?,
+            );
+        }
+        _ => {}
+    }
+    if !state.completed_steps.contains(&step) {
+        state.completed_steps.push(step);
+    }
+    let status = OnboardingStatus::from(state.clone());
+    if status.complete && state.completed_at.is_none() {
+        state.completed_at = Some(crate::models::now_secs());
+        tracing::info!("[Onboarding] Setup complete");
+        crate::audit::record("onboarding_completed", format!("{:?}", step));
+    }
+    crate::settings::update(&app, |s| s.onboarding = Some(state.clone()))?;
+    Ok(OnboardingStatus::from(state))
+}
+
+/// Run setup again from the first step.
+#[tauri::command]
+pub(crate) async fn reset_onboarding(app: AppHandle) -> Result<OnboardingStatus, String> {
+    let state = OnboardingState {
+        started_at: crate::models::now_secs(),
+        ..OnboardingState::default()
+    };
+    crate::settings::update(&app, |s| s.onboarding = Some(state.clone()))?;
+    Ok(OnboardingStatus::from(state))
+}
@@ -0,0 +1,39 @@
+//! One switch to hold all background work — during a video call, say, or on
+//! battery — while chat and everything the user asks for directly keep
+//! working.
+//!
+//! While paused, queued jobs don't start (running ones finish), scheduled
+//! tasks wait and fire once it's lifted, and model pre-warming is skipped.
+//! The state is saved in settings so a pause survives a restart, can be
+//! toggled from the tray, and is announced as `background://paused`.
+
+use tauri::{AppHandle, Emitter};
+
+pub(crate) fn is_paused(app: &AppHandle) -> bool {
+    crate::settings::get(app).background_paused
+}
+
+pub(crate) fn set_paused(app: &AppHandle, paused: bool) -> Result<(), String> {
+    crate::settings::update(app, |s| s.background_paused = paused)?;
+    tracing::info!(
+        "[Pause] Background work {}",
+        if paused { "paused" } else { "resumed" }
+    );
+    crate::tray::set_paused(paused);
+    if !paused {
+        crate::jobs::resume();
+    }
+    let _ = app.emit("background://paused", paused);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_background_paused(app: AppHandle) -> Result<bool, String> {
+    Ok(is_paused(&app))
+}
+
+#[tauri::command]
+pub(crate) async fn set_background_paused(app: AppHandle, paused: bool) -> Result<bool, String> {
+    set_paused(&app, paused)?;
+    Ok(paused)
+}
@@ -0,0 +1,222 @@
+//! A performance profile to attach to "it's slow on my machine" reports.
+//!
+//! `capture_performance_profile` samples once a second for the duration
+//! asked for: CPU and memory of the shell, of the backend (the sidecar and
+//! the processes it started), of Ollama and of the whole machine. The
+//! backend requests made meanwhile (see `traces`) are added with their
+//! timings, and the hardware and versions go at the top. It's written as
+//! one JSON file the user can look over before sharing: routes have their
+//! ids replaced (as in `metrics`), and there are no paths, titles, queries,
+//! host names or request ids in it. One capture at a time.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::AppHandle;
+
+const FORMAT: &str = "localbook-performance-profile";
+const VERSION: u32 = 1;
+const INTERVAL: Duration = Duration::from_secs(1);
+const MIN_SECS: u64 = 5;
+const MAX_SECS: u64 = 300;
+const BACKEND_NAME: &str = "localbook-backend";
+const OLLAMA_NAME: &str = "ollama";
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Default, Serialize)]
+struct Usage {
+    /// Percent of one core, so it can pass 100.
+    cpu_percent: f32,
+    memory_mb: u64,
+    processes: usize,
+}
+
+#[derive(Serialize)]
+struct Sample {
+    /// Since the capture started.
+    offset_ms: u64,
+    shell: Usage,
+    backend: Usage,
+    ollama: Usage,
+    /// Across all cores, 0–100.
+    system_cpu_percent: f32,
+    system_memory_used_mb: u64,
+}
+
+#[derive(Default, Serialize)]
+struct Summary {
+    avg_cpu_percent: f32,
+    max_cpu_percent: f32,
+    max_memory_mb: u64,
+}
+
+fn summarize(samples: &[Sample], usage: impl Fn(&Sample) -> Usage) -> Summary {
+    if samples.is_empty() {
+        return Summary::default();
+    }
+    let all: Vec<Usage> = samples.iter().map(usage).collect();
+    Summary {
+        avg_cpu_percent: all.iter().map(|u| u.cpu_percent).sum::<f32>() / all.len() as f32,
+        max_cpu_percent: all.iter().map(|u| u.cpu_percent).fold(0.0, f32::max),
+        max_memory_mb: all.iter().map(|u| u.memory_mb).max().unwrap_or(0),
+    }
+}
+
+/// `root` and everything under it.
+fn tree(sys: &System, root: Pid) -> HashSet<Pid> {
+    let mut pids = HashSet::from([root]);
+    // Parents are usually listed first; go round until nothing is added.
+    loop {
+        let before = pids.len();
+        for (pid, process) in sys.processes() {
+            if process.parent().is_some_and(|p| pids.contains(&p)) {
+                pids.insert(*pid);
+            }
+        }
+        if pids.len() == before {
+            return pids;
+        }
+    }
+}
+
+fn usage(sys: &System, pids: &HashSet<Pid>) -> Usage {
+    let mut usage = Usage::default();
+    for process in pids.iter().filter_map(|p| sys.process(*p)) {
+        usage.cpu_percent += process.cpu_usage();
+        usage.memory_mb += process.memory() / (1024 * 1024);
+        usage.processes += 1;
+    }
+    usage
+}
+
+fn named(sys: &System, name: &str) -> HashSet<Pid> {
+    sys.processes()
+        .iter()
+        .filter(|(_, p)| p.name().to_string_lossy().to_lowercase().starts_with(name))
+        .map(|(pid, _)| *pid)
+        .collect()
+}
+
+fn sample(sys: &mut System, started: Instant) -> Sample {
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+    sys.refresh_cpu_usage();
+    sys.refresh_memory();
+    let shell = sysinfo::get_current_pid()
+        .map(|pid| HashSet::from([pid]))
+        .unwrap_or_default();
+    let mut backend = named(sys, BACKEND_NAME);
+    if let Some(pid) = crate::backend_events::sidecar_pid() {
+        backend.extend(tree(sys, Pid::from_u32(pid)));
+    }
+    Sample {
+        offset_ms: started.elapsed().as_millis() as u64,
+        shell: usage(sys, &shell),
+        backend: usage(sys, &backend),
+        ollama: usage(sys, &named(sys, OLLAMA_NAME)),
+        system_cpu_percent: sys.global_cpu_usage(),
+        system_memory_used_mb: sys.used_memory() / (1024 * 1024),
+    }
+}
+
+/// Sample for `duration`, blocking.
+fn capture(duration: Duration) -> Vec<Sample> {
+    let mut sys = System::new();
+    let started = Instant::now();
+    // CPU use is measured between refreshes; this first one is the baseline.
+    sample(&mut sys, started);
+    let mut samples = Vec::new();
+    while started.elapsed() < duration {
+        std::thread::sleep(INTERVAL);
+        samples.push(sample(&mut sys, started));
+    }
+    samples
+}
+
+fn requests(since: chrono::DateTime<chrono::Local>) -> Value {
+    let traces = crate::traces::since(since);
+    let list: Vec<Value> = traces
+        .iter()
+        .map(|t| {
+            json!({
+                "origin": t.origin,
+                "method": t.method,
+                "route": crate::metrics::route(&t.path),
+                "status": t.status,
+                "failed": t.error.is_some(),
+                "duration_ms": t.duration_ms,
+            })
+        })
+        .collect();
+    let durations: Vec<u64> = traces.iter().map(|t| t.duration_ms).collect();
+    json!({
+        "count": durations.len(),
+        "avg_ms": durations.iter().sum::<u64>().checked_div(durations.len() as u64).unwrap_or(0),
+        "max_ms": durations.iter().max().copied().unwrap_or(0),
+        "list": list,
+    })
+}
+
+/// Profile the app for `duration_secs` (5–300) and write it to `dest`, a
+/// file or a folder to put it in. Returns the file written.
+#[tauri::command]
+pub(crate) async fn capture_performance_profile(
+    app: AppHandle,
+    duration_secs: u64,
+    dest: String,
+) -> Result<String, String> {
+    let dest = crate::scope::check(&app, Path::new(&dest))?;
+    if CAPTURING.swap(true, Ordering::SeqCst) {
+        return Err("A performance profile is already being captured".to_string());
+    }
+    let duration = Duration::from_secs(duration_secs.clamp(MIN_SECS, MAX_SECS));
+    tracing::info!("[Profile] Capturing for {}s", duration.as_secs());
+    let started_at = chrono::Local::now();
+    let samples = tauri::async_runtime::spawn_blocking(move || capture(duration)).await;
+    CAPTURING.store(false, Ordering::SeqCst);
+    let samples = samples.map_err(|e| e.to_string())?;
+
+    let profile = json!({
+        "format": FORMAT,
+        "version": VERSION,
+        "app_version": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "os_version": System::os_version(),
+        "hardware": crate::hardware::info(false),
+        "debug_mode": crate::debug_mode::enabled(),
+        "started_at": started_at.to_rfc3339(),
+        "duration_secs": duration.as_secs(),
+        "interval_ms": INTERVAL.as_millis() as u64,
+        "summary": {
+            "shell": summarize(&samples, |s| s.shell),
+            "backend": summarize(&samples, |s| s.backend),
+            "ollama": summarize(&samples, |s| s.ollama),
+        },
+        "requests": requests(started_at),
+        "samples": samples,
+    });
+
+    let dest = if dest.is_dir() {
+        dest.join(format!(
+            "localbook-profile-{}.json",
+            started_at.format("%Y%m%d-%H%M%S")
+        ))
+    } else {
+        dest
+    };
+    let body = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    std::fs::write(&dest, body)
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    tracing::info!("[Profile] Written");
+    crate::audit::record("performance_profile_exported", dest.display().to_string());
+    Ok(dest.to_string_lossy().into_owned())
+}
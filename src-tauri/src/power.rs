@@ -0,0 +1,208 @@
+//! Power source awareness — battery vs. AC, low-power mode, charge level.
+//!
+//! Polled every 30s with the platform's own tools (pmset / sysfs / CIM) rather
+//! than a native binding; a change emits `power://state`. The derived
+//! `BackgroundPolicy` is what background work consults: on battery it follows
+//! the user's setting (throttle by default), and low-power mode or a nearly
+//! empty battery always pauses.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Below this charge (on battery) background work pauses regardless of setting.
+const LOW_BATTERY_PERCENT: u8 = 20;
+
+static LAST_STATE: Mutex<Option<PowerState>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BackgroundPolicy {
+    Normal,
+    Throttle,
+    Pause,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub(crate) struct PowerState {
+    on_battery: bool,
+    battery_percent: Option<u8>,
+    low_power_mode: bool,
+    policy: BackgroundPolicy,
+}
+
+#[derive(Default)]
+struct PowerReading {
+    on_battery: bool,
+    battery_percent: Option<u8>,
+    low_power_mode: bool,
+}
+
+#[cfg(target_os = "macos")]
+fn read_power() -> PowerReading {
+    let run = |args: &[&str]| {
+        std::process::Command::new("pmset")
+            .args(args)
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default()
+    };
+    // "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=…)\t83%; discharging; …"
+    let batt = run(&["-g", "batt"]);
+    let battery_percent = batt
+        .split_whitespace()
+        .find_map(|t| t.strip_suffix("%;").and_then(|n| n.parse().ok()));
+    // "lowpowermode         1" in the active profile
+    let low_power_mode = run(&["-g"])
+        .lines()
+        .any(|l| l.split_whitespace().collect::<Vec<_>>() == ["lowpowermode", "1"]);
+    PowerReading {
+        on_battery: batt.contains("'Battery Power'"),
+        battery_percent,
+        low_power_mode,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_power() -> PowerReading {
+    let mut reading = PowerReading::default();
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return reading;
+    };
+    let mut on_mains = None;
+    for e in entries.flatten() {
+        let p = e.path();
+        let read = |f: &str| {
+            std::fs::read_to_string(p.join(f))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" => on_mains = Some(read("online") == "1"),
+            "Battery" => {
+                reading.battery_percent = read("capacity").parse().ok();
+                if read("status") == "Discharging" {
+                    reading.on_battery = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(mains) = on_mains {
+        reading.on_battery = !mains && reading.battery_percent.is_some();
+    }
+    reading
+}
+
+#[cfg(target_os = "windows")]
+fn read_power() -> PowerReading {
+    // BatteryStatus 1 = discharging; no battery → empty output (desktop on AC).
+    let out = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Battery | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }",
+        ])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    let mut parts = out.split_whitespace();
+    let status = parts.next();
+    PowerReading {
+        on_battery: status == Some("1"),
+        battery_percent: parts.next().and_then(|p| p.parse().ok()),
+        low_power_mode: false,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn read_power() -> PowerReading {
+    PowerReading::default()
+}
+
+fn derive_policy(r: &PowerReading, battery_policy: BackgroundPolicy) -> BackgroundPolicy {
+    if r.low_power_mode {
+        return BackgroundPolicy::Pause;
+    }
+    if !r.on_battery {
+        return BackgroundPolicy::Normal;
+    }
+    if r.battery_percent.is_some_and(|p| p < LOW_BATTERY_PERCENT) {
+        return BackgroundPolicy::Pause;
+    }
+    battery_policy
+}
+
+fn sample(app: &AppHandle) -> PowerState {
+    let reading = read_power();
+    let battery_policy = crate::settings::get(app).power.battery_policy;
+    PowerState {
+        policy: derive_policy(&reading, battery_policy),
+        on_battery: reading.on_battery,
+        battery_percent: reading.battery_percent,
+        low_power_mode: reading.low_power_mode,
+    }
+}
+
+/// Re-sample and emit `power://state` if anything changed.
+fn refresh(app: &AppHandle) -> PowerState {
+    let state = sample(app);
+    let changed = match LAST_STATE.lock() {
+        Ok(mut last) if last.as_ref() != Some(&state) => {
+            *last = Some(state.clone());
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        tracing::info!(
+            "[Power] on_battery={} percent={:?} low_power={} → background {:?}",
+            state.on_battery,
+            state.battery_percent,
+            state.low_power_mode,
+            state.policy
+        );
+        let _ = app.emit("power://state", &state);
+    }
+    state
+}
+
+/// Policy from the last poll; None before the first one completes.
+pub(crate) fn current_policy() -> Option<BackgroundPolicy> {
+    LAST_STATE.lock().ok()?.as_ref().map(|s| s.policy)
+}
+
+/// Start the poll loop. Called once from setup.
+pub(crate) fn start_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            // pmset/powershell are blocking process spawns.
+            let _ = tauri::async_runtime::spawn_blocking(move || refresh(&handle)).await;
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_power_state(app: AppHandle) -> Result<PowerState, String> {
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// What background work should do while on battery: "normal" | "throttle" | "pause".
+#[tauri::command]
+pub(crate) async fn set_battery_policy(
+    app: AppHandle,
+    policy: BackgroundPolicy,
+) -> Result<PowerState, String> {
+    crate::settings::update(&app, |s| s.power.battery_policy = policy)?;
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
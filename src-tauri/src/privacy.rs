@@ -0,0 +1,156 @@
+//! Local-only mode: a guarantee that the Rust layer talks to nothing but this
+//! machine.
+//!
+//! Every outbound request the shell makes — model, voice and reranker
+//! downloads, Hugging Face search, API-key checks — goes through `guard()`
+//! first, which refuses anything that isn't loopback while the mode is on.
+//! Refusals are kept (the most recent `MAX_VIOLATIONS`) and announced as
+//! `privacy://violation`, so the user can see what tried to leave. The
+//! connectivity monitor stops probing and reports offline, so
+//! internet-dependent features grey out instead of failing one by one.
+//!
+//! The backend is a separate process: it's started without cloud API keys,
+//! without a remote LLM server or Ollama host, and with the Hugging Face
+//! libraries in offline mode, so cloud providers and model fetches there are
+//! off too. Toggling the mode restarts nothing; it applies to the backend the
+//! next time it starts.
+
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+const MAX_VIOLATIONS: usize = 200;
+
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static VIOLATIONS: Mutex<VecDeque<Violation>> = Mutex::new(VecDeque::new());
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Violation {
+    /// Unix seconds.
+    at: u64,
+    /// What tried to connect ("Model download", "API key check"…).
+    feature: String,
+    host: String,
+}
+
+pub(crate) fn local_only() -> bool {
+    LOCAL_ONLY.load(Ordering::Relaxed)
+}
+
+/// Whether `url` points at this machine. Unparseable URLs are not local.
+pub(crate) fn is_local(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Refuse (and report) a connection to anything but this machine while
+/// local-only mode is on.
+pub(crate) fn guard(url: &str, feature: &str) -> Result<(), String> {
+    if !local_only() || is_local(url) {
+        return Ok(());
+    }
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_else(|| url.chars().take(100).collect());
+    tracing::warn!("[Privacy] Blocked {} → {}", feature, host);
+    let violation = Violation {
+        at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        feature: feature.to_string(),
+        host: host.clone(),
+    };
+    {
+        let mut violations = VIOLATIONS.lock().unwrap_or_else(|e| e.into_inner());
+        if violations.len() == MAX_VIOLATIONS {
+            violations.pop_front();
+        }
+        violations.push_back(violation.clone());
+    }
+    if let Some(app) = APP.get() {
+        let _ = app.emit("privacy://violation", &violation);
+    }
+    Err(format!(
+        "Local-only mode is on: {} can't connect to {}",
+        feature, host
+    ))
+}
+
+/// Environment that keeps the backend's libraries from reaching out.
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    if !local_only() {
+        return Vec::new();
+    }
+    [
+        "HF_HUB_OFFLINE",
+        "TRANSFORMERS_OFFLINE",
+        "HF_DATASETS_OFFLINE",
+    ]
+    .into_iter()
+    .map(|var| (var, "1".to_string()))
+    .collect()
+}
+
+/// Load the mode from settings. Called in setup before anything can connect.
+pub(crate) fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let on = crate::settings::get(app).local_only;
+    LOCAL_ONLY.store(on, Ordering::Relaxed);
+    if on {
+        tracing::info!("[Privacy] Local-only mode is on");
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_local_only() -> Result<bool, String> {
+    Ok(local_only())
+}
+
+#[tauri::command]
+pub(crate) async fn set_local_only(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    crate::settings::update(&app, |s| s.local_only = enabled)?;
+    LOCAL_ONLY.store(enabled, Ordering::Relaxed);
+    tracing::info!(
+        "[Privacy] Local-only mode {}",
+        if enabled { "on" } else { "off" }
+    );
+    // Let the monitor re-probe (or stop probing) right away.
+    let _ = crate::network::get_network_status(app).await;
+    Ok(enabled)
+}
+
+/// Blocked connection attempts, oldest first.
+#[tauri::command]
+pub(crate) async fn get_privacy_violations() -> Result<Vec<Violation>, String> {
+    Ok(VIOLATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub(crate) async fn clear_privacy_violations() -> Result<(), String> {
+    VIOLATIONS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    Ok(())
+}
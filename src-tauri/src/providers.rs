@@ -0,0 +1,124 @@
+//! Detection of other local inference servers with an OpenAI-compatible API
+//! (LM Studio, llamafile / llama.cpp server, vLLM, Jan, KoboldCpp…).
+//!
+//! Each well-known port gets a short `GET /v1/models`; anything that answers
+//! with a model list is offered as a provider. The one the user picks is saved
+//! in settings and handed to the backend as LOCALBOOK_LLAMA_SERVER_URL — the
+//! OpenAI-style route its provider layer already has — on the next start.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// (kind, display name, default port)
+const KNOWN_SERVERS: &[(&str, &str, u16)] = &[
+    ("lmstudio", "LM Studio", 1234),
+    ("jan", "Jan", 1337),
+    ("textgen", "text-generation-webui", 5000),
+    ("koboldcpp", "KoboldCpp", 5001),
+    ("vllm", "vLLM", 8000),
+    ("llamafile", "llamafile / llama.cpp server", 8080),
+    ("llama_server", "llama-server", 8090),
+];
+
+#[derive(Serialize)]
+pub(crate) struct DetectedProvider {
+    kind: String,
+    name: String,
+    base_url: String,
+    models: Vec<String>,
+    /// The provider currently passed to the backend.
+    selected: bool,
+}
+
+async fn probe(
+    client: reqwest::Client,
+    kind: &'static str,
+    name: &'static str,
+    port: u16,
+) -> Option<DetectedProvider> {
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let resp = client
+        .get(format!("{}/v1/models", base_url))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    // {"object":"list","data":[{"id":"…","owned_by":"…"}]}
+    let body: serde_json::Value = resp.json().await.ok()?;
+    let data = body["data"].as_array()?;
+    let models = data
+        .iter()
+        .filter_map(|m| m["id"].as_str().map(String::from))
+        .collect();
+    // Ports are only conventions; owned_by tells vLLM and llama.cpp apart
+    // when one runs on the other's port.
+    let (kind, name) = match data.first().and_then(|m| m["owned_by"].as_str()) {
+        Some("vllm") => ("vllm", "vLLM"),
+        Some("llamacpp") => ("llamafile", "llamafile / llama.cpp server"),
+        _ => (kind, name),
+    };
+    Some(DetectedProvider {
+        kind: kind.to_string(),
+        name: name.to_string(),
+        base_url,
+        models,
+        selected: false,
+    })
+}
+
+/// Probe the well-known ports in parallel. Takes at most ~1.5 s.
+#[tauri::command]
+pub(crate) async fn list_detected_providers(
+    app: AppHandle,
+) -> Result<Vec<DetectedProvider>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .no_proxy()
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    let mut probes = tokio::task::JoinSet::new();
+    for (kind, name, port) in KNOWN_SERVERS {
+        probes.spawn(probe(client.clone(), kind, name, *port));
+    }
+    let selected = crate::settings::get(&app).external_llm_url;
+    let mut found = Vec::new();
+    while let Some(result) = probes.join_next().await {
+        if let Ok(Some(mut provider)) = result {
+            provider.selected = selected.as_deref() == Some(provider.base_url.as_str());
+            found.push(provider);
+        }
+    }
+    found.sort_by(|a, b| a.base_url.cmp(&b.base_url));
+    Ok(found)
+}
+
+/// Choose the OpenAI-compatible server the backend should use (None to go
+/// back to the default). Applies when the backend next starts.
+#[tauri::command]
+pub(crate) async fn select_provider(
+    app: AppHandle,
+    base_url: Option<String>,
+) -> Result<(), String> {
+    let base_url = base_url.map(|u| u.trim().trim_end_matches('/').to_string());
+    if let Some(u) = &base_url {
+        if !(u.starts_with("http://") || u.starts_with("https://")) {
+            return Err(format!("Not an http(s) URL: {}", u));
+        }
+        crate::privacy::guard(u, "External LLM server")?;
+    }
+    crate::settings::update(&app, |s| s.external_llm_url = base_url)?;
+    Ok(())
+}
+
+/// Backend environment for the selected provider.
+pub(crate) fn backend_env(app: &AppHandle) -> Option<(&'static str, String)> {
+    crate::settings::get(app)
+        .external_llm_url
+        .filter(|u| crate::privacy::guard(u, "External LLM server").is_ok())
+        .map(|u| ("LOCALBOOK_LLAMA_SERVER_URL", u))
+}
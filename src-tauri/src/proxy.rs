@@ -0,0 +1,293 @@
+//! Proxy configuration for outbound HTTP.
+//!
+//! Three modes: `system` (the default) uses the OS proxy settings — macOS
+//! network preferences, the Windows registry, `HTTP(S)_PROXY` elsewhere;
+//! `manual` sends everything through one HTTP proxy, optionally with basic
+//! auth whose password lives in the keychain; `off` connects directly.
+//!
+//! Every client that talks to the internet is built from `client()`, and the
+//! backend gets the same choice as `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY`
+//! (applied the next time it starts), which covers URL import and cloud
+//! providers there. Loopback is never proxied. With a manual proxy the
+//! connectivity monitor probes the proxy instead of the internet, since
+//! corporate networks often block direct connections outright.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Keychain entry for the manual proxy's password (Rust-only, see `secrets`).
+pub(crate) const PASSWORD_SECRET: &str = "proxy_password";
+const LOOPBACK: &str = "localhost,127.0.0.1,::1";
+const TEST_URL: &str = "https://huggingface.co/api/models?limit=1";
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProxyMode {
+    #[default]
+    System,
+    Manual,
+    Off,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ProxySettings {
+    pub mode: ProxyMode,
+    /// Manual proxy host, optionally with a scheme ("proxy.corp", "https://proxy.corp").
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    /// Hosts to reach directly, in `NO_PROXY` syntax (".corp.example", "10.0.0.0/8").
+    pub bypass: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProxyStatus {
+    #[serde(flatten)]
+    settings: ProxySettings,
+    has_password: bool,
+}
+
+/// The settings in use, with the password already read from the keychain so
+/// building a client never blocks on it.
+struct Active {
+    settings: ProxySettings,
+    password: Option<String>,
+}
+
+static ACTIVE: RwLock<Option<Active>> = RwLock::new(None);
+
+impl ProxySettings {
+    /// `http://host:port` for manual mode, without credentials.
+    fn url(&self) -> Result<reqwest::Url, String> {
+        let host = self.host.trim();
+        if host.is_empty() {
+            return Err("No proxy host set".to_string());
+        }
+        let raw = if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("http://{}", host)
+        };
+        let mut url = reqwest::Url::parse(&raw)
+            .map_err(|e| format!("Invalid proxy host {:?}: {}", host, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Unsupported proxy scheme: {}", url.scheme()));
+        }
+        if let Some(port) = self.port {
+            let _ = url.set_port(Some(port));
+        }
+        Ok(url)
+    }
+
+    fn no_proxy(&self) -> String {
+        std::iter::once(LOOPBACK)
+            .chain(
+                self.bypass
+                    .iter()
+                    .map(|b| b.trim())
+                    .filter(|b| !b.is_empty()),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn activate(settings: ProxySettings) {
+    let password = match settings.mode {
+        ProxyMode::Manual if settings.username.is_some() => crate::secrets::get(PASSWORD_SECRET)
+            .unwrap_or_else(|e| {
+                tracing::warn!("[Proxy] {}", e);
+                None
+            }),
+        _ => None,
+    };
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = Some(Active { settings, password });
+}
+
+/// Load the proxy settings. Called in setup; the keychain read happens off
+/// the main thread.
+pub(crate) fn start(app: &AppHandle) {
+    let settings = crate::settings::get(app).proxy;
+    if settings.mode != ProxyMode::System {
+        tracing::info!(
+            "[Proxy] {}",
+            if settings.mode == ProxyMode::Manual {
+                "Using a manual proxy"
+            } else {
+                "Proxy off, connecting directly"
+            }
+        );
+    }
+    tauri::async_runtime::spawn_blocking(move || activate(settings));
+}
+
+/// A client builder for internet requests, with the configured proxy and
+/// any extra trusted certificates.
+pub(crate) fn client() -> reqwest::ClientBuilder {
+    let builder = crate::certs::apply(reqwest::Client::builder());
+    let guard = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    let Some(active) = guard.as_ref() else {
+        return builder;
+    };
+    match active.settings.mode {
+        ProxyMode::System => builder,
+        ProxyMode::Off => builder.no_proxy(),
+        ProxyMode::Manual => {
+            let proxy = active
+                .settings
+                .url()
+                .and_then(|url| reqwest::Proxy::all(url).map_err(|e| e.to_string()));
+            match proxy {
+                Ok(mut proxy) => {
+                    if let Some(user) = &active.settings.username {
+                        proxy = proxy.basic_auth(user, active.password.as_deref().unwrap_or(""));
+                    }
+                    builder.proxy(
+                        proxy.no_proxy(reqwest::NoProxy::from_string(&active.settings.no_proxy())),
+                    )
+                }
+                Err(e) => {
+                    tracing::warn!("[Proxy] Ignoring manual proxy: {}", e);
+                    builder
+                }
+            }
+        }
+    }
+}
+
+/// The manual proxy's address, which the connectivity monitor probes instead
+/// of the internet.
+pub(crate) fn probe_target() -> Option<String> {
+    let guard = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    let settings = &guard.as_ref()?.settings;
+    if settings.mode != ProxyMode::Manual {
+        return None;
+    }
+    let url = settings.url().ok()?;
+    Some(format!(
+        "{}:{}",
+        url.host_str()?,
+        url.port_or_known_default()?
+    ))
+}
+
+/// How clients outside `client()` (the updater) should connect.
+pub(crate) enum Route {
+    System,
+    Direct,
+    /// The manual proxy, credentials included.
+    Via(reqwest::Url),
+}
+
+fn manual_url(active: &Active) -> Option<reqwest::Url> {
+    let mut url = active.settings.url().ok()?;
+    if let Some(user) = &active.settings.username {
+        let _ = url.set_username(user);
+        let _ = url.set_password(active.password.as_deref());
+    }
+    Some(url)
+}
+
+pub(crate) fn route() -> Route {
+    let guard = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    let Some(active) = guard.as_ref() else {
+        return Route::System;
+    };
+    match active.settings.mode {
+        ProxyMode::System => Route::System,
+        ProxyMode::Off => Route::Direct,
+        ProxyMode::Manual => manual_url(active).map_or(Route::System, Route::Via),
+    }
+}
+
+/// Proxy environment for the backend process.
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    let guard = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    let Some(active) = guard.as_ref() else {
+        return Vec::new();
+    };
+    match active.settings.mode {
+        ProxyMode::Off => vec![("NO_PROXY", "*".to_string())],
+        ProxyMode::System => {
+            let inherited = std::env::var("NO_PROXY").unwrap_or_default();
+            let no_proxy = if inherited.is_empty() {
+                LOOPBACK.to_string()
+            } else {
+                format!("{},{}", inherited, LOOPBACK)
+            };
+            vec![("NO_PROXY", no_proxy)]
+        }
+        ProxyMode::Manual => {
+            let Some(url) = manual_url(active) else {
+                return Vec::new();
+            };
+            let url = url.to_string();
+            vec![
+                ("HTTP_PROXY", url.clone()),
+                ("HTTPS_PROXY", url),
+                ("NO_PROXY", active.settings.no_proxy()),
+            ]
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_proxy_settings(app: AppHandle) -> Result<ProxyStatus, String> {
+    let has_password =
+        tauri::async_runtime::spawn_blocking(|| crate::secrets::get(PASSWORD_SECRET))
+            .await
+            .map_err(|e| e.to_string())??
+            .is_some();
+    Ok(ProxyStatus {
+        settings: crate::settings::get(&app).proxy,
+        has_password,
+    })
+}
+
+/// Save the proxy settings. `password`: Some("") removes the stored one,
+/// None keeps it.
+#[tauri::command]
+pub(crate) async fn set_proxy_settings(
+    app: AppHandle,
+    proxy: ProxySettings,
+    password: Option<String>,
+) -> Result<(), String> {
+    if proxy.mode == ProxyMode::Manual {
+        proxy.url()?;
+    }
+    tauri::async_runtime::spawn_blocking(move || match password.as_deref() {
+        Some("") => crate::secrets::delete(PASSWORD_SECRET),
+        Some(pw) => crate::secrets::set(PASSWORD_SECRET, pw),
+        None => Ok(()),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    crate::settings::update(&app, |s| s.proxy = proxy.clone())?;
+    tauri::async_runtime::spawn_blocking(move || activate(proxy))
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("[Proxy] Settings updated");
+    // Connectivity may have changed with the proxy.
+    let _ = crate::network::get_network_status(app).await;
+    Ok(())
+}
+
+/// Fetch a small public URL through the current settings. Returns the HTTP
+/// status on success.
+#[tauri::command]
+pub(crate) async fn test_proxy() -> Result<u16, String> {
+    crate::privacy::guard(TEST_URL, "Proxy test")?;
+    let resp = client()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?
+        .get(TEST_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    Ok(resp.status().as_u16())
+}
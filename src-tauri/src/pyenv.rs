@@ -0,0 +1,427 @@
+//! The Python environment for installs that run the backend from source.
+//!
+//! Packaged builds ship a frozen `localbook-backend`; source installs point
+//! `LOCALBOOK_BACKEND_SOURCE` at the `backend/` folder instead (or ship it as
+//! `backend-src/` in the resources). For those, `start_backend` asks this
+//! module for a Python to run `main.py` with. The app keeps its own venv in
+//! `<app data>/python/venv`, created with the newest suitable system Python
+//! and filled from the pinned `requirements.txt`; `env.json` records the hash
+//! of the requirements it was built from, so a changed pin set reinstalls on
+//! the next start. Installs report `pyenv://progress` as pip works through
+//! the packages.
+//!
+//! When the backend doesn't come up, the core imports are tried in the venv
+//! and a failure is announced as `pyenv://broken`; `repair_backend_env`
+//! rebuilds the venv from scratch and restarts the backend.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const SOURCE_ENV: &str = "LOCALBOOK_BACKEND_SOURCE";
+/// The backend source folder inside the resources, for source installs.
+const RESOURCE_SOURCE_DIR: &str = "backend-src";
+const ENV_DIR: &str = "python";
+const VENV_DIR: &str = "venv";
+const MARKER_FILE: &str = "env.json";
+const REQUIREMENTS_FILE: &str = "requirements.txt";
+const PYPI_URL: &str = "https://pypi.org/simple/";
+/// Oldest Python the pinned requirements install on.
+const MIN_PYTHON_MINOR: u32 = 12;
+/// Tried in order; the first new enough wins.
+const PYTHON_CANDIDATES: &[&str] = &[
+    "python3.13",
+    "python3.12",
+    "/opt/homebrew/bin/python3.12",
+    "/usr/local/bin/python3.12",
+    "python3",
+    "python",
+];
+/// Imported to check the venv works, as `install.sh` does after pip.
+const CORE_IMPORTS: &[&str] = &[
+    "fastapi",
+    "uvicorn",
+    "pydantic",
+    "lancedb",
+    "rank_bm25",
+    "ebooklib",
+    "odf",
+    "nbformat",
+    "soundfile",
+];
+
+static BUILDING: AtomicBool = AtomicBool::new(false);
+
+/// What the venv was built from.
+#[derive(Serialize, Deserialize)]
+struct Marker {
+    requirements_sha256: String,
+    python_version: String,
+    /// Unix seconds.
+    built_at: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Progress {
+    stage: &'static str,
+    message: String,
+    /// Packages collected so far, out of the pinned total.
+    done: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BackendEnvStatus {
+    /// The backend runs from source in a venv this app manages.
+    managed: bool,
+    source: Option<PathBuf>,
+    venv: Option<PathBuf>,
+    python_version: Option<String>,
+    /// Built from the current requirements.
+    up_to_date: bool,
+    building: bool,
+}
+
+fn emit(app: &AppHandle, stage: &'static str, message: String, done: usize, total: usize) {
+    let _ = app.emit(
+        "pyenv://progress",
+        Progress {
+            stage,
+            message,
+            done,
+            total,
+        },
+    );
+}
+
+/// The backend source folder, if this install runs from source.
+pub(crate) fn source_dir(app: &AppHandle) -> Option<PathBuf> {
+    let from_env = std::env::var_os(SOURCE_ENV).map(PathBuf::from);
+    let bundled = app
+        .path()
+        .resource_dir()
+        .ok()
+        .map(|d| d.join(RESOURCE_SOURCE_DIR));
+    from_env
+        .into_iter()
+        .chain(bundled)
+        .find(|d| d.join("main.py").is_file())
+}
+
+fn env_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::shared_data_dir(app)?.join(ENV_DIR))
+}
+
+fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+fn requirements_hash(source: &Path) -> Result<String, String> {
+    let path = source.join(REQUIREMENTS_FILE);
+    let bytes =
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", Sha256::digest(bytes)))
+}
+
+fn load_marker(dir: &Path) -> Option<Marker> {
+    let raw = std::fs::read_to_string(dir.join(MARKER_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// `major.minor` of a Python, if it runs.
+async fn python_version(python: &Path) -> Option<(u32, u32)> {
+    let out = tokio::process::Command::new(python)
+        .args(["-c", "import sys; print('%d.%d' % sys.version_info[:2])"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .ok()
+        .filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let (major, minor) = text.trim().split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+async fn find_python() -> Result<(PathBuf, String), String> {
+    for candidate in PYTHON_CANDIDATES {
+        let path = PathBuf::from(candidate);
+        if let Some((3, minor)) = python_version(&path).await {
+            if minor >= MIN_PYTHON_MINOR {
+                return Ok((path, format!("3.{}", minor)));
+            }
+        }
+    }
+    Err(format!(
+        "No Python 3.{} or later found; install it (e.g. `brew install python@3.12`) and try again",
+        MIN_PYTHON_MINOR
+    ))
+}
+
+/// Run a command, passing each output line to `on_line`. Errors carry the
+/// last lines of output.
+async fn run(
+    mut command: tokio::process::Command,
+    what: &str,
+    mut on_line: impl FnMut(&str),
+) -> Result<(), String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{} failed to start: {}", what, e))?;
+    let stderr = child.stderr.take().map(|s| {
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(s).lines();
+            let mut tail = Vec::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                tail.push(line);
+                if tail.len() > 10 {
+                    tail.remove(0);
+                }
+            }
+            tail.join("\n")
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            on_line(&line);
+        }
+    }
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    let tail = match stderr {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed ({}):\n{}", what, status, tail))
+    }
+}
+
+fn pip(python: &Path, args: &[&str]) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new(python);
+    command
+        .args(["-m", "pip"])
+        .args(args)
+        .args(["--disable-pip-version-check", "--progress-bar", "off"])
+        .envs(crate::proxy::backend_env());
+    command
+}
+
+/// Create the venv and install the pinned requirements into it.
+async fn build(app: &AppHandle, source: &Path, fresh: bool) -> Result<(), String> {
+    crate::privacy::guard(PYPI_URL, "Python package install")?;
+    let dir = env_dir(app)?;
+    let venv = dir.join(VENV_DIR);
+    let requirements = source.join(REQUIREMENTS_FILE);
+    let hash = requirements_hash(source)?;
+    let total = std::fs::read_to_string(&requirements)
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| l.starts_with(|c: char| c.is_ascii_alphanumeric()) && l.contains("=="))
+        .count();
+
+    if fresh {
+        let _ = std::fs::remove_dir_all(&venv);
+    }
+    let _ = std::fs::remove_file(dir.join(MARKER_FILE));
+    let python = venv_python(&venv);
+    let python_version = match python_version(&python).await {
+        Some((major, minor)) => format!("{}.{}", major, minor),
+        None => {
+            let (system, version) = find_python().await?;
+            emit(
+                app,
+                "venv",
+                format!("Creating a Python {} environment", version),
+                0,
+                total,
+            );
+            let _ = std::fs::remove_dir_all(&venv);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let mut command = tokio::process::Command::new(&system);
+            command.arg("-m").arg("venv").arg(&venv);
+            run(command, "Creating the venv", |_| {}).await?;
+            version
+        }
+    };
+
+    emit(app, "pip", "Updating pip".to_string(), 0, total);
+    run(
+        pip(&python, &["install", "--upgrade", "pip"]),
+        "Updating pip",
+        |_| {},
+    )
+    .await?;
+
+    let mut done = 0;
+    let requirements_arg = requirements.to_string_lossy().into_owned();
+    run(
+        pip(&python, &["install", "-r", &requirements_arg]),
+        "Installing the backend's requirements",
+        |line| {
+            if let Some(rest) = line.strip_prefix("Collecting ") {
+                done += 1;
+                emit(
+                    app,
+                    "download",
+                    rest.trim().to_string(),
+                    done.min(total),
+                    total,
+                );
+            } else if line.starts_with("Installing collected packages") {
+                emit(
+                    app,
+                    "install",
+                    "Installing packages".to_string(),
+                    total,
+                    total,
+                );
+            }
+        },
+    )
+    .await?;
+
+    emit(
+        app,
+        "verify",
+        "Checking the environment".to_string(),
+        total,
+        total,
+    );
+    check_imports(&python).await?;
+    let marker = Marker {
+        requirements_sha256: hash,
+        python_version,
+        built_at: crate::models::now_secs(),
+    };
+    let json = serde_json::to_string_pretty(&marker).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(MARKER_FILE), json).map_err(|e| e.to_string())?;
+    emit(
+        app,
+        "done",
+        "Python environment ready".to_string(),
+        total,
+        total,
+    );
+    tracing::info!("[PyEnv] Environment ready in {}", venv.display());
+    Ok(())
+}
+
+async fn build_once(app: &AppHandle, source: &Path, fresh: bool) -> Result<(), String> {
+    if BUILDING.swap(true, Ordering::SeqCst) {
+        return Err("The Python environment is already being set up".to_string());
+    }
+    let result = build(app, source, fresh).await;
+    BUILDING.store(false, Ordering::SeqCst);
+    if let Err(e) = &result {
+        tracing::warn!("[PyEnv] {}", e);
+        emit(app, "failed", e.clone(), 0, 0);
+    }
+    result
+}
+
+/// Try the core imports in the venv. The error names what failed to import.
+async fn check_imports(python: &Path) -> Result<(), String> {
+    let script = format!(
+        "import importlib, sys\nbad = []\nfor m in {:?}:\n    try:\n        importlib.import_module(m)\n    except Exception as e:\n        bad.append('%s (%s)' % (m, e))\nprint('; '.join(bad))\nsys.exit(1 if bad else 0)",
+        CORE_IMPORTS
+    );
+    let out = tokio::process::Command::new(python)
+        .args(["-c", &script])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", python.display(), e))?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Backend packages failed to import: {}",
+            String::from_utf8_lossy(&out.stdout).trim()
+        ))
+    }
+}
+
+/// The venv's Python, built or brought up to date first. Called from
+/// `start_backend` for source installs; cheap once the venv is current.
+pub(crate) async fn ensure(app: &AppHandle, source: &Path) -> Result<PathBuf, String> {
+    let dir = env_dir(app)?;
+    let python = venv_python(&dir.join(VENV_DIR));
+    let current =
+        load_marker(&dir).is_some_and(|m| Ok(m.requirements_sha256) == requirements_hash(source));
+    if !(current && python.exists()) {
+        tracing::info!("[PyEnv] Setting up the backend's Python environment");
+        build_once(app, source, false).await?;
+    }
+    Ok(python)
+}
+
+/// After the backend failed to start: if it runs from source and its
+/// packages don't import, tell the UI so it can offer a repair.
+pub(crate) fn diagnose(app: &AppHandle) {
+    if source_dir(app).is_none() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(dir) = env_dir(&app) else {
+            return;
+        };
+        if let Err(e) = check_imports(&venv_python(&dir.join(VENV_DIR))).await {
+            tracing::warn!("[PyEnv] {}", e);
+            let _ = app.emit("pyenv://broken", &e);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_backend_env_status(app: AppHandle) -> Result<BackendEnvStatus, String> {
+    let building = BUILDING.load(Ordering::SeqCst);
+    let Some(source) = source_dir(&app) else {
+        return Ok(BackendEnvStatus {
+            managed: false,
+            source: None,
+            venv: None,
+            python_version: None,
+            up_to_date: false,
+            building,
+        });
+    };
+    let dir = env_dir(&app)?;
+    let marker = load_marker(&dir);
+    let up_to_date = marker
+        .as_ref()
+        .is_some_and(|m| Ok(&m.requirements_sha256) == requirements_hash(&source).as_ref());
+    Ok(BackendEnvStatus {
+        managed: true,
+        venv: Some(dir.join(VENV_DIR)),
+        python_version: marker.map(|m| m.python_version),
+        up_to_date,
+        building,
+        source: Some(source),
+    })
+}
+
+/// Rebuild the venv from scratch and restart the backend on it.
+#[tauri::command]
+pub(crate) async fn repair_backend_env(app: AppHandle) -> Result<(), String> {
+    let source =
+        source_dir(&app).ok_or("This install runs a bundled backend, not a Python environment")?;
+    crate::audit::record("backend_env_repaired", source.display().to_string());
+    build_once(&app, &source, true).await?;
+    crate::restart_backend_and_wait(&app, "Python environment repaired").await
+}
@@ -0,0 +1,84 @@
+//! macOS `com.apple.quarantine` handling.
+//!
+//! Files that arrive via a browser, AirDrop or Mail carry the quarantine xattr.
+//! For documents that's mostly harmless, but helper tools the backend shells out
+//! to on re-processing can refuse or prompt on them, and a quarantined backend
+//! bundle (ad-hoc signed, copied out of a downloaded DMG) trips Gatekeeper on
+//! every spawn — which looks like "first import silently does nothing". We only
+//! ever remove the quarantine attribute itself; other xattrs are left alone.
+//! Everything here is a no-op on other platforms.
+
+use std::path::Path;
+
+use tauri::AppHandle;
+
+#[cfg(target_os = "macos")]
+const QUARANTINE_XATTR: &str = "com.apple.quarantine";
+
+/// Remove the quarantine xattr from `path` (recursively for directories).
+/// Returns whether anything was quarantined to begin with.
+#[cfg(target_os = "macos")]
+pub(crate) fn strip(path: &Path) -> bool {
+    let quarantined = if path.is_dir() {
+        // Any quarantined entry under the dir? `xattr -r` lists "<file>: <name>".
+        std::process::Command::new("xattr")
+            .arg("-r")
+            .arg(path)
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(QUARANTINE_XATTR))
+            .unwrap_or(false)
+    } else {
+        std::process::Command::new("xattr")
+            .args(["-p", QUARANTINE_XATTR])
+            .arg(path)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    };
+    if !quarantined {
+        return false;
+    }
+
+    let flag = if path.is_dir() { "-dr" } else { "-d" };
+    match std::process::Command::new("xattr")
+        .args([flag, QUARANTINE_XATTR])
+        .arg(path)
+        .output()
+    {
+        Ok(o) if o.status.success() => {
+            tracing::info!("[Quarantine] Cleared {} on {:?}", QUARANTINE_XATTR, path);
+        }
+        Ok(o) => tracing::warn!(
+            "[Quarantine] Could not clear {:?}: {}",
+            path,
+            String::from_utf8_lossy(&o.stderr).trim()
+        ),
+        Err(e) => tracing::warn!("[Quarantine] xattr failed for {:?}: {}", path, e),
+    }
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn strip(_path: &Path) -> bool {
+    false
+}
+
+/// Clear quarantine on files the frontend is about to (re)process — e.g. library
+/// copies being re-extracted after a failed import. Returns how many paths were
+/// quarantined.
+#[tauri::command]
+pub(crate) async fn clear_quarantine(app: AppHandle, paths: Vec<String>) -> Result<usize, String> {
+    for path in &paths {
+        crate::scope::check(&app, Path::new(path))?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(Path::new)
+            .filter(|p| p.exists())
+            .filter(|p| strip(p))
+            .count()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
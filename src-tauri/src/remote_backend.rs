@@ -0,0 +1,238 @@
+//! Using a backend on another machine instead of the sidecar.
+//!
+//! Phones and tablets can't run the Python sidecar, so on mobile the shell
+//! never starts one: it needs a backend elsewhere — a desktop LocalBook on the
+//! network, or a server — set with `set_remote_backend`. Until then the
+//! startup status says so, and only on-device generation (`inference`) works.
+//! A desktop can be pointed at one too, and then doesn't start its own.
+//!
+//! The easy way on an iPad or phone is `connect_paired_backend`: pair with a
+//! desktop for sync, and it lends its backend over the network and sends the
+//! address and token through the encrypted sync channel (see
+//! `backend_share`); a new token after that backend restarts is fetched the
+//! same way. Any other backend is set by URL and token.
+//!
+//! The URL is in settings; the backend's app token is in the keychain and
+//! takes the place of the sidecar's token file. Shell calls and the webview
+//! (through `get_backend_url`) both follow the configured URL.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+pub(crate) const TOKEN_SECRET: &str = "remote_backend_token";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+static URL: Mutex<Option<String>> = Mutex::new(None);
+/// The token, once read from the keychain.
+static TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Serialize)]
+pub(crate) struct RemoteBackend {
+    url: Option<String>,
+    has_token: bool,
+    /// No sidecar on this platform, so a remote backend is needed.
+    required: bool,
+}
+
+/// Whether this build can't run the sidecar.
+pub(crate) fn required() -> bool {
+    cfg!(mobile)
+}
+
+/// The remote backend's URL, when one is set.
+pub(crate) fn url() -> Option<String> {
+    URL.lock().ok().and_then(|u| u.clone())
+}
+
+/// The remote backend's app token, read from the keychain on first use.
+pub(crate) async fn token() -> Option<String> {
+    if let Some(token) = TOKEN.lock().ok().and_then(|t| t.clone()) {
+        return Some(token);
+    }
+    let token = tauri::async_runtime::spawn_blocking(|| crate::secrets::get(TOKEN_SECRET))
+        .await
+        .ok()?
+        .unwrap_or_else(|e| {
+            tracing::warn!("[RemoteBackend] {}", e);
+            None
+        })?;
+    if let Ok(mut cached) = TOKEN.lock() {
+        *cached = Some(token.clone());
+    }
+    Some(token)
+}
+
+pub(crate) fn start(app: &AppHandle) {
+    let url = crate::settings::get(app).remote_backend_url;
+    match &url {
+        Some(url) => tracing::info!("[RemoteBackend] Using the backend at {}", url),
+        None if required() => {
+            tracing::info!("[RemoteBackend] No backend set; choose one in settings")
+        }
+        None => {}
+    }
+    if let Ok(mut current) = URL.lock() {
+        *current = url;
+    }
+}
+
+/// A usable backend base URL: http(s), no path, no trailing slash.
+fn normalize(url: &str) -> Result<String, String> {
+    let parsed =
+        reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid backend URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err("The backend URL must start with http:// or https://".to_string());
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+async fn check(url: &str, token: Option<&str>) -> Result<(), String> {
+    crate::privacy::guard(url, "Remote backend")?;
+    let client = crate::proxy::client()
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}/health", url));
+    if let Some(token) = token {
+        request = request.header("X-LocalBook-Token", token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't reach the backend at {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "The backend at {} answered HTTP {}",
+            url,
+            response.status().as_u16()
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn get_remote_backend() -> Result<RemoteBackend, String> {
+    Ok(RemoteBackend {
+        url: url(),
+        has_token: token().await.is_some(),
+        required: required(),
+    })
+}
+
+/// The backend's base URL for the webview: the remote one, or the sidecar's.
+#[tauri::command]
+pub(crate) async fn get_backend_url() -> Result<String, String> {
+    Ok(crate::backend_api::base_url())
+}
+
+/// Remember `url` and its token (None keeps the stored token), or go back
+/// to the sidecar with no `url`. `device` is the paired device it came from.
+async fn store(
+    app: &AppHandle,
+    url: Option<String>,
+    token: Option<String>,
+    device: Option<String>,
+) -> Result<(), String> {
+    let (stored, clear) = (token.clone(), url.is_none());
+    tauri::async_runtime::spawn_blocking(move || match stored {
+        _ if clear => crate::secrets::delete(TOKEN_SECRET),
+        Some(t) => crate::secrets::set(TOKEN_SECRET, &t),
+        None => Ok(()),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if let Ok(mut cached) = TOKEN.lock() {
+        if clear {
+            *cached = None;
+        } else if token.is_some() {
+            *cached = token;
+        }
+    }
+    crate::settings::update(app, |s| {
+        s.remote_backend_url = url.clone();
+        s.remote_backend_device = device;
+    })?;
+    if let Ok(mut current) = URL.lock() {
+        *current = url;
+    }
+    Ok(())
+}
+
+/// Check, store and switch to a backend.
+async fn apply(
+    app: &AppHandle,
+    url: Option<String>,
+    token: Option<String>,
+    device: Option<String>,
+) -> Result<RemoteBackend, String> {
+    if url.is_none() && required() {
+        return Err("This device can't run its own backend; set one to connect to".to_string());
+    }
+    if let Some(url) = &url {
+        let current = match &token {
+            Some(t) => Some(t.clone()),
+            None => self::token().await,
+        };
+        check(url, current.as_deref()).await?;
+    }
+    store(app, url.clone(), token, device).await?;
+    crate::audit::record(
+        "remote_backend_changed",
+        url.as_deref().unwrap_or("sidecar"),
+    );
+    crate::reconnect_backend(app).await?;
+    get_remote_backend().await
+}
+
+/// Called when the backend turns down the token: a backend lent by a paired
+/// device gets a new token (and port) when it restarts, so ask again.
+pub(crate) async fn refresh(app: &AppHandle) -> Option<String> {
+    let device = crate::settings::get(app).remote_backend_device?;
+    match crate::lan_sync::request_backend(app, &device).await {
+        Ok((url, token)) => {
+            let stored = store(app, Some(url), Some(token.clone()), Some(device)).await;
+            if let Err(e) = stored {
+                tracing::warn!("[RemoteBackend] {}", e);
+            }
+            Some(token)
+        }
+        Err(e) => {
+            tracing::warn!("[RemoteBackend] Couldn't renew access: {}", e);
+            None
+        }
+    }
+}
+
+/// Use the backend at `url` (checked first), with its app token; `None`
+/// goes back to the sidecar where there is one. A `token` of `None` keeps
+/// the stored one.
+#[tauri::command]
+pub(crate) async fn set_remote_backend(
+    app: AppHandle,
+    url: Option<String>,
+    token: Option<String>,
+) -> Result<RemoteBackend, String> {
+    let url = url
+        .filter(|u| !u.trim().is_empty())
+        .map(|u| normalize(&u))
+        .transpose()?;
+    let token = token.filter(|t| !t.trim().is_empty());
+    apply(&app, url, token, None).await
+}
+
+/// Use the backend of a device paired for sync (see `backend_share`), which
+/// sends its address and token over the sync channel.
+#[tauri::command]
+pub(crate) async fn connect_paired_backend(
+    app: AppHandle,
+    device_id: String,
+) -> Result<RemoteBackend, String> {
+    if crate::sync::peer(&app, &device_id).is_none() {
+        return Err("Pair with this device first".to_string());
+    }
+    let (url, token) = crate::lan_sync::request_backend(&app, &device_id).await?;
+    apply(&app, Some(url), Some(token), Some(device_id)).await
+}
@@ -0,0 +1,839 @@
+//! Sync through WebDAV or S3-compatible storage, end-to-end encrypted.
+//!
+//! The storage only ever holds ciphertext under opaque names. Under the
+//! configured prefix it keeps:
+//!
+//! - `keyinfo.json`: the Argon2 salt for the sync passphrase and a sealed
+//!   check value, so a device with the wrong passphrase is told so instead
+//!   of writing data nobody else can read;
+//! - `chunks/<id>`: pieces of notebook snapshots (see `sync`) of up to
+//!   `CHUNK_SIZE`, each sealed on its own with XChaCha20-Poly1305. The id is
+//!   a keyed hash of the plaintext, so an unchanged piece is uploaded once
+//!   and the storage can't test guesses about the contents;
+//! - `devices/<device id>`: each device's sealed manifest of the notebooks it
+//!   shares and the chunks that make them up.
+//!
+//! A device only ever writes its own manifest, so two devices syncing at once
+//! can't overwrite each other; `sync_now` takes in what other devices changed
+//! (conflicts are kept for `resolve_conflict`) and then publishes this device's
+//! shared notebooks. Chunks no manifest refers to any more are left in place.
+//! The storage password or S3 secret key and the passphrase live in the
+//! keychain.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::sync::{self, Applied, Conflict, Offer};
+use crate::vault::SealedNotebook;
+
+const SECRET_NAME: &str = "sync_remote_secret";
+const PASSPHRASE_NAME: &str = "sync_remote_passphrase";
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+const MIN_PASSPHRASE_LEN: usize = 8;
+const CHECK_VALUE: &[u8] = b"localbook-sync";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const S3_SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// The derived key, once the passphrase has been checked against the
+/// storage this session.
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RemoteKind {
+    Webdav,
+    S3,
+}
+
+fn default_prefix() -> String {
+    "localbook".to_string()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RemoteConfig {
+    pub kind: RemoteKind,
+    /// The WebDAV folder URL, or the S3 endpoint (addressed path-style).
+    pub url: String,
+    /// S3 only.
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 only; "us-east-1" when unset.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Folder (WebDAV) or key prefix (S3) for LocalBook's files.
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// The WebDAV user, or the S3 access key id.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Unix seconds.
+    #[serde(default)]
+    pub last_synced_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyInfo {
+    salt: String,
+    check: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RemoteNotebook {
+    #[serde(flatten)]
+    offer: Offer,
+    chunks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    device_id: String,
+    device_name: String,
+    /// Unix seconds.
+    updated_at: u64,
+    notebooks: Vec<RemoteNotebook>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RemoteSyncReport {
+    /// Notebooks published (new or changed).
+    uploaded: usize,
+    /// Notebooks taken from other devices.
+    downloaded: usize,
+    /// All unresolved conflicts, including ones found in this run.
+    conflicts: Vec<Conflict>,
+}
+
+fn b64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn unb64(text: &str) -> Result<Vec<u8>, String> {
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(|_| "Damaged sync data on the storage".to_string())
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Damaged sync data on the storage".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Sync data on the storage can't be decrypted".to_string())
+}
+
+fn chunk_id(key: &[u8; 32], piece: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(piece);
+    format!("{:x}", hasher.finalize())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, as SigV4
+/// wants for query values.
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Text between each `open` and the next `close` — enough XML for a
+/// PROPFIND or ListObjectsV2 answer.
+fn between<'a>(xml: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(open) {
+        rest = &rest[start + open.len()..];
+        let end = rest.find(close).unwrap_or(rest.len());
+        let value = rest[..end].trim();
+        if !value.is_empty() {
+            found.push(value);
+        }
+        rest = &rest[end..];
+    }
+    found
+}
+
+fn validate(mut config: RemoteConfig) -> Result<RemoteConfig, String> {
+    let url = reqwest::Url::parse(config.url.trim())
+        .map_err(|_| format!("Invalid storage URL: {}", config.url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("The storage URL must start with http:// or https://".to_string());
+    }
+    config.url = url.to_string().trim_end_matches('/').to_string();
+    config.prefix = config.prefix.trim().trim_matches('/').to_string();
+    let valid_prefix = !config.prefix.is_empty()
+        && config.prefix.split('/').all(|seg| {
+            !seg.is_empty()
+                && seg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        });
+    if !valid_prefix {
+        return Err("The folder may only use letters, digits, '-', '_', '.' and '/'".to_string());
+    }
+    if config.kind == RemoteKind::S3 {
+        let bucket = config.bucket.as_deref().map(str::trim).unwrap_or_default();
+        if bucket.is_empty()
+            || !bucket
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-.".contains(c))
+        {
+            return Err("Enter a valid S3 bucket name".to_string());
+        }
+        config.bucket = Some(bucket.to_string());
+        if config
+            .username
+            .as_deref()
+            .is_none_or(|u| u.trim().is_empty())
+        {
+            return Err("Enter the S3 access key id".to_string());
+        }
+    }
+    Ok(config)
+}
+
+struct Store {
+    config: RemoteConfig,
+    client: reqwest::Client,
+    secret: Option<String>,
+}
+
+impl Store {
+    fn new(config: RemoteConfig, secret: Option<String>) -> Result<Self, String> {
+        crate::privacy::guard(&config.url, "Remote sync")?;
+        let client = crate::proxy::client()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("HTTP client build failed: {}", e))?;
+        Ok(Self {
+            config,
+            client,
+            secret,
+        })
+    }
+
+    fn url(&self, path: &str, query: &str) -> String {
+        let path = format!("{}/{}", self.config.prefix, path);
+        let path = path.trim_end_matches('/');
+        let mut url = match self.config.kind {
+            RemoteKind::Webdav => format!("{}/{}", self.config.url, path),
+            RemoteKind::S3 => format!(
+                "{}/{}/{}",
+                self.config.url,
+                self.config.bucket.as_deref().unwrap_or_default(),
+                path
+            ),
+        };
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(query);
+        }
+        url
+    }
+
+    /// AWS Signature Version 4 headers for an S3 request.
+    fn sign(
+        &self,
+        method: &Method,
+        url: &str,
+        body: &[u8],
+    ) -> Result<Vec<(&'static str, String)>, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+        let host = match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("The storage URL has no host".to_string()),
+        };
+        let region = self
+            .config
+            .region
+            .as_deref()
+            .filter(|r| !r.is_empty())
+            .unwrap_or("us-east-1");
+        let access_key = self.config.username.as_deref().unwrap_or_default();
+        let secret = self.secret.as_deref().ok_or("Enter the S3 secret key")?;
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = format!("{:x}", Sha256::digest(body));
+        let canonical = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            parsed.path(),
+            parsed.query().unwrap_or_default(),
+            host,
+            payload_hash,
+            amz_date,
+            S3_SIGNED_HEADERS,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date,
+            scope,
+            Sha256::digest(canonical.as_bytes())
+        );
+        let mut signing_key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+        for part in [region, "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes());
+        }
+        let signature: String = hmac_sha256(&signing_key, string_to_sign.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok(vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    access_key, scope, S3_SIGNED_HEADERS, signature
+                ),
+            ),
+        ])
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, String> {
+        let url = self.url(path, query);
+        let mut req = self.client.request(method.clone(), &url);
+        match self.config.kind {
+            RemoteKind::Webdav => {
+                if let Some(user) = &self.config.username {
+                    req = req.basic_auth(user, self.secret.as_deref());
+                }
+            }
+            RemoteKind::S3 => {
+                for (name, value) in self.sign(&method, &url, &body)? {
+                    req = req.header(name, value);
+                }
+            }
+        }
+        if method == Method::PUT {
+            req = req.body(body);
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| format!("Storage request failed: {}", e))?;
+        match resp.status().as_u16() {
+            401 | 403 => Err("The storage refused the credentials".to_string()),
+            _ => Ok(resp),
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        let resp = self.send(Method::GET, path, "", Vec::new()).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(format!("Reading {} failed: HTTP {}", path, resp.status()));
+        }
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("Reading {} failed: {}", path, e))?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    async fn put(&self, path: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let resp = self.send(Method::PUT, path, "", bytes).await?;
+        if !resp.status().is_success() {
+            return Err(format!("Writing {} failed: HTTP {}", path, resp.status()));
+        }
+        Ok(())
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, String> {
+        let resp = self.send(Method::HEAD, path, "", Vec::new()).await?;
+        match resp.status() {
+            s if s.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            s => Err(format!("Checking {} failed: HTTP {}", path, s)),
+        }
+    }
+
+    /// Names of the files directly in `dir`.
+    async fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let names = match self.config.kind {
+            RemoteKind::Webdav => {
+                let propfind = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+                let url = format!("{}/", self.url(dir, ""));
+                let mut req = self.client.request(propfind, &url).header("Depth", "1");
+                if let Some(user) = &self.config.username {
+                    req = req.basic_auth(user, self.secret.as_deref());
+                }
+                let resp = req
+                    .send()
+                    .await
+                    .map_err(|e| format!("Storage request failed: {}", e))?;
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(Vec::new());
+                }
+                if !resp.status().is_success() {
+                    return Err(format!("Listing {} failed: HTTP {}", dir, resp.status()));
+                }
+                let xml = resp.text().await.map_err(|e| e.to_string())?;
+                between(&xml, "href>", "<")
+                    .into_iter()
+                    .filter_map(|href| href.trim_end_matches('/').rsplit('/').next())
+                    .filter(|name| *name != dir)
+                    .map(String::from)
+                    .collect()
+            }
+            RemoteKind::S3 => {
+                let prefix = format!("{}/{}/", self.config.prefix, dir);
+                let query = format!("list-type=2&prefix={}", uri_encode(&prefix));
+                let bucket_root = format!(
+                    "{}/{}",
+                    self.config.url,
+                    self.config.bucket.as_deref().unwrap_or_default()
+                );
+                let url = format!("{}?{}", bucket_root, query);
+                let mut req = self.client.get(&url);
+                for (name, value) in self.sign(&Method::GET, &url, &[])? {
+                    req = req.header(name, value);
+                }
+                let resp = req
+                    .send()
+                    .await
+                    .map_err(|e| format!("Storage request failed: {}", e))?;
+                if !resp.status().is_success() {
+                    return Err(format!("Listing {} failed: HTTP {}", dir, resp.status()));
+                }
+                let xml = resp.text().await.map_err(|e| e.to_string())?;
+                between(&xml, "<Key>", "</Key>")
+                    .into_iter()
+                    .filter_map(|key| key.strip_prefix(&prefix))
+                    .filter(|name| !name.is_empty() && !name.contains('/'))
+                    .map(String::from)
+                    .collect()
+            }
+        };
+        Ok(names)
+    }
+
+    /// Create the folders WebDAV needs before files can go in them.
+    async fn prepare(&self) -> Result<(), String> {
+        if self.config.kind != RemoteKind::Webdav {
+            return Ok(());
+        }
+        let mkcol = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        let mut folders = Vec::new();
+        let mut path = String::new();
+        for segment in self.config.prefix.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(segment);
+            folders.push(format!("{}/{}/", self.config.url, path));
+        }
+        for dir in ["chunks", "devices"] {
+            folders.push(format!("{}/", self.url(dir, "")));
+        }
+        for folder in folders {
+            let mut req = self.client.request(mkcol.clone(), &folder);
+            if let Some(user) = &self.config.username {
+                req = req.basic_auth(user, self.secret.as_deref());
+            }
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| format!("Storage request failed: {}", e))?;
+            // 405: already exists.
+            match resp.status().as_u16() {
+                200..=299 | 405 => {}
+                401 | 403 => return Err("The storage refused the credentials".to_string()),
+                s => {
+                    return Err(format!(
+                        "Creating a folder on the storage failed: HTTP {}",
+                        s
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn keychain_get(name: &'static str) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::get(name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The key for `passphrase`, checked against the storage's `keyinfo.json` —
+/// or, on storage nobody has synced to yet, written there.
+async fn unlock(store: &Store, passphrase: String) -> Result<[u8; 32], String> {
+    let info = match store.get("keyinfo.json").await? {
+        Some(bytes) => Some(
+            serde_json::from_slice::<KeyInfo>(&bytes)
+                .map_err(|_| "Damaged keyinfo.json on the storage".to_string())?,
+        ),
+        None => None,
+    };
+    let salt = match &info {
+        Some(info) => unb64(&info.salt)?,
+        None => {
+            let mut salt = vec![0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            salt
+        }
+    };
+    let derive_salt = salt.clone();
+    let key = tauri::async_runtime::spawn_blocking(move || {
+        crate::vault::derive_key(&passphrase, &derive_salt)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    match info {
+        Some(info) => {
+            if open(&key, &unb64(&info.check)?).ok().as_deref() != Some(CHECK_VALUE) {
+                return Err(
+                    "The sync passphrase doesn't match the one this storage was set up with"
+                        .to_string(),
+                );
+            }
+        }
+        None => {
+            let info = KeyInfo {
+                salt: b64(&salt),
+                check: b64(&seal(&key, CHECK_VALUE)?),
+            };
+            store
+                .put(
+                    "keyinfo.json",
+                    serde_json::to_vec_pretty(&info).map_err(|e| e.to_string())?,
+                )
+                .await?;
+            tracing::info!("[RemoteSync] Set up new storage");
+        }
+    }
+    Ok(key)
+}
+
+async fn key(store: &Store) -> Result<[u8; 32], String> {
+    if let Some(key) = *KEY.lock().unwrap_or_else(|e| e.into_inner()) {
+        return Ok(key);
+    }
+    let passphrase = keychain_get(PASSPHRASE_NAME)
+        .await?
+        .ok_or("Enter the sync passphrase again")?;
+    let key = unlock(store, passphrase).await?;
+    *KEY.lock().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    Ok(key)
+}
+
+async fn read_manifest(
+    store: &Store,
+    key: &[u8; 32],
+    device: &str,
+) -> Result<Option<Manifest>, String> {
+    let Some(sealed) = store.get(&format!("devices/{}", device)).await? else {
+        return Ok(None);
+    };
+    let manifest = serde_json::from_slice(&open(key, &sealed)?)
+        .map_err(|_| "Damaged device manifest on the storage".to_string())?;
+    Ok(Some(manifest))
+}
+
+/// Take in what other devices changed. Returns how many notebooks were
+/// imported.
+async fn download(
+    app: &AppHandle,
+    store: &Store,
+    key: &[u8; 32],
+    own_id: &str,
+) -> Result<usize, String> {
+    let mut downloaded = 0;
+    for device in store.list("devices").await? {
+        if device == own_id {
+            continue;
+        }
+        let manifest = match read_manifest(store, key, &device).await {
+            Ok(Some(m)) => m,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("[RemoteSync] Skipping device {}: {}", device, e);
+                continue;
+            }
+        };
+        let offers = manifest.notebooks.iter().map(|n| n.offer.clone()).collect();
+        let wanted = sync::wanted(app, &device, offers);
+        let total = wanted.len();
+        for (i, offer) in wanted.iter().enumerate() {
+            sync::progress(app, &device, &offer.title, i, total);
+            let Some(entry) = manifest.notebooks.iter().find(|n| n.offer.id == offer.id) else {
+                continue;
+            };
+            let mut bytes = Vec::new();
+            for chunk in &entry.chunks {
+                let sealed = store
+                    .get(&format!("chunks/{}", chunk))
+                    .await?
+                    .ok_or_else(|| {
+                        format!("A piece of {} is missing from the storage", offer.title)
+                    })?;
+                // Chunks are named by a keyed hash of their contents, so one
+                // swapped for another sealed under the same key shows here.
+                let piece = open(key, &sealed)?;
+                if chunk_id(key, &piece) != *chunk {
+                    return Err(format!(
+                        "A piece of {} on the storage doesn't match",
+                        offer.title
+                    ));
+                }
+                bytes.extend(piece);
+            }
+            let notebook: SealedNotebook = serde_json::from_slice(&bytes)
+                .map_err(|_| format!("{} is damaged on the storage", offer.title))?;
+            if let Applied::Imported = sync::apply(app, &device, offer, &notebook).await? {
+                downloaded += 1;
+            }
+        }
+        sync::progress(app, &device, "", total, total);
+    }
+    Ok(downloaded)
+}
+
+/// Publish this device's shared notebooks. Returns how many were new or
+/// changed.
+async fn upload(
+    app: &AppHandle,
+    store: &Store,
+    key: &[u8; 32],
+    own_id: &str,
+) -> Result<usize, String> {
+    let previous = read_manifest(store, key, own_id).await.ok().flatten();
+    let offers = sync::offers(app).await;
+    let total = offers.len();
+    let mut uploaded = 0;
+    let mut notebooks = Vec::new();
+    for (i, offer) in offers.into_iter().enumerate() {
+        let unchanged = previous.as_ref().and_then(|m| {
+            m.notebooks
+                .iter()
+                .find(|n| n.offer.id == offer.id && n.offer.fingerprint == offer.fingerprint)
+        });
+        if let Some(entry) = unchanged {
+            notebooks.push(RemoteNotebook {
+                chunks: entry.chunks.clone(),
+                offer,
+            });
+            continue;
+        }
+        sync::progress(app, own_id, &offer.title, i, total);
+        let snapshot = sync::snapshot(app, &offer.id, &offer.fingerprint).await?;
+        let bytes = serde_json::to_vec(&snapshot).map_err(|e| e.to_string())?;
+        let mut chunks = Vec::new();
+        for piece in bytes.chunks(CHUNK_SIZE) {
+            let id = chunk_id(key, piece);
+            let path = format!("chunks/{}", id);
+            if !store.exists(&path).await? {
+                store.put(&path, seal(key, piece)?).await?;
+            }
+            chunks.push(id);
+        }
+        notebooks.push(RemoteNotebook { offer, chunks });
+        uploaded += 1;
+    }
+    let manifest = Manifest {
+        device_id: own_id.to_string(),
+        device_name: sync::device_name(app),
+        updated_at: crate::models::now_secs(),
+        notebooks,
+    };
+    let plaintext = serde_json::to_vec(&manifest).map_err(|e| e.to_string())?;
+    store
+        .put(&format!("devices/{}", own_id), seal(key, &plaintext)?)
+        .await?;
+    sync::progress(app, own_id, "", total, total);
+    Ok(uploaded)
+}
+
+/// Set up remote sync, or turn it off with `config` None. `secret` (the
+/// WebDAV password or S3 secret key) and `passphrase` may be left out to
+/// keep the ones already stored.
+#[tauri::command]
+pub(crate) async fn configure_sync(
+    app: AppHandle,
+    config: Option<RemoteConfig>,
+    secret: Option<String>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    *KEY.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    let Some(config) = config else {
+        for name in [SECRET_NAME, PASSPHRASE_NAME] {
+            let _ =
+                tauri::async_runtime::spawn_blocking(move || crate::secrets::delete(name)).await;
+        }
+        crate::settings::update(&app, |s| s.sync.remote = None)?;
+        crate::audit::record("remote_sync_disabled", "");
+        return Ok(());
+    };
+    let mut config = validate(config)?;
+    if let Some(p) = &passphrase {
+        if p.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(format!(
+                "Passphrase must be at least {} characters",
+                MIN_PASSPHRASE_LEN
+            ));
+        }
+    }
+    let secret = match secret.filter(|s| !s.is_empty()) {
+        Some(s) => Some(s),
+        None => keychain_get(SECRET_NAME).await?,
+    };
+    let passphrase = match passphrase {
+        Some(p) => p,
+        None => keychain_get(PASSPHRASE_NAME)
+            .await?
+            .ok_or("Choose a sync passphrase")?,
+    };
+
+    // Check everything works before anything is saved.
+    let store = Store::new(config.clone(), secret.clone())?;
+    store.prepare().await?;
+    let key = unlock(&store, passphrase.clone()).await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(secret) = secret {
+            crate::secrets::set(SECRET_NAME, &secret)?;
+        }
+        crate::secrets::set(PASSPHRASE_NAME, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    config.last_synced_at = None;
+    let host = reqwest::Url::parse(&config.url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_default();
+    crate::settings::update(&app, |s| s.sync.remote = Some(config.clone()))?;
+    *KEY.lock().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    tracing::info!("[RemoteSync] Configured");
+    crate::audit::record("remote_sync_configured", host);
+    Ok(())
+}
+
+/// Take in other devices' changes from the storage, then publish this
+/// device's shared notebooks.
+#[tauri::command]
+pub(crate) async fn sync_now(app: AppHandle) -> Result<RemoteSyncReport, String> {
+    let config = crate::settings::get(&app)
+        .sync
+        .remote
+        .ok_or("Remote sync isn't set up")?;
+    let _running = RUNNING
+        .try_lock()
+        .map_err(|_| "A sync is already running".to_string())?;
+    sync::begin(&app, "sync storage");
+    let result = async {
+        let secret = keychain_get(SECRET_NAME).await?;
+        let store = Store::new(config, secret)?;
+        let key = key(&store).await?;
+        let own_id = sync::device_id(&app);
+        let downloaded = download(&app, &store, &key, &own_id).await?;
+        let uploaded = upload(&app, &store, &key, &own_id).await?;
+        Ok::<_, String>((downloaded, uploaded))
+    }
+    .await;
+    sync::finish(&app, result.as_ref().err());
+    let (downloaded, uploaded) = result?;
+
+    let settings = crate::settings::update(&app, |s| {
+        if let Some(remote) = s.sync.remote.as_mut() {
+            remote.last_synced_at = Some(crate::models::now_secs());
+        }
+    })?;
+    tracing::info!(
+        "[RemoteSync] {} downloaded, {} uploaded, {} conflicts",
+        downloaded,
+        uploaded,
+        settings.sync.conflicts.len()
+    );
+    Ok(RemoteSyncReport {
+        uploaded,
+        downloaded,
+        conflicts: settings.sync.conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_round_trips() {
+        let key = [7u8; 32];
+        let sealed = seal(&key, b"notebook").unwrap();
+        assert_ne!(&sealed[NONCE_LEN..], b"notebook");
+        assert_eq!(open(&key, &sealed).unwrap(), b"notebook");
+        // A fresh nonce each time.
+        assert_ne!(seal(&key, b"notebook").unwrap(), sealed);
+    }
+
+    #[test]
+    fn open_rejects_wrong_key_and_damage() {
+        let key = [7u8; 32];
+        let mut sealed = seal(&key, b"notebook").unwrap();
+        assert!(open(&[8u8; 32], &sealed).is_err());
+        assert!(open(&key, &sealed[..NONCE_LEN - 1]).is_err());
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(open(&key, &sealed).is_err());
+    }
+
+    #[test]
+    fn chunk_id_depends_on_key_and_piece() {
+        let id = chunk_id(&[1u8; 32], b"piece");
+        assert_eq!(id.len(), 64);
+        assert_eq!(id, chunk_id(&[1u8; 32], b"piece"));
+        assert_ne!(id, chunk_id(&[2u8; 32], b"piece"));
+        assert_ne!(id, chunk_id(&[1u8; 32], b"other"));
+    }
+}
@@ -0,0 +1,213 @@
+//! Local cross-encoder reranking (ONNX, in-process).
+//!
+//! A cross-encoder scores each (query, passage) pair jointly, which orders
+//! retrieved chunks far better than embedding similarity alone and is cheap
+//! enough on a CPU for the handful of candidates a question retrieves. The
+//! model is ms-marco-MiniLM-L-6-v2 (quantized, ~23 MB), fetched into
+//! `<models>/reranker/` with `download_reranker`.
+//!
+//! The engine is only compiled with the `rerank` cargo feature (it links ONNX
+//! Runtime); without it `rerank` reports that reranking isn't available and
+//! callers keep the retrieval order.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+const MODEL_NAME: &str = "ms-marco-MiniLM-L-6-v2";
+const MODEL_URL: &str =
+    "https://huggingface.co/Xenova/ms-marco-MiniLM-L-6-v2/resolve/main/onnx/model_quantized.onnx";
+const TOKENIZER_URL: &str =
+    "https://huggingface.co/Xenova/ms-marco-MiniLM-L-6-v2/resolve/main/tokenizer.json";
+
+#[derive(Serialize)]
+pub(crate) struct RerankerStatus {
+    model: String,
+    installed: bool,
+    /// Whether this build includes the ONNX engine.
+    engine_available: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RankedPassage {
+    /// Index into the `passages` argument.
+    index: usize,
+    /// Relevance in 0..1 (sigmoid of the model's logit).
+    score: f32,
+}
+
+fn reranker_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::models::models_dir(app)?.join("reranker");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn model_files(dir: &Path) -> (PathBuf, PathBuf) {
+    (
+        dir.join(format!("{}.onnx", MODEL_NAME)),
+        dir.join(format!("{}.tokenizer.json", MODEL_NAME)),
+    )
+}
+
+#[cfg(feature = "rerank")]
+mod engine {
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    use ort::session::Session;
+    use ort::value::Tensor;
+    use tokenizers::{Tokenizer, TruncationParams};
+
+    use super::RankedPassage;
+
+    /// Pairs scored per forward pass.
+    const BATCH_SIZE: usize = 32;
+    /// The model's maximum sequence length.
+    const MAX_TOKENS: usize = 512;
+
+    static LOADED: Mutex<Option<(PathBuf, Session, Tokenizer)>> = Mutex::new(None);
+
+    fn load(
+        model: &Path,
+        tokenizer: &Path,
+        threads: usize,
+    ) -> Result<(Session, Tokenizer), String> {
+        let session = Session::builder()
+            .and_then(|b| b.with_intra_threads(threads))
+            .and_then(|b| b.commit_from_file(model))
+            .map_err(|e| format!("Failed to load {}: {}", model.display(), e))?;
+        let mut tok = Tokenizer::from_file(tokenizer)
+            .map_err(|e| format!("Failed to load {}: {}", tokenizer.display(), e))?;
+        tok.with_truncation(Some(TruncationParams {
+            max_length: MAX_TOKENS,
+            ..Default::default()
+        }))
+        .map_err(|e| e.to_string())?;
+        tok.with_padding(Some(Default::default()));
+        Ok((session, tok))
+    }
+
+    fn tensor(shape: [usize; 2], data: Vec<i64>) -> Result<Tensor<i64>, String> {
+        Tensor::from_array((shape, data)).map_err(|e| e.to_string())
+    }
+
+    /// Blocking: loads the model if needed and scores every passage.
+    pub(super) fn rerank(
+        model: &Path,
+        tokenizer: &Path,
+        threads: usize,
+        query: &str,
+        passages: &[String],
+    ) -> Result<Vec<RankedPassage>, String> {
+        let mut loaded = LOADED.lock().map_err(|e| e.to_string())?;
+        if loaded.as_ref().map(|(p, _, _)| p.as_path()) != Some(model) {
+            let (session, tok) = load(model, tokenizer, threads)?;
+            *loaded = Some((model.to_path_buf(), session, tok));
+        }
+        let (_, session, tok) = loaded.as_mut().ok_or("Reranker not loaded")?;
+
+        let mut ranked = Vec::with_capacity(passages.len());
+        for (batch_index, batch) in passages.chunks(BATCH_SIZE).enumerate() {
+            let pairs: Vec<(&str, &str)> = batch.iter().map(|p| (query, p.as_str())).collect();
+            let encodings = tok
+                .encode_batch(pairs, true)
+                .map_err(|e| format!("Tokenization failed: {}", e))?;
+            let len = encodings.first().map(|e| e.len()).unwrap_or(0);
+            let shape = [encodings.len(), len];
+            let flat = |f: fn(&tokenizers::Encoding) -> &[u32]| -> Vec<i64> {
+                encodings
+                    .iter()
+                    .flat_map(|e| f(e).iter().map(|&v| i64::from(v)))
+                    .collect()
+            };
+            let outputs = session
+                .run(ort::inputs! {
+                    "input_ids" => tensor(shape, flat(|e| e.get_ids()))?,
+                    "attention_mask" => tensor(shape, flat(|e| e.get_attention_mask()))?,
+                    "token_type_ids" => tensor(shape, flat(|e| e.get_type_ids()))?,
+                })
+                .map_err(|e| format!("Reranker inference failed: {}", e))?;
+            // logits: [batch, 1]
+            let (_, logits) = outputs["logits"]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| e.to_string())?;
+            for (i, logit) in logits.iter().enumerate() {
+                ranked.push(RankedPassage {
+                    index: batch_index * BATCH_SIZE + i,
+                    score: 1.0 / (1.0 + (-logit).exp()),
+                });
+            }
+        }
+        Ok(ranked)
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_reranker_status(app: AppHandle) -> Result<RerankerStatus, String> {
+    let (model, tokenizer) = model_files(&reranker_dir(&app)?);
+    Ok(RerankerStatus {
+        model: MODEL_NAME.to_string(),
+        installed: model.exists() && tokenizer.exists(),
+        engine_available: cfg!(feature = "rerank"),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn download_reranker(app: AppHandle) -> Result<(), String> {
+    let (model, tokenizer) = model_files(&reranker_dir(&app)?);
+    let client = crate::proxy::client()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    tracing::info!("[Rerank] Downloading {}", MODEL_NAME);
+    for (url, dest) in [(TOKENIZER_URL, tokenizer), (MODEL_URL, model)] {
+        let part = PathBuf::from(format!("{}.part", dest.display()));
+        crate::models::fetch_to_part(&app, &client, MODEL_NAME, url, None, &part).await?;
+        tokio::fs::rename(&part, &dest)
+            .await
+            .map_err(|e| format!("Failed to move {} into place: {}", dest.display(), e))?;
+    }
+    tracing::info!("[Rerank] {} ready", MODEL_NAME);
+    Ok(())
+}
+
+/// Score `passages` against `query`, best first. `top_k` keeps only the best
+/// few.
+#[tauri::command]
+pub(crate) async fn rerank(
+    app: AppHandle,
+    query: String,
+    passages: Vec<String>,
+    top_k: Option<usize>,
+) -> Result<Vec<RankedPassage>, String> {
+    let (model, tokenizer) = model_files(&reranker_dir(&app)?);
+    if !model.exists() || !tokenizer.exists() {
+        return Err("Reranker model is not downloaded".to_string());
+    }
+    if passages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    #[cfg(feature = "rerank")]
+    {
+        let mut ranked = tauri::async_runtime::spawn_blocking(move || {
+            let threads = crate::hardware::tuning(&app).threads;
+            engine::rerank(&model, &tokenizer, threads, &query, &passages)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(k) = top_k {
+            ranked.truncate(k);
+        }
+        Ok(ranked)
+    }
+    #[cfg(not(feature = "rerank"))]
+    {
+        let _ = (query, top_k);
+        Err("This build does not include the built-in reranker".to_string())
+    }
+}
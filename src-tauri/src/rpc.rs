@@ -0,0 +1,480 @@
+//! A local automation interface, so launchers and scripts (Raycast, Alfred,
+//! AutoHotkey…) can drive LocalBook: JSON-RPC 2.0, one request per line.
+//!
+//! Two ways in. With the automation setting on, the running app listens on a
+//! socket only this user can open — `rpc.sock` in the shell's data dir on
+//! macOS and Linux, the `\\.\pipe\localbook-rpc-<user>` pipe on Windows.
+//! And `localbook rpc` speaks the same protocol on stdin and stdout, for
+//! tools that would rather start a process (see `cli`).
+//!
+//! Only a safe subset is offered — nothing that deletes, changes settings or
+//! goes out to the network:
+//!
+//! - `notebooks.list`
+//! - `notebooks.search {query, notebook?, limit?}`: matching passages, with
+//!   no answer generated.
+//! - `notebooks.export {notebook, format?}`: the notebook as Markdown (the
+//!   default) or HTML text.
+//! - `notebooks.ask {question, notebook}`: an answer from the notebook's
+//!   documents, with its citations. Can take minutes on a slow model.
+//! - `notes.capture {text, title?, notebook?}`: a note, in the primary
+//!   notebook unless one is given; returns its source id.
+//! - `sources.import {paths, notebook}`: files and folders, queued as jobs
+//!   (see `jobs`); returns the job ids. Paths must be absolute and already
+//!   granted — chosen in a file dialog, dropped on a window or given on the
+//!   command line (see `scope`); nothing is granted from here.
+//! - `jobs.get {id}`
+//!
+//! `notebook` is an id or an exact title. Requests on the socket are refused
+//! while the app is locked. `localbook rpc`, like the other command-line
+//! runs, doesn't use the app lock (see `lock`): it answers whoever can start
+//! processes as this user, who could read the library directly anyway.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::backend_api;
+
+#[cfg(unix)]
+const SOCKET_FILE: &str = "rpc.sock";
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+/// Answers come from the model and can be slow on modest hardware.
+const ASK_TIMEOUT: Duration = Duration::from_secs(600);
+pub(crate) const DEFAULT_SEARCH_LIMIT: u64 = 10;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Anything that went wrong in the app or the backend.
+const APP_ERROR: i64 = -32000;
+
+static SERVER: Mutex<Option<tauri::async_runtime::JoinHandle<()>>> = Mutex::new(None);
+
+/// A JSON-RPC error code and message.
+struct RpcError(i64, String);
+
+impl From<String> for RpcError {
+    fn from(e: String) -> Self {
+        RpcError(APP_ERROR, e)
+    }
+}
+
+impl From<backend_api::ApiError> for RpcError {
+    fn from(e: backend_api::ApiError) -> Self {
+        RpcError(APP_ERROR, e.to_string())
+    }
+}
+
+fn invalid_params(message: &str) -> RpcError {
+    RpcError(INVALID_PARAMS, message.to_string())
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params[name]
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| invalid_params(&format!("\"{}\" is required", name)))
+}
+
+/// The id of the notebook with `name` as its id or title.
+pub(crate) async fn find_notebook(name: &str) -> Result<String, String> {
+    let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    notebooks["notebooks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|n| n["id"].as_str() == Some(name) || n["title"].as_str() == Some(name))
+        .and_then(|n| n["id"].as_str())
+        .map(String::from)
+        .ok_or_else(|| format!("No notebook \"{}\"", name))
+}
+
+async fn list_notebooks() -> Result<Value, RpcError> {
+    let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT).await?;
+    Ok(notebooks["notebooks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|n| {
+            json!({
+                "id": n["id"],
+                "title": n["title"],
+                "source_count": n["source_count"],
+                "updated_at": n["updated_at"],
+            })
+        })
+        .collect())
+}
+
+/// Passages matching `query` in one notebook or all of them.
+pub(crate) async fn search_passages(
+    query: &str,
+    notebook: Option<&str>,
+    limit: u64,
+) -> Result<Value, String> {
+    let notebook_ids = match notebook {
+        Some(name) => Some(vec![find_notebook(name).await?]),
+        None => None,
+    };
+    let found = backend_api::post_json(
+        "/notebooks/search",
+        &json!({ "query": query, "notebook_ids": notebook_ids, "top_k": limit }),
+        API_TIMEOUT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(found["results"].clone())
+}
+
+async fn search(params: &Value) -> Result<Value, RpcError> {
+    let query = str_param(params, "query")?;
+    let limit = params["limit"].as_u64().unwrap_or(DEFAULT_SEARCH_LIMIT);
+    Ok(search_passages(query, params["notebook"].as_str(), limit).await?)
+}
+
+async fn export(params: &Value) -> Result<Value, RpcError> {
+    let notebook_id = find_notebook(str_param(params, "notebook")?).await?;
+    let format = params["format"].as_str().unwrap_or("markdown");
+    if !matches!(format, "markdown" | "html") {
+        return Err(invalid_params("\"format\" is markdown or html"));
+    }
+    let content = backend_api::post_text(
+        "/export/notebook",
+        &json!({ "notebook_id": notebook_id, "format": format }),
+        API_TIMEOUT,
+    )
+    .await?;
+    Ok(json!({ "format": format, "content": content }))
+}
+
+async fn ask(params: &Value) -> Result<Value, RpcError> {
+    let question = str_param(params, "question")?;
+    let notebook_id = find_notebook(str_param(params, "notebook")?).await?;
+    let answer = backend_api::post_json(
+        "/chat/query",
+        &json!({ "notebook_id": notebook_id, "question": question }),
+        ASK_TIMEOUT,
+    )
+    .await?;
+    let citations: Vec<Value> = answer["citations"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|c| {
+            json!({
+                "number": c["number"],
+                "source_id": c["source_id"],
+                "filename": c["filename"],
+                "text": c["snippet"],
+            })
+        })
+        .collect();
+    Ok(json!({ "answer": answer["answer"], "citations": citations }))
+}
+
+/// The primary notebook, where captures go when none is named.
+async fn inbox() -> Result<String, String> {
+    let primary = backend_api::get_json("/settings/primary-notebook", API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    primary["primary_notebook_id"]
+        .as_str()
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .ok_or_else(|| "No primary notebook is set; name one with \"notebook\"".to_string())
+}
+
+async fn capture(params: &Value) -> Result<Value, RpcError> {
+    let text = str_param(params, "text")?;
+    let notebook_id = match params["notebook"].as_str() {
+        Some(name) => find_notebook(name).await?,
+        None => inbox().await?,
+    };
+    let title = params["title"]
+        .as_str()
+        .filter(|t| !t.trim().is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| {
+            let first = text.lines().find(|l| !l.trim().is_empty()).unwrap_or(text);
+            first.trim().chars().take(60).collect()
+        });
+    let note = backend_api::post_json(
+        &format!("/sources/{}/note", notebook_id),
+        &json!({ "title": title, "content": text }),
+        API_TIMEOUT,
+    )
+    .await?;
+    Ok(json!({
+        "notebook_id": notebook_id,
+        "source_id": note["source_id"],
+        "title": note["filename"],
+    }))
+}
+
+async fn import(app: &AppHandle, params: &Value) -> Result<Value, RpcError> {
+    let paths: Vec<PathBuf> = params["paths"]
+        .as_array()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| invalid_params("\"paths\" is a list of files or folders"))?
+        .iter()
+        .map(|p| p.as_str().map(PathBuf::from).filter(|p| p.is_absolute()))
+        .collect::<Option<_>>()
+        .ok_or_else(|| invalid_params("Paths must be absolute"))?;
+    let notebook_id = find_notebook(str_param(params, "notebook")?).await?;
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if !path.exists() {
+            return Err(format!("{} doesn't exist", path.display()).into());
+        }
+        let path = crate::scope::check(app, path)?;
+        crate::cli::collect(&path, &mut files)?;
+    }
+    let mut queued = Vec::new();
+    for file in files {
+        let spec = crate::jobs::JobSpec::Import {
+            notebook_id: notebook_id.clone(),
+            path: file.to_string_lossy().to_string(),
+        };
+        let job = crate::jobs::enqueue(app, spec, None)?;
+        let job = serde_json::to_value(job).map_err(|e| e.to_string())?;
+        queued.push(json!({ "path": file, "job_id": job["id"], "state": job["state"] }));
+    }
+    Ok(Value::Array(queued))
+}
+
+async fn call(app: &AppHandle, method: &str, params: &Value) -> Result<Value, RpcError> {
+    if crate::lock::is_locked() {
+        return Err("LocalBook is locked".to_string().into());
+    }
+    match method {
+        "notebooks.list" => list_notebooks().await,
+        "notebooks.search" => search(params).await,
+        "notebooks.export" => export(params).await,
+        "notebooks.ask" => ask(params).await,
+        "notes.capture" => capture(params).await,
+        "sources.import" => import(app, params).await,
+        "jobs.get" => {
+            let id = str_param(params, "id")?.to_string();
+            let job = crate::jobs::get_job(id).await?;
+            Ok(serde_json::to_value(job).map_err(|e| e.to_string())?)
+        }
+        _ => Err(RpcError(METHOD_NOT_FOUND, format!("No method {}", method))),
+    }
+}
+
+/// One request from another front end on the same methods, such as the
+/// AppleScript commands (see `scripting`).
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) async fn call_method(
+    app: &AppHandle,
+    method: &str,
+    params: &Value,
+) -> Result<Value, String> {
+    call(app, method, params).await.map_err(|RpcError(_, e)| e)
+}
+
+fn error_response(id: Value, RpcError(code, message): RpcError) -> String {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }).to_string()
+}
+
+/// Answer one request line; None for a notification (no id) or a blank line.
+async fn handle_line(app: &AppHandle, line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError(PARSE_ERROR, e.to_string()),
+            ))
+        }
+    };
+    let id = request.get("id").cloned();
+    let method = match request["method"].as_str() {
+        Some(method) if request["jsonrpc"] == "2.0" => method,
+        _ => {
+            let e = RpcError(INVALID_REQUEST, "Not a JSON-RPC 2.0 request".to_string());
+            return Some(error_response(id.unwrap_or(Value::Null), e));
+        }
+    };
+    let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+    let result = if params.is_object() {
+        call(app, method, &params).await
+    } else {
+        Err(invalid_params("\"params\" must be an object"))
+    };
+    if let Err(RpcError(_, e)) = &result {
+        tracing::warn!("[RPC] {} failed: {}", method, e);
+    }
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+        Err(e) => error_response(id, e),
+    })
+}
+
+/// Answer requests on `stream` until the other end closes it.
+async fn serve_stream<S>(app: AppHandle, stream: S) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(response) = handle_line(&app, &line).await {
+            writer.write_all(response.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// `localbook rpc`: serve stdin and stdout until stdin closes.
+pub(crate) async fn serve_stdio(app: &AppHandle) -> Result<bool, String> {
+    let stdio = tokio::io::join(tokio::io::stdin(), tokio::io::stdout());
+    serve_stream(app.clone(), stdio)
+        .await
+        .map_err(|e| format!("Failed to read requests: {}", e))?;
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn socket_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::data_dir(app)?.join(SOCKET_FILE))
+}
+
+#[cfg(unix)]
+async fn listen(app: AppHandle) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path(&app)?;
+    // Left by a run that didn't exit cleanly.
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to listen on {}: {}", path.display(), e))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict {}: {}", path.display(), e))?;
+    tracing::info!("[RPC] Listening on {}", path.display());
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tauri::async_runtime::spawn(serve_stream(app.clone(), stream));
+            }
+            Err(e) => {
+                tracing::warn!("[RPC] {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn pipe_name() -> String {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    format!(r"\\.\pipe\localbook-rpc-{}", user)
+}
+
+#[cfg(windows)]
+async fn listen(app: AppHandle) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = pipe_name();
+    let mut options = ServerOptions::new();
+    options.reject_remote_clients(true);
+    let mut server = options
+        .first_pipe_instance(true)
+        .create(&name)
+        .map_err(|e| format!("Failed to open {}: {}", name, e))?;
+    options.first_pipe_instance(false);
+    tracing::info!("[RPC] Listening on {}", name);
+    loop {
+        if let Err(e) = server.connect().await {
+            tracing::warn!("[RPC] {}", e);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let next = options
+            .create(&name)
+            .map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        let connected = std::mem::replace(&mut server, next);
+        tauri::async_runtime::spawn(serve_stream(app.clone(), connected));
+    }
+}
+
+#[cfg(mobile)]
+async fn listen(_app: AppHandle) -> Result<(), String> {
+    Err("Not available on this platform".to_string())
+}
+
+fn start_server(app: &AppHandle) {
+    let app = app.clone();
+    let server = tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app).await {
+            tracing::warn!("[RPC] {}", e);
+        }
+    });
+    if let Some(old) = SERVER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .replace(server)
+    {
+        old.abort();
+    }
+}
+
+/// Stop listening and remove the socket. Also called on exit.
+pub(crate) fn stop(app: &AppHandle) {
+    if let Some(server) = SERVER.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        server.abort();
+        tracing::info!("[RPC] Stopped");
+    }
+    #[cfg(unix)]
+    if let Ok(path) = socket_path(app) {
+        let _ = std::fs::remove_file(path);
+    }
+    #[cfg(not(unix))]
+    let _ = app;
+}
+
+/// Start listening if the user turned automation on. Called from setup.
+pub(crate) fn start(app: &AppHandle) {
+    if crate::settings::get(app).automation_api {
+        start_server(app);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_automation_api(app: AppHandle) -> Result<bool, String> {
+    Ok(crate::settings::get(&app).automation_api)
+}
+
+#[tauri::command]
+pub(crate) async fn set_automation_api(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    if enabled && crate::remote_backend::required() {
+        return Err("Automation isn't available on this device".to_string());
+    }
+    crate::settings::update(&app, |s| s.automation_api = enabled)?;
+    if enabled {
+        start_server(&app);
+    } else {
+        stop(&app);
+    }
+    crate::audit::record(
+        "automation_api",
+        if enabled { "enabled" } else { "disabled" },
+    );
+    Ok(enabled)
+}
@@ -0,0 +1,308 @@
+//! Recurring background tasks — Collector runs, re-indexing, backups and
+//! cache pruning — on cron-style schedules.
+//!
+//! Each task has a schedule in settings: five cron fields (minute hour
+//! day-of-month month day-of-week, local time, with `*`, lists, ranges and
+//! `/step`) or one of `@hourly`, `@daily`, `@weekly`, `@monthly`. A due task
+//! enqueues a job, so it shows up in the job list with progress like any
+//! other. Runs missed while the app was closed or the machine asleep happen
+//! once on the next check rather than being skipped; the same goes for runs
+//! that fall due while background work is paused.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::jobs::JobSpec;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How far ahead `next_after` looks before giving up (e.g. "0 0 31 2 *").
+const HORIZON_MINUTES: i64 = 366 * 24 * 60;
+const CACHE_MAX_AGE_DAYS: u64 = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TaskKind {
+    /// Run the Collector for notebooks with automatic collection.
+    CollectFeeds,
+    /// Index sources that haven't been (failed or interrupted imports).
+    Reindex,
+    Backup,
+    PruneCache,
+}
+
+const TASKS: &[TaskKind] = &[
+    TaskKind::CollectFeeds,
+    TaskKind::Reindex,
+    TaskKind::Backup,
+    TaskKind::PruneCache,
+];
+
+impl TaskKind {
+    fn job(self) -> JobSpec {
+        match self {
+            TaskKind::CollectFeeds => JobSpec::Collect { notebook_id: None },
+            TaskKind::Reindex => JobSpec::Index {
+                notebook_id: None,
+                force: false,
+            },
+            TaskKind::Backup => JobSpec::Backup { dest_dir: None },
+            TaskKind::PruneCache => JobSpec::PruneCache {
+                max_age_days: CACHE_MAX_AGE_DAYS,
+            },
+        }
+    }
+
+    fn default_schedule(self) -> TaskSchedule {
+        let (enabled, cron) = match self {
+            // The backend runs its own collection schedule; this is opt-in.
+            TaskKind::CollectFeeds => (false, "0 */6 * * *"),
+            TaskKind::Reindex => (true, "30 3 * * *"),
+            TaskKind::Backup => (true, "0 2 * * 0"),
+            TaskKind::PruneCache => (true, "0 4 * * *"),
+        };
+        TaskSchedule {
+            enabled,
+            cron: cron.to_string(),
+            last_run: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TaskSchedule {
+    pub enabled: bool,
+    pub cron: String,
+    /// Unix seconds of the last run (scheduled or manual).
+    pub last_run: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ScheduledTask {
+    task: TaskKind,
+    enabled: bool,
+    cron: String,
+    last_run: Option<u64>,
+    /// Unix seconds; None when disabled or the expression never matches.
+    next_run: Option<u64>,
+}
+
+/// A parsed schedule: one bit per allowed value of each field.
+struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Cron semantics: if both day fields are restricted, either may match.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (
+                r,
+                s.parse::<u32>()
+                    .map_err(|_| format!("Bad step in {:?}", part))?,
+            ),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("Bad step in {:?}", part));
+        }
+        let num = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("{:?} is not between {} and {}", s, min, max))
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            r => match r.split_once('-') {
+                Some((a, b)) => (num(a)?, num(b)?),
+                // "5/15" means from 5 to the end, every 15.
+                None if part.contains('/') => (num(r)?, max),
+                None => (num(r)?, num(r)?),
+            },
+        };
+        for v in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            e => e,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Expected 5 fields (minute hour day month weekday), got {:?}",
+                expr
+            ));
+        };
+        // 7 is also Sunday.
+        let weekdays = parse_field(weekday, 0, 7)?;
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)? as u32,
+            days: parse_field(day, 1, 31)? as u32,
+            months: parse_field(month, 1, 12)? as u16,
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    fn matches(&self, t: &DateTime<Local>) -> bool {
+        let day = self.days & (1 << t.day()) != 0;
+        let weekday = self.weekdays & (1 << t.weekday().num_days_from_sunday()) != 0;
+        let day_ok = match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes & (1 << t.minute()) != 0
+            && self.hours & (1 << t.hour()) != 0
+            && self.months & (1 << t.month()) != 0
+            && day_ok
+    }
+
+    /// The first matching minute strictly after `after`.
+    fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.with_second(0)?.with_nanosecond(0)?;
+        (1..=HORIZON_MINUTES)
+            .map(|m| start + chrono::Duration::minutes(m))
+            .find(|t| self.matches(t))
+    }
+}
+
+fn local(secs: u64) -> Option<DateTime<Local>> {
+    Local.timestamp_opt(secs as i64, 0).single()
+}
+
+/// Stored schedules, with defaults for tasks that have none yet.
+fn schedules(app: &AppHandle) -> HashMap<TaskKind, TaskSchedule> {
+    let mut stored = crate::settings::get(app).schedules;
+    for task in TASKS {
+        stored
+            .entry(*task)
+            .or_insert_with(|| task.default_schedule());
+    }
+    stored
+}
+
+fn next_run(schedule: &TaskSchedule) -> Option<u64> {
+    if !schedule.enabled {
+        return None;
+    }
+    let after = schedule.last_run.and_then(local).unwrap_or_else(Local::now);
+    Cron::parse(&schedule.cron)
+        .ok()?
+        .next_after(after)
+        .map(|t| t.timestamp() as u64)
+}
+
+fn record_run(app: &AppHandle, task: TaskKind) -> Result<(), String> {
+    let mut schedule = schedules(app)
+        .remove(&task)
+        .unwrap_or_else(|| task.default_schedule());
+    schedule.last_run = Some(crate::models::now_secs());
+    crate::settings::update(app, |s| {
+        s.schedules.insert(task, schedule);
+    })?;
+    Ok(())
+}
+
+fn run_task(app: &AppHandle, task: TaskKind) -> Result<crate::jobs::Job, String> {
+    record_run(app, task)?;
+    tracing::info!("[Scheduler] Running {:?}", task);
+    crate::jobs::enqueue(app, task.job(), None)
+}
+
+fn check(app: &AppHandle) {
+    if crate::pause::is_paused(app) {
+        return;
+    }
+    let now = crate::models::now_secs();
+    for (task, schedule) in schedules(app) {
+        if !schedule.enabled {
+            continue;
+        }
+        if schedule.last_run.is_none() {
+            // First sight of this task: start counting from now instead of
+            // running everything on first launch.
+            let _ = record_run(app, task);
+            continue;
+        }
+        if next_run(&schedule).is_some_and(|t| t <= now) {
+            if let Err(e) = run_task(app, task) {
+                tracing::warn!("[Scheduler] {:?} failed to start: {}", task, e);
+            }
+        }
+    }
+}
+
+/// Start the check loop. Called once from setup.
+pub(crate) fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            check(&app);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn list_scheduled_tasks(app: AppHandle) -> Result<Vec<ScheduledTask>, String> {
+    let schedules = schedules(&app);
+    Ok(TASKS
+        .iter()
+        .filter_map(|task| schedules.get(task).map(|s| (*task, s)))
+        .map(|(task, s)| ScheduledTask {
+            task,
+            enabled: s.enabled,
+            cron: s.cron.clone(),
+            last_run: s.last_run,
+            next_run: next_run(s),
+        })
+        .collect())
+}
+
+/// Run a task now, outside its schedule. Returns the job it queued.
+#[tauri::command]
+pub(crate) async fn run_now(app: AppHandle, task: TaskKind) -> Result<crate::jobs::Job, String> {
+    run_task(&app, task)
+}
+
+#[tauri::command]
+pub(crate) async fn set_task_schedule(
+    app: AppHandle,
+    task: TaskKind,
+    enabled: bool,
+    cron: String,
+) -> Result<(), String> {
+    Cron::parse(&cron)?;
+    let mut schedule = schedules(&app)
+        .remove(&task)
+        .unwrap_or_else(|| task.default_schedule());
+    schedule.enabled = enabled;
+    schedule.cron = cron.trim().to_string();
+    crate::settings::update(&app, |s| {
+        s.schedules.insert(task, schedule);
+    })?;
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! File-system grants — the files and folders the user has handed to
+//! LocalBook, and the check every Rust command that touches a path from the
+//! frontend runs first.
+//!
+//! A path is granted by choosing it in a file dialog (the dialog plugin
+//! allows it in the fs scope, which we listen to) or by dropping it on a
+//! window. Grants are saved in settings and re-applied to the fs scope at
+//! launch, so the frontend keeps access across restarts without the blanket
+//! scope in the capability file. The app's own folders (data, cache, models,
+//! the backend's data) are always allowed.
+//!
+//! Revoking takes effect at once for the Rust side. For the fs plugin a
+//! revoked path is forbidden for the rest of the session — the plugin has no
+//! way to withdraw an allow — so choosing it again only restores frontend
+//! access after a restart.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, DragDropEvent, Manager, WindowEvent};
+use tauri_plugin_fs::FsExt;
+
+/// Settings entry for a granted path.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct GrantedPath {
+    /// Canonical path.
+    pub path: String,
+    pub directory: bool,
+    /// Unix seconds.
+    pub granted_at: u64,
+    /// "dialog" | "drop" | "cli"
+    pub via: String,
+}
+
+/// Resolve symlinks and `..`. For a path that doesn't exist yet (a backup
+/// destination, a new models folder) the nearest existing ancestor is
+/// resolved and the rest appended.
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Not an absolute path: {}", path.display()));
+    }
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return Ok(rest
+                .iter()
+                .rev()
+                .fold(resolved, |acc: PathBuf, part| acc.join(part)));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Err(format!("Can't resolve {}", path.display())),
+        }
+    }
+}
+
+fn owned_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let paths = app.path();
+    [
+        paths.app_data_dir().ok(),
+        paths.app_local_data_dir().ok(),
+        paths.app_cache_dir().ok(),
+        crate::models::models_dir(app).ok(),
+        Some(crate::backend_data_dir()),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|d| d.canonicalize().ok())
+    .collect()
+}
+
+/// Ok with the resolved path if `path` was granted or belongs to the app.
+pub(crate) fn check(app: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let resolved = resolve(path)?;
+    let granted = crate::settings::get(app).granted_paths.iter().any(|g| {
+        let root = Path::new(&g.path);
+        if g.directory {
+            resolved.starts_with(root)
+        } else {
+            resolved == root
+        }
+    });
+    if granted || owned_dirs(app).iter().any(|d| resolved.starts_with(d)) {
+        return Ok(resolved);
+    }
+    tracing::warn!("[Scope] Refused {}", resolved.display());
+    Err(format!(
+        "LocalBook hasn't been given access to {}. Choose it in a file dialog or drop it on the window first.",
+        path.display()
+    ))
+}
+
+pub(crate) fn grant(app: &AppHandle, path: &Path, via: &str) {
+    let Ok(resolved) = path.canonicalize() else {
+        return;
+    };
+    let key = resolved.to_string_lossy().to_string();
+    let directory = resolved.is_dir();
+    let result = crate::settings::update(app, |s| {
+        if !s.granted_paths.iter().any(|g| g.path == key) {
+            crate::audit::record("path_granted", format!("{} ({})", key, via));
+            s.granted_paths.push(GrantedPath {
+                path: key.clone(),
+                directory,
+                granted_at: crate::models::now_secs(),
+                via: via.to_string(),
+            });
+        }
+    });
+    if let Err(e) = result {
+        tracing::warn!("[Scope] Failed to save grant for {}: {}", key, e);
+    }
+}
+
+/// Re-apply saved grants to the fs scope and start recording new ones.
+/// Called once from setup, before any window can open a dialog.
+pub(crate) fn start(app: &AppHandle) {
+    let fs_scope = app.fs_scope();
+    for g in crate::settings::get(app).granted_paths {
+        let applied = if g.directory {
+            fs_scope.allow_directory(&g.path, true)
+        } else {
+            fs_scope.allow_file(&g.path)
+        };
+        if let Err(e) = applied {
+            tracing::warn!("[Scope] Failed to restore {}: {}", g.path, e);
+        }
+    }
+    let handle = app.clone();
+    fs_scope.listen(move |event| {
+        if let tauri::scope::fs::Event::PathAllowed(path) = event {
+            grant(&handle, path, "dialog");
+        }
+    });
+}
+
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event {
+        for path in paths {
+            grant(window.app_handle(), path, "drop");
+        }
+    }
+}
+
+/// Granted paths, newest first.
+#[tauri::command]
+pub(crate) async fn list_granted_paths(app: AppHandle) -> Result<Vec<GrantedPath>, String> {
+    let mut grants = crate::settings::get(&app).granted_paths;
+    grants.sort_by_key(|g| std::cmp::Reverse(g.granted_at));
+    Ok(grants)
+}
+
+#[tauri::command]
+pub(crate) async fn revoke_path(app: AppHandle, path: String) -> Result<(), String> {
+    let mut removed = None;
+    crate::settings::update(&app, |s| {
+        if let Some(i) = s.granted_paths.iter().position(|g| g.path == path) {
+            removed = Some(s.granted_paths.remove(i));
+        }
+    })?;
+    let g = removed.ok_or_else(|| format!("{} isn't a granted path", path))?;
+    let fs_scope = app.fs_scope();
+    if g.directory {
+        fs_scope.forbid_directory(&g.path, true)
+    } else {
+        fs_scope.forbid_file(&g.path)
+    }
+    .map_err(|e| e.to_string())?;
+    tracing::info!("[Scope] Revoked {}", g.path);
+    crate::audit::record("path_revoked", &g.path);
+    Ok(())
+}
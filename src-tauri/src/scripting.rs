@@ -0,0 +1,223 @@
+//! AppleScript commands on macOS, which the Shortcuts app runs through its
+//! "Run AppleScript" action:
+//!
+//! ```applescript
+//! tell application "LocalBook"
+//!     capture "Call the printer people" titled "Todo"
+//!     with timeout of 600 seconds
+//!         ask "What did we decide about pricing?" in notebook "Planning"
+//!     end timeout
+//!     import {POSIX file "/Users/me/report.pdf"} into notebook "Research"
+//! end tell
+//! ```
+//!
+//! The commands are declared in `LocalBook.sdef`, bundled as the app's
+//! scripting definition, and handled as raw Apple events rather than through
+//! Cocoa scripting. Each is the matching automation method (see `rpc`) —
+//! `notes.capture`, `notebooks.ask`, `sources.import` — so they're checked
+//! and refused the same way, including while the app is locked. The event is
+//! suspended while the backend works, so a slow answer doesn't hold up the
+//! app; AppleScript's own two-minute timeout still applies unless the script
+//! raises it.
+
+use tauri::AppHandle;
+
+#[cfg(target_os = "macos")]
+mod events {
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use objc2::rc::Retained;
+    use objc2::runtime::NSObject;
+    use objc2::{define_class, msg_send, sel, AnyThread};
+    use objc2_foundation::{NSAppleEventDescriptor, NSAppleEventManager, NSString};
+    use serde_json::{json, Value};
+    use tauri::AppHandle;
+
+    const SUITE: u32 = code(b"LcBk");
+    const KEY_DIRECT_OBJECT: u32 = code(b"----");
+    const KEY_TITLE: u32 = code(b"Titl");
+    const KEY_NOTEBOOK: u32 = code(b"Ntbk");
+    const KEY_ERROR_NUMBER: u32 = code(b"errn");
+    const KEY_ERROR_STRING: u32 = code(b"errs");
+    /// errAEEventFailed: the script sees the message with this number.
+    const EVENT_FAILED: i32 = -10000;
+    /// How long a command launching the app waits for its backend.
+    const BACKEND_WAIT: Duration = Duration::from_secs(120);
+
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+
+    const fn code(c: &[u8; 4]) -> u32 {
+        u32::from_be_bytes(*c)
+    }
+
+    define_class!(
+        // SAFETY: NSObject has no subclassing requirements and the handler
+        // doesn't implement Drop.
+        #[unsafe(super(NSObject))]
+        #[name = "LocalBookScriptHandler"]
+        struct Handler;
+
+        impl Handler {
+            #[unsafe(method(capture:withReplyEvent:))]
+            fn capture(&self, event: &NSAppleEventDescriptor, _reply: &NSAppleEventDescriptor) {
+                let params = json!({
+                    "text": string_param(event, KEY_DIRECT_OBJECT),
+                    "title": string_param(event, KEY_TITLE),
+                    "notebook": string_param(event, KEY_NOTEBOOK),
+                });
+                dispatch("notes.capture", params, |r| Reply::Text(r["source_id"].clone()));
+            }
+
+            #[unsafe(method(ask:withReplyEvent:))]
+            fn ask(&self, event: &NSAppleEventDescriptor, _reply: &NSAppleEventDescriptor) {
+                let params = json!({
+                    "question": string_param(event, KEY_DIRECT_OBJECT),
+                    "notebook": string_param(event, KEY_NOTEBOOK),
+                });
+                dispatch("notebooks.ask", params, |r| Reply::Text(r["answer"].clone()));
+            }
+
+            #[unsafe(method(import:withReplyEvent:))]
+            fn import(&self, event: &NSAppleEventDescriptor, _reply: &NSAppleEventDescriptor) {
+                let params = json!({
+                    "paths": file_params(event),
+                    "notebook": string_param(event, KEY_NOTEBOOK),
+                });
+                dispatch("sources.import", params, |r| {
+                    Reply::Count(r.as_array().map_or(0, |jobs| jobs.len()))
+                });
+            }
+        }
+    );
+
+    enum Reply {
+        Text(Value),
+        Count(usize),
+    }
+
+    fn param(event: &NSAppleEventDescriptor, key: u32) -> Option<Retained<NSAppleEventDescriptor>> {
+        unsafe { msg_send![event, paramDescriptorForKeyword: key] }
+    }
+
+    fn string_param(event: &NSAppleEventDescriptor, key: u32) -> Option<String> {
+        param(event, key)?.stringValue().map(|s| s.to_string())
+    }
+
+    /// The direct parameter's files: a list of them, or just one.
+    fn file_params(event: &NSAppleEventDescriptor) -> Vec<String> {
+        let Some(direct) = param(event, KEY_DIRECT_OBJECT) else {
+            return Vec::new();
+        };
+        let items: Vec<Retained<NSAppleEventDescriptor>> = match direct.numberOfItems() {
+            0 => vec![direct],
+            n => (1..=n)
+                .filter_map(|i| direct.descriptorAtIndex(i))
+                .collect(),
+        };
+        items
+            .iter()
+            .filter_map(|d| d.fileURLValue()?.path())
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    /// Suspend the event, run `method`, and answer it once that's done.
+    fn dispatch(method: &'static str, params: Value, reply: fn(&Value) -> Reply) {
+        let Some(app) = APP.get().cloned() else {
+            return;
+        };
+        let manager = NSAppleEventManager::sharedAppleEventManager();
+        // The id is only handed back to the manager, on the main thread.
+        let suspension = manager.suspendCurrentAppleEvent() as usize;
+        tauri::async_runtime::spawn(async move {
+            let result = match wait_for_backend(&app).await {
+                Ok(()) => crate::rpc::call_method(&app, method, &params).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &result {
+                tracing::warn!("[Scripting] {} failed: {}", method, e);
+            }
+            let answer = result.map(|r| reply(&r));
+            let _ = app.run_on_main_thread(move || resume(suspension, answer));
+        });
+    }
+
+    fn resume(suspension: usize, answer: Result<Reply, String>) {
+        let manager = NSAppleEventManager::sharedAppleEventManager();
+        let suspension = suspension as _;
+        let reply = unsafe { manager.replyAppleEventForSuspensionID(suspension) };
+        let set = |key: u32, value: &NSAppleEventDescriptor| {
+            let _: () = unsafe { msg_send![&reply, setParamDescriptor: value, forKeyword: key] };
+        };
+        match answer {
+            Ok(Reply::Text(Value::String(text))) => set(
+                KEY_DIRECT_OBJECT,
+                &NSAppleEventDescriptor::descriptorWithString(&NSString::from_str(&text)),
+            ),
+            Ok(Reply::Text(_)) => {}
+            Ok(Reply::Count(n)) => set(
+                KEY_DIRECT_OBJECT,
+                &NSAppleEventDescriptor::descriptorWithInt32(i32::try_from(n).unwrap_or(i32::MAX)),
+            ),
+            Err(e) => {
+                set(
+                    KEY_ERROR_NUMBER,
+                    &NSAppleEventDescriptor::descriptorWithInt32(EVENT_FAILED),
+                );
+                set(
+                    KEY_ERROR_STRING,
+                    &NSAppleEventDescriptor::descriptorWithString(&NSString::from_str(&e)),
+                );
+            }
+        }
+        unsafe { manager.resumeWithSuspensionID(suspension) };
+    }
+
+    /// A command can be what launched the app, before its backend is up.
+    async fn wait_for_backend(app: &AppHandle) -> Result<(), String> {
+        let waiting = async {
+            while !crate::backend_ready(app) {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        };
+        tokio::time::timeout(BACKEND_WAIT, waiting)
+            .await
+            .map_err(|_| "LocalBook is still starting; try again shortly".to_string())
+    }
+
+    pub(super) fn register(app: &AppHandle) {
+        if APP.set(app.clone()).is_err() {
+            return;
+        }
+        let handler: Retained<Handler> = unsafe { msg_send![Handler::alloc(), init] };
+        let manager = NSAppleEventManager::sharedAppleEventManager();
+        for (selector, id) in [
+            (sel!(capture:withReplyEvent:), code(b"Capt")),
+            (sel!(ask:withReplyEvent:), code(b"Ask ")),
+            (sel!(import:withReplyEvent:), code(b"Impt")),
+        ] {
+            let _: () = unsafe {
+                msg_send![
+                    &manager,
+                    setEventHandler: &*handler,
+                    andSelector: selector,
+                    forEventClass: SUITE,
+                    andEventID: id
+                ]
+            };
+        }
+        // The manager doesn't retain its handlers; this one lives as long as
+        // the app.
+        std::mem::forget(handler);
+        tracing::info!("[Scripting] AppleScript commands registered");
+    }
+}
+
+/// Start answering the AppleScript commands. Called from setup; macOS only.
+pub(crate) fn start(app: &AppHandle) {
+    #[cfg(target_os = "macos")]
+    events::register(app);
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
@@ -0,0 +1,216 @@
+//! OS keychain storage (the macOS and iOS Keychain, Windows Credential
+//! Manager, Secret Service on Linux) for tokens and API keys.
+//!
+//! Secrets never touch a config file. Every entry lives under one service name
+//! with the secret's name as the account. Keychain calls can block (or prompt
+//! on macOS), so async callers should run them on a blocking thread.
+//!
+//! Cloud provider API keys are managed from settings through the
+//! `*_secret` commands and reach the backend only as environment variables of
+//! the spawned process; its keychain manager (`services/keychain_manager.py`)
+//! uses them ahead of the keys it stores itself.
+
+const SERVICE: &str = "com.localbook.desktop";
+
+/// Secrets the webview may manage, and the env var each is passed to the
+/// backend as. Anything else (e.g. the Hugging Face token) is Rust-only.
+const API_KEYS: &[(&str, &str)] = &[
+    ("openai_api_key", "OPENAI_API_KEY"),
+    ("anthropic_api_key", "ANTHROPIC_API_KEY"),
+    ("gemini_api_key", "GEMINI_API_KEY"),
+];
+
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, name).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+pub(crate) fn set(name: &str, value: &str) -> Result<(), String> {
+    crate::audit::record("key_stored", name);
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store {} in keychain: {}", name, e))
+}
+
+/// Ok(None) when there is no such secret.
+pub(crate) fn get(name: &str) -> Result<Option<String>, String> {
+    crate::audit::record("key_read", name);
+    match entry(name)?.get_password() {
+        Ok(v) => Ok(Some(v)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} from keychain: {}", name, e)),
+    }
+}
+
+/// Deleting a secret that doesn't exist is not an error.
+pub(crate) fn delete(name: &str) -> Result<(), String> {
+    crate::audit::record("key_deleted", name);
+    match entry(name)?.delete_credential() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete {} from keychain: {}", name, e)),
+    }
+}
+
+/// Whether the keychain answers at all, without reading a real secret (and
+/// so without an audit entry or a prompt). Blocking.
+pub(crate) fn probe() -> Result<(), String> {
+    match entry("keychain_probe")?.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Keychain unavailable: {}", e)),
+    }
+}
+
+fn check_api_key_name(name: &str) -> Result<(), String> {
+    if API_KEYS.iter().any(|(n, _)| *n == name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown secret: {}", name))
+    }
+}
+
+/// Environment for the backend process: every stored API key. Blocking.
+pub(crate) fn backend_env() -> Vec<(&'static str, String)> {
+    // Without keys the backend has no cloud providers to reach.
+    if crate::privacy::local_only() {
+        return Vec::new();
+    }
+    API_KEYS
+        .iter()
+        .filter_map(|(name, var)| match get(name) {
+            Ok(Some(v)) => Some((*var, v)),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("[Secrets] {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) async fn set_secret(name: String, value: String) -> Result<(), String> {
+    check_api_key_name(&name)?;
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err("Value is empty".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || set(&name, &value))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub(crate) async fn get_secret(name: String) -> Result<Option<String>, String> {
+    check_api_key_name(&name)?;
+    tauri::async_runtime::spawn_blocking(move || get(&name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub(crate) async fn delete_secret(name: String) -> Result<(), String> {
+    check_api_key_name(&name)?;
+    tauri::async_runtime::spawn_blocking(move || delete(&name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct ApiKeyValidation {
+    /// "valid" | "invalid" | "quota" | "error"
+    status: String,
+    http_status: Option<u16>,
+    message: Option<String>,
+}
+
+impl ApiKeyValidation {
+    fn new(status: &str, http_status: Option<u16>, message: Option<String>) -> Self {
+        Self {
+            status: status.to_string(),
+            http_status,
+            message,
+        }
+    }
+}
+
+/// Check a key with the cheapest authenticated call each provider has (listing
+/// models). `provider` is "openai", "anthropic" or "gemini"; with no `key` the
+/// stored one is checked.
+#[tauri::command]
+pub(crate) async fn validate_api_key(
+    provider: String,
+    key: Option<String>,
+) -> Result<ApiKeyValidation, String> {
+    let name = format!("{}_api_key", provider);
+    check_api_key_name(&name).map_err(|_| format!("Unknown provider: {}", provider))?;
+    let key = match key.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()) {
+        Some(k) => k,
+        None => tauri::async_runtime::spawn_blocking(move || get(&name))
+            .await
+            .map_err(|e| e.to_string())??
+            .ok_or_else(|| format!("No {} API key stored", provider))?,
+    };
+    let endpoint = match provider.as_str() {
+        "openai" => "https://api.openai.com/v1/models",
+        "anthropic" => "https://api.anthropic.com/v1/models",
+        _ => "https://generativelanguage.googleapis.com/v1beta/models",
+    };
+    if let Err(e) = crate::privacy::guard(endpoint, "API key check") {
+        return Ok(ApiKeyValidation::new("error", None, Some(e)));
+    }
+    if !crate::network::is_online() {
+        return Ok(ApiKeyValidation::new(
+            "error",
+            None,
+            Some("Offline".to_string()),
+        ));
+    }
+
+    let client = crate::proxy::client()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    let request = match provider.as_str() {
+        "openai" => client.get(endpoint).bearer_auth(&key),
+        "anthropic" => client
+            .get(endpoint)
+            .header("x-api-key", &key)
+            .header("anthropic-version", "2023-06-01"),
+        _ => client.get(endpoint).header("x-goog-api-key", &key),
+    };
+    let resp = match request.send().await {
+        Ok(r) => r,
+        Err(e) => {
+            return Ok(ApiKeyValidation::new(
+                "error",
+                None,
+                Some(format!("Request failed: {}", e)),
+            ))
+        }
+    };
+    let code = resp.status().as_u16();
+    if resp.status().is_success() {
+        return Ok(ApiKeyValidation::new("valid", Some(code), None));
+    }
+    // All three return {"error": {"message": "…"}} on failure.
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    let message = body["error"]["message"].as_str().map(String::from);
+    let status = match code {
+        // Gemini answers 400 API_KEY_INVALID rather than 401.
+        401 | 403 => "invalid",
+        400 if body["error"]["details"]
+            .to_string()
+            .contains("API_KEY_INVALID") =>
+        {
+            "invalid"
+        }
+        402 | 429 => "quota",
+        _ => "error",
+    };
+    tracing::info!(
+        "[Secrets] {} key check: {} (HTTP {})",
+        provider,
+        status,
+        code
+    );
+    Ok(ApiKeyValidation::new(status, Some(code), message))
+}
@@ -0,0 +1,349 @@
+//! `localbook --serve`: a home-server install used from any browser on the
+//! network instead of the desktop window.
+//!
+//! The app runs headless (see `cli`) with its backend as usual and serves
+//! the bundled frontend over HTTP on `DEFAULT_PORT`, or the port given as
+//! `--serve=<port>`. Requests under `/api/` go on to the backend with the
+//! app token added, so the browser never holds it; the page learns that path
+//! from a meta tag `api.ts` looks for. Websockets and streamed responses pass
+//! straight through. `/metrics` has the app's timings (see `metrics`) for
+//! a Prometheus scraper, which takes the same password. `/api` requests
+//! whose `Origin` isn't the served one are refused, so another site open in
+//! the same browser can't ride on the saved password (a TLS proxy in front
+//! has to pass the `Host` header on).
+//!
+//! Everything is behind a password (HTTP basic auth, any user name) read
+//! from `LOCALBOOK_SERVE_PASSWORD`, or made up at start and saved to
+//! `serve-password` in the app's data folder, readable only by this user —
+//! never printed, so it doesn't end up in a service log. Like `share`, the
+//! connection isn't encrypted, so this is meant for a trusted network or to
+//! sit behind a TLS proxy. Refused while local-only mode is on.
+
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub(crate) const DEFAULT_PORT: u16 = 8080;
+const PASSWORD_ENV: &str = "LOCALBOOK_SERVE_PASSWORD";
+const PASSWORD_FILE: &str = "serve-password";
+const MAX_HEAD: usize = 64 * 1024;
+/// Slows down guessing; a server that stays up can't stop after N misses
+/// the way a share does.
+const FAILURE_DELAY: Duration = Duration::from_secs(2);
+const API_META: &str = "<meta name=\"localbook-api\" content=\"/api\">";
+const METRICS_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const CHALLENGE: &str = "WWW-Authenticate: Basic realm=\"LocalBook\", charset=\"UTF-8\"\r\n";
+
+struct Server {
+    app: AppHandle,
+    password_hash: [u8; 32],
+}
+
+/// Start serving on `port`. Returns once the port is open; the server runs
+/// until the app exits.
+pub(crate) async fn start(app: &AppHandle, port: u16) -> Result<(), String> {
+    if crate::privacy::local_only() {
+        return Err("Local-only mode is on".to_string());
+    }
+    if crate::remote_backend::url().is_some() {
+        return Err("A remote backend is set; --serve needs this machine's own".to_string());
+    }
+    let given = std::env::var(PASSWORD_ENV).ok().filter(|p| !p.is_empty());
+    let password = given
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    let host =
+        crate::share::lan_address().map_or_else(|| "localhost".to_string(), |ip| ip.to_string());
+    std::println!("Serving LocalBook at http://{}:{}/", host, port);
+    if given.is_none() {
+        let path = save_password(app, &password)?;
+        std::println!(
+            "Password saved to {} (set {} to choose one)",
+            path.display(),
+            PASSWORD_ENV
+        );
+    }
+    tracing::info!("[Serve] Listening on port {}", port);
+    crate::audit::record("serve_started", format!("port {}", port));
+
+    let server = Arc::new(Server {
+        app: app.clone(),
+        password_hash: Sha256::digest(password.as_bytes()).into(),
+    });
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            };
+            tauri::async_runtime::spawn(handle(server.clone(), stream));
+        }
+    });
+    Ok(())
+}
+
+/// Write a made-up password where only this user can read it.
+fn save_password(app: &AppHandle, password: &str) -> Result<std::path::PathBuf, String> {
+    use std::io::Write;
+
+    let path = crate::data_dir(app)?.join(PASSWORD_FILE);
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).write(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", password))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// The value of a request header, if present.
+fn header<'a>(head: &'a str, wanted: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case(wanted)
+            .then(|| value.trim())
+    })
+}
+
+/// Whether a browser request comes from the page this server handed out.
+/// Requests without an `Origin` (same-origin GETs, scripts) pass.
+fn same_origin(head: &str) -> bool {
+    let Some(origin) = header(head, "origin") else {
+        return true;
+    };
+    let authority = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"));
+    match (authority, header(head, "host")) {
+        (Some(authority), Some(host)) => authority.eq_ignore_ascii_case(host),
+        _ => false,
+    }
+}
+
+/// The request head, and whatever of the body came with it.
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Option<(String, Vec<u8>)>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok(Some((String::from_utf8_lossy(&buf).into_owned(), rest)));
+        }
+        if buf.len() >= MAX_HEAD {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+async fn handle(server: Arc<Server>, mut stream: TcpStream) {
+    if let Err(e) = respond(&server, &mut stream).await {
+        tracing::warn!("[Serve] {}", e);
+    }
+}
+
+async fn respond(server: &Server, stream: &mut TcpStream) -> std::io::Result<()> {
+    let Some((head, rest)) = read_head(stream).await? else {
+        return Ok(());
+    };
+    let mut request = head.split_whitespace();
+    let method = request.next().unwrap_or_default();
+    let target = request.next().unwrap_or_default();
+
+    if !crate::share::basic_auth(&head, &server.password_hash) {
+        if head.to_ascii_lowercase().contains("\nauthorization:") {
+            tracing::warn!("[Serve] Wrong password from {:?}", stream.peer_addr().ok());
+            tokio::time::sleep(FAILURE_DELAY).await;
+        }
+        return reply(stream, method, "401 Unauthorized", CHALLENGE, "", &[]).await;
+    }
+    if let Some(path) = target
+        .strip_prefix("/api")
+        .filter(|p| p.is_empty() || p.starts_with(['/', '?']))
+    {
+        if !same_origin(&head) {
+            tracing::warn!(
+                "[Serve] Refused an /api request from {}",
+                header(&head, "origin").unwrap_or_default()
+            );
+            return reply(stream, method, "403 Forbidden", "", "", &[]).await;
+        }
+        return proxy(stream, &head, path, &rest).await;
+    }
+    if method != "GET" && method != "HEAD" {
+        return reply(stream, method, "405 Method Not Allowed", "", "", &[]).await;
+    }
+
+    // Unknown paths get index.html, for the app's own routes.
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    if path == "/metrics" {
+        let body = crate::metrics::prometheus();
+        return reply(stream, method, "200 OK", "", METRICS_TYPE, body.as_bytes()).await;
+    }
+    let Some(asset) = server.app.asset_resolver().get(path.to_string()) else {
+        return reply(stream, method, "404 Not Found", "", "", &[]).await;
+    };
+    let mut body = asset.bytes;
+    if asset.mime_type.starts_with("text/html") {
+        let html =
+            String::from_utf8_lossy(&body).replacen("<head>", &format!("<head>{}", API_META), 1);
+        body = html.into_bytes();
+    }
+    let csp = asset.csp_header.map_or_else(String::new, |c| {
+        format!("Content-Security-Policy: {}\r\n", c)
+    });
+    let headers = format!(
+        "{}Cache-Control: no-cache\r\nX-Content-Type-Options: nosniff\r\n\
+         X-Frame-Options: SAMEORIGIN\r\nReferrer-Policy: no-referrer\r\n",
+        csp
+    );
+    reply(stream, method, "200 OK", &headers, &asset.mime_type, &body).await
+}
+
+async fn reply(
+    stream: &mut TcpStream,
+    method: &str,
+    status: &str,
+    headers: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let content_type = if content_type.is_empty() {
+        "text/plain; charset=utf-8"
+    } else {
+        content_type
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        headers,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if method != "HEAD" {
+        stream.write_all(body).await?;
+    }
+    stream.shutdown().await
+}
+
+/// The backend's current token. Read each time: it changes when the backend
+/// restarts, and the browser can't ask for the new one.
+async fn token() -> String {
+    tokio::fs::read_to_string(crate::app_token_file_path())
+        .await
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Pass a request under `/api` to the backend as `path`, with the app token
+/// instead of the password. One request per connection, except websockets.
+async fn proxy(stream: &mut TcpStream, head: &str, path: &str, rest: &[u8]) -> std::io::Result<()> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().splitn(3, ' ');
+    let method = request_line.next().unwrap_or_default();
+    let version = request_line.nth(1).unwrap_or("HTTP/1.1");
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let upgrade = head.to_ascii_lowercase().contains("\nupgrade:");
+
+    let mut forwarded = format!("{} {} {}\r\n", method, path, version);
+    for line in lines.filter(|l| !l.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        let dropped = ["authorization", "x-localbook-token", "x-request-id", "host"]
+            .iter()
+            .any(|h| name.eq_ignore_ascii_case(h))
+            || (!upgrade && name.eq_ignore_ascii_case("connection"));
+        if !dropped {
+            forwarded.push_str(line);
+            forwarded.push_str("\r\n");
+        }
+    }
+    let backend_addr = (Ipv4Addr::LOCALHOST, crate::backend_api::sidecar_port());
+    // Websockets stay open; only plain requests are traced.
+    let trace = (!upgrade).then(|| crate::traces::start("serve", method, &path));
+    forwarded.push_str(&format!(
+        "Host: {}:{}\r\nX-LocalBook-Token: {}\r\n",
+        backend_addr.0,
+        backend_addr.1,
+        token().await
+    ));
+    if let Some(trace) = &trace {
+        forwarded.push_str(&format!("{}: {}\r\n", crate::traces::HEADER, trace.id()));
+    }
+    if !upgrade {
+        forwarded.push_str("Connection: close\r\n");
+    }
+    forwarded.push_str("\r\n");
+
+    let Ok(mut backend) = TcpStream::connect(backend_addr).await else {
+        if let Some(trace) = trace {
+            trace.finish(None, Some("The backend isn't running".to_string()));
+        }
+        return reply(
+            stream,
+            method,
+            "502 Bad Gateway",
+            "",
+            "",
+            b"The backend isn't running",
+        )
+        .await;
+    };
+    backend.write_all(forwarded.as_bytes()).await?;
+    backend.write_all(rest).await?;
+    let copied = tokio::io::copy_bidirectional(stream, &mut backend).await;
+    if let Some(trace) = trace {
+        trace.finish(None, copied.as_ref().err().map(|e| e.to_string()));
+    }
+    copied.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(headers: &str) -> String {
+        format!("GET /api/notebooks HTTP/1.1\r\n{}\r\n", headers)
+    }
+
+    #[test]
+    fn allows_the_served_origin_or_none() {
+        assert!(same_origin(&head("Host: 10.0.0.5:8080\r\n")));
+        assert!(same_origin(&head(
+            "Host: 10.0.0.5:8080\r\nOrigin: http://10.0.0.5:8080\r\n"
+        )));
+        assert!(same_origin(&head(
+            "host: books.example\r\norigin: https://Books.example\r\n"
+        )));
+    }
+
+    #[test]
+    fn refuses_other_origins() {
+        assert!(!same_origin(&head(
+            "Host: 10.0.0.5:8080\r\nOrigin: http://evil.example\r\n"
+        )));
+        assert!(!same_origin(&head(
+            "Host: 10.0.0.5:8080\r\nOrigin: null\r\n"
+        )));
+        assert!(!same_origin(&head("Origin: http://10.0.0.5:8080\r\n")));
+    }
+}
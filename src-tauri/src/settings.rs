@@ -0,0 +1,410 @@
+//! Shell settings — preferences owned by the Rust layer (window behaviour, tray,
+//! hotkeys…), as opposed to the backend's own settings under /settings.
+//!
+//! One JSON file in the app data dir, loaded once in setup and held in managed
+//! state. Every field is `#[serde(default)]` so older files keep loading as new
+//! settings are added; writes go through `update()` which saves atomically.
+//! The file is versioned so renamed settings carry over on upgrade (see
+//! `settings_migrate`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::appearance::AppearanceSettings;
+use crate::certs::TrustedCert;
+use crate::default_dirs::DefaultDirs;
+use crate::hardware::BackendTuning;
+use crate::jobs::ErrorClass;
+use crate::notebook_settings::NotebookSettings;
+use crate::onboarding::OnboardingState;
+use crate::power::BackgroundPolicy;
+use crate::proxy::ProxySettings;
+use crate::scheduler::{TaskKind, TaskSchedule};
+use crate::scope::GrantedPath;
+use crate::settings_migrate;
+use crate::slow_requests::SlowRequestSettings;
+use crate::startup::{Session, StartupSettings};
+use crate::sync::SyncSettings;
+use crate::updater::UpdateSettings;
+use crate::vault::EncryptedNotebook;
+use crate::versioning::VersioningSettings;
+
+const SETTINGS_FILE: &str = "shell_settings.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct MiniModeSettings {
+    /// Keep the mini chat window above other apps.
+    pub pinned: bool,
+    /// Notebook the mini window was last opened for (tray/hotkey reuse it).
+    pub notebook_id: Option<String>,
+}
+
+impl Default for MiniModeSettings {
+    fn default() -> Self {
+        Self {
+            pinned: true,
+            notebook_id: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PowerSettings {
+    /// Background work policy while running on battery.
+    pub battery_policy: BackgroundPolicy,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self {
+            battery_policy: BackgroundPolicy::Throttle,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct IdleSettings {
+    /// Hold CPU-heavy background work until the user is idle.
+    pub defer_heavy_work: bool,
+    pub idle_threshold_secs: u64,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            defer_heavy_work: false,
+            idle_threshold_secs: 300,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct MemorySettings {
+    /// Unload models when free RAM runs low.
+    pub unload_on_pressure: bool,
+    /// Threshold in MB; None = 10% of RAM, at least 1.5 GB.
+    pub min_available_mb: Option<u64>,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            unload_on_pressure: true,
+            min_available_mb: None,
+        }
+    }
+}
+
+/// How many background jobs of each category may run at once.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct JobConcurrency {
+    pub imports: usize,
+    pub downloads: usize,
+    pub transcriptions: usize,
+    pub ocr: usize,
+    /// Re-indexing, backups, collection and cache pruning.
+    pub maintenance: usize,
+}
+
+impl Default for JobConcurrency {
+    fn default() -> Self {
+        Self {
+            imports: 4,
+            downloads: 2,
+            transcriptions: 1,
+            ocr: 1,
+            maintenance: 1,
+        }
+    }
+}
+
+/// When a failed job is queued again. The delay doubles after each attempt,
+/// up to `max_backoff_secs`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RetryPolicy {
+    /// Total tries, including the first; 1 disables retries.
+    pub max_attempts: u32,
+    pub backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    /// Only failures of these classes are retried.
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_secs: 30,
+            max_backoff_secs: 3600,
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct JobSettings {
+    pub concurrency: JobConcurrency,
+    /// Per job kind ("import", "download"…); kinds missing here use the
+    /// built-in policy.
+    pub retry: HashMap<String, RetryPolicy>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct LockSettings {
+    /// A passcode is set (its hash is in the keychain) and the app locks.
+    pub enabled: bool,
+    /// Offer Touch ID / Windows Hello on the lock screen.
+    pub biometrics: bool,
+    /// Lock after the app has been in the background this long.
+    pub idle_timeout_mins: Option<u64>,
+    /// Lock after no keyboard/mouse input anywhere on the system this long,
+    /// even with an app window in front.
+    pub auto_lock_mins: Option<u64>,
+    /// Argon2 hash of the recovery key (see `lock`).
+    pub recovery_hash: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TtsSettings {
+    /// Piper voice key, e.g. "en_US-lessac-medium".
+    pub default_voice: Option<String>,
+    /// Per-notebook overrides of `default_voice`.
+    pub notebook_voices: HashMap<String, String>,
+}
+
+/// Model choices for chat. Unset fields fall through: notebook → global →
+/// the backend's own configuration.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ModelPrefs {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    /// Backend provider name ("ollama", "openai", "anthropic"…); named after
+    /// the `llm_provider` field of the backend's chat request.
+    pub llm_provider: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub mini_mode: MiniModeSettings,
+    /// "light" | "dark"; None follows the OS.
+    pub theme_override: Option<String>,
+    pub power: PowerSettings,
+    pub idle: IdleSettings,
+    pub memory: MemorySettings,
+    /// Launch parameters picked from the hardware report on first run.
+    pub backend_tuning: Option<BackendTuning>,
+    pub tts: TtsSettings,
+    /// Custom model storage folder (e.g. on an external drive); None = app data.
+    pub models_dir: Option<String>,
+    /// Load the backend's models into memory as soon as it's ready.
+    pub prewarm_models: bool,
+    /// OpenAI-compatible local server (LM Studio, vLLM…) chosen for the backend.
+    pub external_llm_url: Option<String>,
+    pub model_defaults: ModelPrefs,
+    /// Per-notebook overrides of `model_defaults`.
+    pub notebook_models: HashMap<String, ModelPrefs>,
+    /// Chunk size and citation style (see `notebook_settings`).
+    pub notebook_defaults: NotebookSettings,
+    /// Per-notebook overrides of `notebook_defaults`.
+    pub notebook_overrides: HashMap<String, NotebookSettings>,
+    pub jobs: JobSettings,
+    /// Hold the job queue, scheduler and pre-warming (see `pause`).
+    pub background_paused: bool,
+    pub lock: LockSettings,
+    /// Recurring task schedules; tasks missing here use their defaults.
+    pub schedules: HashMap<TaskKind, TaskSchedule>,
+    /// Notebooks sealed in the vault, by vault id (see `vault`).
+    pub encrypted_notebooks: HashMap<String, EncryptedNotebook>,
+    /// Refuse every non-loopback connection (see `privacy`).
+    pub local_only: bool,
+    /// Files and folders the user has given the app (see `scope`).
+    pub granted_paths: Vec<GrantedPath>,
+    /// Count local usage events (see `usage`). Off unless the user opts in.
+    pub usage_analytics: bool,
+    /// Accept automation requests on the local socket (see `rpc`). Off
+    /// unless the user opts in.
+    pub automation_api: bool,
+    /// Log filter, e.g. "debug" or "info,jobs=trace" (see `logging`); None
+    /// is the default.
+    pub log_level: Option<String>,
+    pub proxy: ProxySettings,
+    /// Extra root CAs for TLS-intercepting networks (see `certs`).
+    pub trusted_certs: Vec<TrustedCert>,
+    pub updates: UpdateSettings,
+    /// First-run setup progress (see `onboarding`); None until first asked.
+    pub onboarding: Option<OnboardingState>,
+    /// Notebook sync between devices (see `sync`, `lan_sync`).
+    pub sync: SyncSettings,
+    /// Note history in a local git repository (see `versioning`).
+    pub versioning: VersioningSettings,
+    /// Backend on another machine used instead of the sidecar (see
+    /// `remote_backend`); needed on mobile.
+    pub remote_backend_url: Option<String>,
+    /// The paired device lending that backend, if it came from one.
+    pub remote_backend_device: Option<String>,
+    /// When a backend request counts as slow (see `slow_requests`).
+    pub slow_requests: SlowRequestSettings,
+    /// Verbose logging from the shell and backend (see `debug_mode`).
+    pub debug_mode: bool,
+    /// Font scale, density, accent colour and window effects (see
+    /// `appearance`).
+    pub appearance: AppearanceSettings,
+    /// Folders dialogs open in (see `default_dirs`).
+    pub default_dirs: DefaultDirs,
+    /// Launch behaviour (see `startup`).
+    pub startup: StartupSettings,
+    /// The windows open at the last quit (see `startup`).
+    pub last_session: Session,
+    /// Rebound keyboard shortcuts by action; "" unbinds (see `shortcuts`).
+    pub shortcuts: HashMap<String, String>,
+}
+
+pub(crate) struct SettingsState(Mutex<Settings>);
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::data_dir(app)?.join(SETTINGS_FILE))
+}
+
+impl SettingsState {
+    /// Load from disk, migrating an older file. A missing file gives the
+    /// defaults; an unreadable one is moved aside and replaced by them.
+    pub(crate) fn load(app: &AppHandle) -> Self {
+        let settings = match settings_path(app) {
+            Ok(path) => load_file(app, &path),
+            Err(e) => {
+                tracing::warn!("[Settings] {} (using defaults)", e);
+                Settings::default()
+            }
+        };
+        SettingsState(Mutex::new(settings))
+    }
+}
+
+fn parse(raw: &str) -> Result<(Settings, u32), String> {
+    let Value::Object(mut fields) = serde_json::from_str(raw).map_err(|e| e.to_string())? else {
+        return Err("not a JSON object".to_string());
+    };
+    let from = settings_migrate::migrate(&mut fields);
+    let settings = if from > settings_migrate::SCHEMA_VERSION {
+        settings_migrate::lenient(&fields)?
+    } else {
+        serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())?
+    };
+    Ok((settings, from))
+}
+
+fn load_file(app: &AppHandle, path: &Path) -> Settings {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Settings::default(),
+        Err(e) => {
+            tracing::warn!("[Settings] Could not read {} (using defaults): {}", SETTINGS_FILE, e);
+            return Settings::default();
+        }
+    };
+    match parse(&raw) {
+        Ok((settings, from)) if from < settings_migrate::SCHEMA_VERSION => {
+            settings_migrate::keep_original(path, from);
+            match save(app, &settings) {
+                Ok(()) => tracing::info!(
+                    "[Settings] Migrated {} from version {} to {}",
+                    SETTINGS_FILE,
+                    from,
+                    settings_migrate::SCHEMA_VERSION
+                ),
+                Err(e) => tracing::warn!("[Settings] Could not save migrated settings: {}", e),
+            }
+            settings
+        }
+        Ok((settings, from)) if from > settings_migrate::SCHEMA_VERSION => {
+            settings_migrate::keep_newer(path, from);
+            settings
+        }
+        Ok((settings, _)) => settings,
+        Err(e) => {
+            let settings = Settings::default();
+            if settings_migrate::quarantine(path, e) {
+                if let Err(e) = save(app, &settings) {
+                    tracing::warn!("[Settings] Could not write default settings: {}", e);
+                }
+            }
+            settings
+        }
+    }
+}
+
+/// Snapshot of the current settings.
+pub(crate) fn get(app: &AppHandle) -> Settings {
+    let state = app.state::<SettingsState>();
+    let guard = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    guard.clone()
+}
+
+/// Mutate settings and persist them. Returns the updated snapshot.
+pub(crate) fn update<F>(app: &AppHandle, f: F) -> Result<Settings, String>
+where
+    F: FnOnce(&mut Settings),
+{
+    let state = app.state::<SettingsState>();
+    let (before, snapshot) = {
+        let mut guard = state.0.lock().map_err(|e| e.to_string())?;
+        let before = guard.clone();
+        f(&mut guard);
+        (before, guard.clone())
+    };
+    save(app, &snapshot)?;
+    let changed = changed_fields(&before, &snapshot);
+    if !changed.is_empty() {
+        crate::audit::record("settings_changed", changed.join(", "));
+    }
+    Ok(snapshot)
+}
+
+/// Names of the top-level settings that differ — values stay out of the
+/// audit log.
+fn changed_fields(before: &Settings, after: &Settings) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+    after
+        .iter()
+        .filter(|(k, v)| before.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .collect()
+}
+
+/// Write to a temp file then rename, so a crash mid-write never leaves a
+/// truncated settings file behind. A file from a newer build is left as is.
+fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    if settings_migrate::newer_file() {
+        return Ok(());
+    }
+    let path = settings_path(app)?;
+    let tmp = path.with_extension("json.tmp");
+    let Value::Object(mut fields) = serde_json::to_value(settings).map_err(|e| e.to_string())?
+    else {
+        return Err("Settings aren't an object".to_string());
+    };
+    settings_migrate::stamp(&mut fields);
+    let json = serde_json::to_string_pretty(&fields).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
@@ -0,0 +1,228 @@
+//! Moving settings to another machine: `export_settings` writes one JSON
+//! file, `import_settings` applies it.
+//!
+//! The file holds the shell settings that make sense anywhere (window and
+//! background-work preferences, model choices, schedules, proxy, updates,
+//! note history…) and the backend's app preferences and user profile.
+//! Left out are things tied to this machine or its keychain: hardware
+//! tuning, folders (the default dialog folders too) and granted paths,
+//! encrypted notebooks, the app lock, sync pairings and device id, trusted
+//! certificates, setup progress, the log level and debug mode, slow-request
+//! thresholds (they depend on the hardware), the windows open at the last
+//! quit, and the analytics and automation opt-ins (asked again on each
+//! machine).
+//! Secrets never go in the file; the import lists the ones to enter again.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::backend_api;
+use crate::settings::Settings;
+
+const FORMAT: &str = "localbook-settings";
+const VERSION: u32 = 1;
+const API_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Shell settings that stay with the machine.
+const MACHINE_FIELDS: &[&str] = &[
+    "backend_tuning",
+    "models_dir",
+    "encrypted_notebooks",
+    "granted_paths",
+    "trusted_certs",
+    "lock",
+    "onboarding",
+    "sync",
+    "usage_analytics",
+    "automation_api",
+    "log_level",
+    "slow_requests",
+    "debug_mode",
+    "last_session",
+    "default_dirs",
+];
+
+/// Backend settings carried along, by endpoint under /settings.
+const BACKEND_DOCUMENTS: &[&str] = &["preferences", "user-profile"];
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    format: String,
+    version: u32,
+    app_version: String,
+    /// Unix seconds.
+    exported_at: u64,
+    shell: serde_json::Map<String, Value>,
+    /// Backend documents by endpoint; missing if the backend wasn't running.
+    #[serde(default)]
+    backend: serde_json::Map<String, Value>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ImportReport {
+    /// Shell settings taken from the file.
+    applied: Vec<String>,
+    /// Backend documents restored.
+    backend: Vec<String>,
+    /// Secrets to enter again on this machine.
+    reenter: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn portable(settings: &Settings) -> Result<serde_json::Map<String, Value>, String> {
+    let Value::Object(mut fields) = serde_json::to_value(settings).map_err(|e| e.to_string())?
+    else {
+        return Err("Settings aren't an object".to_string());
+    };
+    fields.retain(|k, _| !MACHINE_FIELDS.contains(&k.as_str()));
+    crate::settings_migrate::stamp(&mut fields);
+    if let Some(versioning) = fields.get_mut("versioning").and_then(Value::as_object_mut) {
+        versioning.remove("last_commit_at");
+        versioning.remove("last_push_at");
+    }
+    Ok(fields)
+}
+
+/// Secrets the imported settings rely on that aren't in the keychain here.
+fn missing_secrets(settings: &Settings) -> Vec<String> {
+    let mut missing = Vec::new();
+    let checks = [
+        (
+            settings.proxy.username.is_some(),
+            crate::proxy::PASSWORD_SECRET,
+            "Proxy password",
+        ),
+        (
+            settings.versioning.remote_url.is_some(),
+            crate::versioning::TOKEN_SECRET,
+            "Note history remote token",
+        ),
+        (
+            settings.remote_backend_url.is_some(),
+            crate::remote_backend::TOKEN_SECRET,
+            "Remote backend token",
+        ),
+    ];
+    for (needed, secret, label) in checks {
+        if needed && !matches!(crate::secrets::get(secret), Ok(Some(_))) {
+            missing.push(label.to_string());
+        }
+    }
+    missing
+}
+
+/// Write this machine's portable settings to `dest` — a file, or a folder
+/// to create `localbook-settings-<time>.json` in. Returns the file written.
+#[tauri::command]
+pub(crate) async fn export_settings(app: AppHandle, dest: String) -> Result<String, String> {
+    let dest = crate::scope::check(&app, Path::new(&dest))?;
+    let dest = if dest.is_dir() {
+        dest.join(format!(
+            "localbook-settings-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    } else {
+        dest
+    };
+    let mut backend = serde_json::Map::new();
+    for document in BACKEND_DOCUMENTS {
+        match backend_api::get_json(&format!("/settings/{}", document), API_TIMEOUT).await {
+            Ok(value) => {
+                backend.insert(document.to_string(), value);
+            }
+            Err(e) => tracing::warn!("[Settings] Not exporting backend {}: {}", document, e),
+        }
+    }
+    let file = SettingsFile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        app_version: app.package_info().version.to_string(),
+        exported_at: crate::models::now_secs(),
+        shell: portable(&crate::settings::get(&app))?,
+        backend,
+    };
+    let bytes = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(&dest, bytes)
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    tracing::info!("[Settings] Exported to {}", dest.display());
+    crate::audit::record("settings_exported", dest.display().to_string());
+    Ok(dest.to_string_lossy().into_owned())
+}
+
+/// Apply settings exported on another machine. Settings the file doesn't
+/// have, and the ones that stay with a machine, keep their values here.
+#[tauri::command]
+pub(crate) async fn import_settings(app: AppHandle, path: String) -> Result<ImportReport, String> {
+    let path: PathBuf = crate::scope::check(&app, Path::new(&path))?;
+    let raw =
+        std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut file: SettingsFile = serde_json::from_slice(&raw)
+        .ok()
+        .filter(|f: &SettingsFile| f.format == FORMAT)
+        .ok_or_else(|| format!("{} isn't a LocalBook settings file", path.display()))?;
+    if file.version > VERSION {
+        return Err(
+            "These settings are from a newer version of LocalBook; update first".to_string(),
+        );
+    }
+
+    // Shell settings from an older build may use old names.
+    crate::settings_migrate::migrate(&mut file.shell);
+
+    let mut warnings = Vec::new();
+    let current = serde_json::to_value(crate::settings::get(&app)).map_err(|e| e.to_string())?;
+    let Value::Object(mut merged) = current else {
+        return Err("Settings aren't an object".to_string());
+    };
+    let mut applied = Vec::new();
+    for (key, value) in file.shell {
+        if MACHINE_FIELDS.contains(&key.as_str()) || !merged.contains_key(&key) {
+            continue;
+        }
+        merged.insert(key.clone(), value);
+        applied.push(key);
+    }
+    let imported: Settings = serde_json::from_value(Value::Object(merged))
+        .map_err(|e| format!("The settings file has invalid values: {}", e))?;
+    crate::settings::update(&app, |s| {
+        let stamps = (s.versioning.last_commit_at, s.versioning.last_push_at);
+        *s = imported.clone();
+        (s.versioning.last_commit_at, s.versioning.last_push_at) = stamps;
+    })?;
+    // Pick up the settings that are cached at startup.
+    crate::privacy::start(&app);
+    crate::proxy::start(&app);
+    crate::notebook_settings::publish(&app);
+
+    let mut backend = Vec::new();
+    for (document, value) in file.backend {
+        if !BACKEND_DOCUMENTS.contains(&document.as_str()) {
+            continue;
+        }
+        match backend_api::post_json(&format!("/settings/{}", document), &value, API_TIMEOUT).await
+        {
+            Ok(_) => backend.push(document),
+            Err(e) => warnings.push(format!("Backend {} not restored: {}", document, e)),
+        }
+    }
+
+    let reenter = tauri::async_runtime::spawn_blocking(move || missing_secrets(&imported))
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!(
+        "[Settings] Imported {} settings from {}",
+        applied.len(),
+        path.display()
+    );
+    crate::audit::record("settings_imported", path.display().to_string());
+    Ok(ImportReport {
+        applied,
+        backend,
+        reenter,
+        warnings,
+    })
+}
@@ -0,0 +1,343 @@
+//! Keeping the settings file readable across versions.
+//!
+//! The file carries `schema_version`. When a version renames a setting or
+//! changes the values an enum takes, it bumps `SCHEMA_VERSION` and adds a
+//! `Migration` saying what moved; `settings::load` runs the ones a file
+//! hasn't had yet, keeps a copy of the file as it was, and saves the result.
+//! Files from before versioning count as version 0. Imported settings from
+//! an older export go through the same steps.
+//!
+//! A file from a newer build (after a downgrade) is read leniently: a value
+//! this build can't take, like an enum value added later, keeps its default
+//! rather than failing the file. It isn't migrated, re-stamped or written
+//! back, so the newer build finds it as it left it; changes made here last
+//! until quit.
+//!
+//! A file that can't be read as settings at all — broken JSON, a value of
+//! the wrong type — no longer stops the app: it's moved aside as
+//! `shell_settings.corrupt-<time>.json` and the defaults are written in its
+//! place. `get_settings_recovery` tells the webview so it can say so.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The version this build writes.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+pub(crate) const VERSION_KEY: &str = "schema_version";
+
+/// What changed going to version `to`. Paths are dotted, e.g.
+/// "startup.backend".
+struct Migration {
+    to: u32,
+    /// (old path, new path).
+    renamed: &'static [(&'static str, &'static str)],
+    /// (path, old value, new value) for enum values that changed.
+    values: &'static [(&'static str, &'static str, &'static str)],
+}
+
+const MIGRATIONS: &[Migration] = &[
+    // The first versioned file; nothing moved.
+    Migration {
+        to: 1,
+        renamed: &[],
+        values: &[],
+    },
+];
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Recovery {
+    /// Where the unreadable file was moved.
+    quarantined: String,
+    reason: String,
+}
+
+static RECOVERY: Mutex<Option<Recovery>> = Mutex::new(None);
+
+/// Set when the file on disk is from a newer build, so it isn't saved over.
+static NEWER_FILE: AtomicBool = AtomicBool::new(false);
+
+/// The file's schema version; 0 if it has none.
+pub(crate) fn version(fields: &Map<String, Value>) -> u32 {
+    fields
+        .get(VERSION_KEY)
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v as u32)
+}
+
+pub(crate) fn stamp(fields: &mut Map<String, Value>) {
+    fields.insert(VERSION_KEY.to_string(), Value::from(SCHEMA_VERSION));
+}
+
+fn take(fields: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    match path.split_once('.') {
+        Some((head, rest)) => take(fields.get_mut(head)?.as_object_mut()?, rest),
+        None => fields.remove(path),
+    }
+}
+
+fn put(fields: &mut Map<String, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            let child = fields
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(child) = child.as_object_mut() {
+                put(child, rest, value);
+            }
+        }
+        None => {
+            fields.insert(path.to_string(), value);
+        }
+    }
+}
+
+fn lookup<'a>(fields: &'a mut Map<String, Value>, path: &str) -> Option<&'a mut Value> {
+    match path.split_once('.') {
+        Some((head, rest)) => lookup(fields.get_mut(head)?.as_object_mut()?, rest),
+        None => fields.get_mut(path),
+    }
+}
+
+/// Bring `fields` up to `SCHEMA_VERSION`. Returns the version it was at.
+/// A file from a newer build is left alone; read it with `lenient`.
+pub(crate) fn migrate(fields: &mut Map<String, Value>) -> u32 {
+    let from = version(fields);
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        for (old, new) in migration.renamed {
+            if let Some(value) = take(fields, old) {
+                put(fields, new, value);
+            }
+        }
+        for (path, old, new) in migration.values {
+            if let Some(value) = lookup(fields, path).filter(|v| v.as_str() == Some(old)) {
+                *value = Value::from(*new);
+            }
+        }
+    }
+    if from < SCHEMA_VERSION {
+        stamp(fields);
+    }
+    from
+}
+
+fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Lay `fields` over `root` value by value, keeping each one only if `root`
+/// still reads as a `T` with it.
+fn overlay<T: DeserializeOwned>(root: &mut Value, path: &[String], fields: &Map<String, Value>) {
+    for (key, value) in fields {
+        let mut at = path.to_vec();
+        at.push(key.clone());
+        if let (Some(Value::Object(_)), Value::Object(inner)) = (root.pointer(&pointer(&at)), value)
+        {
+            overlay::<T>(root, &at, inner);
+            continue;
+        }
+        let Some(parent) = root
+            .pointer_mut(&pointer(path))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+        let previous = parent.insert(key.clone(), value.clone());
+        if T::deserialize(&*root).is_ok() {
+            continue;
+        }
+        if let Some(parent) = root
+            .pointer_mut(&pointer(path))
+            .and_then(Value::as_object_mut)
+        {
+            match previous {
+                Some(previous) => parent.insert(key.clone(), previous),
+                None => parent.remove(key),
+            };
+        }
+    }
+}
+
+/// Read a file from a newer build: start from the defaults and take each
+/// value from `fields` that this build can read, leaving the rest.
+pub(crate) fn lenient<T: Serialize + DeserializeOwned + Default>(
+    fields: &Map<String, Value>,
+) -> Result<T, String> {
+    let mut root = serde_json::to_value(T::default()).map_err(|e| e.to_string())?;
+    overlay::<T>(&mut root, &[], fields);
+    T::deserialize(root).map_err(|e| e.to_string())
+}
+
+/// Note that the file on disk is from a newer build (schema `from`), so
+/// `settings::save` leaves it alone.
+pub(crate) fn keep_newer(path: &Path, from: u32) {
+    NEWER_FILE.store(true, Ordering::Relaxed);
+    tracing::warn!(
+        "[Settings] {} is from a newer version (schema {}, this build knows {}); it won't be changed, and settings changed now last until quit",
+        path.display(),
+        from,
+        SCHEMA_VERSION
+    );
+}
+
+/// Whether the file on disk is from a newer build and mustn't be written.
+pub(crate) fn newer_file() -> bool {
+    NEWER_FILE.load(Ordering::Relaxed)
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Copy the file as it was before migrating, as `<name>.v<from>.json`.
+pub(crate) fn keep_original(path: &Path, from: u32) {
+    let copy = path.with_extension(format!("v{}.json", from));
+    if let Err(e) = std::fs::copy(path, &copy) {
+        tracing::warn!(
+            "[Settings] Could not keep a copy of the old settings: {}",
+            e
+        );
+    }
+}
+
+/// Move an unreadable settings file out of the way, remembering why for
+/// `get_settings_recovery`. False if it couldn't be moved, so it mustn't be
+/// overwritten.
+pub(crate) fn quarantine(path: &Path, reason: String) -> bool {
+    let dest = path.with_extension(format!("corrupt-{}.json", unix_secs()));
+    if let Err(e) = std::fs::rename(path, &dest) {
+        tracing::warn!("[Settings] Could not move {} aside: {}", path.display(), e);
+        return false;
+    }
+    tracing::warn!(
+        "[Settings] {} was unreadable ({}); moved to {} and reset to defaults",
+        path.display(),
+        reason,
+        dest.display()
+    );
+    *RECOVERY.lock().unwrap_or_else(|e| e.into_inner()) = Some(Recovery {
+        quarantined: dest.to_string_lossy().into_owned(),
+        reason,
+    });
+    true
+}
+
+/// Put a reset at startup in the audit log, once it's open. Called in setup.
+pub(crate) fn start() {
+    let recovery = RECOVERY.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(recovery) = recovery {
+        crate::audit::record("settings_reset", recovery.quarantined);
+    }
+}
+
+/// Whether the settings were reset at this launch because the file was
+/// unreadable, and where it went.
+#[tauri::command]
+pub(crate) async fn get_settings_recovery() -> Result<Option<Recovery>, String> {
+    Ok(RECOVERY.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(fields) => fields,
+            _ => unreachable!(),
+        }
+    }
+
+    #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Mode {
+        #[default]
+        Plain,
+        Fancy,
+    }
+
+    #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Inner {
+        mode: Mode,
+        size: u32,
+    }
+
+    #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Sample {
+        mode: Mode,
+        name: String,
+        inner: Inner,
+    }
+
+    #[test]
+    fn unversioned_files_are_stamped() {
+        let mut fields = object(json!({"theme": "dark"}));
+        assert_eq!(migrate(&mut fields), 0);
+        assert_eq!(version(&fields), SCHEMA_VERSION);
+        assert_eq!(fields["theme"], "dark");
+    }
+
+    #[test]
+    fn current_files_are_left_alone() {
+        let original = object(json!({"schema_version": SCHEMA_VERSION, "theme": "dark"}));
+        let mut fields = original.clone();
+        assert_eq!(migrate(&mut fields), SCHEMA_VERSION);
+        assert_eq!(fields, original);
+    }
+
+    #[test]
+    fn newer_files_keep_their_version() {
+        let newer = SCHEMA_VERSION + 1;
+        let original = object(json!({"schema_version": newer, "added_later": true}));
+        let mut fields = original.clone();
+        assert_eq!(migrate(&mut fields), newer);
+        assert_eq!(fields, original);
+    }
+
+    #[test]
+    fn paths_move_between_objects() {
+        let mut fields = object(json!({"startup": {"backend": "eager"}}));
+        let value = take(&mut fields, "startup.backend").unwrap();
+        put(&mut fields, "launch.backend.mode", value);
+        assert_eq!(
+            Value::Object(fields),
+            json!({"startup": {}, "launch": {"backend": {"mode": "eager"}}})
+        );
+        assert!(take(&mut object(json!({"a": 1})), "a.b").is_none());
+    }
+
+    #[test]
+    fn lenient_keeps_defaults_for_unknown_values() {
+        let fields = object(json!({
+            "schema_version": SCHEMA_VERSION + 1,
+            "mode": "added_later",
+            "name": "kept",
+            "inner": {"mode": "fancy", "size": "not a number", "extra": 1},
+        }));
+        let sample: Sample = lenient(&fields).unwrap();
+        assert_eq!(
+            sample,
+            Sample {
+                mode: Mode::Plain,
+                name: "kept".to_string(),
+                inner: Inner {
+                    mode: Mode::Fancy,
+                    size: 0,
+                },
+            }
+        );
+    }
+}
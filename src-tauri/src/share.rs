@@ -0,0 +1,407 @@
+//! Read-only sharing of a notebook on the local network.
+//!
+//! `start_share_server` takes a text snapshot of the notebook (the same one
+//! the vault seals), renders it to a few static pages and serves them over
+//! plain HTTP on a free port, so someone in the same meeting can browse its
+//! sources from a browser. Nothing is written back: the pages are made once
+//! and the backend is never reached through the server.
+//!
+//! Pages are behind the password given (HTTP basic auth, any user name).
+//! The connection isn't encrypted, so this is meant for a trusted network;
+//! too many wrong passwords stop the server, and it stops on its own after
+//! a few hours. Only a few connections are served at once, each must send
+//! its request within seconds, and stopping a share drops any still open.
+//! Refused while local-only mode is on.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::vault::SealedNotebook;
+
+/// How long a share stays up unless stopped sooner.
+const LIFETIME: Duration = Duration::from_secs(4 * 60 * 60);
+/// Wrong passwords before the server stops.
+const MAX_FAILURES: u32 = 20;
+const MIN_PASSWORD_LEN: usize = 8;
+const MAX_HEAD: usize = 16 * 1024;
+/// How long a client gets to send its request.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections served at once; others wait to be accepted.
+const MAX_CONNECTIONS: usize = 16;
+
+#[derive(Clone, Serialize)]
+pub(crate) struct ShareInfo {
+    notebook_id: String,
+    title: String,
+    port: u16,
+    /// Addresses to open from another device.
+    urls: Vec<String>,
+    /// Unix seconds.
+    started_at: u64,
+    expires_at: u64,
+}
+
+struct Share {
+    info: ShareInfo,
+    server: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// The rendered pages and the password they're behind.
+struct Site {
+    password_hash: [u8; 32],
+    index: String,
+    sources: Vec<String>,
+    failures: Mutex<u32>,
+}
+
+static SHARES: Mutex<Option<HashMap<String, Share>>> = Mutex::new(None);
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title><style>\
+         body{{font-family:system-ui,sans-serif;max-width:50rem;margin:2rem auto;padding:0 1rem;line-height:1.5;color:#222}}\
+         pre{{white-space:pre-wrap;word-wrap:break-word;font-family:inherit}}\
+         a{{color:#2457c5}} li{{margin:.3rem 0}} .muted{{color:#777;font-size:.9rem}}\
+         </style></head><body>{}<p class=\"muted\">Shared read-only from LocalBook</p></body></html>",
+        escape(title),
+        body
+    )
+}
+
+fn render(notebook: &SealedNotebook, password: &str) -> Site {
+    let sources: Vec<_> = notebook
+        .sources
+        .iter()
+        .filter(|s| !s.content.trim().is_empty())
+        .collect();
+    let mut list = String::new();
+    for (i, source) in sources.iter().enumerate() {
+        list.push_str(&format!(
+            "<li><a href=\"/sources/{}\">{}</a>{}</li>",
+            i,
+            escape(&source.title),
+            if source.is_note {
+                " <span class=\"muted\">note</span>"
+            } else {
+                ""
+            }
+        ));
+    }
+    let description = notebook
+        .description
+        .as_deref()
+        .map(|d| format!("<p>{}</p>", escape(d)))
+        .unwrap_or_default();
+    let index = page(
+        &notebook.title,
+        &format!(
+            "<h1>{}</h1>{}<h2>Sources ({})</h2><ul>{}</ul>",
+            escape(&notebook.title),
+            description,
+            sources.len(),
+            list
+        ),
+    );
+    let pages = sources
+        .iter()
+        .map(|s| {
+            page(
+                &s.title,
+                &format!(
+                    "<p><a href=\"/\">← {}</a></p><h1>{}</h1><pre>{}</pre>",
+                    escape(&notebook.title),
+                    escape(&s.title),
+                    escape(&s.content)
+                ),
+            )
+        })
+        .collect();
+    Site {
+        password_hash: Sha256::digest(password.as_bytes()).into(),
+        index,
+        sources: pages,
+        failures: Mutex::new(0),
+    }
+}
+
+/// Whether the request's basic auth carries the password with this SHA-256,
+/// comparing hashes so the time taken doesn't give it away. Also used by
+/// `serve`.
+pub(crate) fn basic_auth(head: &str, password_hash: &[u8; 32]) -> bool {
+    let Some(encoded) = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let value = value.trim();
+        (name.eq_ignore_ascii_case("authorization") && value.len() > 6)
+            .then(|| {
+                value
+                    .strip_prefix("Basic ")
+                    .or(value.strip_prefix("basic "))
+            })
+            .flatten()
+    }) else {
+        return false;
+    };
+    let Some(decoded) = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|d| String::from_utf8(d).ok())
+    else {
+        return false;
+    };
+    let password = decoded.split_once(':').map_or("", |(_, p)| p);
+    let hash: [u8; 32] = Sha256::digest(password.as_bytes()).into();
+    hash.iter()
+        .zip(password_hash.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+/// The request line and headers, or `None` if the client hung up or took
+/// too long.
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Option<Vec<u8>>> {
+    let read = async {
+        let mut head = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_HEAD {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        Ok(Some(head))
+    };
+    tokio::time::timeout(HEAD_TIMEOUT, read)
+        .await
+        .unwrap_or(Ok(None))
+}
+
+async fn respond(stream: &mut TcpStream, site: &Site) -> std::io::Result<bool> {
+    let Some(head) = read_head(stream).await? else {
+        return Ok(true);
+    };
+    let head = String::from_utf8_lossy(&head);
+    let mut request = head.split_whitespace();
+    let method = request.next().unwrap_or_default();
+    let path = request.next().unwrap_or_default();
+
+    let mut keep_serving = true;
+    let (status, body) = if method != "GET" && method != "HEAD" {
+        ("405 Method Not Allowed", String::new())
+    } else if !basic_auth(&head, &site.password_hash) {
+        if head.to_ascii_lowercase().contains("\nauthorization:") {
+            let mut failures = site.failures.lock().unwrap_or_else(|e| e.into_inner());
+            *failures += 1;
+            keep_serving = *failures < MAX_FAILURES;
+        }
+        ("401 Unauthorized", String::new())
+    } else if path == "/" {
+        ("200 OK", site.index.clone())
+    } else if let Some(page) = path
+        .strip_prefix("/sources/")
+        .and_then(|i| i.parse::<usize>().ok())
+        .and_then(|i| site.sources.get(i))
+    {
+        ("200 OK", page.clone())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    let challenge = if status.starts_with("401") {
+        "WWW-Authenticate: Basic realm=\"LocalBook\", charset=\"UTF-8\"\r\n"
+    } else {
+        ""
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nX-Frame-Options: DENY\r\nReferrer-Policy: no-referrer\r\n\
+         Content-Security-Policy: default-src 'none'; style-src 'unsafe-inline'\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        challenge,
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    if method == "GET" {
+        stream.write_all(body.as_bytes()).await?;
+    }
+    stream.shutdown().await?;
+    Ok(keep_serving)
+}
+
+/// This machine's address on the local network, as other devices see it.
+/// Connecting a UDP socket sends nothing; it only picks the outgoing route.
+pub(crate) fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("192.0.2.1", 80)).ok()?;
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+fn shares() -> std::sync::MutexGuard<'static, Option<HashMap<String, Share>>> {
+    SHARES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn stop(notebook_id: &str, reason: &str) -> bool {
+    let Some(share) = shares().as_mut().and_then(|s| s.remove(notebook_id)) else {
+        return false;
+    };
+    share.server.abort();
+    tracing::info!("[Share] Stopped sharing {} ({})", notebook_id, reason);
+    crate::audit::record("share_stopped", format!("{} ({})", notebook_id, reason));
+    true
+}
+
+/// Serve a read-only view of a notebook on the local network. Sharing a
+/// notebook that's already shared restarts it with a fresh snapshot.
+#[tauri::command]
+pub(crate) async fn start_share_server(
+    notebook_id: String,
+    password: String,
+) -> Result<ShareInfo, String> {
+    if crate::privacy::local_only() {
+        return Err("Local-only mode is on".to_string());
+    }
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "Use a password of at least {} characters",
+            MIN_PASSWORD_LEN
+        ));
+    }
+    let notebook = crate::vault::export(&notebook_id).await?;
+    let site = Arc::new(render(&notebook, &password));
+    stop(&notebook_id, "restarted");
+
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .await
+        .map_err(|e| format!("Failed to start the share server: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let host = lan_address().map_or_else(|| "localhost".to_string(), |ip| ip.to_string());
+    let started_at = crate::models::now_secs();
+    let info = ShareInfo {
+        notebook_id: notebook_id.clone(),
+        title: notebook.title.clone(),
+        port,
+        urls: vec![format!("http://{}:{}/", host, port)],
+        started_at,
+        expires_at: started_at + LIFETIME.as_secs(),
+    };
+
+    let id = notebook_id.clone();
+    // Connections live in the server task's `JoinSet`, so aborting the server
+    // (on stop or expiry) drops them too.
+    let server = tauri::async_runtime::spawn(async move {
+        let limit = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        let mut connections = JoinSet::new();
+        let serve = async {
+            loop {
+                let Ok(permit) = limit.clone().acquire_owned().await else {
+                    break;
+                };
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                };
+                while connections.try_join_next().is_some() {}
+                let site = site.clone();
+                let id = id.clone();
+                connections.spawn(async move {
+                    let _permit = permit;
+                    if let Ok(false) = respond(&mut stream, &site).await {
+                        tracing::warn!("[Share] Too many wrong passwords for {}", id);
+                        stop(&id, "too many wrong passwords");
+                    }
+                });
+            }
+        };
+        if tokio::time::timeout(LIFETIME, serve).await.is_err() {
+            stop(&id, "expired");
+        }
+    });
+    shares().get_or_insert_with(HashMap::new).insert(
+        notebook_id.clone(),
+        Share {
+            info: info.clone(),
+            server,
+        },
+    );
+    tracing::info!("[Share] Sharing {} on port {}", notebook_id, port);
+    crate::audit::record("share_started", format!("{} on port {}", notebook_id, port));
+    Ok(info)
+}
+
+#[tauri::command]
+pub(crate) async fn stop_share_server(notebook_id: String) -> Result<bool, String> {
+    Ok(stop(&notebook_id, "stopped"))
+}
+
+#[tauri::command]
+pub(crate) async fn list_share_servers() -> Result<Vec<ShareInfo>, String> {
+    Ok(shares()
+        .as_ref()
+        .map(|s| s.values().map(|share| share.info.clone()).collect())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(credentials: &str) -> String {
+        format!(
+            "GET / HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic {}\r\n\r\n",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        )
+    }
+
+    fn hash(password: &str) -> [u8; 32] {
+        Sha256::digest(password.as_bytes()).into()
+    }
+
+    #[test]
+    fn accepts_the_password_with_any_user() {
+        assert!(basic_auth(&head("reader:hunter2"), &hash("hunter2")));
+        assert!(basic_auth(&head(":hunter2"), &hash("hunter2")));
+        let lower = head("x:hunter2").replace("Authorization: Basic", "authorization: basic");
+        assert!(basic_auth(&lower, &hash("hunter2")));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert!(!basic_auth(&head("reader:wrong"), &hash("hunter2")));
+        assert!(!basic_auth("GET / HTTP/1.1\r\n\r\n", &hash("hunter2")));
+        assert!(!basic_auth(
+            "GET / HTTP/1.1\r\nAuthorization: Basic !!!\r\n\r\n",
+            &hash("hunter2")
+        ));
+        assert!(!basic_auth(
+            "GET / HTTP/1.1\r\nAuthorization: Bearer hunter2\r\n\r\n",
+            &hash("hunter2")
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! Keyboard shortcuts the user can rebind.
+//!
+//! Every rebindable action is listed in `ACTIONS` with its default. Global
+//! ones work while another app has focus and are registered here with the
+//! global-shortcut plugin; the rest are handled by the webview, which reads
+//! them with `get_shortcuts` and listens for `shortcuts://changed`. Settings
+//! keep only what the user changed (an empty accelerator unbinds the
+//! action), so a default changed in a later version still reaches them.
+//!
+//! `set_shortcut` refuses an accelerator that another action already has, or
+//! that the app menu or the OS reserve (copy, paste, quit…), and a global one
+//! that can't be registered, perhaps because another app holds it; the
+//! previous binding then stays. Accelerators are compared after resolving
+//! `CmdOrCtrl` for this platform and ignoring case, modifier order and
+//! aliases such as `Option` for `Alt`.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+struct Action {
+    id: &'static str,
+    label: &'static str,
+    default: &'static str,
+    global: bool,
+}
+
+const ACTIONS: &[Action] = &[
+    Action {
+        id: "mini_mode",
+        label: "Toggle mini chat",
+        default: crate::windows::MINI_MODE_SHORTCUT,
+        global: true,
+    },
+    Action {
+        id: "command_palette",
+        label: "Command palette",
+        default: "CmdOrCtrl+K",
+        global: false,
+    },
+    Action {
+        id: "back",
+        label: "Previous view",
+        default: "CmdOrCtrl+[",
+        global: false,
+    },
+    Action {
+        id: "view_chat",
+        label: "Chat",
+        default: "CmdOrCtrl+1",
+        global: false,
+    },
+    Action {
+        id: "view_library",
+        label: "Library",
+        default: "CmdOrCtrl+2",
+        global: false,
+    },
+    Action {
+        id: "view_constellation",
+        label: "Constellation",
+        default: "CmdOrCtrl+3",
+        global: false,
+    },
+    Action {
+        id: "view_timeline",
+        label: "Timeline",
+        default: "CmdOrCtrl+4",
+        global: false,
+    },
+    Action {
+        id: "view_curator",
+        label: "Curator",
+        default: "CmdOrCtrl+5",
+        global: false,
+    },
+];
+
+/// Taken by the edit and app menus or the OS.
+const RESERVED: &[(&str, &str)] = &[
+    ("CmdOrCtrl+Q", "Quit"),
+    ("CmdOrCtrl+W", "Close window"),
+    ("CmdOrCtrl+C", "Copy"),
+    ("CmdOrCtrl+X", "Cut"),
+    ("CmdOrCtrl+V", "Paste"),
+    ("CmdOrCtrl+A", "Select all"),
+    ("CmdOrCtrl+Z", "Undo"),
+    ("CmdOrCtrl+Shift+Z", "Redo"),
+    ("CmdOrCtrl+H", "Hide"),
+    ("CmdOrCtrl+M", "Minimize"),
+    ("Alt+F4", "Close window"),
+    ("Alt+Tab", "Switch apps"),
+    ("Super+Tab", "Switch apps"),
+    ("Super+Space", "Spotlight"),
+];
+
+const SHIFT: u8 = 1;
+const CTRL: u8 = 2;
+const ALT: u8 = 4;
+const SUPER: u8 = 8;
+
+/// An accelerator reduced to what decides which keys press it.
+#[derive(PartialEq, Eq)]
+struct Combo {
+    modifiers: u8,
+    key: String,
+}
+
+fn key_name(key: &str) -> String {
+    let upper = key.to_uppercase();
+    let upper = upper
+        .strip_prefix("KEY")
+        .or_else(|| upper.strip_prefix("DIGIT"))
+        .filter(|k| k.len() == 1)
+        .unwrap_or(&upper);
+    let name = match upper {
+        "BRACKETLEFT" => "[",
+        "BRACKETRIGHT" => "]",
+        "COMMA" => ",",
+        "PERIOD" => ".",
+        "SLASH" => "/",
+        "BACKSLASH" => "\\",
+        "EQUAL" => "=",
+        "MINUS" => "-",
+        "BACKQUOTE" => "`",
+        "SEMICOLON" => ";",
+        "QUOTE" => "'",
+        "ESC" => "ESCAPE",
+        "RETURN" => "ENTER",
+        "DEL" => "DELETE",
+        other => other,
+    };
+    name.to_string()
+}
+
+fn parse(accelerator: &str) -> Result<Combo, String> {
+    let invalid = |why: &str| format!("Invalid shortcut {:?}: {}", accelerator, why);
+    let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    let (key, modifiers) = match parts.split_last() {
+        Some((key, modifiers)) if !key.is_empty() => (key, modifiers),
+        _ => return Err(invalid("no key")),
+    };
+    let mut combo = Combo {
+        modifiers: 0,
+        key: key_name(key),
+    };
+    for modifier in modifiers {
+        let bit = match modifier.to_uppercase().as_str() {
+            "SHIFT" => SHIFT,
+            "CTRL" | "CONTROL" => CTRL,
+            "ALT" | "OPTION" => ALT,
+            "CMD" | "COMMAND" | "SUPER" | "META" => SUPER,
+            "CMDORCTRL" | "CMDORCONTROL" | "COMMANDORCTRL" | "COMMANDORCONTROL" => {
+                if cfg!(target_os = "macos") {
+                    SUPER
+                } else {
+                    CTRL
+                }
+            }
+            _ => return Err(invalid(&format!("{:?} is not a modifier", modifier))),
+        };
+        combo.modifiers |= bit;
+    }
+    Ok(combo)
+}
+
+fn action(id: &str) -> Result<&'static Action, String> {
+    ACTIONS
+        .iter()
+        .find(|a| a.id == id)
+        .ok_or_else(|| format!("Unknown shortcut action: {}", id))
+}
+
+/// What `action` is bound to; None if the user unbound it.
+fn binding(overrides: &HashMap<String, String>, action: &Action) -> Option<String> {
+    let accelerator = overrides
+        .get(action.id)
+        .map(String::as_str)
+        .unwrap_or(action.default);
+    (!accelerator.is_empty()).then(|| accelerator.to_string())
+}
+
+/// What action `id` is bound to now.
+pub(crate) fn accelerator(app: &AppHandle, id: &str) -> Option<String> {
+    binding(&crate::settings::get(app).shortcuts, action(id).ok()?)
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Shortcut {
+    action: &'static str,
+    label: &'static str,
+    /// None when unbound.
+    accelerator: Option<String>,
+    default: &'static str,
+    global: bool,
+}
+
+fn list(app: &AppHandle) -> Vec<Shortcut> {
+    let overrides = crate::settings::get(app).shortcuts;
+    ACTIONS
+        .iter()
+        .map(|a| Shortcut {
+            action: a.id,
+            label: a.label,
+            accelerator: binding(&overrides, a),
+            default: a.default,
+            global: a.global,
+        })
+        .collect()
+}
+
+/// Why `accelerator` can't be given to `action`.
+fn conflict(
+    overrides: &HashMap<String, String>,
+    action: &Action,
+    accelerator: &str,
+) -> Result<(), String> {
+    let combo = parse(accelerator)?;
+    if action.global && combo.modifiers & !SHIFT == 0 {
+        return Err(format!(
+            "{} would stop the key working in other apps; add Ctrl, Alt or Cmd",
+            accelerator
+        ));
+    }
+    for (reserved, label) in RESERVED {
+        if parse(reserved).is_ok_and(|r| r == combo) {
+            return Err(format!("{} is reserved for {}", accelerator, label));
+        }
+    }
+    for other in ACTIONS.iter().filter(|a| a.id != action.id) {
+        let taken = binding(overrides, other)
+            .and_then(|b| parse(&b).ok())
+            .is_some_and(|b| b == combo);
+        if taken {
+            return Err(format!(
+                "{} is already used for {}",
+                accelerator, other.label
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Register the global shortcuts from settings. Called in setup once the
+/// plugin is in; one that can't be registered is skipped with a warning.
+#[cfg(desktop)]
+pub(crate) fn start(app: &AppHandle) {
+    global::STARTED.store(true, std::sync::atomic::Ordering::Relaxed);
+    let overrides = crate::settings::get(app).shortcuts;
+    for action in ACTIONS.iter().filter(|a| a.global) {
+        if let Some(accelerator) = binding(&overrides, action) {
+            if let Err(e) = global::register(app, action.id, &accelerator) {
+                tracing::warn!("[Shortcuts] {} not registered: {}", action.id, e);
+            }
+        }
+    }
+}
+
+#[cfg(desktop)]
+pub(crate) use global::on_global_shortcut;
+
+#[cfg(desktop)]
+mod global {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use tauri::AppHandle;
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    /// The plugin is in; it isn't for a command-line run.
+    pub(super) static STARTED: AtomicBool = AtomicBool::new(false);
+    /// What's registered, by action.
+    static REGISTERED: Mutex<Vec<(&'static str, Shortcut)>> = Mutex::new(Vec::new());
+
+    fn registered() -> std::sync::MutexGuard<'static, Vec<(&'static str, Shortcut)>> {
+        REGISTERED.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(super) fn register(
+        app: &AppHandle,
+        action: &'static str,
+        accelerator: &str,
+    ) -> Result<(), String> {
+        let shortcut: Shortcut = accelerator
+            .parse()
+            .map_err(|e| format!("Invalid shortcut {:?}: {}", accelerator, e))?;
+        app.global_shortcut()
+            .register(shortcut)
+            .map_err(|e| format!("Could not register {}: {}", accelerator, e))?;
+        registered().push((action, shortcut));
+        Ok(())
+    }
+
+    pub(super) fn unregister(app: &AppHandle, action: &str) {
+        let mut registered = registered();
+        registered.retain(|(a, shortcut)| {
+            if *a != action {
+                return true;
+            }
+            if let Err(e) = app.global_shortcut().unregister(*shortcut) {
+                tracing::warn!("[Shortcuts] Could not unregister {}: {}", action, e);
+            }
+            false
+        });
+    }
+
+    /// Move `action` to `accelerator` (None unbinds it), or leave it as it
+    /// was if the new one can't be registered.
+    pub(super) fn rebind(
+        app: &AppHandle,
+        action: &'static str,
+        old: Option<&str>,
+        accelerator: Option<&str>,
+    ) -> Result<(), String> {
+        if !STARTED.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        unregister(app, action);
+        let Some(accelerator) = accelerator else {
+            return Ok(());
+        };
+        let result = register(app, action, accelerator);
+        if result.is_err() {
+            if let Some(old) = old {
+                let _ = register(app, action, old);
+            }
+        }
+        result
+    }
+
+    /// The plugin's handler: run the action `shortcut` is bound to.
+    pub(crate) fn on_global_shortcut(app: &AppHandle, shortcut: &Shortcut) {
+        let action = registered()
+            .iter()
+            .find(|(_, s)| s == shortcut)
+            .map(|(a, _)| *a);
+        match action {
+            Some("mini_mode") => crate::windows::toggle_mini_window(app, None),
+            Some(other) => tracing::warn!("[Shortcuts] No handler for {}", other),
+            None => {}
+        }
+    }
+}
+
+/// Update the places that show the binding.
+fn bound(app: &AppHandle, action: &str, accelerator: Option<&str>) {
+    if action == "mini_mode" {
+        crate::tray::set_mini_shortcut(accelerator);
+    }
+    let _ = app.emit("shortcuts://changed", list(app));
+}
+
+/// Every rebindable action with what it's bound to now.
+#[tauri::command]
+pub(crate) async fn get_shortcuts(app: AppHandle) -> Result<Vec<Shortcut>, String> {
+    Ok(list(&app))
+}
+
+/// Bind `action` to `accelerator`: an empty one unbinds it, None puts back
+/// the default. Returns every shortcut.
+#[tauri::command]
+pub(crate) async fn set_shortcut(
+    app: AppHandle,
+    action: String,
+    accelerator: Option<String>,
+) -> Result<Vec<Shortcut>, String> {
+    let action = self::action(&action)?;
+    let mut overrides = crate::settings::get(&app).shortcuts;
+    let old = binding(&overrides, action);
+    match accelerator.as_deref().map(str::trim) {
+        None => overrides.remove(action.id),
+        Some(accelerator) => overrides.insert(action.id.to_string(), accelerator.to_string()),
+    };
+    let new = binding(&overrides, action);
+    if let Some(accelerator) = &new {
+        conflict(&overrides, action, accelerator)?;
+    }
+    if old == new {
+        crate::settings::update(&app, |s| s.shortcuts = overrides)?;
+        return Ok(list(&app));
+    }
+    #[cfg(desktop)]
+    if action.global {
+        global::rebind(&app, action.id, old.as_deref(), new.as_deref())?;
+    }
+    crate::settings::update(&app, |s| s.shortcuts = overrides)?;
+    tracing::info!(
+        "[Shortcuts] {} -> {}",
+        action.id,
+        new.as_deref().unwrap_or("(none)")
+    );
+    bound(&app, action.id, new.as_deref());
+    Ok(list(&app))
+}
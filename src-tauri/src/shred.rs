@@ -0,0 +1,248 @@
+//! Secure deletion of a single document.
+//!
+//! Deleting a source through the backend unlinks rows and files but leaves
+//! the bytes behind: SQLite reuses freed pages without clearing them, and the
+//! page images extracted from the document stay in the images cache.
+//! `secure_delete` goes further, in this order:
+//!
+//! 1. The stored text, notes, rendered HTML and highlighted passages are
+//!    overwritten in the backend's database on a connection with
+//!    `secure_delete` on, so the old content is zeroed rather than just
+//!    marked free.
+//! 2. Extracted page images (`<backend data>/images/<id>_p*_i*`) are
+//!    overwritten with zeros, flushed, and removed.
+//! 3. The backend deletes the source — its embeddings and index entries, its
+//!    topic-model entry, then the metadata row — and the WAL is checkpointed
+//!    so the zeroed pages replace the originals in the database file.
+//! 4. Import jobs that produced the document are dropped from the job queue
+//!    and history.
+//!
+//! LanceDB keeps superseded data files until the backend next compacts the
+//! vector store, so embeddings may linger on disk until then; and on SSDs an
+//! overwrite can't guarantee the flash cells themselves are cleared.
+//!
+//! `vault` removes whole notebooks the same way, source by source through
+//! `shred_source`, then shreds the notebook's vector table (`shred_tree`) and
+//! rebuilds the database (`flush_db`) to drop what the backend deleted itself.
+
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::Serialize;
+
+use crate::backend_api;
+
+const DB_FILE: &str = "localbook.db";
+const IMAGES_DIR: &str = "images";
+const DELETE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Serialize)]
+pub(crate) struct SecureDeleteReport {
+    notebook_id: String,
+    /// Bytes of stored text overwritten.
+    content_bytes: usize,
+    images_overwritten: usize,
+    jobs_forgotten: usize,
+}
+
+/// The backend's database, which must already exist: opening it never
+/// creates an empty one in its place.
+fn open_db(data_dir: &Path) -> Result<Connection, String> {
+    let path = data_dir.join(DB_FILE);
+    if !path.is_file() {
+        return Err(format!("No database at {}", path.display()));
+    }
+    let flags = OpenFlags::SQLITE_OPEN_READ_WRITE
+        | OpenFlags::SQLITE_OPEN_URI
+        | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = Connection::open_with_flags(&path, flags)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    conn.busy_timeout(Duration::from_secs(15))
+        .and_then(|_| conn.execute_batch("PRAGMA secure_delete = ON;"))
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+/// Overwrite a source's text in place. Returns (notebook id, bytes cleared).
+fn scrub_row(conn: &Connection, doc_id: &str) -> Result<(String, usize), String> {
+    let row: Option<(String, usize)> = conn
+        .query_row(
+            "SELECT notebook_id, length(CAST(coalesce(content, '') AS BLOB))
+                 + length(CAST(coalesce(notes, '') AS BLOB))
+             FROM sources WHERE id = ?1",
+            [doc_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (notebook_id, bytes) = row.ok_or_else(|| format!("No document {}", doc_id))?;
+    conn.execute(
+        "UPDATE sources
+         SET content = '', notes = NULL,
+             metadata_json = json_remove(coalesce(metadata_json, '{}'), '$.content_html')
+         WHERE id = ?1",
+        [doc_id],
+    )
+    .map_err(|e| format!("Failed to overwrite the stored text: {}", e))?;
+    conn.execute(
+        "UPDATE highlights SET highlighted_text = '', annotation = '' WHERE source_id = ?1",
+        [doc_id],
+    )
+    .map_err(|e| format!("Failed to overwrite the highlights: {}", e))?;
+    Ok((notebook_id, bytes))
+}
+
+/// Zero a file's contents, flush, then unlink it. A symlink is only
+/// unlinked; whatever it points to is left alone.
+pub(crate) fn shred_file(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+        return std::fs::remove_file(path);
+    }
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let zeros = vec![0u8; 64 * 1024];
+    file.seek(SeekFrom::Start(0))?;
+    let mut left = len;
+    while left > 0 {
+        let n = left.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        left -= n as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)
+}
+
+/// Shred the files in `dir` whose names start with `prefix`. Returns how
+/// many were.
+pub(crate) fn shred_prefixed(dir: &Path, prefix: &str) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+        .filter(|e| match shred_file(&e.path()) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!("[Shred] Failed to shred {}: {}", e.path().display(), err);
+                false
+            }
+        })
+        .count()
+}
+
+/// Shred every file under `dir`, then remove it. Returns how many files were
+/// shredded. Symlinks are unlinked, never followed, so nothing outside `dir`
+/// is touched.
+pub(crate) fn shred_tree(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut shredded = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.file_type().is_symlink() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("[Shred] Failed to remove {}: {}", path.display(), e);
+            }
+        } else if meta.is_dir() {
+            shredded += shred_tree(&path);
+        } else {
+            match shred_file(&path) {
+                Ok(()) => shredded += 1,
+                Err(e) => tracing::warn!("[Shred] Failed to shred {}: {}", path.display(), e),
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(dir);
+    shredded
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Steps 1 to 4 for one document; the caller checkpoints (`flush_db`), so
+/// removing many documents does it once.
+pub(crate) async fn shred_source(doc_id: &str) -> Result<SecureDeleteReport, String> {
+    if !valid_id(doc_id) {
+        return Err(format!("Invalid document id: {:?}", doc_id));
+    }
+    let data_dir = crate::backend_data_dir();
+    let id = doc_id.to_string();
+    let (notebook_id, content_bytes, images_overwritten) =
+        tauri::async_runtime::spawn_blocking(move || {
+            let conn = open_db(&data_dir)?;
+            let (notebook_id, bytes) = scrub_row(&conn, &id)?;
+            let images = shred_prefixed(&data_dir.join(IMAGES_DIR), &format!("{}_p", id));
+            Ok::<_, String>((notebook_id, bytes, images))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+    backend_api::delete(
+        &format!("/sources/{}/{}", notebook_id, doc_id),
+        DELETE_TIMEOUT,
+    )
+    .await
+    .map_err(|e| {
+        format!(
+            "Content overwritten, but deleting the document failed: {}",
+            e
+        )
+    })?;
+
+    Ok(SecureDeleteReport {
+        notebook_id,
+        content_bytes,
+        images_overwritten,
+        jobs_forgotten: crate::jobs::forget_source(doc_id),
+    })
+}
+
+/// Checkpoint the WAL so zeroed pages replace the originals in the database
+/// file. With `vacuum`, rebuild the file first, which also drops pages the
+/// backend freed without zeroing them.
+pub(crate) async fn flush_db(vacuum: bool) {
+    let dir = crate::backend_data_dir();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open_db(&dir)?;
+        if vacuum {
+            conn.execute_batch("VACUUM;")
+                .map_err(|e| format!("VACUUM failed: {}", e))?;
+        }
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(|e| format!("WAL checkpoint failed: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .unwrap_or_else(|e| tracing::warn!("[Shred] {}", e));
+}
+
+/// Permanently delete a document, overwriting its stored content first.
+#[tauri::command]
+pub(crate) async fn secure_delete(doc_id: String) -> Result<SecureDeleteReport, String> {
+    let report = shred_source(&doc_id).await?;
+    flush_db(false).await;
+    tracing::info!(
+        "[Shred] Securely deleted {} ({} bytes, {} images)",
+        doc_id,
+        report.content_bytes,
+        report.images_overwritten
+    );
+    crate::audit::record(
+        "document_secure_deleted",
+        format!("{} in notebook {}", doc_id, report.notebook_id),
+    );
+    Ok(report)
+}
@@ -0,0 +1,568 @@
+//! Backend updates without reinstalling the app.
+//!
+//! The `localbook-backend` sidecar is published on its own, with a manifest
+//! (`backend.json`) listing the version, the oldest app it works with, and a
+//! `.tar.gz` per platform. The manifest is signed with the app update key
+//! (`backend.json.sig`) and none of it is used unless that verifies. A
+//! bundle is downloaded like a model (resumable, progress under its id),
+//! checked against the manifest's SHA-256 and verified with the same key,
+//! unpacked to `<app data>/backend/versions/<version>/`, and only then made
+//! active by rewriting `state.json` — a crash at any point leaves the
+//! previous backend in use. `start_backend` prefers the active version over
+//! the bundled one.
+//!
+//! The new backend is restarted into straight away; if it doesn't pass its
+//! first health check the previous backend (or the bundled one) is made
+//! active again and restarted. The manifest can also carry an updated model
+//! manifest, saved as `<app data>/backend/models.json`.
+//!
+//! Every passed health check records the app and backend versions as the
+//! last known-good pair, and that backend is kept on disk. After
+//! `ROLLBACK_AFTER_FAILURES` failed checks in a row (at launch or by the
+//! watchdog) the backend goes back to it automatically; `rollback_update`
+//! does the same on request. An app update can't be undone from here, so
+//! when the app changed since the known-good pair the report says which
+//! version to reinstall.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+const MANIFEST_URL: &str =
+    "https://github.com/patsteph/LocalBook/releases/download/backend-latest/backend.json";
+const BACKEND_DIR: &str = "backend";
+const VERSIONS_DIR: &str = "versions";
+const STATE_FILE: &str = "state.json";
+const MODEL_MANIFEST_FILE: &str = "models.json";
+/// Top-level folder inside a bundle, as in the app's resources.
+const BUNDLE_ROOT: &str = "localbook-backend";
+/// Consecutive failed health checks before going back to the known-good
+/// backend. A version that never passed one goes back on its first failure.
+const ROLLBACK_AFTER_FAILURES: u32 = 3;
+
+static INSTALLING: AtomicBool = AtomicBool::new(false);
+
+/// Which installed backend runs. None = the one bundled with the app.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct State {
+    active: Option<String>,
+    previous: Option<String>,
+    /// The active version hasn't passed a health check yet.
+    unverified: bool,
+    /// Failed health checks in a row since the last one passed.
+    failed_checks: u32,
+    known_good: Option<KnownGood>,
+}
+
+/// The last app and backend versions that passed a health check together.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+struct KnownGood {
+    app_version: String,
+    /// None = the bundled backend.
+    backend: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RollbackReport {
+    /// The backend version now running.
+    backend: String,
+    /// Set when the app itself changed since that backend last worked: the
+    /// app version to reinstall if problems persist.
+    known_good_app_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Artifact {
+    url: String,
+    sha256: String,
+    /// Base64 minisign signature, as for app updates. Not needed for the
+    /// model manifest, which the signed `backend.json` pins by hash.
+    #[serde(default)]
+    signature: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    version: String,
+    #[serde(default)]
+    min_app_version: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    /// Keyed like the app updater: "darwin-aarch64", "windows-x86_64"…
+    platforms: HashMap<String, Artifact>,
+    #[serde(default)]
+    models: Option<Artifact>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BackendUpdateInfo {
+    current: String,
+    latest: String,
+    available: bool,
+    notes: Option<String>,
+}
+
+fn backend_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::shared_data_dir(app)?.join(BACKEND_DIR))
+}
+
+fn exe_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "localbook-backend.exe"
+    } else {
+        "localbook-backend"
+    }
+}
+
+fn platform() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    format!("{}-{}", os, std::env::consts::ARCH)
+}
+
+fn load_state(dir: &Path) -> State {
+    std::fs::read_to_string(dir.join(STATE_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Temp file then rename, so the switch to a new version is atomic.
+fn save_state(dir: &Path, state: &State) -> Result<(), String> {
+    let path = dir.join(STATE_FILE);
+    let tmp = dir.join(format!("{}.tmp", STATE_FILE));
+    let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+fn version_exe(dir: &Path, version: &str) -> PathBuf {
+    dir.join(VERSIONS_DIR)
+        .join(version)
+        .join(BUNDLE_ROOT)
+        .join(exe_name())
+}
+
+/// The installed backend to run instead of the bundled one, if any.
+pub(crate) fn active_exe(app: &AppHandle) -> Option<PathBuf> {
+    let dir = backend_dir(app).ok()?;
+    let exe = version_exe(&dir, &load_state(&dir).active?);
+    exe.exists().then_some(exe)
+}
+
+fn current_version(app: &AppHandle, state: &State) -> String {
+    state
+        .active
+        .clone()
+        .unwrap_or_else(|| app.package_info().version.to_string())
+}
+
+/// The backend version in use: an installed update, or the bundled one,
+/// which carries the app's version.
+pub(crate) fn backend_version(app: &AppHandle) -> String {
+    match backend_dir(app) {
+        Ok(dir) => current_version(app, &load_state(&dir)),
+        Err(_) => app.package_info().version.to_string(),
+    }
+}
+
+fn newer(candidate: &str, than: &str) -> bool {
+    match (
+        semver::Version::parse(candidate),
+        semver::Version::parse(than),
+    ) {
+        (Ok(c), Ok(t)) => c > t,
+        _ => false,
+    }
+}
+
+/// The manifest, once it has verified against its signature.
+async fn fetch_manifest(app: &AppHandle) -> Result<Manifest, String> {
+    crate::privacy::guard(MANIFEST_URL, "Backend update check")?;
+    let client = crate::proxy::client()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    let mut files = Vec::new();
+    for url in [MANIFEST_URL.to_string(), format!("{}.sig", MANIFEST_URL)] {
+        let bytes = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Backend update check failed: {}", e))?
+            .bytes()
+            .await
+            .map_err(|e| format!("Backend update check failed: {}", e))?;
+        files.push(bytes);
+    }
+    let signature = String::from_utf8_lossy(&files[1]);
+    crate::updater::verify(app, &files[0], &signature)
+        .map_err(|e| format!("Backend manifest rejected: {}", e))?;
+    serde_json::from_slice(&files[0]).map_err(|e| format!("Unexpected backend manifest: {}", e))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check a bundle against the app's update key (see `updater`).
+fn verify_signature(app: &AppHandle, path: &Path, signature: &str) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    crate::updater::verify(app, &data, signature)
+        .map_err(|e| format!("Backend bundle rejected: {}", e))
+}
+
+/// Unpack into a staging folder, then rename into place.
+fn unpack(archive: &Path, versions: &Path, version: &str) -> Result<(), String> {
+    let dest = versions.join(version);
+    let staging = versions.join(format!("{}.staging", version));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+    let file = std::fs::File::open(archive).map_err(|e| e.to_string())?;
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(&staging)
+        .map_err(|e| format!("Failed to unpack the backend: {}", e))?;
+    if !staging.join(BUNDLE_ROOT).join(exe_name()).exists() {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!("The bundle has no {}/{}", BUNDLE_ROOT, exe_name()));
+    }
+    let _ = std::fs::remove_dir_all(&dest);
+    std::fs::rename(&staging, &dest).map_err(|e| format!("Failed to install the backend: {}", e))
+}
+
+/// Remove installed versions other than the active, previous and known-good
+/// ones.
+fn prune(dir: &Path, state: &State) {
+    let Ok(entries) = std::fs::read_dir(dir.join(VERSIONS_DIR)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let known_good = state.known_good.as_ref().and_then(|g| g.backend.clone());
+        let keep = [&state.active, &state.previous, &known_good]
+            .iter()
+            .any(|v| v.as_deref() == Some(name.as_str()));
+        if !keep {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+async fn update_model_manifest(
+    app: &AppHandle,
+    dir: &Path,
+    artifact: &Artifact,
+) -> Result<(), String> {
+    crate::privacy::guard(&artifact.url, "Model manifest download")?;
+    let bytes = crate::proxy::client()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?
+        .get(&artifact.url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Model manifest download failed: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    if format!("{:x}", Sha256::digest(&bytes)) != artifact.sha256.to_lowercase() {
+        return Err("Model manifest checksum mismatch".to_string());
+    }
+    serde_json::from_slice::<serde_json::Value>(&bytes)
+        .map_err(|e| format!("Malformed model manifest: {}", e))?;
+    let path = dir.join(MODEL_MANIFEST_FILE);
+    let tmp = dir.join(format!("{}.tmp", MODEL_MANIFEST_FILE));
+    std::fs::write(&tmp, &bytes).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+    let _ = app.emit("backend-update://models", ());
+    Ok(())
+}
+
+/// Make `state` active and restart into it. On a failed health check the
+/// version before it is restored (and restarted) and an error returned.
+async fn switch_to(app: &AppHandle, dir: &Path, mut state: State) -> Result<(), String> {
+    let version = current_version(app, &state);
+    save_state(dir, &state)?;
+    match crate::restart_backend_and_wait(app, &format!("backend {}", version)).await {
+        Ok(()) => {
+            mark_healthy(app, &mut state);
+            save_state(dir, &state)?;
+            prune(dir, &state);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[Sidecar] Backend {} failed its health check: {}",
+                version,
+                e
+            );
+            let restored = rolled_back(&state, state.previous.clone());
+            let back_to = current_version(app, &restored);
+            save_state(dir, &restored)?;
+            if let Err(e) = crate::restart_backend_and_wait(app, "backend rollback").await {
+                tracing::warn!(
+                    "[Sidecar] Restored backend {} is unhealthy too: {}",
+                    back_to,
+                    e
+                );
+            }
+            crate::audit::record("backend_rolled_back", format!("{} → {}", version, back_to));
+            Err(format!(
+                "Backend {} didn't start; went back to {}",
+                version, back_to
+            ))
+        }
+    }
+}
+
+fn mark_healthy(app: &AppHandle, state: &mut State) {
+    state.unverified = false;
+    state.failed_checks = 0;
+    state.known_good = Some(KnownGood {
+        app_version: app.package_info().version.to_string(),
+        backend: state.active.clone(),
+    });
+}
+
+/// The backend to go back to: the known-good one, else the one before the
+/// active one. `Some(None)` is the bundled backend.
+fn rollback_target(state: &State) -> Option<Option<String>> {
+    state
+        .known_good
+        .as_ref()
+        .map(|g| g.backend.clone())
+        .filter(|b| *b != state.active)
+        .or_else(|| state.active.is_some().then(|| state.previous.clone()))
+}
+
+/// `state` with `to` active. The version rolled back from is dropped, so a
+/// later rollback can't land on it.
+fn rolled_back(state: &State, to: Option<String>) -> State {
+    State {
+        active: to,
+        known_good: state.known_good.clone(),
+        ..State::default()
+    }
+}
+
+/// Record a backend health check, at launch or by the watchdog. Enough
+/// failures make the known-good backend active again, which the watchdog
+/// then restarts into.
+pub(crate) fn record_health(app: &AppHandle, healthy: bool) {
+    let Ok(dir) = backend_dir(app) else {
+        return;
+    };
+    let mut state = load_state(&dir);
+    if healthy {
+        let before = state.clone();
+        mark_healthy(app, &mut state);
+        if state == before {
+            return;
+        }
+    } else {
+        state.failed_checks += 1;
+        let limit = if state.unverified {
+            1
+        } else {
+            ROLLBACK_AFTER_FAILURES
+        };
+        if let Some(to) = rollback_target(&state).filter(|_| state.failed_checks >= limit) {
+            let from = current_version(app, &state);
+            state = rolled_back(&state, to);
+            let back_to = current_version(app, &state);
+            tracing::warn!(
+                "[Sidecar] Backend {} failed its health check; going back to {}",
+                from,
+                back_to
+            );
+            crate::audit::record("backend_rolled_back", format!("{} → {}", from, back_to));
+        }
+    }
+    if let Err(e) = save_state(&dir, &state) {
+        tracing::warn!("[Sidecar] {}", e);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn check_backend_update(app: AppHandle) -> Result<BackendUpdateInfo, String> {
+    let manifest = fetch_manifest(&app).await?;
+    let current = current_version(&app, &load_state(&backend_dir(&app)?));
+    let compatible = manifest
+        .min_app_version
+        .as_deref()
+        .is_none_or(|min| !newer(min, &app.package_info().version.to_string()));
+    Ok(BackendUpdateInfo {
+        available: compatible
+            && manifest.platforms.contains_key(&platform())
+            && newer(&manifest.version, &current),
+        current,
+        latest: manifest.version,
+        notes: manifest.notes,
+    })
+}
+
+/// Download, verify and switch to the latest backend. Returns the version
+/// now running.
+#[tauri::command]
+pub(crate) async fn install_backend_update(app: AppHandle) -> Result<String, String> {
+    if INSTALLING.swap(true, Ordering::SeqCst) {
+        return Err("A backend update is already being installed".to_string());
+    }
+    let result = install(&app).await;
+    INSTALLING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn install(app: &AppHandle) -> Result<String, String> {
+    let manifest = fetch_manifest(app).await?;
+    let dir = backend_dir(app)?;
+    let state = load_state(&dir);
+    let current = current_version(app, &state);
+    let app_version = app.package_info().version.to_string();
+    if let Some(min) = manifest
+        .min_app_version
+        .as_deref()
+        .filter(|m| newer(m, &app_version))
+    {
+        return Err(format!(
+            "Backend {} needs LocalBook {} or later",
+            manifest.version, min
+        ));
+    }
+    if !newer(&manifest.version, &current) {
+        return Err(format!("Backend {} is up to date", current));
+    }
+    if semver::Version::parse(&manifest.version).is_err() {
+        return Err(format!("Invalid backend version {:?}", manifest.version));
+    }
+    let artifact = manifest
+        .platforms
+        .get(&platform())
+        .ok_or_else(|| format!("No backend {} for {}", manifest.version, platform()))?;
+    let signature = artifact
+        .signature
+        .as_deref()
+        .ok_or("The backend bundle isn't signed")?;
+
+    let versions = dir.join(VERSIONS_DIR);
+    std::fs::create_dir_all(&versions)
+        .map_err(|e| format!("Failed to create {}: {}", versions.display(), e))?;
+    let part = versions.join(format!("{}.tar.gz.part", manifest.version));
+    let id = format!("localbook-backend-{}", manifest.version);
+    let client = crate::proxy::client()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    tracing::info!("[Sidecar] Downloading backend {}", manifest.version);
+    crate::models::fetch_to_part(app, &client, &id, &artifact.url, None, &part).await?;
+
+    let (expected, sig, version) = (
+        artifact.sha256.to_lowercase(),
+        signature.to_string(),
+        manifest.version.clone(),
+    );
+    let (archive, target, app_handle) = (part.clone(), versions.clone(), app.clone());
+    let checked = tauri::async_runtime::spawn_blocking(move || {
+        if sha256_file(&archive)? != expected {
+            return Err("Backend bundle checksum mismatch".to_string());
+        }
+        verify_signature(&app_handle, &archive, &sig)?;
+        unpack(&archive, &target, &version)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(&part);
+    checked?;
+
+    if let Some(models) = &manifest.models {
+        if let Err(e) = update_model_manifest(app, &dir, models).await {
+            tracing::warn!("[Sidecar] {}", e);
+        }
+    }
+
+    let next = State {
+        active: Some(manifest.version.clone()),
+        previous: state.active.clone(),
+        unverified: true,
+        failed_checks: 0,
+        known_good: state.known_good.clone(),
+    };
+    switch_to(app, &dir, next).await?;
+    tracing::info!("[Sidecar] Backend {} installed", manifest.version);
+    crate::audit::record(
+        "backend_updated",
+        format!("{} → {}", current, manifest.version),
+    );
+    let _ = app.emit("backend-update://installed", &manifest.version);
+    Ok(manifest.version)
+}
+
+/// The model manifest from the last backend update, if one came with it.
+#[tauri::command]
+pub(crate) async fn get_model_manifest(
+    app: AppHandle,
+) -> Result<Option<serde_json::Value>, String> {
+    let path = backend_dir(&app)?.join(MODEL_MANIFEST_FILE);
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| format!("Malformed {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Go back to the last backend that passed a health check (or the previous
+/// one) and restart into it.
+#[tauri::command]
+pub(crate) async fn rollback_update(app: AppHandle) -> Result<RollbackReport, String> {
+    if INSTALLING.swap(true, Ordering::SeqCst) {
+        return Err("A backend update is being installed".to_string());
+    }
+    let result = rollback(&app).await;
+    INSTALLING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn rollback(app: &AppHandle) -> Result<RollbackReport, String> {
+    let dir = backend_dir(app)?;
+    let state = load_state(&dir);
+    let to = rollback_target(&state).ok_or("There's no earlier backend to go back to")?;
+    let from = current_version(app, &state);
+    let restored = rolled_back(&state, to);
+    let backend = current_version(app, &restored);
+    save_state(&dir, &restored)?;
+    crate::audit::record("backend_rolled_back", format!("{} → {}", from, backend));
+    crate::restart_backend_and_wait(app, "backend rollback").await?;
+    record_health(app, true);
+    tracing::info!("[Sidecar] Rolled back from backend {} to {}", from, backend);
+    let app_version = app.package_info().version.to_string();
+    Ok(RollbackReport {
+        backend,
+        known_good_app_version: state
+            .known_good
+            .map(|g| g.app_version)
+            .filter(|v| *v != app_version),
+    })
+}
@@ -0,0 +1,139 @@
+//! Warnings for backend requests that take too long, so the UI can suggest
+//! a smaller model when this machine is struggling.
+//!
+//! Every request `traces` finishes is checked against a threshold: the one
+//! for the longest matching route prefix in `routes`, else `threshold_ms`.
+//! Zero turns a check off — audio and video generation are slow by nature.
+//! A slow one is logged and announced as `perf://slow-request`, at most once
+//! a minute per route so a struggling model doesn't flood the UI. The time
+//! is the one `traces` keeps: until the response starts, which for streamed
+//! chat is the wait for the first token.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::traces::Trace;
+
+const QUIET: Duration = Duration::from_secs(60);
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static SETTINGS: RwLock<Option<SlowRequestSettings>> = RwLock::new(None);
+/// When each route last warned.
+static WARNED: Mutex<Option<HashMap<String, Instant>>> = Mutex::new(None);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SlowRequestSettings {
+    /// For routes not in `routes`; 0 never warns.
+    pub threshold_ms: u64,
+    /// By route prefix ("/chat", "/sources/upload").
+    pub routes: HashMap<String, u64>,
+}
+
+impl Default for SlowRequestSettings {
+    fn default() -> Self {
+        Self {
+            threshold_ms: 5_000,
+            routes: HashMap::from([
+                ("/chat".to_string(), 30_000),
+                ("/audio".to_string(), 0),
+                ("/video".to_string(), 0),
+            ]),
+        }
+    }
+}
+
+impl SlowRequestSettings {
+    fn threshold(&self, path: &str) -> u64 {
+        self.routes
+            .iter()
+            .filter(|(prefix, _)| {
+                path.strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.threshold_ms, |(_, ms)| *ms)
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct SlowRequest {
+    id: String,
+    origin: &'static str,
+    method: String,
+    /// Ids replaced with `:id`, as in `metrics`.
+    route: String,
+    status: Option<u16>,
+    duration_ms: u64,
+    threshold_ms: u64,
+}
+
+pub(crate) fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) =
+        Some(crate::settings::get(app).slow_requests);
+}
+
+/// Called by `traces` for every finished request.
+pub(crate) fn check(trace: &Trace) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let threshold_ms = match &*SETTINGS.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(settings) => settings.threshold(&trace.path),
+        None => return,
+    };
+    if threshold_ms == 0 || trace.duration_ms < threshold_ms {
+        return;
+    }
+    let route = crate::metrics::route(&trace.path);
+    {
+        let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+        let warned = warned.get_or_insert_with(HashMap::new);
+        if warned.get(&route).is_some_and(|at| at.elapsed() < QUIET) {
+            return;
+        }
+        warned.insert(route.clone(), Instant::now());
+    }
+    tracing::warn!(
+        trace_id = %trace.id,
+        "[Perf] {} {} took {}ms (over {}ms)",
+        trace.method,
+        route,
+        trace.duration_ms,
+        threshold_ms
+    );
+    let _ = app.emit(
+        "perf://slow-request",
+        SlowRequest {
+            id: trace.id.clone(),
+            origin: trace.origin,
+            method: trace.method.clone(),
+            route,
+            status: trace.status,
+            duration_ms: trace.duration_ms,
+            threshold_ms,
+        },
+    );
+}
+
+#[tauri::command]
+pub(crate) async fn get_slow_request_settings(
+    app: AppHandle,
+) -> Result<SlowRequestSettings, String> {
+    Ok(crate::settings::get(&app).slow_requests)
+}
+
+#[tauri::command]
+pub(crate) async fn set_slow_request_settings(
+    app: AppHandle,
+    settings: SlowRequestSettings,
+) -> Result<(), String> {
+    crate::settings::update(&app, |s| s.slow_requests = settings.clone())?;
+    *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(settings);
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! The small window shown at launch while the backend starts, instead of a
+//! main window that stays blank until it's ready.
+//!
+//! The main window is hidden in the config and setup opens the splash in
+//! front of it. Without a splash the main window is shown straight away:
+//! when the backend is held back until first use (see `startup`), or when
+//! the splash can't be opened. Starting minimized it stays hidden until
+//! opened from the tray, and starting locked until unlocked (see `lock`).
+//! `setup_backend`
+//! reports each stage as it gets there; they go out as `startup://progress`
+//! (the backend status, as `get_backend_status` gives it) and the splash
+//! shows them. Once the backend is ready, or has failed to start (the main
+//! window then says why), the splash closes and the main window is shown.
+//! If startup hasn't got anywhere after `MAX_WAIT` the main window is shown
+//! regardless — unless a model is still downloading, which it shows
+//! progress for.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::BackendStatus;
+
+pub(crate) const SPLASH_WINDOW_LABEL: &str = "splash";
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+struct State {
+    showing: bool,
+    /// Startup reached ready or error.
+    finished: bool,
+    stage: String,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    showing: false,
+    finished: false,
+    stage: String::new(),
+});
+
+fn state() -> std::sync::MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(main) = app.get_webview_window("main") {
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+}
+
+/// Close the splash and show the main window.
+fn finish(app: &AppHandle) {
+    if !std::mem::take(&mut state().showing) {
+        return;
+    }
+    show_main(app);
+    if let Some(splash) = app.get_webview_window(SPLASH_WINDOW_LABEL) {
+        let _ = splash.close();
+    }
+}
+
+/// Open the splash, or show the main window when there's no splash to wait
+/// for. Called in setup.
+pub(crate) fn start(app: &AppHandle, minimized: bool) {
+    if minimized || crate::cli::active() || crate::lock::is_locked() {
+        return;
+    }
+    let mut current = state();
+    if current.finished || crate::startup::deferred(app) {
+        drop(current);
+        show_main(app);
+        return;
+    }
+    let built = WebviewWindowBuilder::new(
+        app,
+        SPLASH_WINDOW_LABEL,
+        WebviewUrl::App("splash.html".into()),
+    )
+    .title("LocalBook")
+    .inner_size(360.0, 220.0)
+    .resizable(false)
+    .maximizable(false)
+    .minimizable(false)
+    .decorations(false)
+    .center()
+    .focused(true)
+    .build();
+    if let Err(e) = built {
+        tracing::warn!("[Splash] Could not open the splash window: {}", e);
+        drop(current);
+        show_main(app);
+        return;
+    }
+    current.showing = true;
+    drop(current);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(MAX_WAIT).await;
+        if state().stage != "downloading_model" {
+            finish(&app);
+        }
+    });
+}
+
+/// Startup got to `status`. Called by `setup_backend` at each stage.
+pub(crate) fn progress(app: &AppHandle, status: &BackendStatus) {
+    let _ = app.emit("startup://progress", status);
+    let done = matches!(status.stage.as_str(), "ready" | "error");
+    {
+        let mut state = state();
+        state.stage = status.stage.clone();
+        state.finished |= done;
+    }
+    if done {
+        finish(app);
+    }
+}
+
+/// Builder-level window event hook: closing the splash early still brings
+/// up the main window.
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if window.label() == SPLASH_WINDOW_LABEL && matches!(event, WindowEvent::Destroyed) {
+        finish(window.app_handle());
+    }
+}
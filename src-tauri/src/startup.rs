@@ -0,0 +1,159 @@
+//! What happens at launch, for people who keep LocalBook running in the
+//! background and don't want it to cost much until they use it.
+//!
+//! - `backend: on_first_use` holds the backend (and Ollama) back until a
+//!   window gets focus or the shell itself calls the backend, instead of
+//!   starting it with the app. With the window hidden at launch that can be
+//!   much later; the status reads "waiting" meanwhile. Command-line runs
+//!   always start it.
+//! - `start_minimized` keeps the main window hidden in the tray, like
+//!   `--minimized`, unless the launch opened files or a notebook. Without a
+//!   tray to bring it back the window is shown anyway.
+//! - `reopen_last_session` opens the notebook windows and the mini window
+//!   that were open at the last quit (not when starting minimized).
+//! - `skip_index_checks` tells the backend to leave out the embedding
+//!   dimension checks and the derived-store reconcile, which read every
+//!   index; after an upgrade they run regardless.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WindowEvent};
+use tokio::sync::Notify;
+
+static WANTED: AtomicBool = AtomicBool::new(false);
+static FIRST_USE: Notify = Notify::const_new();
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BackendLaunch {
+    #[default]
+    Eager,
+    OnFirstUse,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct StartupSettings {
+    pub backend: BackendLaunch,
+    pub start_minimized: bool,
+    pub reopen_last_session: bool,
+    pub skip_index_checks: bool,
+}
+
+/// The windows open at the last quit.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Session {
+    /// Notebook ids with a window of their own.
+    pub notebooks: Vec<String>,
+    pub mini: bool,
+}
+
+/// Whether setup should hold the backend back until `wait_for_first_use`.
+pub(crate) fn deferred(app: &AppHandle) -> bool {
+    !crate::cli::active() && crate::settings::get(app).startup.backend == BackendLaunch::OnFirstUse
+}
+
+/// Something needs the backend; start it if it's being held back.
+pub(crate) fn backend_wanted() {
+    if !WANTED.swap(true, Ordering::SeqCst) {
+        FIRST_USE.notify_one();
+    }
+}
+
+/// Until `backend_wanted`.
+pub(crate) async fn wait_for_first_use() {
+    if !WANTED.load(Ordering::SeqCst) {
+        FIRST_USE.notified().await;
+    }
+}
+
+/// Whether the main window should stay hidden at launch.
+pub(crate) fn minimized(app: &AppHandle) -> bool {
+    crate::cli::options().minimized
+        || (crate::settings::get(app).startup.start_minimized && !crate::cli::has_launch_work())
+}
+
+/// What the backend needs to know.
+pub(crate) fn backend_env(app: &AppHandle) -> Vec<(&'static str, String)> {
+    if crate::settings::get(app).startup.skip_index_checks {
+        vec![("LOCALBOOK_SKIP_INDEX_CHECKS", "1".to_string())]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Remember which windows are open, while the main window still is.
+pub(crate) fn save_session(app: &AppHandle) {
+    let windows = app.webview_windows();
+    if !windows.contains_key("main") {
+        return;
+    }
+    let session = Session {
+        notebooks: windows
+            .keys()
+            .filter_map(|l| l.strip_prefix(crate::windows::NOTEBOOK_WINDOW_PREFIX))
+            .map(String::from)
+            .collect(),
+        mini: windows.contains_key(crate::windows::MINI_WINDOW_LABEL),
+    };
+    if let Err(e) = crate::settings::update(app, |s| s.last_session = session) {
+        tracing::warn!("[Startup] Could not save the session: {}", e);
+    }
+}
+
+/// Reopen the last session's windows if the user asked for that. Called at
+/// the end of setup.
+pub(crate) fn restore_session(app: &AppHandle, minimized: bool) {
+    let settings = crate::settings::get(app);
+    if !settings.startup.reopen_last_session || minimized || crate::lock::is_locked() {
+        return;
+    }
+    let session = settings.last_session;
+    if session.notebooks.is_empty() && !session.mini {
+        return;
+    }
+    tracing::info!(
+        "[Startup] Reopening {} notebook window(s){}",
+        session.notebooks.len(),
+        if session.mini { " and mini chat" } else { "" }
+    );
+    for id in session.notebooks {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::windows::open_notebook_window(app, id).await {
+                tracing::warn!("[Startup] Could not reopen a notebook window: {}", e);
+            }
+        });
+    }
+    if session.mini {
+        crate::windows::toggle_mini_window(app, None);
+    }
+}
+
+/// Builder-level window event hook: a focused window is a first use, and
+/// the session is saved before closing the main window takes the rest.
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    match event {
+        WindowEvent::Focused(true) => backend_wanted(),
+        WindowEvent::CloseRequested { .. } if window.label() == "main" => {
+            save_session(window.app_handle())
+        }
+        _ => {}
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_startup_settings(app: AppHandle) -> Result<StartupSettings, String> {
+    Ok(crate::settings::get(&app).startup)
+}
+
+/// Takes effect at the next launch.
+#[tauri::command]
+pub(crate) async fn set_startup_settings(
+    app: AppHandle,
+    startup: StartupSettings,
+) -> Result<StartupSettings, String> {
+    Ok(crate::settings::update(&app, |s| s.startup = startup)?.startup)
+}
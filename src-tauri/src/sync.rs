@@ -0,0 +1,818 @@
+//! Notebook sync between devices: which notebooks are shared, and taking in
+//! what another device sends.
+//!
+//! Each notebook has a sync mode (`set_notebook_sync_mode`): local-only, the
+//! default, never leaves the device; metadata-only shares its title,
+//! description and colour but none of its sources; synced shares it all. A
+//! peer sees shared notebooks as offers — id, title and a fingerprint of the
+//! notebook's metadata and source list — and fetches the ones whose
+//! fingerprint changed since it last took them. The mode is checked again
+//! when a notebook is sent, so nothing goes out that the mode doesn't allow. A notebook travels as the same text snapshot the
+//! vault seals (`vault::SealedNotebook`), so original files arrive as their
+//! extracted text. The first time, it's imported as a new backend notebook;
+//! after that the local copy is updated in place, source by source.
+//!
+//! A copy remembers where it came from, and a device that fetches a peer's
+//! copy of one of its own notebooks applies it to the original, so edits
+//! flow both ways. Changes are merged three ways against the version both
+//! sides last had (kept under `<app data>/sync/`); notes edited on both
+//! sides that can't be merged line by line leave the notebook as it is here
+//! and become a conflict (see `conflicts`). The transports are `lan_sync`
+//! and `remote_sync`.
+//!
+//! Whatever the transport, sync health is one status — idle, syncing (n of
+//! m notebooks), error or conflicts — sent as `sync://status`, shown in the
+//! tray and returned by `get_sync_status`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use crate::backend_api;
+use crate::conflicts::Merge;
+use crate::remote_sync::RemoteConfig;
+use crate::vault::{SealedNotebook, SealedSource};
+
+const API_TIMEOUT: Duration = Duration::from_secs(60);
+/// Versions of each notebook kept as sent, as merge bases for peers' copies.
+const KEEP_SENT: usize = 5;
+
+/// A paired device. Its pairing secret is in the keychain (`peer_secret_name`).
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Peer {
+    pub id: String,
+    pub name: String,
+    /// Unix seconds.
+    pub paired_at: u64,
+    /// Unix seconds of the last completed sync.
+    pub last_synced_at: Option<u64>,
+}
+
+/// A notebook copy taken from a peer.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Received {
+    pub peer: String,
+    pub remote_id: String,
+    /// The backend notebook holding the copy.
+    pub local_id: String,
+    pub fingerprint: String,
+    /// `content_hash` of the version taken.
+    #[serde(default)]
+    pub content_hash: String,
+    /// Unix seconds.
+    pub synced_at: u64,
+    /// The peer's copy of one of this device's notebooks; `local_id` is the
+    /// original.
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+/// A notebook both this device and a peer changed in ways that don't merge.
+/// The local notebook was left as it is; the peer's version waits in
+/// `<app data>/sync/conflicts/` for `resolve_conflict`.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Conflict {
+    pub peer: String,
+    pub remote_id: String,
+    pub local_id: String,
+    pub title: String,
+    /// Unix seconds.
+    pub detected_at: u64,
+    /// The peer's version's fingerprint and last change.
+    #[serde(default)]
+    pub fingerprint: String,
+    #[serde(default)]
+    pub remote_updated_at: Option<String>,
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+pub(crate) enum Applied {
+    Imported,
+    Conflict,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SyncSettings {
+    /// This device's id as peers know it; made on first use.
+    pub device_id: String,
+    /// Shown to peers; the host name when unset.
+    pub device_name: Option<String>,
+    /// Advertise and accept connections on the local network.
+    pub lan_enabled: bool,
+    /// Backend ids of the notebooks this device shares in full.
+    pub notebooks: Vec<String>,
+    /// Notebooks shared without their sources.
+    pub metadata_only: Vec<String>,
+    pub peers: Vec<Peer>,
+    /// Copies taken from peers, by `origin_key`.
+    pub received: HashMap<String, Received>,
+    pub conflicts: Vec<Conflict>,
+    /// WebDAV or S3 storage to sync through (see `remote_sync`).
+    pub remote: Option<RemoteConfig>,
+    /// Cloud-synced folder holding the library (see `folder_sync`); None
+    /// keeps it in this machine's data dir.
+    pub library_folder: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SyncMode {
+    #[default]
+    LocalOnly,
+    MetadataOnly,
+    Synced,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SyncHealth {
+    #[default]
+    Idle,
+    Syncing,
+    Error,
+    Conflicts,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub(crate) struct SyncStatus {
+    pub state: SyncHealth,
+    /// While syncing: the device (or "sync storage") and how far along.
+    pub with: Option<String>,
+    pub done: usize,
+    pub total: usize,
+    /// Why the last sync failed, until one succeeds.
+    pub error: Option<String>,
+    pub conflicts: usize,
+    /// Unix seconds of the last sync that finished, either way.
+    pub last_synced_at: Option<u64>,
+}
+
+static STATUS: Mutex<Option<SyncStatus>> = Mutex::new(None);
+
+/// A shared notebook as a peer sees it.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Offer {
+    pub id: String,
+    pub title: String,
+    pub fingerprint: String,
+    /// `<device>/<notebook>` this is a copy of, if it came from a peer.
+    pub origin: Option<String>,
+    /// For a copy: the fingerprint of the origin's version it was taken from.
+    #[serde(default)]
+    pub base: Option<String>,
+    /// When the backend last saw it change (RFC 3339).
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    /// Shared without its sources.
+    #[serde(default)]
+    pub metadata_only: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct SyncProgress {
+    peer: String,
+    title: String,
+    done: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SyncState {
+    device_id: String,
+    device_name: String,
+    lan_enabled: bool,
+    /// Sync mode of each shared notebook; others are local-only.
+    modes: HashMap<String, SyncMode>,
+    peers: Vec<Peer>,
+    remote: Option<RemoteConfig>,
+    conflicts: Vec<Conflict>,
+}
+
+/// Keychain entry for a peer's pairing secret.
+pub(crate) fn peer_secret_name(peer_id: &str) -> String {
+    format!("sync_peer_{}", peer_id)
+}
+
+pub(crate) fn origin_key(device: &str, notebook: &str) -> String {
+    format!("{}/{}", device, notebook)
+}
+
+/// `<app data>/sync/<kind>/<name>.json`, for ids that are safe as file names.
+pub(crate) fn state_path(app: &AppHandle, kind: &str, name: &str) -> Result<PathBuf, String> {
+    let safe = name.replace('/', "_");
+    if safe.is_empty()
+        || !safe
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid sync id: {:?}", name));
+    }
+    Ok(crate::data_dir(app)?
+        .join("sync")
+        .join(kind)
+        .join(format!("{}.json", safe)))
+}
+
+pub(crate) fn save_snapshot(path: &Path, notebook: &SealedNotebook) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let bytes = serde_json::to_vec(notebook).map_err(|e| e.to_string())?;
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub(crate) fn load_snapshot(path: &Path) -> Option<SealedNotebook> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+/// This device's id, made and saved the first time it's needed.
+pub(crate) fn device_id(app: &AppHandle) -> String {
+    let current = crate::settings::get(app).sync.device_id;
+    if !current.is_empty() {
+        return current;
+    }
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let _ = crate::settings::update(app, |s| s.sync.device_id = id.clone());
+    id
+}
+
+pub(crate) fn device_name(app: &AppHandle) -> String {
+    crate::settings::get(app)
+        .sync
+        .device_name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(sysinfo::System::host_name)
+        .unwrap_or_else(|| "LocalBook".to_string())
+}
+
+pub(crate) fn peer(app: &AppHandle, id: &str) -> Option<Peer> {
+    crate::settings::get(app)
+        .sync
+        .peers
+        .into_iter()
+        .find(|p| p.id == id)
+}
+
+pub(crate) fn mode(settings: &SyncSettings, id: &str) -> SyncMode {
+    if settings.notebooks.iter().any(|n| n == id) {
+        SyncMode::Synced
+    } else if settings.metadata_only.iter().any(|n| n == id) {
+        SyncMode::MetadataOnly
+    } else {
+        SyncMode::LocalOnly
+    }
+}
+
+/// Cheap change detection: notebook metadata plus (when its sources are
+/// shared) each source's id, name and size, without downloading any content.
+async fn fingerprint(
+    notebook: &serde_json::Value,
+    notebook_id: &str,
+    with_sources: bool,
+) -> Result<String, String> {
+    let sources = if with_sources {
+        backend_api::get_json(&format!("/sources/{}", notebook_id), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        serde_json::Value::Null
+    };
+    let mut entries: Vec<String> = sources
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|s| {
+            format!(
+                "{}|{}|{}|{}",
+                s["id"].as_str().unwrap_or_default(),
+                s["filename"].as_str().unwrap_or_default(),
+                s["characters"],
+                s["content_hash"].as_str().unwrap_or_default()
+            )
+        })
+        .collect();
+    entries.sort();
+    let mut hasher = Sha256::new();
+    for field in ["title", "description", "color"] {
+        hasher.update(notebook[field].to_string());
+        hasher.update([0]);
+    }
+    for entry in entries {
+        hasher.update(entry);
+        hasher.update([0]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The notebooks this device shares.
+pub(crate) async fn offers(app: &AppHandle) -> Vec<Offer> {
+    let settings = crate::settings::get(app).sync;
+    let mut offers = Vec::new();
+    for id in settings.notebooks.iter().chain(&settings.metadata_only) {
+        let metadata_only = mode(&settings, id) == SyncMode::MetadataOnly;
+        let notebook = match backend_api::get_json(&format!("/notebooks/{}", id), API_TIMEOUT).await
+        {
+            Ok(nb) => nb,
+            Err(e) => {
+                tracing::warn!("[Sync] Skipping notebook {}: {}", id, e);
+                continue;
+            }
+        };
+        let fingerprint = match fingerprint(&notebook, id, !metadata_only).await {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::warn!("[Sync] Skipping notebook {}: {}", id, e);
+                continue;
+            }
+        };
+        let copy_of = settings
+            .received
+            .iter()
+            .find(|(_, r)| &r.local_id == id && !r.mirror);
+        offers.push(Offer {
+            id: id.clone(),
+            title: notebook["title"].as_str().unwrap_or("Notebook").to_string(),
+            fingerprint,
+            origin: copy_of.map(|(key, _)| key.clone()),
+            base: copy_of.map(|(_, r)| r.fingerprint.clone()),
+            updated_at: notebook["updated_at"].as_str().map(String::from),
+            metadata_only,
+        });
+    }
+    offers
+}
+
+/// Whether `id` is a notebook this device shares (the only ones a peer may
+/// fetch).
+pub(crate) fn is_shared(app: &AppHandle, id: &str) -> bool {
+    mode(&crate::settings::get(app).sync, id) != SyncMode::LocalOnly
+}
+
+/// The offers from `peer` that are new or changed since last time, leaving
+/// out versions already waiting as a conflict.
+pub(crate) fn wanted(app: &AppHandle, peer: &str, offers: Vec<Offer>) -> Vec<Offer> {
+    let settings = crate::settings::get(app).sync;
+    offers
+        .into_iter()
+        .filter(|o| {
+            settings
+                .received
+                .get(&origin_key(peer, &o.id))
+                .is_none_or(|r| r.fingerprint != o.fingerprint)
+        })
+        .filter(|o| {
+            !settings
+                .conflicts
+                .iter()
+                .any(|c| c.peer == peer && c.remote_id == o.id && c.fingerprint == o.fingerprint)
+        })
+        .collect()
+}
+
+/// A shared notebook's snapshot, for sending, with only what its sync mode
+/// allows. The version is kept, as the merge base for when the peer's copy
+/// comes back with edits.
+pub(crate) async fn snapshot(
+    app: &AppHandle,
+    id: &str,
+    fingerprint: &str,
+) -> Result<SealedNotebook, String> {
+    let notebook = match mode(&crate::settings::get(app).sync, id) {
+        SyncMode::LocalOnly => return Err("Not shared".to_string()),
+        SyncMode::Synced => crate::vault::export(id).await?,
+        SyncMode::MetadataOnly => {
+            let notebook = backend_api::get_json(&format!("/notebooks/{}", id), API_TIMEOUT)
+                .await
+                .map_err(|e| e.to_string())?;
+            SealedNotebook {
+                title: notebook["title"].as_str().unwrap_or("Notebook").to_string(),
+                description: notebook["description"].as_str().map(String::from),
+                color: notebook["color"].as_str().map(String::from),
+                sources: Vec::new(),
+                audio: Vec::new(),
+            }
+        }
+    };
+    let path = state_path(app, &format!("sent/{}", id), fingerprint)?;
+    if let Err(e) = save_snapshot(&path, &notebook) {
+        tracing::warn!("[Sync] {}", e);
+    }
+    if let Some(dir) = path.parent() {
+        prune_sent(dir);
+    }
+    Ok(notebook)
+}
+
+fn prune_sent(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in files.into_iter().skip(KEEP_SENT) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// What a snapshot says, ignoring source titles and order (an import renames
+/// files to their text) and sources with no text (an import skips them).
+pub(crate) fn content_hash(notebook: &SealedNotebook) -> String {
+    let mut contents: Vec<&str> = notebook
+        .sources
+        .iter()
+        .map(|s| s.content.trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+    contents.sort_unstable();
+    let mut hasher = Sha256::new();
+    for field in [
+        Some(notebook.title.as_str()),
+        notebook.description.as_deref(),
+        notebook.color.as_deref(),
+    ] {
+        hasher.update(field.unwrap_or_default());
+        hasher.update([0]);
+    }
+    for content in contents {
+        hasher.update(content);
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Which source in another version of a notebook is "the same" one: notes by
+/// title, documents by the name they're re-imported under.
+pub(crate) fn source_key(source: &SealedSource) -> String {
+    if source.is_note {
+        format!("note:{}", source.title)
+    } else {
+        format!("file:{}", crate::vault::file_stem(&source.title))
+    }
+}
+
+/// Make backend notebook `id`, currently `current` (as exported, with source
+/// ids), match `target`: rename, recolor, and change, add or remove sources.
+pub(crate) async fn update_in_place(
+    app: &AppHandle,
+    id: &str,
+    current: &SealedNotebook,
+    target: &SealedNotebook,
+) -> Result<(), String> {
+    if current.title != target.title && !target.title.trim().is_empty() {
+        backend_api::put_json(
+            &format!("/notebooks/{}/rename", id),
+            &serde_json::json!({ "title": target.title }),
+            API_TIMEOUT,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(color) = target
+        .color
+        .as_ref()
+        .filter(|c| current.color.as_ref() != Some(c))
+    {
+        backend_api::put_json(
+            &format!("/notebooks/{}/color", id),
+            &serde_json::json!({ "color": color }),
+            API_TIMEOUT,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    let mut remaining: Vec<&SealedSource> = current.sources.iter().collect();
+    let mut added = Vec::new();
+    for source in target
+        .sources
+        .iter()
+        .filter(|s| !s.content.trim().is_empty())
+    {
+        let key = source_key(source);
+        let Some(i) = remaining.iter().position(|r| source_key(r) == key) else {
+            added.push(source.clone());
+            continue;
+        };
+        let existing = remaining.remove(i);
+        if existing.content.trim() == source.content.trim() {
+            continue;
+        }
+        let Some(source_id) = &existing.id else {
+            continue;
+        };
+        if existing.is_note {
+            backend_api::put_json(
+                &format!("/sources/{}/{}/note", id, source_id),
+                &serde_json::json!({ "title": source.title, "content": source.content }),
+                API_TIMEOUT,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        } else {
+            backend_api::delete(&format!("/sources/{}/{}", id, source_id), API_TIMEOUT)
+                .await
+                .map_err(|e| e.to_string())?;
+            added.push(source.clone());
+        }
+    }
+    for gone in remaining {
+        if let Some(source_id) = &gone.id {
+            backend_api::delete(&format!("/sources/{}/{}", id, source_id), API_TIMEOUT)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    crate::vault::add_sources(app, id, &added).await.map(|_| ())
+}
+
+/// Note that `theirs`, from `offer`, is now part of local notebook
+/// `local_id`: it becomes the merge base for next time.
+pub(crate) fn record_taken(
+    app: &AppHandle,
+    peer: &str,
+    offer: &Offer,
+    local_id: &str,
+    mirror: bool,
+    theirs: &SealedNotebook,
+) -> Result<(), String> {
+    let key = origin_key(peer, &offer.id);
+    save_snapshot(&state_path(app, "base", &key)?, theirs)?;
+    crate::conflicts::discard(app, &key);
+    crate::settings::update(app, |s| {
+        s.sync.received.insert(
+            key.clone(),
+            Received {
+                peer: peer.to_string(),
+                remote_id: offer.id.clone(),
+                local_id: local_id.to_string(),
+                fingerprint: offer.fingerprint.clone(),
+                content_hash: content_hash(theirs),
+                synced_at: crate::models::now_secs(),
+                mirror,
+            },
+        );
+        s.sync
+            .conflicts
+            .retain(|c| !(c.peer == peer && c.remote_id == offer.id));
+    })?;
+    Ok(())
+}
+
+/// Take in a notebook fetched from `peer`: import it the first time, merge it
+/// into the local copy after that. A peer's copy of one of this device's
+/// notebooks is merged into the original, unless a conflict was settled by
+/// keeping both, which gave it a copy of its own.
+pub(crate) async fn apply(
+    app: &AppHandle,
+    peer: &str,
+    offer: &Offer,
+    theirs: &SealedNotebook,
+) -> Result<Applied, String> {
+    let settings = crate::settings::get(app).sync;
+    let key = origin_key(peer, &offer.id);
+    let own = format!("{}/", settings.device_id);
+    let original = offer
+        .origin
+        .as_deref()
+        .and_then(|o| o.strip_prefix(&own))
+        .map(String::from);
+    let copy = settings
+        .received
+        .get(&key)
+        .filter(|r| !r.mirror)
+        .map(|r| r.local_id.clone());
+    let mirror = copy.is_none() && original.is_some();
+    let target = copy.or(original);
+    let ours = match &target {
+        Some(id) => crate::vault::export(id).await.ok(),
+        None => None,
+    };
+    let (Some(local_id), Some(ours)) = (target, ours) else {
+        // New, or the local notebook was deleted: take it as a fresh copy.
+        let local_id = crate::vault::import(app, theirs).await?;
+        record_taken(app, peer, offer, &local_id, false, theirs)?;
+        crate::audit::record("sync_received", format!("{} from {}", local_id, peer));
+        return Ok(Applied::Imported);
+    };
+    let base = if mirror {
+        match &offer.base {
+            Some(fingerprint) => load_snapshot(&state_path(
+                app,
+                &format!("sent/{}", local_id),
+                fingerprint,
+            )?),
+            None => None,
+        }
+    } else {
+        load_snapshot(&state_path(app, "base", &key)?)
+    };
+    // A metadata-only notebook says nothing about sources: keep the ones
+    // the copy had rather than taking their absence as deletions.
+    let stripped;
+    let theirs = if offer.metadata_only {
+        stripped = SealedNotebook {
+            sources: base
+                .as_ref()
+                .map_or_else(|| ours.sources.clone(), |b| b.sources.clone()),
+            ..theirs.clone()
+        };
+        &stripped
+    } else {
+        theirs
+    };
+    if content_hash(&ours) != content_hash(theirs) {
+        match crate::conflicts::merge(base.as_ref(), &ours, theirs) {
+            Merge::Clean(merged) => {
+                if content_hash(&merged) != content_hash(&ours) {
+                    update_in_place(app, &local_id, &ours, &merged).await?;
+                }
+            }
+            Merge::Conflicted(notes) => {
+                let conflict = Conflict {
+                    peer: peer.to_string(),
+                    remote_id: offer.id.clone(),
+                    local_id,
+                    title: ours.title.clone(),
+                    detected_at: crate::models::now_secs(),
+                    fingerprint: offer.fingerprint.clone(),
+                    remote_updated_at: offer.updated_at.clone(),
+                    mirror,
+                };
+                crate::conflicts::record(app, conflict, base.as_ref(), theirs, notes.len())?;
+                return Ok(Applied::Conflict);
+            }
+        }
+    }
+    record_taken(app, peer, offer, &local_id, mirror, theirs)?;
+    crate::audit::record("sync_received", format!("{} from {}", local_id, peer));
+    Ok(Applied::Imported)
+}
+
+fn current_status() -> SyncStatus {
+    STATUS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Change the status, settle its state and tell the frontend and tray.
+fn set_status(app: &AppHandle, change: impl FnOnce(&mut SyncStatus)) {
+    let status = {
+        let mut guard = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        let status = guard.get_or_insert_with(SyncStatus::default);
+        change(status);
+        status.conflicts = crate::settings::get(app).sync.conflicts.len();
+        status.state = if status.with.is_some() {
+            SyncHealth::Syncing
+        } else if status.error.is_some() {
+            SyncHealth::Error
+        } else if status.conflicts > 0 {
+            SyncHealth::Conflicts
+        } else {
+            SyncHealth::Idle
+        };
+        status.clone()
+    };
+    crate::tray::set_sync_status(app, &status);
+    let _ = app.emit("sync://status", status);
+}
+
+/// A sync with `with` (a device name, or the storage) started.
+pub(crate) fn begin(app: &AppHandle, with: &str) {
+    set_status(app, |s| {
+        s.with = Some(with.to_string());
+        s.done = 0;
+        s.total = 0;
+    });
+}
+
+/// The sync that `begin` announced is over.
+pub(crate) fn finish(app: &AppHandle, error: Option<&String>) {
+    set_status(app, |s| {
+        s.with = None;
+        s.error = error.cloned();
+        s.last_synced_at = Some(crate::models::now_secs());
+    });
+}
+
+/// Re-read the conflict count (after one is resolved).
+pub(crate) fn refresh_status(app: &AppHandle) {
+    set_status(app, |_| {});
+}
+
+pub(crate) fn progress(app: &AppHandle, peer: &str, title: &str, done: usize, total: usize) {
+    set_status(app, |s| {
+        s.done = done;
+        s.total = total;
+    });
+    let _ = app.emit(
+        "sync://progress",
+        SyncProgress {
+            peer: peer.to_string(),
+            title: title.to_string(),
+            done,
+            total,
+        },
+    );
+}
+
+pub(crate) fn mark_synced(app: &AppHandle, peer: &str) {
+    let _ = crate::settings::update(app, |s| {
+        if let Some(p) = s.sync.peers.iter_mut().find(|p| p.id == peer) {
+            p.last_synced_at = Some(crate::models::now_secs());
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn get_sync_status(app: AppHandle) -> Result<SyncStatus, String> {
+    let mut status = current_status();
+    status.conflicts = crate::settings::get(&app).sync.conflicts.len();
+    if status.state == SyncHealth::Idle && status.conflicts > 0 {
+        status.state = SyncHealth::Conflicts;
+    }
+    Ok(status)
+}
+
+#[tauri::command]
+pub(crate) async fn get_sync_state(app: AppHandle) -> Result<SyncState, String> {
+    let device_id = device_id(&app);
+    let settings = crate::settings::get(&app).sync;
+    Ok(SyncState {
+        device_id,
+        device_name: device_name(&app),
+        lan_enabled: settings.lan_enabled,
+        modes: settings
+            .notebooks
+            .iter()
+            .chain(&settings.metadata_only)
+            .map(|id| (id.clone(), mode(&settings, id)))
+            .collect(),
+        peers: settings.peers,
+        remote: settings.remote,
+        conflicts: settings.conflicts,
+    })
+}
+
+/// Share a notebook in full with paired devices, or stop sharing it.
+#[tauri::command]
+pub(crate) async fn set_notebook_sync(
+    app: AppHandle,
+    notebook_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mode = if enabled {
+        SyncMode::Synced
+    } else {
+        SyncMode::LocalOnly
+    };
+    set_notebook_sync_mode(app, notebook_id, mode).await
+}
+
+#[tauri::command]
+pub(crate) async fn set_notebook_sync_mode(
+    app: AppHandle,
+    notebook_id: String,
+    mode: SyncMode,
+) -> Result<(), String> {
+    if mode != SyncMode::LocalOnly {
+        backend_api::get_json(&format!("/notebooks/{}", notebook_id), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    crate::settings::update(&app, |s| {
+        s.sync.notebooks.retain(|n| n != &notebook_id);
+        s.sync.metadata_only.retain(|n| n != &notebook_id);
+        match mode {
+            SyncMode::Synced => s.sync.notebooks.push(notebook_id.clone()),
+            SyncMode::MetadataOnly => s.sync.metadata_only.push(notebook_id.clone()),
+            SyncMode::LocalOnly => {}
+        }
+    })?;
+    tracing::info!("[Sync] Notebook {} is now {:?}", notebook_id, mode);
+    crate::audit::record("sync_mode_changed", format!("{} {:?}", notebook_id, mode));
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn set_sync_device_name(
+    app: AppHandle,
+    name: Option<String>,
+) -> Result<(), String> {
+    crate::settings::update(&app, |s| {
+        s.sync.device_name = name.filter(|n| !n.trim().is_empty())
+    })?;
+    crate::lan_sync::refresh(&app).await;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+//! OS theme (light/dark + accent colour) and the user's theme override.
+//!
+//! Light/dark changes arrive as native ThemeChanged window events. Accent colour
+//! has no such event on any platform, so it's re-read whenever the main window
+//! regains focus (the user has to leave the app to change it) and only emitted
+//! if it actually changed. Either way the webview gets one `theme://changed`
+//! event and never has to poll.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Theme, WindowEvent};
+
+static LAST_ACCENT: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct ThemeInfo {
+    /// What the OS is set to: "light" | "dark".
+    system: String,
+    /// macOS: accent name ("blue", "graphite"…); Windows: "#RRGGBB"; else None.
+    accent_color: Option<String>,
+    /// User override from settings, None = follow the system.
+    override_theme: Option<String>,
+    /// What the UI should render.
+    effective: String,
+}
+
+fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+fn system_theme(app: &AppHandle) -> String {
+    // macOS: read the preference directly — window.theme() reports the app
+    // override once one is set, not the OS appearance.
+    #[cfg(target_os = "macos")]
+    {
+        let _ = app;
+        let dark = std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleInterfaceStyle"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "Dark")
+            .unwrap_or(false);
+        (if dark { "dark" } else { "light" }).to_string()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        app.get_webview_window("main")
+            .and_then(|w| w.theme().ok())
+            .map(theme_name)
+            .unwrap_or("light")
+            .to_string()
+    }
+}
+
+fn accent_color() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        // Unset means the default (multicolor → blue).
+        let out = std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleAccentColor"])
+            .output()
+            .ok()?;
+        let raw = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        let name = match raw.as_str() {
+            "-1" => "graphite",
+            "0" => "red",
+            "1" => "orange",
+            "2" => "yellow",
+            "3" => "green",
+            "5" => "purple",
+            "6" => "pink",
+            _ => "blue",
+        };
+        Some(name.to_string())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // DWM AccentColor is a REG_DWORD laid out as 0xAABBGGRR.
+        let out = std::process::Command::new("reg")
+            .args(["query", r"HKCU\Software\Microsoft\Windows\DWM", "/v", "AccentColor"])
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&out.stdout).to_string();
+        let hex = text.split_whitespace().find(|t| t.starts_with("0x"))?;
+        let v = u32::from_str_radix(hex.trim_start_matches("0x"), 16).ok()?;
+        let (r, g, b) = (v & 0xff, (v >> 8) & 0xff, (v >> 16) & 0xff);
+        Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+/// What the UI renders: "light" | "dark".
+pub(crate) fn effective(app: &AppHandle) -> String {
+    crate::settings::get(app)
+        .theme_override
+        .unwrap_or_else(|| system_theme(app))
+}
+
+fn current(app: &AppHandle) -> ThemeInfo {
+    let system = system_theme(app);
+    let override_theme = crate::settings::get(app).theme_override;
+    let effective = override_theme.clone().unwrap_or_else(|| system.clone());
+    ThemeInfo {
+        system,
+        accent_color: accent_color(),
+        override_theme,
+        effective,
+    }
+}
+
+/// Apply the persisted override to every window. Called once from setup.
+pub(crate) fn apply_override(app: &AppHandle) {
+    let theme = match crate::settings::get(app).theme_override.as_deref() {
+        Some("dark") => Some(Theme::Dark),
+        Some("light") => Some(Theme::Light),
+        _ => None,
+    };
+    app.set_theme(theme);
+    if let Ok(mut last) = LAST_ACCENT.lock() {
+        *last = accent_color();
+    }
+}
+
+fn emit_changed(app: &AppHandle) {
+    // Window effects come in light and dark variants.
+    crate::appearance::reapply(app);
+    let _ = app.emit("theme://changed", current(app));
+}
+
+/// Builder-level window event hook. Only the main window reports, so N open
+/// windows don't produce N identical events.
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    match event {
+        WindowEvent::ThemeChanged(theme) => {
+            tracing::info!("[Theme] OS theme changed → {}", theme_name(*theme));
+            emit_changed(window.app_handle());
+        }
+        WindowEvent::Focused(true) => {
+            let accent = accent_color();
+            let changed = match LAST_ACCENT.lock() {
+                Ok(mut last) if *last != accent => {
+                    *last = accent;
+                    true
+                }
+                _ => false,
+            };
+            if changed {
+                tracing::info!("[Theme] Accent colour changed");
+                emit_changed(window.app_handle());
+            }
+        }
+        _ => {}
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_system_theme(app: AppHandle) -> Result<ThemeInfo, String> {
+    Ok(current(&app))
+}
+
+/// Persist the user's choice: "light" | "dark" | "system" (or null) to follow the OS.
+#[tauri::command]
+pub(crate) async fn set_theme_override(
+    app: AppHandle,
+    theme: Option<String>,
+) -> Result<ThemeInfo, String> {
+    let theme = match theme.as_deref() {
+        None | Some("system") => None,
+        Some("light") | Some("dark") => theme,
+        Some(other) => return Err(format!("Unknown theme: {}", other)),
+    };
+    crate::settings::update(&app, |s| s.theme_override = theme)?;
+    apply_override(&app);
+    emit_changed(&app);
+    Ok(current(&app))
+}
@@ -0,0 +1,389 @@
+//! Custom themes: stylesheets the user installs and the webview applies over
+//! the built-in light and dark looks (see `theme` for those).
+//!
+//! A theme is one `.css` file in `themes/` in the app data folder, named by
+//! its file stem. It may start with a comment giving its title, author,
+//! version and whether it's meant for light or dark (`key: value` lines), so
+//! a list can show more than a file name. `install_theme` copies one in from
+//! a file or downloads it; either way it must be UTF-8, under `MAX_BYTES`,
+//! and load nothing from elsewhere — no `@import`, no remote `url()`, with
+//! CSS escapes resolved before looking — so applying a theme never makes a
+//! request. Installing a theme with the name of one already there replaces
+//! it.
+//!
+//! In debug builds the folder is checked every `WATCH_INTERVAL` and
+//! `themes://changed` goes out when a file changes, so the webview can
+//! reload a theme while it's being written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+const THEMES_DIR: &str = "themes";
+const EXTENSION: &str = "css";
+const MAX_BYTES: usize = 1024 * 1024;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Theme {
+    /// File stem; what `get_theme_css` takes.
+    name: String,
+    title: Option<String>,
+    author: Option<String>,
+    version: Option<String>,
+    /// "light" | "dark", if the theme says.
+    appearance: Option<String>,
+    size: u64,
+    /// Unix seconds.
+    modified: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct Changed {
+    name: String,
+    /// False when it was deleted.
+    exists: bool,
+}
+
+fn themes_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::data_dir(app)?.join(THEMES_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Theme names are file stems: letters, digits, `-` and `_`.
+fn valid_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid theme name: {:?}", name))
+    }
+}
+
+/// `name` made into a valid theme name.
+fn name_from(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .take(64)
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+/// `key: value` lines from a leading comment.
+fn header(css: &str) -> HashMap<String, String> {
+    let Some(comment) = css
+        .trim_start()
+        .strip_prefix("/*")
+        .and_then(|rest| rest.split_once("*/"))
+        .map(|(comment, _)| comment)
+    else {
+        return HashMap::new();
+    };
+    comment
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().trim_start_matches('*').split_once(':')?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| (key.trim().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Resolve CSS escapes, which the browser does before it reads a name:
+/// `@\69mport` is `@import` and `\75rl(` is `url(`.
+fn unescape(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let mut hex = String::new();
+        while let Some(h) = chars
+            .peek()
+            .filter(|h| h.is_ascii_hexdigit() && hex.len() < 6)
+        {
+            hex.push(*h);
+            chars.next();
+        }
+        if hex.is_empty() {
+            // `\` then a newline continues a string; anything else is itself.
+            match chars.next() {
+                Some('\n') | None => {}
+                Some(c) => out.push(c),
+            }
+            continue;
+        }
+        // One whitespace character ends a hex escape and is part of it.
+        if chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let code = u32::from_str_radix(&hex, 16).unwrap_or(0);
+        out.push(
+            char::from_u32(code)
+                .filter(|c| *c != '\0')
+                .unwrap_or('\u{FFFD}'),
+        );
+    }
+    out
+}
+
+/// Refuse anything that isn't a self-contained stylesheet.
+fn check_css(css: &[u8]) -> Result<String, String> {
+    if css.len() > MAX_BYTES {
+        return Err(format!(
+            "Theme is too large ({} KB; the limit is {} KB)",
+            css.len() / 1024,
+            MAX_BYTES / 1024
+        ));
+    }
+    let css = std::str::from_utf8(css)
+        .map_err(|_| "Theme isn't a text stylesheet".to_string())?
+        .to_string();
+    let lower = unescape(&css).to_lowercase();
+    if lower.contains("@import") {
+        return Err("Themes can't @import other stylesheets".to_string());
+    }
+    // `image-set()` takes plain strings as well as `url()`s.
+    let remote = ["url(", "image-set("].iter().any(|function| {
+        lower.match_indices(function).any(|(at, _)| {
+            let target = lower[at + function.len()..].trim_start_matches([' ', '"', '\'']);
+            !target.starts_with("data:") && !target.starts_with('#') && !target.starts_with("url(")
+        })
+    });
+    if remote {
+        return Err("Themes can't load files; use data: URLs for images and fonts".to_string());
+    }
+    Ok(css)
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+fn read(path: &Path) -> Option<Theme> {
+    if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+        return None;
+    }
+    let name = path.file_stem()?.to_str()?.to_string();
+    valid_name(&name).ok()?;
+    let meta = std::fs::metadata(path).ok()?;
+    let css = std::fs::read_to_string(path).ok()?;
+    let mut header = header(&css);
+    let appearance = header
+        .remove("appearance")
+        .map(|a| a.to_lowercase())
+        .filter(|a| a == "light" || a == "dark");
+    Some(Theme {
+        name,
+        title: header.remove("name").or_else(|| header.remove("title")),
+        author: header.remove("author"),
+        version: header.remove("version"),
+        appearance,
+        size: meta.len(),
+        modified: modified_secs(&meta),
+    })
+}
+
+fn list(dir: &Path) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().filter_map(|e| read(&e.path())).collect())
+        .unwrap_or_default();
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
+
+async fn download(url: &str) -> Result<Vec<u8>, String> {
+    crate::privacy::guard(url, "Theme download")?;
+    let client = crate::proxy::client()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))?;
+    let mut resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Theme download failed: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Theme download failed: {}", e))?;
+    let too_large = || format!("Theme is too large (the limit is {} KB)", MAX_BYTES / 1024);
+    if resp
+        .content_length()
+        .is_some_and(|len| len > MAX_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+    // Content-Length may be missing or wrong, so count what actually comes.
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("Theme download failed: {}", e))?
+    {
+        if body.len() + chunk.len() > MAX_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Watch the folder in debug builds. Called in setup.
+pub(crate) fn start(app: &AppHandle) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let Ok(dir) = themes_dir(app) else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut seen: HashMap<String, (u64, u64)> = HashMap::new();
+        let mut first = true;
+        loop {
+            let now: HashMap<String, (u64, u64)> = list(&dir)
+                .into_iter()
+                .map(|t| (t.name, (t.modified, t.size)))
+                .collect();
+            if !first {
+                let changed = now
+                    .iter()
+                    .filter(|(name, stamp)| seen.get(*name) != Some(stamp))
+                    .map(|(name, _)| Changed {
+                        name: name.clone(),
+                        exists: true,
+                    });
+                let removed = seen
+                    .keys()
+                    .filter(|name| !now.contains_key(*name))
+                    .map(|name| Changed {
+                        name: name.clone(),
+                        exists: false,
+                    });
+                for change in changed.chain(removed).collect::<Vec<_>>() {
+                    tracing::info!("[Themes] {} changed", change.name);
+                    let _ = app.emit("themes://changed", change);
+                }
+            }
+            seen = now;
+            first = false;
+            tokio::time::sleep(WATCH_INTERVAL).await;
+        }
+    });
+}
+
+/// The installed themes, by name.
+#[tauri::command]
+pub(crate) async fn list_themes(app: AppHandle) -> Result<Vec<Theme>, String> {
+    Ok(list(&themes_dir(&app)?))
+}
+
+/// Install a theme from a `.css` file or an http(s) URL. The name comes from
+/// the file, or `name` when given.
+#[tauri::command]
+pub(crate) async fn install_theme(
+    app: AppHandle,
+    source: String,
+    name: Option<String>,
+) -> Result<Theme, String> {
+    let remote = source.starts_with("https://") || source.starts_with("http://");
+    let (bytes, stem) = if remote {
+        let url = reqwest::Url::parse(&source).map_err(|e| format!("Invalid URL: {}", e))?;
+        let stem = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .map(|s| s.trim_end_matches(".css").to_string())
+            .unwrap_or_default();
+        (download(&source).await?, stem)
+    } else {
+        let path = crate::scope::check(&app, Path::new(&source))?;
+        if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+            return Err("A theme is a .css file".to_string());
+        }
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let bytes = std::fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        (bytes, stem)
+    };
+    let css = check_css(&bytes)?;
+    let name = name_from(name.as_deref().unwrap_or(&stem));
+    valid_name(&name)?;
+
+    let path = themes_dir(&app)?.join(format!("{}.{}", name, EXTENSION));
+    std::fs::write(&path, css).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    let theme = read(&path).ok_or_else(|| format!("Failed to read {}", path.display()))?;
+    tracing::info!("[Themes] Installed {}", name);
+    crate::audit::record(
+        "theme_installed",
+        if remote {
+            format!("{} from {}", name, crate::logging::redact::line(&source))
+        } else {
+            name.clone()
+        },
+    );
+    let _ = app.emit("themes://changed", Changed { name, exists: true });
+    Ok(theme)
+}
+
+/// The stylesheet of the theme called `name`.
+#[tauri::command]
+pub(crate) async fn get_theme_css(app: AppHandle, name: String) -> Result<String, String> {
+    valid_name(&name)?;
+    let path = themes_dir(&app)?.join(format!("{}.{}", name, EXTENSION));
+    std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("No theme called {}", name),
+        _ => format!("Failed to read {}: {}", path.display(), e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_self_contained_css() {
+        let css = "/* name: Dusk */ body { background: url(\"data:image/png;base64,AA\") }";
+        assert!(check_css(css.as_bytes()).is_ok());
+        assert!(check_css(b"svg { fill: url(#grad) }").is_ok());
+    }
+
+    #[test]
+    fn refuses_loads_even_when_escaped() {
+        for css in [
+            "@import 'https://example.com/x.css';",
+            "@\\69mport 'https://example.com/x.css';",
+            "@\\000069 mport 'https://example.com/x.css';",
+            "body { background: url(https://example.com/x.png) }",
+            "body { background: \\75rl(https://example.com/x.png) }",
+            "body { background: U\\52L('https://example.com/x.png') }",
+            "body { background: image-set('https://example.com/x.png' 1x) }",
+        ] {
+            assert!(check_css(css.as_bytes()).is_err(), "{}", css);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Window controls for a frontend-drawn titlebar.
+//!
+//! Every command acts on the *calling* window, so the same titlebar component
+//! works in the main, notebook and mini windows. Platform differences (macOS
+//! keeps its traffic lights over the content; Windows/Linux drop decorations
+//! entirely; the double-click action is a macOS system preference) are resolved
+//! here so the webview never has to sniff the OS.
+
+use serde::Serialize;
+use tauri::WebviewWindow;
+
+/// Safe area the frontend must leave clear for native controls, in logical px.
+#[derive(Clone, Serialize)]
+pub(crate) struct TitlebarInsets {
+    /// Space reserved for the macOS traffic lights.
+    left: f64,
+    /// Height the custom titlebar should use so native controls stay centred.
+    height: f64,
+}
+
+#[tauri::command]
+pub(crate) async fn window_minimize(window: WebviewWindow) -> Result<(), String> {
+    window.minimize().map_err(|e| e.to_string())
+}
+
+/// Toggles between maximized and restored, like the native button.
+#[tauri::command]
+pub(crate) async fn window_maximize(window: WebviewWindow) -> Result<bool, String> {
+    let maximized = window.is_maximized().map_err(|e| e.to_string())?;
+    if maximized {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    } else {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+    Ok(!maximized)
+}
+
+#[tauri::command]
+pub(crate) async fn window_close(window: WebviewWindow) -> Result<(), String> {
+    window.close().map_err(|e| e.to_string())
+}
+
+/// Call from a mousedown on the titlebar's drag region.
+#[tauri::command]
+pub(crate) async fn window_start_drag(window: WebviewWindow) -> Result<(), String> {
+    window.start_dragging().map_err(|e| e.to_string())
+}
+
+/// Double-click on the titlebar. macOS honours the user's "Double-click a
+/// window's title bar to" preference (zoom / minimize / do nothing); other
+/// platforms always toggle maximize.
+#[tauri::command]
+pub(crate) async fn window_titlebar_double_click(window: WebviewWindow) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let action = std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleActionOnDoubleClick"])
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default();
+        match action.as_str() {
+            "Minimize" => return window.minimize().map_err(|e| e.to_string()),
+            "None" => return Ok(()),
+            _ => {} // "Maximize"/"Fill" or unset — zoom, handled below
+        }
+    }
+    window_maximize(window).await.map(|_| ())
+}
+
+/// Switch the calling window between native chrome and a custom titlebar.
+/// macOS keeps the traffic lights floating over the content (Overlay style);
+/// elsewhere decorations are removed and the frontend draws all controls.
+#[tauri::command]
+pub(crate) async fn window_set_custom_titlebar(
+    window: WebviewWindow,
+    enabled: bool,
+) -> Result<TitlebarInsets, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let style = if enabled {
+            tauri::TitleBarStyle::Overlay
+        } else {
+            tauri::TitleBarStyle::Visible
+        };
+        window.set_title_bar_style(style).map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    window.set_decorations(!enabled).map_err(|e| e.to_string())?;
+
+    Ok(insets(enabled))
+}
+
+/// Insets for the current platform with a custom titlebar enabled.
+#[tauri::command]
+pub(crate) async fn get_titlebar_insets() -> Result<TitlebarInsets, String> {
+    Ok(insets(true))
+}
+
+fn insets(custom: bool) -> TitlebarInsets {
+    if !custom {
+        return TitlebarInsets { left: 0.0, height: 0.0 };
+    }
+    if cfg!(target_os = "macos") {
+        // Default Overlay traffic lights: three 12px buttons starting ~20px in,
+        // vertically centred in a 28px bar.
+        TitlebarInsets { left: 78.0, height: 28.0 }
+    } else {
+        // Frontend draws min/max/close itself; nothing native to avoid.
+        TitlebarInsets { left: 0.0, height: 32.0 }
+    }
+}
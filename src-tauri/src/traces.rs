@@ -0,0 +1,131 @@
+//! Request ids shared with the backend, for following a slow query through
+//! both logs.
+//!
+//! Every request the shell makes to the backend (`backend_api`) and every one
+//! `--serve` passes on gets a fresh id in `X-Request-ID`. The backend logs
+//! under it while it handles the request (`utils/request_id.py`); the shell
+//! logs it with the outcome and keeps the last `KEEP` requests, which
+//! `get_recent_traces` returns newest first. The time is until the response
+//! starts — a streamed body isn't counted — except through `--serve`, where
+//! it's the whole exchange and the status isn't seen. Requests over their
+//! threshold are also announced (see `slow_requests`).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+
+pub(crate) const HEADER: &str = "X-Request-ID";
+const KEEP: usize = 200;
+
+static RECENT: Mutex<VecDeque<Trace>> = Mutex::new(VecDeque::new());
+
+#[derive(Clone, Serialize)]
+pub(crate) struct Trace {
+    pub id: String,
+    /// "shell" or "serve".
+    pub origin: &'static str,
+    pub method: String,
+    /// Without the query string, which can hold search text.
+    pub path: String,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub started_at: String,
+}
+
+/// A request on its way; `finish` records it.
+pub(crate) struct Started {
+    trace: Trace,
+    at: Instant,
+}
+
+pub(crate) fn start(origin: &'static str, method: &str, path: &str) -> Started {
+    let id = uuid::Uuid::new_v4().simple().to_string()[..16].to_string();
+    Started {
+        trace: Trace {
+            id,
+            origin,
+            method: method.to_string(),
+            path: path.split('?').next().unwrap_or_default().to_string(),
+            status: None,
+            error: None,
+            duration_ms: 0,
+            started_at: chrono::Local::now().to_rfc3339(),
+        },
+        at: Instant::now(),
+    }
+}
+
+impl Started {
+    pub(crate) fn id(&self) -> &str {
+        &self.trace.id
+    }
+
+    pub(crate) fn finish(self, status: Option<u16>, error: Option<String>) {
+        let mut trace = self.trace;
+        let took = self.at.elapsed();
+        trace.duration_ms = took.as_millis() as u64;
+        crate::metrics::request(trace.origin, &trace.method, &trace.path, took);
+        trace.status = status;
+        trace.error = error;
+        let outcome = match (&trace.error, trace.status) {
+            (Some(e), _) => e.clone(),
+            (None, Some(status)) => status.to_string(),
+            (None, None) => "done".to_string(),
+        };
+        if trace.error.is_some() || trace.status.is_some_and(|s| s >= 500) {
+            tracing::warn!(
+                trace_id = %trace.id,
+                "[Trace] {} {} {} in {}ms",
+                trace.method,
+                trace.path,
+                outcome,
+                trace.duration_ms
+            );
+        } else {
+            tracing::info!(
+                trace_id = %trace.id,
+                "[Trace] {} {} {} in {}ms",
+                trace.method,
+                trace.path,
+                outcome,
+                trace.duration_ms
+            );
+        }
+        crate::slow_requests::check(&trace);
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == KEEP {
+            recent.pop_front();
+        }
+        recent.push_back(trace);
+    }
+}
+
+/// The requests started since `at` and still kept, oldest first.
+pub(crate) fn since(at: chrono::DateTime<chrono::Local>) -> Vec<Trace> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent
+        .iter()
+        .filter(|t| chrono::DateTime::parse_from_rfc3339(&t.started_at).is_ok_and(|s| s >= at))
+        .cloned()
+        .collect()
+}
+
+/// The latest requests, newest first; with `min_duration_ms`, only those
+/// that took at least that long.
+#[tauri::command]
+pub(crate) async fn get_recent_traces(
+    limit: Option<usize>,
+    min_duration_ms: Option<u64>,
+) -> Result<Vec<Trace>, String> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(recent
+        .iter()
+        .rev()
+        .filter(|t| t.duration_ms >= min_duration_ms.unwrap_or(0))
+        .take(limit.unwrap_or(KEEP))
+        .cloned()
+        .collect())
+}
@@ -6,6 +6,7 @@
 //! webview via a `tray-navigate` event (the frontend already owns opener/modal
 //! handlers) so the Rust surface stays tiny and version-robust.
 
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use serde::Deserialize;
@@ -15,6 +16,14 @@ use tauri::{
     AppHandle, Emitter, Manager, Wry,
 };
 
+/// The "Pause Background Work" toggle, relabelled by `set_paused`.
+static PAUSE_ITEM: OnceLock<MenuItem<Wry>> = OnceLock::new();
+/// The mini chat row, whose shortcut `set_mini_shortcut` keeps current.
+static MINI_ITEM: OnceLock<MenuItem<Wry>> = OnceLock::new();
+/// The sync row, kept current by `set_sync_status`.
+static SYNC_ITEM: OnceLock<MenuItem<Wry>> = OnceLock::new();
+const TRAY_ID: &str = "localbook-tray";
+
 #[derive(Deserialize, Default)]
 struct Models {
     #[serde(default)]
@@ -63,10 +72,13 @@ pub(crate) fn init(app: &AppHandle) -> tauri::Result<()> {
     let models2 = MenuItem::with_id(app, "models2", "", true, None::<&str>)?;
     let metrics = MenuItem::with_id(app, "metrics", "Metrics: …", true, None::<&str>)?;
     let synth = MenuItem::with_id(app, "synth", "🧠 …", true, None::<&str>)?;
+    let sync = MenuItem::with_id(app, "sync", "Sync: up to date", true, None::<&str>)?;
     let open = MenuItem::with_id(app, "open", "Launch App", true, None::<&str>)?;
+    let mini = MenuItem::with_id(app, "mini", "Mini Chat", true, crate::shortcuts::accelerator(app, "mini_mode"))?;
     let portal = MenuItem::with_id(app, "portal", "Health Portal", true, None::<&str>)?;
     let labs = MenuItem::with_id(app, "labs", "Labs (LLM)", true, None::<&str>)?;
     let settings = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
+    let pause = MenuItem::with_id(app, "pause", pause_label(crate::pause::is_paused(app)), true, None::<&str>)?;
     let restart = MenuItem::with_id(app, "restart", "🔄 Backend", true, None::<&str>)?;
     let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
@@ -75,12 +87,15 @@ pub(crate) fn init(app: &AppHandle) -> tauri::Result<()> {
     let menu = Menu::with_items(
         app,
         &[
-            &status, &models, &models2, &metrics, &synth, &sep1, &open, &portal, &labs, &settings,
-            &sep2, &restart, &quit,
+            &status, &models, &models2, &metrics, &synth, &sync, &sep1, &open, &mini, &portal, &labs, &settings,
+            &sep2, &pause, &restart, &quit,
         ],
     )?;
+    let _ = PAUSE_ITEM.set(pause);
+    let _ = MINI_ITEM.set(mini);
+    let _ = SYNC_ITEM.set(sync);
 
-    let mut builder = TrayIconBuilder::with_id("localbook-tray")
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
         .tooltip("LocalBook")
         .on_menu_event(|app, event| on_menu(app, event.id.as_ref()));
@@ -127,7 +142,7 @@ pub(crate) fn init(app: &AppHandle) -> tauri::Result<()> {
 /// transiently-busy backend isn't misreported.
 async fn fetch(client: &reqwest::Client) -> Option<Status> {
     let r = client
-        .get("http://localhost:8000/system/tray-status")
+        .get(format!("{}/system/tray-status", crate::backend_api::base_url()))
         .timeout(Duration::from_secs(10))
         .send()
         .await
@@ -151,7 +166,11 @@ fn render_up(
         "mixed" => " · ⚡ MLX+Ollama",
         _ => "",
     };
-    let _ = status.set_text(format!("🟢 LocalBook running (:8000){}", engine_tag));
+    let _ = status.set_text(format!(
+        "🟢 LocalBook running (:{}){}",
+        crate::backend_api::sidecar_port(),
+        engine_tag
+    ));
     // Two lines keeps the menu narrow (was one very wide row).
     let _ = models.set_text(format!(
         "Main: {} · Vision: {}",
@@ -189,20 +208,96 @@ fn render_down(
 fn on_menu(app: &AppHandle, id: &str) {
     match id {
         "open" => show_main(app),
+        "mini" => crate::windows::toggle_mini_window(app, None),
         // Route into the webview's existing handlers (opener/modals).
-        "labs" | "settings" | "portal" => {
+        "labs" | "settings" | "portal" | "sync" => {
             show_main(app);
             if let Some(w) = app.get_webview_window("main") {
                 let _ = w.emit("tray-navigate", id.to_string());
             }
         }
+        "pause" => {
+            if let Err(e) = crate::pause::set_paused(app, !crate::pause::is_paused(app)) {
+                tracing::warn!("[Tray] {e}");
+            }
+        }
         "restart" => crate::restart_backend_from_tray(app),
         "quit" => app.exit(0),
         _ => {}
     }
 }
 
-fn show_main(app: &AppHandle) {
+fn pause_label(paused: bool) -> &'static str {
+    if paused {
+        "▶️ Resume Background Work"
+    } else {
+        "⏸ Pause Background Work"
+    }
+}
+
+/// Keep the tray toggle in step when pausing from elsewhere.
+pub(crate) fn set_paused(paused: bool) {
+    if let Some(item) = PAUSE_ITEM.get() {
+        let _ = item.set_text(pause_label(paused));
+    }
+}
+
+pub(crate) fn set_mini_shortcut(accelerator: Option<&str>) {
+    if let Some(item) = MINI_ITEM.get() {
+        let _ = item.set_accelerator(accelerator);
+    }
+}
+
+/// Show sync health in the menu, the tooltip and (while syncing or when
+/// something needs attention) next to the icon.
+pub(crate) fn set_sync_status(app: &AppHandle, status: &crate::sync::SyncStatus) {
+    use crate::sync::SyncHealth;
+    let (line, mark) = match status.state {
+        SyncHealth::Idle => ("Sync: up to date".to_string(), None),
+        SyncHealth::Syncing => {
+            let with = status.with.as_deref().unwrap_or("…");
+            let line = if status.total > 0 {
+                format!("🔄 Syncing {} of {} with {}", status.done, status.total, with)
+            } else {
+                format!("🔄 Syncing with {}", with)
+            };
+            (line, Some("🔄"))
+        }
+        SyncHealth::Error => (
+            format!("⚠️ Sync failed: {}", status.error.as_deref().map(short_error).unwrap_or_default()),
+            Some("⚠️"),
+        ),
+        SyncHealth::Conflicts => (
+            format!(
+                "⚠️ Sync: {} conflict{} to resolve",
+                status.conflicts,
+                if status.conflicts == 1 { "" } else { "s" }
+            ),
+            Some("⚠️"),
+        ),
+    };
+    if let Some(item) = SYNC_ITEM.get() {
+        let _ = item.set_text(&line);
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = if mark.is_some() { format!("LocalBook — {}", line) } else { "LocalBook".to_string() };
+        let _ = tray.set_tooltip(Some(tooltip));
+        let _ = tray.set_title(mark);
+    }
+}
+
+fn short_error(e: &str) -> &str {
+    match e.char_indices().nth(60) {
+        Some((i, _)) => &e[..i],
+        None => e,
+    }
+}
+
+pub(crate) fn show_main(app: &AppHandle) {
+    if crate::lock::is_locked() {
+        crate::lock::show_lock_window(app);
+        return;
+    }
     if let Some(w) = app.get_webview_window("main") {
         let _ = w.show();
         let _ = w.unminimize();
@@ -0,0 +1,625 @@
+//! App updates through the Tauri updater, on a stable or beta channel.
+//!
+//! Each channel has its own `latest.json` endpoint (the stable one follows
+//! the latest GitHub release, beta a rolling `beta` release). Packages are
+//! signed, and the updater refuses anything that doesn't verify against the
+//! public key in `tauri.conf.json` — a build without a key can't update at
+//! all.
+//!
+//! With automatic updates on (the default), the app checks a minute after
+//! launch and every six hours, downloads a new version in the background and
+//! installs it when the app quits, so it's simply there on the next launch.
+//! `install_update` installs right away and restarts. Progress is announced as
+//! `updater://progress`; `updater://available` and `updater://ready` mark a
+//! version found and a version downloaded. Checks go through the configured
+//! proxy and are skipped in local-only mode.
+//!
+//! Where the endpoints are blocked, `install_update_from_file` installs from
+//! a release's `latest.json`, its signature `latest.json.sig` and the
+//! package, copied into one folder. The plugin only installs updates it
+//! fetched itself over https, so this checks the manifest against its
+//! signature and the same public key (`verify`, which `sidecar` uses too) —
+//! so its version can be trusted, and an older signed package can't be
+//! passed off as newer — then the package against the signature the manifest
+//! lists, and puts it in place the way the plugin would: the app bundle is
+//! replaced on macOS, the installer runs on Windows, and the AppImage is
+//! replaced on Linux. Deb and rpm installs are left to the package manager.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_updater::{Update, Updater, UpdaterExt};
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/patsteph/LocalBook/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/patsteph/LocalBook/releases/download/beta/latest.json";
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// What the release page calls the manifest, for `install_update_from_file`.
+const MANIFEST_FILE: &str = "latest.json";
+/// The manifest's minisign signature with the update key, base64 like the
+/// package signatures in it.
+const MANIFEST_SIGNATURE_FILE: &str = "latest.json.sig";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct UpdateSettings {
+    pub channel: UpdateChannel,
+    /// Check periodically, download in the background, install on quit.
+    pub automatic: bool,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            automatic: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct UpdateInfo {
+    version: String,
+    current_version: String,
+    /// When the release was published, as announced.
+    date: Option<String>,
+    notes: Option<String>,
+    channel: UpdateChannel,
+    /// Downloaded and verified; installs on quit.
+    downloaded: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct Progress {
+    version: String,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+/// The newest update found, and its package once downloaded.
+struct Pending {
+    update: Update,
+    channel: UpdateChannel,
+    bytes: Option<Vec<u8>>,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+static DOWNLOADING: AtomicBool = AtomicBool::new(false);
+
+impl Pending {
+    fn info(&self) -> UpdateInfo {
+        UpdateInfo {
+            version: self.update.version.clone(),
+            current_version: self.update.current_version.clone(),
+            date: self.update.date.map(|d| d.to_string()),
+            notes: self.update.body.clone(),
+            channel: self.channel,
+            downloaded: self.bytes.is_some(),
+        }
+    }
+}
+
+fn pending_info() -> Option<UpdateInfo> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(Pending::info)
+}
+
+/// The update signing key from `tauri.conf.json` (base64), if this build
+/// has one. Backend bundles are signed with it too (see `sidecar`).
+pub(crate) fn pubkey(app: &AppHandle) -> Option<String> {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|u| u["pubkey"].as_str())
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(String::from)
+}
+
+/// Check `data` against a base64 minisign signature and the update key.
+pub(crate) fn verify(app: &AppHandle, data: &[u8], signature: &str) -> Result<(), String> {
+    let decode = |b64: &str| {
+        base64::engine::general_purpose::STANDARD
+            .decode(b64.trim())
+            .ok()
+            .and_then(|b| String::from_utf8(b).ok())
+    };
+    let pubkey = pubkey(app)
+        .and_then(|k| decode(&k))
+        .and_then(|k| minisign_verify::PublicKey::decode(&k).ok())
+        .ok_or("This build has no update signing key, so it can't verify updates")?;
+    let signature = decode(signature)
+        .and_then(|s| minisign_verify::Signature::decode(&s).ok())
+        .ok_or("Malformed signature")?;
+    pubkey
+        .verify(data, &signature, true)
+        .map_err(|e| format!("Signature check failed: {}", e))
+}
+
+fn updater(app: &AppHandle, channel: UpdateChannel) -> Result<Updater, String> {
+    crate::privacy::guard(channel.endpoint(), "Update check")?;
+    let endpoint = Url::parse(channel.endpoint()).map_err(|e| e.to_string())?;
+    build_updater(app, endpoint, crate::proxy::route())
+}
+
+fn build_updater(
+    app: &AppHandle,
+    endpoint: Url,
+    route: crate::proxy::Route,
+) -> Result<Updater, String> {
+    if pubkey(app).is_none() {
+        return Err("This build has no update signing key, so it can't update itself".to_string());
+    }
+    let builder = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| e.to_string())?
+        .timeout(REQUEST_TIMEOUT);
+    let builder = match route {
+        crate::proxy::Route::System => builder,
+        crate::proxy::Route::Direct => builder.no_proxy(),
+        crate::proxy::Route::Via(url) => builder.proxy(url),
+    };
+    builder
+        .build()
+        .map_err(|e| format!("Updater unavailable: {}", e))
+}
+
+/// Ask the channel's endpoint for a newer version. A version already
+/// downloaded is kept if it's still the newest.
+async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let channel = crate::settings::get(app).updates.channel;
+    let found = updater(app, channel)?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?;
+    let Some(update) = found else {
+        *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        return Ok(None);
+    };
+    let (info, is_new) = {
+        let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        let same = pending
+            .as_ref()
+            .is_some_and(|p| p.update.version == update.version && p.channel == channel);
+        if !same {
+            *pending = Some(Pending {
+                update,
+                channel,
+                bytes: None,
+            });
+        }
+        (pending.as_ref().map(Pending::info), !same)
+    };
+    if is_new {
+        if let Some(info) = &info {
+            tracing::info!("[Updater] {} available ({:?})", info.version, channel);
+            let _ = app.emit("updater://available", info);
+        }
+    }
+    Ok(info)
+}
+
+/// Download and verify the pending update, once.
+async fn download(app: &AppHandle) -> Result<(), String> {
+    let update = {
+        let pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.as_ref() {
+            None => return Err("No update to download".to_string()),
+            Some(p) if p.bytes.is_some() => return Ok(()),
+            Some(p) => p.update.clone(),
+        }
+    };
+    if DOWNLOADING.swap(true, Ordering::SeqCst) {
+        return Err("An update is already downloading".to_string());
+    }
+    let mut downloaded = 0u64;
+    let result = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit(
+                    "updater://progress",
+                    Progress {
+                        version: update.version.clone(),
+                        downloaded,
+                        total,
+                    },
+                );
+            },
+            || {},
+        )
+        .await;
+    DOWNLOADING.store(false, Ordering::SeqCst);
+    let bytes = result.map_err(|e| format!("Update download failed: {}", e))?;
+    let info = {
+        let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.as_mut() {
+            Some(p) if p.update.version == update.version => {
+                p.bytes = Some(bytes);
+                Some(p.info())
+            }
+            _ => None,
+        }
+    };
+    if let Some(info) = info {
+        tracing::info!("[Updater] {} downloaded, installs on quit", info.version);
+        let _ = app.emit("updater://ready", &info);
+    }
+    Ok(())
+}
+
+/// Background checks and downloads while automatic updates are on. Called
+/// once from setup.
+pub(crate) fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            let automatic = crate::settings::get(&app).updates.automatic;
+            if automatic
+                && pubkey(&app).is_some()
+                && !crate::privacy::local_only()
+                && crate::network::is_online()
+            {
+                let outcome = match check(&app).await {
+                    Ok(Some(_)) => download(&app).await,
+                    Ok(None) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = outcome {
+                    tracing::warn!("[Updater] {}", e);
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Install a downloaded update as the app exits. Called from the exit
+/// handler; on Windows the installer takes over from here.
+pub(crate) fn install_on_quit() {
+    let Some(pending) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    let Some(bytes) = pending.bytes else {
+        return;
+    };
+    tracing::info!("[Updater] Installing {}", pending.update.version);
+    if let Err(e) = pending.update.restart_after_install(false).install(bytes) {
+        tracing::warn!("[Updater] Install failed: {}", e);
+    }
+}
+
+/// Check the selected channel now. With automatic updates on, a new
+/// version starts downloading in the background.
+#[tauri::command]
+pub(crate) async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let info = check(&app).await?;
+    if info.as_ref().is_some_and(|i| !i.downloaded) && crate::settings::get(&app).updates.automatic
+    {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = download(&app).await {
+                tracing::warn!("[Updater] {}", e);
+            }
+        });
+    }
+    Ok(info)
+}
+
+/// The update found by the last check, if any.
+#[tauri::command]
+pub(crate) async fn get_pending_update() -> Result<Option<UpdateInfo>, String> {
+    Ok(pending_info())
+}
+
+/// Download (if needed) and install the pending update now, then restart.
+#[tauri::command]
+pub(crate) async fn install_update(app: AppHandle) -> Result<(), String> {
+    if pending_info().is_none() && check(&app).await?.is_none() {
+        return Err("LocalBook is up to date".to_string());
+    }
+    download(&app).await?;
+    let Some(Pending {
+        update,
+        bytes: Some(bytes),
+        ..
+    }) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take()
+    else {
+        return Err("The update is no longer available".to_string());
+    };
+    tracing::info!("[Updater] Installing {} now", update.version);
+    update
+        .install(bytes)
+        .map_err(|e| format!("Update install failed: {}", e))?;
+    app.restart()
+}
+
+#[tauri::command]
+pub(crate) async fn set_update_settings(
+    app: AppHandle,
+    channel: UpdateChannel,
+    automatic: bool,
+) -> Result<(), String> {
+    let before = crate::settings::get(&app).updates.channel;
+    crate::settings::update(&app, |s| {
+        s.updates = UpdateSettings { channel, automatic };
+    })?;
+    if before != channel {
+        // A beta build found earlier isn't an update on the stable channel.
+        *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+    Ok(())
+}
+
+/// The manifest's platform keys a package for this build may be under, most
+/// specific first, as the plugin looks them up.
+fn manifest_targets() -> Vec<String> {
+    let (os, installers): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("darwin", &["app"])
+    } else if cfg!(windows) {
+        ("windows", &["nsis", "msi"])
+    } else {
+        ("linux", &["appimage"])
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86" => "i686",
+        "arm" => "armv7",
+        arch => arch,
+    };
+    installers
+        .iter()
+        .map(|installer| format!("{}-{}-{}", os, arch, installer))
+        .chain([format!("{}-{}", os, arch)])
+        .collect()
+}
+
+/// Replace the running app bundle with the one in the package (a
+/// `.app.tar.gz`). Takes effect on restart.
+#[cfg(target_os = "macos")]
+fn install_package(_package: &Path, bytes: &[u8]) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let bundle = exe
+        .ancestors()
+        .find(|p| p.extension().is_some_and(|e| e == "app"))
+        .ok_or("LocalBook isn't running from an app bundle")?
+        .to_path_buf();
+    let parent = bundle
+        .parent()
+        .ok_or("The app bundle has no parent folder")?;
+    let staging = parent.join(".LocalBook-update");
+    let backup = parent.join(".LocalBook-previous.app");
+    let _ = std::fs::remove_dir_all(&staging);
+    let _ = std::fs::remove_dir_all(&backup);
+    std::fs::create_dir_all(&staging).map_err(|e| {
+        format!(
+            "Can't write next to {} (move LocalBook to Applications): {}",
+            bundle.display(),
+            e
+        )
+    })?;
+    let result = (|| {
+        tar::Archive::new(flate2::read::GzDecoder::new(bytes))
+            .unpack(&staging)
+            .map_err(|e| format!("Failed to unpack the update: {}", e))?;
+        let new_app = std::fs::read_dir(&staging)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.extension().is_some_and(|e| e == "app"))
+            .ok_or("The update package holds no app")?;
+        std::fs::rename(&bundle, &backup)
+            .map_err(|e| format!("Failed to move the current app aside: {}", e))?;
+        if let Err(e) = std::fs::rename(&new_app, &bundle) {
+            let _ = std::fs::rename(&backup, &bundle);
+            return Err(format!("Failed to put the update in place: {}", e));
+        }
+        let _ = std::fs::remove_dir_all(&backup);
+        Ok(())
+    })();
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Start the installer in the package (an NSIS `-setup.exe` or an `.msi`,
+/// possibly zipped). It waits for the app to quit, replaces it and starts it
+/// again.
+#[cfg(windows)]
+fn install_package(package: &Path, bytes: &[u8]) -> Result<(), String> {
+    let dir = std::env::temp_dir().join(format!("LocalBook-update-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let installer = if bytes.starts_with(b"PK\x03\x04") {
+        zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .and_then(|mut archive| archive.extract(&dir))
+            .map_err(|e| format!("Failed to unpack the update: {}", e))?;
+        std::fs::read_dir(&dir)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.extension().is_some_and(|e| e == "exe" || e == "msi"))
+            .ok_or("The update package holds no installer")?
+    } else {
+        let path = dir.join(package.file_name().ok_or("The package has no file name")?);
+        std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+        path
+    };
+    let started = if installer.extension().is_some_and(|e| e == "msi") {
+        std::process::Command::new("msiexec")
+            .arg("/i")
+            .arg(&installer)
+            .args(["/passive", "/promptrestart", "AUTOLAUNCHAPP=True"])
+            .spawn()
+    } else {
+        std::process::Command::new(&installer)
+            .args(["/P", "/UPDATE", "/R"])
+            .spawn()
+    };
+    started
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start the installer: {}", e))
+}
+
+/// Replace the running AppImage with the one in the package (an
+/// `.AppImage.tar.gz`, or the AppImage itself). Takes effect on restart.
+#[cfg(target_os = "linux")]
+fn install_package(_package: &Path, bytes: &[u8]) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let appimage = std::env::var_os("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .ok_or("Only the AppImage updates itself; install the package with your package manager")?;
+    let tmp = appimage.with_extension("AppImage.update");
+    let written = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(bytes));
+        let mut entries = archive
+            .entries()
+            .map_err(|e| format!("Failed to unpack the update: {}", e))?;
+        let mut entry = entries
+            .find_map(|e| {
+                e.ok().filter(|e| {
+                    e.path()
+                        .is_ok_and(|p| p.extension().is_some_and(|x| x == "AppImage"))
+                })
+            })
+            .ok_or("The update package holds no AppImage")?;
+        entry.unpack(&tmp).map(|_| ())
+    } else {
+        std::fs::write(&tmp, bytes)
+    };
+    let result = written
+        .and_then(|()| std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755)))
+        .and_then(|()| std::fs::rename(&tmp, &appimage))
+        .map_err(|e| format!("Failed to put the update in place: {}", e));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn install_package(_package: &Path, _bytes: &[u8]) -> Result<(), String> {
+    Err("Installing updates from a file isn't supported on this platform".to_string())
+}
+
+/// Install an update from a folder holding a release's `latest.json`, its
+/// `latest.json.sig` and this platform's package (and nothing needs to be
+/// online). `path` is the manifest or the package. Restarts into the new
+/// version.
+#[tauri::command]
+pub(crate) async fn install_update_from_file(app: AppHandle, path: String) -> Result<(), String> {
+    let path = crate::scope::check(&app, Path::new(&path))?;
+    let folder = path
+        .parent()
+        .ok_or_else(|| format!("{} is not in a folder", path.display()))?
+        .to_path_buf();
+    let manifest_path = folder.join(MANIFEST_FILE);
+    let raw = std::fs::read(&manifest_path).map_err(|e| {
+        format!(
+            "Failed to read {} (download it from the release page into the same folder): {}",
+            manifest_path.display(),
+            e
+        )
+    })?;
+    let signature_path = folder.join(MANIFEST_SIGNATURE_FILE);
+    let manifest_signature = std::fs::read_to_string(&signature_path).map_err(|e| {
+        format!(
+            "Failed to read {} (download it from the release page into the same folder): {}",
+            signature_path.display(),
+            e
+        )
+    })?;
+    verify(&app, &raw, &manifest_signature)
+        .map_err(|e| format!("{} was rejected: {}", MANIFEST_FILE, e))?;
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&raw).map_err(|e| format!("Malformed {}: {}", MANIFEST_FILE, e))?;
+
+    let version = manifest["version"]
+        .as_str()
+        .map(|v| v.trim_start_matches('v').to_string())
+        .ok_or_else(|| format!("{} has no version", MANIFEST_FILE))?;
+    let current = app.package_info().version.to_string();
+    match (
+        semver::Version::parse(&version),
+        semver::Version::parse(&current),
+    ) {
+        (Ok(v), Ok(c)) if v > c => {}
+        (Ok(_), Ok(_)) => {
+            return Err(format!(
+                "LocalBook {} is not newer than this version ({})",
+                version, current
+            ))
+        }
+        _ => return Err(format!("Invalid version {:?}", version)),
+    }
+
+    let platforms = manifest["platforms"]
+        .as_object()
+        .ok_or_else(|| format!("{} lists no packages", MANIFEST_FILE))?;
+    let (package, signature) = manifest_targets()
+        .iter()
+        .filter_map(|target| platforms.get(target))
+        .find_map(|entry| {
+            let name = entry["url"]
+                .as_str()
+                .and_then(|u| Url::parse(u).ok())
+                .and_then(|u| u.path_segments()?.next_back().map(String::from))?;
+            let local = folder.join(name);
+            let signature = entry["signature"].as_str()?;
+            local.is_file().then(|| (local, signature.to_string()))
+        })
+        .ok_or_else(|| {
+            format!(
+                "This platform's package from {} isn't in {}",
+                MANIFEST_FILE,
+                folder.display()
+            )
+        })?;
+    let bytes = std::fs::read(&package)
+        .map_err(|e| format!("Failed to read {}: {}", package.display(), e))?;
+    verify(&app, &bytes, &signature)
+        .map_err(|e| format!("The update package was rejected: {}", e))?;
+
+    tracing::info!("[Updater] Installing {} from {}", version, folder.display());
+    crate::audit::record(
+        "app_updated_from_file",
+        format!("{} → {} from {}", current, version, folder.display()),
+    );
+    *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    install_package(&package, &bytes)?;
+    if cfg!(windows) {
+        // The installer starts the new version once this one has quit.
+        app.exit(0);
+        return Ok(());
+    }
+    app.restart()
+}
@@ -0,0 +1,189 @@
+//! Opt-in usage analytics that never leave the machine.
+//!
+//! Off until the user turns it on. While on, the app keeps per-day counts of
+//! what happened — documents imported, chats sent, jobs that failed, backend
+//! crashes — in `<app data>/usage.json`. Only event names and counts are
+//! stored: no titles, paths, ids or text. The frontend can count its own
+//! events through `record_usage_event`, limited to `FRONTEND_EVENTS`.
+//!
+//! Nothing is ever sent anywhere. `get_usage_stats` feeds the local
+//! dashboard, and `export_usage_summary` produces a small anonymized summary
+//! (totals and active days, no dates) users can attach to an issue if they
+//! choose. Turning analytics off deletes the store.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const STORE_FILE: &str = "usage.json";
+/// Per-day counts older than this are dropped; totals are kept.
+const KEEP_DAYS: usize = 90;
+
+/// Events the frontend may count. Anything else is refused, so a caller
+/// can't slip content into the store as an event name.
+const FRONTEND_EVENTS: &[&str] = &[
+    "chat_sent",
+    "search_run",
+    "note_created",
+    "audio_generated",
+    "export_created",
+    "error_shown",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+
+struct Store {
+    path: PathBuf,
+    stats: Counts,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Counts {
+    /// Day (YYYY-MM-DD) counting started.
+    since: Option<String>,
+    totals: BTreeMap<String, u64>,
+    /// Last `KEEP_DAYS` days, by YYYY-MM-DD.
+    days: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct UsageStats {
+    enabled: bool,
+    #[serde(flatten)]
+    counts: Counts,
+}
+
+fn load(path: &std::path::Path) -> Counts {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(store: &Store) {
+    let tmp = store.path.with_extension("json.tmp");
+    let written = serde_json::to_string(&store.stats)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&tmp, json).map_err(|e| e.to_string()))
+        .and_then(|_| std::fs::rename(&tmp, &store.path).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        tracing::warn!("[Usage] Failed to save {}: {}", store.path.display(), e);
+    }
+}
+
+/// Pick up the consent setting and the existing counts. Called in setup
+/// after settings load.
+pub(crate) fn start(app: &AppHandle) {
+    let path = match crate::data_dir(app) {
+        Ok(dir) => dir.join(STORE_FILE),
+        Err(e) => {
+            tracing::warn!("[Usage] No app data dir: {}", e);
+            return;
+        }
+    };
+    let enabled = crate::settings::get(app).usage_analytics;
+    let stats = if enabled {
+        load(&path)
+    } else {
+        Counts::default()
+    };
+    *STORE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Store { path, stats });
+    ENABLED.store(enabled, Ordering::Relaxed);
+    count("app_launched");
+}
+
+/// Count one occurrence of `event`. A no-op unless the user opted in.
+pub(crate) fn count(event: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(store) = guard.as_mut() else {
+        return;
+    };
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let stats = &mut store.stats;
+    stats.since.get_or_insert_with(|| today.clone());
+    *stats.totals.entry(event.to_string()).or_default() += 1;
+    *stats
+        .days
+        .entry(today)
+        .or_default()
+        .entry(event.to_string())
+        .or_default() += 1;
+    while stats.days.len() > KEEP_DAYS {
+        stats.days.pop_first();
+    }
+    save(store);
+}
+
+#[tauri::command]
+pub(crate) async fn set_usage_analytics(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    crate::settings::update(&app, |s| s.usage_analytics = enabled)?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(store) = guard.as_mut() {
+            store.stats = Counts::default();
+            match std::fs::remove_file(&store.path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Failed to delete usage data: {}", e)),
+            }
+        }
+    }
+    tracing::info!(
+        "[Usage] Analytics {}",
+        if enabled { "on" } else { "off, data deleted" }
+    );
+    Ok(enabled)
+}
+
+/// Count a frontend event (one of `FRONTEND_EVENTS`).
+#[tauri::command]
+pub(crate) async fn record_usage_event(event: String) -> Result<(), String> {
+    if !FRONTEND_EVENTS.contains(&event.as_str()) {
+        return Err(format!("Unknown usage event: {:?}", event));
+    }
+    count(&event);
+    Ok(())
+}
+
+/// Everything counted so far, for the local dashboard.
+#[tauri::command]
+pub(crate) async fn get_usage_stats() -> Result<UsageStats, String> {
+    let counts = STORE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|s| s.stats.clone())
+        .unwrap_or_default();
+    Ok(UsageStats {
+        enabled: ENABLED.load(Ordering::Relaxed),
+        counts,
+    })
+}
+
+/// A summary safe to paste into an issue: app version, platform, totals and
+/// how many days the app was used — no dates, nothing per-day.
+#[tauri::command]
+pub(crate) async fn export_usage_summary(app: AppHandle) -> Result<String, String> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Err("Usage analytics is off".to_string());
+    }
+    let counts = get_usage_stats().await?.counts;
+    let summary = serde_json::json!({
+        "app_version": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "active_days": counts.days.len(),
+        "totals": counts.totals,
+    });
+    serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())
+}
@@ -0,0 +1,834 @@
+//! Encrypted notebooks — sealed into a passphrase-protected vault file and
+//! only put back into the backend after `unlock_notebook`.
+//!
+//! The backend keeps every notebook in shared stores (one SQLite database,
+//! one LanceDB index), so a notebook can't be encrypted in place. Instead,
+//! encrypting a notebook exports it through the API — its sources (the
+//! extracted text; the backend keeps no original files) and notes, each
+//! source's notes, tags and highlights, and generated audio with its files —
+//! seals that with XChaCha20-Poly1305 under an Argon2id key derived from the
+//! passphrase into `<app data>/vault/<id>.lbv`, checks the file decrypts,
+//! records the vault in settings (with the removal pending, which `start`
+//! finishes if it's interrupted), and then removes the notebook from the
+//! backend the way `shred` deletes a
+//! document: stored text and highlights are overwritten before each source
+//! is deleted, audio files and the notebook's vector table are zeroed, and
+//! the database is rebuilt so nothing of it stays in free pages.
+//!
+//! Unlocking decrypts the vault and imports everything into a fresh backend
+//! notebook, which is re-indexed as usual; if that fails partway, what was
+//! imported is removed again. `lock_notebook` seals the current contents
+//! again (picking up edits) and removes them from the backend. Other
+//! generated content (quizzes, visuals and the like) isn't kept.
+//!
+//! While unlocked, a notebook is as readable on disk as any other, and its
+//! key is kept in the keychain. If the app quits without locking it, `start`
+//! seals it again with that key at the next launch, so it doesn't stay in
+//! the backend until the passphrase is next entered. Encrypting, unlocking
+//! and locking run one at a time per notebook.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+use crate::backend_api;
+
+const MAGIC: &[u8; 4] = b"LBV1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const MIN_PASSPHRASE_LEN: usize = 8;
+const API_TIMEOUT: Duration = Duration::from_secs(60);
+const BACKEND_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Keys of unlocked notebooks (by vault id), so `lock_notebook` can re-seal
+/// without asking for the passphrase again. Never written to disk; the
+/// keychain holds a copy (see `key_secret_name`) until the notebook is locked.
+static KEYS: Mutex<Option<HashMap<String, [u8; 32]>>> = Mutex::new(None);
+/// One encrypt, unlock or lock at a time per vault.
+static OPERATIONS: Mutex<Option<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = Mutex::new(None);
+
+/// Settings entry for an encrypted notebook.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct EncryptedNotebook {
+    pub title: String,
+    /// The backend notebook holding the decrypted contents while unlocked.
+    pub backend_id: Option<String>,
+    /// A backend notebook already sealed in the vault whose removal hasn't
+    /// finished; `start` tries again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removing: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct EncryptedNotebookInfo {
+    /// Stable id of the vault (the notebook's id when it was first encrypted).
+    id: String,
+    title: String,
+    unlocked: bool,
+    backend_id: Option<String>,
+    /// The key is held in memory, so `lock_notebook` works without the
+    /// passphrase.
+    can_relock: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SealedSource {
+    /// The backend source it was exported from; not kept in the vault.
+    #[serde(skip)]
+    pub(crate) id: Option<String>,
+    pub(crate) title: String,
+    pub(crate) is_note: bool,
+    pub(crate) content: String,
+    /// The reader's notes on the source. Vault only, like `tags` and
+    /// `highlights`; `sync` sends the text alone.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) notes: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) highlights: Vec<SealedHighlight>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SealedHighlight {
+    pub(crate) start_offset: i64,
+    pub(crate) end_offset: i64,
+    pub(crate) highlighted_text: String,
+    pub(crate) color: String,
+    pub(crate) annotation: String,
+}
+
+/// A generated audio overview and its file.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SealedAudio {
+    /// The backend's record: script, topic, voices and duration.
+    pub(crate) record: serde_json::Value,
+    /// The file's extension, with the dot.
+    pub(crate) extension: String,
+    /// The file, base64-encoded.
+    pub(crate) data: String,
+}
+
+/// A notebook, as sealed into a vault (and, text only, sent by `sync`).
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SealedNotebook {
+    pub(crate) title: String,
+    pub(crate) description: Option<String>,
+    pub(crate) color: Option<String>,
+    pub(crate) sources: Vec<SealedSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) audio: Vec<SealedAudio>,
+}
+
+fn vault_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid notebook id: {:?}", id));
+    }
+    let dir = crate::data_dir(app)?.join("vault");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir.join(format!("{}.lbv", id)))
+}
+
+pub(crate) fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// `MAGIC | salt | nonce | ciphertext`. The salt is kept with the key so a
+/// re-seal under the cached key produces a file the passphrase still opens.
+fn seal(key: &[u8; 32], salt: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok([MAGIC.as_slice(), salt, &nonce, &ciphertext].concat())
+}
+
+fn salt_of(file: &[u8]) -> Result<&[u8], String> {
+    if file.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || &file[..MAGIC.len()] != MAGIC {
+        return Err("Not a LocalBook vault file".to_string());
+    }
+    Ok(&file[MAGIC.len()..MAGIC.len() + SALT_LEN])
+}
+
+fn open(key: &[u8; 32], file: &[u8]) -> Result<Vec<u8>, String> {
+    salt_of(file)?;
+    let nonce_start = MAGIC.len() + SALT_LEN;
+    let nonce = &file[nonce_start..nonce_start + NONCE_LEN];
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), &file[nonce_start + NONCE_LEN..])
+        .map_err(|_| "Wrong passphrase, or the vault file is damaged".to_string())
+}
+
+/// Write via a temp file so a crash never leaves half a vault behind.
+fn write_vault(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("lbv.tmp");
+    std::fs::write(&tmp, bytes).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+fn keys() -> std::sync::MutexGuard<'static, Option<HashMap<String, [u8; 32]>>> {
+    KEYS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Held for the length of an operation on vault `id`.
+async fn operation(id: &str) -> tokio::sync::OwnedMutexGuard<()> {
+    let lock = OPERATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .entry(id.to_string())
+        .or_default()
+        .clone();
+    lock.lock_owned().await
+}
+
+fn key_secret_name(id: &str) -> String {
+    format!("vault_key_{}", id)
+}
+
+async fn save_key(id: &str, key: &[u8; 32]) -> Result<(), String> {
+    let name = key_secret_name(id);
+    let value = base64::engine::general_purpose::STANDARD.encode(key);
+    tauri::async_runtime::spawn_blocking(move || crate::secrets::set(&name, &value))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+async fn load_key(id: &str) -> Result<Option<[u8; 32]>, String> {
+    let name = key_secret_name(id);
+    let stored = tauri::async_runtime::spawn_blocking(move || crate::secrets::get(&name))
+        .await
+        .map_err(|e| e.to_string())??;
+    let Some(stored) = stored else {
+        return Ok(None);
+    };
+    base64::engine::general_purpose::STANDARD
+        .decode(stored)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Some)
+        .ok_or_else(|| "The stored key is malformed".to_string())
+}
+
+async fn forget_key(id: &str) {
+    if let Some(k) = keys().as_mut() {
+        k.remove(id);
+    }
+    let name = key_secret_name(id);
+    let deleted = tauri::async_runtime::spawn_blocking(move || crate::secrets::delete(&name))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    if let Err(e) = deleted {
+        tracing::warn!("[Vault] Could not remove the key for {}: {}", id, e);
+    }
+}
+
+/// The name a document is re-imported under, without the `.txt` it gets.
+pub(crate) fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || " -_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(100)
+        .collect();
+    stem.trim_end_matches(".txt").to_string()
+}
+
+/// Pull a notebook's text out of the backend.
+pub(crate) async fn export(notebook_id: &str) -> Result<SealedNotebook, String> {
+    export_with(notebook_id, false).await
+}
+
+/// `export`, plus (with `extras`) each source's notes, tags and highlights
+/// and the notebook's audio.
+async fn export_with(notebook_id: &str, extras: bool) -> Result<SealedNotebook, String> {
+    let notebook = backend_api::get_json(&format!("/notebooks/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let sources = backend_api::get_json(&format!("/sources/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut sealed = Vec::new();
+    for source in sources.as_array().into_iter().flatten() {
+        let Some(source_id) = source["id"].as_str() else {
+            continue;
+        };
+        let content = backend_api::get_text(
+            &format!("/sources/{}/{}/download", notebook_id, source_id),
+            API_TIMEOUT,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        sealed.push(SealedSource {
+            id: Some(source_id.to_string()),
+            title: source["filename"]
+                .as_str()
+                .unwrap_or("Untitled")
+                .to_string(),
+            is_note: source["type"].as_str() == Some("note")
+                || source["metadata"]["type"].as_str() == Some("note"),
+            content,
+            notes: if extras {
+                source["notes"].as_str().unwrap_or_default().to_string()
+            } else {
+                String::new()
+            },
+            tags: if extras {
+                source["tags"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|t| t.as_str().map(String::from))
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            highlights: Vec::new(),
+        });
+    }
+    let mut exported = SealedNotebook {
+        title: notebook["title"].as_str().unwrap_or("Notebook").to_string(),
+        description: notebook["description"].as_str().map(String::from),
+        color: notebook["color"].as_str().map(String::from),
+        sources: sealed,
+        audio: Vec::new(),
+    };
+    if extras {
+        export_highlights(notebook_id, &mut exported).await?;
+        exported.audio = export_audio(notebook_id).await?;
+    }
+    Ok(exported)
+}
+
+async fn export_highlights(notebook_id: &str, notebook: &mut SealedNotebook) -> Result<(), String> {
+    let listed = backend_api::get_json(
+        &format!("/source-viewer/highlights/notebook/{}", notebook_id),
+        API_TIMEOUT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    for h in listed["highlights"].as_array().into_iter().flatten() {
+        let source_id = h["source_id"].as_str();
+        let Some(source) = notebook
+            .sources
+            .iter_mut()
+            .find(|s| s.id.as_deref() == source_id)
+        else {
+            continue;
+        };
+        source.highlights.push(SealedHighlight {
+            start_offset: h["start_offset"].as_i64().unwrap_or_default(),
+            end_offset: h["end_offset"].as_i64().unwrap_or_default(),
+            highlighted_text: h["highlighted_text"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            color: h["color"].as_str().unwrap_or("yellow").to_string(),
+            annotation: h["annotation"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(())
+}
+
+async fn export_audio(notebook_id: &str) -> Result<Vec<SealedAudio>, String> {
+    let listed = backend_api::get_json(&format!("/audio/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut audio = Vec::new();
+    for record in listed.as_array().into_iter().flatten() {
+        let Some(audio_id) = record["audio_id"].as_str() else {
+            continue;
+        };
+        if record["status"].as_str() != Some("completed") {
+            continue; // nothing to keep from a failed or unfinished generation
+        }
+        let data = backend_api::get_bytes(&format!("/audio/download/{}", audio_id), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?;
+        let extension = record["audio_file_path"]
+            .as_str()
+            .and_then(|p| Path::new(p).extension().and_then(OsStr::to_str))
+            .unwrap_or("mp3");
+        audio.push(SealedAudio {
+            record: record.clone(),
+            extension: format!(".{}", extension),
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        });
+    }
+    Ok(audio)
+}
+
+/// Recreate a sealed notebook in the backend; returns its new id.
+pub(crate) async fn import(app: &AppHandle, notebook: &SealedNotebook) -> Result<String, String> {
+    let created = backend_api::post_json(
+        "/notebooks/",
+        &json!({
+            "title": notebook.title,
+            "description": notebook.description,
+            "color": notebook.color,
+        }),
+        API_TIMEOUT,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let id = created["id"]
+        .as_str()
+        .ok_or("Backend didn't return the new notebook's id")?
+        .to_string();
+    let restored = async {
+        let ids = add_sources(app, &id, &notebook.sources).await?;
+        restore_extras(&id, notebook, &ids).await
+    }
+    .await;
+    if let Err(e) = restored {
+        if let Err(cleanup) = remove(&id).await {
+            tracing::warn!(
+                "[Vault] Could not remove the partly imported notebook {}: {}",
+                id,
+                cleanup
+            );
+        }
+        return Err(e);
+    }
+    Ok(id)
+}
+
+/// Add sealed sources to a backend notebook. Returns each one's new id
+/// (None for those skipped as empty).
+pub(crate) async fn add_sources(
+    app: &AppHandle,
+    id: &str,
+    sources: &[SealedSource],
+) -> Result<Vec<Option<String>>, String> {
+    // Documents go back in as text files through the normal import path;
+    // they're deleted again as soon as the backend has its copy.
+    let staging = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("vault-staging")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+    let result = async {
+        let mut ids = Vec::new();
+        for source in sources {
+            if source.content.trim().is_empty() {
+                ids.push(None); // nothing was extracted, and the backend rejects empty notes
+                continue;
+            }
+            let added = if source.is_note {
+                backend_api::post_json(
+                    &format!("/sources/{}/note", id),
+                    &json!({ "title": source.title, "content": source.content }),
+                    API_TIMEOUT,
+                )
+                .await
+                .map_err(|e| e.to_string())?
+            } else {
+                let path = staging.join(format!("{}.txt", file_stem(&source.title)));
+                std::fs::write(&path, &source.content)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                let uploaded = backend_api::upload_file(&path, id, |_| {}).await;
+                let _ = std::fs::remove_file(&path);
+                uploaded.map_err(|e| e.to_string())?
+            };
+            ids.push(added["source_id"].as_str().map(String::from));
+        }
+        Ok::<_, String>(ids)
+    }
+    .await;
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+/// Put back what `export_with` keeps beyond the text: notes, tags and
+/// highlights on the sources `add_sources` recreated (`ids`, in the same
+/// order), and the notebook's audio.
+async fn restore_extras(
+    id: &str,
+    notebook: &SealedNotebook,
+    ids: &[Option<String>],
+) -> Result<(), String> {
+    for (source, source_id) in notebook.sources.iter().zip(ids) {
+        let Some(source_id) = source_id else {
+            continue;
+        };
+        if !source.notes.is_empty() {
+            backend_api::post_json(
+                "/source-viewer/notes",
+                &json!({ "notebook_id": id, "source_id": source_id, "notes": source.notes }),
+                API_TIMEOUT,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        if !source.tags.is_empty() {
+            backend_api::put_json(
+                &format!("/sources/{}/{}/tags", id, source_id),
+                &json!({ "tags": source.tags }),
+                API_TIMEOUT,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        for h in &source.highlights {
+            backend_api::post_json(
+                "/source-viewer/highlights",
+                &json!({
+                    "notebook_id": id,
+                    "source_id": source_id,
+                    "start_offset": h.start_offset,
+                    "end_offset": h.end_offset,
+                    "highlighted_text": h.highlighted_text,
+                    "color": h.color,
+                    "annotation": h.annotation,
+                }),
+                API_TIMEOUT,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    for audio in &notebook.audio {
+        let r = &audio.record;
+        backend_api::post_json(
+            &format!("/audio/{}/restore", id),
+            &json!({
+                "script": r["script"].as_str().unwrap_or_default(),
+                "topic": r["topic"].as_str().unwrap_or_default(),
+                "duration_minutes": r["duration_minutes"].as_i64().unwrap_or(10),
+                "host1_gender": r["host1_gender"].as_str().unwrap_or("male"),
+                "host2_gender": r["host2_gender"].as_str().unwrap_or("female"),
+                "accent": r["accent"].as_str().unwrap_or("us"),
+                "skill_id": r["skill_id"].as_str(),
+                "duration_seconds": r["duration_seconds"].as_f64().map(|s| s.round() as i64),
+                "extension": audio.extension,
+                "data": audio.data,
+            }),
+            API_TIMEOUT,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Remove a notebook from the backend through the secure-delete path (see
+/// the module docs).
+async fn remove(notebook_id: &str) -> Result<(), String> {
+    let sources = backend_api::get_json(&format!("/sources/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    for source in sources.as_array().into_iter().flatten() {
+        if let Some(source_id) = source["id"].as_str() {
+            crate::shred::shred_source(source_id).await?;
+        }
+    }
+    let audio = backend_api::get_json(&format!("/audio/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let data_dir = crate::backend_data_dir();
+    for record in audio.as_array().into_iter().flatten() {
+        let Some(audio_id) = record["audio_id"].as_str() else {
+            continue;
+        };
+        let (dir, prefix) = (data_dir.join("audio"), audio_id.to_string());
+        tauri::async_runtime::spawn_blocking(move || crate::shred::shred_prefixed(&dir, &prefix))
+            .await
+            .map_err(|e| e.to_string())?;
+        backend_api::delete(&format!("/audio/remove/{}", audio_id), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    backend_api::delete(&format!("/notebooks/{}", notebook_id), API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Deleting rows adds versions to the notebook's table; the earlier ones
+    // still hold the embeddings and chunk text.
+    let table = data_dir
+        .join("lancedb")
+        .join(format!("notebook_{}.lance", notebook_id));
+    tauri::async_runtime::spawn_blocking(move || crate::shred::shred_tree(&table))
+        .await
+        .map_err(|e| e.to_string())?;
+    crate::shred::flush_db(true).await;
+    Ok(())
+}
+
+/// Export, seal and check the vault, then remove the notebook from the
+/// backend.
+async fn seal_and_remove(
+    app: &AppHandle,
+    vault_id: &str,
+    backend_id: &str,
+    key: &[u8; 32],
+    salt: &[u8],
+) -> Result<String, String> {
+    let notebook = export_with(backend_id, true).await?;
+    let plaintext = serde_json::to_vec(&notebook).map_err(|e| e.to_string())?;
+    let sealed = seal(key, salt, &plaintext)?;
+    if open(key, &sealed)? != plaintext {
+        return Err("Vault verification failed; the notebook was left as it is".to_string());
+    }
+    write_vault(&vault_path(app, vault_id)?, &sealed)?;
+    // Recorded before anything is removed, so the vault is never orphaned and
+    // an unfinished removal is picked up again.
+    crate::settings::update(app, |s| {
+        s.encrypted_notebooks.insert(
+            vault_id.to_string(),
+            EncryptedNotebook {
+                title: notebook.title.clone(),
+                backend_id: None,
+                removing: Some(backend_id.to_string()),
+            },
+        );
+    })?;
+    finish_removal(app, vault_id, backend_id).await.map_err(|e| {
+        format!(
+            "Sealed, but removing the notebook from the backend failed (it's tried again at the next start): {}",
+            e
+        )
+    })?;
+    crate::audit::record("notebook_encrypted", vault_id);
+    tracing::info!(
+        "[Vault] Sealed {} {} ({} sources)",
+        vault_id,
+        crate::logging::redact::text(&notebook.title),
+        notebook.sources.len()
+    );
+    Ok(notebook.title)
+}
+
+/// Remove the sealed copy in the backend and clear `removing`.
+async fn finish_removal(app: &AppHandle, vault_id: &str, backend_id: &str) -> Result<(), String> {
+    remove(backend_id).await?;
+    crate::settings::update(app, |s| {
+        if let Some(nb) = s.encrypted_notebooks.get_mut(vault_id) {
+            nb.removing = None;
+        }
+    })?;
+    Ok(())
+}
+
+fn info(app: &AppHandle) -> Vec<EncryptedNotebookInfo> {
+    let keys = keys();
+    let mut list: Vec<EncryptedNotebookInfo> = crate::settings::get(app)
+        .encrypted_notebooks
+        .into_iter()
+        .map(|(id, nb)| EncryptedNotebookInfo {
+            can_relock: keys.as_ref().is_some_and(|k| k.contains_key(&id)),
+            unlocked: nb.backend_id.is_some(),
+            backend_id: nb.backend_id,
+            title: nb.title,
+            id,
+        })
+        .collect();
+    list.sort_by_key(|nb| nb.title.to_lowercase());
+    list
+}
+
+#[tauri::command]
+pub(crate) async fn list_encrypted_notebooks(
+    app: AppHandle,
+) -> Result<Vec<EncryptedNotebookInfo>, String> {
+    Ok(info(&app))
+}
+
+/// Seal a backend notebook into the vault and remove it from the backend.
+#[tauri::command]
+pub(crate) async fn encrypt_notebook(
+    app: AppHandle,
+    notebook_id: String,
+    passphrase: String,
+) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    let _operation = operation(&notebook_id).await;
+    if crate::settings::get(&app)
+        .encrypted_notebooks
+        .contains_key(&notebook_id)
+    {
+        return Err("This notebook is already encrypted".to_string());
+    }
+    vault_path(&app, &notebook_id)?;
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = tauri::async_runtime::spawn_blocking(move || derive_key(&passphrase, &salt))
+        .await
+        .map_err(|e| e.to_string())??;
+    seal_and_remove(&app, &notebook_id, &notebook_id, &key, &salt).await?;
+    Ok(())
+}
+
+/// Decrypt a vault into a new backend notebook. Returns the backend id.
+#[tauri::command]
+pub(crate) async fn unlock_notebook(
+    app: AppHandle,
+    id: String,
+    passphrase: String,
+) -> Result<String, String> {
+    let _operation = operation(&id).await;
+    let entry = crate::settings::get(&app)
+        .encrypted_notebooks
+        .remove(&id)
+        .ok_or_else(|| format!("No encrypted notebook {}", id))?;
+    if let Some(backend_id) = entry.backend_id {
+        return Ok(backend_id);
+    }
+    let file = std::fs::read(vault_path(&app, &id)?)
+        .map_err(|e| format!("Failed to read the vault for {}: {}", entry.title, e))?;
+    let salt = salt_of(&file)?.to_vec();
+    let key = tauri::async_runtime::spawn_blocking(move || derive_key(&passphrase, &salt))
+        .await
+        .map_err(|e| e.to_string())??;
+    let notebook: SealedNotebook =
+        serde_json::from_slice(&open(&key, &file)?).map_err(|e| e.to_string())?;
+
+    let backend_id = import(&app, &notebook).await?;
+    keys()
+        .get_or_insert_with(HashMap::new)
+        .insert(id.clone(), key);
+    if let Err(e) = save_key(&id, &key).await {
+        tracing::warn!(
+            "[Vault] Could not keep the key for {} in the keychain; it won't be locked again if the app quits first: {}",
+            id, e
+        );
+    }
+    crate::settings::update(&app, |s| {
+        if let Some(nb) = s.encrypted_notebooks.get_mut(&id) {
+            nb.backend_id = Some(backend_id.clone());
+        }
+    })?;
+    tracing::info!("[Vault] Unlocked {} as {}", id, backend_id);
+    crate::audit::record("notebook_decrypted", format!("{} as {}", id, backend_id));
+    Ok(backend_id)
+}
+
+/// Seal an unlocked notebook again, including changes made while it was open.
+/// `passphrase` is only needed if the app was restarted since it was unlocked.
+#[tauri::command]
+pub(crate) async fn lock_notebook(
+    app: AppHandle,
+    id: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let _operation = operation(&id).await;
+    let entry = crate::settings::get(&app)
+        .encrypted_notebooks
+        .remove(&id)
+        .ok_or_else(|| format!("No encrypted notebook {}", id))?;
+    let Some(backend_id) = entry.backend_id else {
+        return Ok(());
+    };
+    let file = std::fs::read(vault_path(&app, &id)?)
+        .map_err(|e| format!("Failed to read the vault for {}: {}", entry.title, e))?;
+    let salt = salt_of(&file)?.to_vec();
+    let cached = keys().as_ref().and_then(|k| k.get(&id).copied());
+    let key = match (cached, passphrase) {
+        (Some(key), _) => key,
+        (None, Some(passphrase)) => {
+            let salt = salt.clone();
+            let key = tauri::async_runtime::spawn_blocking(move || derive_key(&passphrase, &salt))
+                .await
+                .map_err(|e| e.to_string())??;
+            open(&key, &file)?; // proves the passphrase before anything is replaced
+            key
+        }
+        (None, None) => return Err("Enter the passphrase to lock this notebook".to_string()),
+    };
+    let sealed = seal_and_remove(&app, &id, &backend_id, &key, &salt).await;
+    // The vault holds the latest contents even if the removal didn't finish.
+    let locked = crate::settings::get(&app)
+        .encrypted_notebooks
+        .get(&id)
+        .is_some_and(|nb| nb.backend_id.is_none());
+    if locked {
+        forget_key(&id).await;
+    }
+    sealed.map(|_| ())
+}
+
+/// Finish removals the last run left unfinished, and seal again the
+/// notebooks it left unlocked, with the keys kept in the keychain. Called in
+/// setup.
+pub(crate) fn start(app: &AppHandle) {
+    let notebooks = crate::settings::get(app).encrypted_notebooks;
+    let removing: Vec<(String, String)> = notebooks
+        .iter()
+        .filter_map(|(id, nb)| Some((id.clone(), nb.removing.clone()?)))
+        .collect();
+    let open: Vec<String> = notebooks
+        .into_iter()
+        .filter(|(_, nb)| nb.backend_id.is_some())
+        .map(|(id, _)| id)
+        .collect();
+    if open.is_empty() && removing.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        crate::startup::backend_wanted();
+        while !crate::backend_ready(&app) {
+            tokio::time::sleep(BACKEND_POLL_INTERVAL).await;
+        }
+        for (id, backend_id) in removing {
+            let _operation = operation(&id).await;
+            match finish_removal(&app, &id, &backend_id).await {
+                Ok(()) => tracing::info!("[Vault] Finished removing the sealed copy of {}", id),
+                Err(e) => {
+                    tracing::warn!("[Vault] Could not remove the sealed copy of {}: {}", id, e)
+                }
+            }
+        }
+        for id in open {
+            match load_key(&id).await {
+                Ok(Some(key)) => {
+                    keys()
+                        .get_or_insert_with(HashMap::new)
+                        .insert(id.clone(), key);
+                    match lock_notebook(app.clone(), id.clone(), None).await {
+                        Ok(()) => tracing::info!("[Vault] Locked {} again after a restart", id),
+                        Err(e) => tracing::warn!("[Vault] Could not lock {} again: {}", id, e),
+                    }
+                }
+                Ok(None) => tracing::warn!(
+                    "[Vault] {} was left unlocked and its key isn't stored; lock it with the passphrase",
+                    id
+                ),
+                Err(e) => tracing::warn!("[Vault] Could not read the key for {}: {}", id, e),
+            }
+        }
+    });
+}
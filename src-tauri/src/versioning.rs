@@ -0,0 +1,580 @@
+//! Note history in a local git repository.
+//!
+//! When turned on, every note's text is written to `<app data>/history/` as
+//! `notes/<note id>.md` and committed when it changes. Notes are checked once
+//! a minute, and `commit_note_history` commits straight away (after a save,
+//! say). `get_history` lists a note's versions and `get_version_diff` what
+//! changed in one of them, as hunks of lines for a diff view.
+//!
+//! The repository is plain git (managed with libgit2, no git install
+//! needed), so people who already keep things in git can clone or browse it
+//! with their own tools, and add a remote: `push_note_history` pushes the
+//! history there, or every commit does with `auto_push`. An access token for
+//! an HTTPS remote is kept in the keychain.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use git2::{
+    Cred, Delta, DiffOptions, IndexAddOption, Patch, PushOptions, RemoteCallbacks, Repository,
+    RepositoryInitOptions, Signature, Sort, Tree,
+};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::backend_api;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const TOKEN_SECRET: &str = "versioning_remote_token";
+const BRANCH: &str = "main";
+const NOTES_DIR: &str = "notes";
+/// Versions `get_history` lists, newest first.
+const MAX_VERSIONS: usize = 500;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct VersioningSettings {
+    pub enabled: bool,
+    /// Where `push_note_history` pushes: an HTTPS URL or a local path.
+    pub remote_url: Option<String>,
+    /// User name for the remote; the token is in the keychain.
+    pub remote_user: Option<String>,
+    /// Push after every commit.
+    pub auto_push: bool,
+    /// Unix seconds.
+    pub last_commit_at: Option<u64>,
+    pub last_push_at: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct VersioningStatus {
+    #[serde(flatten)]
+    settings: VersioningSettings,
+    /// The repository folder.
+    path: String,
+    has_token: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct NoteVersion {
+    commit: String,
+    message: String,
+    /// Unix seconds.
+    time: i64,
+    /// "added", "modified" or "deleted".
+    change: &'static str,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DiffLine {
+    /// "+", "-" or " ".
+    kind: String,
+    old_line: Option<u32>,
+    new_line: Option<u32>,
+    content: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DiffHunk {
+    header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct VersionDiff {
+    commit: String,
+    /// The note before and after this version; None where it didn't exist.
+    old: Option<String>,
+    new: Option<String>,
+    hunks: Vec<DiffHunk>,
+}
+
+/// A note's text as fetched from the backend.
+struct NoteFile {
+    id: String,
+    title: String,
+    content: String,
+}
+
+/// What each note's `content_hash` was when last written, so unchanged notes
+/// aren't downloaded again. Never written to disk.
+static SEEN: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+/// Held while checking, so the loop and `commit_note_history` don't overlap.
+static CHECKING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn repo_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::data_dir(app)?.join("history"))
+}
+
+/// The note's file in the repository, as a git path.
+fn note_path(doc_id: &str) -> Result<String, String> {
+    let valid = !doc_id.is_empty()
+        && doc_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid note id: {:?}", doc_id));
+    }
+    Ok(format!("{}/{}.md", NOTES_DIR, doc_id))
+}
+
+fn open(dir: &Path) -> Result<Repository, String> {
+    if let Ok(repo) = Repository::open(dir) {
+        return Ok(repo);
+    }
+    tracing::info!("[Versioning] Creating repository in {}", dir.display());
+    Repository::init_opts(dir, RepositoryInitOptions::new().initial_head(BRANCH))
+        .map_err(|e| format!("Failed to create the history repository: {}", e))
+}
+
+fn open_existing(app: &AppHandle) -> Result<Repository, String> {
+    Repository::open(repo_dir(app)?).map_err(|_| "No note history yet".to_string())
+}
+
+/// Every note in the backend, downloading only the ones that changed since
+/// `seen`. Returns the changed notes and the ids of all notes.
+async fn fetch_notes(
+    seen: &HashMap<String, String>,
+) -> Result<(Vec<NoteFile>, HashMap<String, String>), String> {
+    let notebooks = backend_api::get_json("/notebooks/", API_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut changed = Vec::new();
+    let mut hashes = HashMap::new();
+    for notebook in notebooks["notebooks"].as_array().into_iter().flatten() {
+        let Some(notebook_id) = notebook["id"].as_str() else {
+            continue;
+        };
+        let sources = backend_api::get_json(&format!("/sources/{}", notebook_id), API_TIMEOUT)
+            .await
+            .map_err(|e| e.to_string())?;
+        for source in sources.as_array().into_iter().flatten() {
+            let is_note = source["type"].as_str() == Some("note")
+                || source["metadata"]["type"].as_str() == Some("note");
+            let Some(id) = source["id"].as_str().filter(|_| is_note) else {
+                continue;
+            };
+            let hash = format!(
+                "{}|{}|{}",
+                source["content_hash"].as_str().unwrap_or_default(),
+                source["characters"],
+                source["filename"].as_str().unwrap_or_default()
+            );
+            if seen.get(id) != Some(&hash) {
+                let content = backend_api::get_text(
+                    &format!("/sources/{}/{}/download", notebook_id, id),
+                    API_TIMEOUT,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+                changed.push(NoteFile {
+                    id: id.to_string(),
+                    title: source["filename"]
+                        .as_str()
+                        .unwrap_or("Untitled")
+                        .to_string(),
+                    content,
+                });
+            }
+            hashes.insert(id.to_string(), hash);
+        }
+    }
+    Ok((changed, hashes))
+}
+
+/// Write changed notes, remove deleted ones and commit if anything differs.
+/// Returns the new commit's id.
+fn commit(
+    dir: &Path,
+    changed: &[NoteFile],
+    present: &HashSet<&str>,
+) -> Result<Option<String>, String> {
+    let repo = open(dir)?;
+    let notes = dir.join(NOTES_DIR);
+    std::fs::create_dir_all(&notes)
+        .map_err(|e| format!("Failed to create {}: {}", notes.display(), e))?;
+    for note in changed {
+        note_path(&note.id)?;
+        let path = notes.join(format!("{}.md", note.id));
+        std::fs::write(&path, &note.content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    for entry in std::fs::read_dir(&notes)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        if !present.contains(stem) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    index
+        .add_all([NOTES_DIR], IndexAddOption::DEFAULT, None)
+        .map_err(|e| e.to_string())?;
+    index
+        .update_all([NOTES_DIR], None)
+        .map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())?;
+    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+        return Ok(None);
+    }
+
+    let parent_tree = parent.as_ref().and_then(|p| p.tree().ok());
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .map_err(|e| e.to_string())?;
+    let titles: HashMap<&str, &str> = changed
+        .iter()
+        .map(|n| (n.id.as_str(), n.title.as_str()))
+        .collect();
+    let mut lines = Vec::new();
+    for delta in diff.deltas() {
+        let id = delta
+            .new_file()
+            .path()
+            .or(delta.old_file().path())
+            .and_then(|p| p.file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let verb = match delta.status() {
+            Delta::Added => "Add",
+            Delta::Deleted => "Delete",
+            _ => "Edit",
+        };
+        lines.push(format!("{} {}", verb, titles.get(id).unwrap_or(&id)));
+    }
+    let message = match lines.as_slice() {
+        [one] => one.clone(),
+        many => format!("Update {} notes\n\n{}", many.len(), many.join("\n")),
+    };
+    let signature =
+        Signature::now("LocalBook", "localbook@localhost").map_err(|e| e.to_string())?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parents,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(Some(oid.to_string()))
+}
+
+/// Commit whatever notes changed. Returns the new commit's id, if any.
+async fn check(app: &AppHandle) -> Result<Option<String>, String> {
+    let _checking = CHECKING.lock().await;
+    let seen = SEEN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default();
+    let (changed, hashes) = fetch_notes(&seen).await?;
+    let dir = repo_dir(app)?;
+    let written = hashes.clone();
+    let commit_id = tauri::async_runtime::spawn_blocking(move || {
+        let present: HashSet<&str> = written.keys().map(String::as_str).collect();
+        commit(&dir, &changed, &present)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    *SEEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(hashes);
+    let Some(commit_id) = commit_id else {
+        return Ok(None);
+    };
+    tracing::info!("[Versioning] Committed {}", &commit_id[..8]);
+    let settings = crate::settings::update(app, |s| {
+        s.versioning.last_commit_at = Some(crate::models::now_secs())
+    })?
+    .versioning;
+    if settings.auto_push && settings.remote_url.is_some() {
+        if let Err(e) = push(app).await {
+            tracing::warn!("[Versioning] Push failed: {}", e);
+        }
+    }
+    Ok(Some(commit_id))
+}
+
+async fn push(app: &AppHandle) -> Result<(), String> {
+    let settings = crate::settings::get(app).versioning;
+    let url = settings
+        .remote_url
+        .filter(|u| !u.trim().is_empty())
+        .ok_or("Set a remote to push to first")?;
+    if url.contains("://") {
+        crate::privacy::guard(&url, "Note history push")?;
+    }
+    let dir = repo_dir(app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let token = crate::secrets::get(TOKEN_SECRET)?;
+        let repo = Repository::open(&dir).map_err(|_| "No note history yet".to_string())?;
+        let mut remote = repo.remote_anonymous(&url).map_err(|e| e.to_string())?;
+        let mut rejected = None;
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|_, username, _| match &token {
+            Some(token) => Cred::userpass_plaintext(
+                settings
+                    .remote_user
+                    .as_deref()
+                    .or(username)
+                    .unwrap_or("git"),
+                token,
+            ),
+            None => Cred::default(),
+        });
+        callbacks.push_update_reference(|reference, status| {
+            if let Some(status) = status {
+                rejected = Some(format!("{} was rejected: {}", reference, status));
+            }
+            Ok(())
+        });
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", BRANCH);
+        let result = remote.push(&[refspec.as_str()], Some(&mut options));
+        drop(options);
+        result.map_err(|e| format!("Push failed: {}", e.message()))?;
+        rejected.map_or(Ok(()), Err)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    tracing::info!("[Versioning] Pushed");
+    crate::settings::update(app, |s| {
+        s.versioning.last_push_at = Some(crate::models::now_secs())
+    })?;
+    Ok(())
+}
+
+/// Start the check loop. Called once from setup.
+pub(crate) fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let enabled = crate::settings::get(&app).versioning.enabled;
+            if !enabled || crate::pause::is_paused(&app) || !crate::backend_ready(&app) {
+                continue;
+            }
+            if let Err(e) = check(&app).await {
+                tracing::warn!("[Versioning] {}", e);
+            }
+        }
+    });
+}
+
+/// The note's file in `tree`, if it's there.
+fn blob_text(repo: &Repository, tree: Option<&Tree>, path: &str) -> Option<String> {
+    let entry = tree?.get_path(Path::new(path)).ok()?;
+    let blob = entry.to_object(repo).ok()?.peel_to_blob().ok()?;
+    Some(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+fn status(app: &AppHandle) -> Result<VersioningStatus, String> {
+    Ok(VersioningStatus {
+        settings: crate::settings::get(app).versioning,
+        path: repo_dir(app)?.display().to_string(),
+        has_token: crate::secrets::get(TOKEN_SECRET)?.is_some(),
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn get_note_versioning(app: AppHandle) -> Result<VersioningStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || status(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Turn note history on or off and set the remote. `token`: None keeps the
+/// saved one, "" removes it.
+#[tauri::command]
+pub(crate) async fn set_note_versioning(
+    app: AppHandle,
+    enabled: bool,
+    remote_url: Option<String>,
+    remote_user: Option<String>,
+    token: Option<String>,
+    auto_push: bool,
+) -> Result<VersioningStatus, String> {
+    let remote_url = remote_url.filter(|u| !u.trim().is_empty());
+    if let Some(url) = &remote_url {
+        if url.contains("://") && !url.starts_with("https://") && !url.starts_with("file://") {
+            return Err("Only HTTPS remotes and local folders are supported".to_string());
+        }
+    }
+    let was_enabled = crate::settings::get(&app).versioning.enabled;
+    crate::settings::update(&app, |s| {
+        let v = &mut s.versioning;
+        v.enabled = enabled;
+        v.remote_url = remote_url;
+        v.remote_user = remote_user.filter(|u| !u.trim().is_empty());
+        v.auto_push = auto_push;
+    })?;
+    let status_app = app.clone();
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        match token.as_deref() {
+            Some("") => crate::secrets::delete(TOKEN_SECRET)?,
+            Some(token) => crate::secrets::set(TOKEN_SECRET, token)?,
+            None => {}
+        }
+        status(&status_app)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if enabled != was_enabled {
+        tracing::info!("[Versioning] {}", if enabled { "On" } else { "Off" });
+        crate::audit::record("note_versioning", if enabled { "on" } else { "off" });
+    }
+    if enabled && !was_enabled {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = check(&app).await {
+                tracing::warn!("[Versioning] {}", e);
+            }
+        });
+    }
+    Ok(status)
+}
+
+/// Commit changed notes now. Returns the new commit's id, or None if
+/// nothing changed.
+#[tauri::command]
+pub(crate) async fn commit_note_history(app: AppHandle) -> Result<Option<String>, String> {
+    if !crate::settings::get(&app).versioning.enabled {
+        return Err("Note history is off".to_string());
+    }
+    check(&app).await
+}
+
+#[tauri::command]
+pub(crate) async fn push_note_history(app: AppHandle) -> Result<(), String> {
+    push(&app).await?;
+    crate::audit::record("note_history_pushed", "");
+    Ok(())
+}
+
+/// A note's versions, newest first.
+#[tauri::command]
+pub(crate) async fn get_history(
+    app: AppHandle,
+    doc_id: String,
+) -> Result<Vec<NoteVersion>, String> {
+    let path = note_path(&doc_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = open_existing(&app)?;
+        if repo.head().is_err() {
+            return Ok(Vec::new());
+        }
+        let mut walk = repo.revwalk().map_err(|e| e.to_string())?;
+        walk.set_sorting(Sort::TIME).map_err(|e| e.to_string())?;
+        walk.push_head().map_err(|e| e.to_string())?;
+        let mut versions = Vec::new();
+        for oid in walk.flatten() {
+            let Ok(commit) = repo.find_commit(oid) else {
+                continue;
+            };
+            let blob = |tree: Option<Tree>| tree?.get_path(Path::new(&path)).ok().map(|e| e.id());
+            let now = blob(commit.tree().ok());
+            let before = blob(commit.parent(0).ok().and_then(|p| p.tree().ok()));
+            let change = match (before, now) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "deleted",
+                (Some(a), Some(b)) if a != b => "modified",
+                _ => continue,
+            };
+            versions.push(NoteVersion {
+                commit: oid.to_string(),
+                message: commit.summary().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                change,
+            });
+            if versions.len() == MAX_VERSIONS {
+                break;
+            }
+        }
+        Ok(versions)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// What changed in a note in one version (from `get_history`).
+#[tauri::command]
+pub(crate) async fn get_version_diff(
+    app: AppHandle,
+    doc_id: String,
+    commit: String,
+) -> Result<VersionDiff, String> {
+    let path = note_path(&doc_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = open_existing(&app)?;
+        let oid =
+            git2::Oid::from_str(&commit).map_err(|_| format!("Invalid commit: {}", commit))?;
+        let found = repo
+            .find_commit(oid)
+            .map_err(|_| format!("No version {}", commit))?;
+        let tree = found.tree().map_err(|e| e.to_string())?;
+        let parent_tree = found.parent(0).ok().and_then(|p| p.tree().ok());
+        let diff = repo
+            .diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&tree),
+                Some(DiffOptions::new().pathspec(path.as_str())),
+            )
+            .map_err(|e| e.to_string())?;
+        let mut hunks = Vec::new();
+        if diff.deltas().len() > 0 {
+            if let Some(patch) = Patch::from_diff(&diff, 0).map_err(|e| e.to_string())? {
+                for h in 0..patch.num_hunks() {
+                    let (hunk, count) = patch.hunk(h).map_err(|e| e.to_string())?;
+                    let mut lines = Vec::with_capacity(count);
+                    for l in 0..count {
+                        let line = patch.line_in_hunk(h, l).map_err(|e| e.to_string())?;
+                        lines.push(DiffLine {
+                            kind: line.origin().to_string(),
+                            old_line: line.old_lineno(),
+                            new_line: line.new_lineno(),
+                            content: String::from_utf8_lossy(line.content())
+                                .trim_end_matches('\n')
+                                .to_string(),
+                        });
+                    }
+                    hunks.push(DiffHunk {
+                        header: String::from_utf8_lossy(hunk.header())
+                            .trim_end()
+                            .to_string(),
+                        old_start: hunk.old_start(),
+                        old_lines: hunk.old_lines(),
+                        new_start: hunk.new_start(),
+                        new_lines: hunk.new_lines(),
+                        lines,
+                    });
+                }
+            }
+        }
+        Ok(VersionDiff {
+            commit: oid.to_string(),
+            old: blob_text(&repo, parent_tree.as_ref(), &path),
+            new: blob_text(&repo, Some(&tree), &path),
+            hunks,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
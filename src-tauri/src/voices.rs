@@ -0,0 +1,363 @@
+//! Piper TTS voices — catalog, downloads, preview and default voice choice.
+//!
+//! The catalog is rhasspy/piper-voices' `voices.json` on the Hugging Face Hub,
+//! cached in `<models>/voices/` for a day so the picker works offline. A voice
+//! is an `.onnx` model plus its `.onnx.json` config, fetched through the model
+//! manager's resumable downloader. Preview runs the `piper` CLI on a short
+//! sentence and returns the WAV path for the webview to play through the asset
+//! protocol.
+//!
+//! The default voice is a shell setting, with optional per-notebook overrides.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+const CATALOG_URL: &str = "https://huggingface.co/rhasspy/piper-voices/resolve/main/voices.json";
+const FILES_BASE: &str = "https://huggingface.co/rhasspy/piper-voices/resolve/main";
+const CATALOG_FILE: &str = "voices.json";
+const CATALOG_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_PREVIEW: &str = "This is how I sound when reading your notes aloud.";
+/// Which quality to download when none is asked for, best first.
+const QUALITY_PREFERENCE: [&str; 4] = ["medium", "low", "high", "x_low"];
+
+#[derive(Deserialize)]
+struct CatalogVoice {
+    key: String,
+    name: String,
+    language: CatalogLanguage,
+    quality: String,
+    #[serde(default)]
+    num_speakers: u32,
+    /// Repo-relative path → size/digest.
+    files: HashMap<String, CatalogFile>,
+}
+
+#[derive(Deserialize)]
+struct CatalogLanguage {
+    /// "en_US"
+    code: String,
+    #[serde(default)]
+    name_english: String,
+    #[serde(default)]
+    country_english: String,
+}
+
+#[derive(Deserialize)]
+struct CatalogFile {
+    size_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Voice {
+    /// "en_US-lessac-medium"
+    key: String,
+    name: String,
+    language: String,
+    language_name: String,
+    quality: String,
+    num_speakers: u32,
+    size_bytes: u64,
+    installed: bool,
+}
+
+fn voices_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::models::models_dir(app)?.join("voices");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Keys become file names — reject anything that isn't a plain catalog key.
+fn validate_key(key: &str) -> Result<(), String> {
+    let ok = !key.is_empty()
+        && !key.contains("..")
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("Invalid voice key: {:?}", key))
+    }
+}
+
+fn model_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.onnx", key))
+}
+
+fn installed(dir: &Path, key: &str) -> bool {
+    model_path(dir, key).exists() && dir.join(format!("{}.onnx.json", key)).exists()
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    crate::proxy::client()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("HTTP client build failed: {}", e))
+}
+
+/// The catalog, refreshed when the cached copy is stale. Falls back to the
+/// stale copy when offline.
+async fn catalog(app: &AppHandle) -> Result<BTreeMap<String, CatalogVoice>, String> {
+    let path = voices_dir(app)?.join(CATALOG_FILE);
+    let fresh = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age < CATALOG_MAX_AGE);
+
+    if !fresh && crate::network::is_online() {
+        let fetched = async {
+            let resp = client()?
+                .get(CATALOG_URL)
+                .timeout(Duration::from_secs(30))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !resp.status().is_success() {
+                return Err(format!("HTTP {}", resp.status()));
+            }
+            resp.text().await.map_err(|e| e.to_string())
+        }
+        .await;
+        match fetched {
+            Ok(raw) => {
+                if let Err(e) = std::fs::write(&path, &raw) {
+                    tracing::warn!("[Voices] Could not cache catalog: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("[Voices] Catalog refresh failed (using cache): {}", e),
+        }
+    }
+
+    let raw = std::fs::read_to_string(&path).map_err(|_| {
+        "Voice catalog unavailable — connect to the internet once to load it".to_string()
+    })?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid voice catalog: {}", e))
+}
+
+/// Catalog voices, optionally filtered to a language ("en", "en_US").
+#[tauri::command]
+pub(crate) async fn list_voices(
+    app: AppHandle,
+    lang: Option<String>,
+) -> Result<Vec<Voice>, String> {
+    let dir = voices_dir(&app)?;
+    let lang = lang.filter(|l| !l.is_empty()).map(|l| l.replace('-', "_"));
+    let mut voices: Vec<Voice> = catalog(&app)
+        .await?
+        .into_values()
+        .filter(|v| {
+            lang.as_deref().is_none_or(|l| {
+                v.language.code.eq_ignore_ascii_case(l)
+                    || v.language.code.split('_').next() == Some(l)
+            })
+        })
+        .map(|v| Voice {
+            installed: installed(&dir, &v.key),
+            size_bytes: v.files.values().map(|f| f.size_bytes).sum(),
+            language_name: if v.language.country_english.is_empty() {
+                v.language.name_english
+            } else {
+                format!(
+                    "{} ({})",
+                    v.language.name_english, v.language.country_english
+                )
+            },
+            language: v.language.code,
+            key: v.key,
+            name: v.name,
+            quality: v.quality,
+            num_speakers: v.num_speakers,
+        })
+        .collect();
+    voices.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(voices)
+}
+
+/// Download a voice by language code and name, e.g. ("en_US", "lessac").
+/// `quality` picks among "x_low" / "low" / "medium" / "high"; defaults to
+/// medium, else the closest published quality (`QUALITY_PREFERENCE`).
+#[tauri::command]
+pub(crate) async fn download_voice(
+    app: AppHandle,
+    lang: String,
+    name: String,
+    quality: Option<String>,
+) -> Result<String, String> {
+    let catalog = catalog(&app).await?;
+    let prefix = format!("{}-{}-", lang.replace('-', "_"), name);
+    let voice = match quality {
+        Some(q) => catalog.get(&format!("{}{}", prefix, q)),
+        None => QUALITY_PREFERENCE
+            .iter()
+            .find_map(|q| catalog.get(&format!("{}{}", prefix, q)))
+            .or_else(|| catalog.values().find(|v| v.key.starts_with(&prefix))),
+    }
+    .ok_or_else(|| format!("No voice {} {} in the Piper catalog", lang, name))?;
+
+    let dir = voices_dir(&app)?;
+    let client = client()?;
+    tracing::info!("[Voices] Downloading {}", voice.key);
+    for (rel, file) in &voice.files {
+        let file_name = match rel.rsplit('/').next() {
+            Some(f) if f.ends_with(".onnx") || f.ends_with(".onnx.json") => f,
+            _ => continue, // MODEL_CARD etc.
+        };
+        let dest = dir.join(file_name);
+        let part = dir.join(format!("{}.part", file_name));
+        let url = format!("{}/{}", FILES_BASE, rel);
+        crate::models::fetch_to_part(&app, &client, &voice.key, &url, None, &part).await?;
+        let size = tokio::fs::metadata(&part)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+        if size != file.size_bytes {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(format!(
+                "{} is {} bytes, expected {}",
+                file_name, size, file.size_bytes
+            ));
+        }
+        tokio::fs::rename(&part, &dest)
+            .await
+            .map_err(|e| format!("Failed to move {} into place: {}", file_name, e))?;
+    }
+    tracing::info!("[Voices] {} ready", voice.key);
+    Ok(voice.key.clone())
+}
+
+#[tauri::command]
+pub(crate) async fn remove_voice(app: AppHandle, key: String) -> Result<(), String> {
+    validate_key(&key)?;
+    let dir = voices_dir(&app)?;
+    for path in [
+        model_path(&dir, &key),
+        dir.join(format!("{}.onnx.json", key)),
+    ] {
+        match tokio::fs::remove_file(&path).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete {}: {}", path.display(), e)),
+        }
+    }
+    Ok(())
+}
+
+fn find_piper(app: &AppHandle) -> Option<PathBuf> {
+    let exe = if cfg!(windows) { "piper.exe" } else { "piper" };
+    let mut dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|p| std::env::split_paths(&p).collect())
+        .unwrap_or_default();
+    // GUI apps on macOS don't inherit the shell PATH.
+    dirs.extend(["/opt/homebrew/bin", "/usr/local/bin"].map(PathBuf::from));
+    if let Ok(data) = crate::data_dir(app) {
+        dirs.push(data.join("piper"));
+    }
+    dirs.into_iter().map(|d| d.join(exe)).find(|p| p.exists())
+}
+
+/// Synthesize a short sample with an installed voice. Returns the path of a
+/// WAV file in the cache dir.
+#[tauri::command]
+pub(crate) async fn preview_voice(
+    app: AppHandle,
+    key: String,
+    text: Option<String>,
+) -> Result<String, String> {
+    validate_key(&key)?;
+    let dir = voices_dir(&app)?;
+    if !installed(&dir, &key) {
+        return Err(format!("Voice {} is not installed", key));
+    }
+    let piper =
+        find_piper(&app).ok_or("Piper is not installed (https://github.com/rhasspy/piper)")?;
+    let out_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get cache dir: {}", e))?
+        .join("voice-previews");
+    std::fs::create_dir_all(&out_dir)
+        .map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+    let out = out_dir.join(format!("{}.wav", key));
+    let text = text
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PREVIEW.to_string());
+    let model = model_path(&dir, &key);
+
+    let out_for_task = out.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        use std::io::Write;
+        let mut child = std::process::Command::new(piper)
+            .arg("--model")
+            .arg(&model)
+            .arg("--output_file")
+            .arg(&out_for_task)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run piper: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to send text to piper: {}", e))?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "piper failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(out.display().to_string())
+}
+
+/// Set the default voice, for one notebook or (no notebook) globally.
+/// `key: None` clears it — a notebook then falls back to the global default.
+#[tauri::command]
+pub(crate) async fn set_default_voice(
+    app: AppHandle,
+    notebook_id: Option<String>,
+    key: Option<String>,
+) -> Result<(), String> {
+    if let Some(k) = &key {
+        validate_key(k)?;
+        if !installed(&voices_dir(&app)?, k) {
+            return Err(format!("Voice {} is not installed", k));
+        }
+    }
+    crate::settings::update(&app, |s| match notebook_id {
+        Some(id) => match key {
+            Some(k) => {
+                s.tts.notebook_voices.insert(id, k);
+            }
+            None => {
+                s.tts.notebook_voices.remove(&id);
+            }
+        },
+        None => s.tts.default_voice = key,
+    })?;
+    Ok(())
+}
+
+/// The voice to use for a notebook: its override, else the global default.
+#[tauri::command]
+pub(crate) async fn get_default_voice(
+    app: AppHandle,
+    notebook_id: Option<String>,
+) -> Result<Option<String>, String> {
+    let tts = crate::settings::get(&app).tts;
+    Ok(notebook_id
+        .and_then(|id| tts.notebook_voices.get(&id).cloned())
+        .or(tts.default_voice))
+}
@@ -0,0 +1,171 @@
+//! Optional model prewarming once the backend is up.
+//!
+//! Loading a 7B model into (V)RAM is most of the delay on the first question
+//! after launch. With prewarming on, the shell asks the backend which models
+//! it's configured for and has Ollama load each one with a one-token prompt,
+//! so that cost is paid while the user is still looking at the notebook list.
+//! Progress is reported separately from backend readiness as `models://warm`.
+//! Skipped when background work is paused (battery / low-power mode); each
+//! load waits for a background slot in the inference queue.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::power::BackgroundPolicy;
+
+static STATE: Mutex<Option<WarmState>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+pub(crate) struct WarmState {
+    /// "disabled" | "warming" | "warm" | "skipped" | "failed"
+    state: String,
+    /// Model being loaded (while warming) or the models that were warmed.
+    models: Vec<String>,
+    elapsed_ms: Option<u64>,
+    error: Option<String>,
+}
+
+fn set(app: &AppHandle, state: WarmState) {
+    if let Ok(mut s) = STATE.lock() {
+        *s = Some(state.clone());
+    }
+    let _ = app.emit("models://warm", &state);
+}
+
+fn finished(
+    app: &AppHandle,
+    state: &str,
+    models: Vec<String>,
+    started: Instant,
+    error: Option<String>,
+) {
+    set(
+        app,
+        WarmState {
+            state: state.to_string(),
+            models,
+            elapsed_ms: Some(started.elapsed().as_millis() as u64),
+            error,
+        },
+    );
+}
+
+/// The backend's configured Ollama models (main, then fast).
+async fn configured_models() -> Result<Vec<String>, String> {
+    let token = crate::read_app_token().await.unwrap_or_default();
+    let info: serde_json::Value = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .map_err(|e| e.to_string())?
+        .get(format!(
+            "{}/settings/llm-info",
+            crate::backend_api::base_url()
+        ))
+        .header("X-LocalBook-Token", &token)
+        .send()
+        .await
+        .map_err(|e| format!("Backend not reachable: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected /settings/llm-info response: {}", e))?;
+    if info["provider"].as_str().is_some_and(|p| p != "ollama") {
+        return Ok(Vec::new()); // cloud provider — nothing local to load
+    }
+    let mut models: Vec<String> = ["model_name", "fast_model_name"]
+        .iter()
+        .filter_map(|k| info[*k].as_str().map(String::from))
+        .collect();
+    models.dedup();
+    Ok(models)
+}
+
+async fn run(app: AppHandle) {
+    let started = Instant::now();
+    if crate::power::current_policy() == Some(BackgroundPolicy::Pause)
+        || crate::pause::is_paused(&app)
+    {
+        tracing::info!("[Warmup] Skipped: background work is paused");
+        finished(
+            &app,
+            "skipped",
+            Vec::new(),
+            started,
+            Some("Background work is paused".into()),
+        );
+        return;
+    }
+    let models = match configured_models().await {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::warn!("[Warmup] {}", e);
+            finished(&app, "failed", Vec::new(), started, Some(e));
+            return;
+        }
+    };
+
+    let mut warmed = Vec::new();
+    for model in models {
+        set(
+            &app,
+            WarmState {
+                state: "warming".to_string(),
+                models: vec![model.clone()],
+                elapsed_ms: Some(started.elapsed().as_millis() as u64),
+                error: None,
+            },
+        );
+        let _slot =
+            crate::inference::acquire(&app, crate::inference::Priority::Background, "warmup").await;
+        let t = Instant::now();
+        match crate::ollama::warm_model(&model).await {
+            Ok(()) => {
+                tracing::info!(
+                    "[Warmup] {} loaded in {:.1}s",
+                    model,
+                    t.elapsed().as_secs_f64()
+                );
+                warmed.push(model);
+            }
+            Err(e) => {
+                tracing::warn!("[Warmup] {} failed: {}", model, e);
+                finished(&app, "failed", warmed, started, Some(e));
+                return;
+            }
+        }
+    }
+    finished(&app, "warm", warmed, started, None);
+}
+
+/// Warm the configured models in the background if the setting is on.
+/// Called once the backend reports ready.
+pub(crate) fn start(app: &AppHandle) {
+    if !crate::settings::get(app).prewarm_models {
+        set(
+            app,
+            WarmState {
+                state: "disabled".to_string(),
+                models: Vec::new(),
+                elapsed_ms: None,
+                error: None,
+            },
+        );
+        return;
+    }
+    tauri::async_runtime::spawn(run(app.clone()));
+}
+
+#[tauri::command]
+pub(crate) async fn get_warm_state() -> Result<Option<WarmState>, String> {
+    Ok(STATE.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Turning prewarming on also warms right away.
+#[tauri::command]
+pub(crate) async fn set_prewarm_models(app: AppHandle, enabled: bool) -> Result<(), String> {
+    crate::settings::update(&app, |s| s.prewarm_models = enabled)?;
+    start(&app);
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! Built-in Whisper transcription (whisper.cpp, in-process).
+//!
+//! Model files are the whisper.cpp `ggml-<size>.bin` builds, downloaded and
+//! tracked by the model manager like any other weights; `list_whisper_models`
+//! tells the UI which sizes are present and where to fetch the rest. Audio is
+//! decoded to 16 kHz mono with ffmpeg (the same dependency the backend's
+//! transcription already has), and segments are emitted as
+//! `whisper://partial` while decoding runs so long recordings show text early.
+//!
+//! The engine itself is only compiled with the `whisper` cargo feature (it
+//! builds whisper.cpp from source); GPU acceleration is Metal on macOS, or
+//! CUDA with `whisper-cuda`, and follows the hardware tuning.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio_util::sync::CancellationToken;
+
+/// (size, approximate download in MB). English-only `.en` variants are
+/// smaller-vocabulary versions of the same sizes.
+const WHISPER_SIZES: &[(&str, u32)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1500),
+    ("large-v3-turbo", 1620),
+    ("large-v3", 3100),
+];
+const DEFAULT_SIZE: &str = "base";
+
+#[derive(Serialize)]
+pub(crate) struct WhisperModel {
+    size: String,
+    file_name: String,
+    /// Pass to `download_model` to fetch it.
+    source: String,
+    approx_mb: u32,
+    downloaded: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct TranscriptSegment {
+    start_ms: i64,
+    end_ms: i64,
+    text: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Transcript {
+    text: String,
+    segments: Vec<TranscriptSegment>,
+    /// Detected (or requested) language code.
+    language: Option<String>,
+    model: String,
+}
+
+fn file_name_for(size: &str) -> String {
+    format!("ggml-{}.bin", size)
+}
+
+#[tauri::command]
+pub(crate) async fn list_whisper_models(app: AppHandle) -> Result<Vec<WhisperModel>, String> {
+    let dir = crate::models::models_dir(&app)?;
+    Ok(WHISPER_SIZES
+        .iter()
+        .map(|(size, approx_mb)| {
+            let file_name = file_name_for(size);
+            WhisperModel {
+                size: size.to_string(),
+                source: format!("hf://ggerganov/whisper.cpp/{}", file_name),
+                downloaded: dir.join(&file_name).exists(),
+                approx_mb: *approx_mb,
+                file_name,
+            }
+        })
+        .collect())
+}
+
+#[cfg(feature = "whisper")]
+mod engine {
+    use std::path::Path;
+
+    use tauri::{AppHandle, Emitter};
+    use tokio_util::sync::CancellationToken;
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    use super::{Transcript, TranscriptSegment};
+
+    const SAMPLE_RATE: &str = "16000";
+
+    fn find_ffmpeg() -> Option<std::path::PathBuf> {
+        let mut dirs: Vec<std::path::PathBuf> = std::env::var_os("PATH")
+            .map(|p| std::env::split_paths(&p).collect())
+            .unwrap_or_default();
+        // GUI apps on macOS don't inherit the shell PATH.
+        dirs.extend(["/opt/homebrew/bin", "/usr/local/bin"].map(std::path::PathBuf::from));
+        let exe = if cfg!(windows) {
+            "ffmpeg.exe"
+        } else {
+            "ffmpeg"
+        };
+        dirs.into_iter().map(|d| d.join(exe)).find(|p| p.exists())
+    }
+
+    /// Decode any ffmpeg-readable audio/video file to 16 kHz mono f32 samples.
+    fn decode_audio(path: &Path) -> Result<Vec<f32>, String> {
+        let ffmpeg = find_ffmpeg().ok_or("ffmpeg not found. Install with: brew install ffmpeg")?;
+        let output = std::process::Command::new(ffmpeg)
+            .args(["-nostdin", "-loglevel", "error", "-i"])
+            .arg(path)
+            .args(["-vn", "-f", "s16le", "-ac", "1", "-ar", SAMPLE_RATE, "-"])
+            .output()
+            .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "ffmpeg could not decode {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output
+            .stdout
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect())
+    }
+
+    /// Blocking: decode, load the model and transcribe.
+    pub(super) fn transcribe(
+        app: &AppHandle,
+        audio: &Path,
+        model_path: &Path,
+        model: String,
+        language: Option<String>,
+        cancel: CancellationToken,
+    ) -> Result<Transcript, String> {
+        let samples = decode_audio(audio)?;
+        if cancel.is_cancelled() {
+            return Err("Transcription cancelled".to_string());
+        }
+        let tuning = crate::hardware::tuning(app);
+
+        let mut ctx_params = WhisperContextParameters::new();
+        ctx_params.use_gpu(tuning.gpu_layers != 0);
+        let model_str = model_path.to_str().ok_or("Model path is not valid UTF-8")?;
+        let ctx = WhisperContext::new_with_params(model_str, ctx_params)
+            .map_err(|e| format!("Failed to load {}: {}", model_path.display(), e))?;
+        let mut state = ctx
+            .create_state()
+            .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(i32::try_from(tuning.threads).unwrap_or(4));
+        params.set_language(Some(language.as_deref().unwrap_or("auto")));
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+
+        let source = audio.display().to_string();
+        let (partial_app, partial_source) = (app.clone(), source.clone());
+        params.set_segment_callback_safe(move |seg: whisper_rs::SegmentCallbackData| {
+            // Timestamps are in centiseconds.
+            let _ = partial_app.emit(
+                "whisper://partial",
+                serde_json::json!({
+                    "path": partial_source,
+                    "segment": TranscriptSegment {
+                        start_ms: seg.start_timestamp * 10,
+                        end_ms: seg.end_timestamp * 10,
+                        text: seg.text.trim().to_string(),
+                    },
+                }),
+            );
+        });
+        let progress_app = app.clone();
+        params.set_progress_callback_safe(move |percent: i32| {
+            let _ = progress_app.emit(
+                "whisper://progress",
+                serde_json::json!({ "path": source, "percent": percent }),
+            );
+        });
+
+        // Checked by whisper.cpp between decoder steps.
+        params.set_abort_callback_safe(move || cancel.is_cancelled());
+
+        tracing::info!(
+            "[Whisper] Transcribing {:?} with {} ({} s of audio, gpu={})",
+            audio,
+            model,
+            samples.len() / 16_000,
+            tuning.gpu_layers != 0
+        );
+        state
+            .full(params, &samples)
+            .map_err(|e| format!("Transcription failed: {}", e))?;
+
+        let segments: Vec<TranscriptSegment> = state
+            .as_iter()
+            .map(|s| TranscriptSegment {
+                start_ms: s.start_timestamp() * 10,
+                end_ms: s.end_timestamp() * 10,
+                text: s
+                    .to_str_lossy()
+                    .map(|t| t.trim().to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        let text = segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let language = language.or_else(|| {
+            whisper_rs::get_lang_str(state.full_lang_id_from_state()).map(String::from)
+        });
+
+        Ok(Transcript {
+            text,
+            segments,
+            language,
+            model,
+        })
+    }
+}
+
+/// Transcribe an audio or video file. `size` picks the Whisper model
+/// (default "base"); it must already be downloaded. `language` is an ISO code
+/// or None to auto-detect. Cancelling `cancel` stops decoding early.
+pub(crate) async fn transcribe_file(
+    app: AppHandle,
+    path: String,
+    size: Option<String>,
+    language: Option<String>,
+    cancel: CancellationToken,
+) -> Result<Transcript, String> {
+    crate::scope::check(&app, Path::new(&path))?;
+    let size = size.unwrap_or_else(|| DEFAULT_SIZE.to_string());
+    if !WHISPER_SIZES.iter().any(|(s, _)| *s == size) {
+        return Err(format!("Unknown Whisper model size: {}", size));
+    }
+    let model_path = crate::models::models_dir(&app)?.join(file_name_for(&size));
+    if !model_path.exists() {
+        return Err(format!(
+            "Whisper model \"{}\" is not downloaded (hf://ggerganov/whisper.cpp/{})",
+            size,
+            file_name_for(&size)
+        ));
+    }
+
+    #[cfg(feature = "whisper")]
+    {
+        tauri::async_runtime::spawn_blocking(move || {
+            engine::transcribe(
+                &app,
+                std::path::Path::new(&path),
+                &model_path,
+                size,
+                language.filter(|l| !l.is_empty() && l != "auto"),
+                cancel,
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+    #[cfg(not(feature = "whisper"))]
+    {
+        let _ = (path, language, cancel);
+        Err("This build does not include the built-in Whisper engine".to_string())
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn transcribe_audio(
+    app: AppHandle,
+    path: String,
+    size: Option<String>,
+    language: Option<String>,
+) -> Result<Transcript, String> {
+    transcribe_file(app, path, size, language, CancellationToken::new()).await
+}
@@ -0,0 +1,149 @@
+//! Secondary webview windows — notebooks opened side by side.
+//!
+//! Every window loads the same frontend bundle and talks to the same backend on
+//! :8000 (BackendState is app-global), so a notebook window is just the SPA booted
+//! with `?notebook=<id>` and its own navigation history. The main window owns the
+//! app lifetime: closing it closes every secondary window, which lets the normal
+//! RunEvent::Exit path shut the backend down exactly once.
+//!
+//! The mini window is the chat view alone in a small always-on-top window,
+//! toggled from the tray, a global hotkey, or the webview. Its size/position is
+//! handled by the window-state plugin like any other label; the pin state and
+//! last notebook live in shell settings.
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+pub(crate) const NOTEBOOK_WINDOW_PREFIX: &str = "notebook-";
+pub(crate) const MINI_WINDOW_LABEL: &str = "mini";
+pub(crate) const MINI_MODE_SHORTCUT: &str = "CmdOrCtrl+Shift+M";
+
+/// Notebook ids are UUIDs; anything outside `[A-Za-z0-9_-]` is rejected so the id
+/// is safe both as a window label and unescaped in the window's query string.
+fn notebook_window_label(notebook_id: &str) -> Result<String, String> {
+    let valid = !notebook_id.is_empty()
+        && notebook_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid notebook id: {:?}", notebook_id));
+    }
+    Ok(format!("{}{}", NOTEBOOK_WINDOW_PREFIX, notebook_id))
+}
+
+/// Open (or focus, if already open) a window dedicated to one notebook.
+/// Returns the window label so the frontend can target it with events.
+#[tauri::command]
+pub(crate) async fn open_notebook_window(
+    app: AppHandle,
+    notebook_id: String,
+) -> Result<String, String> {
+    if crate::lock::is_locked() {
+        return Err("LocalBook is locked".to_string());
+    }
+    let label = notebook_window_label(&notebook_id)?;
+    crate::audit::record("notebook_opened", &notebook_id);
+    crate::usage::count("notebook_opened");
+
+    if let Some(existing) = app.get_webview_window(&label) {
+        let _ = existing.unminimize();
+        let _ = existing.set_focus();
+        return Ok(label);
+    }
+
+    let url = format!("index.html?notebook={}&window={}", notebook_id, label);
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title("")
+        .inner_size(1100.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .resizable(true)
+        .build()
+        .map_err(|e| format!("Failed to open notebook window: {}", e))?;
+    crate::appearance::apply_window(&window);
+
+    tracing::info!("[Windows] Opened notebook window {}", label);
+    Ok(label)
+}
+
+/// Close the mini window if it's open, otherwise open it for `notebook_id`
+/// (or the notebook it was last opened for). Shared by the tray, the global
+/// hotkey and the `toggle_mini_mode` command.
+pub(crate) fn toggle_mini_window(app: &AppHandle, notebook_id: Option<String>) {
+    if let Err(e) = toggle_mini_window_impl(app, notebook_id) {
+        tracing::warn!("[Windows] Mini mode toggle failed: {}", e);
+    }
+}
+
+fn toggle_mini_window_impl(app: &AppHandle, notebook_id: Option<String>) -> Result<bool, String> {
+    if let Some(existing) = app.get_webview_window(MINI_WINDOW_LABEL) {
+        existing.close().map_err(|e| e.to_string())?;
+        return Ok(false);
+    }
+
+    if crate::lock::is_locked() {
+        crate::lock::show_lock_window(app);
+        return Ok(false);
+    }
+    if let Some(id) = notebook_id.as_deref() {
+        notebook_window_label(id)?; // same id validation as notebook windows
+    }
+    let settings = match notebook_id {
+        Some(id) => crate::settings::update(app, |s| s.mini_mode.notebook_id = Some(id))?,
+        None => crate::settings::get(app),
+    };
+
+    let mut url = "index.html?view=mini-chat".to_string();
+    if let Some(id) = &settings.mini_mode.notebook_id {
+        url.push_str(&format!("&notebook={}", id));
+    }
+    let window = WebviewWindowBuilder::new(app, MINI_WINDOW_LABEL, WebviewUrl::App(url.into()))
+        .title("LocalBook Chat")
+        .inner_size(380.0, 560.0)
+        .min_inner_size(320.0, 400.0)
+        .resizable(true)
+        .always_on_top(settings.mini_mode.pinned)
+        .build()
+        .map_err(|e| format!("Failed to open mini window: {}", e))?;
+    crate::appearance::apply_window(&window);
+
+    tracing::info!(
+        "[Windows] Mini mode opened (pinned={})",
+        settings.mini_mode.pinned
+    );
+    Ok(true)
+}
+
+/// Toggle mini mode from the webview. Returns whether the window is now open.
+#[tauri::command]
+pub(crate) async fn toggle_mini_mode(
+    app: AppHandle,
+    notebook_id: Option<String>,
+) -> Result<bool, String> {
+    toggle_mini_window_impl(&app, notebook_id)
+}
+
+/// Pin/unpin the mini window. Applied live if the window is open and persisted
+/// for the next time it opens.
+#[tauri::command]
+pub(crate) async fn set_mini_mode_pinned(app: AppHandle, pinned: bool) -> Result<(), String> {
+    crate::settings::update(&app, |s| s.mini_mode.pinned = pinned)?;
+    if let Some(w) = app.get_webview_window(MINI_WINDOW_LABEL) {
+        w.set_always_on_top(pinned).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Builder-level window event hook. Closing the main window takes the notebook
+/// and mini windows with it — otherwise the app (and the backend) would keep running
+/// headless behind a lone secondary window.
+pub(crate) fn on_window_event(window: &tauri::Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    if let WindowEvent::Destroyed = event {
+        for (label, w) in window.app_handle().webview_windows() {
+            if label.starts_with(NOTEBOOK_WINDOW_PREFIX) || label == MINI_WINDOW_LABEL {
+                let _ = w.close();
+            }
+        }
+    }
+}
@@ -293,6 +293,24 @@ function App() {
     return () => { if (unlisten) unlisten(); };
   }, [openLLMSelector, openSettings, openHealth]);
 
+  // A `localbook://notebook/<id>` link opened at launch or while running
+  // (cli.rs): the shell holds the notebook until asked, and says when
+  // there's a new one. Safe no-op outside Tauri.
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    const takeLaunchNotebook = () =>
+      import('@tauri-apps/api/core')
+        .then(({ invoke }) => invoke<string | null>('take_launch_notebook'))
+        .then((id) => { if (id) setSelectedNotebookId(id); })
+        .catch(() => { /* not in Tauri — ignore */ });
+    takeLaunchNotebook();
+    import('@tauri-apps/api/event')
+      .then(({ listen }) => listen('launch://notebook', () => { takeLaunchNotebook(); }))
+      .then((fn) => { unlisten = fn; })
+      .catch(() => { /* not in Tauri — ignore */ });
+    return () => { if (unlisten) unlisten(); };
+  }, []);
+
   const toggleDarkMode = useCallback(() => {
     setDarkMode(prev => {
       const next = !prev;
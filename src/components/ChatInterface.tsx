@@ -7,7 +7,8 @@ import { voiceService } from '../services/voice';
 import { ChatMessage, Citation as CitationType } from '../types';
 import { curatorService } from '../services/curatorApi';
 import { correspondentService } from '../services/correspondent';
-import { sourceService } from '../services/sources';
+import { sourceService, isTauri } from '../services/sources';
+import { notebookSettingsService } from '../services/notebookSettings';
 import { Button } from './shared/Button';
 import { ErrorMessage } from './shared/ErrorMessage';
 import { SourceNotesViewer } from './SourceNotesViewer';
@@ -406,6 +407,11 @@ export const ChatInterface: React.FC<ChatInterfaceProps> = ({ notebookId, llmPro
     // Add the streaming message placeholder
     setMessages((prev) => [...prev, streamingMessage]);
 
+    // The notebook's model settings, resolved in the shell over the defaults.
+    const effective = isTauri()
+      ? await notebookSettingsService.effective(notebookId).catch(() => null)
+      : null;
+
     let currentContent = '';
     let currentCitations: CitationType[] = [];
     let isLowConfidence = false;
@@ -421,7 +427,11 @@ export const ChatInterface: React.FC<ChatInterfaceProps> = ({ notebookId, llmPro
           question: currentQuestion,
           top_k: 5,
           enable_web_search: false,
-          llm_provider: llmProvider,
+          llm_provider: effective?.overridden.includes('llm_provider') && effective.llm_provider
+            ? effective.llm_provider
+            : llmProvider,
+          model: effective?.model ?? undefined,
+          temperature: effective?.temperature ?? undefined,
           deep_think: deepThink,
           target: target || undefined,
           ...((target === 'studio' || target === 'collector') ? { chat_context: messages.slice(-8).map(m => `${m.role === 'user' ? 'User' : 'Assistant'}: ${m.content.slice(0, 400)}`).join('\n\n').slice(0, 3000) } : {}),
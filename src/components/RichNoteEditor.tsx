@@ -14,6 +14,7 @@ import { voiceService } from '../services/voice';
 import { settingsService } from '../services/settings';
 import { noteService } from '../services/noteService';
 import { scanService, ScanProgressEvent } from '../services/scanService';
+import { defaultDirsService } from '../services/defaultDirs';
 import { ScanQRBadge } from './ScanQRBadge';
 import { sanitizeOcrMarkdown } from '../lib/sanitizeOcrMarkdown';
 
@@ -446,6 +447,7 @@ export const RichNoteEditor: React.FC<RichNoteEditorProps> = ({ item, compact =
   const handleScan = async (mode: 'document' | 'photo') => {
     try {
       const selected = await open({
+        defaultPath: await defaultDirsService.dialogPath('imports'),
         multiple: false,
         filters: [{
           name: 'Image',
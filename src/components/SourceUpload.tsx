@@ -1,5 +1,6 @@
 import React, { useState, useRef, useEffect } from 'react';
 import { sourceService, UploadProgressEvent, isTauri } from '../services/sources';
+import { defaultDirsService } from '../services/defaultDirs';
 import { ErrorMessage } from './shared/ErrorMessage';
 
 interface SourceUploadProps {
@@ -196,6 +197,28 @@ export const SourceUpload: React.FC<SourceUploadProps> = ({
     return items;
   };
 
+  // ── Files opened with LocalBook ───────────────────────────────────────
+  // Double-clicked or named on the command line (cli.rs): the shell holds
+  // them until asked, and says when more arrive while running.
+  useEffect(() => {
+    if (!inTauri || !notebookId) return;
+    let unlisten: (() => void) | undefined;
+    const takeLaunchFiles = async () => {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const paths = await invoke<string[]>('take_launch_files');
+      if (paths.length > 0) await processItems(await pathsToItems(paths));
+    };
+    takeLaunchFiles().catch((e) => console.warn('[SourceUpload] Launch files:', e));
+    import('@tauri-apps/api/event')
+      .then(({ listen }) => listen('launch://files', () => {
+        takeLaunchFiles().catch((e) => console.warn('[SourceUpload] Launch files:', e));
+      }))
+      .then((fn) => { unlisten = fn; })
+      .catch(() => { /* not in Tauri — ignore */ });
+    return () => { if (unlisten) unlisten(); };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [notebookId, inTauri]);
+
   // ── Browse handler — Tauri dialog when available, HTML input otherwise ──
   const handleBrowseClick = async () => {
     if (uploading || !notebookId) return;
@@ -206,6 +229,7 @@ export const SourceUpload: React.FC<SourceUploadProps> = ({
     try {
       const { open } = await import('@tauri-apps/plugin-dialog');
       const selected = await open({
+        defaultPath: await defaultDirsService.dialogPath('imports'),
         multiple: true,
         directory: false,
         filters: [
@@ -4,7 +4,20 @@ import { invoke } from '@tauri-apps/api/core';
 // If VITE_API_URL is not set, dynamically determine the backend IP
 // based on where the frontend was loaded from (so it works across the network)
 const defaultHost = typeof window !== 'undefined' ? window.location.hostname : 'localhost';
-export const API_BASE_URL = import.meta.env.VITE_API_URL || `http://${defaultHost}:8000`;
+
+// Served to a browser by `localbook --serve`, the backend is proxied on the
+// page's own origin (path in this meta tag) and the server adds the token.
+const servedApiPath = typeof document !== 'undefined'
+  ? document.querySelector<HTMLMetaElement>('meta[name="localbook-api"]')?.content
+  : undefined;
+
+// Set by the shell when it was started with `--port` (cli.rs).
+const backendPort = typeof window !== 'undefined'
+  ? (window as { __LOCALBOOK_BACKEND_PORT__?: number }).__LOCALBOOK_BACKEND_PORT__ ?? 8000
+  : 8000;
+
+export const API_BASE_URL = import.meta.env.VITE_API_URL
+  || (servedApiPath ? `${window.location.origin}${servedApiPath}` : `http://${defaultHost}:${backendPort}`);
 
 export const WS_BASE_URL = (() => {
   try {
@@ -26,6 +39,7 @@ let appToken: string | null = null;
 let tokenPromise: Promise<string | null> | null = null;
 
 async function getAppToken(): Promise<string | null> {
+  if (servedApiPath) return null;
   if (appToken) return appToken;
   if (tokenPromise) return tokenPromise;
   tokenPromise = invoke<string>('get_app_token')
@@ -39,6 +53,7 @@ async function getAppToken(): Promise<string | null> {
 
 /** Clear webview-side cache + ask Rust to clear its cache and re-read from disk. */
 async function refreshAppToken(): Promise<string | null> {
+  if (servedApiPath) return null;
   appToken = null;
   tokenPromise = null;
   try {
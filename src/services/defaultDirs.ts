@@ -0,0 +1,42 @@
+/**
+ * Default folders for file dialogs (imports, exports, backups, downloads),
+ * configured in the shell (src-tauri/src/default_dirs.rs).
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export type DirKind = 'imports' | 'exports' | 'backups' | 'downloads';
+
+export interface DefaultDir {
+    kind: DirKind;
+    path: string | null;
+    /** Chosen by the user rather than the OS default. */
+    custom: boolean;
+}
+
+export const defaultDirsService = {
+    async list(): Promise<DefaultDir[]> {
+        return invoke<DefaultDir[]>('get_default_dirs');
+    },
+
+    /** Set the folder for `kind`; null goes back to the default. */
+    async set(kind: DirKind, path: string | null): Promise<DefaultDir[]> {
+        return invoke<DefaultDir[]>('set_default_dir', { kind, path });
+    },
+
+    /**
+     * A dialog's `defaultPath`: the folder for `kind`, with `fileName` in it
+     * when given. Undefined outside Tauri or when there's no folder.
+     */
+    async dialogPath(kind: DirKind, fileName?: string): Promise<string | undefined> {
+        try {
+            const dir = (await this.list()).find(d => d.kind === kind)?.path;
+            if (!dir) return fileName;
+            if (!fileName) return dir;
+            const sep = dir.includes('\\') && !dir.includes('/') ? '\\' : '/';
+            return `${dir.replace(/[\\/]+$/, '')}${sep}${fileName}`;
+        } catch {
+            return fileName;
+        }
+    },
+};
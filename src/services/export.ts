@@ -5,6 +5,7 @@
 import { save } from '@tauri-apps/plugin-dialog';
 import { writeFile } from '@tauri-apps/plugin-fs';
 import { API_BASE_URL, localFetch } from './api';
+import { defaultDirsService } from './defaultDirs';
 import type { CanvasItem } from '../components/canvas/types';
 
 // ── Phase 5 — unified artifact export ─────────────────────────────────────
@@ -125,7 +126,7 @@ export const exportService = {
         try {
             // Show save dialog and get the path
             const path = await save({
-                defaultPath: filename,
+                defaultPath: await defaultDirsService.dialogPath('exports', filename),
                 filters: [{
                     name: 'Export File',
                     extensions: [filename.split('.').pop() || '*']
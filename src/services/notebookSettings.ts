@@ -0,0 +1,42 @@
+/**
+ * Per-notebook settings (model, chunk size, citation style), resolved in the
+ * shell (src-tauri/src/notebook_settings.rs) over the global defaults. The
+ * backend reads the same resolved values, so what's shown here is what a
+ * notebook's answers and indexing use.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export type CitationStyle = 'inline' | 'paragraph' | 'off';
+
+/** A notebook's own settings; null inherits. */
+export interface NotebookSettings {
+    chunk_size: number | null;
+    citation_style: CitationStyle | null;
+}
+
+export interface EffectiveSettings {
+    model: string | null;
+    temperature: number | null;
+    llm_provider: string | null;
+    /** Null is the backend's default. */
+    chunk_size: number | null;
+    citation_style: CitationStyle;
+    /** The settings the notebook sets itself rather than inheriting. */
+    overridden: string[];
+}
+
+export const notebookSettingsService = {
+    /** What applies in a notebook, or the global defaults for null. */
+    async effective(notebookId: string | null): Promise<EffectiveSettings> {
+        return invoke<EffectiveSettings>('get_effective_settings', { notebookId });
+    },
+
+    /**
+     * Replace a notebook's overrides, or the global defaults for a null id.
+     * `settings: null` clears the notebook's overrides.
+     */
+    async set(notebookId: string | null, settings: NotebookSettings | null): Promise<EffectiveSettings> {
+        return invoke<EffectiveSettings>('set_notebook_settings', { notebookId, settings });
+    },
+};
@@ -0,0 +1,45 @@
+/**
+ * Startup splash window (src-tauri/src/splash.rs): shows where backend
+ * startup has got to until the shell swaps in the main window.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+interface BackendStatus {
+    stage: string;
+    message: string;
+    last_error: string | null;
+}
+
+/** How far along each stage is, for the bar. */
+const STAGE_PROGRESS: Record<string, number> = {
+    starting: 5,
+    starting_ollama: 15,
+    checking_models: 30,
+    downloading_model: 35,
+    starting_backend: 60,
+    waiting_for_backend: 75,
+    ready: 100,
+    error: 100,
+};
+
+// Same theme choice as index.html's pre-paint.
+try {
+    const saved = localStorage.getItem('theme');
+    const dark = saved ? saved === 'dark' : window.matchMedia('(prefers-color-scheme: dark)').matches;
+    document.documentElement.classList.toggle('dark', dark);
+} catch {
+    // Default light look.
+}
+
+function show(status: BackendStatus) {
+    const bar = document.getElementById('progress');
+    const message = document.getElementById('message');
+    const percent = STAGE_PROGRESS[status.stage];
+    if (bar && percent !== undefined) bar.style.width = `${percent}%`;
+    if (message) message.textContent = status.message;
+}
+
+void listen<BackendStatus>('startup://progress', (event) => show(event.payload));
+invoke<BackendStatus>('get_backend_status').then(show).catch(() => {});
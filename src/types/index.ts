@@ -129,6 +129,8 @@ export interface ChatQuery {
   top_k?: number;
   enable_web_search?: boolean;
   llm_provider?: string;
+  model?: string;  // The notebook's model override (notebook settings)
+  temperature?: number;  // The notebook's temperature override
   deep_think?: boolean;  // Enable Deep Think mode with chain-of-thought reasoning
   target?: string;  // v1.4: @mention routing — 'curator', 'collector', 'research', 'studio', or undefined for default RAG
   chat_context?: string;  // v1.5: @studio — recent conversation context for content generation
@@ -15,6 +15,13 @@ export default defineConfig({
     minify: !process.env.TAURI_DEBUG ? 'esbuild' : false,
     sourcemap: !!process.env.TAURI_DEBUG,
     chunkSizeWarningLimit: 3000,
+    rollupOptions: {
+      // The startup splash window is its own small page.
+      input: {
+        main: 'index.html',
+        splash: 'splash.html',
+      },
+    },
   },
   optimizeDeps: {
     include: ['three'],